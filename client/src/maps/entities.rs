@@ -1,34 +1,64 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 use shared::{
     gems::{self, Gem},
     items::{self, Item},
     maps::{
-        entities::{Direction, Entity},
+        entities::{Direction, Entity, FacialExpression},
         Map, TileCoords
     },
+    mechanics::{self, ActionKind},
     messages, Id
 };
 
 use super::{ClientMap, MapRenderer};
 use crate::networking::{self, ConnectionTrait};
 
+/// The maximum number of movements that may await confirmation from the server at once. Further movement is refused
+/// until the server responds so that the predicted position cannot stray far from the server's should the connection
+/// stall.
+const MAX_PENDING_MOVES: usize = 16;
+
+/// The time (in seconds) after which movements awaiting confirmation are discarded should the server not have responded
+/// to any of them, so that a response that never arrives cannot prevent further movement indefinitely.
+const PENDING_MOVES_TIMEOUT: f32 = 5.0;
+
+/// Movement predictions awaiting confirmation from the server, each being the request number of a
+/// [`shared::messages::ToServer::MoveMyEntity`] message paired with the position that the player entity was predicted
+/// to move to (oldest first).
+type PendingMoves = VecDeque<(u32, TileCoords)>;
+
 /// The entity controlled by this client program.
+#[derive(Clone)]
 pub struct MyEntity {
     id: Id,
     contained: Entity,
     /// Request number value to be used for the next [`shared::messages::ToServer::MoveMyEntity`] message. Incremented
     /// after the sending of each message.
     next_request_number: u32,
-    /// Mapping of request numbers to predicted entity coordinates. 'Unverified' in this case means that a
-    /// [`shared::messages::ToServer::MoveMyEntity`] message has been sent and the player entity's coordinates have
-    /// been changed locally but a [`shared::messages::FromServer::YourEntityMoved`] response message from the server
-    /// has not yet been received so it is not yet known whether the predicted coordinates align with those on the
-    /// server side.
-    unverified_movements: HashMap<u32, TileCoords>,
+    /// Movements for which a [`shared::messages::ToServer::MoveMyEntity`] message has been sent and the player
+    /// entity's coordinates have been changed locally but a [`shared::messages::FromServer::YourEntityMoved`] response
+    /// message from the server has not yet been received, so it is not yet known whether the predicted coordinates
+    /// align with those on the server side. The server responds to movement requests in the order they are sent.
+    pending_moves: PendingMoves,
+    /// Time (in seconds) for which movements have awaited confirmation without the server responding to any of them.
+    /// Pending movements are discarded once this reaches [`PENDING_MOVES_TIMEOUT`].
+    time_awaiting_confirmation: f32,
     /// When this value reaches 0 then the required amount of time has passed since the player's last movement before
     /// it can move again.
-    movement_time_countdown: f32
+    movement_time_countdown: f32,
+    /// The maximum number of bombs that the player may have placed at once. Not known until the server first rejects a
+    /// bomb placement (see [`Self::bomb_placement_rejected`]).
+    bomb_limit: Option<u32>,
+    /// Time (in seconds) remaining before each action with a cooldown may be performed again. These mirror the
+    /// cooldowns applied by the server so that requests the server would reject are not sent.
+    cooldowns: HashMap<ActionKind, f32>,
+    /// The position at which a bomb was most recently placed locally, which is removed should the server reject the
+    /// placement due to its cooldown not having passed.
+    last_bomb_position: Option<TileCoords>,
+    /// The health restored by the potion most recently used locally, which is removed (and the potion returned to the
+    /// inventory) should the server reject the potion's use due to its cooldown not having passed.
+    last_potion_health_restored: Option<u32>
 }
 
 impl MyEntity {
@@ -36,30 +66,93 @@ impl MyEntity {
         MyEntity {
             id,
             contained,
-            next_request_number: 0,
-            unverified_movements: HashMap::new(),
-            movement_time_countdown: 0.0
+            next_request_number: messages::SERVER_MOVE_REQUEST_NUMBER + 1,
+            pending_moves: VecDeque::new(),
+            time_awaiting_confirmation: 0.0,
+            movement_time_countdown: 0.0,
+            bomb_limit: None,
+            cooldowns: HashMap::new(),
+            last_bomb_position: None,
+            last_potion_health_restored: None
         }
     }
 
     pub fn update(&mut self, delta: f32) {
         self.movement_time_countdown -= delta;
+
+        if self.pending_moves.is_empty() {
+            self.time_awaiting_confirmation = 0.0;
+        }
+        else {
+            self.time_awaiting_confirmation += delta;
+
+            if self.time_awaiting_confirmation >= PENDING_MOVES_TIMEOUT {
+                log::warn!(
+                    "Discarding {} movement predictions as the server has not responded for {} seconds",
+                    self.pending_moves.len(),
+                    PENDING_MOVES_TIMEOUT
+                );
+                self.pending_moves.clear();
+            }
+        }
+
+        for time_remaining in self.cooldowns.values_mut() {
+            *time_remaining -= delta;
+        }
+        self.cooldowns.retain(|_, time_remaining| *time_remaining > 0.0);
+    }
+
+    /// The time (in seconds) remaining before the specified action may be performed again (zero should it already be
+    /// possible).
+    pub fn cooldown_remaining(&self, action: ActionKind) -> f32 {
+        self.cooldowns.get(&action).copied().unwrap_or(0.0)
+    }
+
+    fn start_cooldown(&mut self, action: ActionKind) {
+        self.cooldowns.insert(action, action.cooldown_ms() as f32 / 1000.0);
+    }
+
+    /// Called should the server reject an action as its cooldown has not yet passed (e.g. due to network delays
+    /// bunching up requests), waiting the given time before the action may be performed again. A rejected bomb
+    /// placement or potion use is undone locally.
+    pub fn action_rejected_by_cooldown(&mut self, action: ActionKind, remaining_ms: u32, map: &mut ClientMap) {
+        self.cooldowns.insert(action, remaining_ms as f32 / 1000.0);
+
+        match action {
+            ActionKind::PlaceBomb => {
+                if let Some(position) = self.last_bomb_position.take() {
+                    self.bomb_placement_rejected(position, None, map);
+                }
+            }
+            ActionKind::UsePotion => {
+                if let Some(health_restored) = self.last_potion_health_restored.take() {
+                    self.contained.health = self.contained.health.saturating_sub(health_restored);
+                    self.contained.item_inventory.give_quantity(items::QuantitativeItem::Potion, 1);
+                }
+            }
+            ActionKind::Emote => {}
+        }
     }
 
     /// Will attempt to move the player entity in the specified direction but will fail if moving now would exceed the
-    /// movement speed limit, or if the destination tile is occupied/blocking, or if unable to contact the server.
+    /// movement speed limit, or if the destination tile is occupied/blocking, or if too many movements await
+    /// confirmation from the server, or if unable to contact the server. The movement is predicted (i.e. the player
+    /// entity is moved locally & its movement animation begins) without waiting for the server's response.
     pub fn move_towards_checked(
         &mut self, direction: Direction, map: &mut ClientMap, connection: &mut networking::Connection,
         renderer: &mut MapRenderer
     ) -> networking::Result<()> {
         // Check if required amount of time has paced since last movement (i.e. don't exceed maximum movement speed):
-        if self.movement_time_countdown <= 0.0 {
+        if self.movement_time_countdown <= 0.0 && self.pending_moves.len() < MAX_PENDING_MOVES {
             // Check if the position the player wants to move to is free (i.e. not a blocking tile and no other
             // entities persent at that position):
             let new_pos = direction.apply(self.contained.pos);
             if map.is_position_free(new_pos) {
                 log::trace!("Moving player entity in direction {} to {}", direction, new_pos);
 
+                // Snapshot entity state prior to optimistic movement so that it can be rolled back if necessary:
+                let snapshot = self.contained.clone();
+
                 // Determine the amount of time needed to move to the destination time:
                 let dest_tile = map.loaded_tile_at(new_pos).unwrap_or_default();
                 let movement_time = self.contained.movement_time(dest_tile);
@@ -74,12 +167,21 @@ impl MyEntity {
                 self.contained.pos = new_pos;
                 self.contained.direction = direction;
 
+                // Predict any sliding across ice that follows the movement:
+                let movement_time = movement_time + self.slide_across_ice(direction, map, renderer);
+
                 // Inform server that this client's player entity wants to move in a given direction:
                 let msg = messages::ToServer::MoveMyEntity { request_number: self.next_request_number, direction };
-                connection.send(&msg)?;
+                if let Err(e) = connection.send(&msg) {
+                    // Server was not informed of the movement so roll back to the state prior to moving:
+                    renderer.my_entity_position_corrected(snapshot.pos);
+                    self.contained = snapshot;
 
-                // Add to collection of movement predictions awaiting confirmation from the server:
-                self.unverified_movements.insert(self.next_request_number, self.contained.pos);
+                    return Err(e);
+                }
+
+                // Add to the queue of movement predictions awaiting confirmation from the server:
+                self.pending_moves.push_back((self.next_request_number, self.contained.pos));
 
                 // Prepare for next movement:
                 self.next_request_number += 1;
@@ -97,20 +199,138 @@ impl MyEntity {
         Ok(())
     }
 
+    /// Move the player entity locally across any ice it is standing on in the given direction, one tile at a time in
+    /// the same manner as the server (see [`Map::ice_slide_destination`]). Each tile slid is animated after those
+    /// before it. Returns the total time taken to slide.
+    fn slide_across_ice(&mut self, direction: Direction, map: &mut ClientMap, renderer: &mut MapRenderer) -> f32 {
+        let mut slide_time = 0.0;
+
+        for _ in 0..mechanics::MAX_ICE_SLIDE {
+            let slide_pos = match map.ice_slide_destination(self.contained.pos, direction) {
+                Some(pos) => pos,
+                None => break
+            };
+
+            let dest_tile = map.loaded_tile_at(slide_pos).unwrap_or_default();
+            let movement_time = self.contained.movement_time(dest_tile);
+
+            map.some_entity_moved_to(slide_pos, renderer);
+            renderer.my_entity_moved(slide_pos, movement_time, dest_tile.get_entity_movement_frame_changes());
+
+            self.contained.pos = slide_pos;
+            slide_time += movement_time;
+        }
+
+        slide_time
+    }
+
     /// This method is called from the main game state whenever a [`shared::messages::FromServer::YouCollectedGems`]
     /// message is received.
     pub fn obtained_gems(&mut self, gem_type: Gem, quantity_increase: u32) {
         self.contained.gem_collection.increase_quantity(gem_type, quantity_increase);
     }
 
-    /// This method is called from the main game state whenever a [`shared::messages::FromSever::YourEntityMoved`]
+    /// This method is called from the main game state whenever a [`shared::messages::FromServer::YouSpentGems`]
+    /// message is received.
+    pub fn spent_gems(&mut self, gem_type: Gem, quantity_decrease: u32) {
+        // Should the local gem collection have fallen out of sync with the server's then it is left as it is rather
+        // than panicking:
+        if let Err(err) = self.contained.gem_collection.try_subtract(gem_type, quantity_decrease) {
+            log::warn!("Cannot remove spent gems from local gem collection as {}", err);
+        }
+    }
+
+    /// This method is called from the main game state whenever a [`shared::messages::FromServer::QuestUpdate`] message
+    /// containing a completed quest is received (as the server gives the quest's reward to the player entity).
+    pub fn obtained_quest_reward(&mut self, reward: &gems::Collection) {
+        self.contained.gem_collection.merge(reward);
+    }
+
+    /// This method is called from the main game state whenever a
+    /// [`shared::messages::FromServer::EntityHealthChanged`] message concerning the player entity is received.
+    pub fn health_changed(&mut self, health: u32) {
+        self.contained.health = health;
+    }
+
+    /// This method is called from the main game state whenever a [`shared::messages::FromServer::YouDied`] message is
+    /// received. The gems dropped are removed from the player entity's gem collection, its health is restored, and it
+    /// is moved to the respawn position (discarding any pending movement predictions).
+    pub fn respawn(&mut self, new_pos: TileCoords, gems_dropped: &gems::Collection, renderer: &mut MapRenderer) {
+        log::info!("Player entity died dropping gems {} and is respawning at {}", gems_dropped, new_pos);
+
+        for gem_type in Gem::all() {
+            self.spent_gems(gem_type, gems_dropped.get_quantity(gem_type));
+        }
+        self.contained.health = self.contained.max_health;
+
+        self.teleported(new_pos, renderer);
+    }
+
+    /// Immediately move the player entity to the given position without animation (jumping the camera there too),
+    /// discarding any pending movement predictions as they were made from the previous position.
+    fn teleported(&mut self, new_pos: TileCoords, renderer: &mut MapRenderer) {
+        self.contained.pos = new_pos;
+        self.pending_moves.clear();
+        self.movement_time_countdown = 0.0;
+
+        renderer.my_entity_teleported(new_pos);
+    }
+
+    /// Request that the server relay the given emote to nearby players (provided the emote cooldown has passed). The
+    /// emote is displayed above this client's player entity once relayed back by the server.
+    pub fn emote(
+        &mut self, emote: messages::EmoteKind, connection: &mut networking::Connection
+    ) -> networking::Result<()> {
+        if self.cooldown_remaining(ActionKind::Emote) <= 0.0 {
+            connection.send(&messages::ToServer::Emote(emote))?;
+            self.start_cooldown(ActionKind::Emote);
+        }
+
+        Ok(())
+    }
+
+    /// Change the player entity's facial expression locally and inform the server of the change.
+    pub fn set_facial_expression(
+        &mut self, expression: FacialExpression, connection: &mut networking::Connection
+    ) -> networking::Result<()> {
+        self.contained.facial_expression = expression;
+
+        connection.send(&messages::ToServer::SetFacialExpression(expression))
+    }
+
+    /// This method is called upon reconnecting to the server after the connection was lost, with the player entity
+    /// as given by the server's new [`shared::messages::FromServer::Welcome`] message. The server's copy of the entity
+    /// is used as the server may have changed it while this client was disconnected, whilst purely local state (such
+    /// as the known bomb limit) is kept. Pending movement predictions are discarded as the server will never respond
+    /// to them.
+    pub fn reconnected(&mut self, contained: Entity) {
+        self.contained = contained;
+        self.pending_moves.clear();
+        self.movement_time_countdown = 0.0;
+    }
+
+    /// This method is called from the main game state whenever a [`shared::messages::FromServer::YourEntityMoved`]
     /// message is received. It is the role of this method to ensure that previous predictions regarding player
-    /// entity position after movement were correct.
+    /// entity position after movement were correct. Should the server's position differ from that predicted, the
+    /// player entity is snapped to the server's position with any later movements still awaiting confirmation
+    /// replayed from there (i.e. their predicted positions are shifted by the same amount). Movements made by the
+    /// server of its own accord (e.g. warping through a portal) are applied immediately, with responses to movements
+    /// predicted before then snapping the player entity to the server's position.
     pub fn received_movement_reconciliation(
         &mut self, request_number: u32, position: TileCoords, renderer: &mut MapRenderer
     ) {
-        if let Some(predicted_position) = self.unverified_movements.get(&request_number) {
-            if *predicted_position != position {
+        if request_number == messages::SERVER_MOVE_REQUEST_NUMBER {
+            log::debug!("Player entity moved to {} by server", position);
+            self.teleported(position, renderer);
+            return;
+        }
+
+        self.time_awaiting_confirmation = 0.0;
+
+        match reconcile_pending_moves(&mut self.pending_moves, request_number, position) {
+            Reconciliation::Confirmed => {}
+
+            Reconciliation::Corrected { predicted_position } => {
                 log::warn!(
                     "Client-side movement prediction #{} position {} differs from server reconciliation of {}",
                     request_number,
@@ -118,21 +338,30 @@ impl MyEntity {
                     position
                 );
 
+                let corrected_position = self.pending_moves.back().map_or(position, |(_, pos)| *pos);
+
                 // Update map renderer:
-                renderer.my_entity_position_corrected(position);
+                renderer.my_entity_position_corrected(corrected_position);
 
                 // Correct position:
-                self.contained.pos = position;
+                self.contained.pos = corrected_position;
             }
-        }
-        else {
-            log::warn!(
-                "Received movement reconciliation for movement request #{} which could not found",
-                request_number
-            );
-        }
 
-        self.unverified_movements.remove(&request_number);
+            Reconciliation::NotFound => {
+                log::warn!(
+                    "Received movement reconciliation for movement request #{} which could not be found",
+                    request_number
+                );
+
+                // The prediction was discarded (e.g. upon being warped through a portal or respawning before the
+                // server responded to movements made beforehand) so the server's position is the best known. Should
+                // later movements still await confirmation then their responses will correct the position instead:
+                if self.pending_moves.is_empty() && self.contained.pos != position {
+                    renderer.my_entity_position_corrected(position);
+                    self.contained.pos = position;
+                }
+            }
+        }
     }
 
     /// Attempt to purchase a 'bool item' (an item that a player can either 0 or 1 of). Will send a message to the
@@ -174,11 +403,47 @@ impl MyEntity {
         Ok(will_buy)
     }
 
+    /// Use one of the given item (e.g. drink a potion), informing the server should doing so have any effect (see
+    /// [`Entity::use_item`]).
+    pub fn use_item(
+        &mut self, item: items::QuantitativeItem, connection: &mut networking::Connection
+    ) -> networking::Result<()> {
+        let action_option = ActionKind::of_using(item);
+
+        if action_option.is_some_and(|action| self.cooldown_remaining(action) > 0.0) {
+            return Ok(());
+        }
+
+        let health_before = self.contained.health;
+
+        if self.contained.use_item(item) {
+            connection.send(&messages::ToServer::UseItem(item))?;
+
+            if item == items::QuantitativeItem::Potion {
+                self.last_potion_health_restored = Some(self.contained.health - health_before);
+            }
+
+            if let Some(action) = action_option {
+                self.start_cooldown(action);
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn place_bomb(
         &mut self, map: &mut ClientMap, connection: &mut networking::Connection
     ) -> networking::Result<()> {
-        // Ensure player has a bomb in inventory to place:
-        if self.contained.item_inventory.has_how_many(items::QuantitativeItem::Bomb) >= 1 {
+        let below_bomb_limit =
+            !matches!(self.bomb_limit, Some(limit) if self.contained.bombs_placed_count >= limit as i32);
+
+        // Ensure player has a bomb in inventory to place, has not already placed the maximum number of bombs, is not
+        // within a safe zone, and is not waiting for the bomb placement cooldown:
+        if self.contained.item_inventory.has_how_many(items::QuantitativeItem::Bomb) >= 1
+            && below_bomb_limit
+            && !map.is_in_safe_zone(self.contained.pos)
+            && self.cooldown_remaining(ActionKind::PlaceBomb) <= 0.0
+        {
             self.contained.bombs_placed_count += 1;
 
             // Place the bomb on the map locally:
@@ -189,18 +454,37 @@ impl MyEntity {
 
             // Remove placed bomb from inventory:
             self.contained.item_inventory.take_quantity(items::QuantitativeItem::Bomb, 1);
+
+            self.start_cooldown(ActionKind::PlaceBomb);
+            self.last_bomb_position = Some(self.contained.pos);
         }
 
         Ok(())
     }
 
+    /// Undo the local placement of a bomb at the given position after the server rejected it. Should the rejection be
+    /// due to the player having reached the given limit on placed bombs, further attempts to exceed that limit will not
+    /// be sent to the server.
+    pub fn bomb_placement_rejected(&mut self, position: TileCoords, limit: Option<u32>, map: &mut ClientMap) {
+        if map.remove_bomb_at(position, self.id) {
+            self.contained.bombs_placed_count -= 1;
+            self.contained.item_inventory.give_quantity(items::QuantitativeItem::Bomb, 1);
+        }
+        else {
+            log::warn!("Could not find rejected bomb placed at {} to remove", position);
+        }
+
+        if limit.is_some() {
+            self.bomb_limit = limit;
+        }
+    }
+
     /// Detonate all the bombs placed by the player *within currently loaded chunks.*
     pub fn detonate_bombs(
         &mut self, map: &mut ClientMap, renderer: &mut MapRenderer, connection: &mut networking::Connection
     ) -> networking::Result<()> {
         // Remove bombs from map:
-        let detonated_bomb_positions =
-            map.take_bombs_placed_by_in_and_around_chunk(self.id, self.contained.pos.as_chunk_coords());
+        let detonated_bomb_positions = map.take_detonated_bombs(self.id, self.contained.pos.as_chunk_coords());
 
         let length = detonated_bomb_positions.len() as i32;
 
@@ -211,6 +495,9 @@ impl MyEntity {
             // Update count of how many bombs have been placed by the player:
             self.contained.bombs_placed_count -= length;
 
+            // Smash rocks caught in the blasts (the server will inform the client of any gems collected):
+            map.smash_tiles_around_bombs(&detonated_bomb_positions);
+
             // Have renderer animate the exploding bombs:
             renderer.bombs_detonated(detonated_bomb_positions);
         }
@@ -222,6 +509,10 @@ impl MyEntity {
         self.contained.bombs_placed_count
     }
 
+    pub fn get_id(&self) -> Id {
+        self.id
+    }
+
     pub fn get_contained_entity(&self) -> &Entity {
         &self.contained
     }
@@ -238,3 +529,272 @@ impl MyEntity {
         &self.contained.item_inventory
     }
 }
+
+/// The outcome of comparing a position confirmed by the server with the corresponding movement prediction.
+#[derive(Debug, PartialEq)]
+enum Reconciliation {
+    /// The server's position matches that predicted.
+    Confirmed,
+    /// The server's position differs from that predicted, so the predicted positions of later pending moves have been
+    /// corrected.
+    Corrected { predicted_position: TileCoords },
+    /// No pending move with the given request number exists.
+    NotFound
+}
+
+/// Remove the pending move with the given request number (along with any older moves, which the server will not
+/// respond to) and compare its predicted position with the given position confirmed by the server. Should they differ,
+/// the remaining pending moves are shifted by the difference so that they are replayed from the server's position.
+fn reconcile_pending_moves(
+    pending_moves: &mut PendingMoves, request_number: u32, position: TileCoords
+) -> Reconciliation {
+    while pending_moves.front().is_some_and(|(number, _)| *number < request_number) {
+        pending_moves.pop_front();
+    }
+
+    match pending_moves.front() {
+        Some(&(number, predicted_position)) if number == request_number => {
+            pending_moves.pop_front();
+
+            if predicted_position == position {
+                return Reconciliation::Confirmed;
+            }
+
+            let (offset_x, offset_y) = (position.x - predicted_position.x, position.y - predicted_position.y);
+
+            for (_, pos) in pending_moves.iter_mut() {
+                *pos = TileCoords { x: pos.x + offset_x, y: pos.y + offset_y };
+            }
+
+            Reconciliation::Corrected { predicted_position }
+        }
+
+        _ => Reconciliation::NotFound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::maps::entities::{EntityBuilder, DEFAULT_MAX_HEALTH};
+
+    use super::*;
+
+    #[test]
+    fn respawn_resets_position_health_and_dropped_gems() {
+        let id = Id::new(0);
+        let mut gem_collection = gems::Collection::default();
+        gem_collection.increase_quantity(Gem::Ruby, 7);
+
+        let entity =
+            EntityBuilder::new().pos(TileCoords { x: 5, y: -7 }).health(0).gem_collection(gem_collection).build();
+        let mut my_entity = MyEntity::new(entity, id);
+        let mut renderer = MapRenderer::new(my_entity.get_pos(), true);
+
+        my_entity.pending_moves.push_back((1, TileCoords { x: 5, y: -6 }));
+        my_entity.movement_time_countdown = 0.2;
+
+        let mut gems_dropped = gems::Collection::default();
+        gems_dropped.increase_quantity(Gem::Ruby, 3);
+        my_entity.respawn(TileCoords { x: 0, y: 0 }, &gems_dropped, &mut renderer);
+
+        assert_eq!(my_entity.get_pos(), TileCoords { x: 0, y: 0 });
+        assert!(my_entity.pending_moves.is_empty());
+        assert!(my_entity.movement_time_countdown <= 0.0);
+        assert_eq!(my_entity.get_contained_entity().health, my_entity.get_contained_entity().max_health);
+        assert_eq!(my_entity.get_contained_entity().gem_collection.get_quantity(Gem::Ruby), 4);
+    }
+
+    #[test]
+    fn server_initiated_move_discards_predictions() {
+        let mut my_entity = MyEntity::new(EntityBuilder::new().pos(TileCoords { x: 5, y: 5 }).build(), Id::new(0));
+        let mut renderer = MapRenderer::new(my_entity.get_pos(), true);
+
+        assert!(my_entity.next_request_number > messages::SERVER_MOVE_REQUEST_NUMBER);
+        my_entity.pending_moves.push_back((1, TileCoords { x: 6, y: 5 }));
+
+        let destination = TileCoords { x: -30, y: 12 };
+        my_entity.received_movement_reconciliation(messages::SERVER_MOVE_REQUEST_NUMBER, destination, &mut renderer);
+
+        assert_eq!(my_entity.get_pos(), destination);
+        assert!(my_entity.pending_moves.is_empty());
+    }
+
+    #[test]
+    fn rejected_bomb_placement_undone() {
+        let id = Id::new(0);
+        let pos = TileCoords { x: 2, y: 3 };
+        let mut my_entity = MyEntity::new(EntityBuilder::new().pos(pos).bombs_placed_count(1).build(), id);
+
+        let mut map = ClientMap::new();
+        map.add_chunk(pos.as_chunk_coords(), Default::default());
+        map.set_bomb_at(pos, id);
+
+        my_entity.bomb_placement_rejected(pos, Some(1), &mut map);
+
+        assert_eq!(my_entity.how_many_bombs_placed(), 0);
+        assert_eq!(my_entity.get_inventory().has_how_many(items::QuantitativeItem::Bomb), 1);
+        assert_eq!(my_entity.bomb_limit, Some(1));
+        assert!(!map.remove_bomb_at(pos, id));
+    }
+
+    #[test]
+    fn cooldown_rejection_undoes_bomb_placement() {
+        let id = Id::new(0);
+        let pos = TileCoords { x: 2, y: 3 };
+        let mut my_entity = MyEntity::new(EntityBuilder::new().pos(pos).bombs_placed_count(1).build(), id);
+
+        let mut map = ClientMap::new();
+        map.add_chunk(pos.as_chunk_coords(), Default::default());
+        map.set_bomb_at(pos, id);
+        my_entity.last_bomb_position = Some(pos);
+
+        my_entity.action_rejected_by_cooldown(ActionKind::PlaceBomb, 250, &mut map);
+
+        assert_eq!(my_entity.how_many_bombs_placed(), 0);
+        assert_eq!(my_entity.get_inventory().has_how_many(items::QuantitativeItem::Bomb), 1);
+        assert!(!map.remove_bomb_at(pos, id));
+
+        // The cooldown counts down from the time given by the server:
+        assert_eq!(my_entity.cooldown_remaining(ActionKind::PlaceBomb), 0.25);
+        my_entity.update(0.2);
+        assert!((my_entity.cooldown_remaining(ActionKind::PlaceBomb) - 0.05).abs() < 0.0001);
+        my_entity.update(0.2);
+        assert_eq!(my_entity.cooldown_remaining(ActionKind::PlaceBomb), 0.0);
+        assert_eq!(my_entity.cooldown_remaining(ActionKind::Emote), 0.0);
+    }
+
+    #[test]
+    fn cooldown_rejection_undoes_potion_use() {
+        let potion = items::QuantitativeItem::Potion;
+        let mut inventory = items::Inventory::default();
+        inventory.give_quantity(potion, 1);
+
+        let entity = EntityBuilder::new().health(DEFAULT_MAX_HEALTH - 10).item_inventory(inventory).build();
+        let mut my_entity = MyEntity::new(entity, Id::new(0));
+        let mut map = ClientMap::new();
+
+        // Simulate the potion having been used locally (as by `MyEntity::use_item`):
+        assert!(my_entity.contained.use_item(potion));
+        my_entity.last_potion_health_restored = Some(10);
+        assert_eq!(my_entity.get_contained_entity().health, DEFAULT_MAX_HEALTH);
+
+        my_entity.action_rejected_by_cooldown(ActionKind::UsePotion, 1000, &mut map);
+
+        assert_eq!(my_entity.get_contained_entity().health, DEFAULT_MAX_HEALTH - 10);
+        assert_eq!(my_entity.get_inventory().has_how_many(potion), 1);
+        assert_eq!(my_entity.cooldown_remaining(ActionKind::UsePotion), 1.0);
+
+        // A further rejection without the potion having been used again has no effect:
+        my_entity.action_rejected_by_cooldown(ActionKind::UsePotion, 1000, &mut map);
+        assert_eq!(my_entity.get_inventory().has_how_many(potion), 1);
+    }
+
+    #[test]
+    fn confirmed_moves_dequeued() {
+        let mut pending_moves =
+            PendingMoves::from(vec![(0, TileCoords { x: 1, y: 0 }), (1, TileCoords { x: 2, y: 0 })]);
+
+        assert_eq!(
+            reconcile_pending_moves(&mut pending_moves, 0, TileCoords { x: 1, y: 0 }),
+            Reconciliation::Confirmed
+        );
+        assert_eq!(pending_moves, [(1, TileCoords { x: 2, y: 0 })]);
+
+        assert_eq!(reconcile_pending_moves(&mut pending_moves, 0, TileCoords { x: 1, y: 0 }), Reconciliation::NotFound);
+        assert_eq!(
+            reconcile_pending_moves(&mut pending_moves, 1, TileCoords { x: 2, y: 0 }),
+            Reconciliation::Confirmed
+        );
+        assert!(pending_moves.is_empty());
+    }
+
+    #[test]
+    fn mispredicted_move_corrects_later_moves() {
+        let mut pending_moves = PendingMoves::from(vec![
+            (3, TileCoords { x: 0, y: 1 }),
+            (4, TileCoords { x: 0, y: 2 }),
+            (5, TileCoords { x: 1, y: 2 }),
+            (6, TileCoords { x: 1, y: 3 }),
+        ]);
+
+        // The server never responds to move #3 (e.g. should it have been sent before reconnecting) and did not allow
+        // move #4 so the entity remained at (0, 1):
+        assert_eq!(
+            reconcile_pending_moves(&mut pending_moves, 4, TileCoords { x: 0, y: 1 }),
+            Reconciliation::Corrected { predicted_position: TileCoords { x: 0, y: 2 } }
+        );
+        assert_eq!(pending_moves, [(5, TileCoords { x: 1, y: 1 }), (6, TileCoords { x: 1, y: 2 })]);
+    }
+
+    #[test]
+    fn reconciliation_snaps_to_server_position() {
+        let id = Id::new(0);
+        let mut my_entity = MyEntity::new(EntityBuilder::new().pos(TileCoords { x: 2, y: 1 }).build(), id);
+        let mut renderer = MapRenderer::new(my_entity.get_pos(), true);
+
+        my_entity.pending_moves.push_back((1, TileCoords { x: 1, y: 1 }));
+        my_entity.pending_moves.push_back((2, TileCoords { x: 2, y: 1 }));
+
+        // Only the latest pending move remains, replayed from the server's position:
+        my_entity.received_movement_reconciliation(1, TileCoords { x: 0, y: 0 }, &mut renderer);
+        assert_eq!(my_entity.get_pos(), TileCoords { x: 1, y: 0 });
+
+        // No pending moves remain so the entity is placed exactly at the server's position:
+        my_entity.received_movement_reconciliation(2, TileCoords { x: 0, y: 0 }, &mut renderer);
+        assert_eq!(my_entity.get_pos(), TileCoords { x: 0, y: 0 });
+        assert!(my_entity.pending_moves.is_empty());
+    }
+
+    #[test]
+    fn unanswered_moves_expire() {
+        let mut my_entity = MyEntity::new(EntityBuilder::new().pos(TileCoords { x: 1, y: 1 }).build(), Id::new(0));
+        let mut renderer = MapRenderer::new(my_entity.get_pos(), true);
+
+        for request_number in 1..=MAX_PENDING_MOVES as u32 {
+            my_entity.pending_moves.push_back((request_number, TileCoords { x: 1, y: 2 }));
+        }
+
+        // Any response from the server restarts the wait:
+        my_entity.update(PENDING_MOVES_TIMEOUT - 1.0);
+        my_entity.received_movement_reconciliation(1, TileCoords { x: 1, y: 2 }, &mut renderer);
+        my_entity.update(PENDING_MOVES_TIMEOUT - 1.0);
+        assert_eq!(my_entity.pending_moves.len(), MAX_PENDING_MOVES - 1);
+
+        my_entity.update(1.0);
+        assert!(my_entity.pending_moves.is_empty());
+    }
+
+    #[test]
+    fn responses_to_discarded_moves_correct_position() {
+        let mut my_entity = MyEntity::new(EntityBuilder::new().pos(TileCoords { x: 5, y: 5 }).build(), Id::new(0));
+        let mut renderer = MapRenderer::new(my_entity.get_pos(), true);
+
+        // Move #1 was sent before the player entity was warped through a portal so the server applied it from the
+        // portal's destination:
+        my_entity.pending_moves.push_back((1, TileCoords { x: 6, y: 5 }));
+        my_entity.received_movement_reconciliation(
+            messages::SERVER_MOVE_REQUEST_NUMBER,
+            TileCoords { x: -30, y: 12 },
+            &mut renderer
+        );
+        my_entity.received_movement_reconciliation(1, TileCoords { x: -29, y: 12 }, &mut renderer);
+
+        assert_eq!(my_entity.get_pos(), TileCoords { x: -29, y: 12 });
+    }
+
+    #[test]
+    fn reconnecting_keeps_local_state() {
+        let id = Id::new(0);
+        let mut my_entity = MyEntity::new(EntityBuilder::new().pos(TileCoords { x: 1, y: 1 }).build(), id);
+
+        my_entity.bomb_limit = Some(3);
+        my_entity.pending_moves.push_back((4, TileCoords { x: 1, y: 2 }));
+
+        my_entity.reconnected(EntityBuilder::new().pos(TileCoords { x: 8, y: 8 }).build());
+
+        assert_eq!(my_entity.get_id(), id);
+        assert_eq!(my_entity.get_pos(), TileCoords { x: 8, y: 8 });
+        assert_eq!(my_entity.bomb_limit, Some(3));
+        assert!(my_entity.pending_moves.is_empty());
+    }
+}
@@ -6,8 +6,9 @@ use std::collections::HashMap;
 pub use rendering::MapRenderer;
 use shared::{
     maps::{
-        entities::{Direction, Entities, Entity},
-        Chunk, ChunkCoords, Chunks, Map, Tile, TileCoords
+        self,
+        entities::{Direction, Entities, Entity, FacialExpression, Monster},
+        Chunk, ChunkCoords, Chunks, Map, SafeZone, Tile, TileCoords, VIEW_RADIUS
     },
     Id
 };
@@ -16,12 +17,38 @@ pub struct ClientMap {
     /// Chunks that are currently loaded (mapped to by chunk coordinate pairs).
     loaded_chunks: Chunks,
     /// All entities (except this client's player entity) that are on this map and within currently loaded chunks.
-    entities: Entities
+    entities: Entities,
+    /// All monsters within currently loaded chunks (mapped to by monster IDs).
+    monsters: HashMap<Id, Monster>,
+    /// Areas in which bombs cannot be placed and players cannot be damaged (as provided by the server).
+    safe_zones: Vec<SafeZone>
 }
 
 impl ClientMap {
     pub fn new() -> Self {
-        ClientMap { loaded_chunks: HashMap::new(), entities: HashMap::new() }
+        ClientMap {
+            loaded_chunks: HashMap::new(),
+            entities: HashMap::new(),
+            monsters: HashMap::new(),
+            safe_zones: Vec::new()
+        }
+    }
+
+    pub fn set_safe_zones(&mut self, safe_zones: Vec<SafeZone>) {
+        self.safe_zones = safe_zones;
+    }
+
+    pub fn safe_zones(&self) -> &[SafeZone] {
+        &self.safe_zones
+    }
+
+    pub fn is_in_safe_zone(&self, coords: TileCoords) -> bool {
+        maps::is_in_safe_zone(&self.safe_zones, coords)
+    }
+
+    /// Get the IDs of all entities positioned at the given tile coordinates.
+    pub fn entities_at_tile(&self, coords: TileCoords) -> Vec<Id> {
+        self.entities.iter().filter(|(_, entity)| entity.pos == coords).map(|(id, _)| *id).collect()
     }
 
     pub fn move_remote_entity(
@@ -49,6 +76,54 @@ impl ClientMap {
         self.some_entity_moved_to(new_pos, renderer);
     }
 
+    pub fn set_remote_entity_expression(&mut self, id: Id, expression: FacialExpression) {
+        if let Some(entity) = self.entities.get_mut(&id) {
+            entity.facial_expression = expression;
+        }
+        else {
+            log::warn!("Cannot set facial expression of entity {} as it is not loaded", id);
+        }
+    }
+
+    /// Set the health of a remote entity (the health of this client's own player entity is instead stored by
+    /// [`entities::MyEntity`]).
+    pub fn set_remote_entity_health(&mut self, id: Id, health: u32) {
+        if let Some(entity) = self.entities.get_mut(&id) {
+            entity.health = health;
+        }
+        else {
+            log::warn!("Cannot set health of entity {} as it is not loaded", id);
+        }
+    }
+
+    pub fn monster_by_id(&self, id: Id) -> Option<&Monster> {
+        self.monsters.get(&id)
+    }
+
+    pub fn add_monster(&mut self, id: Id, monster: Monster, renderer: &mut MapRenderer) {
+        renderer.add_monster(id, monster.pos);
+        self.monsters.insert(id, monster);
+    }
+
+    pub fn remove_monster(&mut self, id: Id, renderer: &mut MapRenderer) {
+        renderer.remove_monster(id);
+        self.monsters.remove(&id);
+    }
+
+    /// Unlike remote player entities, monsters never smash tiles as they move (see
+    /// [`Self::some_entity_moved_to`]).
+    pub fn move_monster(&mut self, id: Id, new_pos: TileCoords, direction: Direction, renderer: &mut MapRenderer) {
+        if let Some(monster) = self.monsters.get_mut(&id) {
+            renderer.monster_moved(id, new_pos);
+
+            monster.pos = new_pos;
+            monster.direction = direction;
+        }
+        else {
+            log::warn!("Cannot set position of monster {} as it is not loaded", id);
+        }
+    }
+
     /// Handles the changing of certain tiles when entities walk over them (e.g. turning a rock tile into a smashed rock
     /// with an animated transition). Should be called whenever an entity (whether remote or the local player entity)
     /// moves.
@@ -61,9 +136,68 @@ impl ClientMap {
         }
     }
 
-    pub fn get_loaded_chunk_coords(&self) -> impl Iterator<Item = ChunkCoords> + '_ {
+    /// Remove all loaded chunks that are more than `keep_radius_in_chunks` chunks away (Chebyshev distance) from the
+    /// chunk containing the given player position. The player's own chunk is never removed, even with a radius of 0.
+    pub fn evict_distant_chunks(&mut self, player_pos: TileCoords, keep_radius_in_chunks: i32) {
+        let centre = player_pos.as_chunk_coords();
+        let keep_radius = keep_radius_in_chunks.max(0);
+        let loaded_count = self.loaded_chunks.len();
+
+        self.loaded_chunks.retain(|coords, _| {
+            (coords.x - centre.x).abs() <= keep_radius && (coords.y - centre.y).abs() <= keep_radius
+        });
+
+        if self.loaded_chunks.len() < loaded_count {
+            log::debug!("Evicted {} chunks distant from {}", loaded_count - self.loaded_chunks.len(), centre);
+        }
+    }
+
+    /// Iterate over the coordinates of all loaded chunks.
+    pub fn loaded_chunk_coords(&self) -> impl Iterator<Item = ChunkCoords> + '_ {
         self.loaded_chunks.keys().copied()
     }
+
+    /// Iterate over the coordinates of the chunks within [`VIEW_RADIUS`] of the given chunk coordinates (typically
+    /// those of the player entity's chunk) that are not loaded. The server provides all chunks within that radius of
+    /// the player entity so these chunks are expected to be received soon.
+    pub fn requested_chunk_coords(&self, centre: ChunkCoords) -> impl Iterator<Item = ChunkCoords> + '_ {
+        (centre.y - VIEW_RADIUS..=centre.y + VIEW_RADIUS)
+            .flat_map(move |y| (centre.x - VIEW_RADIUS..=centre.x + VIEW_RADIUS).map(move |x| ChunkCoords { x, y }))
+            .filter(move |coords| !self.is_chunk_loaded(*coords))
+    }
+
+    /// Produces a grid of characters representing the chunks within the specified radius of the given chunk
+    /// coordinates (typically those of the player entity's chunk) for display in the debug overlay. Each row is
+    /// separated by a newline with the top row being the chunks with the highest y coordinate. Characters used:
+    /// * `▓` - Chunk is loaded.
+    /// * `░` - Chunk is not loaded but is adjacent to the centre chunk so is expected to be provided by the server.
+    /// * `·` - Chunk is not loaded.
+    #[cfg(debug_assertions)]
+    pub fn debug_chunk_grid(&self, centre: ChunkCoords, radius: i32) -> String {
+        let mut grid = String::new();
+
+        for y in (centre.y - radius..centre.y + radius + 1).rev() {
+            for x in centre.x - radius..centre.x + radius + 1 {
+                let coords = ChunkCoords { x, y };
+
+                grid.push(if self.is_chunk_loaded(coords) {
+                    '▓'
+                }
+                else if (coords.x - centre.x).abs() <= 1 && (coords.y - centre.y).abs() <= 1 {
+                    '░'
+                }
+                else {
+                    '·'
+                });
+            }
+
+            if y != centre.y - radius {
+                grid.push('\n');
+            }
+        }
+
+        grid
+    }
 }
 
 impl Map for ClientMap {
@@ -108,3 +242,108 @@ impl Map for ClientMap {
         self.entities.remove(&id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shared::maps::entities::EntityBuilder;
+
+    use super::*;
+
+    #[test]
+    fn debug_chunk_grid() {
+        let mut map = ClientMap::new();
+
+        for coords in &[ChunkCoords { x: 0, y: 0 }, ChunkCoords { x: 1, y: 1 }, ChunkCoords { x: -2, y: -2 }] {
+            map.add_chunk(*coords, Chunk::default());
+        }
+
+        let expected = "·····\n·░░▓·\n·░▓░·\n·░░░·\n▓····";
+        assert_eq!(map.debug_chunk_grid(ChunkCoords { x: 0, y: 0 }, 2), expected);
+    }
+
+    #[test]
+    fn requested_chunks_within_view_radius() {
+        let mut map = ClientMap::new();
+        let centre = ChunkCoords { x: 3, y: -1 };
+
+        map.add_chunk(centre, Chunk::default());
+        map.add_chunk(ChunkCoords { x: 10, y: 10 }, Chunk::default());
+
+        let requested: Vec<ChunkCoords> = map.requested_chunk_coords(centre).collect();
+
+        assert_eq!(requested.len(), ((VIEW_RADIUS * 2 + 1) * (VIEW_RADIUS * 2 + 1) - 1) as usize);
+        assert!(!requested.contains(&centre));
+        assert!(requested.contains(&ChunkCoords { x: 3 + VIEW_RADIUS, y: -1 - VIEW_RADIUS }));
+        assert!(!requested.contains(&ChunkCoords { x: 4 + VIEW_RADIUS, y: -1 }));
+    }
+
+    #[test]
+    fn evict_distant_chunks() {
+        let mut map = ClientMap::new();
+
+        for x in -3..=3 {
+            for y in -3..=3 {
+                map.add_chunk(ChunkCoords { x, y }, Chunk::default());
+            }
+        }
+
+        // Player in chunk (1, -1):
+        let player_pos = TileCoords { x: 20, y: -5 };
+
+        map.evict_distant_chunks(player_pos, 2);
+        assert_eq!(map.loaded_chunks.len(), 5 * 5);
+        assert!(map.is_chunk_loaded(ChunkCoords { x: 3, y: -3 }));
+        assert!(map.is_chunk_loaded(ChunkCoords { x: -1, y: 1 }));
+        assert!(!map.is_chunk_loaded(ChunkCoords { x: -2, y: 0 }));
+        assert!(!map.is_chunk_loaded(ChunkCoords { x: 1, y: 2 }));
+
+        // Player's own chunk is always kept:
+        map.evict_distant_chunks(player_pos, 0);
+        assert_eq!(map.loaded_chunks.keys().collect::<Vec<_>>(), vec![&ChunkCoords { x: 1, y: -1 }]);
+
+        map.evict_distant_chunks(player_pos, -1);
+        assert!(map.is_chunk_loaded(ChunkCoords { x: 1, y: -1 }));
+    }
+
+    #[test]
+    fn monsters_tracked() {
+        let mut map = ClientMap::new();
+        let mut renderer = MapRenderer::default();
+
+        let monster_id = Id::new(3);
+        map.add_monster(monster_id, Monster::new(TileCoords { x: 1, y: 1 }), &mut renderer);
+        map.move_monster(monster_id, TileCoords { x: 1, y: 2 }, Direction::Up, &mut renderer);
+
+        let monster = map.monster_by_id(monster_id).unwrap();
+        assert_eq!(monster.pos, TileCoords { x: 1, y: 2 });
+        assert_eq!(monster.direction, Direction::Up);
+
+        // Monsters do not block the player entity:
+        assert!(!map.is_blocking_entity_at(TileCoords { x: 1, y: 2 }));
+
+        map.remove_monster(monster_id, &mut renderer);
+        assert!(map.monster_by_id(monster_id).is_none());
+    }
+
+    #[test]
+    fn entities_at_tile() {
+        let mut map = ClientMap::new();
+
+        let entity_id = Id::new(1);
+        map.add_entity(entity_id, EntityBuilder::new().pos(TileCoords { x: 3, y: -4 }).build());
+        map.add_entity(Id::new(2), EntityBuilder::new().pos(TileCoords { x: 4, y: -4 }).build());
+
+        assert_eq!(map.entities_at_tile(TileCoords { x: 3, y: -4 }), vec![entity_id]);
+        assert!(map.entities_at_tile(TileCoords { x: 3, y: -3 }).is_empty());
+    }
+
+    #[test]
+    fn safe_zones_provided_by_server() {
+        let mut map = ClientMap::new();
+        assert!(!map.is_in_safe_zone(TileCoords { x: 0, y: 0 }));
+
+        map.set_safe_zones(vec![SafeZone { centre: TileCoords { x: 0, y: 0 }, radius: 3 }]);
+        assert!(map.is_in_safe_zone(TileCoords { x: 3, y: -3 }));
+        assert!(!map.is_in_safe_zone(TileCoords { x: 4, y: 0 }));
+    }
+}
@@ -24,7 +24,7 @@ pub trait Animation {
 }
 
 /// An animation that is just a single frame (i.e. not animated but implements [`Animation`]). Can be shared (see
-/// [`super::tiles::draw_with_stateless_animation`]).
+/// [`super::tiles::TileBatch`]).
 pub struct Static(pub u16, pub u16);
 
 impl Animation for Static {
@@ -38,7 +38,7 @@ pub fn boxed_static(x: u16, y: u16) -> Box<dyn Animation + Sync> {
 }
 
 /// An animation that continuously loops the same set of frames. Can be shared (see
-/// [`super::tiles::draw_with_stateless_animation`]).
+/// [`super::tiles::TileBatch`]).
 pub struct Continuous {
     frames: &'static [Frame],
     duration: f64
@@ -1,13 +1,31 @@
 mod colours;
 
+use colours::ToQuadColor;
 use macroquad::prelude as quad;
-use shared::maps::{
-    entities::{Direction, Entity, FacialExpression, HairStyle},
-    TileCoords
+use shared::{
+    maps::{
+        entities::{Direction, Entity, FacialExpression, HairStyle, Monster},
+        TileCoords
+    },
+    messages::EmoteKind
 };
 
 use super::{tile_coords_to_vec2, SINGLE_TILE_TEXTURE_SIZE};
 
+/// The colours between which the overlay drawn over an entity standing on a hazardous tile pulses.
+const HAZARD_OVERLAY_ORANGE: quad::Color = quad::Color::new(1.0, 0.55, 0.0, 0.45);
+const HAZARD_OVERLAY_RED: quad::Color = quad::Color::new(0.9, 0.05, 0.0, 0.45);
+/// How quickly (in radians per second) the hazard overlay pulses.
+const HAZARD_OVERLAY_PULSE_SPEED: f32 = 6.0;
+
+/// The time (in seconds) for which an emote is displayed above an entity.
+const EMOTE_DURATION: f32 = 2.0;
+/// The time (in seconds) for which each frame of an emote animation is displayed.
+const EMOTE_FRAME_TIME: f32 = 0.25;
+/// The row of the entities texture containing the animation frames of the first emote (with the frames of each
+/// subsequent emote on the rows beneath).
+const EMOTE_TEXTURE_FIRST_ROW: u16 = 5;
+
 /// Handles the rendering of a single entity.
 #[derive(Default)]
 pub struct Renderer {
@@ -23,7 +41,10 @@ pub struct Renderer {
     /// Time that has passed since the last animated walk cycle frame change.
     time_since_frame_change: f32,
     /// Store what frame of the animated walk cycle the entity is currently on.
-    walk_frame: WalkCycle
+    walk_frame: WalkCycle,
+    /// The emote being performed by the entity (if any) along with the time (in seconds) remaining before it is no
+    /// longer displayed.
+    active_emote: Option<(EmoteKind, f32)>
 }
 
 #[derive(Default)]
@@ -64,11 +85,30 @@ impl Renderer {
         });
     }
 
+    /// Immediately move the entity to the given coordinates without any animation, cancelling any queued movements.
+    pub fn teleport(&mut self, to_coords: TileCoords, tile_draw_size: f32) {
+        self.movement_queue.clear();
+        self.current_pos = tile_coords_to_vec2(to_coords, tile_draw_size);
+    }
+
+    /// Begin displaying the given emote above the entity, replacing any emote already being displayed.
+    pub fn start_emote(&mut self, emote: EmoteKind) {
+        self.active_emote = Some((emote, EMOTE_DURATION));
+    }
+
     /// Update draw position and animations.
     pub fn update(&mut self, delta: f32) {
         self.time_since_movement_began += delta;
         self.time_since_frame_change += delta;
 
+        if let Some((_, time_remaining)) = &mut self.active_emote {
+            *time_remaining -= delta;
+
+            if *time_remaining <= 0.0 {
+                self.active_emote = None;
+            }
+        }
+
         if let Some(current_movement) = self.movement_queue.first() {
             // Adjust the position at which the entity is to be drawn:
             self.current_pos += current_movement.movement * delta;
@@ -101,15 +141,15 @@ impl Renderer {
             texture,
             0.0,
             0.0,
-            colours::clothing(entity.clothing_colour),
+            entity.clothing_colour.to_quad_color(),
             body_draw_params(entity, self.walk_frame, tile_draw_size)
         );
     }
 
     /// Draw the upper portion of the entity (head, face, hands, etc.)
     pub fn draw_upper(&self, entity: &Entity, texture: quad::Texture2D, tile_draw_size: f32) {
-        let skin_colour = colours::skin(entity.skin_colour);
-        let hair_colour = colours::hair(entity.hair_colour);
+        let skin_colour = entity.skin_colour.to_quad_color();
+        let hair_colour = entity.hair_colour.to_quad_color();
 
         // Head:
         self.draw_part(
@@ -172,6 +212,43 @@ impl Renderer {
             hair_colour,
             hair_draw_params(entity, tile_draw_size)
         );
+
+        // Emote (drawn just above the entity's hair):
+
+        if let Some((emote, time_remaining)) = self.active_emote {
+            self.draw_part(
+                texture,
+                tile_draw_size * 0.125,
+                (tile_draw_size * 1.375) + head_bob,
+                quad::WHITE,
+                emote_draw_params(emote, time_remaining, tile_draw_size)
+            );
+        }
+    }
+
+    /// Draw a pulsing orange/red overlay over the entity to indicate that it is being damaged by the hazardous tile
+    /// (e.g. lava) that it is standing on.
+    pub fn draw_hazard_overlay(&self, tile_draw_size: f32, elapsed_secs: f32) {
+        // Cover the entity's head too, which extends above its tile:
+        quad::draw_rectangle(
+            self.current_pos.x,
+            self.current_pos.y,
+            tile_draw_size,
+            tile_draw_size * 1.25,
+            hazard_overlay_colour(elapsed_secs)
+        );
+    }
+
+    /// Draw the given monster. Unlike player entities, monsters are drawn using a single texture rect (per walk cycle
+    /// frame) located beneath those of player entities.
+    pub fn draw_monster(&self, monster: &Monster, texture: quad::Texture2D, tile_draw_size: f32) {
+        self.draw_part(
+            texture,
+            0.0,
+            0.0,
+            colours::MONSTER,
+            monster_draw_params(monster, self.walk_frame, tile_draw_size)
+        );
     }
 
     /// Draw a component of the entity (hair, eye, etc.) using the specified drawing parameters.
@@ -329,6 +406,60 @@ fn mouth_draw_params(entity: &Entity, tile_draw_size: f32) -> quad::DrawTextureP
     }
 }
 
+fn monster_draw_params(monster: &Monster, walk_frame: WalkCycle, tile_draw_size: f32) -> quad::DrawTextureParams {
+    let (x_relative, walk_frame_flip) = match walk_frame {
+        WalkCycle::BeforeRight | WalkCycle::BeforeLeft => (0, false),
+        WalkCycle::Right => (1, false),
+        WalkCycle::Left => (1, true)
+    };
+
+    quad::DrawTextureParams {
+        dest_size: Some(quad::vec2(tile_draw_size, tile_draw_size)),
+        source: Some(crate::make_texture_source_rect(SINGLE_TILE_TEXTURE_SIZE, (x_relative, 4), (1, 1))),
+        flip_x: (monster.direction == Direction::Left) != walk_frame_flip,
+        flip_y: true,
+        ..Default::default()
+    }
+}
+
+/// The number of frames in the animation of the given emote.
+fn emote_frame_count(emote: EmoteKind) -> u16 {
+    match emote {
+        EmoteKind::Wave => 2,
+        EmoteKind::Dance => 4,
+        EmoteKind::Cheer => 3,
+        EmoteKind::Sad => 2
+    }
+}
+
+/// The texture coordinates (in tiles) of the animation frame of the given emote to be displayed with the specified
+/// amount of time remaining before the emote is no longer displayed.
+fn emote_frame_texture_coords(emote: EmoteKind, time_remaining: f32) -> (u16, u16) {
+    let frames_elapsed = ((EMOTE_DURATION - time_remaining).max(0.0) / EMOTE_FRAME_TIME) as u16;
+
+    let row = match emote {
+        EmoteKind::Wave => 0,
+        EmoteKind::Dance => 1,
+        EmoteKind::Cheer => 2,
+        EmoteKind::Sad => 3
+    };
+
+    (frames_elapsed % emote_frame_count(emote), EMOTE_TEXTURE_FIRST_ROW + row)
+}
+
+fn emote_draw_params(emote: EmoteKind, time_remaining: f32, tile_draw_size: f32) -> quad::DrawTextureParams {
+    quad::DrawTextureParams {
+        dest_size: Some(quad::vec2(tile_draw_size * 0.75, tile_draw_size * 0.75)),
+        source: Some(crate::make_texture_source_rect(
+            SINGLE_TILE_TEXTURE_SIZE,
+            emote_frame_texture_coords(emote, time_remaining),
+            (1, 1)
+        )),
+        flip_y: true,
+        ..Default::default()
+    }
+}
+
 fn eye_or_mouth_texture_rect(x_relative: u16, y_relative: u16) -> quad::Rect {
     quad::Rect {
         x: ((x_relative + 4) * (SINGLE_TILE_TEXTURE_SIZE / 2)) as f32,
@@ -337,3 +468,61 @@ fn eye_or_mouth_texture_rect(x_relative: u16, y_relative: u16) -> quad::Rect {
         h: (SINGLE_TILE_TEXTURE_SIZE / 2) as f32
     }
 }
+
+/// The colour of the hazard overlay after the specified amount of time, which cycles between orange and red.
+fn hazard_overlay_colour(elapsed_secs: f32) -> quad::Color {
+    let t = ((elapsed_secs * HAZARD_OVERLAY_PULSE_SPEED).sin() + 1.0) / 2.0;
+    let lerp = |from: f32, to: f32| from + ((to - from) * t);
+
+    quad::Color::new(
+        lerp(HAZARD_OVERLAY_ORANGE.r, HAZARD_OVERLAY_RED.r),
+        lerp(HAZARD_OVERLAY_ORANGE.g, HAZARD_OVERLAY_RED.g),
+        lerp(HAZARD_OVERLAY_ORANGE.b, HAZARD_OVERLAY_RED.b),
+        lerp(HAZARD_OVERLAY_ORANGE.a, HAZARD_OVERLAY_RED.a)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hazard_overlay_pulses_between_orange_and_red() {
+        let (mut reddest, mut most_orange) = (f32::MAX, f32::MIN);
+
+        for step in 0..200 {
+            let colour = hazard_overlay_colour(step as f32 * 0.01);
+
+            assert!((HAZARD_OVERLAY_RED.g..=HAZARD_OVERLAY_ORANGE.g).contains(&colour.g));
+            assert!(colour.r >= HAZARD_OVERLAY_RED.r && colour.r <= HAZARD_OVERLAY_ORANGE.r);
+
+            reddest = reddest.min(colour.g);
+            most_orange = most_orange.max(colour.g);
+        }
+
+        assert!(reddest - HAZARD_OVERLAY_RED.g < 0.01);
+        assert!(HAZARD_OVERLAY_ORANGE.g - most_orange < 0.01);
+    }
+
+    #[test]
+    fn emote_animates_then_ends() {
+        let mut renderer = Renderer::default();
+        renderer.start_emote(EmoteKind::Cheer);
+
+        let mut frames = Vec::new();
+        while let Some((emote, time_remaining)) = renderer.active_emote {
+            frames.push(emote_frame_texture_coords(emote, time_remaining));
+            renderer.update(EMOTE_FRAME_TIME);
+        }
+
+        let cheer_row = EMOTE_TEXTURE_FIRST_ROW + 2;
+        assert_eq!(frames.len(), (EMOTE_DURATION / EMOTE_FRAME_TIME) as usize);
+        assert_eq!(&frames[..4], &[(0, cheer_row), (1, cheer_row), (2, cheer_row), (0, cheer_row)]);
+
+        // Starting another emote replaces the current one:
+        renderer.start_emote(EmoteKind::Wave);
+        renderer.update(EMOTE_FRAME_TIME);
+        renderer.start_emote(EmoteKind::Sad);
+        assert_eq!(renderer.active_emote, Some((EmoteKind::Sad, EMOTE_DURATION)));
+    }
+}
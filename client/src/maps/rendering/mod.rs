@@ -8,7 +8,9 @@ use std::collections::HashMap;
 use animations::Animation;
 use macroquad::prelude as quad;
 use shared::{
-    maps::{entities::Entity, ChunkCoords, Map, OffsetCoords, TileCoords},
+    gems::Gem,
+    maps::{self, entities::Entity, Map, OffsetCoords, SafeZone, TileCoords, TileQuery, SIGHT_RADIUS},
+    messages::EmoteKind,
     Id
 };
 
@@ -17,12 +19,37 @@ use crate::{maps::ClientMap, AssetManager, TextureKey};
 /// The width and height (in camera space) that each tile will be draw as.
 const TILE_DRAW_SIZE: f32 = 0.1;
 
+/// The minimum & maximum factors by which the map may be zoomed (see [`MapRenderer::update_zoom`]). Zoom levels below 1
+/// show more of the map while those above 1 show the map in greater detail.
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 2.0;
+
+/// The factor by which the zoom level is multiplied/divided for each step of the mouse wheel.
+const ZOOM_STEP: f32 = 1.1;
+
+/// The opacity of the fog drawn over tiles that have never been seen by a player.
+const UNSEEN_FOG_ALPHA: f32 = 0.7;
+
+/// The opacity of the fog drawn over tiles that have been seen but are not currently within sight of this client's
+/// player entity (i.e. such tiles are drawn at half brightness).
+const OUT_OF_SIGHT_FOG_ALPHA: f32 = 0.5;
+
 /// The width and height (in pixels) that each individual tile on the tiles texture is.
 const SINGLE_TILE_TEXTURE_SIZE: u16 = 16;
 
 /// The time taken for the movement to complete when an entity's position is corrected.
 const ENTITY_POSITION_CORRECTED_MOVEMENT_TIME: f32 = 0.025;
 
+/// The time (in seconds) taken for a monster to move to an adjacent tile. Monsters move at most once per server tick so
+/// this is kept shorter than the default tick interval.
+const MONSTER_MOVEMENT_TIME: f32 = 0.09;
+
+/// The time (in seconds) for which a label indicating the collection of gems is displayed.
+const COLLECTED_GEMS_LABEL_TIME: f32 = 1.0;
+
+/// The distance (in pixels) that a collected gems label rises over the time it is displayed.
+const COLLECTED_GEMS_LABEL_RISE: f32 = 40.0;
+
 /// Handles the drawing of a game map.
 #[derive(Default)]
 pub struct MapRenderer {
@@ -32,20 +59,74 @@ pub struct MapRenderer {
     my_entity_renderer: entities::Renderer,
     /// Entity renderers for remote player entities (mapped to by entity IDs).
     remote_entity_renderers: HashMap<Id, entities::Renderer>,
+    /// Renderers for monsters (mapped to by monster IDs).
+    monster_renderers: HashMap<Id, entities::Renderer>,
     /// Stores animations for transitions between tile types.
     tile_change_animations: HashMap<TileCoords, animations::Once>,
     /// Stores pairs of bomb explosion animations and lists of positions where animations should play.
-    exploding_bomb_animations: Vec<(animations::Once, Vec<TileCoords>)>
+    exploding_bomb_animations: Vec<(animations::Once, Vec<TileCoords>)>,
+    /// Coordinates of the tile beneath the mouse cursor (updated each frame).
+    hovered_tile: Option<TileCoords>,
+    /// Labels displayed above tiles from which this client's player entity has collected gems.
+    collected_gems_labels: Vec<CollectedGemsLabel>,
+    /// Total time (in seconds) that this renderer has been drawing for (used to animate pending tile placeholders).
+    elapsed_secs: f32,
+    /// The factor by which the map is magnified (between [`MIN_ZOOM`] and [`MAX_ZOOM`]).
+    zoom_level: f32,
+    /// The distance (in pixels) between the two touches of the pinch gesture currently being performed (if any) as of
+    /// the previous frame.
+    pinch_distance: Option<f32>,
+    /// Whether tiles that are not within sight of this client's player entity are obscured.
+    fog_of_war: bool
+}
+
+/// A label that rises from & fades away above the tile at which gems were collected.
+struct CollectedGemsLabel {
+    coords: TileCoords,
+    text: String,
+    colour: quad::Color,
+    /// Time (in seconds) remaining before the label is no longer displayed.
+    time_remaining: f32
 }
 
 impl MapRenderer {
-    pub fn new(my_entity_pos: TileCoords) -> Self {
-        MapRenderer { my_entity_renderer: entities::Renderer::new(my_entity_pos), ..Default::default() }
+    pub fn new(my_entity_pos: TileCoords, fog_of_war: bool) -> Self {
+        MapRenderer {
+            my_entity_renderer: entities::Renderer::new(my_entity_pos),
+            zoom_level: 1.0,
+            fog_of_war,
+            ..Default::default()
+        }
+    }
+
+    /// Zoom in/out based on mouse wheel movement or, on touch screen devices, a two-finger pinch gesture. Should be
+    /// called once each frame before drawing.
+    pub fn update_zoom(&mut self) {
+        let (_, wheel_y) = quad::mouse_wheel();
+
+        if wheel_y != 0.0 {
+            self.zoom_level = zoom_within_limits(self.zoom_level * ZOOM_STEP.powf(wheel_y.signum()));
+        }
+
+        match quad::touches().as_slice() {
+            [first, second] => {
+                let distance = first.position.distance(second.position);
+
+                if let Some(previous_distance) = self.pinch_distance.filter(|previous| *previous > 0.0) {
+                    self.zoom_level = zoom_within_limits(self.zoom_level * (distance / previous_distance));
+                }
+
+                self.pinch_distance = Some(distance);
+            }
+
+            _ => self.pinch_distance = None
+        }
     }
 
     /// Draws the tiles & entities than are within the bounds of the camera's viewport.
     pub fn draw(&mut self, map: &ClientMap, my_entity_contained: &Entity, assets: &AssetManager, delta: f32) {
-        // Adjust camera zoom so that textures don't become distorted when the screen is resized:
+        // Adjust camera zoom so that textures don't become distorted when the screen is resized. Magnifying the camera
+        // by the zoom level has the same effect as scaling the draw size of each tile by it:
 
         self.camera.zoom = {
             if quad::screen_width() > quad::screen_height() {
@@ -54,10 +135,12 @@ impl MapRenderer {
             else {
                 quad::vec2(quad::screen_height() / quad::screen_width(), 1.0)
             }
-        };
+        } * self.zoom_level;
 
         // Update this client's entity and centre camera around it:
 
+        self.elapsed_secs += delta;
+
         self.my_entity_renderer.update(delta);
         self.camera.target =
             self.my_entity_renderer.current_pos + quad::vec2(TILE_DRAW_SIZE / 2.0, TILE_DRAW_SIZE / 2.0);
@@ -65,62 +148,119 @@ impl MapRenderer {
         // Begin drawing in camera space:
         quad::set_camera(self.camera);
 
+        self.hovered_tile = Some(self.cursor_tile_coords());
+
         // Establish the area of the map that is actually on-screen:
 
-        let on_screen_tiles_left_boundary = ((self.camera.target.x - 1.0) / TILE_DRAW_SIZE).floor() as i32;
-        let on_screen_tiles_right_boundary = ((self.camera.target.x + 1.0) / TILE_DRAW_SIZE).ceil() as i32;
-        let on_screen_tiles_bottom_boundary = ((self.camera.target.y - 1.0) / TILE_DRAW_SIZE).floor() as i32;
-        let on_screen_tiles_top_boundary = ((self.camera.target.y + 1.0) / TILE_DRAW_SIZE).ceil() as i32;
+        let (
+            on_screen_tiles_left_boundary,
+            on_screen_tiles_right_boundary,
+            on_screen_tiles_bottom_boundary,
+            on_screen_tiles_top_boundary
+        ) = on_screen_tile_boundaries(self.camera.target, self.camera.zoom);
 
         // Draw tiles:
 
         let mut tile_coords;
         let mut draw_pos;
 
+        // All tiles are collected into a batch that is drawn using a single mesh for the tiles texture. Untextured
+        // shapes (placeholders, highlights, debug markers) are drawn only after the batch so that they appear on top:
+        let tiles_texture = assets.texture(TextureKey::Tiles);
+        let mut tile_batch = tiles::TileBatch::new(quad::vec2(tiles_texture.width(), tiles_texture.height()));
+        let time = quad::get_time();
+
+        let mut pending_draw_positions = Vec::new();
+        let mut out_of_bounds_draw_positions = Vec::new();
+        let mut fog_draw_positions = Vec::new();
+        let mut safe_zone_draw_positions = Vec::new();
+        let mut hover_highlight = None;
+
         for tile_x in on_screen_tiles_left_boundary..on_screen_tiles_right_boundary {
             for tile_y in on_screen_tiles_bottom_boundary..on_screen_tiles_top_boundary {
                 tile_coords = TileCoords { x: tile_x, y: tile_y };
                 draw_pos = tile_coords_to_vec2(tile_coords, TILE_DRAW_SIZE);
 
                 // If the tile at the specified coordinates is in a chunk that is already loaded then it will be drawn.
-                // Otherwise, a grey placeholder rectangle will be drawn in its place until the required chunk is
-                // received from the server.
+                // Otherwise, a shimmering grey placeholder rectangle will be drawn in its place until the required
+                // chunk is received from the server. A red placeholder is drawn should the tile's chunk
+                // offset somehow be out of bounds.
+
+                match map.query_tile_at(tile_coords) {
+                    TileQuery::Loaded(tile) => {
+                        tile_batch.push(tile, draw_pos, TILE_DRAW_SIZE, time);
+
+                        if self.hovered_tile == Some(tile_coords) {
+                            hover_highlight = Some((draw_pos, tile.is_blocking()));
+                        }
+
+                        if map.is_in_safe_zone(tile_coords) {
+                            safe_zone_draw_positions.push((draw_pos, safe_zone_borders(map.safe_zones(), tile_coords)));
+                        }
+
+                        if self.fog_of_war {
+                            if !map.is_tile_seen(tile_coords) {
+                                fog_draw_positions.push((draw_pos, UNSEEN_FOG_ALPHA));
+                            }
+                            else if tile_coords.chebyshev_distance(my_entity_contained.pos) > SIGHT_RADIUS as u32 {
+                                fog_draw_positions.push((draw_pos, OUT_OF_SIGHT_FOG_ALPHA));
+                            }
+                        }
+                    }
+                    TileQuery::ChunkNotLoaded => pending_draw_positions.push(draw_pos),
+                    TileQuery::OutOfBounds => out_of_bounds_draw_positions.push(draw_pos)
+                }
+            }
+        }
 
-                if let Some(tile) = map.loaded_tile_at(tile_coords) {
-                    let chunk_corner = tile_coords.as_chunk_offset_coords() == OffsetCoords { x: 0, y: 0 };
+        tile_batch.draw(tiles_texture);
+
+        for (draw_pos, borders) in safe_zone_draw_positions {
+            tiles::draw_safe_zone(draw_pos, TILE_DRAW_SIZE, borders);
+        }
+
+        for (draw_pos, alpha) in fog_draw_positions {
+            tiles::draw_fog(draw_pos, TILE_DRAW_SIZE, alpha);
+        }
+
+        for draw_pos in pending_draw_positions {
+            tiles::draw_pending_tile(draw_pos, TILE_DRAW_SIZE, self.elapsed_secs);
+        }
+
+        for draw_pos in out_of_bounds_draw_positions {
+            tiles::draw_out_of_bounds(draw_pos, TILE_DRAW_SIZE);
+        }
 
-                    tiles::draw_with_stateless_animation(
-                        tile,
-                        draw_pos,
+        if let Some((draw_pos, blocking)) = hover_highlight {
+            tiles::draw_hover_highlight(draw_pos, TILE_DRAW_SIZE, blocking);
+        }
+
+        #[cfg(debug_assertions)]
+        for tile_x in on_screen_tiles_left_boundary..on_screen_tiles_right_boundary {
+            for tile_y in on_screen_tiles_bottom_boundary..on_screen_tiles_top_boundary {
+                tile_coords = TileCoords { x: tile_x, y: tile_y };
+
+                if map.is_tile_loaded(tile_coords) {
+                    let chunk_corner = tile_coords.as_chunk_offset_coords() == OffsetCoords { x: 0, y: 0 };
+                    tiles::draw_debug_marker(
+                        tile_coords_to_vec2(tile_coords, TILE_DRAW_SIZE),
                         TILE_DRAW_SIZE,
-                        assets.texture(TextureKey::Tiles),
                         chunk_corner
                     );
                 }
-                else {
-                    tiles::draw_pending(draw_pos, TILE_DRAW_SIZE);
-                }
             }
         }
 
-        // Identify the coordinates of chunks that are on-screen:
+        // Identify the area of chunks that are on-screen:
 
-        let mut on_screen_chunk_coords = Vec::new();
-
-        let bottom_left_on_screen_chunk_coords =
-            TileCoords { x: on_screen_tiles_left_boundary, y: on_screen_tiles_bottom_boundary }.as_chunk_coords();
-        let top_right_on_screen_chunk_coords =
-            TileCoords { x: on_screen_tiles_right_boundary, y: on_screen_tiles_top_boundary }.as_chunk_coords();
-
-        for x in bottom_left_on_screen_chunk_coords.x..top_right_on_screen_chunk_coords.x + 1 {
-            for y in bottom_left_on_screen_chunk_coords.y..top_right_on_screen_chunk_coords.y + 1 {
-                on_screen_chunk_coords.push(ChunkCoords { x, y });
-            }
-        }
+        let top_left_on_screen_chunk_coords =
+            TileCoords { x: on_screen_tiles_left_boundary, y: on_screen_tiles_top_boundary }.as_chunk_coords();
+        let bottom_right_on_screen_chunk_coords =
+            TileCoords { x: on_screen_tiles_right_boundary, y: on_screen_tiles_bottom_boundary }.as_chunk_coords();
 
         // Draw undetonated bombs:
 
-        for chunk in on_screen_chunk_coords.into_iter().filter_map(|coords| map.loaded_chunk_at(coords)) {
+        for (_, chunk) in map.chunks_in_rect(top_left_on_screen_chunk_coords, bottom_right_on_screen_chunk_coords) {
             // Iterate all bomb positions within the chunk irrespective of who placed them:
             for bomb_coords in chunk.get_undetonated_bomb_positions() {
                 let draw_pos = tile_coords_to_vec2(*bomb_coords, TILE_DRAW_SIZE);
@@ -140,20 +280,31 @@ impl MapRenderer {
 
         // Update remote entities:
 
-        for renderer in self.remote_entity_renderers.values_mut() {
+        for renderer in self.remote_entity_renderers.values_mut().chain(self.monster_renderers.values_mut()) {
             renderer.update(delta);
         }
 
+        // Draw on-screen monsters beneath player entities:
+
+        let is_on_screen = |pos: TileCoords| {
+            on_screen_tiles_left_boundary <= pos.x
+                && pos.x <= on_screen_tiles_right_boundary
+                && on_screen_tiles_bottom_boundary <= pos.y
+                && pos.y <= on_screen_tiles_top_boundary
+        };
+
+        for (id, renderer) in &self.monster_renderers {
+            if let Some(monster) = map.monster_by_id(*id).filter(|monster| is_on_screen(monster.pos)) {
+                renderer.draw_monster(monster, assets.texture(TextureKey::Entities), TILE_DRAW_SIZE);
+            }
+        }
+
         // Draw entities:
 
         let remote_entities_to_draw = self.remote_entity_renderers.iter().filter_map(|(id, renderer)| {
             if let Some(entity) = map.entity_by_id(*id) {
                 // Is the entity actually on screen?
-                if on_screen_tiles_left_boundary <= entity.pos.x
-                    && entity.pos.x <= on_screen_tiles_right_boundary
-                    && on_screen_tiles_bottom_boundary <= entity.pos.y
-                    && entity.pos.y <= on_screen_tiles_top_boundary
-                {
+                if is_on_screen(entity.pos) {
                     return Some((entity, renderer));
                 }
             }
@@ -175,6 +326,15 @@ impl MapRenderer {
             renderer.draw_upper(entity, assets.texture(TextureKey::Entities), TILE_DRAW_SIZE);
         }
 
+        // Indicate that this client's player entity is being damaged by the tile it is standing on (e.g. lava):
+
+        let my_tile_hazardous =
+            map.loaded_tile_at(my_entity_contained.pos).and_then(|tile| tile.hazard_damage()).is_some();
+
+        if my_tile_hazardous && !map.is_in_safe_zone(my_entity_contained.pos) {
+            self.my_entity_renderer.draw_hazard_overlay(TILE_DRAW_SIZE, self.elapsed_secs);
+        }
+
         // Draw exploding bombs:
 
         self.exploding_bomb_animations.retain(|(animation, positions)| {
@@ -211,6 +371,11 @@ impl MapRenderer {
         self.my_entity_renderer.do_movement(correct_coords, ENTITY_POSITION_CORRECTED_MOVEMENT_TIME, 1, TILE_DRAW_SIZE);
     }
 
+    /// Instantly move this client's entity to the given coordinates (e.g. when respawning).
+    pub fn my_entity_teleported(&mut self, coords: TileCoords) {
+        self.my_entity_renderer.teleport(coords, TILE_DRAW_SIZE);
+    }
+
     /// Begin the animated movement of the specified remote entity to the given position. This method is to be called by
     /// the [`ClientMap::set_remote_entity_position`] method.
     pub fn remote_entity_moved(
@@ -224,6 +389,19 @@ impl MapRenderer {
         );
     }
 
+    /// Begin displaying the given emote above this client's player entity.
+    pub fn my_entity_emoted(&mut self, emote: EmoteKind) {
+        self.my_entity_renderer.start_emote(emote);
+    }
+
+    /// Begin displaying the given emote above the specified remote entity (should it have been provided by the
+    /// server).
+    pub fn remote_entity_emoted(&mut self, entity_id: Id, emote: EmoteKind) {
+        if let Some(renderer) = self.remote_entity_renderers.get_mut(&entity_id) {
+            renderer.start_emote(emote);
+        }
+    }
+
     pub fn add_remote_entity(&mut self, entity_id: Id, coords: TileCoords) {
         self.remote_entity_renderers.insert(entity_id, entities::Renderer::new(coords));
     }
@@ -232,6 +410,25 @@ impl MapRenderer {
         self.remote_entity_renderers.remove(&entity_id);
     }
 
+    pub fn add_monster(&mut self, monster_id: Id, coords: TileCoords) {
+        self.monster_renderers.insert(monster_id, entities::Renderer::new(coords));
+    }
+
+    pub fn remove_monster(&mut self, monster_id: Id) {
+        self.monster_renderers.remove(&monster_id);
+    }
+
+    /// Begin the animated movement of the specified monster to the given position. This method is to be called by the
+    /// [`ClientMap::move_monster`] method.
+    pub fn monster_moved(&mut self, monster_id: Id, to_coords: TileCoords) {
+        self.monster_renderers.entry(monster_id).or_default().do_movement(
+            to_coords,
+            MONSTER_MOVEMENT_TIME,
+            2,
+            TILE_DRAW_SIZE
+        );
+    }
+
     /// Has a smashing animation play at the specified coordinates. This method is to be called when a rock tile is
     // turned into a smashed rock by the [`ClientMap::some_entity_moved_to`] method.
     pub fn rock_tile_smashed(&mut self, coords: TileCoords) {
@@ -241,8 +438,281 @@ impl MapRenderer {
     pub fn bombs_detonated(&mut self, positions: Vec<TileCoords>) {
         self.exploding_bomb_animations.push((bombs::make_detonating_bomb_animation(), positions));
     }
+
+    /// Display a label above the specified tile indicating the type & quantity of gems collected there. This method is
+    /// to be called whenever a [`shared::messages::FromServer::YouCollectedGems`] message is received.
+    pub fn gems_collected(&mut self, coords: TileCoords, gem: Gem, quantity: u32) {
+        let colour = match gem {
+            Gem::Emerald => quad::GREEN,
+            Gem::Ruby => quad::RED,
+            Gem::Diamond => quad::SKYBLUE,
+            Gem::Sapphire => quad::BLUE
+        };
+
+        self.collected_gems_labels.push(CollectedGemsLabel {
+            coords,
+            text: format!("+{}", quantity),
+            colour,
+            time_remaining: COLLECTED_GEMS_LABEL_TIME
+        });
+    }
+
+    /// Draw (and remove expired) collected gems labels. As labels are drawn in screen space, this method should be
+    /// called after [`Self::draw`] once the default camera has been set.
+    pub fn draw_collected_gems_labels(&mut self, delta: f32) {
+        for label in &mut self.collected_gems_labels {
+            label.time_remaining -= delta;
+        }
+        self.collected_gems_labels.retain(|label| label.time_remaining > 0.0);
+
+        for label in &self.collected_gems_labels {
+            let progress = 1.0 - (label.time_remaining / COLLECTED_GEMS_LABEL_TIME);
+            let (x, y) = self.world_to_screen(label.coords);
+
+            let mut colour = label.colour;
+            colour.a = 1.0 - progress;
+
+            quad::draw_text(&label.text, x, y - (progress * COLLECTED_GEMS_LABEL_RISE), 32.0, colour);
+        }
+    }
+
+    /// Identify the coordinates of the tile currently beneath the mouse cursor. As the camera is updated by the
+    /// [`Self::draw`] method, this method should be called after drawing.
+    pub fn cursor_tile_coords(&self) -> TileCoords {
+        let (mouse_x, mouse_y) = quad::mouse_position();
+        self.screen_to_world(mouse_x, mouse_y)
+    }
+
+    /// Identify the screen position (in pixels) of the centre of the tile at the given coordinates. Useful for drawing
+    /// UI elements over a specific tile. Like [`Self::cursor_tile_coords`], this relies on the camera having been
+    /// updated by the [`Self::draw`] method.
+    pub fn world_to_screen(&self, coords: TileCoords) -> (f32, f32) {
+        let tile_centre =
+            tile_coords_to_vec2(coords, TILE_DRAW_SIZE) + quad::vec2(TILE_DRAW_SIZE / 2.0, TILE_DRAW_SIZE / 2.0);
+
+        let screen_pos = camera_space_to_screen(
+            tile_centre,
+            quad::vec2(quad::screen_width(), quad::screen_height()),
+            self.camera.target,
+            self.camera.zoom
+        );
+
+        (screen_pos.x, screen_pos.y)
+    }
+
+    /// Identify the coordinates of the tile drawn at the given screen position (in pixels).
+    pub fn screen_to_world(&self, screen_x: f32, screen_y: f32) -> TileCoords {
+        let pos = screen_to_camera_space(
+            quad::vec2(screen_x, screen_y),
+            quad::vec2(quad::screen_width(), quad::screen_height()),
+            self.camera.target,
+            self.camera.zoom
+        );
+
+        vec2_to_tile_coords(pos, TILE_DRAW_SIZE)
+    }
+}
+
+fn zoom_within_limits(zoom_level: f32) -> f32 {
+    zoom_level.clamp(MIN_ZOOM, MAX_ZOOM)
+}
+
+/// Returns the left, right, bottom, and top boundaries (in tile coordinates) of the area of the map that is visible
+/// with the given camera target & zoom.
+fn on_screen_tile_boundaries(camera_target: quad::Vec2, camera_zoom: quad::Vec2) -> (i32, i32, i32, i32) {
+    // The camera's viewport spans from -1 to 1 in normalised device coordinates on both axes:
+    let half_extent = quad::vec2(1.0, 1.0) / camera_zoom;
+
+    (
+        ((camera_target.x - half_extent.x) / TILE_DRAW_SIZE).floor() as i32,
+        ((camera_target.x + half_extent.x) / TILE_DRAW_SIZE).ceil() as i32,
+        ((camera_target.y - half_extent.y) / TILE_DRAW_SIZE).floor() as i32,
+        ((camera_target.y + half_extent.y) / TILE_DRAW_SIZE).ceil() as i32
+    )
+}
+
+/// Identify which sides of the tile at the given coordinates (which is within a safe zone) border tiles that are not
+/// within any of the given safe zones, in the order: towards negative x, positive x, negative y, and positive y.
+fn safe_zone_borders(safe_zones: &[SafeZone], coords: TileCoords) -> [bool; 4] {
+    [(-1, 0), (1, 0), (0, -1), (0, 1)]
+        .map(|(dx, dy)| !maps::is_in_safe_zone(safe_zones, TileCoords { x: coords.x + dx, y: coords.y + dy }))
 }
 
 fn tile_coords_to_vec2(coords: TileCoords, tile_draw_size: f32) -> quad::Vec2 {
     quad::vec2(coords.x as f32 * tile_draw_size, coords.y as f32 * tile_draw_size)
 }
+
+/// Identify the coordinates of the tile drawn at the given camera space position (inverse of
+/// [`tile_coords_to_vec2`]).
+fn vec2_to_tile_coords(pos: quad::Vec2, tile_draw_size: f32) -> TileCoords {
+    TileCoords { x: (pos.x / tile_draw_size).floor() as i32, y: (pos.y / tile_draw_size).floor() as i32 }
+}
+
+/// Unproject a screen space position (in pixels with the origin at the top-left of the screen) into camera space
+/// using the given camera target & zoom. Camera rotation and offset are not considered as neither are used by the map
+/// renderer.
+fn screen_to_camera_space(
+    screen_pos: quad::Vec2, screen_size: quad::Vec2, camera_target: quad::Vec2, camera_zoom: quad::Vec2
+) -> quad::Vec2 {
+    // Normalised device coordinates (-1 to 1 on both axes with y increasing upwards):
+    let ndc = quad::vec2((screen_pos.x / screen_size.x) * 2.0 - 1.0, 1.0 - (screen_pos.y / screen_size.y) * 2.0);
+
+    (ndc / camera_zoom) + camera_target
+}
+
+/// Project a camera space position onto the screen (inverse of [`screen_to_camera_space`]).
+fn camera_space_to_screen(
+    pos: quad::Vec2, screen_size: quad::Vec2, camera_target: quad::Vec2, camera_zoom: quad::Vec2
+) -> quad::Vec2 {
+    let ndc = (pos - camera_target) * camera_zoom;
+
+    quad::vec2((ndc.x + 1.0) / 2.0 * screen_size.x, (1.0 - ndc.y) / 2.0 * screen_size.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_eq_vec2(lhs: quad::Vec2, rhs: quad::Vec2) {
+        assert!((lhs - rhs).length() < 0.0001, "{:?} != {:?}", lhs, rhs);
+    }
+
+    #[test]
+    fn unproject_screen_position() {
+        let screen_size = quad::vec2(800.0, 600.0);
+        let target = quad::vec2(0.55, -0.25);
+        let zoom = quad::vec2(1.0, 800.0 / 600.0);
+
+        // Centre of the screen is the camera target:
+        assert_eq_vec2(screen_to_camera_space(quad::vec2(400.0, 300.0), screen_size, target, zoom), target);
+
+        // Top-left corner of the screen:
+        assert_eq_vec2(
+            screen_to_camera_space(quad::vec2(0.0, 0.0), screen_size, target, zoom),
+            quad::vec2(target.x - 1.0, target.y + 0.75)
+        );
+
+        // Bottom-right corner of the screen:
+        assert_eq_vec2(
+            screen_to_camera_space(screen_size, screen_size, target, zoom),
+            quad::vec2(target.x + 1.0, target.y - 0.75)
+        );
+    }
+
+    #[test]
+    fn project_camera_space_position() {
+        let screen_size = quad::vec2(800.0, 600.0);
+        let target = quad::vec2(0.55, -0.25);
+        let zoom = quad::vec2(1.0, 800.0 / 600.0);
+
+        assert_eq_vec2(camera_space_to_screen(target, screen_size, target, zoom), quad::vec2(400.0, 300.0));
+        assert_eq_vec2(
+            camera_space_to_screen(quad::vec2(target.x - 1.0, target.y + 0.75), screen_size, target, zoom),
+            quad::vec2(0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn tile_screen_position_round_trip() {
+        let screen_size = quad::vec2(1024.0, 768.0);
+
+        for zoom_level in [MIN_ZOOM, 1.0, MAX_ZOOM] {
+            let zoom = quad::vec2(1.0, 1024.0 / 768.0) * zoom_level;
+
+            for (coords, target) in &[
+                (TileCoords { x: 0, y: 0 }, quad::vec2(0.05, 0.05)),
+                (TileCoords { x: -3, y: -7 }, quad::vec2(-0.25, -0.65)),
+                (TileCoords { x: -12, y: 4 }, quad::vec2(-1.15, 0.45)),
+                (TileCoords { x: 6, y: -2 }, quad::vec2(0.35, -0.05))
+            ] {
+                let tile_centre = tile_coords_to_vec2(*coords, TILE_DRAW_SIZE)
+                    + quad::vec2(TILE_DRAW_SIZE / 2.0, TILE_DRAW_SIZE / 2.0);
+                let screen_pos = camera_space_to_screen(tile_centre, screen_size, *target, zoom);
+
+                let round_trip = screen_to_camera_space(screen_pos, screen_size, *target, zoom);
+                assert_eq!(vec2_to_tile_coords(round_trip, TILE_DRAW_SIZE), *coords);
+            }
+        }
+    }
+
+    #[test]
+    fn zoomed_tiles_drawn_larger_on_screen() {
+        let screen_size = quad::vec2(1024.0, 768.0);
+        let target = quad::vec2(0.0, 0.0);
+
+        // Screen space distance between the origins of two horizontally adjacent tiles:
+        let tile_screen_width = |zoom_level: f32| {
+            let zoom = quad::vec2(1.0, 1024.0 / 768.0) * zoom_level;
+            let first = camera_space_to_screen(
+                tile_coords_to_vec2(TileCoords { x: 0, y: 0 }, TILE_DRAW_SIZE),
+                screen_size,
+                target,
+                zoom
+            );
+            let second = camera_space_to_screen(
+                tile_coords_to_vec2(TileCoords { x: 1, y: 0 }, TILE_DRAW_SIZE),
+                screen_size,
+                target,
+                zoom
+            );
+            second.x - first.x
+        };
+
+        assert!((tile_screen_width(1.0) - 51.2).abs() < 0.001);
+        assert!((tile_screen_width(MAX_ZOOM) - (51.2 * MAX_ZOOM)).abs() < 0.001);
+        assert!((tile_screen_width(MIN_ZOOM) - (51.2 * MIN_ZOOM)).abs() < 0.001);
+    }
+
+    #[test]
+    fn camera_space_position_to_tile_coords() {
+        let test_data = &[
+            (quad::vec2(0.0, 0.0), TileCoords { x: 0, y: 0 }),
+            (quad::vec2(0.05, 0.19), TileCoords { x: 0, y: 1 }),
+            (quad::vec2(-0.01, 0.25), TileCoords { x: -1, y: 2 }),
+            (quad::vec2(-1.05, -0.3001), TileCoords { x: -11, y: -4 })
+        ];
+
+        for (pos, coords) in test_data {
+            assert_eq!(vec2_to_tile_coords(*pos, TILE_DRAW_SIZE), *coords);
+            assert_eq!(vec2_to_tile_coords(tile_coords_to_vec2(*coords, TILE_DRAW_SIZE), TILE_DRAW_SIZE), *coords);
+        }
+    }
+
+    #[test]
+    fn more_tiles_on_screen_when_zoomed_out() {
+        let target = quad::vec2(0.55, -0.25);
+        let zoom = quad::vec2(1.0, 800.0 / 600.0);
+
+        let on_screen_tile_count = |zoom_level: f32| {
+            let (left, right, bottom, top) = on_screen_tile_boundaries(target, zoom * zoom_level);
+            (right - left) * (top - bottom)
+        };
+
+        assert!(on_screen_tile_count(MIN_ZOOM) > on_screen_tile_count(1.0));
+        assert!(on_screen_tile_count(1.0) > on_screen_tile_count(MAX_ZOOM));
+
+        // Without any zoom, the visible area spans 2 units horizontally (20 tiles) and 1.5 units vertically (15 tiles)
+        // plus partially visible tiles at the edges:
+        assert_eq!(on_screen_tile_boundaries(target, zoom), (-5, 16, -10, 5));
+    }
+
+    #[test]
+    fn safe_zone_borders_at_edges() {
+        let zones = [SafeZone { centre: TileCoords { x: 0, y: 0 }, radius: 1 }];
+
+        assert_eq!(safe_zone_borders(&zones, TileCoords { x: 0, y: 0 }), [false; 4]);
+        assert_eq!(safe_zone_borders(&zones, TileCoords { x: 1, y: 0 }), [false, true, false, false]);
+        assert_eq!(safe_zone_borders(&zones, TileCoords { x: -1, y: 1 }), [true, false, false, true]);
+
+        // Tiles bordering another safe zone are not given a border on that side:
+        let zones = [zones[0], SafeZone { centre: TileCoords { x: 3, y: 0 }, radius: 1 }];
+        assert_eq!(safe_zone_borders(&zones, TileCoords { x: 1, y: 0 }), [false; 4]);
+    }
+
+    #[test]
+    fn zoom_clamped() {
+        assert_eq!(zoom_within_limits(0.1), MIN_ZOOM);
+        assert_eq!(zoom_within_limits(1.3), 1.3);
+        assert_eq!(zoom_within_limits(10.0), MAX_ZOOM);
+    }
+}
@@ -2,7 +2,10 @@ use std::collections::HashMap;
 
 use array_macro::array;
 use lazy_static::lazy_static;
-use macroquad::prelude as quad;
+use macroquad::{
+    models::{Mesh, Vertex},
+    prelude as quad
+};
 use shared::maps::Tile;
 
 use super::animations::{self, boxed_continuous, boxed_static};
@@ -33,6 +36,27 @@ const WATER_GRASS_CORNER_TOP_LEFT: [animations::Frame; 4] =
 const WATER_GRASS_CORNER_TOP_RIGHT: [animations::Frame; 4] =
     array![index => animations::Frame { at: (4 + index as u16, 5), time: WATER_FRAME_TIME }; 4];
 
+/// The colour of the subtle overlay drawn over tiles within a safe zone.
+const SAFE_ZONE_TINT: quad::Color = quad::Color::new(0.3, 1.0, 0.5, 0.12);
+/// The colour of the border drawn along the edges of a safe zone.
+const SAFE_ZONE_BORDER_COLOUR: quad::Color = quad::Color::new(0.3, 1.0, 0.5, 0.6);
+/// The thickness of the border drawn along the edges of a safe zone as a fraction of the tile draw size.
+const SAFE_ZONE_BORDER_THICKNESS: f32 = 0.08;
+
+/// The darker of the two shades of grey between which the placeholder drawn for pending tiles shimmers.
+const PENDING_SHIMMER_DARK: f32 = 0.25;
+/// The lighter of the two shades of grey between which the placeholder drawn for pending tiles shimmers.
+const PENDING_SHIMMER_LIGHT: f32 = 0.45;
+/// How quickly (in radians per second) the pending tile shimmer cycles.
+const PENDING_SHIMMER_SPEED: f32 = 4.0;
+/// The difference in shimmer phase (in radians) between horizontally adjacent pending tiles. This results in the
+/// shimmer propagating from left to right across the grid.
+const PENDING_SHIMMER_TILE_PHASE: f32 = 0.6;
+
+/// The maximum number of tiles in each mesh drawn by a [`TileBatch`]. MacroQuad clamps the geometry of a single draw
+/// call to fewer than 5000 indices, six of which are needed for each tile.
+const MAX_TILES_PER_MESH: usize = 800;
+
 lazy_static! {
     static ref STATELESS_TILE_ANIMATIONS: HashMap<Tile, Box<dyn animations::Animation + Sync>> = {
         let mut map = HashMap::new();
@@ -57,6 +81,7 @@ lazy_static! {
         map.insert(Tile::RockEmerald, boxed_static(7, 0));
         map.insert(Tile::RockRuby, boxed_static(7, 1));
         map.insert(Tile::RockDiamond, boxed_static(7, 2));
+        map.insert(Tile::RockSapphire, boxed_static(7, 3));
         map.insert(Tile::RockSmashed, boxed_static(6, 1));
         map.insert(Tile::Shrub, boxed_static(4, 2));
         map.insert(Tile::FlowerBlue, boxed_continuous(BLUE_FLOWER_FRAMES));
@@ -74,40 +99,315 @@ lazy_static! {
         map.insert(Tile::WaterGrassCornerTopRight, boxed_continuous(&WATER_GRASS_CORNER_TOP_RIGHT));
         map.insert(Tile::WaterGrassCornerBottomLeft, boxed_static(2, 6));
         map.insert(Tile::WaterGrassCornerBottomRight, boxed_static(2, 5));
+        map.insert(Tile::Wall, boxed_static(0, 2)); // TODO: Dedicated cave wall texture.
+        map.insert(Tile::Ground, boxed_static(2, 1)); // TODO: Dedicated cave ground texture.
+        map.insert(Tile::Sand, boxed_static(2, 1)); // TODO: Dedicated sand texture.
+        map.insert(Tile::Chest, boxed_static(0, 5));
+        map.insert(Tile::ChestOpened, boxed_static(0, 6));
+        map.insert(Tile::Sign, boxed_static(0, 7));
+        map.insert(Tile::Lava, boxed_static(1, 8));
+        map.insert(Tile::Ice, boxed_static(2, 8));
+        map.insert(Tile::Portal, boxed_static(3, 8));
+        map.insert(Tile::GemDrop, boxed_static(0, 8));
 
         map
     };
 }
 
-pub fn draw_with_stateless_animation(
-    tile: Tile, draw_pos: quad::Vec2, draw_size: f32, texture: quad::Texture2D, chunk_corner: bool
-) {
-    let animation = STATELESS_TILE_ANIMATIONS.get(&tile).unwrap();
-    animation.draw(draw_pos, super::SINGLE_TILE_TEXTURE_SIZE, draw_size, texture);
+/// Collects the textured quads of the tiles drawn in a frame so that they can be drawn using a single `draw_mesh` call
+/// (or one per [`MAX_TILES_PER_MESH`] tiles) rather than a separate texture draw for each tile.
+pub struct TileBatch {
+    /// The width and height (in pixels) of the tiles texture, used to convert texture source rectangles to UVs.
+    texture_size: quad::Vec2,
+    meshes: Vec<Mesh>
+}
 
-    #[cfg(debug_assertions)]
-    {
-        let (radius_multiplier, colour) = {
-            if chunk_corner {
-                (0.06, quad::DARKPURPLE)
-            }
-            else {
-                (0.03, quad::RED)
+impl TileBatch {
+    pub fn new(texture_size: quad::Vec2) -> Self {
+        TileBatch { texture_size, meshes: Vec::new() }
+    }
+
+    /// Add the given tile (at the frame of its stateless animation at the specified time) to the batch.
+    pub fn push(&mut self, tile: Tile, draw_pos: quad::Vec2, draw_size: f32, time: f64) {
+        let source = tile_texture_source_rect(tile, time);
+
+        let mesh = match self.meshes.last_mut() {
+            Some(mesh) if mesh.vertices.len() < MAX_TILES_PER_MESH * 4 => mesh,
+            _ => {
+                self.meshes.push(Mesh {
+                    vertices: Vec::with_capacity(MAX_TILES_PER_MESH * 4),
+                    indices: Vec::with_capacity(MAX_TILES_PER_MESH * 6),
+                    texture: None
+                });
+                self.meshes.last_mut().unwrap()
             }
         };
-        quad::draw_circle(draw_pos.x, draw_pos.y, draw_size * radius_multiplier, colour);
+
+        let first_index = mesh.vertices.len() as u16;
+        mesh.vertices.extend_from_slice(&tile_quad(draw_pos, draw_size, source, self.texture_size));
+        mesh.indices.extend([0, 1, 2, 0, 2, 3].iter().map(|offset| first_index + offset));
     }
+
+    /// Draw all the tiles added to the batch using the given tiles texture.
+    pub fn draw(&mut self, texture: quad::Texture2D) {
+        for mesh in &mut self.meshes {
+            mesh.texture = Some(texture);
+            quad::draw_mesh(mesh);
+        }
+    }
+}
+
+/// The area of the tiles texture showing the given tile at the frame of its stateless animation at the specified time.
+fn tile_texture_source_rect(tile: Tile, time: f64) -> quad::Rect {
+    let animation = STATELESS_TILE_ANIMATIONS.get(&tile).unwrap();
+    crate::make_texture_source_rect(
+        super::SINGLE_TILE_TEXTURE_SIZE,
+        animation.get_relative_texture_coords(time),
+        (1, 1)
+    )
+}
+
+/// The vertices of a quad drawn at the specified position & size showing the given area of a texture of the specified
+/// size. As with other tile draws, the texture is flipped vertically as the y-axis points upwards in camera space.
+fn tile_quad(draw_pos: quad::Vec2, draw_size: f32, source: quad::Rect, texture_size: quad::Vec2) -> [Vertex; 4] {
+    let (left, right) = (source.x / texture_size.x, (source.x + source.w) / texture_size.x);
+    let (top, bottom) = (source.y / texture_size.y, (source.y + source.h) / texture_size.y);
+
+    let vertex = |x: f32, y: f32, u: f32, v: f32| Vertex {
+        position: quad::vec3(draw_pos.x + x, draw_pos.y + y, 0.0),
+        uv: quad::vec2(u, v),
+        color: quad::WHITE
+    };
+
+    [
+        vertex(0.0, draw_size, left, top),
+        vertex(draw_size, draw_size, right, top),
+        vertex(draw_size, 0.0, right, bottom),
+        vertex(0.0, 0.0, left, bottom)
+    ]
+}
+
+/// Draw a small circle at a tile's origin (larger & purple for the tile at the corner of a chunk).
+#[cfg(debug_assertions)]
+pub fn draw_debug_marker(draw_pos: quad::Vec2, draw_size: f32, chunk_corner: bool) {
+    let (radius_multiplier, colour) = {
+        if chunk_corner {
+            (0.06, quad::DARKPURPLE)
+        }
+        else {
+            (0.03, quad::RED)
+        }
+    };
+    quad::draw_circle(draw_pos.x, draw_pos.y, draw_size * radius_multiplier, colour);
+}
+
+/// Draw a shimmering grey square at the specified coordinates. This is to act as a place holder while the necessary
+/// data is being fetched from the server.
+pub fn draw_pending_tile(draw_pos: quad::Vec2, draw_size: f32, elapsed_secs: f32) {
+    let tile_column = (draw_pos.x / draw_size).round();
+    draw_placeholder(draw_pos, draw_size, pending_tile_shimmer_colour(tile_column, elapsed_secs));
 }
 
-/// Draw a grey square at the specified coordinates. This is to act as a place holder while the necessary data is being
-/// fetched from the server.
-pub fn draw_pending(draw_pos: quad::Vec2, draw_size: f32) {
+/// The colour of the pending tile placeholder in the given column of tiles after the specified amount of time.
+fn pending_tile_shimmer_colour(tile_column: f32, elapsed_secs: f32) -> quad::Color {
+    let phase = (elapsed_secs * PENDING_SHIMMER_SPEED) - (tile_column * PENDING_SHIMMER_TILE_PHASE);
+    let brightness = (phase.sin() + 1.0) / 2.0;
+    let shade = PENDING_SHIMMER_DARK + ((PENDING_SHIMMER_LIGHT - PENDING_SHIMMER_DARK) * brightness);
+
+    quad::Color::new(shade, shade, shade, 1.0)
+}
+
+/// Draw a red square at the specified coordinates. This indicates that the tile's chunk is loaded but the tile could
+/// not be found within it (i.e. a bug rather than an expected loading state).
+pub fn draw_out_of_bounds(draw_pos: quad::Vec2, draw_size: f32) {
+    draw_placeholder(draw_pos, draw_size, quad::RED);
+}
+
+/// Draw a semi-transparent overlay over the tile beneath the mouse cursor. The overlay is white for tiles that entities
+/// can walk over and tinted red for blocking tiles.
+pub fn draw_hover_highlight(draw_pos: quad::Vec2, draw_size: f32, blocking: bool) {
+    let colour = if blocking { quad::Color::new(1.0, 0.2, 0.2, 0.35) } else { quad::Color::new(1.0, 1.0, 1.0, 0.25) };
+
+    quad::draw_rectangle(draw_pos.x, draw_pos.y, draw_size, draw_size, colour);
+}
+
+/// Tint the tile at the specified coordinates green to indicate that it is within a safe zone, drawing a border along
+/// each of the given sides (in the order: facing negative x, positive x, negative y, and positive y) that are at the
+/// edge of the safe zone.
+pub fn draw_safe_zone(draw_pos: quad::Vec2, draw_size: f32, borders: [bool; 4]) {
+    quad::draw_rectangle(draw_pos.x, draw_pos.y, draw_size, draw_size, SAFE_ZONE_TINT);
+
+    let thickness = draw_size * SAFE_ZONE_BORDER_THICKNESS;
+    let far_x = draw_pos.x + draw_size - thickness;
+    let far_y = draw_pos.y + draw_size - thickness;
+
+    let border_rects = [
+        (draw_pos.x, draw_pos.y, thickness, draw_size),
+        (far_x, draw_pos.y, thickness, draw_size),
+        (draw_pos.x, draw_pos.y, draw_size, thickness),
+        (draw_pos.x, far_y, draw_size, thickness)
+    ];
+
+    for (&(x, y, w, h), &border) in border_rects.iter().zip(borders.iter()) {
+        if border {
+            quad::draw_rectangle(x, y, w, h, SAFE_ZONE_BORDER_COLOUR);
+        }
+    }
+}
+
+/// Darken the tile at the specified coordinates by drawing a black overlay of the given opacity over it (used for fog
+/// of war).
+pub fn draw_fog(draw_pos: quad::Vec2, draw_size: f32, alpha: f32) {
+    quad::draw_rectangle(draw_pos.x, draw_pos.y, draw_size, draw_size, quad::Color::new(0.0, 0.0, 0.0, alpha));
+}
+
+fn draw_placeholder(draw_pos: quad::Vec2, draw_size: f32, colour: quad::Color) {
     let offset = draw_size * 0.2;
     let reduced_size = draw_size - (offset * 2.0);
 
-    quad::draw_rectangle(draw_pos.x + offset, draw_pos.y + offset, reduced_size, reduced_size, quad::DARKGRAY);
+    quad::draw_rectangle(draw_pos.x + offset, draw_pos.y + offset, reduced_size, reduced_size, colour);
 }
 
 pub fn new_rock_smash_animation() -> animations::Once {
     animations::Once::new(&ROCK_SMASH_FRAMES)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_tile_shimmer_colour_range() {
+        let (mut darkest, mut lightest) = (f32::MAX, f32::MIN);
+
+        for column in -20..20 {
+            for step in 0..200 {
+                let colour = pending_tile_shimmer_colour(column as f32, step as f32 * 0.01);
+
+                assert!((PENDING_SHIMMER_DARK..=PENDING_SHIMMER_LIGHT).contains(&colour.r));
+                assert_eq!(colour.r, colour.g);
+                assert_eq!(colour.g, colour.b);
+                assert_eq!(colour.a, 1.0);
+
+                darkest = darkest.min(colour.r);
+                lightest = lightest.max(colour.r);
+            }
+        }
+
+        // Shimmer should cycle through (close to) the full range of shades:
+        assert!(darkest - PENDING_SHIMMER_DARK < 0.01);
+        assert!(PENDING_SHIMMER_LIGHT - lightest < 0.01);
+    }
+
+    /// The size of the tiles texture used by tests.
+    const TEXTURE_SIZE: quad::Vec2 = quad::Vec2::new(128.0, 144.0);
+
+    /// Add the tiles of a square viewport of the given width (in tiles) to a new batch.
+    fn batch_viewport(width: i32) -> TileBatch {
+        let mut batch = TileBatch::new(TEXTURE_SIZE);
+
+        for x in 0..width {
+            for y in 0..width {
+                batch.push(Tile::Grass, quad::vec2(x as f32 * 0.1, y as f32 * 0.1), 0.1, 0.0);
+            }
+        }
+
+        batch
+    }
+
+    #[test]
+    fn tile_batch_quads() {
+        let mut batch = TileBatch::new(TEXTURE_SIZE);
+        batch.push(Tile::Rock, quad::vec2(0.5, -0.2), 0.1, 0.0);
+        batch.push(Tile::Grass, quad::vec2(0.6, -0.2), 0.1, 0.0);
+
+        assert_eq!(batch.meshes.len(), 1);
+        let mesh = &batch.meshes[0];
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7]);
+
+        // The rock texture at (6, 0) is flipped vertically:
+        let corners: Vec<(f32, f32, f32, f32)> =
+            mesh.vertices[..4].iter().map(|v| (v.position.x, v.position.y, v.uv.x, v.uv.y)).collect();
+        let expected = [
+            (0.5, -0.1, 0.75, 0.0),
+            (0.6, -0.1, 0.875, 0.0),
+            (0.6, -0.2, 0.875, 16.0 / 144.0),
+            (0.5, -0.2, 0.75, 16.0 / 144.0)
+        ];
+
+        for (actual, expected) in corners.iter().zip(expected.iter()) {
+            assert!((actual.0 - expected.0).abs() < 0.0001 && (actual.1 - expected.1).abs() < 0.0001);
+            assert!((actual.2 - expected.2).abs() < 0.0001 && (actual.3 - expected.3).abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn tile_batch_mesh_count() {
+        assert_eq!(batch_viewport(20).meshes.len(), 1);
+
+        // Tiles beyond the limit of a single mesh start another:
+        let batch = batch_viewport(40);
+        assert_eq!(batch.meshes.len(), 2);
+        assert!(batch.meshes.iter().all(|mesh| mesh.indices.len() < 5000));
+        assert_eq!(batch.meshes[1].vertices.len(), (1600 - MAX_TILES_PER_MESH) * 4);
+    }
+
+    /// Compares building the geometry of a 20x20 tile viewport as a separate quad for each tile (as drawn by
+    /// `draw_texture_ex`) with building it as a [`TileBatch`]. Submitting geometry to the GPU requires a graphics
+    /// context so only the time spent building geometry & the resulting number of draw calls are compared. Run with
+    /// `--ignored --nocapture` to view the results.
+    #[test]
+    #[ignore]
+    fn tile_batch_benchmark() {
+        use std::time::Instant;
+
+        const FRAMES: u32 = 1000;
+        const VIEWPORT_WIDTH: i32 = 20;
+
+        let single_start = Instant::now();
+        let mut single_draw_calls = 0;
+        for _ in 0..FRAMES {
+            let meshes: Vec<Mesh> = (0..VIEWPORT_WIDTH * VIEWPORT_WIDTH)
+                .map(|i| quad::vec2((i % VIEWPORT_WIDTH) as f32 * 0.1, (i / VIEWPORT_WIDTH) as f32 * 0.1))
+                .map(|draw_pos| Mesh {
+                    vertices: tile_quad(draw_pos, 0.1, tile_texture_source_rect(Tile::Grass, 0.0), TEXTURE_SIZE)
+                        .to_vec(),
+                    indices: vec![0, 1, 2, 0, 2, 3],
+                    texture: None
+                })
+                .collect();
+            single_draw_calls = meshes.len();
+        }
+        let single_duration = single_start.elapsed();
+
+        let batched_start = Instant::now();
+        let mut batched_draw_calls = 0;
+        for _ in 0..FRAMES {
+            batched_draw_calls = batch_viewport(VIEWPORT_WIDTH).meshes.len();
+        }
+        let batched_duration = batched_start.elapsed();
+
+        assert_eq!(single_draw_calls, 400);
+        assert_eq!(batched_draw_calls, 1);
+
+        println!(
+            "Single quad per tile: {} draw calls, {:?} per frame; batched: {} draw call(s), {:?} per frame",
+            single_draw_calls,
+            single_duration / FRAMES,
+            batched_draw_calls,
+            batched_duration / FRAMES
+        );
+    }
+
+    #[test]
+    fn pending_tile_shimmer_propagates_left_to_right() {
+        let delay = PENDING_SHIMMER_TILE_PHASE / PENDING_SHIMMER_SPEED;
+
+        for column in -5..5 {
+            let left = pending_tile_shimmer_colour(column as f32, 1.0);
+            let right_later = pending_tile_shimmer_colour(column as f32 + 1.0, 1.0 + delay);
+
+            assert!((left.r - right_later.r).abs() < 0.0001);
+        }
+    }
+}
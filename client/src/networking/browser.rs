@@ -2,7 +2,7 @@ use std::io;
 
 use sapp_jsutils::JsObject;
 
-use super::{Error, Result};
+use super::{ConnectionStats, Error, Result};
 
 extern "C" {
     fn ws_connect(addr: JsObject);
@@ -24,18 +24,22 @@ impl super::PendingConnectionTrait<Connection> for PendingConnection {
     }
 
     fn ready(&self) -> Result<Option<Connection>> {
-        ConnectionStatus::result(None, Some(Connection))
+        ConnectionStatus::result(None, Some(Connection::default()))
     }
 }
 
 /// WebSocket connection relying on the web browser's JavaScript API.
-pub struct Connection;
+#[derive(Default)]
+pub struct Connection {
+    stats: ConnectionStats
+}
 
 impl super::ConnectionTrait for Connection {
     fn send_bytes(&mut self, bytes: Vec<u8>) -> Result<()> {
         let obj = JsObject::buffer(bytes.as_slice());
         unsafe { ws_send(obj) };
 
+        self.stats.message_sent(bytes.len());
         ConnectionStatus::result((), ())
     }
 
@@ -48,9 +52,15 @@ impl super::ConnectionTrait for Connection {
         else {
             let mut buffer = Vec::new();
             data.to_byte_buffer(&mut buffer);
+
+            self.stats.message_received(buffer.len());
             Ok(Some(buffer))
         }
     }
+
+    fn stats(&self) -> ConnectionStats {
+        self.stats
+    }
 }
 
 enum ConnectionStatus {
@@ -2,7 +2,7 @@ use std::{convert, net, sync::mpsc, thread};
 
 use tungstenite as ws2;
 
-use super::{Error, Result};
+use super::{ConnectionStats, Error, Result};
 
 pub struct PendingConnection {
     thread_receiver: mpsc::Receiver<Result<Connection>>
@@ -24,7 +24,7 @@ impl super::PendingConnectionTrait<Connection> for PendingConnection {
                     tcp_socket.set_nonblocking(true).expect("Failed to transition to non-blocking mode");
                     log::debug!("Underlying TCP/IP socket made to enter non-blocking mode");
 
-                    Ok(Connection { ws })
+                    Ok(Connection::new(ws))
                 }
 
                 Err(e) => {
@@ -50,13 +50,24 @@ impl super::PendingConnectionTrait<Connection> for PendingConnection {
 
 /// WebSocket connection relying on the `tungstenite` library's implementation of the protocol.
 pub struct Connection {
-    ws: ws2::WebSocket<ws2::client::AutoStream>
+    ws: ws2::WebSocket<ws2::client::AutoStream>,
+    stats: ConnectionStats
+}
+
+impl Connection {
+    fn new(ws: ws2::WebSocket<ws2::client::AutoStream>) -> Self {
+        Connection { ws, stats: ConnectionStats::default() }
+    }
 }
 
 impl super::ConnectionTrait for Connection {
     fn send_bytes(&mut self, bytes: Vec<u8>) -> Result<()> {
+        let size = bytes.len();
+
         let msg = ws2::Message::binary(bytes);
         self.ws.write_message(msg)?;
+
+        self.stats.message_sent(size);
         Ok(())
     }
 
@@ -64,7 +75,10 @@ impl super::ConnectionTrait for Connection {
         match self.ws.read_message() {
             Ok(msg) => match msg {
                 // Return binary message:
-                ws2::Message::Binary(data) => Ok(Some(data)),
+                ws2::Message::Binary(data) => {
+                    self.stats.message_received(data.len());
+                    Ok(Some(data))
+                }
 
                 // Indicate to the caller that the connection is now closed (tungstenite should automatically complete
                 // the closing handshake):
@@ -86,6 +100,10 @@ impl super::ConnectionTrait for Connection {
             Err(other_error) => Err(other_error.into())
         }
     }
+
+    fn stats(&self) -> ConnectionStats {
+        self.stats
+    }
 }
 
 impl convert::From<ws2::Error> for Error {
@@ -99,3 +117,36 @@ impl convert::From<ws2::HandshakeError<ws2::ClientHandshake<net::TcpStream>>> fo
         Error::Connection(Box::new(e))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shared::messages;
+
+    use super::*;
+    use crate::networking::ConnectionTrait;
+
+    #[test]
+    fn sending_increments_bytes_sent() {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut ws = ws2::accept(stream).unwrap();
+            ws.read_message().unwrap()
+        });
+
+        let (ws, _) = ws2::connect(format!("ws://{}", addr)).unwrap();
+        let mut connection = Connection::new(ws);
+
+        let msg = messages::ToServer::Hello { protocol_version: shared::PROTOCOL_VERSION, client_id_option: None };
+        connection.send(&msg).unwrap();
+
+        let expected_size = bincode::serialized_size(&msg).unwrap();
+        assert_eq!(connection.stats().bytes_sent, expected_size);
+        assert_eq!(connection.stats().messages_sent, 1);
+        assert_eq!(connection.stats().bytes_received, 0);
+
+        assert_eq!(server.join().unwrap().into_data().len() as u64, expected_size);
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::VecDeque;
+
+use shared::messages;
+
+/// The number of most recent round-trip time samples that are averaged.
+const RTT_SAMPLE_COUNT: usize = 10;
+
+/// Measures the latency of the connection with the server by periodically sending ping messages and timing how long
+/// it takes for the corresponding pong messages to be received. Times are given in seconds since the game started (as
+/// returned by `quad::get_time`).
+pub struct LatencyTracker {
+    /// The sequence number of the next ping to be sent.
+    next_sequence: u32,
+    /// The time at which the next ping should be sent.
+    next_ping_at: f64,
+    /// Round-trip times (in milliseconds) of the most recently received pongs, oldest first.
+    rtt_samples_ms: VecDeque<f64>
+}
+
+impl LatencyTracker {
+    pub fn new() -> Self {
+        LatencyTracker {
+            next_sequence: 0,
+            next_ping_at: 0.0,
+            rtt_samples_ms: VecDeque::with_capacity(RTT_SAMPLE_COUNT)
+        }
+    }
+
+    /// Returns a ping message that should be sent to the server should one be due at the given time.
+    pub fn ping_if_due(&mut self, now: f64) -> Option<messages::ToServer> {
+        if now < self.next_ping_at {
+            return None;
+        }
+
+        let sequence = self.next_sequence;
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        self.next_ping_at = now + messages::PING_INTERVAL_SECS as f64;
+
+        Some(messages::ToServer::Ping { sequence, sent_at_ms: secs_to_ms(now) })
+    }
+
+    /// Record the round-trip time of a ping with the given time sent upon its pong being received at the given time.
+    pub fn pong_received(&mut self, sent_at_ms: u64, now: f64) {
+        if self.rtt_samples_ms.len() == RTT_SAMPLE_COUNT {
+            self.rtt_samples_ms.pop_front();
+        }
+
+        self.rtt_samples_ms.push_back(secs_to_ms(now).saturating_sub(sent_at_ms) as f64);
+    }
+
+    /// The mean round-trip time (in milliseconds) of the most recently received pongs, or `None` if no pongs have yet
+    /// been received.
+    pub fn average_rtt_ms(&self) -> Option<f64> {
+        if self.rtt_samples_ms.is_empty() {
+            None
+        }
+        else {
+            Some(self.rtt_samples_ms.iter().sum::<f64>() / self.rtt_samples_ms.len() as f64)
+        }
+    }
+}
+
+fn secs_to_ms(secs: f64) -> u64 {
+    (secs * 1000.0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pings_sent_at_interval_with_increasing_sequence() {
+        let mut tracker = LatencyTracker::new();
+        let interval = messages::PING_INTERVAL_SECS as f64;
+
+        assert_eq!(tracker.ping_if_due(1.0), Some(messages::ToServer::Ping { sequence: 0, sent_at_ms: 1000 }));
+        assert_eq!(tracker.ping_if_due(1.0 + (interval / 2.0)), None);
+        assert_eq!(
+            tracker.ping_if_due(1.0 + interval),
+            Some(messages::ToServer::Ping { sequence: 1, sent_at_ms: secs_to_ms(1.0 + interval) })
+        );
+    }
+
+    #[test]
+    fn average_of_most_recent_samples() {
+        let mut tracker = LatencyTracker::new();
+        assert_eq!(tracker.average_rtt_ms(), None);
+
+        tracker.pong_received(1000, 1.125);
+        tracker.pong_received(2000, 2.25);
+        assert_eq!(tracker.average_rtt_ms(), Some(187.5));
+
+        // Older samples are discarded once there are more than 10:
+        for i in 0..RTT_SAMPLE_COUNT as u64 {
+            tracker.pong_received(i * 1000, i as f64 + 0.125);
+        }
+        assert_eq!(tracker.average_rtt_ms(), Some(125.0));
+    }
+}
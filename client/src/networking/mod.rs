@@ -5,6 +5,7 @@ pub use browser::*;
 
 #[cfg(not(target_arch = "wasm32"))]
 mod desktop;
+pub mod latency;
 
 use std::{convert, fmt};
 
@@ -52,6 +53,43 @@ pub trait ConnectionTrait {
 
     /// Attempt to receive some bytes (non-blocking).
     fn receive_bytes(&mut self) -> Result<Option<Vec<u8>>>;
+
+    /// Returns statistics regarding the amount of data sent/received over this connection.
+    fn stats(&self) -> ConnectionStats;
+}
+
+/// Statistics describing the amount of data sent/received over a connection (the latency of the connection is measured
+/// separately by [`latency::LatencyTracker`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionStats {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub messages_sent: u64,
+    pub messages_received: u64
+}
+
+impl ConnectionStats {
+    /// Update statistics to account for a message of the given size having been sent.
+    fn message_sent(&mut self, size: usize) {
+        self.bytes_sent += size as u64;
+        self.messages_sent += 1;
+    }
+
+    /// Update statistics to account for a message of the given size having been received.
+    fn message_received(&mut self, size: usize) {
+        self.bytes_received += size as u64;
+        self.messages_received += 1;
+    }
+}
+
+impl fmt::Display for ConnectionStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "sent {} messages ({} bytes), received {} messages ({} bytes)",
+            self.messages_sent, self.bytes_sent, self.messages_received, self.bytes_received
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -8,6 +8,11 @@ const LOCAL_STORAGE_KEY: &str = "clientid";
 #[cfg(not(target_arch = "wasm32"))]
 const FILE_PATH: &str = "clientid.txt";
 
+#[cfg(target_arch = "wasm32")]
+const PLAYER_NAME_LOCAL_STORAGE_KEY: &str = "playername";
+#[cfg(not(target_arch = "wasm32"))]
+const PLAYER_NAME_FILE_PATH: &str = "playername.txt";
+
 use shared::Id;
 
 pub fn store_client_id(id: Id) {
@@ -27,3 +32,22 @@ pub fn retrieve_client_id() -> Option<Id> {
     #[cfg(not(target_arch = "wasm32"))]
     Id::decode(&desktop::get(FILE_PATH).ok()?)
 }
+
+/// Remember the name most recently chosen by the player so that it may be suggested the next time they play.
+pub fn store_player_name(name: &str) {
+    #[cfg(target_arch = "wasm32")]
+    browser::set(PLAYER_NAME_LOCAL_STORAGE_KEY, name);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(e) = desktop::set(PLAYER_NAME_FILE_PATH, name) {
+        log::warn!("Failed to store player name - {}", e);
+    }
+}
+
+pub fn retrieve_player_name() -> Option<String> {
+    #[cfg(target_arch = "wasm32")]
+    return browser::get(PLAYER_NAME_LOCAL_STORAGE_KEY);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    desktop::get(PLAYER_NAME_FILE_PATH).ok()
+}
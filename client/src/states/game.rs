@@ -1,10 +1,16 @@
+use std::collections::VecDeque;
+
 use macroquad::prelude as quad;
 use shared::{
-    maps::{entities::Direction, Map},
-    messages
+    achievements::Achievement,
+    maps::{
+        entities::{Direction, Entity},
+        Map, Tile, TileCoords
+    },
+    messages, quests, Id
 };
 
-use super::State;
+use super::{pregame::ReconnectingState, State};
 use crate::{
     maps::{self, entities::MyEntity, MapRenderer},
     networking::{self, ConnectionTrait},
@@ -12,7 +18,75 @@ use crate::{
     AssetManager, TextureKey
 };
 
+/// How many chunks either side of the player entity's chunk are displayed in the debug overlay's chunk grid.
+#[cfg(debug_assertions)]
+const DEBUG_CHUNK_GRID_RADIUS: i32 = 2;
+
+/// Loaded chunks further than this many chunks from the player entity's chunk are evicted from memory. This is larger
+/// than the area around the player entity that the server keeps loaded so that only chunks the server has already
+/// told the client to unload (or soon will) are evicted.
+const LOADED_CHUNK_KEEP_RADIUS: i32 = 4;
+
+/// Duration (in seconds) of the "You died" screen shown after the player entity dies, during which the time until the
+/// player may move again is counted down.
+const DEATH_SCREEN_DURATION: f32 = 3.0;
+
+/// Duration (in seconds) of the fade out at the end of the death screen.
+const DEATH_SCREEN_FADE_DURATION: f32 = 1.0;
+
+/// The maximum number of received chat messages that are kept.
+const CHAT_LOG_CAPACITY: usize = 50;
+
+/// The number of most recent chat messages displayed on screen.
+const VISIBLE_CHAT_LINES: usize = 6;
+
+/// Key pressed to show/hide the facial expression picker.
+const EXPRESSION_PICKER_KEY: quad::KeyCode = quad::KeyCode::F;
+
+/// Key pressed to show/hide the emote picker.
+const EMOTE_PICKER_KEY: quad::KeyCode = quad::KeyCode::G;
+
+/// Key pressed to interact with (i.e. read or open) the sign or chest adjacent to the player entity.
+const INTERACT_KEY: quad::KeyCode = quad::KeyCode::E;
+
+/// Key pressed to accept the next quest that the player does not already have active.
+const ACCEPT_QUEST_KEY: quad::KeyCode = quad::KeyCode::Q;
+
+/// Key pressed to show/hide the leaderboard.
+const LEADERBOARD_KEY: quad::KeyCode = quad::KeyCode::L;
+
+/// Key pressed to invite the player entity currently being inspected (see [`GameState::inspected_entity`]) to the
+/// player's party.
+const INVITE_TO_PARTY_KEY: quad::KeyCode = quad::KeyCode::P;
+
+/// Key pressed to accept the most recently received party invite.
+const ACCEPT_PARTY_INVITE_KEY: quad::KeyCode = quad::KeyCode::Y;
+
+/// Key pressed to leave the party that the player is in.
+const LEAVE_PARTY_KEY: quad::KeyCode = quad::KeyCode::X;
+
+/// Duration (in seconds) for which a server announcement is displayed, including the time taken for it to fade out.
+const ANNOUNCEMENT_DURATION: f32 = 11.0;
+
+/// Duration (in seconds) of the fade out at the end of a server announcement's display.
+const ANNOUNCEMENT_FADE_DURATION: f32 = 1.0;
+
+/// Duration (in seconds) for which each achievement unlock notification is displayed, including the time taken for it
+/// to slide in & out.
+const ACHIEVEMENT_TOAST_DURATION: f32 = 3.0;
+
+/// Duration (in seconds) of the slide in from the top of the screen at the start of an achievement unlock notification
+/// (and of the slide out at the end).
+const ACHIEVEMENT_TOAST_SLIDE_DURATION: f32 = 0.3;
+
+/// Sender name displayed alongside messages in the chat log that originate from the server rather than other players.
+const SERVER_SENDER_NAME: &str = "Server";
+
 pub struct GameState {
+    /// Address of the server, used should the connection be lost and need to be re-established.
+    connection_str: &'static str,
+    /// Name of the world joined, which is joined again should the connection be lost and re-established.
+    world: String,
     /// Connection with the remote server.
     connection: networking::Connection,
     /// This client's player character entity.
@@ -21,24 +95,78 @@ pub struct GameState {
     map: maps::ClientMap,
     /// The rendering system used to draw the game map to the screen.
     map_renderer: MapRenderer,
+    /// Whether the server has enabled fog of war (in which case tiles within sight of the player entity are marked as
+    /// seen).
+    fog_of_war: bool,
     /// User interface.
-    ui: Ui
+    ui: Ui,
+    /// The total number of gems dropped upon the player entity's most recent death paired with the time remaining (in
+    /// seconds) of the death screen, or `None` should the death screen not be displayed. Player movement is ignored
+    /// while the death screen is displayed.
+    death_screen: Option<(u32, f32)>,
+    /// The most recently received information about the entity that the player clicked on to inspect (if any).
+    inspected_entity: Option<(Id, Entity)>,
+    /// Sender names & text of the most recently received chat messages (oldest first).
+    chat_log: VecDeque<(String, String)>,
+    /// The chat message text typed so far, or `None` if the player is not currently typing a chat message.
+    chat_input: Option<String>,
+    /// Sends pings to the server in order to measure the latency of the connection.
+    latency: networking::latency::LatencyTracker,
+    /// Text of the server announcements currently being displayed along with the time remaining (in seconds) of each
+    /// announcement's display (oldest first).
+    announcements: Vec<(String, f32)>,
+    /// Text of the sign most recently read by the player, or `None` should no sign text be displayed.
+    sign_text: Option<String>,
+    /// The player's active quests as most recently updated by the server (in the order they were accepted).
+    active_quests: Vec<quests::Quest>,
+    /// Newly unlocked achievements to be notified of one at a time, each paired with the time remaining (in seconds)
+    /// of its notification's display (oldest first).
+    achievement_toasts: VecDeque<(Achievement, f32)>,
+    /// The players with the most gems as most recently provided by the server, or `None` should the leaderboard be
+    /// hidden.
+    leaderboard: Option<Vec<messages::LeaderboardEntry>>,
+    /// The entity ID of the player who most recently invited this client's player to their party (if any).
+    party_invite: Option<Id>,
+    /// The entity IDs of the members of the party that the player is in (empty should they not be in a party).
+    party_members: Vec<Id>
 }
 
 impl GameState {
-    pub fn new(connection: networking::Connection, my_entity: MyEntity) -> Self {
+    pub fn new(
+        connection_str: &'static str, world: String, connection: networking::Connection, my_entity: MyEntity,
+        fog_of_war: bool
+    ) -> Self {
         let my_entity_pos = my_entity.get_pos();
         GameState {
+            connection_str,
+            world,
             connection,
             my_entity,
             map: maps::ClientMap::new(),
-            map_renderer: MapRenderer::new(my_entity_pos),
-            ui: Ui::new(0.12)
+            map_renderer: MapRenderer::new(my_entity_pos, fog_of_war),
+            fog_of_war,
+            ui: Ui::new(0.12),
+            death_screen: None,
+            inspected_entity: None,
+            chat_log: VecDeque::with_capacity(CHAT_LOG_CAPACITY),
+            chat_input: None,
+            latency: networking::latency::LatencyTracker::new(),
+            announcements: Vec::new(),
+            sign_text: None,
+            active_quests: Vec::new(),
+            achievement_toasts: VecDeque::new(),
+            leaderboard: None,
+            party_invite: None,
+            party_members: Vec::new()
         }
     }
 
     fn handle_message_from_server(&mut self, msg: messages::FromServer) {
         match msg {
+            messages::FromServer::HelloAck { .. } => {
+                log::warn!("Unexpectedly received 'hello acknowledgement' message from server");
+            }
+
             messages::FromServer::Welcome { .. } => {
                 log::warn!("Unexpectedly received 'welcome' message from server");
                 unimplemented!()
@@ -88,13 +216,212 @@ impl GameState {
             }
 
             messages::FromServer::BombsDetonated { placed_by_entity_id, in_and_around_chunk_coords } => {
-                let positions =
-                    self.map.take_bombs_placed_by_in_and_around_chunk(placed_by_entity_id, in_and_around_chunk_coords);
+                let positions = self.map.take_detonated_bombs(placed_by_entity_id, in_and_around_chunk_coords);
+                self.map.smash_tiles_around_bombs(&positions);
                 self.map_renderer.bombs_detonated(positions);
             }
 
-            messages::FromServer::YouCollectedGems { gem_type, quantity_increase } => {
+            messages::FromServer::YouCollectedGems { gem_type, quantity_increase, source_tile } => {
                 self.my_entity.obtained_gems(gem_type, quantity_increase);
+                self.map_renderer.gems_collected(source_tile, gem_type, quantity_increase);
+            }
+
+            messages::FromServer::EntityInfo(id, entity) => {
+                self.inspected_entity = Some((id, entity));
+            }
+
+            messages::FromServer::YouDied { respawn_at, gems_dropped } => {
+                self.my_entity.respawn(respawn_at, &gems_dropped, &mut self.map_renderer);
+                self.death_screen = Some((gems_dropped.total_count(), DEATH_SCREEN_DURATION));
+            }
+
+            messages::FromServer::PurchaseFailed(err) => {
+                log::warn!("Server rejected purchase as {}", err);
+            }
+
+            messages::FromServer::Pong { sent_at_ms, .. } => {
+                self.latency.pong_received(sent_at_ms, quad::get_time());
+            }
+
+            messages::FromServer::ActionRejected { reason } => {
+                log::warn!("Server rejected action: {}", reason);
+
+                match reason {
+                    messages::RejectionReason::BombLimitReached { limit, position } => {
+                        self.my_entity.bomb_placement_rejected(position, Some(limit), &mut self.map);
+                    }
+                    messages::RejectionReason::InSafeZone { position } => {
+                        self.my_entity.bomb_placement_rejected(position, None, &mut self.map);
+                    }
+                    messages::RejectionReason::Cooldown { action, remaining_ms } => {
+                        self.my_entity.action_rejected_by_cooldown(action, remaining_ms, &mut self.map);
+                    }
+                    messages::RejectionReason::InventoryFull { .. }
+                    | messages::RejectionReason::InventoryTooHeavy { .. }
+                    | messages::RejectionReason::AlreadyInParty
+                    | messages::RejectionReason::NotPartyLeader
+                    | messages::RejectionReason::NoPartyInvite
+                    | messages::RejectionReason::CannotAffordGlobalChat(_) => {}
+                }
+
+                push_chat_message(&mut self.chat_log, SERVER_SENDER_NAME.to_string(), rejection_text(reason));
+            }
+
+            messages::FromServer::ChatMessage { sender_name, text, channel, .. } => {
+                push_chat_message(&mut self.chat_log, chat_sender_label(sender_name, channel), text);
+            }
+
+            messages::FromServer::EntityChangedExpression { entity_id, expression } => {
+                self.map.set_remote_entity_expression(entity_id, expression);
+            }
+
+            messages::FromServer::ServerAnnouncement { text } => {
+                log::info!("Server announcement: {}", text);
+
+                self.announcements.push((text, ANNOUNCEMENT_DURATION));
+            }
+
+            messages::FromServer::ShowSignText { text } => {
+                self.sign_text = Some(text);
+            }
+
+            messages::FromServer::ProvideMonster(id, monster) => {
+                self.map.add_monster(id, monster, &mut self.map_renderer);
+            }
+
+            messages::FromServer::MoveMonster(id, pos, direction) => {
+                self.map.move_monster(id, pos, direction, &mut self.map_renderer);
+            }
+
+            messages::FromServer::ShouldUnloadMonster(id) => {
+                self.map.remove_monster(id, &mut self.map_renderer);
+            }
+
+            messages::FromServer::EntityHealthChanged { entity_id, health } => {
+                if entity_id == self.my_entity.get_id() {
+                    self.my_entity.health_changed(health);
+                }
+                else {
+                    self.map.set_remote_entity_health(entity_id, health);
+                }
+            }
+
+            messages::FromServer::QuestUpdate(quest) => {
+                if quest.is_complete() {
+                    self.my_entity.obtained_quest_reward(&quest.reward);
+
+                    let text = format!("You completed the quest '{}'!", quest.title);
+                    push_chat_message(&mut self.chat_log, SERVER_SENDER_NAME.to_string(), text);
+                }
+
+                update_active_quests(&mut self.active_quests, quest);
+            }
+
+            messages::FromServer::AchievementUnlocked(achievement) => {
+                log::info!("Unlocked achievement: {}", achievement);
+
+                self.achievement_toasts.push_back((achievement, ACHIEVEMENT_TOAST_DURATION));
+            }
+
+            messages::FromServer::YourStats(stats) => self.ui.set_stats(stats),
+
+            messages::FromServer::InvitedToParty { inviter_entity_id } => {
+                let text = format!(
+                    "Player {} invited you to their party - press {:?} to accept.",
+                    inviter_entity_id.encode(),
+                    ACCEPT_PARTY_INVITE_KEY
+                );
+                push_chat_message(&mut self.chat_log, SERVER_SENDER_NAME.to_string(), text);
+
+                self.party_invite = Some(inviter_entity_id);
+            }
+
+            messages::FromServer::PartyUpdated { leader_entity_id, member_entity_ids } => {
+                let text = party_update_text(leader_entity_id, &member_entity_ids, self.my_entity.get_id());
+                push_chat_message(&mut self.chat_log, SERVER_SENDER_NAME.to_string(), text);
+
+                self.party_invite = None;
+                self.party_members = member_entity_ids;
+            }
+
+            messages::FromServer::PartyLeft => {
+                let text = "You are no longer in a party.".to_string();
+                push_chat_message(&mut self.chat_log, SERVER_SENDER_NAME.to_string(), text);
+
+                self.party_members.clear();
+            }
+
+            messages::FromServer::Leaderboard(entries) => {
+                // Ignore leaderboards that arrive after the player has hidden the leaderboard:
+                if let Some(leaderboard) = &mut self.leaderboard {
+                    *leaderboard = entries;
+                }
+            }
+
+            messages::FromServer::YouSpentGems { gem_type, quantity_decrease } => {
+                self.my_entity.spent_gems(gem_type, quantity_decrease);
+            }
+
+            messages::FromServer::NameRejected { .. } => {
+                log::warn!("Unexpectedly received 'name rejected' message from server");
+            }
+
+            messages::FromServer::AvailableWorlds(_) => {
+                log::warn!("Unexpectedly received 'available worlds' message from server");
+            }
+
+            messages::FromServer::SafeZones(safe_zones) => {
+                log::debug!("Received {} safe zones from server", safe_zones.len());
+                self.map.set_safe_zones(safe_zones);
+            }
+
+            messages::FromServer::ReceivedDamage { amount, source } => {
+                log::debug!("Player entity received {} damage from {}", amount, source);
+            }
+
+            messages::FromServer::EntityEmote { entity_id, emote } => {
+                if entity_id == self.my_entity.get_id() {
+                    self.map_renderer.my_entity_emoted(emote);
+                }
+                else {
+                    self.map_renderer.remote_entity_emoted(entity_id, emote);
+                }
+            }
+        }
+    }
+
+    /// Press Enter to begin typing a chat message and again to send it. Escape cancels the message being typed.
+    fn handle_chat_input(&mut self) {
+        match &mut self.chat_input {
+            Some(typed) => {
+                while let Some(c) = quad::get_char_pressed() {
+                    if !c.is_control() && typed.chars().count() < messages::MAX_CHAT_MESSAGE_LENGTH {
+                        typed.push(c);
+                    }
+                }
+
+                if quad::is_key_pressed(quad::KeyCode::Backspace) {
+                    typed.pop();
+                }
+
+                if quad::is_key_pressed(quad::KeyCode::Enter) {
+                    let text = self.chat_input.take().unwrap();
+
+                    if messages::is_valid_chat_message(&text) {
+                        let channel = self.ui.chat_channel();
+                        let msg = messages::ToServer::ChatMessage { text, channel };
+                        self.connection.send(&msg).unwrap(); // TODO: Don't unwrap.
+                    }
+                }
+                else if quad::is_key_pressed(quad::KeyCode::Escape) {
+                    self.chat_input = None;
+                }
+            }
+
+            None => {
+                if quad::is_key_pressed(quad::KeyCode::Enter) {
+                    self.chat_input = Some(String::new());
+                }
             }
         }
     }
@@ -106,27 +433,182 @@ impl State for GameState {
     }
 
     fn update_and_draw(&mut self, assets: &AssetManager, delta: f32) -> Option<Box<dyn State>> {
+        // Regularly ping the server to measure latency (which also lets the server know that the connection is still
+        // alive even if the player is idle):
+        if let Some(ping) = self.latency.ping_if_due(quad::get_time()) {
+            self.connection.send(&ping).unwrap(); // TODO: Don't unwrap.
+        }
+
+        self.map.evict_distant_chunks(self.my_entity.get_pos(), LOADED_CHUNK_KEEP_RADIUS);
+
+        // The server marks tiles within sight of the player entity as seen in its own copy of the map and so the same
+        // is done locally rather than having the server send the changes:
+        if self.fog_of_war {
+            self.map.mark_tiles_seen_around(self.my_entity.get_pos());
+        }
+
+        self.map_renderer.update_zoom();
         self.map_renderer.draw(&self.map, self.my_entity.get_contained_entity(), assets, delta);
 
         self.ui
             .update_and_draw(&mut self.my_entity, &mut self.map, &mut self.map_renderer, &mut self.connection, assets)
             .unwrap(); // TODO: Don't unwrap.
 
-        #[cfg(debug_assertions)]
-        ui::draw_debug_text(
-            28.0,
-            quad::DARKPURPLE,
-            assets,
-            self.my_entity.get_contained_entity(),
-            self.map.get_loaded_chunk_coords()
+        self.map_renderer.draw_collected_gems_labels(delta);
+
+        if let Some((id, entity)) = &self.inspected_entity {
+            ui::draw_entity_inspect_panel(*id, entity);
+        }
+
+        ui::draw_chat_overlay(
+            -0.47,
+            0.3,
+            0.025,
+            &self.chat_log,
+            VISIBLE_CHAT_LINES,
+            self.chat_input.as_deref().map(|typed| (typed, self.ui.chat_channel()))
+        );
+
+        let player_chunk = self.my_entity.get_pos().as_chunk_coords();
+        ui::draw_minimap(
+            0.42,
+            -0.36,
+            self.map.loaded_chunk_coords(),
+            self.map.requested_chunk_coords(player_chunk),
+            player_chunk
         );
 
+        update_announcements(&mut self.announcements, delta);
+        let visible_announcements =
+            self.announcements.iter().map(|(text, remaining)| (text.as_str(), announcement_alpha(*remaining)));
+        ui::draw_announcement_overlay(-0.35, 0.035, visible_announcements);
+
+        ui::draw_quest_tracker(0.48, -0.22, 0.022, &self.active_quests, &format!("{:?}", ACCEPT_QUEST_KEY));
+
+        update_achievement_toasts(&mut self.achievement_toasts, delta);
+        if let Some((achievement, remaining)) = self.achievement_toasts.front() {
+            ui::draw_achievement_toast(0.03, &achievement.to_string(), achievement_toast_visibility(*remaining));
+        }
+
+        if let Some(entries) = &self.leaderboard {
+            ui::draw_leaderboard_menu(-0.2, 0.025, entries, self.my_entity.get_id());
+        }
+
+        if let Some(text) = &self.sign_text {
+            ui::draw_sign_text(0.03, text);
+        }
+
+        if let Some((gems_dropped, remaining)) = self.death_screen {
+            ui::draw_death_screen(0.05, gems_dropped, remaining.ceil() as u32, death_screen_alpha(remaining));
+        }
+        update_death_screen(&mut self.death_screen, delta);
+
+        #[cfg(debug_assertions)]
+        {
+            let cursor_tile_coords = self.map_renderer.cursor_tile_coords();
+
+            // Label the tile beneath the cursor with its coordinates:
+            let (label_x, label_y) = self.map_renderer.world_to_screen(cursor_tile_coords);
+            quad::draw_text(&cursor_tile_coords.to_string(), label_x, label_y, 20.0, quad::WHITE);
+
+            ui::draw_debug_text(
+                28.0,
+                quad::DARKPURPLE,
+                assets,
+                self.my_entity.get_contained_entity(),
+                &self.map.debug_chunk_grid(self.my_entity.get_pos().as_chunk_coords(), DEBUG_CHUNK_GRID_RADIUS),
+                (cursor_tile_coords, self.map.query_tile_at(cursor_tile_coords)),
+                (self.connection.stats(), self.latency.average_rtt_ms())
+            );
+        }
+
         // Player entity updates/input handling:
 
         self.my_entity.update(delta);
 
+        // Left-click on another entity to request information about it or on an adjacent sign or unopened chest to
+        // interact with it, right-click to close the inspect panel. Clicking while sign text is displayed closes it:
+        if quad::is_mouse_button_pressed(quad::MouseButton::Left) && self.sign_text.take().is_none() {
+            let clicked_tile_coords = self.map_renderer.cursor_tile_coords();
+
+            if let Some(id) = self.map.entities_at_tile(clicked_tile_coords).first() {
+                self.connection.send(&messages::ToServer::RequestEntityInfo(*id)).unwrap(); // TODO: Don't unwrap.
+            }
+            else if is_interactable(&self.map, clicked_tile_coords)
+                && self.my_entity.get_pos().manhattan_distance(clicked_tile_coords) == 1
+            {
+                let msg = messages::ToServer::Interact { tile: clicked_tile_coords };
+                self.connection.send(&msg).unwrap(); // TODO: Don't unwrap.
+            }
+        }
+        else if quad::is_mouse_button_pressed(quad::MouseButton::Right) {
+            self.inspected_entity = None;
+        }
+
+        let was_typing = self.chat_input.is_some();
+        self.handle_chat_input();
+
+        if !was_typing && self.chat_input.is_none() && quad::is_key_pressed(EXPRESSION_PICKER_KEY) {
+            self.ui.toggle_expression_picker();
+        }
+
+        if !was_typing && self.chat_input.is_none() && quad::is_key_pressed(EMOTE_PICKER_KEY) {
+            self.ui.toggle_emote_picker();
+        }
+
+        // Press the interact key to read/open an adjacent sign or chest, or to close the sign text being displayed:
+        if !was_typing && self.chat_input.is_none() {
+            if self.sign_text.is_some() {
+                if quad::is_key_pressed(INTERACT_KEY) || quad::is_key_pressed(quad::KeyCode::Escape) {
+                    self.sign_text = None;
+                }
+            }
+            else if quad::is_key_pressed(INTERACT_KEY) {
+                if let Some(tile) = adjacent_interactable_tile(&self.map, self.my_entity.get_contained_entity()) {
+                    self.connection.send(&messages::ToServer::Interact { tile }).unwrap(); // TODO: Don't unwrap.
+                }
+            }
+
+            if quad::is_key_pressed(ACCEPT_QUEST_KEY) {
+                if let Some(quest_id) = next_quest_to_accept(&self.active_quests) {
+                    self.connection.send(&messages::ToServer::AcceptQuest(quest_id)).unwrap(); // TODO: Don't unwrap.
+                }
+            }
+
+            if quad::is_key_pressed(INVITE_TO_PARTY_KEY) {
+                if let Some((entity_id, _)) =
+                    self.inspected_entity.as_ref().filter(|(id, _)| *id != self.my_entity.get_id())
+                {
+                    let msg = messages::ToServer::InviteToParty(*entity_id);
+                    self.connection.send(&msg).unwrap(); // TODO: Don't unwrap.
+                }
+            }
+
+            if quad::is_key_pressed(ACCEPT_PARTY_INVITE_KEY) {
+                if let Some(inviter_id) = self.party_invite.take() {
+                    let msg = messages::ToServer::AcceptPartyInvite(inviter_id);
+                    self.connection.send(&msg).unwrap(); // TODO: Don't unwrap.
+                }
+            }
+
+            if quad::is_key_pressed(LEAVE_PARTY_KEY) && !self.party_members.is_empty() {
+                self.connection.send(&messages::ToServer::LeaveParty).unwrap(); // TODO: Don't unwrap.
+            }
+
+            // Press the leaderboard key to show the leaderboard (which is empty until the server responds with the
+            // current rankings) or to hide it:
+            if quad::is_key_pressed(LEADERBOARD_KEY) && self.leaderboard.take().is_none() {
+                self.leaderboard = Some(Vec::new());
+                self.connection.send(&messages::ToServer::RequestLeaderboard).unwrap(); // TODO: Don't unwrap.
+            }
+        }
+
         let direction_option = {
-            if quad::is_key_down(quad::KeyCode::W) {
+            if was_typing || self.chat_input.is_some() {
+                // Movement keys are used for typing chat messages:
+                None
+            }
+            else if quad::is_key_down(quad::KeyCode::W) {
                 Some(Direction::Up)
             }
             else if quad::is_key_down(quad::KeyCode::A) {
@@ -143,7 +625,8 @@ impl State for GameState {
             }
         };
 
-        if let Some(direction) = direction_option {
+        // Movement is not permitted while the death screen is displayed:
+        if let Some(direction) = direction_option.filter(|_| self.death_screen.is_none()) {
             // TODO: Don't just unwrap.
             self.my_entity
                 .move_towards_checked(direction, &mut self.map, &mut self.connection, &mut self.map_renderer)
@@ -155,16 +638,18 @@ impl State for GameState {
         match self.connection.receive::<messages::FromServer>() {
             Ok(msg_option) => {
                 if let Some(msg) = msg_option {
-                    log::info!("Received message from server: {}", msg);
+                    log::debug!("Received message from server: {}", msg);
 
                     self.handle_message_from_server(msg);
                 }
             }
 
             Err(e) => {
-                match e {
+                match &e {
                     networking::Error::Bincode(bincode_error) => {
                         log::warn!("Failed to decode message from server due to error: {}", bincode_error);
+
+                        return None;
                     }
                     networking::Error::Connection(connection_error) => {
                         log::warn!("Failed to receive from server due to connection error: {}", connection_error);
@@ -174,7 +659,13 @@ impl State for GameState {
                         log::error!("Connection closed by the server");
                     }
                 }
-                panic!() // TODO
+
+                return Some(Box::new(ReconnectingState::new(
+                    self.connection_str,
+                    self.world.clone(),
+                    self.my_entity.clone(),
+                    e
+                )));
             }
         }
 
@@ -185,3 +676,266 @@ impl State for GameState {
         "Game"
     }
 }
+
+/// Whether the tile at the given position is one that the player can interact with (i.e. a sign or unopened chest).
+fn is_interactable(map: &maps::ClientMap, coords: TileCoords) -> bool {
+    matches!(map.loaded_tile_at(coords), Some(Tile::Sign | Tile::Chest))
+}
+
+/// The position of a tile adjacent to the given entity that can be interacted with, preferring the tile the entity is
+/// facing.
+fn adjacent_interactable_tile(map: &maps::ClientMap, entity: &Entity) -> Option<TileCoords> {
+    [entity.direction, Direction::Up, Direction::Down, Direction::Left, Direction::Right]
+        .iter()
+        .map(|direction| direction.apply(entity.pos))
+        .find(|coords| is_interactable(map, *coords))
+}
+
+/// Text displayed in the chat log explaining to the player why an action they attempted was rejected by the server.
+fn rejection_text(reason: messages::RejectionReason) -> String {
+    match reason {
+        messages::RejectionReason::BombLimitReached { limit, .. } => {
+            format!("You cannot have more than {} bombs placed at once.", limit)
+        }
+        messages::RejectionReason::InventoryFull { max_slots } => {
+            format!("Your inventory does not have room for that (it holds at most {} items).", max_slots)
+        }
+        messages::RejectionReason::InventoryTooHeavy { max_weight } => {
+            format!("That would be too heavy to carry (you can carry a weight of at most {}).", max_weight)
+        }
+        messages::RejectionReason::AlreadyInParty => "That player is already in a party.".to_string(),
+        messages::RejectionReason::NotPartyLeader => "Only the leader of your party can invite players.".to_string(),
+        messages::RejectionReason::NoPartyInvite => "That party invite is no longer valid.".to_string(),
+        messages::RejectionReason::CannotAffordGlobalChat(err) => {
+            format!("Sending a global chat message costs {} {:?} gems.", err.requested, err.gem)
+        }
+        messages::RejectionReason::InSafeZone { .. } => "Bombs cannot be placed within a safe zone.".to_string(),
+        messages::RejectionReason::Cooldown { action, remaining_ms } => {
+            format!(
+                "You must wait another {:.1} seconds before you can {} again.",
+                remaining_ms as f32 / 1000.0,
+                action
+            )
+        }
+    }
+}
+
+/// The sender name displayed alongside a received chat message, marking messages sent on the global channel as such.
+fn chat_sender_label(sender_name: String, channel: messages::ChatChannel) -> String {
+    match channel {
+        messages::ChatChannel::Local => sender_name,
+        messages::ChatChannel::Global => format!("[{}] {}", channel, sender_name)
+    }
+}
+
+/// The text added to the chat log upon being informed of a change to the members of the player's party.
+fn party_update_text(leader_id: Id, member_ids: &[Id], my_id: Id) -> String {
+    let leader = if leader_id == my_id { "you".to_string() } else { format!("Player {}", leader_id.encode()) };
+    format!("Your party led by {} now has {} members.", leader, member_ids.len())
+}
+
+/// The ID of the first quest (see [`quests::all_quests`]) that is not among the given active quests.
+fn next_quest_to_accept(active_quests: &[quests::Quest]) -> Option<u32> {
+    quests::all_quests().into_iter().map(|quest| quest.id).find(|id| active_quests.iter().all(|quest| quest.id != *id))
+}
+
+/// Replace the active quest with the same ID as the given updated quest (or add it should it have just been accepted).
+/// Completed quests are removed from the active quests.
+fn update_active_quests(active_quests: &mut Vec<quests::Quest>, updated: quests::Quest) {
+    match active_quests.iter_mut().find(|quest| quest.id == updated.id) {
+        Some(quest) => *quest = updated,
+        None => active_quests.push(updated)
+    }
+
+    active_quests.retain(|quest| !quest.is_complete());
+}
+
+/// Add a received chat message to the given log, removing the oldest message should the log exceed
+/// [`CHAT_LOG_CAPACITY`].
+fn push_chat_message(chat_log: &mut VecDeque<(String, String)>, sender_name: String, text: String) {
+    chat_log.push_back((sender_name, text));
+
+    while chat_log.len() > CHAT_LOG_CAPACITY {
+        chat_log.pop_front();
+    }
+}
+
+/// Reduce the time remaining of each displayed server announcement by the given time delta, removing those that have
+/// been displayed for [`ANNOUNCEMENT_DURATION`].
+fn update_announcements(announcements: &mut Vec<(String, f32)>, delta: f32) {
+    for (_, remaining) in announcements.iter_mut() {
+        *remaining -= delta;
+    }
+
+    announcements.retain(|(_, remaining)| *remaining > 0.0);
+}
+
+/// The opacity with which an announcement with the given display time remaining is to be drawn. Announcements are
+/// fully opaque until the final [`ANNOUNCEMENT_FADE_DURATION`] seconds during which they fade out.
+fn announcement_alpha(remaining: f32) -> f32 {
+    (remaining / ANNOUNCEMENT_FADE_DURATION).clamp(0.0, 1.0)
+}
+
+/// Reduce the time remaining of the death screen (should it be displayed) by the given time delta, hiding it once it
+/// has been displayed for [`DEATH_SCREEN_DURATION`].
+fn update_death_screen(death_screen: &mut Option<(u32, f32)>, delta: f32) {
+    if let Some((_, remaining)) = death_screen {
+        *remaining -= delta;
+
+        if *remaining <= 0.0 {
+            *death_screen = None;
+        }
+    }
+}
+
+/// The opacity with which the death screen is to be drawn given its display time remaining. The death screen is fully
+/// opaque until the final [`DEATH_SCREEN_FADE_DURATION`] seconds during which it fades out to reveal the map around
+/// the respawn position.
+fn death_screen_alpha(remaining: f32) -> f32 {
+    (remaining / DEATH_SCREEN_FADE_DURATION).clamp(0.0, 1.0)
+}
+
+/// Reduce the time remaining of the achievement unlock notification currently being displayed (i.e. the oldest) by the
+/// given time delta, removing it once it has been displayed for [`ACHIEVEMENT_TOAST_DURATION`].
+fn update_achievement_toasts(toasts: &mut VecDeque<(Achievement, f32)>, delta: f32) {
+    if let Some((_, remaining)) = toasts.front_mut() {
+        *remaining -= delta;
+
+        if *remaining <= 0.0 {
+            toasts.pop_front();
+        }
+    }
+}
+
+/// How far an achievement unlock notification with the given display time remaining has slid into view, from 0 (just
+/// above the top of the screen) to 1 (fully visible).
+fn achievement_toast_visibility(remaining: f32) -> f32 {
+    let elapsed = ACHIEVEMENT_TOAST_DURATION - remaining;
+    (elapsed.min(remaining) / ACHIEVEMENT_TOAST_SLIDE_DURATION).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::maps::{entities::EntityBuilder, Chunk, ChunkCoords, OffsetCoords};
+
+    use super::*;
+
+    #[test]
+    fn facing_interactable_tile_preferred() {
+        let mut map = maps::ClientMap::new();
+        let mut chunk = Chunk::default();
+        chunk.place_sign(OffsetCoords { x: 5, y: 6 }, "North".to_string());
+        chunk.set_tile_at_offset(OffsetCoords { x: 4, y: 5 }, Tile::Chest);
+        chunk.set_tile_at_offset(OffsetCoords { x: 6, y: 5 }, Tile::ChestOpened);
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+
+        let entity = EntityBuilder::new().pos(TileCoords { x: 5, y: 5 }).direction(Direction::Left).build();
+        assert_eq!(adjacent_interactable_tile(&map, &entity), Some(TileCoords { x: 4, y: 5 }));
+
+        // Opened chests cannot be interacted with:
+        let entity = EntityBuilder::new().pos(TileCoords { x: 5, y: 5 }).direction(Direction::Right).build();
+        assert_eq!(adjacent_interactable_tile(&map, &entity), Some(TileCoords { x: 5, y: 6 }));
+
+        let entity = EntityBuilder::new().pos(TileCoords { x: 10, y: 10 }).build();
+        assert_eq!(adjacent_interactable_tile(&map, &entity), None);
+    }
+
+    #[test]
+    fn chat_log_bounded() {
+        let mut chat_log = VecDeque::new();
+
+        for i in 0..CHAT_LOG_CAPACITY + 5 {
+            push_chat_message(&mut chat_log, "Player".to_string(), i.to_string());
+        }
+
+        assert_eq!(chat_log.len(), CHAT_LOG_CAPACITY);
+        assert_eq!(chat_log.front().unwrap().1, "5");
+        assert_eq!(chat_log.back().unwrap().1, (CHAT_LOG_CAPACITY + 4).to_string());
+    }
+
+    #[test]
+    fn global_chat_messages_labelled() {
+        assert_eq!(chat_sender_label("Player".to_string(), messages::ChatChannel::Local), "Player");
+        assert_eq!(chat_sender_label("Player".to_string(), messages::ChatChannel::Global), "[Global] Player");
+    }
+
+    #[test]
+    fn quest_updates_applied() {
+        let mut active_quests = Vec::new();
+        assert_eq!(next_quest_to_accept(&active_quests), Some(1));
+
+        update_active_quests(&mut active_quests, quests::quest_by_id(1).unwrap());
+        update_active_quests(&mut active_quests, quests::quest_by_id(2).unwrap());
+        assert_eq!(next_quest_to_accept(&active_quests), Some(3));
+
+        let mut progressed = quests::quest_by_id(1).unwrap();
+        progressed.record(quests::QuestEvent::RockMined);
+        update_active_quests(&mut active_quests, progressed.clone());
+        assert_eq!(active_quests, vec![progressed, quests::quest_by_id(2).unwrap()]);
+
+        let mut completed = quests::quest_by_id(2).unwrap();
+        completed.record(quests::QuestEvent::GemsCollected { gem: shared::gems::Gem::Ruby, quantity: 100 });
+        update_active_quests(&mut active_quests, completed);
+        assert_eq!(active_quests.len(), 1);
+        assert_eq!(next_quest_to_accept(&active_quests), Some(2));
+    }
+
+    #[test]
+    fn party_update_names_leader() {
+        let (my_id, other_id) = (Id::new(1), Id::new(2));
+
+        assert_eq!(party_update_text(my_id, &[my_id, other_id], my_id), "Your party led by you now has 2 members.");
+        assert!(party_update_text(other_id, &[other_id, my_id], my_id).starts_with("Your party led by Player "));
+    }
+
+    #[test]
+    fn achievement_toasts_shown_one_at_a_time() {
+        let mut toasts: VecDeque<(Achievement, f32)> = [Achievement::FirstGem, Achievement::MinedRock]
+            .iter()
+            .map(|achievement| (*achievement, ACHIEVEMENT_TOAST_DURATION))
+            .collect();
+
+        assert_eq!(achievement_toast_visibility(ACHIEVEMENT_TOAST_DURATION), 0.0);
+
+        update_achievement_toasts(&mut toasts, ACHIEVEMENT_TOAST_SLIDE_DURATION / 2.0);
+        assert!((achievement_toast_visibility(toasts[0].1) - 0.5).abs() < 0.001);
+
+        update_achievement_toasts(&mut toasts, 1.0);
+        assert_eq!(achievement_toast_visibility(toasts[0].1), 1.0);
+        assert_eq!(toasts[1].1, ACHIEVEMENT_TOAST_DURATION);
+
+        update_achievement_toasts(&mut toasts, ACHIEVEMENT_TOAST_DURATION);
+        assert_eq!(toasts.len(), 1);
+        assert_eq!(toasts[0].0, Achievement::MinedRock);
+    }
+
+    #[test]
+    fn announcements_displayed_for_duration_then_fade() {
+        let mut announcements = vec![("Restarting soon".to_string(), ANNOUNCEMENT_DURATION)];
+
+        update_announcements(&mut announcements, 10.0);
+        assert_eq!(announcements.len(), 1);
+        assert_eq!(announcement_alpha(announcements[0].1), 1.0);
+
+        update_announcements(&mut announcements, 0.5);
+        assert!((announcement_alpha(announcements[0].1) - 0.5).abs() < 0.001);
+
+        update_announcements(&mut announcements, 0.5);
+        assert!(announcements.is_empty());
+    }
+
+    #[test]
+    fn death_screen_counts_down_then_fades() {
+        let mut death_screen = Some((12, DEATH_SCREEN_DURATION));
+
+        update_death_screen(&mut death_screen, 1.5);
+        assert_eq!(death_screen.map(|(_, remaining)| remaining.ceil() as u32), Some(2));
+        assert_eq!(death_screen_alpha(death_screen.unwrap().1), 1.0);
+
+        update_death_screen(&mut death_screen, 1.0);
+        assert!((death_screen_alpha(death_screen.unwrap().1) - 0.5).abs() < 0.001);
+
+        update_death_screen(&mut death_screen, 0.5);
+        assert!(death_screen.is_none());
+    }
+}
@@ -1,15 +1,43 @@
 use macroquad::prelude as quad;
-use shared::messages;
+use shared::{
+    maps::entities::{self, Entity},
+    messages, Id
+};
 
 use super::State;
 use crate::{
     maps::entities::MyEntity,
     networking::{self, ConnectionTrait, PendingConnectionTrait},
-    sessions, AssetManager
+    sessions,
+    ui::{self, Button},
+    AssetManager, TextureKey
 };
 
 const CONNECTING_TEXT: &str = "Connecting...";
 const FAILED_TEXT: &str = "Failed to connect to server :(";
+const TIMED_OUT_TEXT: &str = "Could not connect to server. Check your internet connection.";
+const RETRY_TEXT: &str = "Retry";
+const RECONNECTING_TEXT: &str = "Connection lost. Reconnecting...";
+const STOP_RECONNECTING_TEXT: &str = "Press Escape to stop reconnecting";
+const CONNECTION_LOST_TEXT: &str = "Lost connection to server";
+const QUIT_TEXT: &str = "Quit";
+const CHOOSE_NAME_TEXT: &str = "Choose a name for your player:";
+const JOINING_TEXT: &str = "Joining...";
+const CHOOSE_WORLD_TEXT: &str = "Choose a world to play in:";
+const FETCHING_WORLDS_TEXT: &str = "Fetching worlds...";
+/// The number of worlds listed at once on the world selection screen (one for each of the number keys 1 to 9).
+const WORLDS_PER_PAGE: usize = 9;
+
+/// Font size of the text (& button labels) displayed by [`ConnectionLostState`].
+const CONNECTION_LOST_FONT_SIZE: f32 = 32.0;
+/// Size of the retry & quit buttons displayed by [`ConnectionLostState`] as a fraction of the screen size.
+const CONNECTION_LOST_BUTTON_SIZE: f32 = 0.1;
+
+/// Time in seconds after which an attempt to connect to the server is abandoned.
+const DEFAULT_CONNECTION_TIMEOUT_SECS: f32 = 15.0;
+
+/// The maximum time in seconds waited between attempts to reconnect to the server after the connection is lost.
+const MAX_RECONNECT_BACKOFF_SECS: f32 = 30.0;
 
 #[cfg(target_arch = "wasm32")]
 const WRONG_VERSION_TEXT: &str = "Please clear your browser cache!";
@@ -17,25 +45,65 @@ const WRONG_VERSION_TEXT: &str = "Please clear your browser cache!";
 const WRONG_VERSION_TEXT: &str = "Please download the latest version of the game!";
 
 pub struct ConnectingState {
+    connection_str: &'static str,
     pending_connection: networking::PendingConnection,
-    text: &'static str
+    text: &'static str,
+    /// The world chosen during the previous connection (if any), which is joined again without the player being asked
+    /// to choose a world once connected.
+    world: Option<String>,
+    /// The reason given by the server for rejecting the name chosen during the previous connection (if any), which is
+    /// displayed by the [`NameSelectionState`] entered once connected.
+    name_rejection: Option<String>,
+    /// Time in seconds after which the connection attempt is abandoned and a [`ConnectionFailedState`] is entered.
+    timeout_secs: f32,
+    /// Time in seconds since the connection attempt began.
+    elapsed: f32
 }
 
 impl ConnectingState {
     pub fn new(connection_str: &'static str) -> Self {
+        ConnectingState::with_timeout(connection_str, DEFAULT_CONNECTION_TIMEOUT_SECS)
+    }
+
+    pub fn with_timeout(connection_str: &'static str, timeout_secs: f32) -> Self {
         log::info!("Connecting to '{}'...", connection_str);
-        ConnectingState { pending_connection: networking::connect(connection_str), text: CONNECTING_TEXT }
+
+        ConnectingState {
+            connection_str,
+            pending_connection: networking::connect(connection_str),
+            text: CONNECTING_TEXT,
+            world: None,
+            name_rejection: None,
+            timeout_secs,
+            elapsed: 0.0
+        }
+    }
+
+    /// Connect again after the server rejected the player's chosen name (and so closed the connection), allowing the
+    /// player to choose another name once connected. The same world is joined again.
+    fn after_name_rejected(connection_str: &'static str, world: String, reason: String) -> Self {
+        ConnectingState { world: Some(world), name_rejection: Some(reason), ..ConnectingState::new(connection_str) }
     }
 }
 
 impl State for ConnectingState {
-    fn update_and_draw(&mut self, _assets: &AssetManager, _delta: f32) -> Option<Box<dyn State>> {
+    fn update_and_draw(&mut self, _assets: &AssetManager, delta: f32) -> Option<Box<dyn State>> {
+        self.elapsed += delta;
+
+        if connection_timed_out(self.elapsed, self.timeout_secs) {
+            log::error!("Failed to connect to server within {} seconds", self.timeout_secs);
+
+            return Some(Box::new(ConnectionFailedState { connection_str: self.connection_str }));
+        }
+
         match self.pending_connection.ready() {
             Ok(connection_option) => {
                 if let Some(connection) = connection_option {
                     log::info!("Connection to server established!");
 
-                    return Some(Box::new(ConnectedState::new(connection)));
+                    let world = self.world.take();
+                    let name_rejection = self.name_rejection.take();
+                    return Some(Box::new(ConnectedState::new(self.connection_str, connection, world, name_rejection)));
                 }
             }
 
@@ -56,95 +124,988 @@ impl State for ConnectingState {
     }
 }
 
+/// Returns `true` should the time elapsed since a connection attempt began exceed the given timeout.
+fn connection_timed_out(elapsed: f32, timeout_secs: f32) -> bool {
+    elapsed > timeout_secs
+}
+
+/// State entered should a connection to the server not be established in time. Displays an error message along with a
+/// button that allows for the connection to be retried.
+struct ConnectionFailedState {
+    connection_str: &'static str
+}
+
+impl State for ConnectionFailedState {
+    fn update_and_draw(&mut self, _assets: &AssetManager, _delta: f32) -> Option<Box<dyn State>> {
+        quad::draw_text(TIMED_OUT_TEXT, 0.0, 32.0, 32.0, quad::WHITE);
+
+        // Draw retry button beneath the error message:
+
+        let button = quad::Rect { x: 0.0, y: 48.0, w: 120.0, h: 40.0 };
+        let (mouse_x, mouse_y) = quad::mouse_position();
+        let hover = button.contains(quad::vec2(mouse_x, mouse_y));
+
+        quad::draw_rectangle(button.x, button.y, button.w, button.h, if hover { quad::GRAY } else { quad::DARKGRAY });
+        quad::draw_text(RETRY_TEXT, button.x + 28.0, button.y + 28.0, 32.0, quad::WHITE);
+
+        if hover && quad::is_mouse_button_pressed(quad::MouseButton::Left) {
+            return Some(Box::new(ConnectingState::new(self.connection_str)));
+        }
+
+        None
+    }
+
+    fn title(&self) -> &'static str {
+        "Failed To Connect To Server"
+    }
+}
+
+/// State entered should the server reject this client due to a protocol version mismatch.
+struct ProtocolMismatchState {
+    server_version: u32
+}
+
+impl State for ProtocolMismatchState {
+    fn update_and_draw(&mut self, _assets: &AssetManager, _delta: f32) -> Option<Box<dyn State>> {
+        quad::draw_text(WRONG_VERSION_TEXT, 0.0, 32.0, 32.0, quad::WHITE);
+        quad::draw_text(&protocol_mismatch_text(self.server_version), 0.0, 64.0, 24.0, quad::GRAY);
+
+        None
+    }
+
+    fn title(&self) -> &'static str {
+        "Incompatible Version"
+    }
+}
+
+/// Returns text detailing the protocol versions of both the server and this client.
+fn protocol_mismatch_text(server_version: u32) -> String {
+    format!("(server protocol version {}, client protocol version {})", server_version, shared::PROTOCOL_VERSION)
+}
+
 struct ConnectedState {
+    connection_str: &'static str,
     connection: Option<networking::Connection>,
-    text: &'static str
+    text: &'static str,
+    /// Whether a 'hello acknowledgement' message accepting this client's protocol version has been received.
+    hello_accepted: bool,
+    /// The world chosen during a previous connection (if any), passed on to the [`WorldSelectionState`].
+    world: Option<String>,
+    /// Passed on to the [`WorldSelectionState`] entered once the server accepts this client's protocol version.
+    name_rejection: Option<String>
 }
 
 impl ConnectedState {
-    fn new(mut connection: networking::Connection) -> Self {
-        let hello_msg = messages::ToServer::Hello { client_id_option: sessions::retrieve_client_id() };
+    fn new(
+        connection_str: &'static str, mut connection: networking::Connection, world: Option<String>,
+        name_rejection: Option<String>
+    ) -> Self {
+        let text = if send_hello(&mut connection) { CONNECTING_TEXT } else { FAILED_TEXT };
+
+        ConnectedState {
+            connection_str,
+            connection: Some(connection),
+            text,
+            hello_accepted: false,
+            world,
+            name_rejection
+        }
+    }
+}
+
+impl State for ConnectedState {
+    fn update_and_draw(&mut self, _assets: &AssetManager, _delta: f32) -> Option<Box<dyn State>> {
+        match receive_handshake_message(self.connection.as_mut().unwrap(), &mut self.hello_accepted) {
+            Handshake::Pending => {}
+
+            Handshake::HelloAccepted => {
+                // Have the player choose a world and then their name before entering the main game state:
+
+                let taken_connection = self.connection.take().unwrap();
+                let world = self.world.take();
+                let name_rejection = self.name_rejection.take();
+
+                return Some(Box::new(WorldSelectionState::new(
+                    self.connection_str,
+                    taken_connection,
+                    world,
+                    name_rejection
+                )));
+            }
+
+            Handshake::Rejected { server_version } => {
+                return Some(Box::new(ProtocolMismatchState { server_version }));
+            }
+
+            // The server only lists worlds once asked and only welcomes clients (or rejects their names) once a name
+            // has been chosen:
+            Handshake::AvailableWorlds(_)
+            | Handshake::Welcomed(..)
+            | Handshake::NameRejected { .. }
+            | Handshake::Failed => {
+                self.text = FAILED_TEXT;
+            }
+        }
+
+        quad::draw_text(self.text, 0.0, 0.0, 32.0, quad::WHITE);
+
+        None
+    }
+
+    fn title(&self) -> &'static str {
+        "Connected To Server"
+    }
+}
+
+/// State entered once the server has accepted this client's protocol version, in which the worlds hosted by the server
+/// are requested and the player chooses which to join by pressing the number key displayed beside it. The worlds are
+/// listed [`WORLDS_PER_PAGE`] at a time, with the left and right arrow keys changing page. Should the server host only
+/// a single world, or should a world have been chosen during a previous connection, that world is joined without the
+/// player being asked. The [`NameSelectionState`] is entered once a world is joined.
+struct WorldSelectionState {
+    connection_str: &'static str,
+    connection: Option<networking::Connection>,
+    /// The names of the worlds hosted by the server, or `None` should they not yet have been received.
+    worlds: Option<Vec<String>>,
+    /// The world chosen during a previous connection (if any).
+    previous_world: Option<String>,
+    /// Whether the names of the worlds hosted by the server have been requested.
+    requested: bool,
+    /// The page of the list of worlds currently displayed.
+    page: usize,
+    /// Passed on to the [`NameSelectionState`] entered once a world is joined.
+    name_rejection: Option<String>
+}
+
+impl WorldSelectionState {
+    fn new(
+        connection_str: &'static str, connection: networking::Connection, previous_world: Option<String>,
+        name_rejection: Option<String>
+    ) -> Self {
+        WorldSelectionState {
+            connection_str,
+            connection: Some(connection),
+            worlds: None,
+            previous_world,
+            requested: false,
+            page: 0,
+            name_rejection
+        }
+    }
+
+    /// Request the names of the worlds hosted by the server. Returns `false` should the message fail to send.
+    fn request_worlds(&mut self) -> bool {
+        match self.connection.as_mut().unwrap().send(&messages::ToServer::ListWorlds) {
+            Ok(_) => true,
+
+            Err(e) => {
+                log::error!("Failed to request list of worlds due to error: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Tell the server which world to join then enter the name selection state.
+    fn join(&mut self, world: String) -> Box<dyn State> {
+        let mut connection = self.connection.take().unwrap();
+        let msg = messages::ToServer::JoinWorld(world.clone());
 
-        let text = match connection.send(&hello_msg) {
+        match connection.send(&msg) {
             Ok(_) => {
-                log::debug!("Sent 'hello' message to server: {}", hello_msg);
-                CONNECTING_TEXT
+                log::debug!("Sent chosen world to server: {}", msg);
+
+                let name_rejection = self.name_rejection.take();
+                Box::new(NameSelectionState::new(self.connection_str, connection, world, name_rejection))
             }
 
             Err(e) => {
-                log::error!("Failed to send 'hello' message due to error: {}", e);
-                FAILED_TEXT
+                log::error!("Failed to send chosen world due to error: {}", e);
+
+                Box::new(ConnectionFailedState { connection_str: self.connection_str })
             }
-        };
+        }
+    }
+}
+
+impl State for WorldSelectionState {
+    fn update_and_draw(&mut self, _assets: &AssetManager, _delta: f32) -> Option<Box<dyn State>> {
+        // Rejoin the world chosen during the previous connection without asking the player:
+        if let Some(world) = self.previous_world.take() {
+            return Some(self.join(world));
+        }
+
+        if let Some(worlds) = &self.worlds {
+            let page_count = world_page_count(worlds.len());
+
+            if quad::is_key_pressed(quad::KeyCode::Right) {
+                self.page = (self.page + 1) % page_count;
+            }
+            if quad::is_key_pressed(quad::KeyCode::Left) {
+                self.page = (self.page + page_count - 1) % page_count;
+            }
+
+            let mut choice = None;
+
+            while let Some(c) = quad::get_char_pressed() {
+                choice = choice.or_else(|| world_choice(c, worlds.len(), self.page));
+            }
+
+            if let Some(index) = choice {
+                let world = worlds[index].clone();
+                return Some(self.join(world));
+            }
+        }
+        else {
+            if !self.requested {
+                if !self.request_worlds() {
+                    return Some(Box::new(ConnectionFailedState { connection_str: self.connection_str }));
+                }
+                self.requested = true;
+            }
+
+            match receive_handshake_message(self.connection.as_mut().unwrap(), &mut true) {
+                Handshake::Pending => {}
 
-        ConnectedState { connection: Some(connection), text }
+                Handshake::AvailableWorlds(worlds) => {
+                    log::debug!("Worlds hosted by server: {}", worlds.join(", "));
+
+                    match worlds.as_slice() {
+                        [] => return Some(Box::new(ConnectionFailedState { connection_str: self.connection_str })),
+                        [only_world] => return Some(self.join(only_world.clone())),
+                        _ => self.worlds = Some(worlds)
+                    }
+                }
+
+                Handshake::HelloAccepted
+                | Handshake::Welcomed(..)
+                | Handshake::Rejected { .. }
+                | Handshake::NameRejected { .. }
+                | Handshake::Failed => {
+                    return Some(Box::new(ConnectionFailedState { connection_str: self.connection_str }));
+                }
+            }
+        }
+
+        match &self.worlds {
+            Some(worlds) => {
+                quad::draw_text(CHOOSE_WORLD_TEXT, 0.0, 32.0, 32.0, quad::WHITE);
+
+                let page_worlds = worlds.iter().skip(self.page * WORLDS_PER_PAGE).take(WORLDS_PER_PAGE);
+
+                for (index, world) in page_worlds.enumerate() {
+                    let y = 64.0 + (index as f32 * 32.0);
+                    quad::draw_text(&world_choice_text(index, world), 0.0, y, 32.0, quad::WHITE);
+                }
+
+                let page_count = world_page_count(worlds.len());
+                if page_count > 1 {
+                    let y = 64.0 + (WORLDS_PER_PAGE as f32 * 32.0);
+                    quad::draw_text(&world_page_text(self.page, page_count), 0.0, y, 32.0, quad::WHITE);
+                }
+            }
+
+            None => quad::draw_text(FETCHING_WORLDS_TEXT, 0.0, 32.0, 32.0, quad::WHITE)
+        }
+
+        None
+    }
+
+    fn title(&self) -> &'static str {
+        "Choose World"
     }
 }
 
-impl State for ConnectedState {
+/// Returns the index of the world chosen by typing the given character (a digit from 1 to 9 giving the position of
+/// the world on the given page of the list displayed) should it correspond to one of the given number of worlds.
+fn world_choice(c: char, world_count: usize, page: usize) -> Option<usize> {
+    let number = c.to_digit(10)? as usize;
+    let index = (page * WORLDS_PER_PAGE) + number.checked_sub(1)?;
+    if number <= WORLDS_PER_PAGE && index < world_count {
+        Some(index)
+    }
+    else {
+        None
+    }
+}
+
+/// The number of pages needed to list the given number of worlds (at least one).
+fn world_page_count(world_count: usize) -> usize {
+    world_count.div_ceil(WORLDS_PER_PAGE).max(1)
+}
+
+/// Text displayed below the list of worlds should they not all fit on a single page.
+fn world_page_text(page: usize, page_count: usize) -> String {
+    format!("< Page {} of {} >", page + 1, page_count)
+}
+
+/// Text displayed beside the world at the given position on the current page of the list of worlds to choose from.
+fn world_choice_text(index: usize, world: &str) -> String {
+    format!("[{}] {}", index + 1, world)
+}
+
+/// State entered once the player's world has been chosen, in which the player types the name by
+/// which other players will know them. The chosen name is sent to the server and the main game state is entered once
+/// the server welcomes the client. Should the server reject the name, the connection (which the server closes) is
+/// re-established so that the player can choose another.
+pub struct NameSelectionState {
+    connection_str: &'static str,
+    connection: Option<networking::Connection>,
+    /// The name of the world joined.
+    world: String,
+    /// The name typed so far, initially the name last chosen on this device (if any).
+    name: String,
+    /// Why the last name chosen cannot be used, either as it is invalid or as it was rejected by the server.
+    problem: Option<String>,
+    /// Whether the chosen name has been sent to the server, in which case a response is being waited for.
+    submitted: bool
+}
+
+impl NameSelectionState {
+    fn new(
+        connection_str: &'static str, connection: networking::Connection, world: String, problem: Option<String>
+    ) -> Self {
+        NameSelectionState {
+            connection_str,
+            connection: Some(connection),
+            world,
+            name: sessions::retrieve_player_name().unwrap_or_default(),
+            problem,
+            submitted: false
+        }
+    }
+
+    /// Send the typed name to the server should it be valid, or display why it is not otherwise. Returns `false`
+    /// should the message fail to send.
+    fn submit_name(&mut self) -> bool {
+        if let Err(err) = entities::validate_player_name(&self.name) {
+            self.problem = Some(err.to_string());
+            return true;
+        }
+
+        let msg = messages::ToServer::SetName(self.name.clone());
+
+        match self.connection.as_mut().unwrap().send(&msg) {
+            Ok(_) => {
+                log::debug!("Sent chosen name to server: {}", msg);
+
+                self.submitted = true;
+                self.problem = None;
+                true
+            }
+
+            Err(e) => {
+                log::error!("Failed to send chosen name due to error: {}", e);
+                false
+            }
+        }
+    }
+}
+
+impl State for NameSelectionState {
     fn update_and_draw(&mut self, _assets: &AssetManager, _delta: f32) -> Option<Box<dyn State>> {
-        match self.connection.as_mut().unwrap().receive() {
-            Ok(msg_option) => {
-                if let Some(msg) = msg_option {
-                    match msg {
-                        messages::FromServer::Welcome {
-                            version,
-                            your_client_id,
-                            your_entity_with_id: (entity_id, entity)
-                        } => {
-                            log::debug!("Server version: {}", version);
+        if self.submitted {
+            // Wait for the server to either welcome this client or reject the chosen name:
 
-                            // Check version aligns with that of the version:
+            match receive_handshake_message(self.connection.as_mut().unwrap(), &mut true) {
+                Handshake::Pending => {}
 
-                            if version == shared::VERSION {
-                                // Save the client ID (browser local storage):
+                Handshake::Welcomed(entity_id, entity, fog_of_war) => {
+                    // Enter the main game state:
 
-                                log::debug!("Given client ID: {}", your_client_id);
+                    log::debug!("Given player entity: {} - {}", entity, entity_id);
 
-                                sessions::store_client_id(your_client_id);
+                    sessions::store_player_name(&self.name);
 
-                                // Enter the main game state:
+                    let my_entity = MyEntity::new(*entity, entity_id);
+                    let taken_connection = self.connection.take().unwrap();
+                    let world = std::mem::take(&mut self.world);
+                    let game_state = super::game::GameState::new(
+                        self.connection_str,
+                        world,
+                        taken_connection,
+                        my_entity,
+                        fog_of_war
+                    );
 
-                                log::debug!("Given player entity: {} - {}", entity, entity_id);
+                    return Some(Box::new(game_state));
+                }
 
-                                let my_entity = MyEntity::new(entity, entity_id);
-                                let taken_connection = self.connection.take().unwrap();
-                                let game_state = super::game::GameState::new(taken_connection, my_entity);
+                Handshake::NameRejected { reason } => {
+                    let world = std::mem::take(&mut self.world);
+                    return Some(Box::new(ConnectingState::after_name_rejected(self.connection_str, world, reason)));
+                }
 
-                                return Some(Box::new(game_state));
-                            }
-                            else {
-                                log::error!(
-                                    "Version of server ({}) differs from that of this client ({})",
-                                    version,
-                                    shared::VERSION
-                                );
-
-                                self.text = WRONG_VERSION_TEXT;
+                Handshake::HelloAccepted
+                | Handshake::AvailableWorlds(_)
+                | Handshake::Rejected { .. }
+                | Handshake::Failed => {
+                    return Some(Box::new(ConnectionFailedState { connection_str: self.connection_str }));
+                }
+            }
+        }
+        else {
+            while let Some(c) = quad::get_char_pressed() {
+                if accepts_name_character(&self.name, c) {
+                    self.name.push(c);
+                }
+            }
+
+            if quad::is_key_pressed(quad::KeyCode::Backspace) {
+                self.name.pop();
+            }
+
+            if quad::is_key_pressed(quad::KeyCode::Enter) && !self.submit_name() {
+                return Some(Box::new(ConnectionFailedState { connection_str: self.connection_str }));
+            }
+        }
+
+        quad::draw_text(CHOOSE_NAME_TEXT, 0.0, 32.0, 32.0, quad::WHITE);
+        quad::draw_text(&format!("> {}_", self.name), 0.0, 64.0, 32.0, quad::WHITE);
+
+        if self.submitted {
+            quad::draw_text(JOINING_TEXT, 0.0, 96.0, 24.0, quad::GRAY);
+        }
+        else {
+            quad::draw_text(&name_rules_text(), 0.0, 96.0, 24.0, quad::GRAY);
+        }
+
+        if let Some(problem) = &self.problem {
+            quad::draw_text(problem, 0.0, 128.0, 24.0, quad::RED);
+        }
+
+        None
+    }
+
+    fn title(&self) -> &'static str {
+        "Choose Name"
+    }
+}
+
+/// Returns whether the given character may be typed as the next character of the given partially typed name. Only
+/// characters permitted by [`entities::validate_player_name`] may be typed, up to the maximum name length.
+fn accepts_name_character(name: &str, c: char) -> bool {
+    (c.is_ascii_alphanumeric() || c == '_') && name.len() < entities::MAX_PLAYER_NAME_LENGTH
+}
+
+/// Text describing the names that players may choose.
+fn name_rules_text() -> String {
+    format!(
+        "Between {} and {} letters, digits, or underscores. Press Enter to play.",
+        entities::MIN_PLAYER_NAME_LENGTH,
+        entities::MAX_PLAYER_NAME_LENGTH
+    )
+}
+
+/// Send a 'hello' message to the server over a newly established connection. Returns `false` should the message fail
+/// to send.
+fn send_hello(connection: &mut networking::Connection) -> bool {
+    let hello_msg = messages::ToServer::Hello {
+        protocol_version: shared::PROTOCOL_VERSION,
+        client_id_option: sessions::retrieve_client_id()
+    };
+
+    match connection.send(&hello_msg) {
+        Ok(_) => {
+            log::debug!("Sent 'hello' message to server: {}", hello_msg);
+            true
+        }
+
+        Err(e) => {
+            log::error!("Failed to send 'hello' message due to error: {}", e);
+            false
+        }
+    }
+}
+
+/// The progress of the handshake performed with the server after a 'hello' message is sent.
+enum Handshake {
+    /// No message has been received from the server since last checked.
+    Pending,
+    /// The server accepted this client's protocol version so now expects the player's chosen world (see
+    /// [`messages::ToServer::JoinWorld`]) followed by their chosen name (see [`messages::ToServer::SetName`]).
+    HelloAccepted,
+    /// The server sent the names of the worlds that it hosts in response to a [`messages::ToServer::ListWorlds`]
+    /// message.
+    AvailableWorlds(Vec<String>),
+    /// The server sent a 'welcome' message containing the player entity & its ID as well as whether fog of war is
+    /// enabled. The entity is boxed as it is far larger than the data of the other variants.
+    Welcomed(Id, Box<Entity>, bool),
+    /// The server does not support this client's protocol version.
+    Rejected { server_version: u32 },
+    /// The server would not accept the player's chosen name for the given reason and so closed the connection.
+    NameRejected { reason: String },
+    /// An unexpected message was received or the connection experienced an error.
+    Failed
+}
+
+/// Check for a 'hello acknowledgement', 'available worlds', 'welcome', or 'name rejected' message from the server
+/// (non-blocking). The client ID given by the server in its 'welcome' message is stored.
+fn receive_handshake_message(connection: &mut networking::Connection, hello_accepted: &mut bool) -> Handshake {
+    match connection.receive() {
+        Ok(None) => Handshake::Pending,
+
+        Ok(Some(messages::FromServer::HelloAck { accepted, server_version })) if !*hello_accepted => {
+            if accepted {
+                log::debug!("Server accepted protocol version {}", shared::PROTOCOL_VERSION);
+
+                *hello_accepted = true;
+                Handshake::HelloAccepted
+            }
+            else {
+                log::error!(
+                    "Protocol version of server ({}) differs from that of this client ({})",
+                    server_version,
+                    shared::PROTOCOL_VERSION
+                );
+
+                Handshake::Rejected { server_version }
+            }
+        }
+
+        Ok(Some(messages::FromServer::Welcome {
+            version,
+            your_client_id,
+            your_entity_with_id: (entity_id, entity),
+            fog_of_war
+        })) if *hello_accepted => {
+            log::debug!("Server version: {}", version);
+
+            if version != shared::VERSION {
+                log::warn!(
+                    "Version of server ({}) differs from that of this client ({}) though both use the same protocol \
+                     version",
+                    version,
+                    shared::VERSION
+                );
+            }
+
+            // Save the client ID (browser local storage):
+
+            log::debug!("Given client ID: {}", your_client_id);
+
+            sessions::store_client_id(your_client_id);
+
+            Handshake::Welcomed(entity_id, Box::new(entity), fog_of_war)
+        }
+
+        Ok(Some(messages::FromServer::AvailableWorlds(worlds))) if *hello_accepted => {
+            Handshake::AvailableWorlds(worlds)
+        }
+
+        Ok(Some(messages::FromServer::NameRejected { reason })) if *hello_accepted => {
+            log::warn!("Server rejected chosen name as {}", reason);
+
+            Handshake::NameRejected { reason }
+        }
+
+        Ok(Some(other_msg)) => {
+            log::error!(
+                "Expected a 'hello acknowledgement', 'available worlds', 'welcome', or 'name rejected' message from \
+                 server but instead received: {}",
+                other_msg
+            );
+
+            Handshake::Failed
+        }
+
+        Err(e) => {
+            log::error!("Connecting error while waiting to receive a 'welcome' message: {}", e);
+
+            Handshake::Failed
+        }
+    }
+}
+
+/// State entered should the connection with the server be lost during gameplay. Attempts to re-establish the
+/// connection are made with an exponentially increasing delay between each (see [`reconnect_backoff_secs`]).
+pub struct ReconnectingState {
+    connection_str: &'static str,
+    /// The name of the world that the player was in when the connection was lost, which is joined again.
+    world: String,
+    /// The error that caused the connection to be lost. Taken should the player choose to stop reconnecting.
+    cause: Option<networking::Error>,
+    /// The player entity as it was when the connection was lost. Taken once the connection is re-established.
+    snapshot: Option<MyEntity>,
+    /// The number of the current reconnection attempt (starting at 1).
+    attempt: u32,
+    phase: ReconnectPhase
+}
+
+enum ReconnectPhase {
+    /// Waiting for the given number of seconds before the next attempt is made.
+    BackingOff { remaining_secs: f32 },
+    /// Waiting for the connection to be established.
+    Connecting { pending_connection: networking::PendingConnection, elapsed: f32 },
+    /// Connected & sent a 'hello' message (followed by the player's world & name once the 'hello' message is accepted)
+    /// so now waiting for a 'welcome' message.
+    AwaitingWelcome { connection: Option<Box<networking::Connection>>, hello_accepted: bool }
+}
+
+impl ReconnectingState {
+    pub fn new(connection_str: &'static str, world: String, snapshot: MyEntity, cause: networking::Error) -> Self {
+        log::info!("Connection to server lost - attempting to reconnect...");
+
+        ReconnectingState {
+            connection_str,
+            world,
+            cause: Some(cause),
+            snapshot: Some(snapshot),
+            attempt: 1,
+            phase: ReconnectPhase::BackingOff { remaining_secs: reconnect_backoff_secs(1) }
+        }
+    }
+
+    /// Wait before making the next reconnection attempt.
+    fn attempt_failed(&mut self) {
+        self.attempt += 1;
+        self.phase = ReconnectPhase::BackingOff { remaining_secs: reconnect_backoff_secs(self.attempt) };
+    }
+}
+
+impl State for ReconnectingState {
+    fn update_and_draw(&mut self, _assets: &AssetManager, delta: f32) -> Option<Box<dyn State>> {
+        if quad::is_key_pressed(quad::KeyCode::Escape) {
+            log::info!("Reconnection attempts stopped by player");
+
+            let cause = self.cause.take().unwrap();
+            return Some(Box::new(ConnectionLostState::new(self.connection_str, cause)));
+        }
+
+        let mut failed = false;
+
+        match &mut self.phase {
+            ReconnectPhase::BackingOff { remaining_secs } => {
+                *remaining_secs -= delta;
+
+                if *remaining_secs <= 0.0 {
+                    log::info!("Reconnection attempt {} - connecting to '{}'...", self.attempt, self.connection_str);
+
+                    self.phase = ReconnectPhase::Connecting {
+                        pending_connection: networking::connect(self.connection_str),
+                        elapsed: 0.0
+                    };
+                }
+            }
+
+            ReconnectPhase::Connecting { pending_connection, elapsed } => {
+                *elapsed += delta;
+
+                match pending_connection.ready() {
+                    Ok(Some(mut connection)) => {
+                        log::info!("Connection to server re-established!");
+
+                        if send_hello(&mut connection) {
+                            self.phase = ReconnectPhase::AwaitingWelcome {
+                                connection: Some(Box::new(connection)),
+                                hello_accepted: false
+                            };
+                        }
+                        else {
+                            failed = true;
+                        }
+                    }
+
+                    Ok(None) => {
+                        if connection_timed_out(*elapsed, DEFAULT_CONNECTION_TIMEOUT_SECS) {
+                            log::warn!("Failed to reconnect within {} seconds", DEFAULT_CONNECTION_TIMEOUT_SECS);
+                            failed = true;
+                        }
+                    }
+
+                    Err(e) => {
+                        log::warn!("Failed to reconnect due to error: {}", e);
+                        failed = true;
+                    }
+                }
+            }
+
+            ReconnectPhase::AwaitingWelcome { connection, hello_accepted } => {
+                match receive_handshake_message(connection.as_mut().unwrap(), hello_accepted) {
+                    Handshake::Pending => {}
+
+                    Handshake::HelloAccepted => {
+                        // Continue playing in the same world & under the same name as before the connection was lost:
+                        let name = self.snapshot.as_ref().unwrap().get_contained_entity().name.clone();
+                        let msgs =
+                            [messages::ToServer::JoinWorld(self.world.clone()), messages::ToServer::SetName(name)];
+
+                        for msg in &msgs {
+                            if let Err(e) = connection.as_mut().unwrap().send(msg) {
+                                log::warn!("Failed to send '{}' message after reconnecting due to error: {}", msg, e);
+                                failed = true;
+                                break;
                             }
                         }
+                    }
+
+                    // Worlds are only listed when asked for, which is not done when reconnecting:
+                    Handshake::AvailableWorlds(_) => failed = true,
+
+                    // The name may still be in use by the player entity of the lost connection should the server not
+                    // yet have noticed that connection close, in which case a later attempt should succeed:
+                    Handshake::NameRejected { .. } => failed = true,
+
+                    Handshake::Welcomed(entity_id, entity, fog_of_war) => {
+                        let mut my_entity = self.snapshot.take().unwrap();
+
+                        if my_entity.get_id() == entity_id {
+                            log::info!("Resuming as the same player entity {}", entity_id);
 
-                        other_msg => {
-                            log::error!("Expected a 'welcome' message from server but instead received: {}", other_msg);
+                            my_entity.reconnected(*entity);
+                        }
+                        else {
+                            log::warn!(
+                                "Server assigned a different player entity ({}) to that before the connection was \
+                                 lost ({}) so local state is discarded",
+                                entity_id,
+                                my_entity.get_id()
+                            );
 
-                            self.text = FAILED_TEXT;
+                            my_entity = MyEntity::new(*entity, entity_id);
                         }
+
+                        let taken_connection = *connection.take().unwrap();
+                        let game_state = super::game::GameState::new(
+                            self.connection_str,
+                            self.world.clone(),
+                            taken_connection,
+                            my_entity,
+                            fog_of_war
+                        );
+
+                        return Some(Box::new(game_state));
                     }
+
+                    Handshake::Rejected { server_version } => {
+                        return Some(Box::new(ProtocolMismatchState { server_version }));
+                    }
+
+                    Handshake::Failed => failed = true
                 }
             }
+        }
 
-            Err(e) => {
-                log::error!("Connecting error while waiting to receive a 'welcome' message: {}", e);
-                self.text = FAILED_TEXT;
-            }
+        if failed {
+            self.attempt_failed();
         }
 
-        quad::draw_text(self.text, 0.0, 0.0, 32.0, quad::WHITE);
+        // Draw "Reconnecting..." overlay:
+
+        let remaining_secs = match self.phase {
+            ReconnectPhase::BackingOff { remaining_secs } => Some(remaining_secs),
+            _ => None
+        };
+
+        quad::draw_rectangle(
+            0.0,
+            0.0,
+            quad::screen_width(),
+            quad::screen_height(),
+            quad::Color::new(0.0, 0.0, 0.0, 0.6)
+        );
+        quad::draw_text(RECONNECTING_TEXT, 0.0, 32.0, 32.0, quad::WHITE);
+        quad::draw_text(&reconnect_status_text(self.attempt, remaining_secs), 0.0, 64.0, 24.0, quad::GRAY);
+        quad::draw_text(STOP_RECONNECTING_TEXT, 0.0, 96.0, 24.0, quad::GRAY);
 
         None
     }
 
     fn title(&self) -> &'static str {
-        "Connected To Server"
+        "Reconnecting To Server"
+    }
+}
+
+/// State entered should the player choose to stop attempting to reconnect after the connection with the server was
+/// lost. Displays the error that caused the connection to be lost along with buttons allowing the player to either
+/// retry connecting or quit the game.
+pub struct ConnectionLostState {
+    connection_str: &'static str,
+    /// The error that caused the connection to be lost.
+    cause: networking::Error,
+    retry_button: ui::SimpleButton,
+    quit_button: ui::SimpleButton
+}
+
+impl ConnectionLostState {
+    pub fn new(connection_str: &'static str, cause: networking::Error) -> Self {
+        ConnectionLostState {
+            connection_str,
+            cause,
+            retry_button: ui::SimpleButton::new(-0.06, 0.1, 0, 8),
+            quit_button: ui::SimpleButton::new(0.06, 0.1, 2, 8)
+        }
+    }
+}
+
+impl State for ConnectionLostState {
+    fn required_textures(&self) -> &[TextureKey] {
+        &[TextureKey::Ui]
+    }
+
+    fn update_and_draw(&mut self, assets: &AssetManager, _delta: f32) -> Option<Box<dyn State>> {
+        if self.retry_button.update(CONNECTION_LOST_BUTTON_SIZE) {
+            return Some(Box::new(ConnectingState::new(self.connection_str)));
+        }
+
+        if self.quit_button.update(CONNECTION_LOST_BUTTON_SIZE) {
+            log::info!("Quitting after connection to server was lost");
+
+            #[cfg(not(target_arch = "wasm32"))]
+            std::process::exit(0);
+        }
+
+        // Draw the error message in a box centred horizontally above the buttons:
+
+        let cause_text = self.cause.to_string();
+        let text_width = [CONNECTION_LOST_TEXT, cause_text.as_str()]
+            .iter()
+            .map(|text| quad::measure_text(text, None, CONNECTION_LOST_FONT_SIZE as u16, 1.0).width)
+            .fold(0.0, f32::max);
+
+        let padding = CONNECTION_LOST_FONT_SIZE / 2.0;
+        let box_width = text_width + (padding * 2.0);
+        let box_height = (CONNECTION_LOST_FONT_SIZE * 2.0) + (padding * 2.0);
+        let box_x = (quad::screen_width() - box_width) / 2.0;
+        let box_y = (quad::screen_height() / 2.0) - box_height - padding;
+
+        quad::draw_rectangle(box_x, box_y, box_width, box_height, quad::DARKGRAY);
+        quad::draw_text(
+            CONNECTION_LOST_TEXT,
+            box_x + padding,
+            box_y + padding + CONNECTION_LOST_FONT_SIZE,
+            CONNECTION_LOST_FONT_SIZE,
+            quad::WHITE
+        );
+        quad::draw_text(
+            &cause_text,
+            box_x + padding,
+            box_y + padding + (CONNECTION_LOST_FONT_SIZE * 2.0),
+            CONNECTION_LOST_FONT_SIZE,
+            quad::LIGHTGRAY
+        );
+
+        // Draw the buttons, each labelled beneath:
+
+        for (button, label) in [(&self.retry_button, RETRY_TEXT), (&self.quit_button, QUIT_TEXT)] {
+            let ((button_x, button_y), button_size) = button.draw(assets, CONNECTION_LOST_BUTTON_SIZE);
+            let label_width = quad::measure_text(label, None, CONNECTION_LOST_FONT_SIZE as u16, 1.0).width;
+
+            quad::draw_text(
+                label,
+                button_x + ((button_size - label_width) / 2.0),
+                button_y + button_size + CONNECTION_LOST_FONT_SIZE,
+                CONNECTION_LOST_FONT_SIZE,
+                quad::WHITE
+            );
+        }
+
+        None
+    }
+
+    fn title(&self) -> &'static str {
+        "Lost Connection To Server"
+    }
+}
+
+/// The number of seconds to wait before making the given reconnection attempt (starting at 1). The delay doubles with
+/// each attempt up to a maximum of [`MAX_RECONNECT_BACKOFF_SECS`].
+fn reconnect_backoff_secs(attempt: u32) -> f32 {
+    let exponent = attempt.saturating_sub(1).min(16) as i32;
+    2f32.powi(exponent).min(MAX_RECONNECT_BACKOFF_SECS)
+}
+
+/// Text describing the progress of reconnecting to the server, including the time remaining until the next attempt
+/// (if waiting for one).
+fn reconnect_status_text(attempt: u32, remaining_secs: Option<f32>) -> String {
+    match remaining_secs {
+        Some(secs) => format!("Attempt {} in {:.0}s", attempt, secs.max(0.0).ceil()),
+        None => format!("Attempt {} in progress...", attempt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_timeout() {
+        let frame_time = 1.0 / 60.0;
+        let mut elapsed = 0.0;
+        let mut frames = 0;
+
+        while !connection_timed_out(elapsed, DEFAULT_CONNECTION_TIMEOUT_SECS) {
+            elapsed += frame_time;
+            frames += 1;
+        }
+
+        // Timeout should occur on the first frame after 15 seconds have passed:
+        assert!(elapsed > DEFAULT_CONNECTION_TIMEOUT_SECS);
+        assert!(elapsed - frame_time <= DEFAULT_CONNECTION_TIMEOUT_SECS);
+        assert_eq!(frames, 901);
+
+        assert!(!connection_timed_out(DEFAULT_CONNECTION_TIMEOUT_SECS, DEFAULT_CONNECTION_TIMEOUT_SECS));
+        assert!(connection_timed_out(5.5, 5.0));
+    }
+
+    #[test]
+    fn protocol_mismatch_text_includes_both_versions() {
+        let text = protocol_mismatch_text(shared::PROTOCOL_VERSION + 1);
+
+        assert!(text.contains(&format!("server protocol version {}", shared::PROTOCOL_VERSION + 1)));
+        assert!(text.contains(&format!("client protocol version {}", shared::PROTOCOL_VERSION)));
+    }
+
+    #[test]
+    fn name_characters_restricted() {
+        assert!(accepts_name_character("", 'a'));
+        assert!(accepts_name_character("Gem", '_'));
+        assert!(accepts_name_character("Gem", '7'));
+        assert!(!accepts_name_character("Gem", ' '));
+        assert!(!accepts_name_character("Gem", 'é'));
+        assert!(!accepts_name_character(&"a".repeat(entities::MAX_PLAYER_NAME_LENGTH), 'a'));
+
+        assert!(name_rules_text().starts_with("Between 2 and 24 "));
+    }
+
+    #[test]
+    fn world_chosen_by_number() {
+        assert_eq!(world_choice('1', 3, 0), Some(0));
+        assert_eq!(world_choice('3', 3, 0), Some(2));
+        assert_eq!(world_choice('4', 3, 0), None);
+        assert_eq!(world_choice('0', 3, 0), None);
+        assert_eq!(world_choice('a', 3, 0), None);
+        assert_eq!(world_choice('9', 12, 0), Some(8));
+
+        assert_eq!(world_choice_text(0, "arena"), "[1] arena");
+    }
+
+    #[test]
+    fn worlds_beyond_the_ninth_chosen_on_later_pages() {
+        assert_eq!(world_page_count(0), 1);
+        assert_eq!(world_page_count(9), 1);
+        assert_eq!(world_page_count(10), 2);
+        assert_eq!(world_page_count(19), 3);
+
+        assert_eq!(world_choice('1', 12, 1), Some(9));
+        assert_eq!(world_choice('3', 12, 1), Some(11));
+        assert_eq!(world_choice('4', 12, 1), None);
+        assert_eq!(world_choice('1', 9, 1), None);
+
+        assert_eq!(world_page_text(1, 2), "< Page 2 of 2 >");
+    }
+
+    #[test]
+    fn reconnect_backoff_doubles_up_to_cap() {
+        let delays: Vec<f32> = (1..=7).map(reconnect_backoff_secs).collect();
+        assert_eq!(delays, vec![1.0, 2.0, 4.0, 8.0, 16.0, 30.0, 30.0]);
+
+        assert_eq!(reconnect_backoff_secs(u32::MAX), MAX_RECONNECT_BACKOFF_SECS);
+    }
+
+    #[test]
+    fn reconnect_status_text_shows_attempt_and_countdown() {
+        assert_eq!(reconnect_status_text(3, Some(3.2)), "Attempt 3 in 4s");
+        assert_eq!(reconnect_status_text(3, Some(-0.1)), "Attempt 3 in 0s");
+        assert_eq!(reconnect_status_text(4, None), "Attempt 4 in progress...");
     }
 }
@@ -3,13 +3,27 @@ mod widgets;
 use macroquad::prelude as quad;
 use shared::{
     items,
-    maps::{entities::Entity, ChunkCoords}
+    maps::{
+        entities::{Entity, FacialExpression},
+        TileCoords, TileQuery
+    },
+    mechanics::ActionKind,
+    messages,
+    stats::PlayerStats,
+    Id
+};
+pub use widgets::{
+    menus::{
+        draw_achievement_toast, draw_announcement_overlay, draw_chat_overlay, draw_death_screen, draw_leaderboard_menu,
+        draw_minimap, draw_quest_tracker, draw_sign_text
+    },
+    Button, SimpleButton
 };
-use widgets::Button;
 
 use crate::{
     maps::{entities::MyEntity, rendering::MapRenderer, ClientMap},
-    networking, AssetManager
+    networking::{self, ConnectionTrait},
+    AssetManager
 };
 
 pub struct Ui {
@@ -18,9 +32,23 @@ pub struct Ui {
     show_purchase_buttons_button: widgets::SimpleButton,
     place_bomb_button: widgets::QuantityButton,
     detonate_bombs_button: widgets::QuantityButton,
+    use_potion_button: widgets::QuantityButton,
     showing_purchase_buttons: bool,
     bool_item_purchase_buttons: Vec<widgets::PurchaseButton<items::BoolItem>>,
-    quantitative_item_purchase_buttons: Vec<widgets::PurchaseButton<items::QuantitativeItem>>
+    quantitative_item_purchase_buttons: Vec<widgets::PurchaseButton<items::QuantitativeItem>>,
+    /// The player's statistics as last provided by the server, shown alongside the item purchase buttons.
+    stats: Option<PlayerStats>,
+    showing_expression_picker: bool,
+    /// Buttons allowing the player to change their entity's facial expression.
+    expression_buttons: Vec<(FacialExpression, widgets::SimpleButton)>,
+    showing_emote_picker: bool,
+    /// Grid of buttons allowing the player to perform an emote.
+    emote_buttons: Vec<(messages::EmoteKind, widgets::SimpleButton)>,
+    /// Cycles through the channels on which chat messages may be sent, displaying the icon of the selected channel.
+    chat_channel_button: widgets::SimpleButton,
+    /// The channel on which the player's chat messages are sent (shown alongside the chat input).
+    chat_channel: messages::ChatChannel,
+    health_bar: widgets::ProgressBar
 }
 
 impl Ui {
@@ -31,6 +59,7 @@ impl Ui {
             show_purchase_buttons_button: widgets::SimpleButton::new(-0.425, 0.4, 2, 4),
             place_bomb_button: widgets::QuantityButton::new(0.425, 0.4, 2, 6),
             detonate_bombs_button: widgets::QuantityButton::new(0.325, 0.4, 4, 6),
+            use_potion_button: widgets::QuantityButton::new(0.225, 0.4, 4, 10),
             showing_purchase_buttons: false,
             bool_item_purchase_buttons: vec![widgets::PurchaseButton::new(
                 -0.32,
@@ -39,16 +68,55 @@ impl Ui {
                 0,
                 items::BoolItem::RunningShoes
             )],
-            quantitative_item_purchase_buttons: vec![widgets::PurchaseButton::new(
-                -0.24,
-                0.4,
-                6,
-                2,
-                items::QuantitativeItem::Bomb
-            )]
+            quantitative_item_purchase_buttons: vec![
+                widgets::PurchaseButton::new(-0.24, 0.4, 6, 2, items::QuantitativeItem::Bomb),
+                widgets::PurchaseButton::new(-0.16, 0.4, 6, 10, items::QuantitativeItem::Potion),
+            ],
+            stats: None,
+            showing_expression_picker: false,
+            expression_buttons: vec![
+                (FacialExpression::Neutral, widgets::SimpleButton::new(-0.12, 0.25, 4, 8)),
+                (FacialExpression::Angry, widgets::SimpleButton::new(-0.04, 0.25, 6, 8)),
+                (FacialExpression::Shocked, widgets::SimpleButton::new(0.04, 0.25, 0, 10)),
+                (FacialExpression::Skeptical, widgets::SimpleButton::new(0.12, 0.25, 2, 10)),
+            ],
+            showing_emote_picker: false,
+            emote_buttons: vec![
+                (messages::EmoteKind::Wave, widgets::SimpleButton::new(0.3, 0.17, 0, 12)),
+                (messages::EmoteKind::Dance, widgets::SimpleButton::new(0.38, 0.17, 2, 12)),
+                (messages::EmoteKind::Cheer, widgets::SimpleButton::new(0.3, 0.25, 4, 12)),
+                (messages::EmoteKind::Sad, widgets::SimpleButton::new(0.38, 0.25, 6, 12)),
+            ],
+            chat_channel_button: {
+                let (icon_x, icon_y) = chat_channel_icon(messages::ChatChannel::default());
+                widgets::SimpleButton::new(0.135, 0.4, icon_x, icon_y)
+            },
+            chat_channel: messages::ChatChannel::default(),
+            health_bar: widgets::ProgressBar::new(-0.425, -0.25, 0.1, 0.02)
         }
     }
 
+    /// Show the facial expression picker should it be hidden, or hide it otherwise.
+    pub fn toggle_expression_picker(&mut self) {
+        self.showing_expression_picker = !self.showing_expression_picker;
+    }
+
+    /// Show the emote picker should it be hidden, or hide it otherwise.
+    pub fn toggle_emote_picker(&mut self) {
+        self.showing_emote_picker = !self.showing_emote_picker;
+    }
+
+    /// The channel on which chat messages typed by the player are to be sent.
+    pub fn chat_channel(&self) -> messages::ChatChannel {
+        self.chat_channel
+    }
+
+    /// Set the player statistics displayed alongside the item purchase buttons (see
+    /// [`messages::FromServer::YourStats`]).
+    pub fn set_stats(&mut self, stats: PlayerStats) {
+        self.stats = Some(stats);
+    }
+
     pub fn update_and_draw(
         &mut self, player: &mut MyEntity, map: &mut ClientMap, map_renderer: &mut MapRenderer,
         connection: &mut networking::Connection, assets: &AssetManager
@@ -59,9 +127,24 @@ impl Ui {
         // Set detonate bomb button quantity meter based on how many bombs the player has placed in the world:
         self.detonate_bombs_button.quantity = player.how_many_bombs_placed() as u32;
 
+        self.use_potion_button.quantity = player.get_inventory().has_how_many(items::QuantitativeItem::Potion);
+
+        // Grey out the buttons of actions that are cooling down:
+        self.place_bomb_button.set_cooldown_remaining(player.cooldown_remaining(ActionKind::PlaceBomb));
+        self.use_potion_button.set_cooldown_remaining(player.cooldown_remaining(ActionKind::UsePotion));
+
+        for (_, btn) in &mut self.emote_buttons {
+            btn.set_cooldown_remaining(player.cooldown_remaining(ActionKind::Emote));
+        }
+
         if self.show_purchase_buttons_button.update(self.large_button_size) {
             // Toggle visibility of item purchase buttons:
             self.showing_purchase_buttons = !self.showing_purchase_buttons;
+
+            // Request up-to-date statistics to display alongside the purchase buttons:
+            if self.showing_purchase_buttons {
+                connection.send(&messages::ToServer::RequestStats)?;
+            }
         }
 
         // Perform actions (e.g. placement of bombs, purchase, of items, etc.) based on button presses:
@@ -74,31 +157,74 @@ impl Ui {
             player.detonate_bombs(map, map_renderer, connection)?;
         }
 
+        if self.use_potion_button.update(self.large_button_size) {
+            player.use_item(items::QuantitativeItem::Potion, connection)?;
+        }
+
+        if self.chat_channel_button.update(self.small_button_size) {
+            self.chat_channel = self.chat_channel.next();
+
+            let (icon_x, icon_y) = chat_channel_icon(self.chat_channel);
+            self.chat_channel_button.set_icon(icon_x, icon_y);
+        }
+
         if self.showing_purchase_buttons {
             for btn in &mut self.bool_item_purchase_buttons {
+                btn.update_availability(player.get_contained_entity());
+
                 if btn.update(self.small_button_size) {
                     player.purchase_bool_item(btn.purchase_item, connection)?;
                 }
             }
 
             for btn in &mut self.quantitative_item_purchase_buttons {
+                btn.update_availability(player.get_contained_entity());
+
                 if btn.update(self.small_button_size) {
                     player.purchase_quantitative_item(btn.purchase_item, 1, connection)?;
                 }
             }
         }
 
+        if self.showing_expression_picker {
+            for (expression, btn) in &mut self.expression_buttons {
+                if btn.update(self.small_button_size) {
+                    player.set_facial_expression(*expression, connection)?;
+                    self.showing_expression_picker = false;
+                }
+            }
+        }
+
+        if self.showing_emote_picker {
+            for (emote, btn) in &mut self.emote_buttons {
+                if btn.update(self.small_button_size) {
+                    player.emote(*emote, connection)?;
+                    self.showing_emote_picker = false;
+                }
+            }
+        }
+
         quad::set_default_camera();
 
         widgets::menus::draw_gem_collection_menu(-0.425, -0.38, 0.1, player.get_gem_collection(), assets);
+        widgets::menus::draw_inventory_weight_indicator(-0.47, -0.28, 0.02, player.get_inventory());
+
+        let entity = player.get_contained_entity();
+        self.update_and_draw_health_bar(entity.health, entity.max_health);
 
-        let large_buttons: &[&dyn Button] =
-            &[&self.show_purchase_buttons_button, &self.place_bomb_button, &self.detonate_bombs_button];
+        let large_buttons: &[&dyn Button] = &[
+            &self.show_purchase_buttons_button,
+            &self.place_bomb_button,
+            &self.detonate_bombs_button,
+            &self.use_potion_button
+        ];
 
         for large_btn in large_buttons {
             large_btn.draw(assets, self.large_button_size);
         }
 
+        self.chat_channel_button.draw(assets, self.small_button_size);
+
         if self.showing_purchase_buttons {
             let bool_item_buttons = self.bool_item_purchase_buttons.iter().map(|x| x as &dyn Button);
             let quantitative_item_buttons = self.quantitative_item_purchase_buttons.iter().map(|x| x as &dyn Button);
@@ -106,30 +232,122 @@ impl Ui {
             for small_btn in bool_item_buttons.chain(quantitative_item_buttons) {
                 small_btn.draw(assets, self.small_button_size);
             }
+
+            if let Some(stats) = &self.stats {
+                widgets::menus::draw_stats_panel(-0.11, 0.45, 0.022, stats);
+            }
+        }
+
+        if self.showing_expression_picker {
+            for (_, btn) in &self.expression_buttons {
+                btn.draw(assets, self.small_button_size);
+            }
+        }
+
+        if self.showing_emote_picker {
+            for (_, btn) in &self.emote_buttons {
+                btn.draw(assets, self.small_button_size);
+            }
         }
 
         Ok(())
     }
+
+    /// Draws the player's health bar beneath the gem collection menu.
+    pub fn update_and_draw_health_bar(&self, health: u32, max_health: u32) {
+        let fraction = if max_health > 0 { health as f32 / max_health as f32 } else { 0.0 };
+
+        self.health_bar.draw(fraction, health_bar_colour(fraction));
+    }
+}
+
+/// Health bar is red when health is below 25%, yellow when below 50%, and green otherwise.
+/// The coordinates on the UI texture of the icon representing the given chat channel.
+fn chat_channel_icon(channel: messages::ChatChannel) -> (u16, u16) {
+    match channel {
+        messages::ChatChannel::Local => (0, 14),
+        messages::ChatChannel::Global => (2, 14)
+    }
+}
+
+fn health_bar_colour(health_fraction: f32) -> quad::Color {
+    if health_fraction < 0.25 {
+        quad::RED
+    }
+    else if health_fraction < 0.5 {
+        quad::YELLOW
+    }
+    else {
+        quad::GREEN
+    }
+}
+
+/// Draws a panel in the top-left corner of the screen displaying information about an entity the player has
+/// clicked on.
+pub fn draw_entity_inspect_panel(entity_id: Id, entity: &Entity) {
+    quad::set_default_camera();
+
+    let font_size = quad::screen_height() * 0.03;
+
+    let lines = [
+        if entity.name.is_empty() { format!("Player {}", entity_id) } else { entity.name.clone() },
+        format!("Position: ({}, {})", entity.pos.x, entity.pos.y),
+        format!("Gems: {}", entity.gem_collection),
+        "Right-click to close".to_string()
+    ];
+
+    let (panel_x, panel_y) = (quad::screen_width() * 0.15, font_size * 0.5);
+    let panel_width =
+        lines.iter().map(|line| quad::measure_text(line, None, font_size as u16, 1.0).width).fold(0.0, f32::max);
+
+    quad::draw_rectangle(
+        panel_x,
+        panel_y,
+        panel_width + font_size,
+        font_size * (lines.len() as f32 + 0.5),
+        quad::Color::new(0.0, 0.0, 0.0, 0.6)
+    );
+
+    for (i, line) in lines.iter().enumerate() {
+        quad::draw_text(
+            line,
+            panel_x + font_size * 0.5,
+            panel_y + font_size * (i as f32 + 1.0),
+            font_size,
+            quad::WHITE
+        );
+    }
 }
 
 /// Draws debug information to the screen.
 #[cfg(debug_assertions)]
 pub fn draw_debug_text(
-    font_size: f32, font_colour: quad::Color, assets: &AssetManager, my_entity: &Entity,
-    loaded_chunk_coords: impl Iterator<Item = ChunkCoords>
+    font_size: f32, font_colour: quad::Color, assets: &AssetManager, my_entity: &Entity, chunk_grid: &str,
+    (cursor_tile_coords, cursor_tile): (TileCoords, TileQuery),
+    (connection_stats, average_rtt_ms): (networking::ConnectionStats, Option<f64>)
 ) {
     quad::set_default_camera();
 
-    let mut loaded_chunks_string = String::new();
-    for coords in loaded_chunk_coords {
-        loaded_chunks_string += &format!("({}, {}) ", coords.x, coords.y);
-    }
+    let cursor_chunk_coords = cursor_tile_coords.as_chunk_coords();
+
+    let cursor_tile_description = match cursor_tile {
+        TileQuery::Loaded(tile) => {
+            format!("{:?} ({})", tile, if tile.is_blocking() { "blocking" } else { "not blocking" })
+        }
+        TileQuery::ChunkNotLoaded => "not loaded".to_string(),
+        TileQuery::OutOfBounds => "out of bounds".to_string()
+    };
 
-    let msgs = &[
+    let mut msgs = vec![
         format!("Version: {}", shared::VERSION),
         format!("Frames: {}/sec", quad::get_fps()),
         format!("Delta: {:.2}ms", quad::get_frame_time() * 1000.0),
         format!("Textures loaded: {}", assets.count_loaded_textures()),
+        format!("Connection: {}", connection_stats),
+        match average_rtt_ms {
+            Some(avg_rtt) => format!("Latency: {:.1}ms", avg_rtt),
+            None => "Latency: unknown".to_string()
+        },
         format!(
             "Player entity position: {}, {}, {}",
             my_entity.pos,
@@ -137,10 +355,40 @@ pub fn draw_debug_text(
             my_entity.pos.as_chunk_offset_coords()
         ),
         format!("Player entity direction: {:?}", my_entity.direction),
-        format!("Loaded chunks: {}", loaded_chunks_string)
+        format!(
+            "Cursor tile: ({}, {}) chunk ({}, {})",
+            cursor_tile_coords.x, cursor_tile_coords.y, cursor_chunk_coords.x, cursor_chunk_coords.y
+        ),
+        format!("Cursor tile type: {}", cursor_tile_description),
+        "Loaded chunks:".to_string(),
     ];
+    msgs.extend(chunk_grid.lines().map(String::from));
 
     for (i, msg) in msgs.iter().rev().enumerate() {
         quad::draw_text(msg, 0.0, quad::screen_height() - ((i as f32 + 1.5) * font_size), font_size, font_colour);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn health_bar_colour_thresholds() {
+        assert_eq!(health_bar_colour(0.0), quad::RED);
+        assert_eq!(health_bar_colour(0.24), quad::RED);
+        assert_eq!(health_bar_colour(0.25), quad::YELLOW);
+        assert_eq!(health_bar_colour(0.49), quad::YELLOW);
+        assert_eq!(health_bar_colour(0.5), quad::GREEN);
+        assert_eq!(health_bar_colour(1.0), quad::GREEN);
+    }
+
+    #[test]
+    fn chat_channels_have_distinct_icons() {
+        let local = chat_channel_icon(messages::ChatChannel::Local);
+        let global = chat_channel_icon(messages::ChatChannel::Global);
+
+        assert_ne!(local, global);
+        assert_eq!(chat_channel_icon(messages::ChatChannel::Local.next()), global);
+    }
+}
@@ -0,0 +1,26 @@
+use macroquad::prelude as quad;
+
+const BACKGROUND_COLOUR: quad::Color = quad::Color { r: 0.0, g: 0.0, b: 0.0, a: 0.5 };
+
+/// Horizontal bar that is filled from left to right to indicate progress/quantity (e.g. the player's health).
+pub struct ProgressBar {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32
+}
+
+impl ProgressBar {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        ProgressBar { x, y, width, height }
+    }
+
+    /// Draw the bar with the given fraction (clamped between 0 and 1) of it filled with the specified colour.
+    pub fn draw(&self, fraction: f32, colour: quad::Color) {
+        let (draw_width, draw_height) = super::calculate_draw_size(self.width, self.height);
+        let (draw_x, draw_y) = super::calculate_draw_position(self.x, self.y, draw_width, draw_height);
+
+        quad::draw_rectangle(draw_x, draw_y, draw_width, draw_height, BACKGROUND_COLOUR);
+        quad::draw_rectangle(draw_x, draw_y, draw_width * fraction.clamp(0.0, 1.0), draw_height, colour);
+    }
+}
@@ -1,5 +1,9 @@
 use macroquad::prelude as quad;
-use shared::items::Item;
+use shared::{
+    gems::{self, Gem},
+    items::Item,
+    maps::entities::Entity
+};
 
 use super::UI_TEXTURE_TILE_SIZE;
 use crate::{AssetManager, TextureKey};
@@ -14,6 +18,12 @@ const QUANTITY_BARS_TEXTURE_COORDS: (u16, u16) = (0, 6);
 
 const INTERACT_SIZE_MULTIPLIER: f32 = 0.6;
 
+/// Colour with which disabled buttons are drawn (regardless of whether or not they are being hovered over).
+const DISABLED_COLOUR: quad::Color = quad::GRAY;
+
+/// Colour of the overlay drawn over buttons whose action is cooling down.
+const COOLDOWN_OVERLAY_COLOUR: quad::Color = quad::Color::new(0.0, 0.0, 0.0, 0.5);
+
 pub trait Button {
     /// Determines whether the button is being hovered over and/or pressed based on mouse position & whether or not the
     /// left mouse button is down. Returns true once when the button is clicked on.
@@ -27,6 +37,11 @@ pub trait Button {
 pub struct SimpleButton {
     is_hover: bool,
     is_down: bool,
+    /// Disabled buttons cannot be pressed.
+    disabled: bool,
+    /// Time (in seconds) remaining before the button's action may be performed again. The button cannot be pressed
+    /// and displays a countdown while this is above zero.
+    cooldown_remaining: f32,
     x: f32,
     y: f32,
     icon_texture_x: u16,
@@ -35,7 +50,31 @@ pub struct SimpleButton {
 
 impl SimpleButton {
     pub fn new(x: f32, y: f32, icon_texture_x: u16, icon_texture_y: u16) -> Self {
-        SimpleButton { is_hover: false, is_down: false, x, y, icon_texture_x, icon_texture_y }
+        SimpleButton {
+            is_hover: false,
+            is_down: false,
+            disabled: false,
+            cooldown_remaining: 0.0,
+            x,
+            y,
+            icon_texture_x,
+            icon_texture_y
+        }
+    }
+
+    /// Set the time (in seconds) remaining before the button's action may be performed again.
+    pub fn set_cooldown_remaining(&mut self, secs: f32) {
+        self.cooldown_remaining = secs;
+    }
+
+    /// Change the icon displayed on the button to that at the given coordinates of the UI texture.
+    pub fn set_icon(&mut self, icon_texture_x: u16, icon_texture_y: u16) {
+        self.icon_texture_x = icon_texture_x;
+        self.icon_texture_y = icon_texture_y;
+    }
+
+    fn is_unavailable(&self) -> bool {
+        self.disabled || self.cooldown_remaining > 0.0
     }
 }
 
@@ -51,7 +90,7 @@ impl Button for SimpleButton {
         let was_down = self.is_down;
 
         self.is_hover = rect.contains(quad::vec2(mouse_x, mouse_y));
-        self.is_down = self.is_hover && quad::is_mouse_button_down(quad::MouseButton::Left);
+        self.is_down = !self.is_unavailable() && self.is_hover && quad::is_mouse_button_down(quad::MouseButton::Left);
 
         !was_down && self.is_down
     }
@@ -67,16 +106,18 @@ impl Button for SimpleButton {
 
         let (draw_x, draw_y) = super::calculate_draw_position(self.x, self.y, draw_size, draw_size);
 
+        let colour = if self.is_unavailable() { DISABLED_COLOUR } else { quad::WHITE };
+
         quad::draw_texture_ex(
             assets.texture(TextureKey::Ui),
             draw_x,
             draw_y,
-            quad::WHITE,
+            colour,
             quad::DrawTextureParams {
                 dest_size,
                 source: Some(crate::make_texture_source_rect(
                     UI_TEXTURE_TILE_SIZE,
-                    match (self.is_hover, self.is_down) {
+                    match (self.is_hover && !self.is_unavailable(), self.is_down) {
                         (true, false) => BUTTON_HOVER_TEXTURE_COORDS,
                         (_, true) => BUTTON_DOWN_TEXTURE_COORDS,
                         _ => BUTTON_UP_TEXTURE_COORDS
@@ -91,7 +132,7 @@ impl Button for SimpleButton {
             assets.texture(TextureKey::Ui),
             draw_x,
             draw_y,
-            quad::WHITE,
+            colour,
             quad::DrawTextureParams {
                 dest_size,
                 source: Some(crate::make_texture_source_rect(
@@ -103,10 +144,33 @@ impl Button for SimpleButton {
             }
         );
 
+        // Darken the button and display the number of seconds until its action may be performed again:
+        if self.cooldown_remaining > 0.0 {
+            quad::draw_rectangle(draw_x, draw_y, draw_size, draw_size, COOLDOWN_OVERLAY_COLOUR);
+
+            let label = cooldown_label(self.cooldown_remaining);
+            let font_size = draw_size * 0.5;
+            let text_width = quad::measure_text(&label, None, font_size as u16, 1.0).width;
+
+            quad::draw_text(
+                &label,
+                draw_x + ((draw_size - text_width) / 2.0),
+                draw_y + ((draw_size + (font_size / 2.0)) / 2.0),
+                font_size,
+                quad::WHITE
+            );
+        }
+
         ((draw_x, draw_y), draw_size)
     }
 }
 
+/// The countdown displayed over a button whose action may be performed again after the given number of seconds, which
+/// is rounded up to a whole number of seconds.
+fn cooldown_label(secs: f32) -> String {
+    (secs.ceil() as u32).to_string()
+}
+
 pub struct QuantityButton {
     button: SimpleButton,
     pub quantity: u32
@@ -116,6 +180,11 @@ impl QuantityButton {
     pub fn new(x: f32, y: f32, icon_texture_x: u16, icon_texture_y: u16) -> Self {
         QuantityButton { button: SimpleButton::new(x, y, icon_texture_x, icon_texture_y), quantity: 0 }
     }
+
+    /// Set the time (in seconds) remaining before the button's action may be performed again.
+    pub fn set_cooldown_remaining(&mut self, secs: f32) {
+        self.button.set_cooldown_remaining(secs);
+    }
 }
 
 impl Button for QuantityButton {
@@ -155,22 +224,146 @@ impl Button for QuantityButton {
 
 pub struct PurchaseButton<T> {
     button: SimpleButton,
-    pub purchase_item: T
+    pub purchase_item: T,
+    /// The gems required to purchase the item.
+    cost: gems::Collection,
+    /// Whether there was no room for the item in the player's inventory when last checked.
+    inventory_full: bool
 }
 
-impl<T> PurchaseButton<T> {
+impl<T: Item> PurchaseButton<T> {
     pub fn new(x: f32, y: f32, icon_texture_x: u16, icon_texture_y: u16, purchase_item: T) -> Self {
-        PurchaseButton { button: SimpleButton::new(x, y, icon_texture_x, icon_texture_y), purchase_item }
+        let (gem, quantity) = purchase_item.get_price();
+
+        let mut cost = gems::Collection::default();
+        cost.increase_quantity(gem, quantity);
+
+        PurchaseButton {
+            button: SimpleButton::new(x, y, icon_texture_x, icon_texture_y),
+            purchase_item,
+            cost,
+            inventory_full: false
+        }
+    }
+
+    /// Whether or not the given player entity has enough gems to make this purchase.
+    pub fn is_affordable(&self, player: &Entity) -> bool {
+        Gem::all().all(|gem| player.gem_collection.get_quantity(gem) >= self.cost.get_quantity(gem))
+    }
+
+    /// Disable this button should the given player entity be unable to afford the purchase or have no room for the
+    /// item in their inventory.
+    pub fn update_availability(&mut self, player: &Entity) {
+        self.inventory_full = !self.purchase_item.fits_in(&player.item_inventory);
+        self.button.disabled = self.inventory_full || !self.is_affordable(player);
+    }
+
+    /// Text describing the cost of the purchase (e.g. "Costs 5 x Ruby").
+    fn cost_description(&self) -> String {
+        let gem_costs: Vec<String> = Gem::all()
+            .filter(|gem| self.cost.get_quantity(*gem) > 0)
+            .map(|gem| format!("{} x {:?}", self.cost.get_quantity(gem), gem))
+            .collect();
+
+        format!("Costs {}", gem_costs.join(", "))
+    }
+
+    /// Text explaining why the purchase cannot currently be made.
+    fn disabled_description(&self) -> String {
+        if self.inventory_full {
+            "Inventory full".to_string()
+        }
+        else {
+            self.cost_description()
+        }
     }
 }
 
 impl<T: Item> Button for PurchaseButton<T> {
+    /// Always returns false when the button is disabled due to the purchase being unaffordable or the player's
+    /// inventory being full.
     fn update(&mut self, size: f32) -> bool {
         self.button.update(size)
     }
 
     fn draw(&self, assets: &AssetManager, size: f32) -> ((f32, f32), f32) {
-        self.button.draw(assets, size)
-        // TODO: Draw some sort of indicator of item cost.
+        let ((draw_x, draw_y), draw_size) = self.button.draw(assets, size);
+
+        // Show a tooltip explaining why the purchase cannot be made when hovering over a disabled purchase button:
+        if self.button.disabled && self.button.is_hover {
+            quad::draw_text(&self.disabled_description(), draw_x, draw_y, draw_size * 0.3, quad::WHITE);
+        }
+
+        ((draw_x, draw_y), draw_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::{items, maps::entities::EntityBuilder};
+
+    use super::*;
+
+    #[test]
+    fn cooldown_labels_round_up() {
+        assert_eq!(cooldown_label(2.0), "2");
+        assert_eq!(cooldown_label(1.2), "2");
+        assert_eq!(cooldown_label(0.05), "1");
+    }
+
+    #[test]
+    fn purchase_affordability() {
+        let btn = PurchaseButton::new(0.0, 0.0, 0, 0, items::QuantitativeItem::Bomb);
+        let (gem, price) = items::QuantitativeItem::Bomb.get_price();
+
+        assert!(!btn.is_affordable(&Entity::default()));
+
+        let mut gem_collection = gems::Collection::default();
+        gem_collection.increase_quantity(gem, price - 1);
+        assert!(!btn.is_affordable(&EntityBuilder::new().gem_collection(gem_collection.clone()).build()));
+
+        gem_collection.increase_quantity(gem, 1);
+        assert!(btn.is_affordable(&EntityBuilder::new().gem_collection(gem_collection.clone()).build()));
+
+        gem_collection.increase_quantity(gem, 10);
+        assert!(btn.is_affordable(&EntityBuilder::new().gem_collection(gem_collection).build()));
+    }
+
+    #[test]
+    fn purchase_disabled_when_unaffordable() {
+        let mut btn = PurchaseButton::new(0.0, 0.0, 0, 0, items::BoolItem::RunningShoes);
+        let (gem, price) = items::BoolItem::RunningShoes.get_price();
+
+        btn.update_availability(&Entity::default());
+        assert!(btn.button.disabled);
+
+        let mut gem_collection = gems::Collection::default();
+        gem_collection.increase_quantity(gem, price);
+        btn.update_availability(&EntityBuilder::new().gem_collection(gem_collection).build());
+        assert!(!btn.button.disabled);
+
+        assert_eq!(btn.cost_description(), format!("Costs {} x {:?}", price, gem));
+    }
+
+    #[test]
+    fn purchase_disabled_when_inventory_full() {
+        let mut btn = PurchaseButton::new(0.0, 0.0, 0, 0, items::QuantitativeItem::Bomb);
+        let (gem, price) = items::QuantitativeItem::Bomb.get_price();
+
+        let mut gem_collection = gems::Collection::default();
+        gem_collection.increase_quantity(gem, price);
+
+        let mut inventory = items::Inventory::with_max_slots(2);
+        inventory.give_quantity(items::QuantitativeItem::Bomb, 1);
+        let player =
+            |inventory| EntityBuilder::new().gem_collection(gem_collection.clone()).item_inventory(inventory).build();
+
+        btn.update_availability(&player(inventory.clone()));
+        assert!(!btn.button.disabled);
+
+        inventory.give_quantity(items::QuantitativeItem::Bomb, 1);
+        btn.update_availability(&player(inventory));
+        assert!(btn.button.disabled);
+        assert_eq!(btn.disabled_description(), "Inventory full");
     }
 }
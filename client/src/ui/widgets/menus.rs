@@ -1,14 +1,41 @@
+use std::collections::VecDeque;
+
 use macroquad::prelude as quad;
-use shared::gems::{self, Gem};
+use shared::{
+    gems::{self, Gem},
+    items,
+    maps::{ChunkCoords, VIEW_RADIUS},
+    mechanics,
+    messages::{ChatChannel, LeaderboardEntry},
+    quests,
+    stats::PlayerStats,
+    Id
+};
 
 use crate::{AssetManager, TextureKey};
 
 const GEM_COLLECTION_TEXTURE_SOURCE: quad::Rect =
-    crate::make_texture_source_rect(super::UI_TEXTURE_TILE_SIZE, (0, 3), (2, 3));
+    crate::make_texture_source_rect(super::UI_TEXTURE_TILE_SIZE, (6, 4), (2, 4));
+
+/// Position of the baseline of the text for the first row of the gem collection menu (relative to menu height).
+const GEM_COLLECTION_FIRST_ROW_OFFSET: f32 = 0.21;
+/// Distance between the rows of the gem collection menu (relative to menu height).
+const GEM_COLLECTION_ROW_SPACING: f32 = 0.1875;
+
+/// Width & height (in pixels) of the minimap.
+const MINIMAP_SIZE: f32 = 120.0;
+/// Gap (in pixels) between the squares representing chunks on the minimap.
+const MINIMAP_CHUNK_GAP: f32 = 2.0;
+const MINIMAP_BACKGROUND_COLOUR: quad::Color = quad::Color::new(0.0, 0.0, 0.0, 0.4);
+const MINIMAP_LOADED_COLOUR: quad::Color = quad::Color::new(0.3, 0.7, 0.3, 0.8);
+const MINIMAP_REQUESTED_COLOUR: quad::Color = quad::Color::new(0.7, 0.6, 0.2, 0.5);
+
+/// The maximum number of characters on each line of text displayed on the sign text modal.
+const SIGN_TEXT_LINE_LENGTH: usize = 40;
 
 pub fn draw_gem_collection_menu(x: f32, y: f32, width: f32, gem_collection: &gems::Collection, assets: &AssetManager) {
     let draw_width = quad::screen_width() * width;
-    let draw_height = draw_width * 1.5;
+    let draw_height = draw_width * 2.0;
 
     let (draw_x, draw_y) = super::calculate_draw_position(x, y, draw_width, draw_height);
 
@@ -24,15 +51,478 @@ pub fn draw_gem_collection_menu(x: f32, y: f32, width: f32, gem_collection: &gem
         }
     );
 
-    for (gem, offset) in &[(Gem::Emerald, -0.25), (Gem::Ruby, 0.0), (Gem::Diamond, 0.25)] {
+    for (row, gem) in Gem::all().enumerate() {
         quad::draw_text(
-            &format!("{:2}", gem_collection.get_quantity(*gem)),
+            &format!("{:2}", gem_collection.get_quantity(gem)),
             draw_x + (draw_width * 0.6),
-            draw_y + (draw_height * (0.53 + offset)),
+            draw_y + (draw_height * gem_collection_row_offset(row)),
             draw_width * 0.2,
             quad::GRAY
         );
     }
 }
 
-// pub fn draw_leaderboard_menu
+/// Vertical position of the text for the given row of the gem collection menu relative to the menu's height.
+fn gem_collection_row_offset(row: usize) -> f32 {
+    GEM_COLLECTION_FIRST_ROW_OFFSET + (row as f32 * GEM_COLLECTION_ROW_SPACING)
+}
+
+/// Draws text showing the total weight of the player's inventory along with the resulting movement speed.
+pub fn draw_inventory_weight_indicator(x: f32, y: f32, font_size: f32, inventory: &items::Inventory) {
+    let (draw_x, draw_y) = super::calculate_draw_position(x, y, 0.0, 0.0);
+
+    quad::draw_text(
+        &format!(
+            "Weight: {} ({:.0}% speed)",
+            inventory.total_weight(),
+            mechanics::movement_speed_multiplier(inventory) * 100.0
+        ),
+        draw_x,
+        draw_y,
+        quad::screen_width() * font_size,
+        quad::GRAY
+    );
+}
+
+/// Draws the most recent `visible_lines` chat messages (sender name & text pairs) from the given log, oldest first,
+/// with the bottom line at the specified position. Should the player be typing a chat message, the text typed so far
+/// is drawn beneath the messages along with the channel that the message will be sent on.
+pub fn draw_chat_overlay(
+    x: f32, y: f32, font_size: f32, chat_log: &VecDeque<(String, String)>, visible_lines: usize,
+    typing_option: Option<(&str, ChatChannel)>
+) {
+    let (draw_x, draw_y) = super::calculate_draw_position(x, y, 0.0, 0.0);
+    let font_size = quad::screen_height() * font_size;
+
+    let typing_line = typing_option.map(|(typed, channel)| chat_typing_line(typed, channel));
+    let message_lines = chat_log
+        .iter()
+        .skip(chat_log.len().saturating_sub(visible_lines))
+        .map(|(sender_name, text)| format!("{}: {}", sender_name, text));
+
+    let lines: Vec<String> = message_lines.chain(typing_line).collect();
+
+    for (i, line) in lines.iter().rev().enumerate() {
+        quad::draw_text(line, draw_x, draw_y - (font_size * i as f32), font_size, quad::WHITE);
+    }
+}
+
+fn chat_typing_line(typed: &str, channel: ChatChannel) -> String {
+    format!("[{}] > {}_", channel, typed)
+}
+
+/// Draws server announcements (text & opacity pairs) centred horizontally, one beneath the other with the first at the
+/// specified vertical position. Announcements are drawn in a larger font than chat messages over a dark background so
+/// as to stand out.
+pub fn draw_announcement_overlay<'a>(y: f32, font_size: f32, announcements: impl Iterator<Item = (&'a str, f32)>) {
+    let (_, draw_y) = super::calculate_draw_position(0.0, y, 0.0, 0.0);
+    let font_size = quad::screen_height() * font_size;
+    let padding = font_size / 4.0;
+
+    for (i, (text, alpha)) in announcements.enumerate() {
+        let text_width = quad::measure_text(text, None, font_size as u16, 1.0).width;
+        let text_x = (quad::screen_width() - text_width) / 2.0;
+        let text_y = draw_y + ((font_size + (padding * 2.0)) * i as f32);
+
+        quad::draw_rectangle(
+            text_x - padding,
+            text_y - font_size,
+            text_width + (padding * 2.0),
+            font_size + (padding * 2.0),
+            quad::Color::new(0.0, 0.0, 0.0, 0.6 * alpha)
+        );
+        quad::draw_text(text, text_x, text_y, font_size, quad::Color::new(1.0, 0.85, 0.2, alpha));
+    }
+}
+
+/// Draws a notification of a newly unlocked achievement with the given title centred horizontally at the top of the
+/// screen. The notification slides down into view as `visibility` increases from 0 (just above the top of the screen)
+/// to 1 (fully visible).
+pub fn draw_achievement_toast(font_size: f32, title: &str, visibility: f32) {
+    let font_size = quad::screen_height() * font_size;
+    let padding = font_size / 2.0;
+
+    let heading = "Achievement unlocked!";
+    let text_width = [heading, title]
+        .iter()
+        .map(|line| quad::measure_text(line, None, font_size as u16, 1.0).width)
+        .fold(0.0, f32::max);
+    let (width, height) = (text_width + (padding * 2.0), (font_size * 2.0) + (padding * 2.0));
+
+    let draw_x = (quad::screen_width() - width) / 2.0;
+    let draw_y = -height + ((height + padding) * visibility);
+
+    quad::draw_rectangle(draw_x, draw_y, width, height, quad::Color::new(0.1, 0.1, 0.1, 0.85));
+    quad::draw_rectangle_lines(draw_x, draw_y, width, height, padding / 2.0, quad::Color::new(1.0, 0.85, 0.2, 1.0));
+
+    quad::draw_text(
+        heading,
+        draw_x + padding,
+        draw_y + padding + (font_size * 0.8),
+        font_size,
+        quad::Color::new(1.0, 0.85, 0.2, 1.0)
+    );
+    quad::draw_text(title, draw_x + padding, draw_y + padding + (font_size * 1.8), font_size, quad::WHITE);
+}
+
+/// Draws a minimap centred at the given position showing the chunks within [`VIEW_RADIUS`] of the player entity's
+/// chunk. Loaded chunks and those that are expected to be provided by the server but are not yet loaded are drawn as
+/// squares of different colours with the player's position marked by a dot at the centre.
+pub fn draw_minimap(
+    x: f32, y: f32, loaded_chunks: impl Iterator<Item = ChunkCoords>,
+    requested_chunks: impl Iterator<Item = ChunkCoords>, player_chunk: ChunkCoords
+) {
+    let (draw_x, draw_y) = super::calculate_draw_position(x, y, MINIMAP_SIZE, MINIMAP_SIZE);
+    let chunk_draw_size = MINIMAP_SIZE / ((VIEW_RADIUS * 2) + 1) as f32;
+
+    quad::draw_rectangle(draw_x, draw_y, MINIMAP_SIZE, MINIMAP_SIZE, MINIMAP_BACKGROUND_COLOUR);
+
+    let loaded = loaded_chunks.map(|coords| (coords, MINIMAP_LOADED_COLOUR));
+    let requested = requested_chunks.map(|coords| (coords, MINIMAP_REQUESTED_COLOUR));
+
+    for (coords, colour) in loaded.chain(requested) {
+        if let Some((column, row)) = minimap_cell(coords, player_chunk) {
+            quad::draw_rectangle(
+                draw_x + (column as f32 * chunk_draw_size) + (MINIMAP_CHUNK_GAP / 2.0),
+                draw_y + (row as f32 * chunk_draw_size) + (MINIMAP_CHUNK_GAP / 2.0),
+                chunk_draw_size - MINIMAP_CHUNK_GAP,
+                chunk_draw_size - MINIMAP_CHUNK_GAP,
+                colour
+            );
+        }
+    }
+
+    quad::draw_circle(draw_x + (MINIMAP_SIZE / 2.0), draw_y + (MINIMAP_SIZE / 2.0), chunk_draw_size / 6.0, quad::WHITE);
+}
+
+/// The column & row (from the top-left) of the minimap cell representing the chunk at the given coordinates, or `None`
+/// should that chunk be further than [`VIEW_RADIUS`] from the player entity's chunk.
+fn minimap_cell(coords: ChunkCoords, player_chunk: ChunkCoords) -> Option<(i32, i32)> {
+    let (x_offset, y_offset) = (coords.x - player_chunk.x, coords.y - player_chunk.y);
+
+    // Chunks with higher y coordinates are further up the screen:
+    (x_offset.abs() <= VIEW_RADIUS && y_offset.abs() <= VIEW_RADIUS)
+        .then(|| (x_offset + VIEW_RADIUS, VIEW_RADIUS - y_offset))
+}
+
+/// Draws a modal centred on the screen displaying the text of a sign the player interacted with, wrapped over multiple
+/// lines, along with instructions on how to close it.
+pub fn draw_sign_text(font_size: f32, text: &str) {
+    let font_size = quad::screen_height() * font_size;
+    let padding = font_size;
+
+    let mut lines = wrap_text(text, SIGN_TEXT_LINE_LENGTH);
+    lines.push(String::new());
+    lines.push("Press Escape or click to close".to_string());
+
+    let text_width =
+        lines.iter().map(|line| quad::measure_text(line, None, font_size as u16, 1.0).width).fold(0.0, f32::max);
+    let (width, height) = (text_width + (padding * 2.0), (font_size * lines.len() as f32) + (padding * 2.0));
+    let (draw_x, draw_y) = super::calculate_draw_position(0.0, 0.0, width, height);
+
+    quad::draw_rectangle(draw_x, draw_y, width, height, quad::Color::new(0.25, 0.17, 0.08, 0.9));
+    quad::draw_rectangle_lines(draw_x, draw_y, width, height, padding / 4.0, quad::Color::new(0.5, 0.35, 0.15, 1.0));
+
+    for (i, line) in lines.iter().enumerate() {
+        quad::draw_text(
+            line,
+            draw_x + padding,
+            draw_y + padding + (font_size * (i as f32 + 0.8)),
+            font_size,
+            quad::WHITE
+        );
+    }
+}
+
+/// Draws a dark overlay across the whole screen with text informing the player that their entity died, how many gems
+/// were dropped, and the number of seconds until they may move again. Everything is drawn with the given opacity.
+pub fn draw_death_screen(font_size: f32, gems_dropped: u32, respawn_countdown: u32, alpha: f32) {
+    let font_size = quad::screen_height() * font_size;
+
+    quad::draw_rectangle(
+        0.0,
+        0.0,
+        quad::screen_width(),
+        quad::screen_height(),
+        quad::Color::new(0.0, 0.0, 0.0, alpha * 0.85)
+    );
+
+    let lines = death_screen_lines(gems_dropped, respawn_countdown);
+    let first_line_y = (quad::screen_height() - (font_size * lines.len() as f32)) / 2.0;
+
+    for (i, line) in lines.iter().enumerate() {
+        // The heading is drawn larger and in red:
+        let (line_font_size, colour) = if i == 0 {
+            (font_size * 2.0, quad::Color::new(0.9, 0.15, 0.15, alpha))
+        }
+        else {
+            (font_size, quad::Color::new(1.0, 1.0, 1.0, alpha))
+        };
+
+        let line_width = quad::measure_text(line, None, line_font_size as u16, 1.0).width;
+        quad::draw_text(
+            line,
+            (quad::screen_width() - line_width) / 2.0,
+            first_line_y + (font_size * 1.5 * i as f32),
+            line_font_size,
+            colour
+        );
+    }
+}
+
+/// The lines of text displayed on the death screen (see [`draw_death_screen`]).
+fn death_screen_lines(gems_dropped: u32, respawn_countdown: u32) -> Vec<String> {
+    let mut lines = vec!["You died".to_string()];
+
+    if gems_dropped > 0 {
+        lines.push(format!("You dropped {} gems where you fell", gems_dropped));
+    }
+    lines.push(format!("Respawning in {}...", respawn_countdown));
+
+    lines
+}
+
+/// Draws the titles of the player's active quests along with the progress made towards each quest's objective, with
+/// the top-right corner of the tracker at the given position. Should the player have no active quests, a hint
+/// explaining how to accept one is drawn instead.
+pub fn draw_quest_tracker(x: f32, y: f32, font_size: f32, active_quests: &[quests::Quest], accept_key_name: &str) {
+    let (right_x, top_y) = super::calculate_draw_position(x, y, 0.0, 0.0);
+    let font_size = quad::screen_height() * font_size;
+    let padding = font_size / 2.0;
+
+    let lines = quest_tracker_lines(active_quests, accept_key_name);
+
+    let text_width =
+        lines.iter().map(|line| quad::measure_text(line, None, font_size as u16, 1.0).width).fold(0.0, f32::max);
+    let (width, height) = (text_width + (padding * 2.0), (font_size * lines.len() as f32) + (padding * 2.0));
+
+    quad::draw_rectangle(right_x - width, top_y, width, height, quad::Color::new(0.0, 0.0, 0.0, 0.4));
+
+    for (i, line) in lines.iter().enumerate() {
+        quad::draw_text(
+            line,
+            right_x - width + padding,
+            top_y + padding + (font_size * (i as f32 + 0.8)),
+            font_size,
+            quad::WHITE
+        );
+    }
+}
+
+/// The lines of text displayed by the quest tracker (see [`draw_quest_tracker`]).
+fn quest_tracker_lines(active_quests: &[quests::Quest], accept_key_name: &str) -> Vec<String> {
+    if active_quests.is_empty() {
+        return vec![format!("Press {} to accept a quest", accept_key_name)];
+    }
+
+    active_quests.iter().flat_map(|quest| vec![quest.title.clone(), format!("  {}", quest.objective)]).collect()
+}
+
+/// Draws the player's statistics with the bottom-left corner of the panel at the given position.
+pub fn draw_stats_panel(x: f32, y: f32, font_size: f32, stats: &PlayerStats) {
+    let (left_x, bottom_y) = super::calculate_draw_position(x, y, 0.0, 0.0);
+    let font_size = quad::screen_height() * font_size;
+    let padding = font_size / 2.0;
+
+    let lines = stats_panel_lines(stats);
+
+    let text_width =
+        lines.iter().map(|line| quad::measure_text(line, None, font_size as u16, 1.0).width).fold(0.0, f32::max);
+    let (width, height) = (text_width + (padding * 2.0), (font_size * lines.len() as f32) + (padding * 2.0));
+    let top_y = bottom_y - height;
+
+    quad::draw_rectangle(left_x, top_y, width, height, quad::Color::new(0.0, 0.0, 0.0, 0.4));
+
+    for (i, line) in lines.iter().enumerate() {
+        quad::draw_text(
+            line,
+            left_x + padding,
+            top_y + padding + (font_size * (i as f32 + 0.8)),
+            font_size,
+            quad::WHITE
+        );
+    }
+}
+
+/// The lines of text displayed by the statistics panel (see [`draw_stats_panel`]).
+fn stats_panel_lines(stats: &PlayerStats) -> Vec<String> {
+    vec![
+        format!("Tiles walked: {}", stats.tiles_walked),
+        format!("Gems collected: {}", stats.gems_collected_total),
+        format!("Bombs placed: {}", stats.bombs_placed),
+        format!("Bombs detonated: {}", stats.bombs_detonated),
+        format!("Chunks explored: {}", stats.chunks_explored),
+    ]
+}
+
+/// Split the given text into lines of at most `max_line_length` characters, breaking lines between words where
+/// possible. Words longer than a line are split across lines.
+fn wrap_text(text: &str, max_line_length: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let mut remaining: Vec<char> = word.chars().collect();
+
+        if !line.is_empty() && line.chars().count() + 1 + remaining.len() > max_line_length {
+            lines.push(std::mem::take(&mut line));
+        }
+
+        while remaining.len() > max_line_length {
+            lines.push(remaining.drain(..max_line_length).collect());
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.extend(remaining);
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Draws a table of the players with the most gems (ranked highest first) centred horizontally with its top at the
+/// given vertical position. The row of the player with the given entity ID (if present) is highlighted.
+pub fn draw_leaderboard_menu(y: f32, font_size: f32, entries: &[LeaderboardEntry], my_entity_id: Id) {
+    let (_, top_y) = super::calculate_draw_position(0.0, y, 0.0, 0.0);
+    let font_size = quad::screen_height() * font_size;
+    let padding = font_size;
+
+    let heading = ["Rank".to_string(), "Name".to_string(), "Gems".to_string()];
+    let rows: Vec<[String; 3]> = std::iter::once(heading).chain(leaderboard_rows(entries)).collect();
+
+    // Each column is as wide as its widest cell:
+    let column_widths: Vec<f32> = (0..3)
+        .map(|column| {
+            rows.iter()
+                .map(|row| quad::measure_text(&row[column], None, font_size as u16, 1.0).width)
+                .fold(0.0, f32::max)
+        })
+        .collect();
+
+    let width = column_widths.iter().sum::<f32>() + (padding * 4.0);
+    let height = (font_size * (rows.len() as f32 + 0.5)) + (padding * 2.0);
+    let left_x = (quad::screen_width() - width) / 2.0;
+
+    quad::draw_rectangle(left_x, top_y, width, height, quad::Color::new(0.0, 0.0, 0.0, 0.6));
+
+    for (i, row) in rows.iter().enumerate() {
+        // The heading row is followed by a gap of half a line:
+        let gap = if i > 0 { 0.5 } else { 0.0 };
+        let row_y = top_y + padding + (font_size * (i as f32 + 0.8 + gap));
+
+        let colour = match i.checked_sub(1).map(|index| entries[index].entity_id) {
+            None => quad::LIGHTGRAY,
+            Some(entity_id) if entity_id == my_entity_id => quad::YELLOW,
+            Some(_) => quad::WHITE
+        };
+
+        let mut cell_x = left_x + padding;
+
+        for (cell, column_width) in row.iter().zip(&column_widths) {
+            quad::draw_text(cell, cell_x, row_y, font_size, colour);
+            cell_x += column_width + padding;
+        }
+    }
+}
+
+/// The rank, name, and gem count cells of each row of the leaderboard (see [`draw_leaderboard_menu`]).
+fn leaderboard_rows(entries: &[LeaderboardEntry]) -> Vec<[String; 3]> {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| [format!("#{}", i + 1), entry.name.clone(), entry.gem_total.to_string()])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn death_screen_mentions_dropped_gems() {
+        assert_eq!(death_screen_lines(0, 3), vec!["You died", "Respawning in 3..."]);
+        assert_eq!(
+            death_screen_lines(5, 1),
+            vec!["You died", "You dropped 5 gems where you fell", "Respawning in 1..."]
+        );
+    }
+
+    #[test]
+    fn gem_collection_rows_within_menu() {
+        let offsets: Vec<f32> = (0..Gem::all().count()).map(gem_collection_row_offset).collect();
+
+        // Text for every gem should be drawn within the menu's border (which begins 56 pixels into the 64 pixel tall
+        // texture):
+        assert!(offsets.iter().all(|offset| *offset > 0.0 && *offset < 56.0 / 64.0));
+        assert!(offsets.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn minimap_cells_centred_on_player_chunk() {
+        let player_chunk = ChunkCoords { x: 4, y: -2 };
+
+        assert_eq!(minimap_cell(player_chunk, player_chunk), Some((VIEW_RADIUS, VIEW_RADIUS)));
+        assert_eq!(minimap_cell(ChunkCoords { x: 4 - VIEW_RADIUS, y: -2 + VIEW_RADIUS }, player_chunk), Some((0, 0)));
+        assert_eq!(
+            minimap_cell(ChunkCoords { x: 4 + VIEW_RADIUS, y: -2 - VIEW_RADIUS }, player_chunk),
+            Some((VIEW_RADIUS * 2, VIEW_RADIUS * 2))
+        );
+        assert_eq!(minimap_cell(ChunkCoords { x: 5 + VIEW_RADIUS, y: -2 }, player_chunk), None);
+    }
+
+    #[test]
+    fn quest_tracker_shows_progress() {
+        assert_eq!(quest_tracker_lines(&[], "Q"), vec!["Press Q to accept a quest"]);
+
+        let mut quest = quests::quest_by_id(1).unwrap();
+        quest.record(quests::QuestEvent::RockMined);
+        assert_eq!(quest_tracker_lines(&[quest], "Q"), vec!["Rock Breaker", "  mine rocks 1/20"]);
+    }
+
+    #[test]
+    fn chat_typing_line_shows_channel() {
+        assert_eq!(chat_typing_line("hi", ChatChannel::Local), "[Local] > hi_");
+        assert_eq!(chat_typing_line("", ChatChannel::Global), "[Global] > _");
+    }
+
+    #[test]
+    fn stats_panel_lists_every_statistic() {
+        let stats = PlayerStats { tiles_walked: 120, bombs_placed: 3, chunks_explored: 7, ..Default::default() };
+        let lines = stats_panel_lines(&stats);
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "Tiles walked: 120");
+        assert_eq!(lines[1], "Gems collected: 0");
+        assert_eq!(lines[4], "Chunks explored: 7");
+    }
+
+    #[test]
+    fn leaderboard_rows_ranked_in_order() {
+        let entries = vec![
+            LeaderboardEntry { entity_id: Id::new(2), name: "Player AAAAAA".into(), gem_total: 120 },
+            LeaderboardEntry { entity_id: Id::new(1), name: "Player BBBBBB".into(), gem_total: 45 },
+        ];
+
+        assert_eq!(
+            leaderboard_rows(&entries),
+            vec![
+                ["#1".to_string(), "Player AAAAAA".to_string(), "120".to_string()],
+                ["#2".to_string(), "Player BBBBBB".to_string(), "45".to_string()]
+            ]
+        );
+        assert!(leaderboard_rows(&[]).is_empty());
+    }
+
+    #[test]
+    fn sign_text_wrapped_between_words() {
+        assert_eq!(wrap_text("Welcome to  GemGame!", 10), vec!["Welcome to", "GemGame!"]);
+        assert_eq!(wrap_text("a bb ccc", 4), vec!["a bb", "ccc"]);
+        assert_eq!(wrap_text("Supercalifragilistic is long", 8), vec!["Supercal", "ifragili", "stic is", "long"]);
+        assert!(wrap_text("   ", 10).is_empty());
+    }
+}
@@ -1,6 +1,8 @@
+pub mod bars;
 pub mod buttons;
 pub mod menus;
 
+pub use bars::ProgressBar;
 pub use buttons::{Button, PurchaseButton, QuantityButton, SimpleButton};
 use macroquad::prelude as quad;
 
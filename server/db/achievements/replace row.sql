@@ -0,0 +1,4 @@
+INSERT INTO achievements (client_id, unlocked)
+VALUES ($1, $2)
+ON CONFLICT (client_id) DO UPDATE
+    SET unlocked = achievements.unlocked | $2
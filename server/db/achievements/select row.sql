@@ -0,0 +1 @@
+SELECT * FROM achievements WHERE client_id = $1
@@ -0,0 +1,32 @@
+UPDATE client_entities
+SET tile_x = data.tile_x, tile_y = data.tile_y,
+    direction = data.direction, facial_expression = data.facial_expression,
+    hair_style = data.hair_style, clothing_colour = data.clothing_colour,
+    skin_colour = data.skin_colour, hair_colour = data.hair_colour,
+    gem_collection = data.gem_collection, item_inventory = data.item_inventory,
+    bombs_placed_count = data.bombs_placed_count,
+    health = data.health, max_health = data.max_health,
+    stats = data.stats,
+    name = data.name
+FROM UNNEST(
+    $1::INTEGER[], $2::INTEGER[],
+    $3::SMALLINT[], $4::SMALLINT[],
+    $5::SMALLINT[], $6::SMALLINT[], $7::SMALLINT[], $8::SMALLINT[],
+    $9::BYTEA[], $10::BYTEA[],
+    $11::INTEGER[],
+    $12::INTEGER[], $13::INTEGER[],
+    $14::BYTEA[],
+    $15::TEXT[],
+    $16::TEXT[]
+) AS data(
+    tile_x, tile_y,
+    direction, facial_expression,
+    hair_style, clothing_colour, skin_colour, hair_colour,
+    gem_collection, item_inventory,
+    bombs_placed_count,
+    health, max_health,
+    stats,
+    name,
+    entity_id
+)
+WHERE client_entities.entity_id = data.entity_id
@@ -1,8 +1,12 @@
 INSERT INTO client_entities (
     tile_x, tile_y,
+    direction, facial_expression,
     hair_style, clothing_colour, skin_colour, hair_colour,
     gem_collection, item_inventory,
     bombs_placed_count,
-    client_id, entity_id
+    health, max_health,
+    stats,
+    name,
+    client_id, entity_id, world
 )
-VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
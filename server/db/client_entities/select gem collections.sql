@@ -0,0 +1 @@
+SELECT entity_id, name, gem_collection FROM client_entities WHERE world = $1
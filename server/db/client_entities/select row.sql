@@ -1 +1 @@
-SELECT * FROM client_entities WHERE client_id = $1
+SELECT * FROM client_entities WHERE client_id = $1 AND world = $2
@@ -1,5 +1,9 @@
 UPDATE client_entities
 SET tile_x = $1, tile_y = $2,
-    hair_style = $3, clothing_colour = $4, skin_colour = $5, hair_colour = $6,
-    gem_collection = $7, item_inventory = $8, bombs_placed_count = $9
-WHERE client_id = $10
+    direction = $3, facial_expression = $4,
+    hair_style = $5, clothing_colour = $6, skin_colour = $7, hair_colour = $8,
+    gem_collection = $9, item_inventory = $10, bombs_placed_count = $11,
+    health = $12, max_health = $13,
+    stats = $14,
+    name = $15
+WHERE client_id = $16 AND world = $17
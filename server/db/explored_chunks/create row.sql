@@ -0,0 +1,3 @@
+INSERT INTO explored_chunks (client_id, world, chunk_x, chunk_y)
+VALUES ($1, $2, $3, $4)
+ON CONFLICT DO NOTHING
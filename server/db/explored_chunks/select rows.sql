@@ -0,0 +1 @@
+SELECT chunk_x, chunk_y FROM explored_chunks WHERE client_id = $1 AND world = $2
@@ -1,2 +1,2 @@
-INSERT INTO map (seed)
-VALUES ($1)
+INSERT INTO map (world, seed, generator)
+VALUES ($1, $2, $3)
@@ -1 +1 @@
-SELECT * FROM map
+SELECT * FROM map WHERE world = $1
@@ -1 +1 @@
-SELECT * from map_chunks where chunk_x = $1 AND chunk_y = $2
+SELECT * from map_chunks where world = $1 AND chunk_x = $2 AND chunk_y = $3
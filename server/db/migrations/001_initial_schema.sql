@@ -0,0 +1,34 @@
+-- Tables as they existed before database migrations were introduced. 'IF NOT EXISTS' is used so that databases
+-- created by earlier versions of the server can be migrated.
+
+CREATE TABLE IF NOT EXISTS client_entities (
+    client_id TEXT PRIMARY KEY,
+    entity_id TEXT NOT NULL UNIQUE,
+    tile_x INTEGER NOT NULL,
+    tile_y INTEGER NOT NULL,
+    hair_style SMALLINT NOT NULL,
+    clothing_colour SMALLINT NOT NULL,
+    skin_colour SMALLINT NOT NULL,
+    hair_colour SMALLINT NOT NULL,
+    gem_collection BYTEA,
+    item_inventory BYTEA,
+    bombs_placed_count INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS map (
+    single_row BOOLEAN PRIMARY KEY DEFAULT TRUE,
+    seed INTEGER NOT NULL,
+    CONSTRAINT single_row_constraint CHECK (single_row)
+);
+
+CREATE TABLE IF NOT EXISTS map_chunks (
+    chunk_x INTEGER NOT NULL,
+    chunk_y INTEGER NOT NULL,
+    data BYTEA,
+    PRIMARY KEY (chunk_x, chunk_y)
+);
+
+CREATE TABLE IF NOT EXISTS achievements (
+    client_id TEXT PRIMARY KEY,
+    unlocked BIGINT NOT NULL
+);
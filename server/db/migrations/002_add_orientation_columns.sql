@@ -0,0 +1,3 @@
+ALTER TABLE client_entities
+    ADD COLUMN IF NOT EXISTS direction SMALLINT NOT NULL DEFAULT 1,
+    ADD COLUMN IF NOT EXISTS facial_expression SMALLINT NOT NULL DEFAULT 0;
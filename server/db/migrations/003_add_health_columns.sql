@@ -0,0 +1,3 @@
+ALTER TABLE client_entities
+    ADD COLUMN IF NOT EXISTS health INTEGER NOT NULL DEFAULT 100,
+    ADD COLUMN IF NOT EXISTS max_health INTEGER NOT NULL DEFAULT 100;
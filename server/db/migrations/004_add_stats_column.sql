@@ -0,0 +1,2 @@
+ALTER TABLE client_entities
+    ADD COLUMN IF NOT EXISTS stats BYTEA NOT NULL DEFAULT '';
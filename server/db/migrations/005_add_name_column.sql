@@ -0,0 +1,2 @@
+ALTER TABLE client_entities
+    ADD COLUMN IF NOT EXISTS name TEXT NOT NULL DEFAULT '';
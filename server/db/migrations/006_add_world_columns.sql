@@ -0,0 +1,17 @@
+-- Allow the server to host multiple worlds. Existing chunks, player entities & map seeds belong to the world named
+-- 'default'. Each client has a separate player entity in each world that they have joined.
+
+ALTER TABLE map
+    ADD COLUMN world TEXT NOT NULL DEFAULT 'default',
+    DROP COLUMN single_row,
+    ADD PRIMARY KEY (world);
+
+ALTER TABLE map_chunks
+    ADD COLUMN world TEXT NOT NULL DEFAULT 'default',
+    DROP CONSTRAINT map_chunks_pkey,
+    ADD PRIMARY KEY (world, chunk_x, chunk_y);
+
+ALTER TABLE client_entities
+    ADD COLUMN world TEXT NOT NULL DEFAULT 'default',
+    DROP CONSTRAINT client_entities_pkey,
+    ADD PRIMARY KEY (client_id, world);
@@ -0,0 +1,6 @@
+-- Record the name of the generator used to generate each world's map so that a server restarted with a different
+-- generator configured continues to generate chunks consistent with those already stored. Maps stored before this
+-- column was added are assumed to have used the 'default' generator.
+
+ALTER TABLE map
+    ADD COLUMN generator TEXT NOT NULL DEFAULT 'default';
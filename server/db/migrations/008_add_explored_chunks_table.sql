@@ -0,0 +1,10 @@
+-- The distinct chunks that each client's player entity has visited in each world, so that chunks explored in earlier
+-- sessions do not count towards a player's explored chunks statistic again.
+
+CREATE TABLE IF NOT EXISTS explored_chunks (
+    client_id TEXT NOT NULL,
+    world TEXT NOT NULL,
+    chunk_x INTEGER NOT NULL,
+    chunk_y INTEGER NOT NULL,
+    PRIMARY KEY (client_id, world, chunk_x, chunk_y)
+);
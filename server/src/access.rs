@@ -0,0 +1,197 @@
+//! Restriction of which IP addresses may connect to the server using whitelist & blacklist files (specified using the
+//! `--whitelist-file` and `--blacklist-file` command-line options).
+//!
+//! Each file contains one IP address (e.g. `192.168.1.20`) or CIDR range (e.g. `10.0.0.0/8`) per line. Blank lines
+//! and lines beginning with `#` are ignored. Both files are watched for changes and reloaded without the need to
+//! restart the server.
+
+use std::{
+    fmt, io,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::Arc
+};
+
+use ipnet::IpNet;
+use notify::Watcher;
+use parking_lot::RwLock;
+
+/// The parsed contents of the whitelist & blacklist files. A list is `None` should no file have been specified for it.
+#[derive(Debug, Default, PartialEq)]
+pub struct BanList {
+    whitelist: Option<Vec<IpNet>>,
+    blacklist: Option<Vec<IpNet>>
+}
+
+impl BanList {
+    /// Read and parse the whitelist & blacklist files at the given paths (either of which may be omitted).
+    pub fn load(whitelist_path: Option<&Path>, blacklist_path: Option<&Path>) -> Result<Self> {
+        Ok(BanList {
+            whitelist: whitelist_path.map(load_list).transpose()?,
+            blacklist: blacklist_path.map(load_list).transpose()?
+        })
+    }
+
+    /// Check whether a client with the given IP address may connect to the server. Addresses must be covered by the
+    /// whitelist (should there be one) and must not be covered by the blacklist (should there be one).
+    pub fn check(&self, ip: IpAddr) -> std::result::Result<(), Rejection> {
+        if let Some(whitelist) = &self.whitelist {
+            if !whitelist.iter().any(|net| net.contains(&ip)) {
+                return Err(Rejection::NotWhitelisted);
+            }
+        }
+
+        if let Some(blacklist) = &self.blacklist {
+            if let Some(net) = blacklist.iter().find(|net| net.contains(&ip)) {
+                return Err(Rejection::Blacklisted(*net));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The reason for which a connection was refused by [`BanList::check`].
+#[derive(Debug, PartialEq)]
+pub enum Rejection {
+    NotWhitelisted,
+    /// The address falls within the given blacklisted range.
+    Blacklisted(IpNet)
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::NotWhitelisted => write!(f, "address not in whitelist"),
+            Rejection::Blacklisted(net) => write!(f, "address in blacklist (matches {})", net)
+        }
+    }
+}
+
+/// Reload the whitelist & blacklist files at the given paths into the given ban list whenever either file changes.
+/// Should a modified file fail to load then the previously loaded lists remain in use. Files are watched for only as
+/// long as the returned watcher is kept.
+///
+/// The directories containing the files are watched rather than the files themselves as many text editors save
+/// changes by replacing a file (which would otherwise end the watch).
+pub fn watch(
+    ban_list: Arc<RwLock<BanList>>, whitelist_path: Option<PathBuf>, blacklist_path: Option<PathBuf>
+) -> notify::Result<notify::RecommendedWatcher> {
+    let paths: Vec<PathBuf> = whitelist_path.iter().chain(blacklist_path.iter()).cloned().collect();
+    let file_names: Vec<_> = paths.iter().filter_map(|path| path.file_name().map(ToOwned::to_owned)).collect();
+
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+        Ok(event) => {
+            let concerns_list_file = event
+                .paths
+                .iter()
+                .any(|path| path.file_name().is_some_and(|name| file_names.iter().any(|list| list == name)));
+
+            if concerns_list_file && (event.kind.is_create() || event.kind.is_modify()) {
+                match BanList::load(whitelist_path.as_deref(), blacklist_path.as_deref()) {
+                    Ok(reloaded) => {
+                        *ban_list.write() = reloaded;
+                        tracing::info!("Reloaded IP address whitelist/blacklist");
+                    }
+                    Err(e) => tracing::error!("{} - continuing to use previously loaded whitelist/blacklist", e)
+                }
+            }
+        }
+        Err(e) => tracing::warn!("Error while watching whitelist/blacklist files - {}", e)
+    })?;
+
+    for path in &paths {
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new(".")
+        };
+        watcher.watch(directory, notify::RecursiveMode::NonRecursive)?;
+    }
+
+    Ok(watcher)
+}
+
+fn load_list(path: &Path) -> Result<Vec<IpNet>> {
+    let text = std::fs::read_to_string(path).map_err(|e| Error::IoError(path.to_owned(), e))?;
+    parse_list(&text).map_err(|(line, text)| Error::InvalidEntry(path.to_owned(), line, text))
+}
+
+/// Parse the contents of a whitelist/blacklist file. Individual addresses are treated as ranges containing only that
+/// address. Should a line be neither an address nor a CIDR range, its (1-based) line number and text are returned.
+fn parse_list(text: &str) -> std::result::Result<Vec<IpNet>, (usize, String)> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line_number, line)| {
+            line.parse::<IpNet>()
+                .or_else(|_| line.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| (line_number, line.to_string()))
+        })
+        .collect()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Failed to read IP address list file {0} - {1}")]
+    IoError(PathBuf, #[source] io::Error),
+    #[error("IP address list file {0} line {1} is not a valid IP address or CIDR range: {2}")]
+    InvalidEntry(PathBuf, usize, String)
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_addresses_and_ranges() {
+        let list = parse_list("# Office network\n10.0.0.0/8\n\n  192.168.1.20  \n::1\n").unwrap();
+
+        assert_eq!(
+            list,
+            vec![
+                "10.0.0.0/8".parse::<IpNet>().unwrap(),
+                "192.168.1.20/32".parse().unwrap(),
+                "::1/128".parse().unwrap()
+            ]
+        );
+
+        assert_eq!(parse_list("10.0.0.1\nlocalhost"), Err((2, "localhost".to_string())));
+        assert_eq!(parse_list("10.0.0.0/33"), Err((1, "10.0.0.0/33".to_string())));
+    }
+
+    #[test]
+    fn whitelist_and_blacklist_checked() {
+        assert_eq!(BanList::default().check(ip("1.2.3.4")), Ok(()));
+
+        let ban_list = BanList {
+            whitelist: Some(parse_list("10.0.0.0/8\n192.168.1.20").unwrap()),
+            blacklist: Some(parse_list("10.0.5.0/24").unwrap())
+        };
+
+        assert_eq!(ban_list.check(ip("10.1.2.3")), Ok(()));
+        assert_eq!(ban_list.check(ip("192.168.1.20")), Ok(()));
+        assert_eq!(ban_list.check(ip("192.168.1.21")), Err(Rejection::NotWhitelisted));
+        assert_eq!(ban_list.check(ip("10.0.5.9")), Err(Rejection::Blacklisted("10.0.5.0/24".parse().unwrap())));
+
+        let blacklist_only = BanList { whitelist: None, blacklist: Some(parse_list("1.2.3.4").unwrap()) };
+
+        assert_eq!(blacklist_only.check(ip("1.2.3.5")), Ok(()));
+        assert!(blacklist_only.check(ip("1.2.3.4")).is_err());
+    }
+
+    #[test]
+    fn rejection_display() {
+        assert_eq!(Rejection::NotWhitelisted.to_string(), "address not in whitelist");
+        assert_eq!(
+            Rejection::Blacklisted("10.0.5.0/24".parse().unwrap()).to_string(),
+            "address in blacklist (matches 10.0.5.0/24)"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Keeps track of the achievements unlocked by each player (see [`shared::achievements`]) and includes functions to
+//! fetch/save those achievements from/to the database. Unlocked achievements are stored as a bitfield in which the bit
+//! at each achievement's index (see [`Achievement::index`]) is set should that achievement be unlocked.
+
+use std::collections::HashSet;
+
+use shared::{
+    achievements::{Achievement, EXPLORED_CHUNKS_MILESTONES},
+    gems, Id
+};
+use sqlx::Row;
+
+use crate::db_query_from_file;
+
+/// Something done by a player that may result in them unlocking an achievement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    GemsCollected {
+        gem: gems::Gem,
+        quantity: u32
+    },
+    RockMined,
+    MonsterKilled,
+    QuestCompleted,
+    /// The player's entity visited a chunk for the first time this session, bringing the total number of chunks
+    /// explored by the player to the given value (see [`shared::stats::PlayerStats::chunks_explored`]).
+    ChunkExplored {
+        total_explored: u64
+    }
+}
+
+/// Determines which achievements a player unlocks as they play.
+#[derive(Debug, Default, Clone)]
+pub struct AchievementChecker {
+    unlocked: HashSet<Achievement>
+}
+
+impl AchievementChecker {
+    /// Create a checker for a player who has previously unlocked the achievements whose bits are set in the given
+    /// bitfield.
+    pub fn from_bitfield(bitfield: i64) -> Self {
+        let unlocked = Achievement::all()
+            .enumerate()
+            .filter(|(index, _)| bitfield & (1 << index) != 0)
+            .map(|(_, achievement)| achievement)
+            .collect();
+
+        AchievementChecker { unlocked }
+    }
+
+    pub fn to_bitfield(&self) -> i64 {
+        self.unlocked
+            .iter()
+            .filter_map(|achievement| achievement.index())
+            .fold(0, |bitfield, index| bitfield | (1 << index))
+    }
+
+    pub fn unlocked_count(&self) -> usize {
+        self.unlocked.len()
+    }
+
+    /// Update the player's progress based on the given event, returning any achievements that have just been unlocked
+    /// as a result.
+    pub fn check(&mut self, event: GameEvent) -> Vec<Achievement> {
+        let candidates = match event {
+            GameEvent::GemsCollected { .. } => vec![Achievement::FirstGem],
+            GameEvent::RockMined => vec![Achievement::MinedRock],
+            GameEvent::MonsterKilled => vec![Achievement::KilledMonster],
+            GameEvent::QuestCompleted => vec![Achievement::CompletedQuest],
+            GameEvent::ChunkExplored { total_explored } => EXPLORED_CHUNKS_MILESTONES
+                .iter()
+                .filter(|milestone| total_explored >= u64::from(**milestone))
+                .map(|milestone| Achievement::ExploredChunks(*milestone))
+                .collect()
+        };
+
+        candidates.into_iter().filter(|achievement| self.unlocked.insert(*achievement)).collect()
+    }
+}
+
+/// Fetch the achievements previously unlocked by the player with the given client ID. A checker with no achievements
+/// unlocked is returned should the player have no row in the achievements table.
+pub async fn achievements_from_database(
+    client_id: Id, db: &mut sqlx::PgConnection
+) -> sqlx::Result<AchievementChecker> {
+    let bitfield_option = db_query_from_file!("achievements/select row")
+        .bind(client_id.encode())
+        .map(|row: sqlx::postgres::PgRow| row.get::<i64, _>("unlocked"))
+        .fetch_optional(db)
+        .await?;
+
+    Ok(bitfield_option.map(AchievementChecker::from_bitfield).unwrap_or_default())
+}
+
+/// Insert or update the row of the achievements table for the player with the given client ID. Achievements already
+/// recorded as unlocked in the row are kept so that saves completing out of order never lose an achievement.
+pub async fn update_database_for_achievements(
+    achievements: &AchievementChecker, client_id: Id, db: &mut sqlx::PgConnection
+) -> sqlx::Result<()> {
+    db_query_from_file!("achievements/replace row", db, client_id.encode(), achievements.to_bitfield()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GEMS_COLLECTED: GameEvent = GameEvent::GemsCollected { gem: gems::Gem::Emerald, quantity: 2 };
+
+    #[test]
+    fn achievements_unlocked_once() {
+        let mut checker = AchievementChecker::default();
+
+        assert_eq!(checker.check(GEMS_COLLECTED), vec![Achievement::FirstGem]);
+        assert!(checker.check(GEMS_COLLECTED).is_empty());
+        assert_eq!(checker.check(GameEvent::QuestCompleted), vec![Achievement::CompletedQuest]);
+        assert_eq!(checker.unlocked_count(), 2);
+    }
+
+    #[test]
+    fn exploration_milestones_unlocked_in_order() {
+        let mut checker = AchievementChecker::default();
+        let [first, second, third] = EXPLORED_CHUNKS_MILESTONES;
+
+        let explored = |total_explored: u32| GameEvent::ChunkExplored { total_explored: total_explored.into() };
+
+        assert!(checker.check(explored(first - 1)).is_empty());
+        assert_eq!(checker.check(explored(first)), vec![Achievement::ExploredChunks(first)]);
+        assert!(checker.check(explored(first + 1)).is_empty());
+
+        // Passing multiple milestones at once (e.g. when loaded stats already exceed them) unlocks each of them:
+        assert_eq!(
+            checker.check(explored(third)),
+            vec![Achievement::ExploredChunks(second), Achievement::ExploredChunks(third)]
+        );
+    }
+
+    #[test]
+    fn bitfield_round_trip() {
+        let mut checker = AchievementChecker::default();
+        checker.check(GameEvent::RockMined);
+        checker.check(GameEvent::MonsterKilled);
+
+        let bitfield = checker.to_bitfield();
+        assert_eq!(bitfield, 0b110);
+
+        let mut loaded = AchievementChecker::from_bitfield(bitfield);
+        assert_eq!(loaded.unlocked_count(), 2);
+        assert!(loaded.check(GameEvent::RockMined).is_empty());
+        assert_eq!(loaded.check(GEMS_COLLECTED), vec![Achievement::FirstGem]);
+    }
+
+    /// Requires a PostgreSQL database specified by the `TEST_DATABASE_URL` environment variable.
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn achievements_database_round_trip() {
+        let db_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        let db_pool = sqlx::postgres::PgPoolOptions::new().connect(&db_url).await.unwrap();
+
+        crate::MIGRATOR.run(&db_pool).await.unwrap();
+
+        let mut db = db_pool.acquire().await.unwrap();
+        let client_id = crate::id::generate_with_timestamp();
+
+        assert_eq!(achievements_from_database(client_id, &mut db).await.unwrap().unlocked_count(), 0);
+
+        let mut checker = AchievementChecker::default();
+        checker.check(GEMS_COLLECTED);
+        update_database_for_achievements(&checker, client_id, &mut db).await.unwrap();
+        checker.check(GameEvent::MonsterKilled);
+        update_database_for_achievements(&checker, client_id, &mut db).await.unwrap();
+
+        let loaded = achievements_from_database(client_id, &mut db).await.unwrap();
+        assert_eq!(loaded.to_bitfield(), checker.to_bitfield());
+    }
+}
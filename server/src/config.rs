@@ -0,0 +1,255 @@
+//! Loading of server settings from a TOML configuration file (specified using the `--config` command-line option).
+//!
+//! Every field of [`Options`] except for `config` itself may be given in the configuration file using the same name as
+//! the field (e.g. `rate_limit_capacity = 30`). Values given in the file are used as defaults with any options also
+//! specified on the command line taking precedence. See `server/config.toml` for an example configuration file.
+
+use std::{
+    io,
+    path::{Path, PathBuf}
+};
+
+use serde::Deserialize;
+
+use crate::{maps, Options};
+
+/// Server settings read from a configuration file. Each field corresponds to a field of [`Options`] of the same name,
+/// with fields that are absent from the file being `None`.
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub port: Option<u16>,
+    pub map_directory: Option<PathBuf>,
+    pub worlds_dir: Option<PathBuf>,
+    pub map_generator: Option<String>,
+    pub chunk_gen_queue_size: Option<usize>,
+    pub rate_limit_capacity: Option<u32>,
+    pub rate_limit_refill: Option<f32>,
+    pub rate_limit_max_violations: Option<usize>,
+    pub idle_timeout_seconds: Option<u64>,
+    pub max_bombs_per_player: Option<u32>,
+    pub global_chat_gem_cost: Option<u32>,
+    pub disable_fog_of_war: Option<bool>,
+    pub chunk_compression_level: Option<i32>,
+    pub no_chunk_compression: Option<bool>,
+    pub tick_interval: Option<u64>,
+    pub monster_spawn_interval_seconds: Option<u64>,
+    pub max_monsters: Option<usize>,
+    pub spawn_point_x: Option<i32>,
+    pub spawn_point_y: Option<i32>,
+    pub safe_zones_file: Option<PathBuf>,
+    pub metrics_port: Option<u16>,
+    pub whitelist_file: Option<PathBuf>,
+    pub blacklist_file: Option<PathBuf>,
+    pub database_connection_string: Option<String>,
+    pub max_database_connections: Option<u32>,
+    pub log_debug: Option<bool>,
+    pub log_trace: Option<bool>,
+    pub log_to_file: Option<bool>,
+    pub log_format: Option<crate::logging::LogFormat>,
+    pub import_map: Option<PathBuf>,
+    pub export_map: Option<PathBuf>,
+    pub export_map_text: Option<PathBuf>
+}
+
+impl Config {
+    /// Read and parse the configuration file at the given path.
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Apply the values in this configuration to the given options, except for those options that were explicitly
+    /// specified on the command line (as indicated by the given argument matches). The combined options are then
+    /// checked in the same way as they would be had they all been given on the command line.
+    pub fn merge_into(self, options: &mut Options, matches: &structopt::clap::ArgMatches) -> Result<()> {
+        // Command-line argument names are the kebab-case equivalents of field names:
+        let specified_on_command_line = |field: &str| matches.occurrences_of(field.replace('_', "-")) > 0;
+
+        macro_rules! merge {
+            ($($field:ident),+) => {
+                $(if let Some(value) = self.$field {
+                    if !specified_on_command_line(stringify!($field)) {
+                        options.$field = value;
+                    }
+                })+
+            };
+        }
+
+        macro_rules! merge_optional {
+            ($($field:ident),+) => {
+                $(if self.$field.is_some() && !specified_on_command_line(stringify!($field)) {
+                    options.$field = self.$field;
+                })+
+            };
+        }
+
+        merge!(
+            port,
+            map_directory,
+            map_generator,
+            chunk_gen_queue_size,
+            rate_limit_capacity,
+            rate_limit_refill,
+            rate_limit_max_violations,
+            database_connection_string,
+            max_database_connections,
+            log_debug,
+            log_trace,
+            log_to_file,
+            log_format,
+            no_chunk_compression,
+            disable_fog_of_war
+        );
+
+        merge_optional!(
+            idle_timeout_seconds,
+            max_bombs_per_player,
+            global_chat_gem_cost,
+            chunk_compression_level,
+            tick_interval,
+            monster_spawn_interval_seconds,
+            max_monsters,
+            spawn_point_x,
+            spawn_point_y,
+            safe_zones_file,
+            metrics_port,
+            worlds_dir,
+            whitelist_file,
+            blacklist_file,
+            import_map,
+            export_map,
+            export_map_text
+        );
+
+        validate(options)
+    }
+}
+
+/// Check the given options for values that would have been rejected had they been given on the command line.
+fn validate(options: &Options) -> Result<()> {
+    if !maps::generators::GENERATOR_NAMES.contains(&options.map_generator.as_str()) {
+        return Err(Error::UnknownMapGenerator(options.map_generator.clone()));
+    }
+
+    if let Some(level) = options.chunk_compression_level {
+        crate::parse_compression_level(&level.to_string()).map_err(Error::InvalidChunkCompressionLevel)?;
+
+        if options.no_chunk_compression {
+            return Err(Error::ConflictingOptions("no_chunk_compression", "chunk_compression_level"));
+        }
+    }
+
+    if options.log_debug && options.log_trace {
+        return Err(Error::ConflictingOptions("log_debug", "log_trace"));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[allow(clippy::enum_variant_names)]
+pub enum Error {
+    #[error("Failed to read configuration file - {0}")]
+    IoError(#[from] io::Error),
+    #[error("Failed to parse configuration file as TOML - {0}")]
+    TomlError(#[from] toml::de::Error),
+    #[error("Unknown map generator '{0}'")]
+    UnknownMapGenerator(String),
+    #[error("Invalid chunk compression level - {0}")]
+    InvalidChunkCompressionLevel(String),
+    #[error("Options '{0}' and '{1}' cannot both be given")]
+    ConflictingOptions(&'static str, &'static str)
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use structopt::StructOpt;
+
+    use super::*;
+
+    fn sample_config() -> Config {
+        Config::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("config.toml")).unwrap()
+    }
+
+    #[test]
+    fn load_sample_config() {
+        let config = sample_config();
+
+        assert_eq!(config.port, Some(8080));
+        assert_eq!(config.map_generator.as_deref(), Some("island"));
+        assert_eq!(config.rate_limit_capacity, Some(30));
+        assert_eq!(config.rate_limit_refill, Some(15.0));
+        assert_eq!(config.max_bombs_per_player, Some(8));
+        assert_eq!(config.tick_interval, Some(50));
+        assert_eq!(config.database_connection_string.as_deref(), Some("postgres://localhost/gemgame"));
+        assert_eq!(config.log_to_file, Some(true));
+        assert_eq!(config.export_map, None);
+    }
+
+    #[test]
+    fn unknown_field_rejected() {
+        assert!(toml::from_str::<Config>("prot = 1234").is_err());
+        assert_eq!(toml::from_str::<Config>("").unwrap(), Config::default());
+    }
+
+    fn merge_config_text(text: &str, args: &[&str]) -> Result<Options> {
+        let matches = Options::clap().get_matches_from(std::iter::once("gemgame-server").chain(args.iter().copied()));
+        let mut options = Options::from_clap(&matches);
+
+        toml::from_str::<Config>(text).unwrap().merge_into(&mut options, &matches)?;
+        Ok(options)
+    }
+
+    #[test]
+    fn invalid_merged_values_rejected() {
+        let result = merge_config_text("map_generator = \"moon\"", &[]);
+        assert!(matches!(result, Err(Error::UnknownMapGenerator(name)) if name == "moon"));
+        assert_eq!(merge_config_text("map_generator = \"desert\"", &[]).unwrap().map_generator, "desert");
+
+        assert!(matches!(
+            merge_config_text("chunk_compression_level = 40", &[]),
+            Err(Error::InvalidChunkCompressionLevel(_))
+        ));
+        assert_eq!(merge_config_text("chunk_compression_level = 22", &[]).unwrap().chunk_compression_level, Some(22));
+
+        // Options that conflict with each other when one is given in the configuration file and the other on the
+        // command line:
+        assert!(matches!(
+            merge_config_text("no_chunk_compression = true", &["--chunk-compression-level", "5"]),
+            Err(Error::ConflictingOptions(..))
+        ));
+        assert!(matches!(merge_config_text("log_trace = true", &["--log-debug"]), Err(Error::ConflictingOptions(..))));
+    }
+
+    #[test]
+    fn command_line_overrides_config() {
+        let matches =
+            Options::clap().get_matches_from(vec!["gemgame-server", "--port", "1234", "--tick-interval", "20"]);
+        let mut options = Options::from_clap(&matches);
+
+        sample_config().merge_into(&mut options, &matches).unwrap();
+
+        assert_eq!(options.port, 1234);
+        assert_eq!(options.tick_interval, Some(20));
+        assert_eq!(options.map_generator, "island");
+        assert_eq!(options.rate_limit_capacity, 30);
+        assert_eq!(options.max_bombs_per_player, Some(8));
+        assert!(options.log_to_file);
+
+        // Options specified in neither the command line nor the configuration file keep their default values:
+        assert_eq!(options.chunk_gen_queue_size, 32);
+        assert_eq!(options.export_map, None);
+    }
+
+    #[test]
+    fn generator_alias_overrides_config() {
+        let matches = Options::clap().get_matches_from(vec!["gemgame-server", "--generator", "desert"]);
+        let mut options = Options::from_clap(&matches);
+
+        sample_config().merge_into(&mut options, &matches).unwrap();
+
+        assert_eq!(options.map_generator, "desert");
+    }
+}
@@ -0,0 +1,107 @@
+//! Commands entered by the server operator via standard input while the server is running. Currently the only command
+//! is `/announce <text>` which broadcasts the given text to all connected clients (in every world) as a
+//! [`messages::FromServer::ServerAnnouncement`] message.
+
+use std::sync::Arc;
+
+use shared::messages;
+use tokio::io::{self, AsyncBufReadExt};
+
+use crate::{maps, worlds::WorldRegistry};
+
+/// Prefix of lines that are to be broadcast as announcements.
+const ANNOUNCE_PREFIX: &str = "/announce ";
+
+/// A command given by the server operator.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Broadcast the given text to all connected clients.
+    Announce(String)
+}
+
+impl Command {
+    /// Parse a single line of input as a command. Returns `Ok(None)` for blank lines.
+    pub fn parse(line: &str) -> Result<Option<Self>> {
+        let line = line.trim_end_matches(&['\r', '\n'][..]);
+
+        if line.trim().is_empty() {
+            Ok(None)
+        }
+        else if let Some(text) = line.strip_prefix(ANNOUNCE_PREFIX) {
+            if messages::is_valid_announcement(text) {
+                Ok(Some(Command::Announce(text.to_string())))
+            }
+            else {
+                Err(Error::InvalidAnnouncement)
+            }
+        }
+        else {
+            Err(Error::UnknownCommand(line.to_string()))
+        }
+    }
+}
+
+/// Read commands from standard input line by line until the end of input is reached, informing connection tasks of
+/// any announcements via the map changes channel of every world.
+pub async fn read_commands(worlds: Arc<WorldRegistry>) {
+    let mut lines = io::BufReader::new(io::stdin()).lines();
+
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => match Command::parse(&line) {
+                Ok(Some(Command::Announce(text))) => {
+                    tracing::info!("Broadcasting announcement: {}", text);
+
+                    for (_, world) in worlds.iter() {
+                        let announcement = maps::Modification::Announcement { text: text.clone() };
+                        world.map_changes_sender.send(announcement).unwrap();
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Ignoring console input - {}", e)
+            },
+
+            Ok(None) => {
+                tracing::debug!("End of standard input reached so no longer reading console commands");
+                break;
+            }
+
+            Err(e) => {
+                tracing::error!("Failed to read console commands from standard input - {}", e);
+                break;
+            }
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Unknown command '{0}' (expected '{}<text>')", ANNOUNCE_PREFIX)]
+    UnknownCommand(String),
+    #[error("Announcement text must not be blank or exceed {} characters", messages::MAX_ANNOUNCEMENT_LENGTH)]
+    InvalidAnnouncement
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_announcements() {
+        assert_eq!(
+            Command::parse("/announce Server restarting in 5 minutes\n").unwrap(),
+            Some(Command::Announce("Server restarting in 5 minutes".to_string()))
+        );
+        assert_eq!(Command::parse("  \n").unwrap(), None);
+
+        assert!(matches!(Command::parse("/announce   "), Err(Error::InvalidAnnouncement)));
+        assert!(matches!(
+            Command::parse(&format!("/announce {}", "a".repeat(messages::MAX_ANNOUNCEMENT_LENGTH + 1))),
+            Err(Error::InvalidAnnouncement)
+        ));
+        assert!(matches!(Command::parse("/shutdown"), Err(Error::UnknownCommand(command)) if command == "/shutdown"));
+        assert!(matches!(Command::parse("/announce"), Err(Error::UnknownCommand(_))));
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant}
+};
+
+use shared::{mechanics::ActionKind, messages::RejectionReason};
+
+/// Keeps track of when the player last performed each action that has a cooldown so that requests to perform an
+/// action again too soon can be rejected. Cooldowns are only kept in memory so are reset should the player reconnect.
+#[derive(Default)]
+pub struct Cooldowns {
+    last_performed: HashMap<ActionKind, Instant>
+}
+
+impl Cooldowns {
+    /// The time remaining from the given time until the specified action may be performed again (zero should it
+    /// already be possible).
+    pub fn remaining(&self, action: ActionKind, now: Instant) -> Duration {
+        self.last_performed.get(&action).map_or(Duration::ZERO, |last| {
+            Duration::from_millis(action.cooldown_ms().into()).saturating_sub(now.saturating_duration_since(*last))
+        })
+    }
+
+    /// Returns the reason for rejecting the specified action should it be performed at the given time before its
+    /// cooldown has passed.
+    pub fn check(&self, action: ActionKind, now: Instant) -> Result<(), RejectionReason> {
+        let remaining = self.remaining(action, now);
+
+        if remaining.is_zero() {
+            Ok(())
+        }
+        else {
+            // Round up so that a client waiting the given time is never rejected again:
+            let remaining_ms = remaining.as_nanos().div_ceil(1_000_000) as u32;
+            Err(RejectionReason::Cooldown { action, remaining_ms })
+        }
+    }
+
+    /// Record that the specified action was performed at the given time, beginning its cooldown.
+    pub fn performed(&mut self, action: ActionKind, now: Instant) {
+        self.last_performed.insert(action, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_rejected_until_cooldown_passes() {
+        let start = Instant::now();
+        let mut cooldowns = Cooldowns::default();
+
+        assert_eq!(cooldowns.check(ActionKind::PlaceBomb, start), Ok(()));
+
+        cooldowns.performed(ActionKind::PlaceBomb, start);
+
+        let cooldown = Duration::from_millis(ActionKind::PlaceBomb.cooldown_ms().into());
+        let partway = start + (cooldown / 4) + Duration::from_micros(500);
+
+        assert_eq!(cooldowns.remaining(ActionKind::PlaceBomb, partway), cooldown - (partway - start));
+        assert_eq!(
+            cooldowns.check(ActionKind::PlaceBomb, partway),
+            Err(RejectionReason::Cooldown {
+                action: ActionKind::PlaceBomb,
+                remaining_ms: (ActionKind::PlaceBomb.cooldown_ms() * 3 / 4)
+            })
+        );
+
+        // Cooldowns of other actions are independent:
+        assert_eq!(cooldowns.check(ActionKind::Emote, partway), Ok(()));
+
+        assert_eq!(cooldowns.remaining(ActionKind::PlaceBomb, start + cooldown), Duration::ZERO);
+        assert_eq!(cooldowns.check(ActionKind::PlaceBomb, start + cooldown), Ok(()));
+    }
+}
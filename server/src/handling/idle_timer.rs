@@ -0,0 +1,64 @@
+use std::time::{Duration, Instant};
+
+/// The default number of seconds that a client may go without sending any messages before its connection is closed.
+pub const IDLE_TIMEOUT_SECONDS: u64 = 60;
+
+/// Keeps track of how long it has been since a client last sent a message so that stalled connections (e.g. those of
+/// clients that crashed without closing their connection) can be closed.
+pub struct IdleTimer {
+    timeout: Duration,
+    last_activity: Instant
+}
+
+impl IdleTimer {
+    /// Create a new timer that considers the connection to have been active at the given time.
+    pub fn new(timeout: Duration, now: Instant) -> Self {
+        IdleTimer { timeout, last_activity: now }
+    }
+
+    /// Record that a message was received from the client at the given time.
+    pub fn reset(&mut self, now: Instant) {
+        self.last_activity = now;
+    }
+
+    /// The time remaining from the given time until the connection is considered idle (zero should it already be).
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.timeout.saturating_sub(now.saturating_duration_since(self.last_activity))
+    }
+
+    /// How long a client may go without sending any messages.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remaining_time_decreases_until_reset() {
+        let start = Instant::now();
+        let mut timer = IdleTimer::new(Duration::from_secs(60), start);
+
+        assert_eq!(timer.remaining(start), Duration::from_secs(60));
+        assert_eq!(timer.remaining(start + Duration::from_secs(45)), Duration::from_secs(15));
+        assert_eq!(timer.remaining(start + Duration::from_secs(90)), Duration::ZERO);
+
+        timer.reset(start + Duration::from_secs(50));
+        assert_eq!(timer.remaining(start + Duration::from_secs(90)), Duration::from_secs(20));
+
+        // Times before the most recent activity should not extend the timeout:
+        assert_eq!(timer.remaining(start), Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn timeout_wrapping_silent_receive_elapses() {
+        let timer = IdleTimer::new(Duration::from_millis(10), Instant::now());
+
+        // A receive that never completes, as would be the case with a client that has stalled:
+        let silent_receive = std::future::pending::<()>();
+
+        assert!(tokio::time::timeout(timer.remaining(Instant::now()), silent_receive).await.is_err());
+    }
+}
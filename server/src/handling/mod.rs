@@ -1,56 +1,188 @@
+mod cooldowns;
+mod idle_timer;
+mod rate_limiting;
 mod tests;
 
-use std::{convert::Into, net::SocketAddr};
+use std::{
+    collections::HashSet,
+    convert::Into,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc
+    },
+    time::{Duration, Instant}
+};
 
-use rand::Rng;
+use cooldowns::Cooldowns;
+use idle_timer::IdleTimer;
+pub use idle_timer::IDLE_TIMEOUT_SECONDS;
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    Rng
+};
+pub use rate_limiting::RateLimits;
+use rate_limiting::{RateLimitOutcome, RateLimiter};
 use shared::{
+    gems,
     items::{self, Item},
-    maps::{ChunkCoords, Map, TileCoords},
-    messages, Id
+    maps::{
+        entities::{Direction, Entity},
+        ChunkCoords, GemDrop, Map, Tile, TileCoords, VIEW_RADIUS
+    },
+    mechanics::{self, ActionKind},
+    messages,
+    quests::{self, QuestEvent},
+    Id
 };
 use thiserror::Error;
 use tokio::{net::TcpStream, sync::broadcast};
-use tokio_tungstenite::tungstenite;
+use tokio_tungstenite::tungstenite::{self, protocol::frame::coding::CloseCode};
+use tracing::Instrument;
 
 use crate::{
+    achievements::{self, AchievementChecker, GameEvent},
+    leaderboard,
     maps::{self, entities, EntityMovement, ServerMap},
+    metrics::Metrics,
     networking::{self, Connection},
+    parties::{self, Parties, PartyChange},
+    safe_zones::SafeZoneRegistry,
+    worlds::WorldRegistry,
     Shared
 };
 
-const MAX_LOADED_CHUNKS_PER_CLIENT: usize = 12;
+/// The default maximum number of undetonated bombs that a single player may have placed at once.
+pub const MAX_BOMBS_PER_PLAYER: u32 = 5;
+
+/// The type of gem with which players pay to send chat messages on the [`messages::ChatChannel::Global`] channel.
+pub const GLOBAL_CHAT_COST_GEM: gems::Gem = gems::Gem::Emerald;
+
+/// The default quantity of [`GLOBAL_CHAT_COST_GEM`] gems that each global chat message costs to send.
+pub const GLOBAL_CHAT_GEM_COST: u32 = 2;
+
+/// Used to give each connection a unique ID by which the log messages of its handler can be identified.
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Limits applied by each connection handler to its remote client along with other handler settings.
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    pub rate_limits: RateLimits,
+    /// The maximum number of undetonated bombs that a single player may have placed at once.
+    pub max_bombs_per_player: u32,
+    /// Whether players can only see the tiles within [`shared::maps::SIGHT_RADIUS`] of their entity (with tiles they
+    /// have seen marked as such) rather than all provided tiles.
+    pub fog_of_war: bool,
+    /// The zstd compression level used when saving unloaded chunks to the database (`None` if uncompressed).
+    pub chunk_compression_level: Option<i32>,
+    /// Connections with clients that send no messages for this long are closed.
+    pub idle_timeout: Duration,
+    /// The quantity of [`GLOBAL_CHAT_COST_GEM`] gems that each global chat message costs to send.
+    pub global_chat_gem_cost: u32
+}
 
-/// Creates a new [`Handler`] instance and then calls its [`Handler::handle`] method.
+/// Creates a new [`Handler`] instance (initially in the default world of the given registry) and then calls its
+/// [`Handler::handle`] method. The client is counted as connected in the given metrics until the connection is closed.
+/// Messages logged by the handler are emitted within a `connection` span with fields identifying the connection & (once
+/// known) the client's player entity.
+#[allow(clippy::too_many_arguments)]
 pub async fn handle_connection(
-    stream: TcpStream, address: SocketAddr, game_map: Shared<ServerMap>, db_pool: sqlx::PgPool,
-    map_changes_sender: broadcast::Sender<maps::Modification>,
-    map_changes_receiver: broadcast::Receiver<maps::Modification>
+    stream: TcpStream, address: SocketAddr, worlds: Arc<WorldRegistry>, parties: Shared<Parties>,
+    safe_zones: Arc<SafeZoneRegistry>, db_pool: sqlx::PgPool, config: Config, metrics: Arc<Metrics>
 ) {
+    let (world_name, world) = worlds.default_world();
+
     let mut handler = Handler {
         address,
-        game_map,
+        world: world_name.to_string(),
+        game_map: Arc::clone(&world.map),
+        map_changes_receiver: world.map_changes_sender.subscribe(),
+        map_changes_sender: world.map_changes_sender.clone(),
+        worlds: Arc::clone(&worlds),
+        parties,
+        safe_zones,
         db_pool,
-        map_changes_sender,
-        map_changes_receiver,
-        remote_loaded_chunk_coords: Vec::new()
+        remote_loaded_chunk_coords: HashSet::new(),
+        last_processed_request: None,
+        rate_limiter: RateLimiter::new(config.rate_limits, Instant::now()),
+        max_bombs_per_player: config.max_bombs_per_player,
+        fog_of_war: config.fog_of_war,
+        chunk_compression_level: config.chunk_compression_level,
+        idle_timer: IdleTimer::new(config.idle_timeout, Instant::now()),
+        global_chat_gem_cost: config.global_chat_gem_cost,
+        active_quests: Vec::new(),
+        client_id: None,
+        achievements: AchievementChecker::default(),
+        visited_chunks: HashSet::new(),
+        cooldowns: Cooldowns::default()
     };
 
-    handler.handle(stream).await;
+    let span = tracing::info_span!(
+        "connection",
+        connection_id = NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+        address = %address,
+        world = tracing::field::Empty,
+        entity_id = tracing::field::Empty
+    );
+
+    let _connected_client = metrics.client_connected();
+    handler.handle(stream).instrument(span).await;
 }
 
 /// Structure containing information required by a connection coroutine/task.
 struct Handler {
     /// The address of the remote client.
     address: SocketAddr,
-    /// Arc mutex containing the game map.
+    /// The name of the world that the client has joined (see [`Self::join_world`]).
+    world: String,
+    /// Arc mutex containing the game map of the joined world.
     game_map: Shared<ServerMap>,
+    /// All worlds hosted by the server, any of which the client may choose to join.
+    worlds: Arc<WorldRegistry>,
+    /// Arc mutex containing the parties & party invites of all players.
+    parties: Shared<Parties>,
+    /// Areas in which players cannot place bombs.
+    safe_zones: Arc<SafeZoneRegistry>,
     /// The database connection pool.
     db_pool: sqlx::PgPool,
+    /// Channel through which the tasks of clients in the same world are informed of changes to that world.
     map_changes_sender: broadcast::Sender<maps::Modification>,
     map_changes_receiver: broadcast::Receiver<maps::Modification>,
-    /// Set used to track of the coordinates of chunks that this handler's remote client has loaded. Stored as a vector
-    /// so that chunk coordinate pairs can stored in from least to most recently loaded.
-    remote_loaded_chunk_coords: Vec<ChunkCoords>
+    /// Set used to track of the coordinates of chunks that have been provided to this handler's remote client (and not
+    /// since unloaded).
+    remote_loaded_chunk_coords: HashSet<ChunkCoords>,
+    /// The request number of the most recently processed [`messages::ToServer::MoveMyEntity`] message. Movement
+    /// requests with a number less than or equal to this are considered stale and ignored.
+    last_processed_request: Option<u32>,
+    /// Limits the rate at which messages from the remote client are handled.
+    rate_limiter: RateLimiter,
+    /// Requests to place a bomb are rejected should the player already have this many bombs placed.
+    max_bombs_per_player: u32,
+    /// Whether tiles within sight of the player entity are marked as seen (see [`Config::fog_of_war`]).
+    fog_of_war: bool,
+    /// The zstd compression level used when saving chunks that are no longer needed to the database.
+    chunk_compression_level: Option<i32>,
+    /// Used to close the connection should the remote client stop sending messages.
+    idle_timer: IdleTimer,
+    /// Global chat messages are rejected (see [`messages::RejectionReason::CannotAffordGlobalChat`]) should the player
+    /// have fewer than this many [`GLOBAL_CHAT_COST_GEM`] gems.
+    global_chat_gem_cost: u32,
+    /// The quests that the player has accepted but not yet completed. These are only kept in memory so are lost
+    /// should the player disconnect.
+    active_quests: Vec<quests::Quest>,
+    /// The remote client's ID, known once the 'hello' message has been received and the player's entity loaded or
+    /// created.
+    client_id: Option<Id>,
+    /// The achievements unlocked by the player, loaded from the database once the player's client ID is known. Newly
+    /// unlocked achievements are written to the database immediately (see [`Self::handle_game_events`]).
+    achievements: AchievementChecker,
+    /// The coordinates of the chunks visited by the player entity in this world, loaded from the database once the
+    /// player's client ID is known. The player's explored chunks count (see
+    /// [`shared::stats::PlayerStats::chunks_explored`]) is incremented upon visiting a chunk not in this set, which is
+    /// then saved to the database straight away (see [`Self::save_explored_chunk`]).
+    visited_chunks: HashSet<ChunkCoords>,
+    /// When the player last performed each action that has a cooldown (e.g. placing a bomb).
+    cooldowns: Cooldowns
 }
 
 impl Handler {
@@ -80,7 +212,7 @@ impl Handler {
                     }
                 }
 
-                log::info!("Client disconnected: {}", self.address);
+                tracing::info!("Client disconnected: {}", self.address);
             }
 
             Err(e) => {
@@ -90,13 +222,78 @@ impl Handler {
     }
 
     /// This function is to be called after the WebSocket connection handshake finishes. It is the role of this function
-    /// to complete the exchange of 'hello' and 'welcome' messages between client and server before passing control onto
-    /// the [`Self::handle_established_connection`] method.
+    /// to complete the exchange of 'hello', 'join world', 'set name', and 'welcome' messages between client and server
+    /// before passing control onto the [`Self::handle_established_connection`] method. Connections from clients using a
+    /// different protocol version, asking to join an unknown world, or choosing an unacceptable name are closed
+    /// immediately (after informing the client of the problem where possible).
     async fn handle_websocket_connection(&mut self, mut ws: Connection) -> Result<()> {
         // Expect a 'hello' message from the client:
 
-        if let Some(messages::ToServer::Hello { client_id_option }) = ws.receive().await? {
-            let (client_id, player_id, player_entity) = {
+        if let Some(messages::ToServer::Hello { protocol_version, client_id_option }) = ws.receive().await? {
+            // Reject clients using an incompatible protocol version:
+
+            let accepted = protocol_version == shared::PROTOCOL_VERSION;
+            ws.send(&messages::FromServer::HelloAck { accepted, server_version: shared::PROTOCOL_VERSION }).await?;
+
+            if !accepted {
+                self.log_warn(&format!(
+                    "Client protocol version {} does not match server protocol version {} - closing connection",
+                    protocol_version,
+                    shared::PROTOCOL_VERSION
+                ));
+                return ws.close().await.map_err(Into::into);
+            }
+
+            // Provide the client with the names of the available worlds for as long as it asks for them, then expect
+            // it to choose which world to join:
+
+            loop {
+                match ws.receive().await? {
+                    Some(messages::ToServer::ListWorlds) => {
+                        ws.send(&messages::FromServer::AvailableWorlds(self.worlds.names())).await?;
+                    }
+
+                    Some(messages::ToServer::JoinWorld(world)) => {
+                        if self.join_world(&world) {
+                            break;
+                        }
+
+                        self.log_warn(&format!(
+                            "Client asked to join unknown world \"{}\" - closing connection",
+                            world
+                        ));
+                        return ws.close().await.map_err(Into::into);
+                    }
+
+                    _ => {
+                        self.log_error("Did not receive 'join world' message after accepting 'hello' message");
+                        return ws.close().await.map_err(Into::into);
+                    }
+                }
+            }
+
+            // Expect the client to choose a name for their player:
+
+            let name = match ws.receive().await? {
+                Some(messages::ToServer::SetName(name)) => name,
+                _ => {
+                    self.log_error("Did not receive 'set name' message after receiving 'join world' message");
+                    return ws.close().await.map_err(Into::into);
+                }
+            };
+
+            // The name remains reserved until this function returns (i.e. the player has disconnected and their entity
+            // has been removed from the map) so that no other player joining at the same time can also use it:
+            let _name_reservation = match self.reserve_name(&name) {
+                Ok(reservation) => reservation,
+                Err(reason) => {
+                    self.log_warn(&format!("Rejected name \"{}\" as {} - closing connection", name, reason));
+                    ws.send(&messages::FromServer::NameRejected { reason }).await?;
+                    return ws.close().await.map_err(Into::into);
+                }
+            };
+
+            let (client_id, player_id, mut player_entity) = {
                 let mut db = self.db_pool.acquire().await?;
 
                 if let Some(client_id) = client_id_option {
@@ -104,7 +301,9 @@ impl Handler {
 
                     // Get the client their existing player entity (if any) from database:
 
-                    if let Some((entity_id, entity)) = entities::player_from_database(client_id, &mut db).await? {
+                    if let Some((entity_id, entity)) =
+                        entities::player_from_database(client_id, &self.world, &mut db).await?
+                    {
                         (client_id, entity_id, entity)
                     }
                     else {
@@ -113,7 +312,15 @@ impl Handler {
                             client_id
                         ));
 
-                        let (entity_id, entity) = entities::new_player_in_database(client_id, &mut db).await?;
+                        let spawn_point = self.game_map.lock().spawn_point();
+                        let (entity_id, entity) = entities::new_player_in_database(
+                            client_id,
+                            name.clone(),
+                            spawn_point,
+                            &self.world,
+                            &mut db
+                        )
+                        .await?;
                         (client_id, entity_id, entity)
                     }
                 }
@@ -123,29 +330,55 @@ impl Handler {
 
                     // Create a new entity for this client and insert into the database:
 
-                    let (new_entity_id, new_entity) = entities::new_player_in_database(new_id, &mut db).await?;
+                    let spawn_point = self.game_map.lock().spawn_point();
+                    let (new_entity_id, new_entity) =
+                        entities::new_player_in_database(new_id, name.clone(), spawn_point, &self.world, &mut db)
+                            .await?;
                     (new_id, new_entity_id, new_entity)
                 }
             };
 
+            // Returning players may choose a different name each time they play:
+            player_entity.name = name;
+
+            self.client_id = Some(client_id);
+            self.achievements =
+                achievements::achievements_from_database(client_id, &mut *self.db_pool.acquire().await?).await?;
+            self.visited_chunks =
+                entities::explored_chunks_from_database(client_id, &self.world, &mut *self.db_pool.acquire().await?)
+                    .await?;
+
             // Send a 'welcome' message to the client:
             ws.send(&messages::FromServer::Welcome {
                 version: shared::VERSION.to_string(),
                 your_client_id: client_id,
-                your_entity_with_id: (player_id, player_entity.clone())
+                your_entity_with_id: (player_id, player_entity.clone()),
+                fog_of_war: self.fog_of_war
             })
             .await?;
 
-            // Provide all the chunks surrounding the player entity plus any entities that may be in those chunks:
+            ws.send(&messages::FromServer::SafeZones(self.safe_zones.zones().to_vec())).await?;
+
+            // Provide all the chunks within view of the player entity plus any entities that may be in those chunks:
 
-            let chunks_and_entities = self
-                .provide_chunks_at_and_surrounding_with_entities(player_entity.pos.as_chunk_coords(), player_id)
-                .await?;
+            tracing::Span::current().record("entity_id", tracing::field::display(player_id));
+
+            let chunks_and_entities = self.update_chunks_in_view_with_entities(player_entity.pos, player_id).await?;
 
             for msg in chunks_and_entities {
                 ws.send(&msg).await?;
             }
 
+            // The chunk that the player entity begins in counts as visited:
+            let start_chunk_coords = player_entity.pos.as_chunk_coords();
+            let explored_event = explore_chunk(&mut self.visited_chunks, &mut player_entity, start_chunk_coords);
+            if explored_event.is_some() {
+                self.save_explored_chunk(start_chunk_coords);
+            }
+            for msg in self.handle_game_events(player_id, &explored_event.into_iter().collect::<Vec<_>>()) {
+                ws.send(&msg).await?;
+            }
+
             // Place this client's player entity on the game map:
             self.game_map.lock().add_entity(player_id, player_entity);
 
@@ -161,18 +394,16 @@ impl Handler {
                 self.chunk_not_needed(*coords).await?;
             }
 
-            // Remove this client's player entity from the game world and update database with changes to said entity:
-            let entity_option = self.game_map.lock().remove_entity(player_id);
-            if let Some(player_entity) = entity_option {
-                {
-                    let mut db = self.db_pool.acquire().await?;
-                    entities::update_database_for_player(&player_entity, client_id, &mut db).await?;
-                }
+            // Leave any party the player is in (disbanding it should they be its leader) and withdraw any invites sent
+            // to or by the player:
+            self.leave_party(player_id);
+            self.parties.lock().remove_invites_involving(player_id);
 
-                // Inform other tasks that an entity has been removed from the game map:
-                let modification_msg =
-                    maps::Modification::EntityRemoved(player_id, player_entity.pos.as_chunk_coords());
-                self.map_changes_sender.send(modification_msg).unwrap();
+            // Remove this client's player entity from the game world and update database with changes to said entity:
+            if let Some(player_entity) = self.remove_player_entity(player_id) {
+                let mut db = self.db_pool.acquire().await?;
+                entities::update_database_for_player(&player_entity, client_id, &self.world, &mut db).await?;
+                achievements::update_database_for_achievements(&self.achievements, client_id, &mut db).await?;
             }
 
             result
@@ -188,11 +419,58 @@ impl Handler {
     async fn handle_established_connection(&mut self, ws: &mut Connection, player_id: Id) -> Result<()> {
         loop {
             // Wait for incoming messages on both the WebSocket connection and the world modifications channel (or close
-            // connection on Ctrl-C signal):
+            // connection on Ctrl-C signal). The connection is closed should the client not send any messages before the
+            // idle timeout elapses:
             tokio::select!(
-                res = ws.receive() => {
+                res = tokio::time::timeout(self.idle_timer.remaining(Instant::now()), ws.receive()) => {
+                    let res = match res {
+                        Ok(res) => res,
+                        Err(_) => {
+                            self.log_warn(&format!(
+                                "Closing connection as no messages were received for {} seconds",
+                                self.idle_timer.timeout().as_secs()
+                            ));
+                            ws.close_with_code(CloseCode::Normal).await?;
+                            break;
+                        }
+                    };
+
                     if let Some(msg) = res? {
                         self.log(&format!("Message received: {}", msg));
+                        self.idle_timer.reset(Instant::now());
+
+                        // Close the connection normally should the client indicate that it is disconnecting (the
+                        // player entity is removed & saved by the caller):
+                        if msg == messages::ToServer::Disconnect {
+                            ws.close_with_code(CloseCode::Normal).await?;
+                            break;
+                        }
+
+                        // Drop the message should the client be sending messages too quickly, closing the connection
+                        // should they continue to do so. A dropped movement request is still answered with the
+                        // entity's authoritative position so that the client does not wait on it indefinitely:
+
+                        match self.rate_limiter.check(Instant::now()) {
+                            RateLimitOutcome::Allowed => {}
+
+                            RateLimitOutcome::Dropped => {
+                                self.log_warn(&format!("Rate limit exceeded so dropping message: {}", msg));
+
+                                if let messages::ToServer::MoveMyEntity { request_number, .. } = msg {
+                                    ws.send(&self.movement_confirmation(player_id, request_number)).await?;
+                                }
+                                continue;
+                            }
+
+                            RateLimitOutcome::ViolationLimitExceeded => {
+                                self.log_warn(&format!(
+                                    "Closing connection as rate limit was exceeded {} times in quick succession",
+                                    self.rate_limiter.recent_violations()
+                                ));
+                                ws.close_with_code(CloseCode::Policy).await?;
+                                break;
+                            }
+                        }
 
                         // Handle and respond to received message:
 
@@ -212,7 +490,18 @@ impl Handler {
                 res = self.map_changes_receiver.recv() => {
                     match res {
                         Ok(modification) => {
-                            if let Some(response) = self.handle_map_change(modification).await {
+                            let responses = match modification {
+                                maps::Modification::EntityDied { entity_id, respawn_at, gems_dropped }
+                                    if entity_id == player_id =>
+                                {
+                                    self.player_died(player_id, respawn_at, gems_dropped).await?
+                                }
+                                modification => {
+                                    self.handle_map_change(modification, player_id).await.into_iter().collect()
+                                }
+                            };
+
+                            for response in responses {
                                 self.log(&format!("Informing client of change to game world: {}", response));
                                 ws.send(&response).await?;
                             }
@@ -248,22 +537,91 @@ impl Handler {
                 Ok(vec![])
             }
 
+            // Players cannot change their name once the connection is established:
+            messages::ToServer::SetName(_) => {
+                self.log_warn(&format!("Received unexpected 'set name' message: {}", msg));
+                Ok(vec![])
+            }
+
+            messages::ToServer::ListWorlds => Ok(vec![messages::FromServer::AvailableWorlds(self.worlds.names())]),
+
+            // Players cannot move to another world once the connection is established:
+            messages::ToServer::JoinWorld(_) => {
+                self.log_warn(&format!("Received unexpected 'join world' message: {}", msg));
+                Ok(vec![])
+            }
+
+            messages::ToServer::Disconnect => {
+                // Disconnection is handled by the main connection loop so no response is produced:
+                self.log("Client is disconnecting");
+                Ok(vec![])
+            }
+
+            // Echo pings back immediately so that the client can measure round-trip time:
+            messages::ToServer::Ping { sequence, sent_at_ms } => {
+                Ok(vec![messages::FromServer::Pong { sequence, sent_at_ms }])
+            }
+
             messages::ToServer::MoveMyEntity { request_number, direction } => {
+                if let Some(last_request_number) = self.last_processed_request {
+                    if request_number <= last_request_number {
+                        self.log_warn(&format!(
+                            "Ignoring out of order movement request #{} (last processed was #{})",
+                            request_number, last_request_number
+                        ));
+                        return Ok(vec![self.movement_confirmation(player_id, request_number)]);
+                    }
+                }
+                self.last_processed_request = Some(request_number);
+
                 let mut responses = Vec::new();
 
                 // TODO: Prevent player exceeding movement rate.
 
-                let movement_option = self.game_map.lock().move_entity_towards(player_id, direction);
+                let mut movement_option = self.game_map.lock().move_entity_towards(player_id, direction);
 
-                if let Some(EntityMovement { old_position, new_position, smashed_tile_option }) = movement_option {
-                    // If moving into a new chunk, ensure chunks adjacent to the destination chunk are loaded and create
-                    // message(s) to provide them to the client:
-                    if old_position.as_chunk_coords() != new_position.as_chunk_coords() {
-                        let msgs = self
-                            .provide_chunks_at_and_surrounding_with_entities(new_position.as_chunk_coords(), player_id)
-                            .await?;
+                // The number of tiles moved (more than one should the entity slide across ice) and the index of the
+                // responses at which the movement is confirmed to the remote client:
+                let mut tiles_moved = 0;
+                let mut confirmation_index = None;
+
+                // The number of gem drops picked up along the way, each of which is removed from the map:
+                let mut gem_drops_picked_up = 0;
+
+                while let Some(EntityMovement { old_position, new_position, smashed_tile_option }) = movement_option {
+                    // Clients only ever specify a direction so an entity can never move more than a single tile at
+                    // once (sliding across ice is made up of separate single tile movements):
+                    debug_assert_eq!(old_position.manhattan_distance(new_position), 1);
+                    tiles_moved += 1;
 
+                    // If moving into a new chunk, ensure chunks now in view are loaded and create message(s) to provide
+                    // them to the client (as well as messages to unload chunks that are no longer in view):
+                    let mut events = Vec::new();
+
+                    if old_position.as_chunk_coords() != new_position.as_chunk_coords() {
+                        let msgs = self.update_chunks_in_view_with_entities(new_position, player_id).await?;
                         responses.extend(msgs);
+
+                        let explored_event = match self.game_map.lock().entity_by_id_mut(player_id) {
+                            Some(entity) => {
+                                explore_chunk(&mut self.visited_chunks, entity, new_position.as_chunk_coords())
+                            }
+                            None => None
+                        };
+                        if explored_event.is_some() {
+                            self.save_explored_chunk(new_position.as_chunk_coords());
+                        }
+                        events.extend(explored_event);
+                    }
+
+                    if let Some(entity) = self.game_map.lock().entity_by_id_mut(player_id) {
+                        entity.stats.tiles_walked += 1;
+                    }
+
+                    // The remote client marks the tiles now within sight as seen itself so only the server's copy of
+                    // the map need be updated:
+                    if self.fog_of_war {
+                        self.game_map.lock().mark_tiles_seen_around(new_position);
                     }
 
                     // Inform other tasks of the entity's movement:
@@ -276,120 +634,209 @@ impl Handler {
                         })
                         .unwrap();
 
-                    // Confirm to the remote client that the movement could go ahead:
-                    responses.push(messages::FromServer::YourEntityMoved { request_number, new_position });
+                    // The remote client is later told of the entity's final position (i.e. after any sliding):
+                    confirmation_index.get_or_insert(responses.len());
 
                     if let Some(smashed_tile) = smashed_tile_option {
                         self.log(&format!("Smashed tile {:?} at {}", smashed_tile, new_position));
 
-                        // If the smashed tile yields gems, calculate a quantity within the determined range, provide
-                        // that quantity of gems to the player on the server side, and send a message to the remote
-                        // client informing them of how many more gems they now have:
+                        let msg_option = collect_gems_from_smashed_tile(
+                            &mut self.game_map.lock(),
+                            player_id,
+                            smashed_tile,
+                            new_position
+                        );
+                        let msg_option = msg_option.and_then(|msg| self.share_collected_gems(player_id, msg));
 
-                        if let Some(gem_yield) = smashed_tile.get_gem_yield() {
-                            // Random gem quantity within the range specified by the yield specific by the tile type:
-                            let quantity_increase = rand::thread_rng()
-                                .gen_range(gem_yield.minimum_quantity..(gem_yield.maximum_quantity + 1));
+                        events.push(GameEvent::RockMined);
+                        events.extend(msg_option.iter().filter_map(gems_event));
 
-                            // Increase gem quantity on the server side:
-                            if let Some(entity) = self.game_map.lock().entity_by_id_mut(player_id) {
-                                entity.gem_collection.increase_quantity(gem_yield.gem, quantity_increase);
-                            }
+                        if let Some(msg) = msg_option {
+                            self.log(&format!("Obtained gems: {}", msg));
+                            responses.push(msg);
+                        }
+                    }
 
-                            // Produce message to send to the remote client to inform them of how many more gems they
-                            // now have:
-                            responses.push(messages::FromServer::YouCollectedGems {
-                                gem_type: gem_yield.gem,
-                                quantity_increase
-                            });
+                    let gem_drop_option = self.game_map.lock().take_gem_drop_at(new_position);
 
-                            self.log(&format!(
-                                "Obtained an additional {} gems of type {:?}",
-                                quantity_increase, gem_yield.gem
-                            ));
-                        }
+                    if let Some(gem_drop) = gem_drop_option {
+                        responses.extend(self.pick_up_gem_drop(player_id, new_position, gem_drop));
+                        gem_drops_picked_up += 1;
                     }
-                }
 
-                if responses.is_empty() {
-                    // The `responses` vector will only be empty if the movement was not allowed. In that case, inform
-                    // the remote client:
+                    responses.extend(self.handle_game_events(player_id, &events));
+
+                    // An entity that moved on to ice continues to slide in the same direction until it is no longer on
+                    // ice, is blocked, or has slid the maximum distance:
+                    movement_option = {
+                        let mut map = self.game_map.lock();
+                        let slides = tiles_moved <= mechanics::MAX_ICE_SLIDE
+                            && map.ice_slide_destination(new_position, direction).is_some();
 
-                    let new_position = self.game_map.lock().entity_by_id(player_id).unwrap().pos;
-                    Ok(vec![messages::FromServer::YourEntityMoved { request_number, new_position }])
+                        if slides {
+                            map.move_entity_towards(player_id, direction)
+                        }
+                        else {
+                            None
+                        }
+                    };
                 }
-                else {
-                    // The `responses` vector will be populated only if the movement could go ahead. If it did then a
-                    // message will be sent to all tasks informing them of the entity movement. That message isn't
-                    // however relevant to the task that sent it so immediately receive and discard:
-                    self.map_changes_receiver.recv().await.unwrap();
+
+                // Inform the remote client of the entity's authoritative position, which is unchanged should the
+                // movement not have been allowed:
+                let new_position = self.game_map.lock().entity_by_id(player_id).unwrap().pos;
+                let confirmation = messages::FromServer::YourEntityMoved { request_number, new_position };
+
+                if let Some(index) = confirmation_index {
+                    responses.insert(index, confirmation);
+
+                    // A message is sent to all tasks informing them of each tile moved (and of each gem drop picked
+                    // up). Those messages aren't however relevant to the task that sent them so immediately receive
+                    // and discard:
+                    for _ in 0..(tiles_moved + gem_drops_picked_up) {
+                        self.map_changes_receiver.recv().await.unwrap();
+                    }
+
+                    responses.extend(self.enter_portal(player_id, new_position, direction).await?);
 
                     Ok(responses)
                 }
+                else {
+                    Ok(vec![confirmation])
+                }
             }
 
             messages::ToServer::PlaceBomb => {
-                // Get player's position & check if the player actually posses a bomb to place:
-                let (can_place_bomb, pos) = self
-                    .game_map
-                    .lock()
-                    .entity_by_id(player_id)
-                    .map(|player| (player.item_inventory.has_how_many(items::QuantitativeItem::Bomb) >= 1, player.pos))
-                    .unwrap_or((false, TileCoords { x: 0, y: 0 }));
-
-                if can_place_bomb {
-                    // Place the bomb (server-side):
-                    self.game_map.lock().set_bomb_at(pos, player_id);
-
-                    // Inform other tasks that a bomb has been placed:
-                    self.map_changes_sender.send(maps::Modification::BombPlaced(pos, player_id)).unwrap();
-
-                    // The client that placed the bomb obviously does not need to be informed by the server that a bomb
-                    // has been placed so immediately discarded map modification message on this task:
-                    self.map_changes_receiver.recv().await.unwrap();
+                if let Some(rejection) = self.cooldown_rejection(ActionKind::PlaceBomb) {
+                    return Ok(vec![rejection]);
+                }
+
+                let max_bombs = self.max_bombs_per_player;
+
+                // Provided the player actually possesses a bomb to place, is not within a safe zone, and has not
+                // reached the limit on placed bombs, place the bomb (server-side), remove it from the
+                // player's inventory, and update their bombs placed count. This is all done while the
+                // map is locked so that the count cannot drift from the bombs on the map:
+                let placement = {
+                    let mut map = self.game_map.lock();
+
+                    let player_option = map
+                        .entity_by_id(player_id)
+                        .filter(|player| player.item_inventory.has_how_many(items::QuantitativeItem::Bomb) >= 1)
+                        .map(|player| (player.pos, player.bombs_placed_count));
+
+                    player_option.map(|(pos, bombs_placed_count)| {
+                        if self.safe_zones.contains(pos) {
+                            return Err(messages::RejectionReason::InSafeZone { position: pos });
+                        }
 
-                    // Remove the placed bomb from the player's inventory and update bombs placed count:
-                    if let Some(player) = self.game_map.lock().entity_by_id_mut(player_id) {
-                        player.item_inventory.take_quantity(items::QuantitativeItem::Bomb, 1);
-                        player.bombs_placed_count += 1;
+                        if bombs_placed_count >= max_bombs as i32 {
+                            return Err(messages::RejectionReason::BombLimitReached {
+                                limit: max_bombs,
+                                position: pos
+                            });
+                        }
+
+                        map.set_bomb_at(pos, player_id);
+
+                        if let Some(player) = map.entity_by_id_mut(player_id) {
+                            player.item_inventory.take_quantity(items::QuantitativeItem::Bomb, 1);
+                            player.bombs_placed_count += 1;
+                            player.stats.bombs_placed += 1;
+                        }
+
+                        Ok(pos)
+                    })
+                };
+
+                match placement {
+                    Some(Ok(pos)) => {
+                        self.cooldowns.performed(ActionKind::PlaceBomb, Instant::now());
+
+                        // Inform other tasks that a bomb has been placed:
+                        self.map_changes_sender.send(maps::Modification::BombPlaced(pos, player_id)).unwrap();
+
+                        // The client that placed the bomb obviously does not need to be informed by the server that a
+                        // bomb has been placed so immediately discarded map modification message on this task:
+                        self.map_changes_receiver.recv().await.unwrap();
+
+                        Ok(vec![])
                     }
-                }
 
-                Ok(vec![])
+                    Some(Err(reason)) => {
+                        self.log_warn(&format!("Rejected request to place bomb: {}", reason));
+                        Ok(vec![messages::FromServer::ActionRejected { reason }])
+                    }
+
+                    None => Ok(vec![])
+                }
             }
 
             messages::ToServer::DetonateBombs => {
-                // Remove bombs from map server-side and update player's bombs placed count:
-                {
+                // Remove bombs from map server-side and update player's bombs placed count (while the map remains
+                // locked so that the count cannot drift from the bombs on the map). Rocks caught in the blasts are
+                // smashed with any gems they yield going to the player, and other bombs placed by the player that are
+                // caught in the blasts detonate in turn. Clients smash the same tiles locally upon being informed of
+                // the detonation so tile change messages need not be sent. Monsters caught in the blasts are killed:
+                let (gem_msgs, smashed_count, killed_monsters) = {
                     let mut map = self.game_map.lock();
 
                     let coords = map.entity_by_id(player_id).map(|e| e.pos.as_chunk_coords()).unwrap_or_default();
-                    let detonated_count = map.take_bombs_placed_by_in_and_around_chunk(player_id, coords).len();
+                    let detonated_positions = map.take_detonated_bombs(player_id, coords);
 
                     if let Some(entity) = map.entity_by_id_mut(player_id) {
-                        entity.bombs_placed_count -= detonated_count as i32;
+                        entity.bombs_placed_count -= detonated_positions.len() as i32;
+                        entity.stats.bombs_detonated += detonated_positions.len() as u64;
                     }
-                }
+
+                    let smashed = map.smash_tiles_around_bombs(&detonated_positions);
+                    let responses = smashed
+                        .iter()
+                        .filter_map(|(position, tile)| {
+                            collect_gems_from_smashed_tile(&mut map, player_id, *tile, *position)
+                        })
+                        .collect::<Vec<_>>();
+
+                    (responses, smashed.len(), map.kill_monsters_around_bombs(&detonated_positions))
+                };
 
                 // Inform other tasks of detonated bombs:
 
                 self.map_changes_sender.send(maps::Modification::BombsDetonated(player_id)).unwrap();
                 self.map_changes_receiver.recv().await.unwrap();
 
-                Ok(vec![])
+                // Inform all tasks (including this one) of killed monsters so that clients unload them:
+                for (monster_id, chunk_coords) in &killed_monsters {
+                    self.map_changes_sender
+                        .send(maps::Modification::MonsterRemoved(*monster_id, *chunk_coords))
+                        .unwrap();
+                }
+
+                let mut responses: Vec<messages::FromServer> =
+                    gem_msgs.into_iter().filter_map(|msg| self.share_collected_gems(player_id, msg)).collect();
+
+                let events: Vec<GameEvent> = responses
+                    .iter()
+                    .filter_map(gems_event)
+                    .chain((0..smashed_count).map(|_| GameEvent::RockMined))
+                    .chain(killed_monsters.iter().map(|_| GameEvent::MonsterKilled))
+                    .collect();
+                responses.extend(self.handle_game_events(player_id, &events));
+
+                Ok(responses)
             }
 
             messages::ToServer::PurchaseSingleItem(item) => {
                 let (cost_gem, cost_quantity) = item.get_price();
 
                 if let Some(entity) = self.game_map.lock().entity_by_id_mut(player_id) {
-                    // If the player has enough gems...
-                    if entity.gem_collection.get_quantity(cost_gem) >= cost_quantity {
-                        // Remove the required number of gems:
-                        entity.gem_collection.decrease_quantity(cost_gem, cost_quantity);
-                        // Give them their item:
-                        entity.item_inventory.give(item);
+                    // Remove the required number of gems provided the player has enough:
+                    if let Err(err) = entity.gem_collection.try_subtract(cost_gem, cost_quantity) {
+                        self.log_warn(&format!("Cannot purchase {:?} as {}", item, err));
+                        return Ok(vec![messages::FromServer::PurchaseFailed(err)]);
                     }
+                    // Give them their item:
+                    entity.item_inventory.give(item);
                 }
 
                 Ok(vec![])
@@ -397,32 +844,525 @@ impl Handler {
 
             messages::ToServer::PurchaseItemQuantity { item, quantity } => {
                 let (cost_gem, single_cost_quantity) = item.get_price();
-                let total_cost_quantity = single_cost_quantity * quantity;
+                let total_cost_quantity = single_cost_quantity.saturating_mul(quantity);
 
                 if let Some(entity) = self.game_map.lock().entity_by_id_mut(player_id) {
-                    // If the player has enough gems for the specified quantity of items...
-                    if entity.gem_collection.get_quantity(cost_gem) >= total_cost_quantity {
-                        // Remove the spent gems:
-                        entity.gem_collection.decrease_quantity(cost_gem, total_cost_quantity);
-                        // Give the player their quantity of items:
-                        entity.item_inventory.give_quantity(item, quantity);
+                    if !entity.item_inventory.can_add(item, quantity) {
+                        self.log_warn(&format!("Cannot purchase {} of {:?} as inventory is full", quantity, item));
+
+                        let reason =
+                            messages::RejectionReason::InventoryFull { max_slots: entity.item_inventory.max_slots };
+                        return Ok(vec![messages::FromServer::ActionRejected { reason }]);
+                    }
+
+                    if !entity.item_inventory.can_carry(item, quantity) {
+                        self.log_warn(&format!("Cannot purchase {} of {:?} as inventory is too heavy", quantity, item));
+
+                        let reason =
+                            messages::RejectionReason::InventoryTooHeavy { max_weight: items::MAX_INVENTORY_WEIGHT };
+                        return Ok(vec![messages::FromServer::ActionRejected { reason }]);
+                    }
+
+                    // Remove the spent gems provided the player has enough for the specified quantity of items:
+                    if let Err(err) = entity.gem_collection.try_subtract(cost_gem, total_cost_quantity) {
+                        self.log_warn(&format!("Cannot purchase {} of {:?} as {}", quantity, item, err));
+                        return Ok(vec![messages::FromServer::PurchaseFailed(err)]);
+                    }
+                    // Give the player their quantity of items:
+                    entity.item_inventory.give_quantity(item, quantity);
+                }
+
+                Ok(vec![])
+            }
+
+            messages::ToServer::RequestEntityInfo(entity_id) => {
+                let entity_option = self.game_map.lock().entity_by_id(entity_id).cloned();
+
+                // Only provide information about entities that are within the client's loaded chunks:
+                match entity_option {
+                    Some(entity) if self.remote_loaded_chunk_coords.contains(&entity.pos.as_chunk_coords()) => {
+                        Ok(vec![messages::FromServer::EntityInfo(entity_id, entity)])
+                    }
+                    _ => Ok(vec![])
+                }
+            }
+
+            messages::ToServer::ChatMessage { text, channel } => {
+                if !messages::is_valid_chat_message(&text) {
+                    self.log_warn("Ignoring chat message that is either blank or too long");
+                    return Ok(vec![]);
+                }
+
+                let mut responses = Vec::new();
+
+                // Global chat messages must be paid for before being sent:
+                if channel == messages::ChatChannel::Global && self.global_chat_gem_cost > 0 {
+                    let payment_result = self.game_map.lock().entity_by_id_mut(player_id).map(|entity| {
+                        entity.gem_collection.try_subtract(GLOBAL_CHAT_COST_GEM, self.global_chat_gem_cost)
+                    });
+
+                    match payment_result {
+                        Some(Ok(())) => responses.push(messages::FromServer::YouSpentGems {
+                            gem_type: GLOBAL_CHAT_COST_GEM,
+                            quantity_decrease: self.global_chat_gem_cost
+                        }),
+                        Some(Err(err)) => {
+                            self.log_warn(&format!("Cannot send global chat message as {}", err));
+
+                            let reason = messages::RejectionReason::CannotAffordGlobalChat(err);
+                            return Ok(vec![messages::FromServer::ActionRejected { reason }]);
+                        }
+                        None => return Ok(vec![])
+                    }
+                }
+
+                let sender_name =
+                    self.game_map.lock().entity_by_id(player_id).map(|entity| entity.name.clone()).unwrap_or_default();
+
+                // Inform other tasks of the chat message so that they may relay it to their clients (should those
+                // clients be within range of a local message):
+                self.map_changes_sender
+                    .send(maps::Modification::ChatMessage { sender_entity_id: player_id, sender_name, text, channel })
+                    .unwrap();
+
+                // The sender's own client need not be sent their message so immediately receive and discard:
+                self.map_changes_receiver.recv().await.unwrap();
+
+                Ok(responses)
+            }
+
+            messages::ToServer::SetFacialExpression(expression) => {
+                // The change is made only to the in-memory entity which is written to the database along with the rest
+                // of the entity's state when the player disconnects or the game world is saved:
+                let chunk_coords_option = self.game_map.lock().entity_by_id_mut(player_id).map(|entity| {
+                    entity.facial_expression = expression;
+                    entity.pos.as_chunk_coords()
+                });
+
+                if let Some(chunk_coords) = chunk_coords_option {
+                    // Inform other tasks of the change (discarding the message on this task as the client has already
+                    // updated its own player entity's expression):
+                    self.map_changes_sender
+                        .send(maps::Modification::EntityChangedExpression {
+                            entity_id: player_id,
+                            chunk_coords,
+                            expression
+                        })
+                        .unwrap();
+                    self.map_changes_receiver.recv().await.unwrap();
+                }
+
+                Ok(vec![])
+            }
+
+            messages::ToServer::Interact { tile } => {
+                let sign_text_option = read_sign(&self.game_map.lock(), player_id, tile);
+
+                if let Some(text) = sign_text_option {
+                    return Ok(vec![messages::FromServer::ShowSignText { text }]);
+                }
+
+                // The chest is opened and its gems given to the player while the map is locked so that should multiple
+                // players interact with the same chest at once, only one of them receives its gems:
+                let msg_option = open_chest(&mut self.game_map.lock(), player_id, tile);
+
+                if let Some(msg) = &msg_option {
+                    self.log(&format!("Opened chest at {} - {}", tile, msg));
+
+                    // Inform all tasks of the opened chest (including this task so that its remote client is also sent
+                    // the change):
+                    self.map_changes_sender.send(maps::Modification::TileChanged(tile, Tile::ChestOpened)).unwrap();
+                }
+
+                let msg_option = msg_option.and_then(|msg| self.share_collected_gems(player_id, msg));
+
+                let events: Vec<GameEvent> = msg_option.iter().filter_map(gems_event).collect();
+                let event_msgs = self.handle_game_events(player_id, &events);
+
+                Ok(msg_option.into_iter().chain(event_msgs).collect())
+            }
+
+            messages::ToServer::UseItem(item) => {
+                let action_option = ActionKind::of_using(item);
+
+                if let Some(rejection) = action_option.and_then(|action| self.cooldown_rejection(action)) {
+                    return Ok(vec![rejection]);
+                }
+
+                // As with facial expressions, only the in-memory entity is changed (which is later written to the
+                // database). The client applies the same change to its own copy of the entity so no response is needed:
+                let used = self.game_map.lock().entity_by_id_mut(player_id).map(|entity| entity.use_item(item));
+
+                if let (Some(true), Some(action)) = (used, action_option) {
+                    self.cooldowns.performed(action, Instant::now());
+                }
+
+                if used == Some(false) {
+                    self.log_warn(&format!(
+                        "Cannot use {:?} as player does not have one or it would have no effect",
+                        item
+                    ));
+                }
+
+                Ok(vec![])
+            }
+
+            messages::ToServer::AcceptQuest(quest_id) => {
+                if self.active_quests.iter().any(|quest| quest.id == quest_id) {
+                    self.log_warn(&format!("Cannot accept quest #{} as it is already active", quest_id));
+                    return Ok(vec![]);
+                }
+
+                match quests::quest_by_id(quest_id) {
+                    Some(quest) => {
+                        self.log(&format!("Accepted {}", quest));
+                        self.active_quests.push(quest.clone());
+                        Ok(vec![messages::FromServer::QuestUpdate(quest)])
+                    }
+                    None => {
+                        self.log_warn(&format!("Cannot accept quest #{} as no such quest exists", quest_id));
+                        Ok(vec![])
+                    }
+                }
+            }
+
+            messages::ToServer::RequestStats => {
+                let stats_option = self.game_map.lock().entity_by_id(player_id).map(|entity| entity.stats);
+                Ok(stats_option.map(messages::FromServer::YourStats).into_iter().collect())
+            }
+
+            messages::ToServer::RequestLeaderboard => {
+                // The gem collections of online players are taken from the game map as the database rows of those
+                // players may not be up to date:
+                let online = self.game_map.lock().player_gem_collections();
+
+                let result = match self.db_pool.acquire().await {
+                    Ok(mut db) => leaderboard::leaderboard_from_database(online, &self.world, &mut db).await,
+                    Err(e) => Err(e)
+                };
+
+                // Failing to produce the leaderboard is not reason enough to close the connection:
+                match result {
+                    Ok(entries) => Ok(vec![messages::FromServer::Leaderboard(entries)]),
+                    Err(e) => {
+                        self.log_warn(&format!("Failed to fetch leaderboard from database - {}", e));
+                        Ok(vec![])
+                    }
+                }
+            }
+
+            messages::ToServer::InviteToParty(invitee_id) => {
+                if invitee_id == player_id || self.game_map.lock().entity_by_id(invitee_id).is_none() {
+                    self.log_warn(&format!(
+                        "Cannot invite {} to party as they are not another online player",
+                        invitee_id
+                    ));
+                    return Ok(vec![]);
+                }
+
+                let result = self.parties.lock().invite(player_id, invitee_id);
+
+                match result {
+                    Ok(()) => {
+                        self.log(&format!("Invited {} to party", invitee_id));
+
+                        // Inform the invited player's task (the message is not relevant to this task so is immediately
+                        // received and discarded):
+                        self.map_changes_sender
+                            .send(maps::Modification::PartyInvite {
+                                inviter_entity_id: player_id,
+                                invitee_entity_id: invitee_id
+                            })
+                            .unwrap();
+                        self.map_changes_receiver.recv().await.unwrap();
+
+                        Ok(vec![])
+                    }
+                    Err(reason) => {
+                        self.log_warn(&format!("Rejected request to invite {} to party: {}", invitee_id, reason));
+                        Ok(vec![messages::FromServer::ActionRejected { reason }])
+                    }
+                }
+            }
+
+            messages::ToServer::AcceptPartyInvite(inviter_id) => {
+                let result = self.parties.lock().accept_invite(player_id, inviter_id).cloned();
+
+                match result {
+                    Ok(party) => {
+                        self.log(&format!("Joined party {} led by {}", party.id, party.leader));
+
+                        // Inform the tasks of all party members (including this task) of the new member:
+                        self.map_changes_sender
+                            .send(maps::Modification::PartyUpdated {
+                                leader_entity_id: party.leader,
+                                member_entity_ids: party.members
+                            })
+                            .unwrap();
+
+                        Ok(vec![])
+                    }
+                    Err(reason) => {
+                        self.log_warn(&format!(
+                            "Rejected request to accept party invite from {}: {}",
+                            inviter_id, reason
+                        ));
+                        Ok(vec![messages::FromServer::ActionRejected { reason }])
                     }
                 }
+            }
+
+            messages::ToServer::LeaveParty => {
+                if !self.leave_party(player_id) {
+                    self.log_warn("Cannot leave party as player is not in one");
+                }
+
+                Ok(vec![])
+            }
+
+            messages::ToServer::Emote(emote) => {
+                if let Some(rejection) = self.cooldown_rejection(ActionKind::Emote) {
+                    return Ok(vec![rejection]);
+                }
+
+                let chunk_coords_option =
+                    self.game_map.lock().entity_by_id(player_id).map(|entity| entity.pos.as_chunk_coords());
+
+                if let Some(chunk_coords) = chunk_coords_option {
+                    self.cooldowns.performed(ActionKind::Emote, Instant::now());
+
+                    // Inform all tasks of the emote (including this task so that its remote client animates its own
+                    // player entity only once the emote is accepted):
+                    self.map_changes_sender
+                        .send(maps::Modification::EntityEmote { entity_id: player_id, chunk_coords, emote })
+                        .unwrap();
+                }
 
                 Ok(vec![])
             }
         }
     }
 
+    /// Create a message informing the remote client of the authoritative position of its player entity in response to
+    /// the movement request with the given number. This is sent even should the request be ignored so that the client
+    /// can reconcile its predicted position and stop waiting for a response to that request.
+    fn movement_confirmation(&self, player_id: Id, request_number: u32) -> messages::FromServer {
+        let new_position = self.game_map.lock().entity_by_id(player_id).unwrap().pos;
+        messages::FromServer::YourEntityMoved { request_number, new_position }
+    }
+
+    /// Returns a message rejecting the specified action should it be requested before its cooldown since the player
+    /// last performed it has passed.
+    fn cooldown_rejection(&self, action: ActionKind) -> Option<messages::FromServer> {
+        let reason = self.cooldowns.check(action, Instant::now()).err()?;
+        self.log_warn(&format!("Rejected request to {}: {}", action, reason));
+
+        Some(messages::FromServer::ActionRejected { reason })
+    }
+
+    /// Update the player's active quests, achievements & gems collected statistic based on the given events, producing
+    /// messages informing the remote client of any progress made towards their quests and of newly unlocked
+    /// achievements. Newly unlocked achievements are saved to the database straight away (in a separate task) so that
+    /// they are not lost should the server be stopped before the player disconnects.
+    fn handle_game_events(&mut self, player_id: Id, events: &[GameEvent]) -> Vec<messages::FromServer> {
+        let gems_collected: u64 = events
+            .iter()
+            .map(|event| match event {
+                GameEvent::GemsCollected { quantity, .. } => u64::from(*quantity),
+                _ => 0
+            })
+            .sum();
+
+        if gems_collected > 0 {
+            if let Some(entity) = self.game_map.lock().entity_by_id_mut(player_id) {
+                entity.stats.gems_collected_total += gems_collected;
+            }
+        }
+
+        let quest_events: Vec<QuestEvent> = events.iter().filter_map(|event| quest_event(*event)).collect();
+        let mut msgs = self.progress_quests(player_id, &quest_events);
+
+        let completed_quests = msgs
+            .iter()
+            .filter(|msg| matches!(msg, messages::FromServer::QuestUpdate(quest) if quest.is_complete()))
+            .count();
+
+        for event in events.iter().copied().chain((0..completed_quests).map(|_| GameEvent::QuestCompleted)) {
+            for achievement in self.achievements.check(event) {
+                self.log(&format!(
+                    "Unlocked achievement \"{}\" ({} of {} unlocked)",
+                    achievement,
+                    self.achievements.unlocked_count(),
+                    shared::achievements::Achievement::all().count()
+                ));
+                msgs.push(messages::FromServer::AchievementUnlocked(achievement));
+            }
+        }
+
+        let any_unlocked = msgs.iter().any(|msg| matches!(msg, messages::FromServer::AchievementUnlocked(_)));
+
+        if let (true, Some(client_id)) = (any_unlocked, self.client_id) {
+            let db_pool = self.db_pool.clone();
+            let achievements = self.achievements.clone();
+            let address = self.address;
+
+            tokio::spawn(async move {
+                let result = match db_pool.acquire().await {
+                    Ok(mut db) => {
+                        achievements::update_database_for_achievements(&achievements, client_id, &mut db).await
+                    }
+                    Err(e) => Err(e)
+                };
+
+                if let Err(e) = result {
+                    tracing::error!("Handler for client {} -- Failed to save unlocked achievements: {}", address, e);
+                }
+            });
+        }
+
+        msgs
+    }
+
+    /// Should the player be in a party, split the gems described by the given 'you collected gems' message evenly
+    /// among the party's members (see [`parties::split_gems`]). As the player's entity will have already been given
+    /// all of the gems, the other members' shares are transferred from it and their tasks informed. The shares of
+    /// members not on the map are kept by the player. Returns the message adjusted to describe only the gems the
+    /// player kept, or `None` should that be nothing. Other messages are returned unchanged.
+    fn share_collected_gems(&self, player_id: Id, msg: messages::FromServer) -> Option<messages::FromServer> {
+        let (gem, quantity, source_tile) = match msg {
+            messages::FromServer::YouCollectedGems { gem_type, quantity_increase, source_tile } => {
+                (gem_type, quantity_increase, source_tile)
+            }
+            other => return Some(other)
+        };
+
+        let shares = match self.parties.lock().party_of(player_id) {
+            Some(party) => parties::split_gems(party, quantity),
+            None => return Some(msg)
+        };
+
+        let mut kept = quantity;
+        let mut map = self.game_map.lock();
+
+        for (member_id, share) in shares {
+            if member_id == player_id || share == 0 || map.entity_by_id(member_id).is_none() {
+                continue;
+            }
+
+            kept -= share;
+
+            if let Some(player) = map.entity_by_id_mut(player_id) {
+                player.gem_collection.try_subtract(gem, share).unwrap();
+            }
+            if let Some(member) = map.entity_by_id_mut(member_id) {
+                member.gem_collection.increase_quantity(gem, share);
+                member.stats.gems_collected_total += u64::from(share);
+            }
+
+            self.map_changes_sender
+                .send(maps::Modification::PartyGemsShared {
+                    recipient_entity_id: member_id,
+                    gem,
+                    quantity: share,
+                    source_tile
+                })
+                .unwrap();
+        }
+
+        (kept > 0).then_some(messages::FromServer::YouCollectedGems {
+            gem_type: gem,
+            quantity_increase: kept,
+            source_tile
+        })
+    }
+
+    /// Reserve the given name for this handler's player should it be acceptable (see
+    /// [`messages::ToServer::SetName`]), otherwise return the reason that it may not be used. Whether the name is
+    /// taken is checked while the map is locked to reserve it so that two players cannot both be given the same name.
+    fn reserve_name(&self, name: &str) -> std::result::Result<NameReservation, String> {
+        shared::maps::entities::validate_player_name(name).map_err(|err| err.to_string())?;
+
+        if self.game_map.lock().reserve_player_name(name) {
+            Ok(NameReservation { game_map: Arc::clone(&self.game_map), name: name.to_string() })
+        }
+        else {
+            Err("name is already in use by another player".to_string())
+        }
+    }
+
+    /// Remove the player from their party (if they are in one) and inform the tasks of the affected players. Returns
+    /// whether the player was in a party.
+    fn leave_party(&self, player_id: Id) -> bool {
+        let change_option = self.parties.lock().leave(player_id);
+
+        match change_option {
+            Some(PartyChange::Disbanded { former_members }) => {
+                self.log("Left party which was disbanded as a result");
+
+                let modification = maps::Modification::PartyLeft { member_entity_ids: former_members };
+                self.map_changes_sender.send(modification).unwrap();
+            }
+
+            Some(PartyChange::MemberLeft { member, party }) => {
+                self.log(&format!("Left party {} leaving {} remaining members", party.id, party.members.len()));
+
+                let left = maps::Modification::PartyLeft { member_entity_ids: vec![member] };
+                let updated = maps::Modification::PartyUpdated {
+                    leader_entity_id: party.leader,
+                    member_entity_ids: party.members
+                };
+
+                self.map_changes_sender.send(left).unwrap();
+                self.map_changes_sender.send(updated).unwrap();
+            }
+
+            None => return false
+        }
+
+        true
+    }
+
+    /// Record the given events against the player's active quests, producing a [`messages::FromServer::QuestUpdate`]
+    /// message for each quest that progressed. Quests whose objectives are now complete have their rewards given to
+    /// the player's entity and are no longer considered active.
+    fn progress_quests(&mut self, player_id: Id, events: &[QuestEvent]) -> Vec<messages::FromServer> {
+        let mut progressed = Vec::new();
+
+        for quest in &mut self.active_quests {
+            // Every event is recorded (rather than stopping at the first that counts towards the objective):
+            if events.iter().filter(|event| quest.record(**event)).count() > 0 {
+                progressed.push(quest.clone());
+            }
+        }
+
+        for quest in progressed.iter().filter(|quest| quest.is_complete()) {
+            self.log(&format!("Completed {} so delivering reward", quest));
+
+            if let Some(entity) = self.game_map.lock().entity_by_id_mut(player_id) {
+                entity.gem_collection.merge(&quest.reward);
+            }
+        }
+
+        self.active_quests.retain(|quest| !quest.is_complete());
+
+        progressed.into_iter().map(messages::FromServer::QuestUpdate).collect()
+    }
+
     /// May produce a message that is to be sent to the client based on map modification messages received from other
-    /// connection handling tasks.
-    async fn handle_map_change(&mut self, modification: maps::Modification) -> Option<messages::FromServer> {
+    /// connection handling tasks. The given player entity ID is that of this task's client.
+    async fn handle_map_change(
+        &mut self, modification: maps::Modification, player_id: Id
+    ) -> Option<messages::FromServer> {
         match modification {
             maps::Modification::TileChanged(position, tile) => {
                 let is_position_loaded = self.remote_loaded_chunk_coords.contains(&position.as_chunk_coords());
                 is_position_loaded.then(|| messages::FromServer::ChangeTile(position, tile))
             }
 
+            // Only the movement of this task's own entity upon respawning is received by this task, which is instead
+            // handled by `Self::player_died`:
+            maps::Modification::EntityMoved { entity_id, .. } if entity_id == player_id => None,
+
             maps::Modification::EntityMoved { entity_id, old_position, new_position, direction } => {
                 let was_in_loaded = self.remote_loaded_chunk_coords.contains(&old_position.as_chunk_coords());
                 let is_in_loaded = self.remote_loaded_chunk_coords.contains(&new_position.as_chunk_coords());
@@ -473,37 +1413,126 @@ impl Handler {
                     }
                 })
             }
+
+            maps::Modification::ChatMessage { sender_entity_id, sender_name, text, channel } => {
+                let in_range = match channel {
+                    messages::ChatChannel::Global => true,
+                    messages::ChatChannel::Local => {
+                        let map = self.game_map.lock();
+                        let sender_chunk_option = map.entity_by_id(sender_entity_id).map(|e| e.pos.as_chunk_coords());
+                        let own_chunk_option = map.entity_by_id(player_id).map(|e| e.pos.as_chunk_coords());
+
+                        matches!(
+                            (sender_chunk_option, own_chunk_option),
+                            (Some(sender_chunk), Some(own_chunk))
+                                if sender_chunk.chebyshev_distance(own_chunk) <= messages::LOCAL_CHAT_RADIUS
+                        )
+                    }
+                };
+
+                in_range.then_some(messages::FromServer::ChatMessage { sender_entity_id, sender_name, text, channel })
+            }
+            maps::Modification::Announcement { text } => Some(messages::FromServer::ServerAnnouncement { text }),
+            maps::Modification::EntityChangedExpression { entity_id, chunk_coords, expression } => self
+                .remote_loaded_chunk_coords
+                .contains(&chunk_coords)
+                .then_some(messages::FromServer::EntityChangedExpression { entity_id, expression }),
+
+            maps::Modification::MonsterAdded(monster_id) => {
+                self.game_map.lock().monster_by_id(monster_id).and_then(|monster| {
+                    self.remote_loaded_chunk_coords
+                        .contains(&monster.pos.as_chunk_coords())
+                        .then(|| messages::FromServer::ProvideMonster(monster_id, monster.clone()))
+                })
+            }
+
+            maps::Modification::MonsterMoved { monster_id, old_position, new_position, direction } => {
+                let was_in_loaded = self.remote_loaded_chunk_coords.contains(&old_position.as_chunk_coords());
+                let is_in_loaded = self.remote_loaded_chunk_coords.contains(&new_position.as_chunk_coords());
+
+                if was_in_loaded && is_in_loaded {
+                    Some(messages::FromServer::MoveMonster(monster_id, new_position, direction))
+                }
+                else if was_in_loaded {
+                    Some(messages::FromServer::ShouldUnloadMonster(monster_id))
+                }
+                else if is_in_loaded {
+                    self.game_map
+                        .lock()
+                        .monster_by_id(monster_id)
+                        .map(|monster| messages::FromServer::ProvideMonster(monster_id, monster.clone()))
+                }
+                else {
+                    None
+                }
+            }
+
+            maps::Modification::MonsterRemoved(monster_id, chunk_coords) => self
+                .remote_loaded_chunk_coords
+                .contains(&chunk_coords)
+                .then_some(messages::FromServer::ShouldUnloadMonster(monster_id)),
+
+            maps::Modification::EntityHealthChanged { entity_id, chunk_coords, health } => self
+                .remote_loaded_chunk_coords
+                .contains(&chunk_coords)
+                .then_some(messages::FromServer::EntityHealthChanged { entity_id, health }),
+
+            maps::Modification::PartyInvite { inviter_entity_id, invitee_entity_id } => {
+                (invitee_entity_id == player_id).then_some(messages::FromServer::InvitedToParty { inviter_entity_id })
+            }
+            maps::Modification::PartyUpdated { leader_entity_id, member_entity_ids } => member_entity_ids
+                .contains(&player_id)
+                .then_some(messages::FromServer::PartyUpdated { leader_entity_id, member_entity_ids }),
+            maps::Modification::PartyLeft { member_entity_ids } => {
+                member_entity_ids.contains(&player_id).then_some(messages::FromServer::PartyLeft)
+            }
+            maps::Modification::PartyGemsShared { recipient_entity_id, gem, quantity, source_tile } => {
+                (recipient_entity_id == player_id).then_some(messages::FromServer::YouCollectedGems {
+                    gem_type: gem,
+                    quantity_increase: quantity,
+                    source_tile
+                })
+            }
+            maps::Modification::EntityDamaged { entity_id, amount, source } => {
+                (entity_id == player_id).then_some(messages::FromServer::ReceivedDamage { amount, source })
+            }
+
+            maps::Modification::EntityEmote { entity_id, chunk_coords, emote } => self
+                .remote_loaded_chunk_coords
+                .contains(&chunk_coords)
+                .then_some(messages::FromServer::EntityEmote { entity_id, emote }),
+
+            // The death of this task's own entity is handled by `Self::player_died` while other clients are informed
+            // of the dead entity's movement & restored health by separate modifications:
+            maps::Modification::EntityDied { .. } => None
         }
     }
 
     /// Will begin by ensuring the chunk at the specified coordinates is loaded (i.e. if not already in-memory within
     /// the game map object, it will either be loaded from disk or newly generated before being added to the game map).
     /// Messages will then be created to provide the remote client with the chunk as well as any entities in said chunk.
-    /// This method will add the given chunk coordinates to the set of remote loaded chunk coordinates however it is
-    /// the responsiblity of the caller to actually send the messages returned over the network.
+    /// No messages are created should the remote client already have the chunk loaded. This method will add the given
+    /// chunk coordinates to the set of remote loaded chunk coordinates however it is the responsiblity of the caller to
+    /// actually send the messages returned over the network. Should fog of war be enabled, tiles in the chunk within
+    /// [`shared::maps::SIGHT_RADIUS`] of the given player position are marked as seen before the chunk is provided.
     async fn provide_chunk_with_entities(
-        &mut self, coords: ChunkCoords, player_id: Id
+        &mut self, coords: ChunkCoords, player_id: Id, player_pos: TileCoords
     ) -> Result<Vec<messages::FromServer>> {
         let mut msgs = Vec::new();
 
-        // Load new chunk & entities:
-
-        let search = self.remote_loaded_chunk_coords.iter().position(|x| coords == *x);
-
-        if let Some(index) = search {
-            // Remote client already has the chunk with those chunk coordinates loaded. Ensure those coordinates are
-            // positioned at the end of `remote_loaded_chunk_coords` so that those coordinates will not be unloaded yet:
-
-            self.remote_loaded_chunk_coords.remove(index);
-            self.remote_loaded_chunk_coords.push(coords);
-        }
-        else {
+        if !self.remote_loaded_chunk_coords.contains(&coords) {
             // The remote client does not already have the chunk loaded so prepare messages to provide the client with
             // the chunks and any entities in that chunk:
 
-            let chunk =
-                maps::chunks::get_or_load_or_generate_chunk(self.db_pool.acquire().await?, &self.game_map, coords)
-                    .await;
+            let mut chunk = maps::chunks::get_or_load_or_generate_chunk(&self.db_pool, &self.game_map, coords).await?;
+
+            if self.fog_of_war {
+                // Mark tiles as seen in both the game map's copy of the chunk (so that they are saved) and the copy
+                // provided to the remote client:
+                self.game_map.lock().mark_tiles_seen_around(player_pos);
+                chunk.mark_tiles_seen_around(coords, player_pos);
+            }
+
             msgs.push(messages::FromServer::ProvideChunk(coords, chunk));
 
             // Get entities in the chunk but filter out this task's own player entity:
@@ -514,47 +1543,190 @@ impl Handler {
                 msgs.push(messages::FromServer::ProvideEntity(entity_id, entity));
             }
 
-            self.remote_loaded_chunk_coords.push(coords);
+            for (monster_id, monster) in self.game_map.lock().monsters_in_chunk(coords) {
+                msgs.push(messages::FromServer::ProvideMonster(monster_id, monster));
+            }
+
+            self.remote_loaded_chunk_coords.insert(coords);
             self.game_map.lock().chunk_in_use(coords);
         }
 
-        // If too many chunks now loaded, unload oldest chunk & entities:
+        Ok(msgs)
+    }
+
+    /// Call [`Self::provide_chunk_with_entities`] for all chunk coordinates within [`VIEW_RADIUS`] of the chunk
+    /// containing the specified player position and create messages to unload any chunks (and the entities within
+    /// them) that the remote client has loaded but which are outside of that radius.
+    async fn update_chunks_in_view_with_entities(
+        &mut self, player_pos: TileCoords, player_id: Id
+    ) -> Result<Vec<messages::FromServer>> {
+        let centre = player_pos.as_chunk_coords();
+        let mut msgs = Vec::new();
+
+        for x_offset in -VIEW_RADIUS..VIEW_RADIUS + 1 {
+            for y_offset in -VIEW_RADIUS..VIEW_RADIUS + 1 {
+                let msg = self
+                    .provide_chunk_with_entities(
+                        ChunkCoords { x: centre.x + x_offset, y: centre.y + y_offset },
+                        player_id,
+                        player_pos
+                    )
+                    .await?;
+
+                msgs.extend(msg);
+            }
+        }
 
-        if self.remote_loaded_chunk_coords.len() > MAX_LOADED_CHUNKS_PER_CLIENT {
-            let coords = self.remote_loaded_chunk_coords.remove(0);
+        let out_of_view: Vec<ChunkCoords> = self
+            .remote_loaded_chunk_coords
+            .iter()
+            .filter(|coords| (coords.x - centre.x).abs() > VIEW_RADIUS || (coords.y - centre.y).abs() > VIEW_RADIUS)
+            .copied()
+            .collect();
 
+        for coords in out_of_view {
             for (entity_id, _) in self.game_map.lock().entities_in_chunk(coords).into_iter() {
                 msgs.push(messages::FromServer::ShouldUnloadEntity(entity_id));
             }
 
+            for (monster_id, _) in self.game_map.lock().monsters_in_chunk(coords) {
+                msgs.push(messages::FromServer::ShouldUnloadMonster(monster_id));
+            }
+
             msgs.push(messages::FromServer::ShouldUnloadChunk(coords));
+
+            self.remote_loaded_chunk_coords.remove(&coords);
             self.chunk_not_needed(coords).await?;
         }
 
         Ok(msgs)
     }
 
-    /// Call [`Self::provide_chunk_with_entities`] for the specified chunk coordinates as well as the 9 surrounding set
-    /// of coordinates.
-    async fn provide_chunks_at_and_surrounding_with_entities(
-        &mut self, coords: ChunkCoords, player_id: Id
+    /// Should the given position of this task's player entity be a portal (see [`Tile::Portal`]), warp the entity to
+    /// that portal's destination. Messages are created to provide the remote client with the chunks in view of the
+    /// destination followed by a [`messages::FromServer::YourEntityMoved`] message with the request number
+    /// [`messages::SERVER_MOVE_REQUEST_NUMBER`]. The entity is left on the portal should the destination be blocked.
+    async fn enter_portal(
+        &mut self, player_id: Id, position: TileCoords, direction: Direction
     ) -> Result<Vec<messages::FromServer>> {
-        let mut msgs = Vec::new();
+        let destination = match self.game_map.lock().portal_destination_at(position) {
+            Some(destination) => destination,
+            None => return Ok(Vec::new())
+        };
+
+        // The destination may be far from the portal so ensure that its chunk is loaded before checking whether the
+        // destination is blocked:
+        maps::chunks::get_or_load_or_generate_chunk(&self.db_pool, &self.game_map, destination.as_chunk_coords())
+            .await?;
 
-        for x_offset in -1..2 {
-            for y_offset in -1..2 {
-                let msg = self
-                    .provide_chunk_with_entities(
-                        ChunkCoords { x: coords.x + x_offset, y: coords.y + y_offset },
-                        player_id
-                    )
-                    .await?;
+        if self.game_map.lock().teleport_entity(player_id, destination).is_none() {
+            self.log_warn(&format!(
+                "Cannot enter portal at {} as its destination {} is blocked",
+                position, destination
+            ));
+            return Ok(Vec::new());
+        }
+        self.log(&format!("Entered portal at {} leading to {}", position, destination));
+
+        let mut responses = self.player_relocated(player_id, destination).await?;
+
+        // Inform other tasks of the entity's movement (immediately receiving and discarding the same message as it is
+        // not relevant to this task):
+        self.map_changes_sender
+            .send(maps::Modification::EntityMoved {
+                entity_id: player_id,
+                old_position: position,
+                new_position: destination,
+                direction
+            })
+            .unwrap();
+        self.map_changes_receiver.recv().await.unwrap();
 
-                msgs.extend(msg);
+        responses.push(messages::FromServer::YourEntityMoved {
+            request_number: messages::SERVER_MOVE_REQUEST_NUMBER,
+            new_position: destination
+        });
+
+        Ok(responses)
+    }
+
+    /// Give the player the gems of the given gem drop (already removed from the map) that was at the specified
+    /// position and inform other tasks that the tile the drop replaced has been restored. Returns messages informing
+    /// the remote client of the restored tile and of the gems collected. Picking up dropped gems does not count
+    /// towards quests, achievements, or the player's total gems collected.
+    fn pick_up_gem_drop(&self, player_id: Id, position: TileCoords, gem_drop: GemDrop) -> Vec<messages::FromServer> {
+        self.log(&format!("Picked up gems {} dropped at {}", gem_drop.gems, position));
+
+        if let Some(entity) = self.game_map.lock().entity_by_id_mut(player_id) {
+            entity.gem_collection.merge(&gem_drop.gems);
+        }
+
+        self.map_changes_sender.send(maps::Modification::TileChanged(position, gem_drop.replaced_tile)).unwrap();
+
+        let collected = gems::Gem::all().filter_map(|gem_type| {
+            let quantity_increase = gem_drop.gems.get_quantity(gem_type);
+            (quantity_increase > 0).then_some(messages::FromServer::YouCollectedGems {
+                gem_type,
+                quantity_increase,
+                source_tile: position
+            })
+        });
+
+        std::iter::once(messages::FromServer::ChangeTile(position, gem_drop.replaced_tile)).chain(collected).collect()
+    }
+
+    /// Called once this task's player entity has died and been moved to the given respawn position by the game map
+    /// (see [`ServerMap::damage_player`]). Provides the chunks in view of the respawn position and informs the remote
+    /// client of the death, which is responsible for removing the dropped gems from its copy of the entity's gem
+    /// collection and restoring its health.
+    async fn player_died(
+        &mut self, player_id: Id, respawn_at: TileCoords, gems_dropped: gems::Collection
+    ) -> Result<Vec<messages::FromServer>> {
+        self.log(&format!("Died dropping gems {} and respawned at {}", gems_dropped, respawn_at));
+
+        let mut responses = self.player_relocated(player_id, respawn_at).await?;
+        responses.push(messages::FromServer::YouDied { respawn_at, gems_dropped });
+
+        Ok(responses)
+    }
+
+    /// Should this task's player entity be moved to the given position other than by walking (e.g. by entering a portal
+    /// or respawning), provide the chunks in view of that position and mark the tiles within sight of it as seen.
+    /// Messages informing the remote client of those chunks (and of any resulting game events) are returned.
+    async fn player_relocated(&mut self, player_id: Id, destination: TileCoords) -> Result<Vec<messages::FromServer>> {
+        let mut responses = self.update_chunks_in_view_with_entities(destination, player_id).await?;
+
+        let explored_event = {
+            let mut map = self.game_map.lock();
+
+            if self.fog_of_war {
+                map.mark_tiles_seen_around(destination);
             }
+            match map.entity_by_id_mut(player_id) {
+                Some(entity) => explore_chunk(&mut self.visited_chunks, entity, destination.as_chunk_coords()),
+                None => None
+            }
+        };
+        if explored_event.is_some() {
+            self.save_explored_chunk(destination.as_chunk_coords());
         }
 
-        Ok(msgs)
+        responses.extend(self.handle_game_events(player_id, &explored_event.into_iter().collect::<Vec<_>>()));
+
+        Ok(responses)
+    }
+
+    /// Removes this task's player entity from the game map and informs other tasks of its removal (so that they may
+    /// instruct their remote clients to unload it). The removed entity is returned so that it may be saved.
+    fn remove_player_entity(&mut self, player_id: Id) -> Option<Entity> {
+        let entity_option = self.game_map.lock().remove_entity(player_id);
+
+        if let Some(player_entity) = &entity_option {
+            let modification_msg = maps::Modification::EntityRemoved(player_id, player_entity.pos.as_chunk_coords());
+            self.map_changes_sender.send(modification_msg).unwrap();
+        }
+
+        entity_option
     }
 
     /// Informs the game map that the chunk at the specified chunk coordinates is no longer loaded by this task's
@@ -564,25 +1736,171 @@ impl Handler {
         let unloaded_chunk_option = self.game_map.lock().chunk_not_in_use(coords);
 
         if let Some(unloaded_chunk) = unloaded_chunk_option {
-            maps::chunks::save_chunk(self.db_pool.acquire().await?, coords, &unloaded_chunk).await?;
+            maps::chunks::save_chunk(
+                self.db_pool.acquire().await?,
+                &self.world,
+                coords,
+                &unloaded_chunk,
+                self.chunk_compression_level
+            )
+            .await?;
         }
 
         Ok(())
     }
 
+    /// Record that the player has explored the chunk at the given coordinates in the database (in a separate task) so
+    /// that it is not counted as explored again in later sessions. Does nothing should the client ID not yet be known.
+    fn save_explored_chunk(&self, coords: ChunkCoords) {
+        if let Some(client_id) = self.client_id {
+            let db_pool = self.db_pool.clone();
+            let world = self.world.clone();
+            let address = self.address;
+
+            tokio::spawn(async move {
+                let result = match db_pool.acquire().await {
+                    Ok(mut db) => entities::add_explored_chunk_to_database(client_id, &world, coords, &mut db).await,
+                    Err(e) => Err(e)
+                };
+
+                if let Err(e) = result {
+                    tracing::error!("Handler for client {} -- Failed to save explored chunk: {}", address, e);
+                }
+            });
+        }
+    }
+
+    /// Move this handler to the world with the given name, using that world's game map & map changes channel from then
+    /// on. Returns `false` should no world with that name exist.
+    fn join_world(&mut self, name: &str) -> bool {
+        match self.worlds.get(name) {
+            Some(world) => {
+                self.game_map = Arc::clone(&world.map);
+                self.map_changes_sender = world.map_changes_sender.clone();
+                self.map_changes_receiver = world.map_changes_sender.subscribe();
+                self.world = name.to_string();
+
+                tracing::Span::current().record("world", name);
+                true
+            }
+            None => false
+        }
+    }
+
     fn log(&self, msg: &str) {
-        log::debug!("Handler for client {} -- {}", self.address, msg);
+        tracing::debug!("Handler for client {} -- {}", self.address, msg);
     }
 
     fn log_warn(&self, msg: &str) {
-        log::warn!("Handler for client {} -- {}", self.address, msg);
+        tracing::warn!("Handler for client {} -- {}", self.address, msg);
     }
 
     fn log_error(&self, msg: &str) {
-        log::error!("Handler for client {} -- {}", self.address, msg);
+        tracing::error!("Handler for client {} -- {}", self.address, msg);
+    }
+}
+
+/// A player name reserved on a game map (see [`ServerMap::reserve_player_name`]), which is released when dropped.
+struct NameReservation {
+    game_map: Shared<ServerMap>,
+    name: String
+}
+
+impl Drop for NameReservation {
+    fn drop(&mut self) {
+        self.game_map.lock().release_player_name(&self.name);
+    }
+}
+
+/// Should the given smashed tile yield gems, give the player with the specified ID a random quantity of gems within the
+/// range specified by the tile's yield and produce a message informing the remote client of the gems they collected.
+fn collect_gems_from_smashed_tile(
+    map: &mut ServerMap, player_id: Id, smashed_tile: Tile, source_tile: TileCoords
+) -> Option<messages::FromServer> {
+    let gem_yield = smashed_tile.get_gem_yield()?;
+
+    let quantity_increase = rand::thread_rng().gen_range(gem_yield.minimum_quantity..(gem_yield.maximum_quantity + 1));
+
+    map.entity_by_id_mut(player_id)?.gem_collection.increase_quantity(gem_yield.gem, quantity_increase);
+
+    Some(messages::FromServer::YouCollectedGems { gem_type: gem_yield.gem, quantity_increase, source_tile })
+}
+
+/// Returns the game event corresponding to the given message should it inform the player of gems they collected.
+fn gems_event(msg: &messages::FromServer) -> Option<GameEvent> {
+    match msg {
+        messages::FromServer::YouCollectedGems { gem_type, quantity_increase, .. } => {
+            Some(GameEvent::GemsCollected { gem: *gem_type, quantity: *quantity_increase })
+        }
+        _ => None
     }
 }
 
+/// Should the chunk at the given coordinates not be in the given set of chunks visited this session, add it and
+/// increment the player entity's explored chunks count, producing an event containing the new count.
+fn explore_chunk(
+    visited_chunks: &mut HashSet<ChunkCoords>, player_entity: &mut Entity, coords: ChunkCoords
+) -> Option<GameEvent> {
+    if visited_chunks.insert(coords) {
+        player_entity.stats.chunks_explored += 1;
+        Some(GameEvent::ChunkExplored { total_explored: player_entity.stats.chunks_explored })
+    }
+    else {
+        None
+    }
+}
+
+/// Returns the event that counts towards the objectives of quests (if any) corresponding to the given game event.
+fn quest_event(event: GameEvent) -> Option<QuestEvent> {
+    match event {
+        GameEvent::GemsCollected { gem, quantity } => Some(QuestEvent::GemsCollected { gem, quantity }),
+        GameEvent::RockMined => Some(QuestEvent::RockMined),
+        GameEvent::MonsterKilled => Some(QuestEvent::MonsterKilled),
+        GameEvent::QuestCompleted | GameEvent::ChunkExplored { .. } => None
+    }
+}
+
+/// Open the unopened chest at the given position should it be adjacent to the player's entity, giving the player a
+/// random yield of gems chosen from [`gems::CHEST_LOOT_TABLE`]. Returns a message informing the player of the gems
+/// collected or `None` should there be no such chest.
+fn open_chest(map: &mut ServerMap, player_id: Id, chest_pos: TileCoords) -> Option<messages::FromServer> {
+    if !is_adjacent_to_player(map, player_id, chest_pos) || map.loaded_tile_at(chest_pos) != Some(Tile::Chest) {
+        return None;
+    }
+
+    map.set_loaded_tile_at(chest_pos, Tile::ChestOpened);
+
+    let (gem_type, quantity_increase) = roll_chest_loot(&mut rand::thread_rng());
+    map.entity_by_id_mut(player_id)?.gem_collection.increase_quantity(gem_type, quantity_increase);
+
+    Some(messages::FromServer::YouCollectedGems { gem_type, quantity_increase, source_tile: chest_pos })
+}
+
+/// Returns the text of the sign at the given position should it be adjacent to the player's entity.
+fn read_sign(map: &ServerMap, player_id: Id, sign_pos: TileCoords) -> Option<String> {
+    if !is_adjacent_to_player(map, player_id, sign_pos) {
+        return None;
+    }
+
+    map.sign_text_at(sign_pos).map(str::to_string)
+}
+
+/// Returns `true` should the given position be horizontally or vertically adjacent to the position of the player's
+/// entity.
+fn is_adjacent_to_player(map: &ServerMap, player_id: Id, pos: TileCoords) -> bool {
+    map.entity_by_id(player_id).is_some_and(|player| player.pos.manhattan_distance(pos) == 1)
+}
+
+/// Randomly choose a yield from [`gems::CHEST_LOOT_TABLE`] (based on the weight of each yield) and then a quantity of
+/// gems within that yield's range.
+fn roll_chest_loot(rng: &mut impl Rng) -> (gems::Gem, u32) {
+    let weights = gems::CHEST_LOOT_TABLE.iter().map(|(_, weight)| *weight);
+    let index = WeightedIndex::new(weights).unwrap().sample(rng);
+    let gem_yield = &gems::CHEST_LOOT_TABLE[index].0;
+
+    (gem_yield.gem, rng.gen_range(gem_yield.minimum_quantity..(gem_yield.maximum_quantity + 1)))
+}
+
 #[derive(Error, Debug)]
 enum Error {
     #[error("Networking error - {0}")]
@@ -0,0 +1,157 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant}
+};
+
+/// Violations of the rate limit that occurred longer ago than this are forgotten.
+const VIOLATION_WINDOW: Duration = Duration::from_secs(10);
+
+/// Limits on the rate at which a client may send messages to the server.
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimits {
+    /// The maximum number of tokens that can be held in the bucket (i.e. the largest burst of messages permitted).
+    pub capacity: u32,
+    /// Number of tokens added to the bucket each second.
+    pub refill_per_sec: f32,
+    /// The number of messages that may be dropped within the violation window before the connection is closed.
+    pub max_violations: usize
+}
+
+/// The outcome of a message being checked by a [`RateLimiter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateLimitOutcome {
+    /// The message is within the rate limit and should be handled.
+    Allowed,
+    /// The message exceeds the rate limit and should be dropped.
+    Dropped,
+    /// The message exceeds the rate limit and too many messages have recently been dropped, so the connection should
+    /// be closed.
+    ViolationLimitExceeded
+}
+
+/// Token bucket used to limit the rate at which messages from a single client are handled. Each message consumes a
+/// single token and tokens are replenished at a constant rate up to the capacity of the bucket.
+pub struct RateLimiter {
+    limits: RateLimits,
+    tokens: f32,
+    last_refill: Instant,
+    /// Times at which messages were dropped within the violation window, oldest first.
+    violations: VecDeque<Instant>
+}
+
+impl RateLimiter {
+    /// Create a new rate limiter with a full bucket.
+    pub fn new(limits: RateLimits, now: Instant) -> Self {
+        RateLimiter { limits, tokens: limits.capacity as f32, last_refill: now, violations: VecDeque::new() }
+    }
+
+    /// Attempt to consume a token for a message received at the given time.
+    pub fn check(&mut self, now: Instant) -> RateLimitOutcome {
+        self.refill(now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return RateLimitOutcome::Allowed;
+        }
+
+        // Forget violations that have fallen outside of the window before recording this one:
+
+        while let Some(oldest) = self.violations.front() {
+            if now.saturating_duration_since(*oldest) <= VIOLATION_WINDOW {
+                break;
+            }
+            self.violations.pop_front();
+        }
+
+        self.violations.push_back(now);
+
+        if self.violations.len() > self.limits.max_violations {
+            RateLimitOutcome::ViolationLimitExceeded
+        }
+        else {
+            RateLimitOutcome::Dropped
+        }
+    }
+
+    /// The number of messages dropped within the violation window as of the most recent call to [`Self::check`].
+    pub fn recent_violations(&self) -> usize {
+        self.violations.len()
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f32();
+
+        self.tokens = (self.tokens + elapsed * self.limits.refill_per_sec).min(self.limits.capacity as f32);
+        self.last_refill = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_LIMITS: RateLimits = RateLimits { capacity: 5, refill_per_sec: 2.0, max_violations: 3 };
+
+    #[test]
+    fn burst_up_to_capacity_allowed() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(TEST_LIMITS, start);
+
+        for _ in 0..5 {
+            assert_eq!(limiter.check(start), RateLimitOutcome::Allowed);
+        }
+        assert_eq!(limiter.check(start), RateLimitOutcome::Dropped);
+        assert_eq!(limiter.recent_violations(), 1);
+    }
+
+    #[test]
+    fn excess_messages_dropped_then_connection_closed() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(TEST_LIMITS, start);
+
+        // Simulate a client sending 100 messages within a single millisecond:
+        let outcomes: Vec<_> = (0..100).map(|i| limiter.check(start + Duration::from_micros(i * 10))).collect();
+
+        assert!(outcomes[..5].iter().all(|outcome| *outcome == RateLimitOutcome::Allowed));
+        assert!(outcomes[5..8].iter().all(|outcome| *outcome == RateLimitOutcome::Dropped));
+        assert!(outcomes[8..].iter().all(|outcome| *outcome == RateLimitOutcome::ViolationLimitExceeded));
+    }
+
+    #[test]
+    fn tokens_refill_over_time() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(TEST_LIMITS, start);
+
+        for _ in 0..5 {
+            limiter.check(start);
+        }
+        assert_eq!(limiter.check(start), RateLimitOutcome::Dropped);
+
+        // Refills at 2 tokens per second so one token should be available after half a second:
+        let later = start + Duration::from_millis(500);
+        assert_eq!(limiter.check(later), RateLimitOutcome::Allowed);
+        assert_eq!(limiter.check(later), RateLimitOutcome::Dropped);
+
+        // Bucket should never hold more tokens than its capacity:
+        let much_later = later + Duration::from_secs(60);
+        for _ in 0..5 {
+            assert_eq!(limiter.check(much_later), RateLimitOutcome::Allowed);
+        }
+        assert_eq!(limiter.check(much_later), RateLimitOutcome::Dropped);
+    }
+
+    #[test]
+    fn old_violations_forgotten() {
+        let start = Instant::now();
+        let limits = RateLimits { capacity: 1, refill_per_sec: 0.0, max_violations: 2 };
+        let mut limiter = RateLimiter::new(limits, start);
+
+        limiter.check(start);
+        assert_eq!(limiter.check(start), RateLimitOutcome::Dropped);
+        assert_eq!(limiter.check(start), RateLimitOutcome::Dropped);
+
+        let later = start + VIOLATION_WINDOW + Duration::from_secs(1);
+        assert_eq!(limiter.check(later), RateLimitOutcome::Dropped);
+        assert_eq!(limiter.recent_violations(), 1);
+    }
+}
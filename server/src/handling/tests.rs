@@ -7,44 +7,74 @@ use std::{
 
 use parking_lot::Mutex;
 use shared::{
-    gems, items,
+    achievements::Achievement,
+    gems,
     maps::{
-        entities::{ClothingColour, Direction, Entity, FacialExpression, HairColour, HairStyle, SkinColour},
-        Chunk, ChunkCoords, OffsetCoords, Tile, TileCoords, CHUNK_WIDTH
-    }
+        entities::{Direction, EntityBuilder, FacialExpression, Monster, DEFAULT_MAX_HEALTH},
+        Chunk, ChunkCoords, OffsetCoords, SafeZone, Tile, TileCoords, CHUNK_WIDTH, SIGHT_RADIUS
+    },
+    stats::PlayerStats
 };
 
 use super::*;
+use crate::worlds::DEFAULT_WORLD_NAME;
+
+/// The world other than the default world in which test handlers may be placed.
+const OTHER_WORLD_NAME: &str = "arena";
 
+/// Create a handler in the default world of a registry containing that world & one other.
 async fn make_test_handler() -> Handler {
-    let (map_changes_sender, map_changes_receiver) = broadcast::channel(5);
+    let mut worlds = WorldRegistry::default();
+    worlds.insert(DEFAULT_WORLD_NAME.to_string(), ServerMap::new_with_default_generator(0));
+    worlds.insert(OTHER_WORLD_NAME.to_string(), ServerMap::new_with_default_generator(0));
+
+    let worlds = Arc::new(worlds);
+    let world = worlds.get(DEFAULT_WORLD_NAME).unwrap();
 
     super::Handler {
         address: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0),
         db_pool: sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://").unwrap(),
-        game_map: Arc::new(Mutex::new(ServerMap::new_with_default_generator(0))),
-        map_changes_sender,
-        map_changes_receiver,
-        remote_loaded_chunk_coords: Vec::new()
+        world: DEFAULT_WORLD_NAME.to_string(),
+        game_map: Arc::clone(&world.map),
+        parties: Arc::new(Mutex::new(Parties::default())),
+        // Tests concerned with safe zones add their own so that players may otherwise be placed anywhere:
+        safe_zones: Arc::new(SafeZoneRegistry::new(Vec::new())),
+        map_changes_sender: world.map_changes_sender.clone(),
+        map_changes_receiver: world.map_changes_sender.subscribe(),
+        worlds: Arc::clone(&worlds),
+        remote_loaded_chunk_coords: HashSet::new(),
+        last_processed_request: None,
+        rate_limiter: RateLimiter::new(
+            RateLimits { capacity: 20, refill_per_sec: 10.0, max_violations: 10 },
+            std::time::Instant::now()
+        ),
+        max_bombs_per_player: MAX_BOMBS_PER_PLAYER,
+        fog_of_war: true,
+        chunk_compression_level: Some(maps::chunks::DEFAULT_COMPRESSION_LEVEL),
+        idle_timer: idle_timer::IdleTimer::new(
+            std::time::Duration::from_secs(IDLE_TIMEOUT_SECONDS),
+            std::time::Instant::now()
+        ),
+        global_chat_gem_cost: GLOBAL_CHAT_GEM_COST,
+        active_quests: Vec::new(),
+        // Every achievement is already unlocked so that tests not concerned with achievements are not sent them:
+        client_id: None,
+        achievements: AchievementChecker::from_bitfield(!0),
+        visited_chunks: HashSet::new(),
+        cooldowns: Cooldowns::default()
     }
 }
 
+/// The entity ID of the player of handlers receiving map changes in tests where that player is not involved.
+fn observer_id() -> Id {
+    Id::new(0)
+}
+
 impl Handler {
     fn add_test_entity(&mut self, pos: TileCoords) -> Id {
         let entity_id = crate::id::generate_with_timestamp();
 
-        let entity = Entity {
-            pos,
-            direction: Direction::Down,
-            facial_expression: FacialExpression::Neutral,
-            hair_style: HairStyle::Quiff,
-            clothing_colour: ClothingColour::Grey,
-            skin_colour: SkinColour::Black,
-            hair_colour: HairColour::Black,
-            gem_collection: gems::Collection::default(),
-            item_inventory: items::Inventory::default(),
-            bombs_placed_count: 0
-        };
+        let entity = EntityBuilder::new().pos(pos).build();
         self.game_map.lock().add_entity(entity_id, entity);
 
         entity_id
@@ -56,7 +86,7 @@ impl Handler {
 
     fn add_chunk(&mut self, coords: ChunkCoords, chunk: Chunk) {
         self.game_map.lock().add_chunk(coords, chunk);
-        self.remote_loaded_chunk_coords.push(coords);
+        self.remote_loaded_chunk_coords.insert(coords);
     }
 }
 
@@ -67,7 +97,7 @@ async fn handle_unexpected_hello_msg() {
     let mut handler = make_test_handler().await;
 
     let id = crate::id::generate_random();
-    let msg = messages::ToServer::Hello { client_id_option: None };
+    let msg = messages::ToServer::Hello { protocol_version: shared::PROTOCOL_VERSION, client_id_option: None };
 
     assert!(handler.handle_message(msg, id).await.unwrap().is_empty());
 }
@@ -146,6 +176,310 @@ async fn handle_move_my_entity_blocking() {
     assert!(matches!(other_map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
 }
 
+/// Ensure that a player entity stepping on to a strip of ice slides across it in the same direction (with each tile
+/// moved being broadcast to other tasks) and stops on the open ground beyond, with the client informed only of the
+/// final position.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_move_my_entity_slides_across_ice() {
+    let mut handler = make_test_handler().await;
+    let mut other_map_changes_receiver = handler.map_changes_sender.subscribe();
+
+    let mut chunk = Chunk::default();
+    for x in 6..9 {
+        chunk.set_tile_at_offset(OffsetCoords { x, y: 5 }, Tile::Ice);
+    }
+
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+
+    let msg = messages::ToServer::MoveMyEntity { request_number: 0, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::YourEntityMoved { request_number: 0, new_position: TileCoords { x: 9, y: 5 } }]
+    ));
+    assert_eq!(handler.game_map.lock().entity_by_id(player_id).unwrap().pos, TileCoords { x: 9, y: 5 });
+    assert!(matches!(handler.map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+
+    // One tile stepped on to the ice followed by exactly 3 tiles slid:
+    for x in 5..9 {
+        assert!(matches!(
+            other_map_changes_receiver.try_recv(),
+            Ok(maps::Modification::EntityMoved { old_position, new_position, direction: Direction::Right, .. })
+                if old_position == TileCoords { x, y: 5 } && new_position == TileCoords { x: x + 1, y: 5 }
+        ));
+    }
+    assert!(matches!(other_map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+
+    // Moving off the end of the ice does not slide:
+    let msg = messages::ToServer::MoveMyEntity { request_number: 1, direction: Direction::Right };
+    handler.handle_message(msg, player_id).await.unwrap();
+    assert_eq!(handler.game_map.lock().entity_by_id(player_id).unwrap().pos, TileCoords { x: 10, y: 5 });
+}
+
+/// Ensure that walking on to a gem drop gives the player its gems (without counting them towards the player's total
+/// gems collected), restores the tile that the drop replaced, and informs other tasks of that restored tile.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_move_my_entity_picks_up_gem_drop() {
+    let mut handler = make_test_handler().await;
+    let mut other_map_changes_receiver = handler.map_changes_sender.subscribe();
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+
+    let drop_position = TileCoords { x: 6, y: 5 };
+    let mut dropped = gems::Collection::default();
+    dropped.increase_quantity(gems::Gem::Ruby, 4);
+    assert!(handler.game_map.lock().drop_gems_at(drop_position, dropped));
+
+    let msg = messages::ToServer::MoveMyEntity { request_number: 0, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+    assert!(matches!(
+        responses.as_slice(),
+        [
+            messages::FromServer::YourEntityMoved { request_number: 0, new_position },
+            messages::FromServer::ChangeTile(changed_position, Tile::Grass),
+            messages::FromServer::YouCollectedGems { gem_type: gems::Gem::Ruby, quantity_increase: 4, source_tile }
+        ] if *new_position == drop_position && *changed_position == drop_position && *source_tile == drop_position
+    ));
+
+    {
+        let map = handler.game_map.lock();
+        let player = map.entity_by_id(player_id).unwrap();
+        assert_eq!(player.gem_collection.get_quantity(gems::Gem::Ruby), 4);
+        assert_eq!(player.stats.gems_collected_total, 0);
+
+        assert!(map.gem_drop_at(drop_position).is_none());
+        assert_eq!(map.loaded_tile_at(drop_position), Some(Tile::Grass));
+    }
+
+    assert!(matches!(handler.map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+    assert!(matches!(other_map_changes_receiver.try_recv(), Ok(maps::Modification::EntityMoved { .. })));
+    assert!(matches!(
+        other_map_changes_receiver.try_recv(),
+        Ok(maps::Modification::TileChanged(position, Tile::Grass)) if position == drop_position
+    ));
+}
+
+/// Ensure that every gem drop passed over while sliding across ice is picked up, and that the map changes broadcast
+/// for each tile moved and each drop picked up are all discarded by the task that sent them.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_move_my_entity_picks_up_gem_drops_while_sliding() {
+    let mut handler = make_test_handler().await;
+    let mut other_map_changes_receiver = handler.map_changes_sender.subscribe();
+
+    let mut chunk = Chunk::default();
+    for x in 6..9 {
+        chunk.set_tile_at_offset(OffsetCoords { x, y: 5 }, Tile::Ice);
+    }
+
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+
+    for (x, gem) in [(7, gems::Gem::Emerald), (8, gems::Gem::Diamond)].iter() {
+        let mut dropped = gems::Collection::default();
+        dropped.increase_quantity(*gem, 2);
+        assert!(handler.game_map.lock().drop_gems_at(TileCoords { x: *x, y: 5 }, dropped));
+    }
+
+    let msg = messages::ToServer::MoveMyEntity { request_number: 0, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+    // The slide continues across the ice restored beneath each drop:
+    assert!(matches!(
+        responses.first(),
+        Some(messages::FromServer::YourEntityMoved { request_number: 0, new_position: TileCoords { x: 9, y: 5 } })
+    ));
+    let restored_tiles: Vec<TileCoords> = responses
+        .iter()
+        .filter_map(|msg| match msg {
+            messages::FromServer::ChangeTile(position, Tile::Ice) => Some(*position),
+            _ => None
+        })
+        .collect();
+    assert_eq!(restored_tiles, vec![TileCoords { x: 7, y: 5 }, TileCoords { x: 8, y: 5 }]);
+
+    {
+        let map = handler.game_map.lock();
+        let player = map.entity_by_id(player_id).unwrap();
+        assert_eq!(player.pos, TileCoords { x: 9, y: 5 });
+        assert_eq!(player.gem_collection.get_quantity(gems::Gem::Emerald), 2);
+        assert_eq!(player.gem_collection.get_quantity(gems::Gem::Diamond), 2);
+    }
+
+    // Four tiles moved and two drops picked up:
+    assert!(matches!(handler.map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+    for _ in 0..6 {
+        assert!(other_map_changes_receiver.try_recv().is_ok());
+    }
+    assert!(matches!(other_map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+}
+
+/// Ensure that the client of a player that died is provided the chunks in view of its respawn position (and told to
+/// unload those no longer in view) before being informed of the death itself.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_player_died() {
+    let mut handler = make_test_handler().await;
+
+    // Client has all chunks in view of chunk (0, 0) loaded. Chunks are also marked as in use by some other client so
+    // that they are not saved to the database when unloaded by this task:
+    for x in -VIEW_RADIUS..VIEW_RADIUS + 1 {
+        for y in -VIEW_RADIUS..VIEW_RADIUS + 1 {
+            handler.add_empty_chunk(ChunkCoords { x, y });
+            handler.game_map.lock().chunk_in_use(ChunkCoords { x, y });
+            handler.game_map.lock().chunk_in_use(ChunkCoords { x, y });
+        }
+    }
+
+    // The chunks in view of the spawn point are loaded on the server (but not by the client):
+    let spawn_chunk_x = VIEW_RADIUS * 2 + 1;
+    for x in spawn_chunk_x - VIEW_RADIUS..spawn_chunk_x + VIEW_RADIUS + 1 {
+        for y in -VIEW_RADIUS..VIEW_RADIUS + 1 {
+            handler.game_map.lock().add_chunk(ChunkCoords { x, y }, Chunk::default());
+        }
+    }
+    let spawn_point = TileCoords { x: spawn_chunk_x * CHUNK_WIDTH + 5, y: 5 };
+    handler.game_map.lock().set_spawn_point(spawn_point);
+
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+    handler.game_map.lock().entity_by_id_mut(player_id).unwrap().gem_collection.increase_quantity(gems::Gem::Ruby, 6);
+
+    let modifications = handler.game_map.lock().damage_player(player_id, DEFAULT_MAX_HEALTH);
+    let (respawn_at, gems_dropped) = match modifications.as_slice() {
+        [.., maps::Modification::EntityDied { respawn_at, gems_dropped, .. }, _] => (*respawn_at, gems_dropped.clone()),
+        other => panic!("Unexpected modifications: {:?}", other)
+    };
+    assert_eq!(respawn_at, spawn_point);
+
+    let responses = handler.player_died(player_id, respawn_at, gems_dropped).await.unwrap();
+
+    let provided_chunks = responses.iter().filter(|msg| matches!(msg, messages::FromServer::ProvideChunk(..))).count();
+    let unloaded_chunks =
+        responses.iter().filter(|msg| matches!(msg, messages::FromServer::ShouldUnloadChunk(..))).count();
+    assert_eq!(provided_chunks, ((VIEW_RADIUS * 2 + 1) * (VIEW_RADIUS * 2 + 1)) as usize);
+    assert_eq!(unloaded_chunks, ((VIEW_RADIUS * 2 + 1) * (VIEW_RADIUS * 2 + 1)) as usize);
+
+    match responses.last() {
+        Some(messages::FromServer::YouDied { respawn_at, gems_dropped }) => {
+            assert_eq!(*respawn_at, spawn_point);
+            assert_eq!(gems_dropped.get_quantity(gems::Gem::Ruby), 3);
+        }
+        _ => panic!("Expected 'you died' message last")
+    }
+    assert!(handler.remote_loaded_chunk_coords.iter().all(|coords| coords.x >= spawn_chunk_x - VIEW_RADIUS));
+}
+
+/// Ensure that a player entity moving on to a portal is warped to the portal's destination (with the client informed
+/// using a server-initiated movement message) unless that destination is blocked.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_move_my_entity_through_portal() {
+    let mut handler = make_test_handler().await;
+    let mut other_map_changes_receiver = handler.map_changes_sender.subscribe();
+
+    let mut chunk = Chunk::default();
+    chunk.place_portal(OffsetCoords { x: 5, y: 5 }, TileCoords { x: 10, y: 12 });
+    chunk.place_portal(OffsetCoords { x: 10, y: 11 }, TileCoords { x: 2, y: 2 });
+    chunk.set_tile_at_offset(OffsetCoords { x: 2, y: 2 }, Tile::Water);
+
+    // The client already has every chunk in view loaded:
+    for x in -VIEW_RADIUS..VIEW_RADIUS + 1 {
+        for y in -VIEW_RADIUS..VIEW_RADIUS + 1 {
+            handler.add_empty_chunk(ChunkCoords { x, y });
+        }
+    }
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+    let player_id = handler.add_test_entity(TileCoords { x: 4, y: 5 });
+
+    let moved_positions = |responses: Vec<messages::FromServer>| -> Vec<(u32, TileCoords)> {
+        responses
+            .into_iter()
+            .filter_map(|response| match response {
+                messages::FromServer::YourEntityMoved { request_number, new_position } => {
+                    Some((request_number, new_position))
+                }
+                _ => None
+            })
+            .collect()
+    };
+
+    let msg = messages::ToServer::MoveMyEntity { request_number: 1, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+    // The movement on to the portal is confirmed before the warp to its destination:
+    assert_eq!(
+        moved_positions(responses),
+        vec![(1, TileCoords { x: 5, y: 5 }), (messages::SERVER_MOVE_REQUEST_NUMBER, TileCoords { x: 10, y: 12 })]
+    );
+    assert_eq!(handler.game_map.lock().entity_by_id(player_id).unwrap().pos, TileCoords { x: 10, y: 12 });
+    assert!(matches!(handler.map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+
+    other_map_changes_receiver.recv().await.unwrap();
+    assert!(matches!(
+        other_map_changes_receiver.recv().await.unwrap(),
+        maps::Modification::EntityMoved {
+            old_position: TileCoords { x: 5, y: 5 },
+            new_position: TileCoords { x: 10, y: 12 },
+            ..
+        }
+    ));
+
+    // The destination of this portal is water so the player entity remains on the portal:
+    let msg = messages::ToServer::MoveMyEntity { request_number: 2, direction: Direction::Down };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+    assert_eq!(moved_positions(responses), vec![(2, TileCoords { x: 10, y: 11 })]);
+    assert_eq!(handler.game_map.lock().entity_by_id(player_id).unwrap().pos, TileCoords { x: 10, y: 11 });
+}
+
+/// Ensure that tiles within sight of a moved player entity are marked as seen unless fog of war is disabled.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_move_my_entity_marks_tiles_seen() {
+    for fog_of_war in [true, false] {
+        let mut handler = make_test_handler().await;
+        handler.fog_of_war = fog_of_war;
+
+        handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+        let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+
+        let msg = messages::ToServer::MoveMyEntity { request_number: 0, direction: Direction::Right };
+        handler.handle_message(msg, player_id).await.unwrap();
+
+        let map = handler.game_map.lock();
+        assert_eq!(map.is_tile_seen(TileCoords { x: 6 + SIGHT_RADIUS, y: 5 }), fog_of_war);
+        assert!(!map.is_tile_seen(TileCoords { x: 7 + SIGHT_RADIUS, y: 5 }));
+    }
+}
+
+/// Ensure that moving into a rock tile that yields gems results in the remote client being informed of the type and
+/// quantity of gems collected as well as the position of the smashed rock.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_move_my_entity_collect_gems() {
+    let mut handler = make_test_handler().await;
+
+    let mut chunk = Chunk::default();
+    chunk.set_tile_at_offset(OffsetCoords { x: 6, y: 5 }, Tile::RockRuby);
+
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+
+    let msg = messages::ToServer::MoveMyEntity { request_number: 0, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+    assert_eq!(responses.len(), 2);
+    assert!(matches!(
+        responses[1],
+        messages::FromServer::YouCollectedGems {
+            gem_type: gems::Gem::Ruby,
+            quantity_increase: 1..=3,
+            source_tile: TileCoords { x: 6, y: 5 }
+        }
+    ));
+
+    assert_eq!(handler.game_map.lock().loaded_tile_at(TileCoords { x: 6, y: 5 }), Some(Tile::RockSmashed));
+}
+
 /// Ensure that a task produces an entity moved message to send to its remote client when it receives an entity moved
 /// message on the map modification broadcast channel, provided the entity in question moved within one of the remote
 /// client's loaded chunks.
@@ -164,7 +498,7 @@ async fn handle_entity_moved_within_loaded_chunk() {
     };
 
     assert!(matches!(
-        handler.handle_map_change(modification).await.unwrap(),
+        handler.handle_map_change(modification, observer_id()).await.unwrap(),
         messages::FromServer::MoveEntity(id, TileCoords { x: 6, y: 5 }, _) if id == entity_id
     ));
 }
@@ -186,7 +520,7 @@ async fn handle_entity_moved_into_loaded_chunk() {
     };
 
     assert!(matches!(
-        handler.handle_map_change(modification).await.unwrap(),
+        handler.handle_map_change(modification, observer_id()).await.unwrap(),
         messages::FromServer::ProvideEntity(id, _) if id == entity_id
     ));
 }
@@ -208,7 +542,7 @@ async fn handle_entity_moved_leaving_loaded_chunk() {
     };
 
     assert!(matches!(
-        handler.handle_map_change(modification).await.unwrap(),
+        handler.handle_map_change(modification, observer_id()).await.unwrap(),
         messages::FromServer::ShouldUnloadEntity(id) if id == entity_id
     ));
 }
@@ -228,7 +562,7 @@ async fn handle_entity_moved_outside_loaded_chunk() {
         direction: Direction::Left
     };
 
-    assert!(handler.handle_map_change(modification).await.is_none());
+    assert!(handler.handle_map_change(modification, observer_id()).await.is_none());
 }
 
 /// Ensure that a task produces a provide entity message to send to its remote client when it is informed via the map
@@ -243,7 +577,7 @@ async fn handle_entity_added_within_loaded_chunks() {
     let modification = maps::Modification::EntityAdded(entity_id);
 
     assert!(matches!(
-        handler.handle_map_change(modification).await.unwrap(),
+        handler.handle_map_change(modification, observer_id()).await.unwrap(),
         messages::FromServer::ProvideEntity(id, _) if id == entity_id
     ));
 }
@@ -260,17 +594,1395 @@ async fn handle_entity_removed_within_loaded_chunks() {
     let modification = maps::Modification::EntityRemoved(entity_id, ChunkCoords { x: 0, y: 0 });
 
     assert!(matches!(
-        handler.handle_map_change(modification).await.unwrap(),
+        handler.handle_map_change(modification, observer_id()).await.unwrap(),
         messages::FromServer::ShouldUnloadEntity(id) if id == entity_id
     ));
 }
 
+/// Ensure that monsters spawning and moving within (and out of) the remote client's loaded chunks produce the
+/// appropriate messages, as do changes to the health of player entities within those chunks.
 #[tokio::test(flavor = "multi_thread")]
-async fn handle_smashed_rock_within_loaded_chunks() {
-    // TODO
+async fn handle_monster_modifications() {
+    let mut handler = make_test_handler().await;
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let monster_id = crate::id::generate_random();
+    handler.game_map.lock().add_monster(monster_id, Monster::new(TileCoords { x: 0, y: 5 }));
+
+    assert!(matches!(
+        handler.handle_map_change(maps::Modification::MonsterAdded(monster_id), observer_id()).await.unwrap(),
+        messages::FromServer::ProvideMonster(id, Monster { pos: TileCoords { x: 0, y: 5 }, .. }) if id == monster_id
+    ));
+
+    let moved = |old_x, new_x| maps::Modification::MonsterMoved {
+        monster_id,
+        old_position: TileCoords { x: old_x, y: 5 },
+        new_position: TileCoords { x: new_x, y: 5 },
+        direction: Direction::Left
+    };
+
+    assert!(matches!(
+        handler.handle_map_change(moved(1, 0), observer_id()).await.unwrap(),
+        messages::FromServer::MoveMonster(id, TileCoords { x: 0, y: 5 }, Direction::Left) if id == monster_id
+    ));
+    assert!(matches!(
+        handler.handle_map_change(moved(0, -1), observer_id()).await.unwrap(),
+        messages::FromServer::ShouldUnloadMonster(id) if id == monster_id
+    ));
+    assert!(handler.handle_map_change(moved(-1, -2), observer_id()).await.is_none());
+
+    let entity_id = handler.add_test_entity(TileCoords { x: 3, y: 3 });
+    let modification =
+        maps::Modification::EntityHealthChanged { entity_id, chunk_coords: ChunkCoords { x: 0, y: 0 }, health: 42 };
+
+    assert!(matches!(
+        handler.handle_map_change(modification, observer_id()).await.unwrap(),
+        messages::FromServer::EntityHealthChanged { entity_id: id, health: 42 } if id == entity_id
+    ));
 }
 
+/// Ensure that damage dealt to a player is only reported to that player's client.
 #[tokio::test(flavor = "multi_thread")]
-async fn handle_smashed_rock_outside_loaded_chunks() {
-    // TODO
+async fn handle_entity_damaged() {
+    let mut handler = make_test_handler().await;
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let entity_id = handler.add_test_entity(TileCoords { x: 3, y: 3 });
+    let damaged = || maps::Modification::EntityDamaged {
+        entity_id,
+        amount: 5,
+        source: messages::DamageSource::EnvironmentalHazard
+    };
+
+    assert!(matches!(
+        handler.handle_map_change(damaged(), entity_id).await.unwrap(),
+        messages::FromServer::ReceivedDamage { amount: 5, source: messages::DamageSource::EnvironmentalHazard }
+    ));
+    assert!(handler.handle_map_change(damaged(), observer_id()).await.is_none());
+}
+
+/// Ensure that a 'disconnect' message produces no response and that the subsequent removal of the player entity takes
+/// it off the game map and informs other tasks (which should then instruct their remote clients to unload it).
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_disconnect() {
+    let mut handler = make_test_handler().await;
+    let mut other_handler = make_test_handler().await;
+    other_handler.game_map = Arc::clone(&handler.game_map);
+    other_handler.map_changes_receiver = handler.map_changes_sender.subscribe();
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    other_handler.remote_loaded_chunk_coords.insert(ChunkCoords { x: 0, y: 0 });
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+
+    assert!(handler.handle_message(messages::ToServer::Disconnect, player_id).await.unwrap().is_empty());
+
+    let removed = handler.remove_player_entity(player_id);
+    assert_eq!(removed.unwrap().pos, TileCoords { x: 5, y: 5 });
+    assert!(handler.game_map.lock().entity_by_id(player_id).is_none());
+
+    // The other task should be informed of the removal and produce a message to have its client unload the entity:
+    let change = other_handler.map_changes_receiver.recv().await.unwrap();
+    assert!(matches!(
+        other_handler.handle_map_change(change, observer_id()).await.unwrap(),
+        messages::FromServer::ShouldUnloadEntity(id) if id == player_id
+    ));
+}
+
+/// Ensure that valid chat messages are relayed to other tasks (which produce messages to send to their clients) while
+/// blank and overly long messages are ignored.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_chat_message() {
+    let mut handler = make_test_handler().await;
+    let mut other_handler = make_test_handler().await;
+    other_handler.map_changes_receiver = handler.map_changes_sender.subscribe();
+    other_handler.game_map = Arc::clone(&handler.game_map);
+
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+    let nearby_id = other_handler.add_test_entity(TileCoords { x: 5 + CHUNK_WIDTH, y: 5 });
+    handler.game_map.lock().entity_by_id_mut(player_id).unwrap().name = "Chatty".to_string();
+
+    let msg = messages::ToServer::ChatMessage { text: "Hello!".to_string(), channel: messages::ChatChannel::Local };
+    assert!(handler.handle_message(msg, player_id).await.unwrap().is_empty());
+
+    // Sender's own task should not relay the message back to its client:
+    assert!(matches!(handler.map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+
+    // Other task should relay the message even though it has no chunks loaded:
+    let change = other_handler.map_changes_receiver.recv().await.unwrap();
+    assert!(matches!(
+        other_handler.handle_map_change(change, nearby_id).await.unwrap(),
+        messages::FromServer::ChatMessage { sender_entity_id, sender_name, text, .. }
+            if sender_entity_id == player_id && sender_name == "Chatty" && text == "Hello!"
+    ));
+
+    for text in &["   ".to_string(), "a".repeat(messages::MAX_CHAT_MESSAGE_LENGTH + 1)] {
+        let msg = messages::ToServer::ChatMessage { text: text.clone(), channel: messages::ChatChannel::Local };
+        assert!(handler.handle_message(msg, player_id).await.unwrap().is_empty());
+    }
+
+    assert!(matches!(other_handler.map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+}
+
+/// Names should be rejected should they be invalid, already in use by an online player, or reserved by a player who is
+/// joining (ignoring case). Reserved names should be released once the reservation is dropped.
+#[tokio::test(flavor = "multi_thread")]
+async fn name_rejection_reasons() {
+    let mut handler = make_test_handler().await;
+
+    let reason = |result: std::result::Result<NameReservation, String>| result.err();
+
+    assert!(reason(handler.reserve_name("x")).unwrap().contains("at least"));
+    assert!(reason(handler.reserve_name("gem hunter")).unwrap().contains("letters, digits & underscores"));
+
+    // A name reserved by one player cannot be used by another until released:
+    let reservation = handler.reserve_name("Gem_Hunter").unwrap();
+    assert!(reason(handler.reserve_name("gem_hunter")).unwrap().contains("already in use"));
+    drop(reservation);
+    assert_eq!(reason(handler.reserve_name("Gem_Hunter")), None);
+
+    let player_id = handler.add_test_entity(TileCoords { x: 0, y: 0 });
+    handler.game_map.lock().entity_by_id_mut(player_id).unwrap().name = "Gem_Hunter".to_string();
+
+    assert!(reason(handler.reserve_name("gem_HUNTER")).unwrap().contains("already in use"));
+
+    handler.game_map.lock().remove_entity(player_id);
+    assert_eq!(reason(handler.reserve_name("Gem_Hunter")), None);
+}
+
+/// Local chat messages should only be relayed to players whose entity is within [`messages::LOCAL_CHAT_RADIUS`] chunks
+/// of the sender's entity, whereas global messages are relayed to all players.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_map_change_chat_message_range() {
+    let mut handler = make_test_handler().await;
+
+    let radius = messages::LOCAL_CHAT_RADIUS as i32;
+    let sender_id = handler.add_test_entity(TileCoords { x: 0, y: 0 });
+    let edge_id = handler.add_test_entity(TileCoords { x: 0, y: radius * CHUNK_WIDTH });
+    let distant_id = handler.add_test_entity(TileCoords { x: -(radius + 1) * CHUNK_WIDTH, y: 0 });
+
+    let change = |channel| maps::Modification::ChatMessage {
+        sender_entity_id: sender_id,
+        sender_name: "Sender".into(),
+        text: "Hi".into(),
+        channel
+    };
+
+    assert!(handler.handle_map_change(change(messages::ChatChannel::Local), edge_id).await.is_some());
+    assert!(handler.handle_map_change(change(messages::ChatChannel::Local), distant_id).await.is_none());
+    assert!(matches!(
+        handler.handle_map_change(change(messages::ChatChannel::Global), distant_id).await.unwrap(),
+        messages::FromServer::ChatMessage { channel: messages::ChatChannel::Global, .. }
+    ));
+
+    // Local messages from entities no longer on the map cannot be placed so are not relayed:
+    handler.game_map.lock().remove_entity(sender_id);
+    assert!(handler.handle_map_change(change(messages::ChatChannel::Local), edge_id).await.is_none());
+}
+
+/// Global chat messages should only be sent should the player be able to pay for them, with the cost being removed
+/// from their gem collection.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_global_chat_message_cost() {
+    let mut handler = make_test_handler().await;
+    let mut other_handler = make_test_handler().await;
+    other_handler.map_changes_receiver = handler.map_changes_sender.subscribe();
+
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+    let msg = || messages::ToServer::ChatMessage { text: "Hello all".into(), channel: messages::ChatChannel::Global };
+
+    assert!(matches!(
+        handler.handle_message(msg(), player_id).await.unwrap().as_slice(),
+        [messages::FromServer::ActionRejected { reason: messages::RejectionReason::CannotAffordGlobalChat(_) }]
+    ));
+    assert!(matches!(other_handler.map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+
+    handler
+        .game_map
+        .lock()
+        .entity_by_id_mut(player_id)
+        .unwrap()
+        .gem_collection
+        .increase_quantity(GLOBAL_CHAT_COST_GEM, GLOBAL_CHAT_GEM_COST + 1);
+
+    assert!(matches!(
+        handler.handle_message(msg(), player_id).await.unwrap().as_slice(),
+        [messages::FromServer::YouSpentGems {
+            gem_type: GLOBAL_CHAT_COST_GEM,
+            quantity_decrease: GLOBAL_CHAT_GEM_COST
+        }]
+    ));
+
+    let remaining =
+        handler.game_map.lock().entity_by_id(player_id).unwrap().gem_collection.get_quantity(GLOBAL_CHAT_COST_GEM);
+    assert_eq!(remaining, 1);
+
+    // Global messages are relayed regardless of where the recipient's entity is (or whether it is even known):
+    let change = other_handler.map_changes_receiver.recv().await.unwrap();
+    assert!(other_handler.handle_map_change(change, observer_id()).await.is_some());
+}
+
+/// Changing facial expression should update the entity on the server and inform only those clients with the entity's
+/// chunk loaded.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_set_facial_expression() {
+    let mut handler = make_test_handler().await;
+    let mut other_handler = make_test_handler().await;
+    other_handler.map_changes_receiver = handler.map_changes_sender.subscribe();
+
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+
+    let msg = messages::ToServer::SetFacialExpression(FacialExpression::Shocked);
+    assert!(handler.handle_message(msg, player_id).await.unwrap().is_empty());
+
+    assert_eq!(handler.game_map.lock().entity_by_id(player_id).unwrap().facial_expression, FacialExpression::Shocked);
+    assert!(matches!(handler.map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+
+    // Other task should not inform its client until the entity's chunk is loaded:
+    let change = other_handler.map_changes_receiver.recv().await.unwrap();
+    assert!(other_handler.handle_map_change(change.clone(), observer_id()).await.is_none());
+
+    other_handler.remote_loaded_chunk_coords.insert(ChunkCoords { x: 0, y: 0 });
+    assert!(matches!(
+        other_handler.handle_map_change(change, observer_id()).await,
+        Some(messages::FromServer::EntityChangedExpression { entity_id, expression: FacialExpression::Shocked })
+            if entity_id == player_id
+    ));
+}
+
+/// Emotes should be relayed to every client with the emoting entity's chunk loaded (including the emoting player's own
+/// client), while emotes performed too soon after the player's previous emote should be rejected.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_emote() {
+    let mut handler = make_test_handler().await;
+    let mut other_handler = make_test_handler().await;
+    other_handler.map_changes_receiver = handler.map_changes_sender.subscribe();
+
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+    handler.remote_loaded_chunk_coords.insert(ChunkCoords { x: 0, y: 0 });
+
+    let msg = messages::ToServer::Emote(messages::EmoteKind::Wave);
+    assert!(handler.handle_message(msg, player_id).await.unwrap().is_empty());
+
+    let change = handler.map_changes_receiver.recv().await.unwrap();
+    match handler.handle_map_change(change, player_id).await {
+        Some(messages::FromServer::EntityEmote { entity_id, emote: messages::EmoteKind::Wave }) => {
+            assert_eq!(entity_id, player_id)
+        }
+        _ => panic!("Expected an emote response")
+    }
+
+    // Other task should not inform its client until the entity's chunk is loaded:
+    let change = other_handler.map_changes_receiver.recv().await.unwrap();
+    assert!(other_handler.handle_map_change(change.clone(), observer_id()).await.is_none());
+
+    other_handler.remote_loaded_chunk_coords.insert(ChunkCoords { x: 0, y: 0 });
+    assert!(matches!(
+        other_handler.handle_map_change(change, observer_id()).await,
+        Some(messages::FromServer::EntityEmote { emote: messages::EmoteKind::Wave, .. })
+    ));
+
+    // A second emote immediately after the first is rejected:
+    let msg = messages::ToServer::Emote(messages::EmoteKind::Dance);
+    assert!(matches!(
+        handler.handle_message(msg, player_id).await.unwrap().as_slice(),
+        [messages::FromServer::ActionRejected {
+            reason: messages::RejectionReason::Cooldown { action: ActionKind::Emote, remaining_ms: 1..=2000 }
+        }]
+    ));
+    assert!(matches!(handler.map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+
+    // Emotes are accepted again once the cooldown has passed:
+    handler.cooldowns = Cooldowns::default();
+    let msg = messages::ToServer::Emote(messages::EmoteKind::Cheer);
+    assert!(handler.handle_message(msg, player_id).await.unwrap().is_empty());
+    assert!(matches!(
+        handler.map_changes_receiver.try_recv(),
+        Ok(maps::Modification::EntityEmote { emote: messages::EmoteKind::Cheer, .. })
+    ));
+}
+
+/// Announcements should be relayed to every client regardless of their loaded chunks.
+#[tokio::test]
+async fn handle_announcement() {
+    let mut handler = make_test_handler().await;
+
+    let change = maps::Modification::Announcement { text: "Server restarting in 5 minutes".to_string() };
+    assert!(matches!(
+        handler.handle_map_change(change, observer_id()).await.unwrap(),
+        messages::FromServer::ServerAnnouncement { text } if text == "Server restarting in 5 minutes"
+    ));
+}
+
+/// Movement requests with a request number no greater than that of the last processed request should be ignored but
+/// still answered with the entity's authoritative position so that the client does not wait on them indefinitely.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_move_my_entity_out_of_order() {
+    let mut handler = make_test_handler().await;
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+
+    let msg = messages::ToServer::MoveMyEntity { request_number: 1, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::YourEntityMoved { request_number: 1, new_position: TileCoords { x: 6, y: 5 } }]
+    ));
+
+    // Request that arrives after one with a higher request number should be ignored:
+    let msg = messages::ToServer::MoveMyEntity { request_number: 0, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::YourEntityMoved { request_number: 0, new_position: TileCoords { x: 6, y: 5 } }]
+    ));
+
+    // As should a duplicate request:
+    let msg = messages::ToServer::MoveMyEntity { request_number: 1, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::YourEntityMoved { request_number: 1, new_position: TileCoords { x: 6, y: 5 } }]
+    ));
+
+    assert_eq!(handler.game_map.lock().entity_by_id(player_id).unwrap().pos, TileCoords { x: 6, y: 5 });
+}
+
+/// Ensure that a movement into a blocking tile is rejected with a message containing the entity's authoritative
+/// position, and that the position that would be saved to the database is unchanged.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_move_my_entity_into_blocking_tile() {
+    let mut handler = make_test_handler().await;
+
+    let mut chunk = Chunk::default();
+    chunk.set_tile_at_offset(OffsetCoords { x: 6, y: 5 }, Tile::Water);
+
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+
+    let msg = messages::ToServer::MoveMyEntity { request_number: 0, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::YourEntityMoved { request_number: 0, new_position: TileCoords { x: 5, y: 5 } }]
+    ));
+
+    let save_data = handler.game_map.lock().save();
+    let (_, saved_entity) = save_data.entities.iter().find(|(id, _)| *id == player_id).unwrap();
+    assert_eq!(saved_entity.pos, TileCoords { x: 5, y: 5 });
+}
+
+/// Ensure that no sequence of movement requests (including stale, duplicate, and blocked requests) can ever result in
+/// the player entity moving more than a single tile at once or into a blocking tile.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_move_my_entity_sequence_never_teleports() {
+    let mut handler = make_test_handler().await;
+
+    // Column of water tiles that the player entity should never be able to pass:
+    let mut chunk = Chunk::default();
+    for y in 0..CHUNK_WIDTH as u8 {
+        chunk.set_tile_at_offset(OffsetCoords { x: 8, y }, Tile::Water);
+    }
+
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 1 });
+
+    let requests = [
+        (0, Direction::Right),
+        (1, Direction::Right),
+        (2, Direction::Right), // Blocked by water.
+        (1, Direction::Left),  // Duplicate.
+        (3, Direction::Down),
+        (4, Direction::Down), // Blocked by unloaded chunk.
+        (0, Direction::Up),   // Stale.
+        (5, Direction::Right),
+        (6, Direction::Up)
+    ];
+
+    let mut previous_position = TileCoords { x: 5, y: 1 };
+
+    for (request_number, direction) in requests.iter().copied() {
+        let msg = messages::ToServer::MoveMyEntity { request_number, direction };
+        let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+        let position = handler.game_map.lock().entity_by_id(player_id).unwrap().pos;
+
+        assert!(previous_position.manhattan_distance(position) <= 1);
+        assert_ne!(handler.game_map.lock().loaded_tile_at(position), Some(Tile::Water));
+
+        // Any response should always contain the entity's authoritative position:
+        for response in responses {
+            if let messages::FromServer::YourEntityMoved { new_position, .. } = response {
+                assert_eq!(new_position, position);
+            }
+        }
+
+        previous_position = position;
+    }
+
+    assert_eq!(previous_position, TileCoords { x: 7, y: 1 });
+}
+
+/// Ensure that moving into a new chunk results in the client being provided with chunks (and the entities within them)
+/// that have come into view, and being told to unload chunks (and entities) that are no longer in view.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_move_my_entity_into_new_chunk() {
+    let mut handler = make_test_handler().await;
+
+    // Client has all chunks in view of chunk (0, 0) loaded. Chunks are also marked as in use by some other client so
+    // that they are not saved to the database when unloaded by this task:
+    for x in -VIEW_RADIUS..VIEW_RADIUS + 1 {
+        for y in -VIEW_RADIUS..VIEW_RADIUS + 1 {
+            handler.add_empty_chunk(ChunkCoords { x, y });
+            handler.game_map.lock().chunk_in_use(ChunkCoords { x, y });
+            handler.game_map.lock().chunk_in_use(ChunkCoords { x, y });
+        }
+    }
+
+    // Column of chunks that will come into view is loaded on the server (but not by the client):
+    let new_column_x = VIEW_RADIUS + 1;
+    for y in -VIEW_RADIUS..VIEW_RADIUS + 1 {
+        handler.game_map.lock().add_chunk(ChunkCoords { x: new_column_x, y }, Chunk::default());
+    }
+
+    let player_id = handler.add_test_entity(TileCoords { x: CHUNK_WIDTH - 1, y: 5 });
+    let entering_view_id = handler.add_test_entity(TileCoords { x: new_column_x * CHUNK_WIDTH, y: 5 });
+    let leaving_view_id = handler.add_test_entity(TileCoords { x: -VIEW_RADIUS * CHUNK_WIDTH, y: 5 });
+
+    let msg = messages::ToServer::MoveMyEntity { request_number: 0, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+    let provided_chunks: Vec<ChunkCoords> = responses
+        .iter()
+        .filter_map(|msg| {
+            if let messages::FromServer::ProvideChunk(coords, _) = msg {
+                Some(*coords)
+            }
+            else {
+                None
+            }
+        })
+        .collect();
+    let unloaded_chunks: Vec<ChunkCoords> = responses
+        .iter()
+        .filter_map(|msg| {
+            if let messages::FromServer::ShouldUnloadChunk(coords) = msg {
+                Some(*coords)
+            }
+            else {
+                None
+            }
+        })
+        .collect();
+
+    assert_eq!(provided_chunks.len(), (VIEW_RADIUS * 2 + 1) as usize);
+    assert!(provided_chunks.iter().all(|coords| coords.x == new_column_x));
+
+    assert_eq!(unloaded_chunks.len(), (VIEW_RADIUS * 2 + 1) as usize);
+    assert!(unloaded_chunks.iter().all(|coords| coords.x == -VIEW_RADIUS));
+
+    assert!(responses
+        .iter()
+        .any(|msg| matches!(msg, messages::FromServer::ProvideEntity(id, _) if *id == entering_view_id)));
+    assert!(responses
+        .iter()
+        .any(|msg| matches!(msg, messages::FromServer::ShouldUnloadEntity(id) if *id == leaving_view_id)));
+
+    assert_eq!(handler.remote_loaded_chunk_coords.len(), ((VIEW_RADIUS * 2 + 1) * (VIEW_RADIUS * 2 + 1)) as usize);
+    assert!(handler
+        .remote_loaded_chunk_coords
+        .iter()
+        .all(|coords| coords.x > -VIEW_RADIUS && coords.x <= new_column_x));
+
+    // Entering a chunk not previously visited this session counts as exploring it:
+    assert_eq!(handler.game_map.lock().entity_by_id(player_id).unwrap().stats.chunks_explored, 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_request_entity_info() {
+    let mut handler = make_test_handler().await;
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let player_id = handler.add_test_entity(TileCoords { x: 1, y: 1 });
+    let other_id = handler.add_test_entity(TileCoords { x: 4, y: 2 });
+
+    handler.game_map.lock().entity_by_id_mut(other_id).unwrap().gem_collection.increase_quantity(gems::Gem::Ruby, 7);
+
+    let responses = handler.handle_message(messages::ToServer::RequestEntityInfo(other_id), player_id).await.unwrap();
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::EntityInfo(id, entity)]
+            if *id == other_id && entity.gem_collection.get_quantity(gems::Gem::Ruby) == 7
+    ));
+
+    // No information is provided about entities outside the client's loaded chunks:
+    let far_id = crate::id::generate_with_timestamp();
+    handler.game_map.lock().add_entity(far_id, EntityBuilder::new().pos(TileCoords { x: 100, y: 100 }).build());
+
+    let responses = handler.handle_message(messages::ToServer::RequestEntityInfo(far_id), player_id).await.unwrap();
+    assert!(responses.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_smashed_rock_within_loaded_chunks() {
+    // TODO
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_smashed_rock_outside_loaded_chunks() {
+    // TODO
+}
+
+/// Ensure that purchasing an item the player can afford removes the appropriate gems and gives the player the item
+/// without any response message.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_purchase_item_quantity() {
+    let mut handler = make_test_handler().await;
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let player_id = handler.add_test_entity(TileCoords { x: 0, y: 0 });
+
+    let (cost_gem, cost_quantity) = items::QuantitativeItem::Bomb.get_price();
+    handler
+        .game_map
+        .lock()
+        .entity_by_id_mut(player_id)
+        .unwrap()
+        .gem_collection
+        .increase_quantity(cost_gem, cost_quantity * 3);
+
+    let msg = messages::ToServer::PurchaseItemQuantity { item: items::QuantitativeItem::Bomb, quantity: 2 };
+    assert!(handler.handle_message(msg, player_id).await.unwrap().is_empty());
+
+    let map = handler.game_map.lock();
+    let entity = map.entity_by_id(player_id).unwrap();
+    assert_eq!(entity.gem_collection.get_quantity(cost_gem), cost_quantity);
+    assert_eq!(entity.item_inventory.has_how_many(items::QuantitativeItem::Bomb), 2);
+}
+
+/// Ensure that attempting to purchase items without enough gems produces a 'purchase failed' response and leaves the
+/// player's gems & items unchanged.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_purchase_with_insufficient_gems() {
+    let mut handler = make_test_handler().await;
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let player_id = handler.add_test_entity(TileCoords { x: 0, y: 0 });
+
+    let (cost_gem, cost_quantity) = items::QuantitativeItem::Bomb.get_price();
+    handler
+        .game_map
+        .lock()
+        .entity_by_id_mut(player_id)
+        .unwrap()
+        .gem_collection
+        .increase_quantity(cost_gem, cost_quantity);
+
+    let msg = messages::ToServer::PurchaseItemQuantity { item: items::QuantitativeItem::Bomb, quantity: 2 };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+    assert_eq!(responses.len(), 1);
+    assert!(matches!(
+        responses[0],
+        messages::FromServer::PurchaseFailed(gems::InsufficientGemsError { gem, requested, available })
+            if gem == cost_gem && requested == cost_quantity * 2 && available == cost_quantity
+    ));
+
+    // An absurd quantity should not cause an overflow (and cannot fit in the player's inventory regardless):
+    let msg = messages::ToServer::PurchaseItemQuantity { item: items::QuantitativeItem::Bomb, quantity: u32::MAX };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+    assert!(matches!(
+        responses[0],
+        messages::FromServer::ActionRejected { reason: messages::RejectionReason::InventoryFull { .. } }
+    ));
+
+    let map = handler.game_map.lock();
+    let entity = map.entity_by_id(player_id).unwrap();
+    assert_eq!(entity.gem_collection.get_quantity(cost_gem), cost_quantity);
+    assert_eq!(entity.item_inventory.has_how_many(items::QuantitativeItem::Bomb), 0);
+}
+
+/// Ensure that purchases that would exceed the capacity of the player's inventory are rejected without removing any
+/// gems.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_purchase_with_full_inventory() {
+    let mut handler = make_test_handler().await;
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let player_id = handler.add_test_entity(TileCoords { x: 0, y: 0 });
+
+    let (cost_gem, cost_quantity) = items::QuantitativeItem::Bomb.get_price();
+    {
+        let mut map = handler.game_map.lock();
+        let entity = map.entity_by_id_mut(player_id).unwrap();
+        entity.gem_collection.increase_quantity(cost_gem, cost_quantity * 10);
+        entity.item_inventory.max_slots = 3;
+        entity.item_inventory.give_quantity(items::QuantitativeItem::Bomb, 2);
+    }
+
+    let msg = messages::ToServer::PurchaseItemQuantity { item: items::QuantitativeItem::Bomb, quantity: 2 };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::ActionRejected { reason: messages::RejectionReason::InventoryFull { max_slots: 3 } }]
+    ));
+
+    // A purchase that exactly fills the inventory is permitted:
+    let msg = messages::ToServer::PurchaseItemQuantity { item: items::QuantitativeItem::Bomb, quantity: 1 };
+    assert!(handler.handle_message(msg, player_id).await.unwrap().is_empty());
+
+    let map = handler.game_map.lock();
+    let entity = map.entity_by_id(player_id).unwrap();
+    assert_eq!(entity.gem_collection.get_quantity(cost_gem), cost_quantity * 9);
+    assert_eq!(entity.item_inventory.has_how_many(items::QuantitativeItem::Bomb), 3);
+}
+
+/// Ensure that purchases that would take the weight of the player's inventory above the maximum are rejected without
+/// removing any gems (even should the inventory have free slots).
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_purchase_exceeding_weight_limit() {
+    let mut handler = make_test_handler().await;
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let player_id = handler.add_test_entity(TileCoords { x: 0, y: 0 });
+
+    let bomb = items::QuantitativeItem::Bomb;
+    let bombs_at_limit = items::MAX_INVENTORY_WEIGHT / bomb.weight();
+    let (cost_gem, cost_quantity) = bomb.get_price();
+    {
+        let mut map = handler.game_map.lock();
+        let entity = map.entity_by_id_mut(player_id).unwrap();
+        entity.gem_collection.increase_quantity(cost_gem, cost_quantity * (bombs_at_limit + 1));
+        entity.item_inventory.max_slots = u32::MAX;
+    }
+
+    let msg = messages::ToServer::PurchaseItemQuantity { item: bomb, quantity: bombs_at_limit + 1 };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::ActionRejected { reason: messages::RejectionReason::InventoryTooHeavy { .. } }]
+    ));
+
+    let msg = messages::ToServer::PurchaseItemQuantity { item: bomb, quantity: bombs_at_limit };
+    assert!(handler.handle_message(msg, player_id).await.unwrap().is_empty());
+
+    let map = handler.game_map.lock();
+    let entity = map.entity_by_id(player_id).unwrap();
+    assert_eq!(entity.gem_collection.get_quantity(cost_gem), cost_quantity);
+    assert_eq!(entity.item_inventory.has_how_many(bomb), bombs_at_limit);
+}
+
+/// Ensure that using a potion consumes it and restores the player's health, and that using a potion at full health
+/// (or without having a potion) has no effect.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_use_potion() {
+    let mut handler = make_test_handler().await;
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let player_id = handler.add_test_entity(TileCoords { x: 0, y: 0 });
+
+    let potion = items::QuantitativeItem::Potion;
+    let player = |handler: &Handler| handler.game_map.lock().entity_by_id(player_id).unwrap().clone();
+
+    // Without any potions:
+    handler.game_map.lock().entity_by_id_mut(player_id).unwrap().health = 10;
+    assert!(handler.handle_message(messages::ToServer::UseItem(potion), player_id).await.unwrap().is_empty());
+    assert_eq!(player(&handler).health, 10);
+
+    handler.game_map.lock().entity_by_id_mut(player_id).unwrap().item_inventory.give_quantity(potion, 2);
+
+    assert!(handler.handle_message(messages::ToServer::UseItem(potion), player_id).await.unwrap().is_empty());
+    let entity = player(&handler);
+    assert_eq!(entity.health, 10 + items::POTION_HEALTH_RECOVERY);
+    assert_eq!(entity.item_inventory.has_how_many(potion), 1);
+
+    // Using another potion straight away is rejected due to the cooldown:
+    let responses = handler.handle_message(messages::ToServer::UseItem(potion), player_id).await.unwrap();
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::ActionRejected {
+            reason: messages::RejectionReason::Cooldown { action: ActionKind::UsePotion, .. }
+        }]
+    ));
+    assert_eq!(player(&handler).item_inventory.has_how_many(potion), 1);
+
+    // At full health:
+    handler.cooldowns = Cooldowns::default();
+    handler.game_map.lock().entity_by_id_mut(player_id).unwrap().health = entity.max_health;
+    assert!(handler.handle_message(messages::ToServer::UseItem(potion), player_id).await.unwrap().is_empty());
+    let entity = player(&handler);
+    assert_eq!(entity.health, entity.max_health);
+    assert_eq!(entity.item_inventory.has_how_many(potion), 1);
+}
+
+/// Ensure that bombs can be placed up to the limit, after which further placements are rejected without removing a
+/// bomb from the player's inventory, and that detonation allows more bombs to be placed.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_place_bomb_limit() {
+    let mut handler = make_test_handler().await;
+    handler.max_bombs_per_player = 2;
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let player_id = handler.add_test_entity(TileCoords { x: 3, y: 3 });
+    handler
+        .game_map
+        .lock()
+        .entity_by_id_mut(player_id)
+        .unwrap()
+        .item_inventory
+        .give_quantity(items::QuantitativeItem::Bomb, 5);
+
+    for _ in 0..2 {
+        assert!(handler.handle_message(messages::ToServer::PlaceBomb, player_id).await.unwrap().is_empty());
+        handler.cooldowns = Cooldowns::default();
+    }
+
+    let responses = handler.handle_message(messages::ToServer::PlaceBomb, player_id).await.unwrap();
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::ActionRejected {
+            reason: messages::RejectionReason::BombLimitReached { limit: 2, position: TileCoords { x: 3, y: 3 } }
+        }]
+    ));
+
+    {
+        let map = handler.game_map.lock();
+        let player = map.entity_by_id(player_id).unwrap();
+        assert_eq!(player.bombs_placed_count, 2);
+        assert_eq!(player.item_inventory.has_how_many(items::QuantitativeItem::Bomb), 3);
+        assert_eq!(
+            map.loaded_chunk_at(ChunkCoords { x: 0, y: 0 }).unwrap().get_undetonated_bomb_positions().count(),
+            2
+        );
+    }
+
+    // Detonation should reset the count so that bombs may be placed again:
+    assert!(handler.handle_message(messages::ToServer::DetonateBombs, player_id).await.unwrap().is_empty());
+    assert_eq!(handler.game_map.lock().entity_by_id(player_id).unwrap().bombs_placed_count, 0);
+    assert!(handler.handle_message(messages::ToServer::PlaceBomb, player_id).await.unwrap().is_empty());
+    assert_eq!(handler.game_map.lock().entity_by_id(player_id).unwrap().bombs_placed_count, 1);
+}
+
+/// Ensure that a bomb placed before the cooldown of the player's previous placement has passed is rejected without
+/// being placed or removed from the player's inventory.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_place_bomb_cooldown() {
+    let mut handler = make_test_handler().await;
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let player_id = handler.add_test_entity(TileCoords { x: 3, y: 3 });
+    handler
+        .game_map
+        .lock()
+        .entity_by_id_mut(player_id)
+        .unwrap()
+        .item_inventory
+        .give_quantity(items::QuantitativeItem::Bomb, 2);
+
+    assert!(handler.handle_message(messages::ToServer::PlaceBomb, player_id).await.unwrap().is_empty());
+
+    handler.game_map.lock().move_entity_towards(player_id, Direction::Right).unwrap();
+    let responses = handler.handle_message(messages::ToServer::PlaceBomb, player_id).await.unwrap();
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::ActionRejected {
+            reason: messages::RejectionReason::Cooldown { action: ActionKind::PlaceBomb, remaining_ms: 1..=500 }
+        }]
+    ));
+
+    let map = handler.game_map.lock();
+    let player = map.entity_by_id(player_id).unwrap();
+    assert_eq!(player.bombs_placed_count, 1);
+    assert_eq!(player.item_inventory.has_how_many(items::QuantitativeItem::Bomb), 1);
+}
+
+/// Ensure that requests to place a bomb within a safe zone are rejected without the bomb being placed.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_place_bomb_in_safe_zone() {
+    let mut handler = make_test_handler().await;
+    handler.safe_zones =
+        Arc::new(SafeZoneRegistry::new(vec![SafeZone { centre: TileCoords { x: 2, y: 2 }, radius: 2 }]));
+
+    handler.add_empty_chunk(ChunkCoords { x: 0, y: 0 });
+    let player_id = handler.add_test_entity(TileCoords { x: 4, y: 3 });
+    handler
+        .game_map
+        .lock()
+        .entity_by_id_mut(player_id)
+        .unwrap()
+        .item_inventory
+        .give_quantity(items::QuantitativeItem::Bomb, 2);
+
+    let responses = handler.handle_message(messages::ToServer::PlaceBomb, player_id).await.unwrap();
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::ActionRejected {
+            reason: messages::RejectionReason::InSafeZone { position: TileCoords { x: 4, y: 3 } }
+        }]
+    ));
+
+    {
+        let map = handler.game_map.lock();
+        let player = map.entity_by_id(player_id).unwrap();
+        assert_eq!(player.bombs_placed_count, 0);
+        assert_eq!(player.item_inventory.has_how_many(items::QuantitativeItem::Bomb), 2);
+    }
+
+    // Bombs may be placed once outside of the safe zone:
+    handler.game_map.lock().move_entity_towards(player_id, Direction::Right).unwrap();
+    assert!(handler.handle_message(messages::ToServer::PlaceBomb, player_id).await.unwrap().is_empty());
+    assert_eq!(handler.game_map.lock().entity_by_id(player_id).unwrap().bombs_placed_count, 1);
+}
+
+/// Ensure that detonating bombs smashes nearby rocks and gives the player any gems that those rocks yield.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_detonate_bombs_smashes_rocks() {
+    let mut handler = make_test_handler().await;
+
+    let mut chunk = Chunk::default();
+    chunk.set_tile_at_offset(OffsetCoords { x: 4, y: 5 }, Tile::RockEmerald);
+    chunk.set_tile_at_offset(OffsetCoords { x: 6, y: 6 }, Tile::Rock);
+    chunk.set_tile_at_offset(OffsetCoords { x: 8, y: 5 }, Tile::RockRuby);
+
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+    handler
+        .game_map
+        .lock()
+        .entity_by_id_mut(player_id)
+        .unwrap()
+        .item_inventory
+        .give_quantity(items::QuantitativeItem::Bomb, 1);
+
+    assert!(handler.handle_message(messages::ToServer::PlaceBomb, player_id).await.unwrap().is_empty());
+
+    let responses = handler.handle_message(messages::ToServer::DetonateBombs, player_id).await.unwrap();
+
+    // Only the emerald rock yields gems (the ruby rock is outside of the blast radius):
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::YouCollectedGems {
+            gem_type: gems::Gem::Emerald,
+            quantity_increase: 3..=5,
+            source_tile: TileCoords { x: 4, y: 5 }
+        }]
+    ));
+
+    let map = handler.game_map.lock();
+    assert_eq!(map.loaded_tile_at(TileCoords { x: 4, y: 5 }), Some(Tile::RockSmashed));
+    assert_eq!(map.loaded_tile_at(TileCoords { x: 6, y: 6 }), Some(Tile::RockSmashed));
+    assert_eq!(map.loaded_tile_at(TileCoords { x: 8, y: 5 }), Some(Tile::RockRuby));
+    assert!(map.entity_by_id(player_id).unwrap().gem_collection.get_quantity(gems::Gem::Emerald) >= 3);
+}
+
+/// Ensure that detonating bombs also detonates the player's other bombs caught in their blasts (even those in chunks
+/// beyond the reach of the first detonation) while leaving bombs out of reach of the chain undetonated.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_detonate_bombs_chain_reaction() {
+    let mut handler = make_test_handler().await;
+
+    let mut far_chunk = Chunk::default();
+    far_chunk.set_tile_at_offset(OffsetCoords { x: 2, y: 5 }, Tile::Rock);
+    far_chunk.set_tile_at_offset(OffsetCoords { x: 4, y: 5 }, Tile::Rock);
+
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, Chunk::default());
+    handler.add_chunk(ChunkCoords { x: 1, y: 0 }, Chunk::default());
+    handler.add_chunk(ChunkCoords { x: 2, y: 0 }, far_chunk);
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+
+    // Bombs leading from the chunks adjacent to the player's chunk into chunk (2, 0), which would not be detonated
+    // without a chain reaction, along with an isolated bomb that is out of reach of the chain:
+    let chained = [TileCoords { x: 31, y: 5 }, TileCoords { x: 32, y: 5 }, TileCoords { x: 33, y: 5 }];
+    let isolated = TileCoords { x: 40, y: 5 };
+    {
+        let mut map = handler.game_map.lock();
+        for pos in chained.iter().chain(Some(&isolated)) {
+            assert!(map.set_bomb_at(*pos, player_id));
+        }
+        map.entity_by_id_mut(player_id).unwrap().bombs_placed_count = 4;
+    }
+
+    assert!(handler.handle_message(messages::ToServer::DetonateBombs, player_id).await.unwrap().is_empty());
+
+    let mut map = handler.game_map.lock();
+    // Rock at x = 34 is adjacent to the last bomb in the chain whereas the rock at x = 36 is out of range:
+    assert_eq!(map.loaded_tile_at(TileCoords { x: 34, y: 5 }), Some(Tile::RockSmashed));
+    assert_eq!(map.loaded_tile_at(TileCoords { x: 36, y: 5 }), Some(Tile::Rock));
+    assert_eq!(map.entity_by_id(player_id).unwrap().bombs_placed_count, 1);
+    assert!(map.remove_bomb_at(isolated, player_id));
+    for pos in chained {
+        assert!(!map.remove_bomb_at(pos, player_id));
+    }
+}
+
+/// Pings should be echoed back unchanged as pongs without the game map being modified.
+#[tokio::test]
+async fn handle_ping() {
+    let mut handler = make_test_handler().await;
+    let player_id = handler.add_test_entity(TileCoords { x: 0, y: 0 });
+
+    for (sequence, sent_at_ms) in [(0, 0), (1, 5000), (u32::MAX, u64::MAX)] {
+        let responses =
+            handler.handle_message(messages::ToServer::Ping { sequence, sent_at_ms }, player_id).await.unwrap();
+
+        assert!(matches!(
+            responses.as_slice(),
+            [messages::FromServer::Pong { sequence: s, sent_at_ms: t }] if *s == sequence && *t == sent_at_ms
+        ));
+    }
+
+    assert!(handler.map_changes_receiver.try_recv().is_err());
+}
+
+/// Ensure that interacting with an adjacent unopened chest opens it, gives the player gems, and informs all tasks of
+/// the opened chest while interacting with distant or already opened chests does nothing.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_interact_with_chest() {
+    let mut handler = make_test_handler().await;
+
+    let mut chunk = Chunk::default();
+    chunk.set_tile_at_offset(OffsetCoords { x: 3, y: 3 }, Tile::Chest);
+    chunk.set_tile_at_offset(OffsetCoords { x: 8, y: 3 }, Tile::Chest);
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+
+    let player_id = handler.add_test_entity(TileCoords { x: 2, y: 3 });
+    let chest_pos = TileCoords { x: 3, y: 3 };
+
+    // Chests that are not adjacent to the player cannot be opened:
+    let msg = messages::ToServer::Interact { tile: TileCoords { x: 8, y: 3 } };
+    assert!(handler.handle_message(msg, player_id).await.unwrap().is_empty());
+    assert_eq!(handler.game_map.lock().loaded_tile_at(TileCoords { x: 8, y: 3 }), Some(Tile::Chest));
+
+    let msg = messages::ToServer::Interact { tile: chest_pos };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+    let (gem_type, quantity_increase) = match responses.as_slice() {
+        [messages::FromServer::YouCollectedGems { gem_type, quantity_increase, source_tile }] => {
+            assert_eq!(*source_tile, chest_pos);
+            (*gem_type, *quantity_increase)
+        }
+        _ => panic!("Expected a single 'you collected gems' response")
+    };
+
+    let (gem_yield, _) = gems::CHEST_LOOT_TABLE.iter().find(|(gem_yield, _)| gem_yield.gem == gem_type).unwrap();
+    assert!((gem_yield.minimum_quantity..=gem_yield.maximum_quantity).contains(&quantity_increase));
+
+    {
+        let map = handler.game_map.lock();
+        assert_eq!(map.loaded_tile_at(chest_pos), Some(Tile::ChestOpened));
+        assert_eq!(map.entity_by_id(player_id).unwrap().gem_collection.get_quantity(gem_type), quantity_increase);
+    }
+
+    // The opened chest is broadcast to all tasks, including this one:
+    assert!(matches!(
+        handler.map_changes_receiver.try_recv(),
+        Ok(maps::Modification::TileChanged(pos, Tile::ChestOpened)) if pos == chest_pos
+    ));
+
+    // An opened chest yields nothing:
+    let msg = messages::ToServer::Interact { tile: chest_pos };
+    assert!(handler.handle_message(msg, player_id).await.unwrap().is_empty());
+    assert!(handler.map_changes_receiver.try_recv().is_err());
+}
+
+/// Ensure that interacting with an adjacent sign shows the player its text without removing it, and that signs that
+/// are not adjacent to the player cannot be read.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_interact_with_sign() {
+    let mut handler = make_test_handler().await;
+
+    let mut chunk = Chunk::default();
+    assert!(chunk.place_sign(OffsetCoords { x: 3, y: 3 }, "Beware of the water".to_string()));
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+
+    let distant_player_id = handler.add_test_entity(TileCoords { x: 3, y: 5 });
+    let player_id = handler.add_test_entity(TileCoords { x: 3, y: 4 });
+    let sign_pos = TileCoords { x: 3, y: 3 };
+
+    // Signs that are not adjacent to the player cannot be read:
+    let msg = messages::ToServer::Interact { tile: sign_pos };
+    assert!(handler.handle_message(msg, distant_player_id).await.unwrap().is_empty());
+
+    let msg = messages::ToServer::Interact { tile: sign_pos };
+    match handler.handle_message(msg, player_id).await.unwrap().as_slice() {
+        [messages::FromServer::ShowSignText { text }] => assert_eq!(text, "Beware of the water"),
+        _ => panic!("Expected a single 'show sign text' response")
+    }
+
+    // Reading a sign leaves it in place:
+    assert_eq!(handler.game_map.lock().loaded_tile_at(sign_pos), Some(Tile::Sign));
+    assert!(handler.map_changes_receiver.try_recv().is_err());
+}
+
+/// Ensure that should two players interact with the same chest at once, only one of them receives its gems.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_simultaneous_interact_with_chest() {
+    let mut first_handler = make_test_handler().await;
+    let mut second_handler = make_test_handler().await;
+    second_handler.game_map = Arc::clone(&first_handler.game_map);
+
+    let mut chunk = Chunk::default();
+    chunk.set_tile_at_offset(OffsetCoords { x: 3, y: 3 }, Tile::Chest);
+    first_handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+
+    let first_player_id = first_handler.add_test_entity(TileCoords { x: 2, y: 3 });
+    let second_player_id = second_handler.add_test_entity(TileCoords { x: 4, y: 3 });
+
+    let chest_pos = TileCoords { x: 3, y: 3 };
+    let (first_responses, second_responses) = tokio::join!(
+        first_handler.handle_message(messages::ToServer::Interact { tile: chest_pos }, first_player_id),
+        second_handler.handle_message(messages::ToServer::Interact { tile: chest_pos }, second_player_id)
+    );
+
+    assert_eq!(first_responses.unwrap().len() + second_responses.unwrap().len(), 1);
+
+    let map = first_handler.game_map.lock();
+    let total_gems = |id| map.entity_by_id(id).unwrap().gem_collection.total_count();
+    assert!((total_gems(first_player_id) == 0) != (total_gems(second_player_id) == 0));
+}
+
+/// Ensure that accepting a quest makes it active and informs the client, and that quests which are already active or do
+/// not exist cannot be accepted.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_accept_quest() {
+    let mut handler = make_test_handler().await;
+    let player_id = handler.add_test_entity(TileCoords { x: 0, y: 0 });
+
+    let responses = handler.handle_message(messages::ToServer::AcceptQuest(1), player_id).await.unwrap();
+    assert!(matches!(responses.as_slice(), [messages::FromServer::QuestUpdate(quest)] if quest.id == 1));
+    assert_eq!(handler.active_quests, vec![quests::quest_by_id(1).unwrap()]);
+
+    // Quests that are already active or that do not exist cannot be accepted:
+    assert!(handler.handle_message(messages::ToServer::AcceptQuest(1), player_id).await.unwrap().is_empty());
+    assert!(handler.handle_message(messages::ToServer::AcceptQuest(0), player_id).await.unwrap().is_empty());
+    assert_eq!(handler.active_quests.len(), 1);
+}
+
+/// Ensure that smashing a rock counts towards an active quest and that the quest's reward is given to the player once
+/// its objective is complete.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_quest_completed_by_mining_rock() {
+    let mut handler = make_test_handler().await;
+
+    let mut chunk = Chunk::default();
+    chunk.set_tile_at_offset(OffsetCoords { x: 6, y: 5 }, Tile::Rock);
+
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+
+    let mut quest = quests::quest_by_id(1).unwrap();
+    quest.objective = quests::QuestObjective::MineRocks { required: 1, mined: 0 };
+    handler.active_quests.push(quest);
+
+    let msg = messages::ToServer::MoveMyEntity { request_number: 0, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::YourEntityMoved { .. }, messages::FromServer::QuestUpdate(quest)] if quest.is_complete()
+    ));
+    assert!(handler.active_quests.is_empty());
+
+    let map = handler.game_map.lock();
+    assert_eq!(map.entity_by_id(player_id).unwrap().gem_collection.get_quantity(gems::Gem::Emerald), 25);
+}
+
+/// Ensure that monsters caught in a bomb blast are killed (with all tasks informed of their removal) and that the kills
+/// count towards an active quest.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_detonate_bombs_kills_monsters() {
+    let mut handler = make_test_handler().await;
+
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, Chunk::default());
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+    handler.active_quests.push(quests::quest_by_id(3).unwrap());
+
+    let monster_id = crate::id::generate_random();
+    {
+        let mut map = handler.game_map.lock();
+        map.add_monster(monster_id, Monster::new(TileCoords { x: 6, y: 6 }));
+        assert!(map.set_bomb_at(TileCoords { x: 5, y: 5 }, player_id));
+        map.entity_by_id_mut(player_id).unwrap().bombs_placed_count = 1;
+    }
+
+    let responses = handler.handle_message(messages::ToServer::DetonateBombs, player_id).await.unwrap();
+
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::QuestUpdate(quests::Quest {
+            objective: quests::QuestObjective::KillMonsters { killed: 1, .. },
+            ..
+        })]
+    ));
+    assert!(handler.game_map.lock().monster_by_id(monster_id).is_none());
+
+    let modification = handler.map_changes_receiver.recv().await.unwrap();
+    assert!(matches!(
+        handler.handle_map_change(modification, observer_id()).await,
+        Some(messages::FromServer::ShouldUnloadMonster(id)) if id == monster_id
+    ));
+}
+
+/// Ensure that achievements are unlocked (only once each) as the player smashes rocks and collects gems.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_achievements_unlocked() {
+    let mut handler = make_test_handler().await;
+    handler.achievements = AchievementChecker::default();
+
+    let mut chunk = Chunk::default();
+    chunk.set_tile_at_offset(OffsetCoords { x: 6, y: 5 }, Tile::RockRuby);
+    chunk.set_tile_at_offset(OffsetCoords { x: 7, y: 5 }, Tile::RockRuby);
+
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+
+    let msg = messages::ToServer::MoveMyEntity { request_number: 0, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+
+    assert!(matches!(
+        responses.as_slice(),
+        [
+            messages::FromServer::YourEntityMoved { .. },
+            messages::FromServer::YouCollectedGems { .. },
+            messages::FromServer::AchievementUnlocked(Achievement::MinedRock),
+            messages::FromServer::AchievementUnlocked(Achievement::FirstGem)
+        ]
+    ));
+
+    let msg = messages::ToServer::MoveMyEntity { request_number: 1, direction: Direction::Right };
+    let responses = handler.handle_message(msg, player_id).await.unwrap();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(handler.achievements.unlocked_count(), 2);
+}
+
+/// Ensure that walking, collecting gems, and placing & detonating bombs are counted in the player's statistics, which
+/// are provided to the client upon request.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_request_stats() {
+    let mut handler = make_test_handler().await;
+
+    let mut chunk = Chunk::default();
+    chunk.set_tile_at_offset(OffsetCoords { x: 6, y: 5 }, Tile::RockEmerald);
+
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+    let player_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+    handler
+        .game_map
+        .lock()
+        .entity_by_id_mut(player_id)
+        .unwrap()
+        .item_inventory
+        .give_quantity(items::QuantitativeItem::Bomb, 1);
+
+    let msg = messages::ToServer::MoveMyEntity { request_number: 0, direction: Direction::Right };
+    handler.handle_message(msg, player_id).await.unwrap();
+    handler.handle_message(messages::ToServer::PlaceBomb, player_id).await.unwrap();
+    handler.handle_message(messages::ToServer::DetonateBombs, player_id).await.unwrap();
+
+    let emeralds =
+        handler.game_map.lock().entity_by_id(player_id).unwrap().gem_collection.get_quantity(gems::Gem::Emerald);
+
+    let responses = handler.handle_message(messages::ToServer::RequestStats, player_id).await.unwrap();
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::YourStats(PlayerStats {
+            tiles_walked: 1,
+            gems_collected_total,
+            bombs_placed: 1,
+            bombs_detonated: 1,
+            chunks_explored: 0
+        })] if *gems_collected_total == u64::from(emeralds)
+    ));
+}
+
+#[test]
+fn chunks_explored_once() {
+    let mut visited_chunks = HashSet::new();
+    let mut entity = EntityBuilder::new().build();
+
+    assert_eq!(
+        explore_chunk(&mut visited_chunks, &mut entity, ChunkCoords { x: 0, y: 0 }),
+        Some(GameEvent::ChunkExplored { total_explored: 1 })
+    );
+    assert_eq!(explore_chunk(&mut visited_chunks, &mut entity, ChunkCoords { x: 0, y: 0 }), None);
+    assert_eq!(
+        explore_chunk(&mut visited_chunks, &mut entity, ChunkCoords { x: 1, y: 0 }),
+        Some(GameEvent::ChunkExplored { total_explored: 2 })
+    );
+    assert_eq!(entity.stats.chunks_explored, 2);
+
+    // Chunks explored in an earlier session (i.e. loaded from the database) are not counted again:
+    let mut visited_chunks: HashSet<_> = vec![ChunkCoords { x: 0, y: 0 }].into_iter().collect();
+    assert_eq!(explore_chunk(&mut visited_chunks, &mut entity, ChunkCoords { x: 0, y: 0 }), None);
+    assert_eq!(entity.stats.chunks_explored, 2);
+}
+
+/// Ensure that a player can invite another to form a party, after which gems collected by either are split between
+/// them, and that the party is disbanded upon its leader leaving.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_party_gem_sharing() {
+    let mut handler = make_test_handler().await;
+    let mut other_handler = make_test_handler().await;
+    other_handler.game_map = Arc::clone(&handler.game_map);
+    other_handler.parties = Arc::clone(&handler.parties);
+    other_handler.map_changes_sender = handler.map_changes_sender.clone();
+    other_handler.map_changes_receiver = handler.map_changes_sender.subscribe();
+
+    let mut chunk = Chunk::default();
+    chunk.set_tile_at_offset(OffsetCoords { x: 6, y: 5 }, Tile::RockEmerald);
+    handler.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+
+    let leader_id = handler.add_test_entity(TileCoords { x: 5, y: 5 });
+    let member_id = handler.add_test_entity(TileCoords { x: 5, y: 8 });
+
+    // Invite is relayed only to the invited player's task:
+    let msg = messages::ToServer::InviteToParty(member_id);
+    assert!(handler.handle_message(msg, leader_id).await.unwrap().is_empty());
+    assert!(matches!(handler.map_changes_receiver.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+
+    let change = other_handler.map_changes_receiver.recv().await.unwrap();
+    assert!(matches!(
+        other_handler.handle_map_change(change, member_id).await,
+        Some(messages::FromServer::InvitedToParty { inviter_entity_id }) if inviter_entity_id == leader_id
+    ));
+
+    // Both members are informed of the party once the invite is accepted:
+    let msg = messages::ToServer::AcceptPartyInvite(leader_id);
+    assert!(other_handler.handle_message(msg, member_id).await.unwrap().is_empty());
+
+    for (handler, player_id) in [(&mut handler, leader_id), (&mut other_handler, member_id)] {
+        let change = handler.map_changes_receiver.recv().await.unwrap();
+        assert!(matches!(
+            handler.handle_map_change(change, player_id).await,
+            Some(messages::FromServer::PartyUpdated { leader_entity_id, member_entity_ids })
+                if leader_entity_id == leader_id && member_entity_ids == vec![leader_id, member_id]
+        ));
+    }
+
+    // Smash a rock and check that the yielded gems are split between the party's members:
+    let msg = messages::ToServer::MoveMyEntity { request_number: 0, direction: Direction::Right };
+    let responses = handler.handle_message(msg, leader_id).await.unwrap();
+
+    let leader_share = match responses.as_slice() {
+        [messages::FromServer::YourEntityMoved { .. }, messages::FromServer::YouCollectedGems { quantity_increase, .. }] => {
+            *quantity_increase
+        }
+        _ => panic!("unexpected responses to movement")
+    };
+
+    other_handler.map_changes_receiver.recv().await.unwrap(); // Leader's entity movement.
+    let change = other_handler.map_changes_receiver.recv().await.unwrap();
+    let member_share = match other_handler.handle_map_change(change, member_id).await {
+        Some(messages::FromServer::YouCollectedGems { gem_type: gems::Gem::Emerald, quantity_increase, .. }) => {
+            quantity_increase
+        }
+        _ => panic!("member not informed of their share of the gems")
+    };
+
+    assert!(leader_share >= member_share && leader_share - member_share <= 1);
+    {
+        let map = handler.game_map.lock();
+        let quantity_of = |id| map.entity_by_id(id).unwrap().gem_collection.get_quantity(gems::Gem::Emerald);
+        assert_eq!((quantity_of(leader_id), quantity_of(member_id)), (leader_share, member_share));
+    }
+
+    // The party is disbanded should the leader leave:
+    assert!(handler.handle_message(messages::ToServer::LeaveParty, leader_id).await.unwrap().is_empty());
+
+    let change = other_handler.map_changes_receiver.recv().await.unwrap();
+    assert!(matches!(other_handler.handle_map_change(change, member_id).await, Some(messages::FromServer::PartyLeft)));
+    assert!(handler.parties.lock().party_of(member_id).is_none());
+}
+
+/// Ensure that the shares of party members not on the map are kept by the player that collected the gems and that the
+/// player is told of all the gems they kept.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_party_gem_sharing_with_absent_member() {
+    let mut handler = make_test_handler().await;
+
+    let leader_id = handler.add_test_entity(TileCoords { x: 0, y: 0 });
+    let absent_id = Id::new(99);
+    handler.parties.lock().invite(leader_id, absent_id).unwrap();
+    handler.parties.lock().accept_invite(absent_id, leader_id).unwrap();
+
+    handler.game_map.lock().entity_by_id_mut(leader_id).unwrap().gem_collection.increase_quantity(gems::Gem::Ruby, 5);
+    let msg = messages::FromServer::YouCollectedGems {
+        gem_type: gems::Gem::Ruby,
+        quantity_increase: 5,
+        source_tile: TileCoords { x: 1, y: 0 }
+    };
+
+    assert!(matches!(
+        handler.share_collected_gems(leader_id, msg),
+        Some(messages::FromServer::YouCollectedGems { quantity_increase: 5, .. })
+    ));
+    assert_eq!(
+        handler.game_map.lock().entity_by_id(leader_id).unwrap().gem_collection.get_quantity(gems::Gem::Ruby),
+        5
+    );
+}
+
+/// Ensure that requests to accept invites that were never sent are rejected.
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_accept_party_invite_not_sent() {
+    let mut handler = make_test_handler().await;
+
+    let player_id = handler.add_test_entity(TileCoords { x: 0, y: 0 });
+    let other_id = handler.add_test_entity(TileCoords { x: 1, y: 0 });
+
+    let responses = handler.handle_message(messages::ToServer::AcceptPartyInvite(other_id), player_id).await.unwrap();
+    assert!(matches!(
+        responses.as_slice(),
+        [messages::FromServer::ActionRejected { reason: messages::RejectionReason::NoPartyInvite }]
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn list_worlds() {
+    let mut handler = make_test_handler().await;
+
+    let responses = handler.handle_message(messages::ToServer::ListWorlds, observer_id()).await.unwrap();
+
+    assert_eq!(responses.len(), 1);
+    assert!(matches!(
+        &responses[0],
+        messages::FromServer::AvailableWorlds(names) if names == &[OTHER_WORLD_NAME, DEFAULT_WORLD_NAME]
+    ));
+}
+
+/// Ensure that joining a world switches to that world's map & map changes channel so that players in other worlds are
+/// not seen.
+#[tokio::test(flavor = "multi_thread")]
+async fn join_world_switches_map_and_channel() {
+    let mut handler = make_test_handler().await;
+    let default_world_entity_id = handler.add_test_entity(TileCoords { x: 0, y: 0 });
+
+    assert!(!handler.join_world("nowhere"));
+    assert_eq!(handler.world, DEFAULT_WORLD_NAME);
+
+    assert!(handler.join_world(OTHER_WORLD_NAME));
+    assert_eq!(handler.world, OTHER_WORLD_NAME);
+    assert!(Arc::ptr_eq(&handler.game_map, &handler.worlds.get(OTHER_WORLD_NAME).unwrap().map));
+    assert!(handler.game_map.lock().entity_by_id(default_world_entity_id).is_none());
+
+    // Changes made to the default world are not received:
+    let default_world = handler.worlds.get(DEFAULT_WORLD_NAME).unwrap();
+    let _default_world_receiver = default_world.map_changes_sender.subscribe();
+    default_world.map_changes_sender.send(maps::Modification::EntityAdded(default_world_entity_id)).unwrap();
+    assert!(handler.map_changes_receiver.try_recv().is_err());
+
+    // Whereas changes made to the joined world are:
+    handler.map_changes_sender.send(maps::Modification::EntityAdded(default_world_entity_id)).unwrap();
+    assert!(matches!(handler.map_changes_receiver.try_recv(), Ok(maps::Modification::EntityAdded(_))));
+}
+
+/// Ensure that clients cannot move to another world once their connection is established.
+#[tokio::test(flavor = "multi_thread")]
+async fn join_world_ignored_after_handshake() {
+    let mut handler = make_test_handler().await;
+
+    let msg = messages::ToServer::JoinWorld(OTHER_WORLD_NAME.to_string());
+    assert!(handler.handle_message(msg, observer_id()).await.unwrap().is_empty());
+    assert_eq!(handler.world, DEFAULT_WORLD_NAME);
 }
@@ -0,0 +1,137 @@
+//! Ranks players by the total number of gems they have (see [`shared::messages::FromServer::Leaderboard`]).
+
+use std::collections::HashMap;
+
+use shared::{
+    gems,
+    messages::{LeaderboardEntry, LEADERBOARD_SIZE},
+    Id
+};
+use sqlx::Row;
+
+use crate::db_query_from_file;
+
+/// Produce the leaderboard of the named world from the names & gem collections of all player entities stored in the
+/// database for that world. The given names & gem collections of online players are used in place of those stored for
+/// the same entities, as the database rows of online players are only updated periodically.
+pub async fn leaderboard_from_database(
+    online: Vec<(Id, String, gems::Collection)>, world: &str, db: &mut sqlx::PgConnection
+) -> sqlx::Result<Vec<LeaderboardEntry>> {
+    let mut players: HashMap<Id, (String, gems::Collection)> =
+        db_query_from_file!("client_entities/select gem collections")
+            .bind(world)
+            .map(|row: sqlx::postgres::PgRow| {
+                (
+                    Id::decode(row.get("entity_id")).unwrap(),
+                    (
+                        row.get("name"),
+                        row.get::<Option<&[u8]>, _>("gem_collection")
+                            .and_then(|bytes| bincode::deserialize(bytes).ok())
+                            .unwrap_or_default()
+                    )
+                )
+            })
+            .fetch_all(db)
+            .await?
+            .into_iter()
+            .collect();
+
+    players.extend(online.into_iter().map(|(entity_id, name, collection)| (entity_id, (name, collection))));
+
+    Ok(rank(players.into_iter().map(|(entity_id, (name, collection))| (entity_id, name, collection))))
+}
+
+/// Rank the given players by the total quantity of gems in their collections (highest first), keeping at most
+/// [`LEADERBOARD_SIZE`] entries. Players with equal totals are ordered by entity ID so that the ranking is stable.
+fn rank(players: impl IntoIterator<Item = (Id, String, gems::Collection)>) -> Vec<LeaderboardEntry> {
+    let mut totals: Vec<(Id, String, u32)> =
+        players.into_iter().map(|(entity_id, name, collection)| (entity_id, name, collection.total_count())).collect();
+
+    totals.sort_unstable_by(|(id_a, _, total_a), (id_b, _, total_b)| total_b.cmp(total_a).then(id_a.cmp(id_b)));
+    totals.truncate(LEADERBOARD_SIZE);
+
+    totals
+        .into_iter()
+        .map(|(entity_id, name, gem_total)| LeaderboardEntry {
+            entity_id,
+            name: if name.is_empty() { placeholder_name(entity_id) } else { name },
+            gem_total
+        })
+        .collect()
+}
+
+/// Players who have not chosen a name since names were introduced are referred to by the last few characters of their
+/// entity's encoded ID (the leading characters of IDs generated at around the same time being alike).
+fn placeholder_name(entity_id: Id) -> String {
+    let encoded = entity_id.encode();
+    format!("Player {}", &encoded[encoded.len() - 6..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collection_of(quantity: u32) -> gems::Collection {
+        let mut collection = gems::Collection::default();
+        collection.increase_quantity(gems::Gem::Emerald, quantity);
+        collection
+    }
+
+    #[test]
+    fn players_ranked_by_gem_total() {
+        let players = (0..15).map(|i| (Id::new(i), format!("Player_{}", i), collection_of((i as u32 % 5) * 10)));
+        let entries = rank(players);
+
+        assert_eq!(entries.len(), LEADERBOARD_SIZE);
+        assert!(entries.windows(2).all(|pair| pair[0].gem_total >= pair[1].gem_total));
+
+        // Ties are broken by entity ID:
+        let top: Vec<(Id, u32)> = entries.iter().take(3).map(|entry| (entry.entity_id, entry.gem_total)).collect();
+        assert_eq!(top, vec![(Id::new(4), 40), (Id::new(9), 40), (Id::new(14), 40)]);
+    }
+
+    #[test]
+    fn placeholder_names_differ() {
+        let first = crate::id::generate_with_timestamp();
+        let second = crate::id::generate_with_timestamp();
+
+        assert!(placeholder_name(first).starts_with("Player "));
+        assert_ne!(placeholder_name(first), placeholder_name(second));
+
+        // Placeholders are only used for players without names:
+        let entries = rank(vec![(first, String::new(), collection_of(2)), (second, "Named".into(), collection_of(1))]);
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, vec![placeholder_name(first).as_str(), "Named"]);
+    }
+
+    /// Requires a PostgreSQL database specified by the `TEST_DATABASE_URL` environment variable.
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn online_collections_replace_stored() {
+        let db_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        let db_pool = sqlx::postgres::PgPoolOptions::new().connect(&db_url).await.unwrap();
+
+        crate::MIGRATOR.run(&db_pool).await.unwrap();
+
+        let mut db = db_pool.acquire().await.unwrap();
+
+        let client_id = crate::id::generate_with_timestamp();
+        let (entity_id, _) = crate::maps::entities::new_player_in_database(
+            client_id,
+            "Leader".to_string(),
+            crate::maps::DEFAULT_SPAWN_POINT,
+            "test",
+            &mut db
+        )
+        .await
+        .unwrap();
+
+        // A total far higher than that of any other player so that the entity is certain to be ranked first:
+        let online = vec![(entity_id, "Leader".to_string(), collection_of(u32::MAX))];
+        let entries = leaderboard_from_database(online, "test", &mut db).await.unwrap();
+
+        assert_eq!(entries[0].entity_id, entity_id);
+        assert_eq!(entries[0].gem_total, u32::MAX);
+        assert_eq!(entries[0].name, "Leader");
+    }
+}
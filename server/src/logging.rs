@@ -0,0 +1,169 @@
+//! Structured JSON log output (selected using `--log-format json`) built on `tracing` & `tracing-subscriber`.
+//!
+//! Log messages are emitted using the macros of the `tracing` crate throughout the server. Should the default text
+//! format be used, those messages are passed on to `flexi_logger` as `log` records (the `log` feature of `tracing`).
+//! Should the JSON format be used instead, a `tracing` subscriber writes each message as a single line of JSON
+//! including its timestamp, level, target, and message along with the fields of the span it was emitted within (e.g.
+//! the `connection_id` & `entity_id` of the connection task responsible).
+
+use std::{fmt, str::FromStr};
+
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{filter::Targets, fmt::MakeWriter, layer::SubscriberExt};
+
+/// The names accepted by the `--log-format` command-line option.
+pub const LOG_FORMAT_NAMES: &[&str] = &["text", "json"];
+
+/// Modules that log a great deal of information not of interest to server operators so only have warnings and errors
+/// displayed.
+pub const QUIET_MODULES: &[&str] = &["sqlx", "tungstenite", "tokio_tungstenite", "mio"];
+
+/// The format in which log messages are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable text (optionally coloured), written using `flexi_logger`.
+    #[default]
+    Text,
+    /// One JSON object per line, written using `tracing-subscriber`.
+    Json
+}
+
+impl FromStr for LogFormat {
+    type Err = UnknownLogFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(UnknownLogFormatError(s.to_string()))
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Unknown log format '{0}' (expected one of: text, json)")]
+pub struct UnknownLogFormatError(String);
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Json => write!(f, "json")
+        }
+    }
+}
+
+/// Create a subscriber that writes log messages at or above the given level as JSON objects (one per line) using the
+/// given writer. Messages from [`QUIET_MODULES`] are only written should they be warnings or errors.
+pub fn json_subscriber<W>(level: LevelFilter, writer: W) -> impl tracing::Subscriber + Send + Sync
+where W: for<'a> MakeWriter<'a> + Send + Sync + 'static {
+    let filter = QUIET_MODULES
+        .iter()
+        .fold(Targets::new().with_default(level), |filter, module| filter.with_target(*module, LevelFilter::WARN));
+
+    let format = tracing_subscriber::fmt::layer()
+        .json()
+        .flatten_event(true)
+        .with_current_span(true)
+        .with_span_list(false)
+        .with_writer(writer);
+
+    tracing_subscriber::registry().with(format).with(filter)
+}
+
+/// Write log messages at or above the given level to stdout as JSON (see [`json_subscriber`]). Messages logged using
+/// the macros of the `log` crate (e.g. by dependencies) are also written.
+pub fn init_json(level: LevelFilter) {
+    tracing_log::LogTracer::init().expect("Failed to redirect log records to tracing subscriber");
+    tracing::subscriber::set_global_default(json_subscriber(level, std::io::stdout))
+        .expect("Failed to initialise JSON logger");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex}
+    };
+
+    use super::*;
+
+    /// Writer that appends to a shared buffer so that log output can be inspected.
+    #[derive(Clone, Default)]
+    struct CapturedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for CapturedOutput {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for CapturedOutput {
+        type Writer = CapturedOutput;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    impl CapturedOutput {
+        fn lines(&self) -> Vec<serde_json::Value> {
+            let output = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+            output.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+        }
+    }
+
+    #[test]
+    fn json_output_includes_span_fields() {
+        let output = CapturedOutput::default();
+
+        tracing::subscriber::with_default(json_subscriber(LevelFilter::INFO, output.clone()), || {
+            let span = tracing::info_span!("connection", connection_id = 7, entity_id = tracing::field::Empty);
+            let _entered = span.enter();
+            span.record("entity_id", "abc");
+
+            tracing::info!(chunk_coords = "(1, -2)", "Chunk loaded");
+            tracing::debug!("Below the minimum level so not written");
+        });
+
+        let lines = output.lines();
+        assert_eq!(lines.len(), 1);
+
+        let line = &lines[0];
+        assert!(line["timestamp"].is_string());
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["target"], module_path!());
+        assert_eq!(line["message"], "Chunk loaded");
+        assert_eq!(line["chunk_coords"], "(1, -2)");
+        assert_eq!(line["span"]["name"], "connection");
+        assert_eq!(line["span"]["connection_id"], 7);
+        assert_eq!(line["span"]["entity_id"], "abc");
+    }
+
+    #[test]
+    fn quiet_modules_filtered() {
+        let output = CapturedOutput::default();
+
+        tracing::subscriber::with_default(json_subscriber(LevelFilter::DEBUG, output.clone()), || {
+            tracing::info!(target: "sqlx::query", "Query executed");
+            tracing::warn!(target: "sqlx::pool", "Pool exhausted");
+        });
+
+        let lines = output.lines();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["target"], "sqlx::pool");
+    }
+
+    #[test]
+    fn parse_log_format() {
+        for name in LOG_FORMAT_NAMES {
+            assert_eq!(&name.parse::<LogFormat>().unwrap().to_string(), name);
+        }
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
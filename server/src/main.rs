@@ -1,17 +1,41 @@
+mod access;
+mod achievements;
+mod config;
+mod console;
 mod handling;
 mod id;
+mod leaderboard;
+mod logging;
 mod maps;
+mod metrics;
 mod networking;
+mod parties;
+mod safe_zones;
+mod worlds;
 
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant}
+};
 
 use maps::ServerMap;
-use parking_lot::Mutex;
+use metrics::Metrics;
+use parking_lot::{Mutex, RwLock};
+use shared::{maps::entities::Entity, Id};
 use structopt::StructOpt;
 use tokio::{net::TcpListener, sync::broadcast};
 
 /// Create an [`sqlx::query::Query`] instance using the SQL query in the specified file with the `.sql` extension
-/// (`server/db/` directory). In a database argument is provided then a query execution future is created.
+/// (`server/db/` directory). In a database argument is provided then a query execution future is created. Any further
+/// arguments are bound to the query's parameters (`$1`, `$2`, etc.) in the order given.
+///
+/// ```ignore
+/// db_query_from_file!("map/select row").fetch_optional(db_pool);
+/// db_query_from_file!("health/select one", db_pool).await?;
+/// db_query_from_file!("map/create row", db_pool, world, seed, generator_name).await?;
+/// db_query_from_file!("map_chunks/replace row", &mut db, world, coords.x, coords.y, data).await?;
+/// ```
 #[macro_export]
 macro_rules! db_query_from_file {
     ($file:expr) => {
@@ -20,12 +44,31 @@ macro_rules! db_query_from_file {
     ($file:expr, $db:expr) => {
         db_query_from_file!($file).execute($db)
     };
+    ($file:expr, $db:expr, $($param:expr),+) => {
+        db_query_from_file!($file)$(.bind($param))+.execute($db)
+    };
 }
 
+/// Brings the database schema up to date by applying any of the numbered SQL migration files in the
+/// `server/db/migrations/` directory that have not already been applied. Changes to the schema (e.g. adding a column)
+/// must be made by adding a new migration file rather than by modifying an existing one.
+pub static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("db/migrations");
+
 #[tokio::main]
 async fn main() {
-    // Command-line arguments:
-    let options = Options::from_args();
+    let metrics = Arc::new(Metrics::new(Instant::now()));
+
+    // Command-line arguments (with defaults taken from a configuration file should one be specified):
+
+    let arg_matches = Options::clap().get_matches();
+    let mut options = Options::from_clap(&arg_matches);
+
+    if let Some(path) = options.config.clone() {
+        let config = config::Config::load(&path).expect("Failed to load configuration file");
+        if let Err(e) = config.merge_into(&mut options, &arg_matches) {
+            structopt::clap::Error::with_description(&e.to_string(), structopt::clap::ErrorKind::InvalidValue).exit();
+        }
+    }
 
     // Logger initialisation:
 
@@ -42,38 +85,99 @@ async fn main() {
     }
     .to_level_filter();
 
-    let mut log_spec_builder = flexi_logger::LogSpecBuilder::new();
-    log_spec_builder.default(log_level);
+    match options.log_format {
+        logging::LogFormat::Text => {
+            let mut log_spec_builder = flexi_logger::LogSpecBuilder::new();
+            log_spec_builder.default(log_level);
+
+            for module in logging::QUIET_MODULES {
+                log_spec_builder.module(module, flexi_logger::LevelFilter::Warn);
+            }
 
-    for module in &["sqlx", "tungstenite", "tokio_tungstenite", "mio"] {
-        log_spec_builder.module(module, flexi_logger::LevelFilter::Warn);
+            let log_spec = log_spec_builder.finalize();
+
+            let mut logger = flexi_logger::Logger::with(log_spec)
+                .log_target(flexi_logger::LogTarget::StdOut)
+                .format_for_stdout(flexi_logger::colored_detailed_format);
+
+            if options.log_to_file {
+                logger = logger
+                    .log_target(flexi_logger::LogTarget::File)
+                    .format_for_files(flexi_logger::detailed_format)
+                    .duplicate_to_stdout(flexi_logger::Duplicate::All)
+                    .rotate(
+                        flexi_logger::Criterion::Age(flexi_logger::Age::Day),
+                        flexi_logger::Naming::Timestamps,
+                        flexi_logger::Cleanup::KeepLogFiles(3)
+                    );
+            }
+            logger.start().expect("Failed to initialise logger");
+        }
+
+        logging::LogFormat::Json => {
+            let level_filter = match log_level {
+                flexi_logger::LevelFilter::Trace => tracing::level_filters::LevelFilter::TRACE,
+                flexi_logger::LevelFilter::Debug => tracing::level_filters::LevelFilter::DEBUG,
+                _ => tracing::level_filters::LevelFilter::INFO
+            };
+            logging::init_json(level_filter);
+
+            if options.log_to_file {
+                tracing::warn!("Log messages are only written to stdout when using the JSON log format");
+            }
+        }
     }
 
-    let log_spec = log_spec_builder.finalize();
+    // Determine how chunk data is to be compressed before being stored in the database:
+
+    let chunk_compression_level = {
+        if options.no_chunk_compression {
+            None
+        }
+        else {
+            Some(options.chunk_compression_level.unwrap_or(maps::chunks::DEFAULT_COMPRESSION_LEVEL))
+        }
+    };
+
+    // Load the lists of IP addresses permitted/forbidden from connecting, reloading them whenever they are changed:
 
-    let mut logger = flexi_logger::Logger::with(log_spec)
-        .log_target(flexi_logger::LogTarget::StdOut)
-        .format_for_stdout(flexi_logger::colored_detailed_format);
+    let ban_list = access::BanList::load(options.whitelist_file.as_deref(), options.blacklist_file.as_deref())
+        .expect("Failed to load IP address whitelist/blacklist");
+    let ban_list = Arc::new(RwLock::new(ban_list));
 
-    if options.log_to_file {
-        logger = logger
-            .log_target(flexi_logger::LogTarget::File)
-            .format_for_files(flexi_logger::detailed_format)
-            .duplicate_to_stdout(flexi_logger::Duplicate::All)
-            .rotate(
-                flexi_logger::Criterion::Age(flexi_logger::Age::Day),
-                flexi_logger::Naming::Timestamps,
-                flexi_logger::Cleanup::KeepLogFiles(3)
-            );
+    let _ban_list_watcher = if options.whitelist_file.is_some() || options.blacklist_file.is_some() {
+        let watcher =
+            access::watch(Arc::clone(&ban_list), options.whitelist_file.clone(), options.blacklist_file.clone())
+                .expect("Failed to watch IP address whitelist/blacklist files for changes");
+        tracing::info!("Loaded IP address whitelist/blacklist");
+        Some(watcher)
     }
-    logger.start().expect("Failed to initialise logger");
+    else {
+        None
+    };
+
+    // Determine where new players are placed and dead players respawn:
+
+    let spawn_point = shared::maps::TileCoords {
+        x: options.spawn_point_x.unwrap_or(maps::DEFAULT_SPAWN_POINT.x),
+        y: options.spawn_point_y.unwrap_or(maps::DEFAULT_SPAWN_POINT.y)
+    };
+
+    // Load the areas in which players cannot place bombs or be damaged (only the spawn area if no file is given):
+
+    let safe_zones = match &options.safe_zones_file {
+        Some(path) => safe_zones::SafeZoneRegistry::load(path).expect("Failed to load safe zones"),
+        None => safe_zones::SafeZoneRegistry::around_spawn_point(spawn_point)
+    };
+    tracing::info!("Loaded {} safe zones", safe_zones.zones().len());
+    let safe_zones = Arc::new(safe_zones);
 
     // Bind socket and handle connections:
 
     let host_address = format!("0.0.0.0:{}", options.port);
 
     let listener = TcpListener::bind(&host_address).await.expect("Failed to create TCP/IP listener");
-    log::info!("Created TCP/IP listener bound to address: {}", host_address);
+    tracing::info!("Created TCP/IP listener bound to address: {}", host_address);
 
     // Connect to database:
 
@@ -81,56 +185,291 @@ async fn main() {
     let db_pool =
         db_pool_options.connect(&options.database_connection_string).await.expect("Failed to connect to database");
 
-    log::info!(
+    tracing::info!(
         "Created connection pool with maximum of {} simultaneous connections to database",
         options.max_database_connections
     );
 
-    db_query_from_file!("client_entities/create table", &db_pool).await.unwrap();
-    db_query_from_file!("map/create table", &db_pool).await.unwrap();
-    db_query_from_file!("map_chunks/create table", &db_pool).await.unwrap();
+    MIGRATOR.run(&db_pool).await.expect("Failed to apply database migrations");
+
+    tracing::info!("Applied any outstanding database migrations");
+
+    // Load/create the game map of each world, each of which is to be shared between threads:
+
+    let world_names = match &options.worlds_dir {
+        Some(directory) => worlds::world_names_in_directory(directory).expect("Failed to read worlds directory"),
+        None => vec![worlds::DEFAULT_WORLD_NAME.to_string()]
+    };
+    assert!(!world_names.is_empty(), "Worlds directory must contain at least one world subdirectory");
+
+    // A tick interval of zero is not possible so use the shortest possible interval instead:
+    let tick_interval = Duration::from_millis(options.tick_interval.unwrap_or(maps::systems::TICK_INTERVAL_MS).max(1));
+
+    let monster_spawn_interval = Duration::from_secs(
+        options.monster_spawn_interval_seconds.unwrap_or(maps::monsters::MONSTER_SPAWN_INTERVAL_SECONDS)
+    );
+
+    let mut world_registry = worlds::WorldRegistry::default();
+
+    for name in world_names {
+        let mut map = ServerMap::load_or_new(&db_pool, &name, &options.map_generator, options.chunk_gen_queue_size)
+            .await
+            .unwrap();
+
+        map.set_spawn_point(spawn_point);
+        map.add_system(Box::new(maps::monsters::MonsterSystem::new(
+            monster_spawn_interval,
+            tick_interval,
+            options.max_monsters.unwrap_or(maps::monsters::MAX_MONSTERS),
+            Arc::clone(&safe_zones)
+        )));
+        map.add_system(Box::new(maps::hazards::HazardSystem::new(
+            Duration::from_millis(maps::hazards::HAZARD_DAMAGE_INTERVAL_MS),
+            tick_interval,
+            Arc::clone(&safe_zones)
+        )));
+
+        let world = world_registry.insert(name.clone(), map);
+
+        // Each world's channel informs the connection tasks of clients in that world of changes made to it. A
+        // receiver must always exist for messages to be sent so changes are also received (and discarded) by a
+        // separate task:
+        tokio::spawn(discard_map_changes(world.map_changes_sender.subscribe()));
+
+        // Simulate the world at regular intervals independently of client messages:
+        tokio::spawn(world_tick(
+            Arc::clone(&world.map),
+            world.map_changes_sender.clone(),
+            tick_interval,
+            db_pool.clone(),
+            Arc::clone(&metrics)
+        ));
+
+        tracing::info!("Prepared game map of world '{}'", name);
+    }
+
+    let worlds = Arc::new(world_registry);
+    let (default_world_name, default_world) = worlds.default_world();
+
+    // Import map chunks into the default world from a JSON snapshot if one was specified:
 
-    log::info!("Prepared necessary database tables");
+    if let Some(path) = &options.import_map {
+        let snapshot = std::fs::read_to_string(path).expect("Failed to read map snapshot file");
+        let value = serde_json::from_str(&snapshot).expect("Failed to parse map snapshot file as JSON");
+        let imported_chunks = ServerMap::import_from_json(&value).expect("Failed to import map snapshot");
 
-    // Load/create game map that is to be shared between threads:
+        for (coords, chunk) in &imported_chunks {
+            let db = db_pool.acquire().await.expect("Failed to acquire database connection");
+            maps::chunks::save_chunk(db, default_world_name, *coords, chunk, chunk_compression_level)
+                .await
+                .expect("Failed to save imported chunk to database");
+        }
+
+        tracing::info!(
+            "Imported {} chunks from map snapshot file {} into world '{}'",
+            imported_chunks.len(),
+            path.display(),
+            default_world_name
+        );
+    }
 
-    let contained_map = ServerMap::load_or_new(&db_pool).await.unwrap();
-    let map: Shared<ServerMap> = Arc::new(Mutex::new(contained_map));
-    log::info!("Prepared game map");
+    // Parties only exist in memory so every player begins without a party when the server starts:
+    let parties: Shared<parties::Parties> = Arc::new(Mutex::new(parties::Parties::default()));
 
-    // Create multi-producer, multi-consumer channel so that each task may notify every other task of changes made to
-    // the game world:
+    // On Unix platforms, a save of the game worlds can be triggered at any time by sending the SIGUSR1 signal:
 
-    let (map_changes_sender, mut map_changes_receiver) = broadcast::channel(5);
+    let mut save_signal = listen_for_save_signal();
 
-    log::info!("Listening for incoming TCP/IP connections...");
+    // Serve the metrics HTTP endpoints on a separate port if requested:
+
+    if let Some(metrics_port) = options.metrics_port {
+        let metrics_address = format!("0.0.0.0:{}", metrics_port);
+        let metrics_listener =
+            std::net::TcpListener::bind(&metrics_address).expect("Failed to create metrics HTTP listener");
+        tracing::info!("Serving metrics over HTTP at address: {}", metrics_address);
+
+        tokio::spawn(metrics::serve(metrics_listener, Arc::clone(&metrics), Arc::clone(&worlds), db_pool.clone()));
+    }
+
+    // Commands (e.g. announcements) may be entered by the server operator via standard input:
+
+    tokio::spawn(console::read_commands(Arc::clone(&worlds)));
+
+    let handler_config = handling::Config {
+        rate_limits: handling::RateLimits {
+            capacity: options.rate_limit_capacity,
+            refill_per_sec: options.rate_limit_refill,
+            max_violations: options.rate_limit_max_violations
+        },
+        max_bombs_per_player: options.max_bombs_per_player.unwrap_or(handling::MAX_BOMBS_PER_PLAYER),
+        fog_of_war: !options.disable_fog_of_war,
+        chunk_compression_level,
+        idle_timeout: Duration::from_secs(options.idle_timeout_seconds.unwrap_or(handling::IDLE_TIMEOUT_SECONDS)),
+        global_chat_gem_cost: options.global_chat_gem_cost.unwrap_or(handling::GLOBAL_CHAT_GEM_COST)
+    };
+
+    tracing::info!("Listening for incoming TCP/IP connections...");
 
     loop {
-        // Connections will be continuously listened for unless Ctrl-C is pressed and the loop is exited. Messages on
-        // the world modifcation channel are also listened for and immediately discarded. This is done as the main task
-        // must maintain access to the channel in order to clone and pass it to new connection tasks while also not
-        // blocking the broadcasted message queue.
+        // Connections will be continuously listened for unless Ctrl-C is pressed and the loop is exited:
         tokio::select!(
             res = listener.accept() => {
                 let (stream, address) = res.unwrap();
 
-                log::info!("Incoming connection from: {}", address);
+                if let Err(reason) = ban_list.read().check(address.ip()) {
+                    tracing::info!("Refused incoming connection from {} - {}", address, reason);
+                    drop(stream); // Close the TCP/IP stream immediately.
+                    continue;
+                }
+
+                tracing::info!("Incoming connection from: {}", address);
 
                 tokio::spawn(handling::handle_connection(
                     stream,
                     address,
-                    Arc::clone(&map),
+                    Arc::clone(&worlds),
+                    Arc::clone(&parties),
+                    Arc::clone(&safe_zones),
                     db_pool.clone(),
-                    map_changes_sender.clone(),
-                    map_changes_sender.subscribe()
+                    handler_config,
+                    Arc::clone(&metrics)
                 ));
             }
-            _ = map_changes_receiver.recv() => {} // Discard the broadcasted world modification message.
+            _ = save_signal_received(&mut save_signal) => {
+                for (name, world) in worlds.iter() {
+                    save_world(name, &world.map, &db_pool, chunk_compression_level).await;
+                }
+            }
             _ = tokio::signal::ctrl_c() => break // Break on Ctrl-C.
         );
     }
 
-    log::info!("No longer listening for connections");
+    tracing::info!("No longer listening for connections");
+
+    // Save the state of every game world before exiting:
+
+    for (name, world) in worlds.iter() {
+        let save_data = world.map.lock().save();
+        let report = save_data.write_to_database_on_shutdown(&db_pool, chunk_compression_level).await;
+
+        for error in &report.errors {
+            tracing::error!("{}", error);
+        }
+        tracing::info!("Saved world '{}' - {}", name, report);
+    }
+    tracing::info!("Shutdown complete");
+
+    // Export a JSON snapshot of the default world's map if requested:
+
+    if let Some(path) = &options.export_map {
+        let snapshot = default_world.map.lock().export_to_json();
+
+        match std::fs::write(path, snapshot.to_string()) {
+            Ok(()) => tracing::info!("Exported map snapshot to file {}", path.display()),
+            Err(e) => tracing::error!("Failed to export map snapshot to file {} - {}", path.display(), e)
+        }
+    }
+
+    if let Some(path) = &options.export_map_text {
+        let text = default_world.map.lock().export_to_text();
+
+        match std::fs::write(path, text) {
+            Ok(()) => tracing::info!("Exported plain text view of map to file {}", path.display()),
+            Err(e) => tracing::error!("Failed to export plain text view of map to file {} - {}", path.display(), e)
+        }
+    }
+}
+
+/// Receive & discard every change made to a world so that its map changes channel always has at least one receiver.
+async fn discard_map_changes(mut map_changes_receiver: broadcast::Receiver<maps::Modification>) {
+    loop {
+        if let Err(broadcast::error::RecvError::Closed) = map_changes_receiver.recv().await {
+            break;
+        }
+    }
+}
+
+/// Tick the game map (see [`ServerMap::tick`]) at the given interval indefinitely, informing connection tasks of any
+/// changes made, saving any player entities changed by each tick to the database (in a single query), and recording
+/// the rate at which ticks take place.
+async fn world_tick(
+    map: Shared<ServerMap>, map_changes_sender: broadcast::Sender<maps::Modification>, tick_interval: Duration,
+    db_pool: sqlx::PgPool, metrics: Arc<Metrics>
+) {
+    let mut interval = tokio::time::interval(tick_interval);
+    let mut previous_tick = interval.tick().await;
+
+    loop {
+        let tick = interval.tick().await;
+        metrics.tick_completed(tick.duration_since(previous_tick));
+        previous_tick = tick;
+
+        let (modifications, changed_entities) = {
+            let mut map = map.lock();
+            let modifications = map.tick();
+            let changed_entities = map.player_entities_changed_by(&modifications);
+            (modifications, changed_entities)
+        };
+
+        for modification in modifications {
+            map_changes_sender.send(modification).unwrap();
+        }
+
+        if !changed_entities.is_empty() {
+            let entities: Vec<(Id, &Entity)> = changed_entities.iter().map(|(id, entity)| (*id, entity)).collect();
+
+            let result = match db_pool.acquire().await {
+                Ok(mut db) => maps::entities::bulk_update_entities(&entities, &mut db).await,
+                Err(e) => Err(e)
+            };
+
+            if let Err(e) = result {
+                tracing::error!("Failed to save {} player entities changed by world tick - {}", entities.len(), e);
+            }
+        }
+    }
+}
+
+/// Write all loaded chunks & player entities of the named world to the database, logging whether or not this was
+/// successful.
+async fn save_world(name: &str, map: &Shared<ServerMap>, db_pool: &sqlx::PgPool, compression_level: Option<i32>) {
+    let save_data = map.lock().save();
+
+    match save_data.write_to_database(db_pool, compression_level).await {
+        Ok(()) => tracing::info!(
+            "Saved {} chunks and {} player entities of world '{}' to database",
+            save_data.chunks.len(),
+            save_data.entities.len(),
+            name
+        ),
+        Err(e) => tracing::error!("Failed to save world '{}' to database - {}", name, e)
+    }
+}
+
+#[cfg(unix)]
+type SaveSignal = tokio::signal::unix::Signal;
+
+#[cfg(not(unix))]
+type SaveSignal = ();
+
+#[cfg(unix)]
+fn listen_for_save_signal() -> SaveSignal {
+    tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined1())
+        .expect("Failed to listen for SIGUSR1 signal")
+}
+
+#[cfg(not(unix))]
+fn listen_for_save_signal() -> SaveSignal {}
+
+/// Completes when a SIGUSR1 signal is received. Never completes on non-Unix platforms.
+#[cfg(unix)]
+async fn save_signal_received(signal: &mut SaveSignal) {
+    signal.recv().await;
+}
+
+#[cfg(not(unix))]
+async fn save_signal_received(_signal: &mut SaveSignal) {
+    std::future::pending().await
 }
 
 /// Alias for a [`Mutex`] wrapped in an [`Arc`].
@@ -140,6 +479,11 @@ type Shared<T> = Arc<Mutex<T>>;
 #[derive(StructOpt, Debug)]
 #[structopt(name = "GemGame Server")]
 struct Options {
+    /// Read default values for any of the following options from the specified TOML configuration file (options given
+    /// on the command line take precedence).
+    #[structopt(long, parse(from_os_str))]
+    config: Option<PathBuf>,
+
     /// The port on which listen for incoming connections.
     #[structopt(short, long, default_value = "5678")]
     port: u16,
@@ -148,6 +492,111 @@ struct Options {
     #[structopt(long, default_value = "map/", parse(from_os_str))]
     map_directory: PathBuf,
 
+    /// Host a separate world for each subdirectory of the specified directory, named after that subdirectory. Only a
+    /// single world named "default" is hosted if not specified.
+    #[structopt(long, parse(from_os_str))]
+    worlds_dir: Option<PathBuf>,
+
+    /// The generator used to generate the chunks of newly created maps. Existing maps continue to use the generator
+    /// they were created with.
+    #[structopt(
+        long,
+        alias = "generator",
+        default_value = "default",
+        possible_values = maps::generators::GENERATOR_NAMES
+    )]
+    map_generator: String,
+
+    /// The maximum number of chunk generation requests that may be waiting to be processed at once. Chunks are
+    /// generated one at a time so as to prevent latency spikes when many new chunks are needed simultaneously.
+    #[structopt(long, default_value = "32")]
+    chunk_gen_queue_size: usize,
+
+    /// The maximum number of messages a client may send in a single burst before further messages are dropped.
+    #[structopt(long, default_value = "20")]
+    rate_limit_capacity: u32,
+
+    /// The number of messages per second that a client may send on average without any being dropped.
+    #[structopt(long, default_value = "10")]
+    rate_limit_refill: f32,
+
+    /// The number of messages from a client that may be dropped within a 10 second period before that client's
+    /// connection is closed.
+    #[structopt(long, default_value = "10")]
+    rate_limit_max_violations: usize,
+
+    /// The number of seconds that a client may go without sending any messages before its connection is closed (60 if
+    /// not specified).
+    #[structopt(long)]
+    idle_timeout_seconds: Option<u64>,
+
+    /// The maximum number of undetonated bombs that a single player may have placed at once (5 if not specified).
+    #[structopt(long)]
+    max_bombs_per_player: Option<u32>,
+
+    /// The number of emeralds that players must pay to send each chat message on the global channel, which is
+    /// delivered to every connected player (2 if not specified).
+    #[structopt(long)]
+    global_chat_gem_cost: Option<u32>,
+
+    /// Allow players to see every tile provided to them rather than only those within sight of their entity. Tiles are
+    /// then no longer marked as seen.
+    #[structopt(long)]
+    disable_fog_of_war: bool,
+
+    /// The zstd compression level (1 to 22) applied to chunk data before it is stored in the database. Higher levels
+    /// reduce the size of the database at the cost of slower chunk saving (3 if not specified).
+    #[structopt(long, parse(try_from_str = parse_compression_level))]
+    chunk_compression_level: Option<i32>,
+
+    /// Store chunk data in the database uncompressed. Chunks that were previously stored compressed can still be
+    /// loaded.
+    #[structopt(long, conflicts_with = "chunk-compression-level")]
+    no_chunk_compression: bool,
+
+    /// The number of milliseconds between each server tick, during which the game world is simulated (100 if not
+    /// specified, at least 1).
+    #[structopt(long)]
+    tick_interval: Option<u64>,
+
+    /// The number of seconds between each monster spawn (10 if not specified).
+    #[structopt(long)]
+    monster_spawn_interval_seconds: Option<u64>,
+
+    /// No more monsters are spawned while there are at least this many on the map (20 if not specified).
+    #[structopt(long)]
+    max_monsters: Option<usize>,
+
+    /// The x coordinate of the tile at which new players are placed and around which players respawn after dying (0 if
+    /// not specified).
+    #[structopt(long)]
+    spawn_point_x: Option<i32>,
+
+    /// The y coordinate of the tile at which new players are placed and around which players respawn after dying (0 if
+    /// not specified).
+    #[structopt(long)]
+    spawn_point_y: Option<i32>,
+
+    /// Read the areas in which players cannot place bombs and are not damaged from the specified JSON file. Only the
+    /// area within 5 tiles of the spawn point is safe if not specified.
+    #[structopt(long, parse(from_os_str))]
+    safe_zones_file: Option<PathBuf>,
+
+    /// Serve server metrics (`/metrics`) and a health check (`/health`) over HTTP on the specified port. Disabled if
+    /// not specified.
+    #[structopt(long)]
+    metrics_port: Option<u16>,
+
+    /// Only accept connections from the IP addresses & CIDR ranges listed (one per line) in the specified file. The
+    /// file is reloaded whenever it changes.
+    #[structopt(long, parse(from_os_str))]
+    whitelist_file: Option<PathBuf>,
+
+    /// Refuse connections from the IP addresses & CIDR ranges listed (one per line) in the specified file. The file is
+    /// reloaded whenever it changes.
+    #[structopt(long, parse(from_os_str))]
+    blacklist_file: Option<PathBuf>,
+
     /// Specify how to connect to the database.
     #[structopt(long, default_value = "postgres://localhost/gemgame")]
     database_connection_string: String,
@@ -165,7 +614,78 @@ struct Options {
     #[structopt(long)]
     log_trace: bool,
 
-    /// Specifiy whether or not log messages should be written to a file in addition to stdout.
+    /// Specifiy whether or not log messages should be written to a file in addition to stdout (text log format only).
     #[structopt(long)]
-    log_to_file: bool
+    log_to_file: bool,
+
+    /// The format in which log messages are written to stdout: either human-readable "text" or "json" (one object per
+    /// line, suitable for processing by tools such as jq).
+    #[structopt(long, default_value = "text", possible_values = logging::LOG_FORMAT_NAMES)]
+    log_format: logging::LogFormat,
+
+    /// Import map chunks from the specified JSON snapshot file into the database for the default world on start up
+    /// (overwriting any existing chunks at the same coordinates).
+    #[structopt(long, parse(from_os_str))]
+    import_map: Option<PathBuf>,
+
+    /// Write a JSON snapshot of the loaded map chunks & player entities of the default world to the specified file on
+    /// shutdown.
+    #[structopt(long, parse(from_os_str))]
+    export_map: Option<PathBuf>,
+
+    /// Write a plain text view of the loaded map chunks of the default world (one character per tile) to the specified
+    /// file on shutdown.
+    #[structopt(long, parse(from_os_str))]
+    export_map_text: Option<PathBuf>
+}
+
+/// Parse a zstd compression level given as a command-line argument, rejecting levels outside of
+/// [`maps::chunks::COMPRESSION_LEVELS`].
+fn parse_compression_level(arg: &str) -> Result<i32, String> {
+    let level: i32 = arg.parse().map_err(|e| format!("{}", e))?;
+
+    if maps::chunks::COMPRESSION_LEVELS.contains(&level) {
+        Ok(level)
+    }
+    else {
+        Err(format!(
+            "Chunk compression level must be between {} and {}",
+            maps::chunks::COMPRESSION_LEVELS.start(),
+            maps::chunks::COMPRESSION_LEVELS.end()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn db_query_from_file_bind_params() {
+        let db_pool = sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://").unwrap();
+
+        // Query execution futures are created but never awaited:
+        let _zero_params = db_query_from_file!("health/select one", &db_pool);
+        let _one_param = db_query_from_file!("map/create row", &db_pool, 0);
+        let _four_params = db_query_from_file!("map_chunks/replace row", &db_pool, "default", 1, -2, vec![0_u8]);
+    }
+
+    #[test]
+    fn compression_level_validated() {
+        use structopt::StructOpt;
+
+        let parse = |level: &str| super::Options::from_iter_safe(&["server", "--chunk-compression-level", level]);
+
+        assert_eq!(parse("22").unwrap().chunk_compression_level, Some(22));
+        assert!(parse("0").is_err());
+        assert!(parse("23").is_err());
+        assert!(parse("three").is_err());
+    }
+
+    #[test]
+    fn migrations_numbered_consecutively() {
+        let versions: Vec<i64> = super::MIGRATOR.iter().map(|migration| migration.version).collect();
+
+        assert!(!versions.is_empty());
+        assert_eq!(versions, (1..=versions.len() as i64).collect::<Vec<_>>());
+        assert_eq!(super::MIGRATOR.iter().next().unwrap().description, "initial schema");
+    }
 }
@@ -2,95 +2,405 @@
 //! [`super::ServerMap`] so that the mutex that that object is contained in is locked for only shortest required period
 //! of time.
 
-use std::convert;
+use std::{borrow::Cow, convert};
 
-use shared::maps::{Chunk, ChunkCoords, Map};
+use shared::maps::{Chunk, ChunkCoords, Map, CHUNK_TILE_COUNT};
 use sqlx::Row;
 
+use super::generators::NeighborHints;
 use crate::{db_query_from_file, Shared};
 
 /// This function will try the following steps until one succeeds:
 /// * Fetch the chunk at the specified coordinates from the given map object's loaded chunks.
-/// * Read the chunk at the given coordinates from the filesystem before inserting it into the given map's loaded
-///   chunks.
-/// * Newly generate a chunk before inserting it into the given map's loaded chunks.
-/// Once a chunk is obtained from any of the above steps, it is cloned before being returned from this function.
+/// * Read the chunk at the given coordinates from the database before inserting it into the given map's loaded chunks.
+/// * Newly generate a chunk (see [`generate_and_load_chunk`]) should no chunk at the given coordinates be stored in the
+///   database.
+///
+/// Once a chunk is obtained from any of the above steps, it is cloned before being returned from this function. Any
+/// other failure to load the chunk from the database is returned as an error rather than a chunk being generated, as
+/// that generated chunk would otherwise overwrite the stored chunk when next saved.
 pub async fn get_or_load_or_generate_chunk(
-    db: sqlx::pool::PoolConnection<sqlx::Postgres>, map: &Shared<super::ServerMap>, coords: ChunkCoords
-) -> Chunk {
+    db_pool: &sqlx::PgPool, map: &Shared<super::ServerMap>, coords: ChunkCoords
+) -> Result<Chunk> {
     let loaded_chunk_option = map.lock().loaded_chunk_at(coords).cloned();
 
     if let Some(loaded_chunk) = loaded_chunk_option {
-        log::debug!("Chunk at {} already loaded", coords);
+        tracing::debug!(chunk_coords = %coords, "Chunk already loaded");
 
-        loaded_chunk
+        Ok(loaded_chunk)
     }
     else {
         // Chunk is not already in memory so needs to either be fetched from the database or newly generated before
         // being loaded into the map.
 
-        let new_chunk = load_chunk(db, coords).await.unwrap_or_else(|_| {
-            let generator = &map.lock().generator;
+        let world = map.lock().world().to_string();
 
-            log::debug!(
-                "Chunk at {} could not be loaded from the database so will be newly generated using generator '{}'",
-                coords,
-                generator.name()
-            );
-
-            generator.generate(coords)
-        });
+        match load_chunk(db_pool.acquire().await?, &world, coords).await {
+            Ok(loaded_chunk) => {
+                // Add the loaded chunk to map's loaded chunks:
+                map.lock().add_chunk(coords, loaded_chunk.clone());
 
-        // Add the new chunk to map's loaded chunks:
-        map.lock().add_chunk(coords, new_chunk.clone());
+                Ok(loaded_chunk)
+            }
+            Err(Error::DatabaseError(sqlx::Error::RowNotFound)) => {
+                tracing::debug!(chunk_coords = %coords, "Chunk not stored in the database so will be newly generated");
 
-        new_chunk
+                Ok(generate_and_load_chunk(map, coords).await)
+            }
+            Err(e) => Err(e)
+        }
     }
 }
 
-/// Attempt to asynchronously read data from the file system for the chunk at the specified coordinates.
-pub async fn load_chunk(mut db: sqlx::pool::PoolConnection<sqlx::Postgres>, coords: ChunkCoords) -> Result<Chunk> {
-    log::trace!("Attempting to load chunk at {} from database", coords);
+/// Generate the chunk at the specified coordinates using the map's chunk generation queue (hinting the generator with
+/// the edges of any loaded neighbouring chunks) before inserting it into the map's loaded chunks. A clone of the
+/// generated chunk is returned.
+pub async fn generate_and_load_chunk(map: &Shared<super::ServerMap>, coords: ChunkCoords) -> Chunk {
+    // Clone the queue so that the map mutex need not be held while waiting for generation to complete:
+    let (generation_queue, hints) = {
+        let map = map.lock();
+        (map.generation_queue.clone(), NeighborHints::from_loaded_chunks(&*map, coords))
+    };
+
+    tracing::debug!(
+        chunk_coords = %coords,
+        "Queuing generation of chunk using generator '{}'",
+        generation_queue.generator_name()
+    );
+
+    let new_chunk = generation_queue.generate(coords, hints).await;
+    map.lock().add_chunk(coords, new_chunk.clone());
+
+    new_chunk
+}
+
+/// Attempt to asynchronously read data from the database for the chunk of the named world at the specified coordinates.
+/// Should no such chunk be stored, the error returned is [`sqlx::Error::RowNotFound`].
+pub async fn load_chunk(
+    mut db: sqlx::pool::PoolConnection<sqlx::Postgres>, world: &str, coords: ChunkCoords
+) -> Result<Chunk> {
+    tracing::trace!(chunk_coords = %coords, "Attempting to load chunk from database");
 
     let res = db_query_from_file!("map_chunks/select row")
+        .bind(world)
         .bind(coords.x)
         .bind(coords.y)
         .map(|row| {
             let data: &[u8] = row.get("data");
-            Ok(bincode::deserialize(data)?)
+            decode_chunk_or_legacy(&decompress_chunk_data(data)?)
         })
         .fetch_one(&mut db)
         .await?;
 
-    log::debug!("Successfully loaded chunk at {} from database", coords);
+    tracing::debug!(chunk_coords = %coords, "Successfully loaded chunk from database");
 
     res
 }
 
-/// Attempt to asynchronously write the data comprising the provided chunk to the file system.
+/// Attempt to asynchronously write the data comprising the provided chunk of the named world to the database. The chunk
+/// data is compressed using zstd at the given compression level unless `None` is specified.
 pub async fn save_chunk(
-    mut db: sqlx::pool::PoolConnection<sqlx::Postgres>, coords: ChunkCoords, chunk: &Chunk
+    mut db: sqlx::pool::PoolConnection<sqlx::Postgres>, world: &str, coords: ChunkCoords, chunk: &Chunk,
+    compression_level: Option<i32>
 ) -> Result<()> {
-    log::trace!("Attempting to save chunk at {} to database", coords);
+    tracing::trace!(chunk_coords = %coords, "Attempting to save chunk to database");
 
-    db_query_from_file!("map_chunks/replace row")
-        .bind(coords.x)
-        .bind(coords.y)
-        .bind(bincode::serialize(chunk)?)
-        .execute(&mut db)
+    let data = compress_chunk_data(encode_chunk(chunk)?, compression_level)?;
+
+    db_query_from_file!("map_chunks/replace row", &mut db, world, coords.x, coords.y, data)
         .await
         .map(|_| {
-            log::debug!("Successfully wrote chunk at {} to database", coords);
+            tracing::debug!(chunk_coords = %coords, "Successfully wrote chunk to database");
         })
         .map_err(convert::Into::into) // Map error type.
 }
 
+/// The default zstd compression level used for chunk data stored in the database.
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+
+/// The range of supported zstd compression levels (higher levels compress better but more slowly).
+pub const COMPRESSION_LEVELS: std::ops::RangeInclusive<i32> = 1..=22;
+
+/// Prepended to chunk data that has been compressed using zstd. Uncompressed data begins with either a tile variant
+/// index (bincode 1.x format) or the bytes `GGC2` (bincode 2.x format), neither of which will ever match this byte.
+const ZSTD_COMPRESSED_FLAG: u8 = 0xFF;
+
+/// Compress encoded chunk data using zstd at the given compression level, or leave it uncompressed if `None`.
+fn compress_chunk_data(data: Vec<u8>, compression_level: Option<i32>) -> Result<Vec<u8>> {
+    match compression_level {
+        Some(level) => {
+            let mut compressed = vec![ZSTD_COMPRESSED_FLAG];
+            compressed.extend(zstd::encode_all(data.as_slice(), level)?);
+            Ok(compressed)
+        }
+        None => Ok(data)
+    }
+}
+
+/// Decompress chunk data read from the database should it have been compressed, otherwise it is returned unchanged.
+fn decompress_chunk_data(data: &[u8]) -> Result<Cow<'_, [u8]>> {
+    match data.split_first() {
+        Some((&ZSTD_COMPRESSED_FLAG, compressed)) => Ok(Cow::Owned(zstd::decode_all(compressed)?)),
+        _ => Ok(Cow::Borrowed(data))
+    }
+}
+
+/// Prepended to chunk data encoded in the bincode 2.x format so that it can be distinguished from chunk data written
+/// using bincode 1.x. Data in the old format begins with a little-endian `u32` tile variant index which will never
+/// match these bytes.
+#[cfg(feature = "bincode-v2")]
+const BINCODE_V2_MAGIC: &[u8] = b"GGC2";
+
+/// Encode the given chunk in the format that it should be stored in the database.
+#[cfg(not(feature = "bincode-v2"))]
+fn encode_chunk(chunk: &Chunk) -> Result<Vec<u8>> {
+    Ok(bincode::serialize(chunk)?)
+}
+
+/// Encode the given chunk in the format that it should be stored in the database.
+#[cfg(feature = "bincode-v2")]
+fn encode_chunk(chunk: &Chunk) -> Result<Vec<u8>> {
+    let mut data = BINCODE_V2_MAGIC.to_vec();
+    bincode2::serde::encode_into_std_write(chunk, &mut data, bincode2::config::standard())?;
+    Ok(data)
+}
+
+/// Decode chunk data read from the database.
+#[cfg(not(feature = "bincode-v2"))]
+fn decode_chunk(data: &[u8]) -> Result<Chunk> {
+    Ok(bincode::deserialize(data)?)
+}
+
+/// Decode chunk data read from the database, be it in the bincode 2.x format or the older bincode 1.x format.
+#[cfg(feature = "bincode-v2")]
+fn decode_chunk(data: &[u8]) -> Result<Chunk> {
+    match data.strip_prefix(BINCODE_V2_MAGIC) {
+        Some(mut v2_data) => Ok(bincode2::serde::decode_from_std_read(&mut v2_data, bincode2::config::standard())?),
+        None => Ok(bincode::deserialize(data)?)
+    }
+}
+
+/// The number of bytes with which the seen flags of a chunk's tiles are encoded (in both bincode formats, each flag
+/// byte is encoded as is).
+const SEEN_FLAGS_ENCODED_LEN: usize = CHUNK_TILE_COUNT / 8;
+
+/// The number of bytes with which an empty list (of sign texts, portal destinations, or gem drops) is encoded in the
+/// bincode 1.x format (a `u64` length) and the bincode 2.x format (a single byte variable-length integer) respectively.
+const EMPTY_LIST_ENCODED_LENS: [usize; 2] = [8, 1];
+
+/// Chunks stored before tile seen flags, sign texts, portal destinations, and gem drops were introduced lack the
+/// trailing encodings of those fields. As the default values of all four fields are encoded as zero bytes, such chunks
+/// can be decoded by padding their data with one of these numbers of zero bytes. Lengths are in ascending order so
+/// that the shortest padding that decodes is used.
+const LEGACY_CHUNK_PADDING_LENS: [usize; 8] = [
+    EMPTY_LIST_ENCODED_LENS[1],
+    EMPTY_LIST_ENCODED_LENS[1] * 2,
+    EMPTY_LIST_ENCODED_LENS[1] * 3,
+    EMPTY_LIST_ENCODED_LENS[0],
+    EMPTY_LIST_ENCODED_LENS[0] * 2,
+    EMPTY_LIST_ENCODED_LENS[0] * 3,
+    SEEN_FLAGS_ENCODED_LEN + (EMPTY_LIST_ENCODED_LENS[1] * 3),
+    SEEN_FLAGS_ENCODED_LEN + (EMPTY_LIST_ENCODED_LENS[0] * 3)
+];
+
+/// Decode chunk data read from the database. Chunks stored before tiles were marked as seen are decoded as having no
+/// tiles seen while those stored before signs, portals, or gem drops were introduced are decoded as having no sign
+/// texts, portal destinations, or gem drops.
+fn decode_chunk_or_legacy(data: &[u8]) -> Result<Chunk> {
+    decode_chunk(data).or_else(|e| {
+        LEGACY_CHUNK_PADDING_LENS
+            .iter()
+            .find_map(|padding_len| {
+                let mut padded = data.to_vec();
+                padded.resize(data.len() + padding_len, 0);
+                decode_chunk(&padded).ok()
+            })
+            .ok_or(e)
+    })
+}
+
 #[derive(Debug, thiserror::Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     #[error("Failed to access database - {0}")]
     DatabaseError(#[from] sqlx::Error),
     #[error("Failed to (de)serialise chunk data with Bincode - {0}")]
-    BincodeError(#[from] bincode::Error)
+    BincodeError(#[from] bincode::Error),
+    #[error("Failed to (de)compress chunk data with zstd - {0}")]
+    CompressionError(#[from] std::io::Error),
+    #[cfg(feature = "bincode-v2")]
+    #[error("Failed to serialise chunk data with Bincode 2.x - {0}")]
+    BincodeV2EncodeError(#[from] bincode2::error::EncodeError),
+    #[cfg(feature = "bincode-v2")]
+    #[error("Failed to deserialise chunk data with Bincode 2.x - {0}")]
+    BincodeV2DecodeError(#[from] bincode2::error::DecodeError)
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use shared::maps::{OffsetCoords, Tile, TileCoords};
+
+    use super::*;
+
+    fn test_chunk() -> Chunk {
+        let mut chunk = Chunk::default();
+        chunk.set_tile_at_offset(OffsetCoords { x: 3, y: 7 }, Tile::Water);
+        chunk.set_tile_at_offset(OffsetCoords { x: 15, y: 0 }, Tile::Rock);
+        chunk.mark_tile_seen(OffsetCoords { x: 3, y: 7 });
+        chunk
+    }
+
+    #[test]
+    fn chunk_encoding_round_trip() {
+        let chunk = test_chunk();
+        let decoded = decode_chunk(&encode_chunk(&chunk).unwrap()).unwrap();
+        assert_eq!(decoded.to_string(), chunk.to_string());
+        assert!(decoded.is_tile_seen(OffsetCoords { x: 3, y: 7 }));
+        assert!(!decoded.is_tile_seen(OffsetCoords { x: 15, y: 0 }));
+    }
+
+    #[test]
+    fn chunks_without_seen_flags_signs_portals_or_gem_drops_load() {
+        let chunk = test_chunk();
+        let data = encode_chunk(&chunk).unwrap();
+
+        #[cfg(not(feature = "bincode-v2"))]
+        let empty_list_len = EMPTY_LIST_ENCODED_LENS[0];
+        #[cfg(feature = "bincode-v2")]
+        let empty_list_len = EMPTY_LIST_ENCODED_LENS[1];
+
+        // Chunks stored before gem drops were introduced lack the trailing gem drops:
+        let dropless_data = &data[..data.len() - empty_list_len];
+        assert!(decode_chunk(dropless_data).is_err());
+        assert_eq!(decode_chunk_or_legacy(dropless_data).unwrap().to_string(), chunk.to_string());
+
+        // Chunks stored before portals were introduced also lack the portal destinations:
+        let portalless_data = &dropless_data[..dropless_data.len() - empty_list_len];
+        assert!(decode_chunk(portalless_data).is_err());
+        assert_eq!(decode_chunk_or_legacy(portalless_data).unwrap().to_string(), chunk.to_string());
+
+        // Chunks stored before signs were introduced also lack the sign texts:
+        let signless_data = &portalless_data[..portalless_data.len() - empty_list_len];
+        assert!(decode_chunk(signless_data).is_err());
+
+        let decoded = decode_chunk_or_legacy(signless_data).unwrap();
+        assert_eq!(decoded.to_string(), chunk.to_string());
+        assert!(decoded.is_tile_seen(OffsetCoords { x: 3, y: 7 }));
+
+        // Chunks stored before tiles were marked as seen also lack the seen flags:
+        let unseen_data = &signless_data[..signless_data.len() - SEEN_FLAGS_ENCODED_LEN];
+        assert!(decode_chunk(unseen_data).is_err());
+
+        let decoded = decode_chunk_or_legacy(unseen_data).unwrap();
+        assert_eq!(decoded.to_string(), chunk.to_string());
+        assert!(!decoded.is_tile_seen(OffsetCoords { x: 3, y: 7 }));
+
+        assert!(decode_chunk_or_legacy(&data).unwrap().is_tile_seen(OffsetCoords { x: 3, y: 7 }));
+    }
+
+    #[test]
+    fn sign_texts_round_trip() {
+        let mut chunk = test_chunk();
+        assert!(chunk.place_sign(OffsetCoords { x: 1, y: 1 }, "Hello there".to_string()));
+
+        let decoded = decode_chunk_or_legacy(&encode_chunk(&chunk).unwrap()).unwrap();
+        assert_eq!(decoded.sign_text(OffsetCoords { x: 1, y: 1 }), Some("Hello there"));
+    }
+
+    #[test]
+    fn portal_destinations_round_trip() {
+        let mut chunk = test_chunk();
+        chunk.place_portal(OffsetCoords { x: 2, y: 3 }, TileCoords { x: -50, y: 12 });
+
+        let decoded = decode_chunk_or_legacy(&encode_chunk(&chunk).unwrap()).unwrap();
+        assert_eq!(decoded.portal_destination(OffsetCoords { x: 2, y: 3 }), Some(TileCoords { x: -50, y: 12 }));
+    }
+
+    #[test]
+    fn old_format_chunks_load() {
+        let chunk = test_chunk();
+        let old_data = bincode::serialize(&chunk).unwrap();
+        assert_eq!(decode_chunk(&old_data).unwrap().to_string(), chunk.to_string());
+    }
+
+    #[test]
+    fn compressed_chunks_round_trip() {
+        let data = encode_chunk(&test_chunk()).unwrap();
+
+        for level in [COMPRESSION_LEVELS.start(), &DEFAULT_COMPRESSION_LEVEL, COMPRESSION_LEVELS.end()] {
+            let compressed = compress_chunk_data(data.clone(), Some(*level)).unwrap();
+            assert_eq!(compressed[0], ZSTD_COMPRESSED_FLAG);
+            assert!(compressed.len() < data.len());
+            assert_eq!(decompress_chunk_data(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn uncompressed_chunks_load() {
+        let data = encode_chunk(&test_chunk()).unwrap();
+
+        assert_eq!(compress_chunk_data(data.clone(), None).unwrap(), data);
+        assert_eq!(decompress_chunk_data(&data).unwrap(), data);
+
+        // Chunks stored before compression was introduced:
+        let old_data = bincode::serialize(&test_chunk()).unwrap();
+        assert!(matches!(decompress_chunk_data(&old_data).unwrap(), Cow::Borrowed(_)));
+    }
+
+    /// Compares the storage size and save/load latency of 100 generated chunks with & without compression. Requires a
+    /// PostgreSQL database specified by the `TEST_DATABASE_URL` environment variable. Run with `--nocapture` to view
+    /// the results.
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn compression_benchmark() {
+        use std::time::Instant;
+
+        use crate::maps::generators::{DefaultGenerator, Generator};
+
+        let db_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        let db_pool = sqlx::postgres::PgPoolOptions::new().connect(&db_url).await.unwrap();
+
+        crate::MIGRATOR.run(&db_pool).await.unwrap();
+
+        let generator = DefaultGenerator::new(0);
+        let chunks: Vec<_> = (0..100)
+            .map(|i| ChunkCoords { x: 1000 + (i % 10), y: 1000 + (i / 10) })
+            .map(|coords| (coords, generator.generate(coords)))
+            .collect();
+
+        for compression_level in [None, Some(*COMPRESSION_LEVELS.start()), Some(DEFAULT_COMPRESSION_LEVEL)] {
+            let stored_size: usize = chunks
+                .iter()
+                .map(|(_, chunk)| compress_chunk_data(encode_chunk(chunk).unwrap(), compression_level).unwrap().len())
+                .sum();
+
+            let save_start = Instant::now();
+            for (coords, chunk) in &chunks {
+                save_chunk(db_pool.acquire().await.unwrap(), "test", *coords, chunk, compression_level).await.unwrap();
+            }
+            let save_duration = save_start.elapsed();
+
+            let load_start = Instant::now();
+            for (coords, chunk) in &chunks {
+                let loaded = load_chunk(db_pool.acquire().await.unwrap(), "test", *coords).await.unwrap();
+                assert_eq!(loaded.to_string(), chunk.to_string());
+            }
+            let load_duration = load_start.elapsed();
+
+            println!(
+                "Compression level {:?}: {} bytes stored, {:?} to save, {:?} to load",
+                compression_level, stored_size, save_duration, load_duration
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "bincode-v2")]
+    fn new_format_chunks_marked() {
+        let data = encode_chunk(&test_chunk()).unwrap();
+        assert!(data.starts_with(BINCODE_V2_MAGIC));
+        assert!(decode_chunk(&data[1..]).is_err());
+    }
+}
@@ -1,11 +1,12 @@
 //! Includes functions to handle the fetching/saving of player entities from/to the database.
 
+use std::collections::HashSet;
+
 use rand::seq::IteratorRandom;
 use shared::{
-    gems, items,
     maps::{
-        entities::{Direction, Entity, FacialExpression},
-        TileCoords
+        entities::{Entity, EntityBuilder},
+        ChunkCoords, TileCoords
     },
     Id
 };
@@ -14,50 +15,56 @@ use strum::IntoEnumIterator;
 
 use crate::db_query_from_file;
 
-/// Create a new player entity that will be stored in the database.
-pub async fn new_player_in_database(client_id: Id, db: &mut sqlx::PgConnection) -> sqlx::Result<(Id, Entity)> {
+/// Create a new player entity with the given name & position in the named world that will be stored in the database.
+pub async fn new_player_in_database(
+    client_id: Id, name: String, pos: TileCoords, world: &str, db: &mut sqlx::PgConnection
+) -> sqlx::Result<(Id, Entity)> {
     let entity_id = crate::id::generate_with_timestamp();
 
-    let entity = Entity {
-        pos: TileCoords { x: 0, y: 0 }, // TODO: Nearest free position.
-        direction: Direction::Down,
-        facial_expression: FacialExpression::Neutral,
-        hair_style: random_variant(),
-        clothing_colour: random_variant(),
-        skin_colour: random_variant(),
-        hair_colour: random_variant(),
-        gem_collection: gems::Collection::default(),
-        item_inventory: items::Inventory::default(),
-        bombs_placed_count: 0
-    };
+    let entity = EntityBuilder::new()
+        .name(name)
+        .pos(pos) // TODO: Nearest free position.
+        .hair_style(random_variant())
+        .clothing_colour(random_variant())
+        .skin_colour(random_variant())
+        .hair_colour(random_variant())
+        .build();
 
     bind_entity_data(db_query_from_file!("client_entities/create row"), &entity)
         .bind(client_id.encode())
         .bind(entity_id.encode())
+        .bind(world)
         .execute(db)
         .await?;
 
     Ok((entity_id, entity))
 }
 
-/// Fetch an existing player entity from the database.
-pub async fn player_from_database(client_id: Id, db: &mut sqlx::PgConnection) -> sqlx::Result<Option<(Id, Entity)>> {
+/// Fetch the existing player entity of a client in the named world from the database.
+pub async fn player_from_database(
+    client_id: Id, world: &str, db: &mut sqlx::PgConnection
+) -> sqlx::Result<Option<(Id, Entity)>> {
     let res = db_query_from_file!("client_entities/select row")
         .bind(client_id.encode())
+        .bind(world)
         .map(|row: sqlx::postgres::PgRow| {
             (
                 Id::decode(row.get("entity_id")).unwrap(),
                 Entity {
+                    name: row.get("name"),
                     pos: TileCoords { x: row.get("tile_x"), y: row.get("tile_y") },
-                    direction: Direction::Down,
-                    facial_expression: FacialExpression::Neutral,
+                    direction: decode_variant(row.get("direction")),
+                    facial_expression: decode_variant(row.get("facial_expression")),
                     hair_style: decode_variant(row.get("hair_style")),
                     clothing_colour: decode_variant(row.get("clothing_colour")),
                     skin_colour: decode_variant(row.get("skin_colour")),
                     hair_colour: decode_variant(row.get("hair_colour")),
                     gem_collection: bincode::deserialize(row.get("gem_collection")).unwrap_or_default(),
                     item_inventory: bincode::deserialize(row.get("item_inventory")).unwrap_or_default(),
-                    bombs_placed_count: row.get("bombs_placed_count")
+                    bombs_placed_count: row.get("bombs_placed_count"),
+                    health: row.get::<i32, _>("health") as u32,
+                    max_health: row.get::<i32, _>("max_health") as u32,
+                    stats: bincode::deserialize(row.get("stats")).unwrap_or_default()
                 }
             )
         })
@@ -67,18 +74,19 @@ pub async fn player_from_database(client_id: Id, db: &mut sqlx::PgConnection) ->
     res
 }
 
-/// Update an existing player entity in the database.
+/// Update the existing player entity of a client in the named world in the database.
 pub async fn update_database_for_player(
-    entity: &Entity, client_id: Id, db: &mut sqlx::PgConnection
+    entity: &Entity, client_id: Id, world: &str, db: &mut sqlx::PgConnection
 ) -> sqlx::Result<()> {
     bind_entity_data(db_query_from_file!("client_entities/update row"), entity)
         .bind(client_id.encode())
+        .bind(world)
         .execute(db)
         .await
         .map(|result| {
             let rows_changed = result.rows_affected();
             if rows_changed != 1 {
-                log::warn!(
+                tracing::warn!(
                     "Modified {} rows when update player entity data for client with ID {}",
                     rows_changed,
                     client_id
@@ -87,6 +95,75 @@ pub async fn update_database_for_player(
         })
 }
 
+/// Update multiple existing player entities (identified by their entity IDs) in the database using a single query.
+pub async fn bulk_update_entities(entities: &[(Id, &Entity)], db: &mut sqlx::PgConnection) -> sqlx::Result<()> {
+    let column = |f: fn(&Entity) -> i16| entities.iter().map(|(_, entity)| f(entity)).collect::<Vec<i16>>();
+
+    db_query_from_file!(
+        "client_entities/bulk update",
+        db,
+        entities.iter().map(|(_, entity)| entity.pos.x).collect::<Vec<i32>>(),
+        entities.iter().map(|(_, entity)| entity.pos.y).collect::<Vec<i32>>(),
+        column(|entity| encode_variant(entity.direction)),
+        column(|entity| encode_variant(entity.facial_expression)),
+        column(|entity| encode_variant(entity.hair_style)),
+        column(|entity| encode_variant(entity.clothing_colour)),
+        column(|entity| encode_variant(entity.skin_colour)),
+        column(|entity| encode_variant(entity.hair_colour)),
+        entities
+            .iter()
+            .map(|(_, entity)| bincode::serialize(&entity.gem_collection).unwrap_or_default())
+            .collect::<Vec<Vec<u8>>>(),
+        entities
+            .iter()
+            .map(|(_, entity)| bincode::serialize(&entity.item_inventory).unwrap_or_default())
+            .collect::<Vec<Vec<u8>>>(),
+        entities.iter().map(|(_, entity)| entity.bombs_placed_count).collect::<Vec<i32>>(),
+        entities.iter().map(|(_, entity)| entity.health as i32).collect::<Vec<i32>>(),
+        entities.iter().map(|(_, entity)| entity.max_health as i32).collect::<Vec<i32>>(),
+        entities
+            .iter()
+            .map(|(_, entity)| bincode::serialize(&entity.stats).unwrap_or_default())
+            .collect::<Vec<Vec<u8>>>(),
+        entities.iter().map(|(_, entity)| entity.name.clone()).collect::<Vec<String>>(),
+        entities.iter().map(|(id, _)| id.encode()).collect::<Vec<String>>()
+    )
+    .await
+    .map(|result| {
+        let rows_changed = result.rows_affected();
+        if rows_changed != entities.len() as u64 {
+            tracing::warn!(
+                "Modified {} rows when bulk updating player entity data for {} entities",
+                rows_changed,
+                entities.len()
+            );
+        }
+    })
+}
+
+/// Fetch the coordinates of all the distinct chunks explored by a client's player entity in the named world.
+pub async fn explored_chunks_from_database(
+    client_id: Id, world: &str, db: &mut sqlx::PgConnection
+) -> sqlx::Result<HashSet<ChunkCoords>> {
+    let coords = db_query_from_file!("explored_chunks/select rows")
+        .bind(client_id.encode())
+        .bind(world)
+        .map(|row: sqlx::postgres::PgRow| ChunkCoords { x: row.get("chunk_x"), y: row.get("chunk_y") })
+        .fetch_all(db)
+        .await?;
+
+    Ok(coords.into_iter().collect())
+}
+
+/// Record in the database that a client's player entity has explored the chunk at the given coordinates in the named
+/// world. Nothing is changed should the chunk already be recorded as explored.
+pub async fn add_explored_chunk_to_database(
+    client_id: Id, world: &str, coords: ChunkCoords, db: &mut sqlx::PgConnection
+) -> sqlx::Result<()> {
+    db_query_from_file!("explored_chunks/create row", db, client_id.encode(), world, coords.x, coords.y).await?;
+    Ok(())
+}
+
 /// Binds all the components of a player entity to the given database query (excluding the entity ID & client ID).
 fn bind_entity_data<'a>(
     query: sqlx::query::Query<'a, sqlx::Postgres, sqlx::postgres::PgArguments>, entity: &Entity
@@ -94,6 +171,8 @@ fn bind_entity_data<'a>(
     query
         .bind(entity.pos.x)
         .bind(entity.pos.y)
+        .bind(encode_variant(entity.direction))
+        .bind(encode_variant(entity.facial_expression))
         .bind(encode_variant(entity.hair_style))
         .bind(encode_variant(entity.clothing_colour))
         .bind(encode_variant(entity.skin_colour))
@@ -101,6 +180,10 @@ fn bind_entity_data<'a>(
         .bind(bincode::serialize(&entity.gem_collection).unwrap_or_default())
         .bind(bincode::serialize(&entity.item_inventory).unwrap_or_default())
         .bind(entity.bombs_placed_count)
+        .bind(entity.health as i32)
+        .bind(entity.max_health as i32)
+        .bind(bincode::serialize(&entity.stats).unwrap_or_default())
+        .bind(entity.name.clone())
 }
 
 /// Encode an enum variant as a 16-bit integer.
@@ -112,7 +195,11 @@ fn encode_variant<T: IntoEnumIterator + PartialEq>(val: T) -> i16 {
 /// of the given enum type, then a random variant is returned and a warning message is printed.
 fn decode_variant<T: IntoEnumIterator>(val: i16) -> T {
     T::iter().nth(val as usize).unwrap_or_else(|| {
-        log::warn!("Failed to decode 32-bit integer {} into enum variant of type {}", val, std::any::type_name::<T>());
+        tracing::warn!(
+            "Failed to decode 32-bit integer {} into enum variant of type {}",
+            val,
+            std::any::type_name::<T>()
+        );
         random_variant()
     })
 }
@@ -121,3 +208,127 @@ fn decode_variant<T: IntoEnumIterator>(val: i16) -> T {
 fn random_variant<T: IntoEnumIterator>() -> T {
     T::iter().choose(&mut rand::thread_rng()).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use shared::{
+        gems, items,
+        maps::entities::{ClothingColour, Direction, FacialExpression, HairColour, HairStyle, SkinColour},
+        stats
+    };
+
+    use super::*;
+    use crate::maps::DEFAULT_SPAWN_POINT;
+
+    #[test]
+    fn encode_and_decode_variants() {
+        for direction in Direction::iter() {
+            assert_eq!(decode_variant::<Direction>(encode_variant(direction)), direction);
+        }
+        for expression in FacialExpression::iter() {
+            assert_eq!(decode_variant::<FacialExpression>(encode_variant(expression)), expression);
+        }
+
+        // Defaults of the direction & facial expression database columns:
+        assert_eq!(decode_variant::<Direction>(1), Direction::Down);
+        assert_eq!(decode_variant::<FacialExpression>(0), FacialExpression::Neutral);
+    }
+
+    /// Requires a PostgreSQL database specified by the `TEST_DATABASE_URL` environment variable.
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn player_database_round_trip() {
+        let db_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        let db_pool = sqlx::postgres::PgPoolOptions::new().connect(&db_url).await.unwrap();
+
+        crate::MIGRATOR.run(&db_pool).await.unwrap();
+
+        let mut db = db_pool.acquire().await.unwrap();
+
+        let client_id = crate::id::generate_with_timestamp();
+        let (entity_id, _) =
+            new_player_in_database(client_id, "Player".to_string(), DEFAULT_SPAWN_POINT, "test", &mut db)
+                .await
+                .unwrap();
+
+        let mut gem_collection = gems::Collection::default();
+        gem_collection.increase_quantity(gems::Gem::Diamond, 2);
+        let mut item_inventory = items::Inventory::default();
+        item_inventory.give(items::BoolItem::RunningShoes);
+
+        let entity = EntityBuilder::new()
+            .name("Renamed_Player".to_string())
+            .pos(TileCoords { x: -5, y: 12 })
+            .direction(Direction::Left)
+            .facial_expression(FacialExpression::Skeptical)
+            .hair_style(HairStyle::Quiff)
+            .clothing_colour(ClothingColour::Blue)
+            .skin_colour(SkinColour::Black)
+            .hair_colour(HairColour::Black)
+            .gem_collection(gem_collection)
+            .item_inventory(item_inventory)
+            .health(40)
+            .stats(stats::PlayerStats { tiles_walked: 80, chunks_explored: 3, ..Default::default() })
+            .build();
+        update_database_for_player(&entity, client_id, "test", &mut db).await.unwrap();
+
+        let (loaded_entity_id, loaded_entity) =
+            player_from_database(client_id, "test", &mut db).await.unwrap().unwrap();
+        assert_eq!(loaded_entity_id, entity_id);
+        assert_eq!(loaded_entity, entity);
+    }
+
+    /// Requires a PostgreSQL database specified by the `TEST_DATABASE_URL` environment variable.
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn bulk_update_database_entities() {
+        let db_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        let db_pool = sqlx::postgres::PgPoolOptions::new().connect(&db_url).await.unwrap();
+
+        crate::MIGRATOR.run(&db_pool).await.unwrap();
+
+        let mut db = db_pool.acquire().await.unwrap();
+
+        let mut players = Vec::new();
+        for i in 0..3 {
+            let client_id = crate::id::generate_with_timestamp();
+            let (entity_id, _) =
+                new_player_in_database(client_id, "Player".to_string(), DEFAULT_SPAWN_POINT, "test", &mut db)
+                    .await
+                    .unwrap();
+
+            let mut gem_collection = gems::Collection::default();
+            gem_collection.increase_quantity(gems::Gem::Emerald, i);
+
+            let entity = EntityBuilder::new()
+                .name(format!("Player_{}", i))
+                .pos(TileCoords { x: i as i32, y: -(i as i32) })
+                .direction(Direction::Up)
+                .gem_collection(gem_collection)
+                .build();
+
+            players.push((client_id, entity_id, entity));
+        }
+
+        let entities: Vec<(Id, &Entity)> = players.iter().map(|(_, entity_id, entity)| (*entity_id, entity)).collect();
+        bulk_update_entities(&entities, &mut db).await.unwrap();
+
+        let entity_ids: Vec<String> = entities.iter().map(|(id, _)| id.encode()).collect();
+        let updated_rows: i64 =
+            sqlx::query("SELECT COUNT(*) FROM client_entities WHERE entity_id = ANY($1) AND direction = $2")
+                .bind(entity_ids)
+                .bind(encode_variant(Direction::Up))
+                .fetch_one(&mut db)
+                .await
+                .unwrap()
+                .get(0);
+        assert_eq!(updated_rows, 3);
+
+        for (client_id, entity_id, entity) in players {
+            let (loaded_entity_id, loaded_entity) =
+                player_from_database(client_id, "test", &mut db).await.unwrap().unwrap();
+            assert_eq!(loaded_entity_id, entity_id);
+            assert_eq!(loaded_entity, entity);
+        }
+    }
+}
@@ -1,7 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
+use rand::Rng;
 use shared::maps::{Chunk, OffsetCoords, Tile, CHUNK_HEIGHT, CHUNK_WIDTH};
 
+use super::NeighborHints;
+
+/// The number of tiles from each edge of a chunk within which tile categories may be changed to match those of the
+/// neighbouring chunk (see [`ChunkPlan::blend_with_neighbors`]).
+pub const BLEND_WIDTH: i32 = 3;
+
 #[derive(Default)]
 pub struct ChunkPlan {
     tile_categories: HashMap<(i32, i32), TileCategory>
@@ -45,6 +52,12 @@ impl ChunkPlan {
     fn remove_juttting_and_unconnected_tiles_at(&mut self, offset_x: i32, offset_y: i32) {
         let category = self.get_category_at(offset_x, offset_y);
 
+        // Tiles of the default category cannot be removed, and continuing would recurse endlessly between enclosed
+        // default category tiles:
+        if category == TileCategory::default() {
+            return;
+        }
+
         let (above, below, left, right) = self.surrounding_not_equal_to(category, offset_x, offset_y);
 
         if (above && below && left) || (above && below && right) || (above && left && right) || (below && left && right)
@@ -66,6 +79,153 @@ impl ChunkPlan {
         }
     }
 
+    /// Feather the tile categories near the edges of the chunk into those of the given neighbouring chunk edges so
+    /// that grass-water & grass-dirt boundaries do not end abruptly at the chunk border. For each tile along a
+    /// neighbour's edge, the tiles of this chunk in line with it are changed to the neighbour's category with a
+    /// probability decreasing from 1 at the edge to 0 at [`BLEND_WIDTH`] tiles in, stopping at the first tile left
+    /// unchanged so that blended tiles always remain connected to the neighbouring chunk. The positions just beyond
+    /// each edge are set to the neighbour's categories so that correct transition tiles are placed.
+    pub fn blend_with_neighbors(&mut self, hints: &NeighborHints, rng: &mut impl Rng) {
+        if let Some(edge) = &hints.north {
+            self.blend_edge(edge, |along, depth| (along, CHUNK_HEIGHT - 1 - depth), rng);
+        }
+        if let Some(edge) = &hints.south {
+            self.blend_edge(edge, |along, depth| (along, depth), rng);
+        }
+        if let Some(edge) = &hints.east {
+            self.blend_edge(edge, |along, depth| (CHUNK_WIDTH - 1 - depth, along), rng);
+        }
+        if let Some(edge) = &hints.west {
+            self.blend_edge(edge, |along, depth| (depth, along), rng);
+        }
+    }
+
+    /// Blend with a single neighbouring edge. The `position` function maps an index along the edge & a depth into the
+    /// chunk (with a depth of -1 being just beyond the edge) to offset coordinates.
+    fn blend_edge(&mut self, edge: &[Tile], position: impl Fn(i32, i32) -> (i32, i32), rng: &mut impl Rng) {
+        for (along, tile) in edge.iter().enumerate() {
+            let neighbour_category = match TileCategory::of_tile(*tile) {
+                Some(category) => category,
+                None => continue
+            };
+
+            let (outside_x, outside_y) = position(along as i32, -1);
+            self.set_category_at(outside_x, outside_y, neighbour_category);
+
+            for depth in 0..BLEND_WIDTH {
+                let (offset_x, offset_y) = position(along as i32, depth);
+                let category = self.get_category_at(offset_x, offset_y);
+
+                if category == neighbour_category {
+                    continue;
+                }
+
+                let blendable = category == TileCategory::Grass || neighbour_category == TileCategory::Grass;
+                let probability = 1.0 - (depth as f64 / BLEND_WIDTH as f64);
+
+                if !blendable || !rng.gen_bool(probability) {
+                    break;
+                }
+
+                self.set_category_at(offset_x, offset_y, neighbour_category);
+            }
+        }
+    }
+
+    /// Fill the chunk with organic, cave-like regions using a cellular automaton. Each tile within the chunk is
+    /// initially filled ([`TileCategory::Dirt`]) with the given probability or otherwise left empty (the default
+    /// category). Then, for each iteration, filled tiles with fewer than `death_threshold` filled tiles among their 8
+    /// surrounding tiles are emptied while empty tiles with more than `birth_threshold` filled surrounding tiles are
+    /// filled. Positions beyond the edges of the chunk are always considered empty.
+    pub fn cellular_automata_fill(
+        &mut self, initial_fill_probability: f64, iterations: u32, birth_threshold: u8, death_threshold: u8,
+        rng: &mut impl Rng
+    ) {
+        let index = |offset_x: i32, offset_y: i32| (offset_y * CHUNK_WIDTH + offset_x) as usize;
+
+        let mut filled: Vec<bool> =
+            (0..CHUNK_WIDTH * CHUNK_HEIGHT).map(|_| rng.gen_bool(initial_fill_probability)).collect();
+
+        for _ in 0..iterations {
+            let previous = filled.clone();
+
+            for offset_x in 0..CHUNK_WIDTH {
+                for offset_y in 0..CHUNK_HEIGHT {
+                    let filled_neighbours = (offset_x - 1..=offset_x + 1)
+                        .flat_map(|x| (offset_y - 1..=offset_y + 1).map(move |y| (x, y)))
+                        .filter(|(x, y)| (*x, *y) != (offset_x, offset_y))
+                        .filter(|(x, y)| (0..CHUNK_WIDTH).contains(x) && (0..CHUNK_HEIGHT).contains(y))
+                        .filter(|(x, y)| previous[index(*x, *y)])
+                        .count() as u8;
+
+                    filled[index(offset_x, offset_y)] = {
+                        if previous[index(offset_x, offset_y)] {
+                            filled_neighbours >= death_threshold
+                        }
+                        else {
+                            filled_neighbours > birth_threshold
+                        }
+                    };
+                }
+            }
+
+            // No further iterations will make any difference once a stable state is reached:
+            if filled == previous {
+                break;
+            }
+        }
+
+        for offset_x in 0..CHUNK_WIDTH {
+            for offset_y in 0..CHUNK_HEIGHT {
+                let category =
+                    if filled[index(offset_x, offset_y)] { TileCategory::Dirt } else { TileCategory::default() };
+                self.set_category_at(offset_x, offset_y, category);
+            }
+        }
+    }
+
+    /// Returns the sizes (in ascending order) of each group of vertically/horizontally connected tiles of the given
+    /// category within the bounds of the chunk.
+    pub fn connected_component_sizes(&self, category: TileCategory) -> Vec<usize> {
+        let mut visited = HashSet::new();
+        let mut sizes = Vec::new();
+
+        for start_x in 0..CHUNK_WIDTH {
+            for start_y in 0..CHUNK_HEIGHT {
+                if self.get_category_at(start_x, start_y) != category || !visited.insert((start_x, start_y)) {
+                    continue;
+                }
+
+                // Breadth-first search of all tiles connected to the starting tile:
+
+                let mut size = 0;
+                let mut queue = VecDeque::new();
+                queue.push_back((start_x, start_y));
+
+                while let Some((x, y)) = queue.pop_front() {
+                    size += 1;
+
+                    for (neighbour_x, neighbour_y) in [(x, y + 1), (x, y - 1), (x - 1, y), (x + 1, y)] {
+                        let in_bounds =
+                            (0..CHUNK_WIDTH).contains(&neighbour_x) && (0..CHUNK_HEIGHT).contains(&neighbour_y);
+
+                        if in_bounds
+                            && self.get_category_at(neighbour_x, neighbour_y) == category
+                            && visited.insert((neighbour_x, neighbour_y))
+                        {
+                            queue.push_back((neighbour_x, neighbour_y));
+                        }
+                    }
+                }
+
+                sizes.push(size);
+            }
+        }
+
+        sizes.sort_unstable();
+        sizes
+    }
+
     fn maybe_transition_tile(
         &self, offset_x: i32, offset_y: i32, dirt_transitions: &TransitionTiles, water_transitions: &TransitionTiles
     ) -> Option<Tile> {
@@ -113,7 +273,7 @@ impl ChunkPlan {
         })
     }
 
-    fn get_category_at(&self, offset_x: i32, offset_y: i32) -> TileCategory {
+    pub fn get_category_at(&self, offset_x: i32, offset_y: i32) -> TileCategory {
         *self.tile_categories.get(&(offset_x, offset_y)).unwrap_or(&TileCategory::default())
     }
 
@@ -136,6 +296,59 @@ pub enum TileCategory {
     Water
 }
 
+impl TileCategory {
+    /// The category of the given generated tile, or `None` for tiles not placed using a chunk plan (e.g. cave walls).
+    pub fn of_tile(tile: Tile) -> Option<TileCategory> {
+        match tile {
+            Tile::Grass
+            | Tile::FlowerPatch
+            | Tile::Stones
+            | Tile::Shrub
+            | Tile::FlowerBlue
+            | Tile::FlowersYellowOrange
+            | Tile::Chest
+            | Tile::ChestOpened
+            | Tile::Sign => Some(TileCategory::Grass),
+
+            Tile::Dirt
+            | Tile::DirtGrassTop
+            | Tile::DirtGrassBottom
+            | Tile::DirtGrassLeft
+            | Tile::DirtGrassRight
+            | Tile::DirtGrassTopLeft
+            | Tile::DirtGrassTopRight
+            | Tile::DirtGrassBottomLeft
+            | Tile::DirtGrassBottomRight
+            | Tile::DirtGrassCornerTopLeft
+            | Tile::DirtGrassCornerTopRight
+            | Tile::DirtGrassCornerBottomLeft
+            | Tile::DirtGrassCornerBottomRight
+            | Tile::Rock
+            | Tile::RockEmerald
+            | Tile::RockRuby
+            | Tile::RockDiamond
+            | Tile::RockSapphire
+            | Tile::RockSmashed => Some(TileCategory::Dirt),
+
+            Tile::Water
+            | Tile::WaterGrassTop
+            | Tile::WaterGrassBottom
+            | Tile::WaterGrassLeft
+            | Tile::WaterGrassRight
+            | Tile::WaterGrassTopLeft
+            | Tile::WaterGrassTopRight
+            | Tile::WaterGrassBottomLeft
+            | Tile::WaterGrassBottomRight
+            | Tile::WaterGrassCornerTopLeft
+            | Tile::WaterGrassCornerTopRight
+            | Tile::WaterGrassCornerBottomLeft
+            | Tile::WaterGrassCornerBottomRight => Some(TileCategory::Water),
+
+            Tile::Wall | Tile::Ground | Tile::Sand | Tile::Lava | Tile::Ice | Tile::Portal | Tile::GemDrop => None
+        }
+    }
+}
+
 impl Default for TileCategory {
     fn default() -> Self {
         TileCategory::Grass
@@ -159,6 +372,8 @@ pub struct TransitionTiles {
 
 #[cfg(test)]
 mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
     use super::*;
 
     fn test_chunk_plan(
@@ -274,4 +489,170 @@ mod tests {
             dirt_positions
         );
     }
+
+    #[test]
+    fn enclosed_grass_left_alone() {
+        // ###
+        // #.#
+        // #.#
+        // ###
+
+        let mut chunk = ChunkPlan::default();
+        for x in 0..3 {
+            for y in 0..4 {
+                if (x, y) != (1, 1) && (x, y) != (1, 2) {
+                    chunk.set_category_at(x, y, TileCategory::Water);
+                }
+            }
+        }
+
+        chunk.remove_all_juttting_and_unconnected_tiles();
+
+        assert_eq!(chunk.get_category_at(1, 1), TileCategory::Grass);
+        assert_eq!(chunk.get_category_at(1, 2), TileCategory::Grass);
+        assert_eq!(chunk.connected_component_sizes(TileCategory::Water), vec![10]);
+    }
+
+    fn chunk_plan_with_dirt_at(dirt_positions: &[(i32, i32)]) -> ChunkPlan {
+        let mut chunk = ChunkPlan::default();
+
+        for (x, y) in dirt_positions {
+            chunk.set_category_at(*x, *y, TileCategory::Dirt);
+        }
+
+        chunk
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn connected_component_sizes() {
+        // .....
+        // ..##.
+        // .###.
+        // .....
+
+        let chunk = chunk_plan_with_dirt_at(&[(2, 1), (3, 1), (1, 2), (2, 2), (3, 2)]);
+        assert_eq!(chunk.connected_component_sizes(TileCategory::Dirt), vec![5]);
+        assert_eq!(chunk.connected_component_sizes(TileCategory::Water), Vec::<usize>::new());
+
+        // ......
+        // .#..#.
+        // .#..#.
+        // ....#.
+        // #.....
+
+        let mut chunk = chunk_plan_with_dirt_at(&[(1, 1), (1, 2), (4, 1), (4, 2), (4, 3), (0, 4)]);
+        assert_eq!(chunk.connected_component_sizes(TileCategory::Dirt), vec![1, 2, 3]);
+
+        // Diagonally adjacent tiles are not connected:
+        let chunk_diagonal = chunk_plan_with_dirt_at(&[(1, 1), (2, 2)]);
+        assert_eq!(chunk_diagonal.connected_component_sizes(TileCategory::Dirt), vec![1, 1]);
+
+        // None of these components survive the removal of jutting & unconnected tiles:
+        chunk.remove_all_juttting_and_unconnected_tiles();
+        assert_eq!(chunk.connected_component_sizes(TileCategory::Dirt), Vec::<usize>::new());
+
+        let grass_sizes = chunk.connected_component_sizes(TileCategory::Grass);
+        assert_eq!(grass_sizes, vec![(CHUNK_WIDTH * CHUNK_HEIGHT) as usize]);
+    }
+
+    #[test]
+    fn blend_feathers_towards_neighbour() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut plan = ChunkPlan::default();
+        let hints = NeighborHints { north: Some([Tile::Water; CHUNK_WIDTH as usize]), ..Default::default() };
+        plan.blend_with_neighbors(&hints, &mut rng);
+
+        for x in 0..CHUNK_WIDTH {
+            // Transition tiles are placed against the neighbouring chunk:
+            assert_eq!(plan.get_category_at(x, CHUNK_HEIGHT), TileCategory::Water);
+            // Row along the edge always takes the neighbour's category:
+            assert_eq!(plan.get_category_at(x, CHUNK_HEIGHT - 1), TileCategory::Water);
+
+            // Blended tiles are connected to the edge:
+            let water_depth = (0..CHUNK_HEIGHT)
+                .take_while(|depth| plan.get_category_at(x, CHUNK_HEIGHT - 1 - depth) == TileCategory::Water)
+                .count();
+            assert!((1..=BLEND_WIDTH as usize).contains(&water_depth));
+
+            for y in 0..CHUNK_HEIGHT - water_depth as i32 {
+                assert_eq!(plan.get_category_at(x, y), TileCategory::Grass);
+            }
+        }
+    }
+
+    #[test]
+    fn blend_only_grass_boundaries() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut plan = ChunkPlan::default();
+        for x in 0..CHUNK_WIDTH {
+            for y in 0..CHUNK_HEIGHT {
+                plan.set_category_at(x, y, TileCategory::Dirt);
+            }
+        }
+
+        // Dirt is not blended into water, and tiles of no category (e.g. sand) are ignored:
+        let hints = NeighborHints {
+            east: Some([Tile::Water; CHUNK_HEIGHT as usize]),
+            west: Some([Tile::Sand; CHUNK_HEIGHT as usize]),
+            ..Default::default()
+        };
+        plan.blend_with_neighbors(&hints, &mut rng);
+
+        assert_eq!(plan.connected_component_sizes(TileCategory::Dirt), vec![(CHUNK_WIDTH * CHUNK_HEIGHT) as usize]);
+        assert_eq!(plan.get_category_at(CHUNK_WIDTH, 0), TileCategory::Water);
+        assert_eq!(plan.get_category_at(-1, 0), TileCategory::Grass);
+
+        // Grass neighbours are blended into dirt:
+        let hints = NeighborHints { south: Some([Tile::Shrub; CHUNK_WIDTH as usize]), ..Default::default() };
+        plan.blend_with_neighbors(&hints, &mut rng);
+
+        assert!((0..CHUNK_WIDTH).all(|x| plan.get_category_at(x, 0) == TileCategory::Grass));
+        assert!((0..CHUNK_WIDTH).all(|x| plan.get_category_at(x, BLEND_WIDTH) == TileCategory::Dirt));
+    }
+
+    fn categories_within_chunk(plan: &ChunkPlan) -> Vec<TileCategory> {
+        (0..CHUNK_WIDTH).flat_map(|x| (0..CHUNK_HEIGHT).map(move |y| plan.get_category_at(x, y))).collect()
+    }
+
+    #[test]
+    fn cellular_automata_initial_fill() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut plan = ChunkPlan::default();
+        plan.cellular_automata_fill(0.45, 0, 4, 3, &mut rng);
+
+        let categories = categories_within_chunk(&plan);
+        let fill_fraction = categories.iter().filter(|category| **category == TileCategory::Dirt).count() as f64
+            / categories.len() as f64;
+        assert!((0.3..0.6).contains(&fill_fraction), "{:.3} of the chunk initially filled", fill_fraction);
+
+        plan.cellular_automata_fill(0.0, 5, 4, 3, &mut rng);
+        assert!(categories_within_chunk(&plan).iter().all(|category| *category == TileCategory::Grass));
+
+        // Even corner tiles (with only 3 neighbours within the chunk) survive:
+        plan.cellular_automata_fill(1.0, 5, 4, 3, &mut rng);
+        assert!(categories_within_chunk(&plan).iter().all(|category| *category == TileCategory::Dirt));
+    }
+
+    #[test]
+    fn cellular_automata_converges() {
+        for seed in 0..20 {
+            let fill = |iterations| {
+                let mut plan = ChunkPlan::default();
+                plan.cellular_automata_fill(0.45, iterations, 4, 3, &mut StdRng::seed_from_u64(seed));
+                plan
+            };
+
+            let converged = categories_within_chunk(&fill(50));
+            assert_eq!(converged, categories_within_chunk(&fill(51)));
+            assert_eq!(converged, categories_within_chunk(&fill(100)));
+
+            // The automaton smooths the initial noise into fewer, larger regions:
+            let initial_regions = fill(0).connected_component_sizes(TileCategory::Dirt).len();
+            assert!(fill(50).connected_component_sizes(TileCategory::Dirt).len() < initial_regions);
+        }
+    }
 }
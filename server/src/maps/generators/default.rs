@@ -1,49 +1,233 @@
 use noise::Seedable;
-use rand::{distributions::Distribution, rngs::StdRng, Rng, SeedableRng};
-use shared::maps::{Chunk, ChunkCoords, Tile, CHUNK_HEIGHT, CHUNK_WIDTH};
+use rand::{distributions::Distribution, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use shared::maps::{Chunk, ChunkCoords, OffsetCoords, Tile, TileCoords, CHUNK_HEIGHT, CHUNK_WIDTH};
 
 use super::{
     chunknoise::ChunkNoise,
-    chunkplan::{ChunkPlan, TileCategory}
+    chunkplan::{ChunkPlan, TileCategory},
+    NeighborHints
 };
 
-const DIRT_TILE_CHOICES: &[Tile] = &[Tile::Dirt, Tile::Rock, Tile::RockEmerald, Tile::RockRuby, Tile::RockDiamond];
-const DIRT_TILE_WEIGHTS: &[usize] = &[600, 15, 10, 5, 1];
+pub(super) const DIRT_TILE_CHOICES: &[Tile] =
+    &[Tile::Dirt, Tile::Rock, Tile::RockEmerald, Tile::RockRuby, Tile::RockDiamond];
+pub(super) const DIRT_TILE_WEIGHTS: &[usize] = &[600, 15, 10, 5, 1];
 
-const GRASS_TILE_CHOICES: &[Tile] = &[Tile::Grass, Tile::FlowerPatch, Tile::Stones, Tile::Shrub];
-const GRASS_TILE_WEIGHTS: &[usize] = &[900, 10, 8, 5];
+pub(super) const GRASS_TILE_CHOICES: &[Tile] = &[Tile::Grass, Tile::FlowerPatch, Tile::Stones, Tile::Shrub];
+pub(super) const GRASS_TILE_WEIGHTS: &[usize] = &[900, 10, 8, 5];
+
+/// Sapphires are only found in caves.
+const CAVE_GROUND_TILE_CHOICES: &[Tile] =
+    &[Tile::Ground, Tile::Rock, Tile::RockEmerald, Tile::RockRuby, Tile::RockDiamond, Tile::RockSapphire];
+const CAVE_GROUND_TILE_WEIGHTS: &[usize] = &[300, 20, 12, 6, 2, 1];
+
+/// Connected groups of dirt/water tiles smaller than this size are considered 'small'.
+const SMALL_COMPONENT_SIZE: usize = 4;
+/// A warning is logged should a generated chunk contain more than this many small groups of dirt/water tiles.
+const MAX_SMALL_COMPONENTS: usize = 3;
+
+/// The probability of a river crossing each edge between two surface chunks.
+const RIVER_CHANCE_PER_EDGE: f64 = 0.15;
+/// Rivers cross chunk edges at least this many tiles away from the chunk's corners.
+const RIVER_EDGE_MARGIN: i32 = 2;
+/// Rivers head straight into a chunk for this many tiles after crossing its edge so that they meet the river in the
+/// neighbouring chunk head on.
+const RIVER_STRAIGHT_LENGTH: i32 = 2;
+/// How many times more likely each step of a river's random walk is to head downhill (negative y) than sideways.
+const RIVER_DOWNHILL_BIAS: u32 = 3;
 
 /// Default map chunk generator for GemGame. Algorithm is as follows:
 /// * Generate Perlin noise for coordinates within the chunk as well as immediately around the chunk (see
 ///   [`ChunkNoise`]).
 /// * Use the noise to determine which category (grass, water, or dirt) each tile will be.
+/// * Feather the categories near the chunk's edges into those of any already generated neighbouring chunks (see
+///   [`ChunkPlan::blend_with_neighbors`]).
+/// * Carve rivers of water through the plan (see [`DefaultGenerator::generate_river_seeds`]).
 /// * Iterate through tile categories and turn into water all dirt and grass tile categories that have 3 or 4 water tile
 ///   category neighbours (considering only vertically & hoizontally adjacent - ignore diagonally adjacent).
 /// * Iterate through tile categories again and begin placing tiles using the relevant random distributions (see
 ///   [`super::maybe_transition_tile`] for how transition tiles are placed).
+///
+/// Chunks with a negative y coordinate are instead generated as a cave system - see
+/// [`DefaultGenerator::generate_cave`].
 pub struct DefaultGenerator {
+    seed: u32,
     terrain_noise_func: noise::OpenSimplex,
     flower_noise_func: noise::Perlin,
+    cave_noise_func: noise::OpenSimplex,
     dirt_dist: rand::distributions::WeightedIndex<usize>,
-    grass_dist: rand::distributions::WeightedIndex<usize>
+    grass_dist: rand::distributions::WeightedIndex<usize>,
+    cave_ground_dist: rand::distributions::WeightedIndex<usize>,
+    /// The probability of a chest being placed in each generated surface chunk.
+    chest_chance: f64
 }
 
-impl super::Generator for DefaultGenerator {
-    fn new(seed: u32) -> Self {
+impl DefaultGenerator {
+    pub fn with_chest_chance(seed: u32, chest_chance: f64) -> Self {
         DefaultGenerator {
+            seed,
             terrain_noise_func: noise::OpenSimplex::new().set_seed(seed),
             flower_noise_func: noise::Perlin::new().set_seed(seed),
+            cave_noise_func: noise::OpenSimplex::new().set_seed(seed.wrapping_add(1)),
             dirt_dist: rand::distributions::WeightedIndex::new(DIRT_TILE_WEIGHTS).unwrap(),
-            grass_dist: rand::distributions::WeightedIndex::new(GRASS_TILE_WEIGHTS).unwrap()
+            grass_dist: rand::distributions::WeightedIndex::new(GRASS_TILE_WEIGHTS).unwrap(),
+            cave_ground_dist: rand::distributions::WeightedIndex::new(CAVE_GROUND_TILE_WEIGHTS).unwrap(),
+            chest_chance
         }
     }
 
+    /// Generate a cave chunk. Tiles where the cave noise value is close to zero become passages of
+    /// [`Tile::Ground`] (with rock & gem deposits scattered throughout) while all other tiles become [`Tile::Wall`].
+    fn generate_cave(&self, chunk_coords: ChunkCoords, rng: &mut StdRng) -> Chunk {
+        let cave_noise = ChunkNoise::new(self.cave_noise_func, chunk_coords, 0.08, 1.0);
+
+        let mut chunk = Chunk::default();
+
+        for offset_x in 0..CHUNK_WIDTH {
+            for offset_y in 0..CHUNK_HEIGHT {
+                let tile = {
+                    if should_be_cave_passage(cave_noise.sample(offset_x, offset_y)) {
+                        CAVE_GROUND_TILE_CHOICES[self.cave_ground_dist.sample(rng)]
+                    }
+                    else {
+                        Tile::Wall
+                    }
+                };
+
+                let coords = OffsetCoords { x: offset_x as u8, y: offset_y as u8 };
+                chunk.set_tile_at_offset(coords, tile);
+            }
+        }
+
+        chunk
+    }
+
+    /// The tile coordinates of the points on the edges of the chunk at the given coordinates where rivers enter or
+    /// leave the chunk. Whether a river crosses each edge (and where along the edge it does so) is determined solely by
+    /// the generator seed & the coordinates of the edge, so the chunks either side of an edge always agree on where
+    /// rivers cross it. Rivers are not generated in caves or in the chunk in which players are spawned.
+    pub fn generate_river_seeds(&self, chunk_x: i32, chunk_y: i32) -> Vec<TileCoords> {
+        let (base_x, base_y) = (chunk_x * CHUNK_WIDTH, chunk_y * CHUNK_HEIGHT);
+
+        let crossings = [
+            self.river_crossing_offset(chunk_x, chunk_y, false).map(|offset| (offset, CHUNK_HEIGHT - 1)),
+            self.river_crossing_offset(chunk_x, chunk_y - 1, false).map(|offset| (offset, 0)),
+            self.river_crossing_offset(chunk_x, chunk_y, true).map(|offset| (CHUNK_WIDTH - 1, offset)),
+            self.river_crossing_offset(chunk_x - 1, chunk_y, true).map(|offset| (0, offset))
+        ];
+
+        crossings
+            .iter()
+            .flatten()
+            .map(|(offset_x, offset_y)| TileCoords { x: base_x + offset_x, y: base_y + offset_y })
+            .collect()
+    }
+
+    /// The offset along the edge between the chunk at the given coordinates and the chunk either to its right
+    /// (`vertical` edge) or above it at which a river crosses that edge, or `None` should no river cross the edge.
+    fn river_crossing_offset(&self, chunk_x: i32, chunk_y: i32, vertical: bool) -> Option<i32> {
+        let other_chunk = if vertical { (chunk_x + 1, chunk_y) } else { (chunk_x, chunk_y + 1) };
+        if chunk_y < 0 || (chunk_x, chunk_y) == (0, 0) || other_chunk == (0, 0) {
+            return None;
+        }
+
+        let coords = ChunkCoords { x: chunk_x, y: chunk_y };
+        let mut rng = StdRng::seed_from_u64(self.river_rng_seed(coords, vertical as u64));
+        let edge_length = if vertical { CHUNK_HEIGHT } else { CHUNK_WIDTH };
+
+        // Rivers are 2 tiles wide so room is left for the second tile before the margin:
+        rng.gen_bool(RIVER_CHANCE_PER_EDGE)
+            .then(|| rng.gen_range(RIVER_EDGE_MARGIN..edge_length - RIVER_EDGE_MARGIN - 1))
+    }
+
+    /// Carve a river into the plan from each of the chunk's river seeds. Each river runs straight into the chunk
+    /// before randomly walking (favouring heading downhill) to a point within the chunk at which all the chunk's rivers
+    /// meet. A lake is formed at that point should only a single river flow into the chunk.
+    fn carve_rivers(&self, chunk_coords: ChunkCoords, plan: &mut ChunkPlan) {
+        let seeds = self.generate_river_seeds(chunk_coords.x, chunk_coords.y);
+        if seeds.is_empty() {
+            return;
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.river_rng_seed(chunk_coords, 2));
+
+        let confluence = (
+            rng.gen_range(RIVER_EDGE_MARGIN..CHUNK_WIDTH - RIVER_EDGE_MARGIN - 1),
+            rng.gen_range(RIVER_EDGE_MARGIN..CHUNK_HEIGHT - RIVER_EDGE_MARGIN - 1)
+        );
+
+        for seed in &seeds {
+            let offset = (seed.x - chunk_coords.x * CHUNK_WIDTH, seed.y - chunk_coords.y * CHUNK_HEIGHT);
+
+            let inward = match offset {
+                (_, y) if y == CHUNK_HEIGHT - 1 => (0, -1),
+                (_, 0) => (0, 1),
+                (0, _) => (1, 0),
+                _ => (-1, 0)
+            };
+
+            // Begin just beyond the edge so that suitable transition tiles are placed alongside the river:
+            let mut position = (offset.0 - inward.0, offset.1 - inward.1);
+
+            for _ in 0..=RIVER_STRAIGHT_LENGTH {
+                carve_river_at(plan, position);
+                position = (position.0 + inward.0, position.1 + inward.1);
+            }
+
+            while position != confluence {
+                let step_x = (confluence.0 - position.0).signum();
+                let step_y = (confluence.1 - position.1).signum();
+
+                // Only steps that bring the river closer to the confluence are considered, ensuring that it is reached:
+                let mut steps = Vec::with_capacity(2);
+                if step_x != 0 {
+                    steps.push(((step_x, 0), 1));
+                }
+                if step_y != 0 {
+                    steps.push(((0, step_y), if step_y < 0 { RIVER_DOWNHILL_BIAS } else { 1 }));
+                }
+
+                let ((step_x, step_y), _) = *steps.choose_weighted(&mut rng, |(_, weight)| *weight).unwrap();
+                position = (position.0 + step_x, position.1 + step_y);
+
+                carve_river_at(plan, position);
+            }
+        }
+
+        if seeds.len() == 1 {
+            for x in confluence.0 - 1..=confluence.0 + 1 {
+                for y in confluence.1 - 1..=confluence.1 + 1 {
+                    carve_river_at(plan, (x, y));
+                }
+            }
+        }
+    }
+
+    /// Seed for a random number generator that is unique to the given chunk coordinates & purpose.
+    fn river_rng_seed(&self, coords: ChunkCoords, purpose: u64) -> u64 {
+        let packed_coords = ((coords.x as u32 as u64) << 32) | coords.y as u32 as u64;
+        packed_coords.wrapping_mul(5).wrapping_add(purpose) ^ ((self.seed as u64) << 23)
+    }
+}
+
+impl super::Generator for DefaultGenerator {
+    fn new(seed: u32) -> Self {
+        DefaultGenerator::with_chest_chance(seed, super::CHEST_CHANCE_PER_CHUNK)
+    }
+
     fn generate(&self, chunk_coords: ChunkCoords) -> Chunk {
+        self.generate_with_hints(chunk_coords, &NeighborHints::default())
+    }
+
+    fn generate_with_hints(&self, chunk_coords: ChunkCoords, hints: &NeighborHints) -> Chunk {
         // Prepare RNG, noise, distributions:
 
         let rng_seed = (chunk_coords.x as u64) ^ (chunk_coords.y as u64);
         let mut rng = StdRng::seed_from_u64(rng_seed);
 
+        if chunk_coords.y < 0 {
+            return self.generate_cave(chunk_coords, &mut rng);
+        }
+
         let terrain_noise = ChunkNoise::new(self.terrain_noise_func, chunk_coords, 0.05, 1.0);
 
         let flower_noise_generators = vec![
@@ -71,11 +255,33 @@ impl super::Generator for DefaultGenerator {
             }
         }
 
+        self.carve_rivers(chunk_coords, &mut plan);
+        plan.blend_with_neighbors(hints, &mut rng);
         plan.remove_all_juttting_and_unconnected_tiles();
 
+        // Removal of jutting & unconnected tiles should leave few small groups of dirt & water tiles:
+
+        for category in [TileCategory::Dirt, TileCategory::Water] {
+            let small_components = plan
+                .connected_component_sizes(category)
+                .into_iter()
+                .take_while(|size| *size < SMALL_COMPONENT_SIZE)
+                .count();
+
+            if small_components > MAX_SMALL_COMPONENTS {
+                tracing::warn!(
+                    "Generated chunk at {} has {} groups of {:?} tiles smaller than {} tiles",
+                    chunk_coords,
+                    small_components,
+                    category,
+                    SMALL_COMPONENT_SIZE
+                );
+            }
+        }
+
         // Produce a chunk based on the chunk plan:
 
-        plan.to_chunk(
+        let mut chunk = plan.to_chunk(
             &super::DIRT_GRASS_TRANSITION_TILES,
             &super::WATER_GRASS_TRANSITION_TILES,
             |category, offset_x, offset_y| {
@@ -107,7 +313,12 @@ impl super::Generator for DefaultGenerator {
                     TileCategory::Water => Tile::Water // TODO: Add more water tile types.
                 }
             }
-        )
+        );
+
+        super::maybe_place_chest(&mut chunk, &mut rng, self.chest_chance);
+        super::place_signs(chunk_coords, &mut chunk);
+
+        chunk
     }
 
     fn name(&self) -> &'static str {
@@ -115,10 +326,150 @@ impl super::Generator for DefaultGenerator {
     }
 }
 
+/// Rivers are 2 tiles wide, so each position a river passes through is the bottom-left of a 2x2 area of water.
+fn carve_river_at(plan: &mut ChunkPlan, (offset_x, offset_y): (i32, i32)) {
+    for (x, y) in
+        [(offset_x, offset_y), (offset_x + 1, offset_y), (offset_x, offset_y + 1), (offset_x + 1, offset_y + 1)]
+    {
+        plan.set_category_at(x, y, TileCategory::Water);
+    }
+}
+
 fn should_be_water(noise_sample: f64) -> bool {
     noise_sample <= -0.15
 }
 
-fn should_be_dirt(noise_sample: f64) -> bool {
+pub(super) fn should_be_dirt(noise_sample: f64) -> bool {
     noise_sample >= 0.25
 }
+
+fn should_be_cave_passage(noise_sample: f64) -> bool {
+    noise_sample.abs() <= 0.2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maps::generators::Generator;
+
+    fn chunk_contains(chunk: &Chunk, tile: Tile) -> bool {
+        chunk.tiles().any(|(_, t)| *t == tile)
+    }
+
+    #[test]
+    fn generate_cave_chunk() {
+        let gen = DefaultGenerator::new(0);
+
+        let cave_chunk = gen.generate(ChunkCoords { x: 0, y: -1 });
+        assert!(chunk_contains(&cave_chunk, Tile::Wall));
+        assert!(chunk_contains(&cave_chunk, Tile::Ground));
+
+        let surface_chunk = gen.generate(ChunkCoords { x: 0, y: 0 });
+        assert!(!chunk_contains(&surface_chunk, Tile::Wall));
+        assert!(!chunk_contains(&surface_chunk, Tile::Ground));
+    }
+
+    #[test]
+    fn surface_blended_with_neighbours() {
+        let gen = DefaultGenerator::new(0);
+        let coords = ChunkCoords { x: 3, y: 2 };
+
+        // Without hints, chunks are generated as they would be otherwise:
+        let unhinted = gen.generate_with_hints(coords, &NeighborHints::default());
+        assert!(unhinted.tiles().eq(gen.generate(coords).tiles()));
+
+        let hints = NeighborHints { north: Some([Tile::Water; CHUNK_WIDTH as usize]), ..Default::default() };
+        let chunk = gen.generate_with_hints(coords, &hints);
+
+        for x in 0..CHUNK_WIDTH {
+            let tile = chunk.tile_at_offset(OffsetCoords { x: x as u8, y: (CHUNK_HEIGHT - 1) as u8 });
+            assert_eq!(TileCategory::of_tile(tile), Some(TileCategory::Water));
+        }
+    }
+
+    #[test]
+    fn river_seeds_shared_with_neighbours() {
+        // Fixed seeds so that any failure can be reproduced:
+        for seed in [0, 1, 42, 1234, u32::MAX] {
+            assert_river_seeds_shared_with_neighbours(seed);
+        }
+    }
+
+    /// Asserts that each river seed generated using the given seed has a matching seed in the neighbouring chunk.
+    fn assert_river_seeds_shared_with_neighbours(seed: u32) {
+        let gen = DefaultGenerator::new(seed);
+        let mut seed_count = 0;
+
+        for chunk_x in -10..10 {
+            for chunk_y in 0..10 {
+                let seeds = gen.generate_river_seeds(chunk_x, chunk_y);
+                seed_count += seeds.len();
+
+                for seed in seeds {
+                    let offset = (seed.x - chunk_x * CHUNK_WIDTH, seed.y - chunk_y * CHUNK_HEIGHT);
+
+                    // The tile on the other side of the edge is where the river enters/leaves the neighbouring chunk:
+                    let (other_chunk, other_seed) = match offset {
+                        (_, 0) => ((chunk_x, chunk_y - 1), TileCoords { y: seed.y - 1, ..seed }),
+                        (_, y) if y == CHUNK_HEIGHT - 1 => {
+                            ((chunk_x, chunk_y + 1), TileCoords { y: seed.y + 1, ..seed })
+                        }
+                        (0, _) => ((chunk_x - 1, chunk_y), TileCoords { x: seed.x - 1, ..seed }),
+                        _ => ((chunk_x + 1, chunk_y), TileCoords { x: seed.x + 1, ..seed })
+                    };
+
+                    assert!(gen.generate_river_seeds(other_chunk.0, other_chunk.1).contains(&other_seed));
+                }
+            }
+        }
+
+        assert!(seed_count > 0);
+
+        // No rivers in the spawn chunk or caves:
+        assert!(gen.generate_river_seeds(0, 0).is_empty());
+        assert!(gen.generate_river_seeds(3, -1).is_empty());
+    }
+
+    #[test]
+    fn rivers_continuous_across_chunks() {
+        // Fixed seeds so that any failure can be reproduced:
+        for seed in [0, 1, 42, 1234, u32::MAX] {
+            assert_rivers_continuous_across_chunks(seed);
+        }
+    }
+
+    /// Asserts that every river seed generated using the given seed is water in the generated chunk.
+    fn assert_rivers_continuous_across_chunks(seed: u32) {
+        let gen = DefaultGenerator::new(seed);
+        let is_water = |tile| TileCategory::of_tile(tile) == Some(TileCategory::Water);
+
+        for chunk_x in 1..8 {
+            for chunk_y in 1..8 {
+                let chunk = gen.generate(ChunkCoords { x: chunk_x, y: chunk_y });
+
+                for seed in gen.generate_river_seeds(chunk_x, chunk_y) {
+                    let offset = seed.as_chunk_offset_coords();
+                    assert!(is_water(chunk.tile_at_offset(offset)), "River missing at {}", seed);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chests_placed_at_configured_chance() {
+        let always = DefaultGenerator::with_chest_chance(0, 1.0);
+        let never = DefaultGenerator::with_chest_chance(0, 0.0);
+
+        for x in 0..4 {
+            let coords = ChunkCoords { x, y: 0 };
+            assert_eq!(
+                chunk_contains(&always.generate(coords), Tile::Chest),
+                chunk_contains(&never.generate(coords), Tile::Grass)
+            );
+            assert!(!chunk_contains(&never.generate(coords), Tile::Chest));
+        }
+
+        // Chests are not placed in caves:
+        assert!(!chunk_contains(&always.generate(ChunkCoords { x: 0, y: -1 }), Tile::Chest));
+    }
+}
@@ -0,0 +1,114 @@
+use noise::Seedable;
+use rand::{distributions::Distribution, rngs::StdRng, SeedableRng};
+use shared::maps::{Chunk, ChunkCoords, Tile, CHUNK_HEIGHT, CHUNK_WIDTH};
+
+use super::{
+    chunknoise::ChunkNoise,
+    chunkplan::{ChunkPlan, TileCategory},
+    NeighborHints
+};
+
+/// Desert ground is mostly bare dirt with sparse rocks (the occasional one containing a gem). No plants grow here.
+const DESERT_TILE_CHOICES: &[Tile] = &[Tile::Dirt, Tile::Rock, Tile::RockRuby, Tile::RockDiamond];
+const DESERT_TILE_WEIGHTS: &[usize] = &[900, 40, 4, 1];
+
+/// Generates an arid map of dirt and rocks broken up only by rare oases. Algorithm is as follows:
+/// * Generate noise for coordinates within & immediately around the chunk (see [`ChunkNoise`]).
+/// * Plan every tile as dirt except where the noise is low enough for there to be an oasis, which is planned as water.
+/// * Feather the plan near the chunk's edges into any already generated neighbouring chunks.
+/// * Remove jutting & unconnected tiles from the plan and then place tiles, surrounding each oasis with transition
+///   tiles while the rest of the dirt is scattered with rocks.
+pub struct DesertGenerator {
+    terrain_noise_func: noise::OpenSimplex,
+    desert_dist: rand::distributions::WeightedIndex<usize>
+}
+
+impl super::Generator for DesertGenerator {
+    fn new(seed: u32) -> Self {
+        DesertGenerator {
+            terrain_noise_func: noise::OpenSimplex::new().set_seed(seed),
+            desert_dist: rand::distributions::WeightedIndex::new(DESERT_TILE_WEIGHTS).unwrap()
+        }
+    }
+
+    fn generate(&self, chunk_coords: ChunkCoords) -> Chunk {
+        self.generate_with_hints(chunk_coords, &NeighborHints::default())
+    }
+
+    fn generate_with_hints(&self, chunk_coords: ChunkCoords, hints: &NeighborHints) -> Chunk {
+        let rng_seed = (chunk_coords.x as u64) ^ (chunk_coords.y as u64);
+        let mut rng = StdRng::seed_from_u64(rng_seed);
+
+        let terrain_noise = ChunkNoise::new(self.terrain_noise_func, chunk_coords, 0.04, 1.0);
+
+        let mut plan = ChunkPlan::default();
+
+        for offset_x in -1..CHUNK_WIDTH + 2 {
+            for offset_y in -1..CHUNK_HEIGHT + 2 {
+                let category = {
+                    if should_be_oasis(terrain_noise.sample(offset_x, offset_y)) {
+                        TileCategory::Water
+                    }
+                    else {
+                        TileCategory::Dirt
+                    }
+                };
+
+                plan.set_category_at(offset_x, offset_y, category);
+            }
+        }
+
+        plan.blend_with_neighbors(hints, &mut rng);
+        plan.remove_all_juttting_and_unconnected_tiles();
+
+        let mut chunk = plan.to_chunk(
+            &super::DIRT_GRASS_TRANSITION_TILES,
+            &super::WATER_GRASS_TRANSITION_TILES,
+            |category, _, _| match category {
+                TileCategory::Water => Tile::Water,
+                // Tiles removed from the plan for jutting out become grass tiles in the plan, which would otherwise
+                // leave plants in the desert:
+                TileCategory::Dirt | TileCategory::Grass => DESERT_TILE_CHOICES[self.desert_dist.sample(&mut rng)]
+            }
+        );
+
+        super::place_signs(chunk_coords, &mut chunk);
+
+        chunk
+    }
+
+    fn name(&self) -> &'static str {
+        "desert"
+    }
+}
+
+fn should_be_oasis(noise_sample: f64) -> bool {
+    noise_sample <= -0.45
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::maps::generators::Generator;
+
+    #[test]
+    fn generate_desert() {
+        let generator = DesertGenerator::new(rand::random());
+        let mut rng = rand::thread_rng();
+
+        let chunks: Vec<Chunk> = (0..10)
+            .map(|_| ChunkCoords { x: rng.gen_range(-1000..1000), y: rng.gen_range(-1000..1000) })
+            .map(|coords| generator.generate(coords))
+            .collect();
+
+        let tile_count = chunks.len() * (CHUNK_WIDTH * CHUNK_HEIGHT) as usize;
+        let dirt_count = chunks.iter().flat_map(|chunk| chunk.tiles()).filter(|(_, tile)| **tile == Tile::Dirt).count();
+        let dirt_fraction = dirt_count as f64 / tile_count as f64;
+        assert!((0.7..0.98).contains(&dirt_fraction), "Dirt makes up {:.3} of the desert", dirt_fraction);
+
+        let plants = [Tile::Grass, Tile::FlowerPatch, Tile::FlowerBlue, Tile::FlowersYellowOrange, Tile::Shrub];
+        assert!(chunks.iter().flat_map(|chunk| chunk.tiles()).all(|(_, tile)| !plants.contains(tile)));
+    }
+}
@@ -0,0 +1,423 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use shared::maps::{Chunk, ChunkCoords, OffsetCoords, Tile, CHUNK_HEIGHT, CHUNK_WIDTH};
+
+use super::{
+    chunkplan::{ChunkPlan, TileCategory},
+    NeighborHints
+};
+
+/// Partitions are only split should both resulting partitions be at least this many tiles wide/tall.
+const MIN_PARTITION_SIZE: i32 = 6;
+/// The number of times each chunk is recursively split into partitions (so each chunk has at most 2^n rooms).
+const MAX_SPLIT_DEPTH: u32 = 2;
+/// Rooms are at least this many tiles wide & tall.
+const MIN_ROOM_SIZE: i32 = 3;
+/// Corridors leave chunks at least this many tiles away from the chunk's corners.
+const EXIT_CORNER_MARGIN: i32 = 2;
+
+/// Parameters of the cellular automaton that determines where caverns may open up around rooms & corridors (see
+/// [`ChunkPlan::cellular_automata_fill`]).
+const CAVERN_FILL_PROBABILITY: f64 = 0.6;
+const CAVERN_ITERATIONS: u32 = 4;
+const CAVERN_BIRTH_THRESHOLD: u8 = 4;
+const CAVERN_DEATH_THRESHOLD: u8 = 3;
+
+/// Position of a tile within a chunk (x & y offsets), allowing for negative offsets during calculations.
+type Offset = (i32, i32);
+
+/// Generates an underground maze of rectangular rooms connected by narrow corridors. Algorithm is as follows:
+/// * Fill the chunk with [`Tile::Wall`].
+/// * Recursively split the chunk into partitions (binary space partitioning) and place a room of [`Tile::Ground`]
+///   within each partition, leaving at least one wall tile between the room and the edge of its partition.
+/// * Connect the rooms of each pair of sibling partitions with an L-shaped corridor.
+/// * Carve a corridor from each exit point on the chunk's edges to the nearest room. Exit points are derived from the
+///   generator seed & the coordinates of the edge, so neighbouring chunks agree on where the corridors between them
+///   are. Should a neighbouring chunk have already been generated then the corridors leaving it are used instead (see
+///   [`NeighborHints`]).
+/// * Open up caverns of ground wherever walls connected to the rooms & corridors are left empty by a cellular automaton
+///   (excluding the chunk's edges so that only exits lead into neighbouring chunks).
+pub struct DungeonGenerator {
+    seed: u32
+}
+
+impl DungeonGenerator {
+    /// Generate the chunk at the given coordinates, also returning the rooms (in offset coordinates) placed in it.
+    fn generate_layout(&self, chunk_coords: ChunkCoords, hints: &NeighborHints) -> (Chunk, Vec<Rect>) {
+        let mut rng = StdRng::seed_from_u64(self.rng_seed(chunk_coords, 0));
+
+        let mut chunk = Chunk::default();
+        chunk.tiles_mut().for_each(|(_, tile)| *tile = Tile::Wall);
+
+        let mut rooms = Vec::new();
+        let mut corridors = Vec::new();
+        let whole_chunk = Rect { x: 0, y: 0, width: CHUNK_WIDTH, height: CHUNK_HEIGHT };
+        partition(whole_chunk, 0, &mut rng, &mut rooms, &mut corridors);
+
+        for room in &rooms {
+            for x in room.x..room.x + room.width {
+                for y in room.y..room.y + room.height {
+                    set_ground(&mut chunk, x, y);
+                }
+            }
+        }
+
+        for (from, to) in corridors {
+            carve_corridor(&mut chunk, from, to, false);
+        }
+
+        for (exit, edge) in self.exits(chunk_coords, hints) {
+            let nearest_room_centre =
+                rooms.iter().map(Rect::centre).min_by_key(|(x, y)| (x - exit.0).abs() + (y - exit.1).abs()).unwrap();
+
+            // Corridors head straight into the chunk from exits so as to not open up the rest of the edge:
+            carve_corridor(&mut chunk, exit, nearest_room_centre, matches!(edge, Edge::North | Edge::South));
+        }
+
+        carve_caverns(&mut chunk, &mut rng);
+
+        // Players are spawned at tile coordinates (0, 0), which is the bottom-left corner of chunk (0, 0), so a
+        // corridor from there is carved so that they are not trapped in a wall:
+        if chunk_coords == (ChunkCoords { x: 0, y: 0 }) {
+            set_ground(&mut chunk, 0, 0);
+            set_ground(&mut chunk, 1, 0);
+
+            let nearest_room_centre = rooms.iter().map(Rect::centre).min_by_key(|(x, y)| x + y).unwrap();
+            carve_corridor(&mut chunk, (1, 1), nearest_room_centre, true);
+        }
+
+        (chunk, rooms)
+    }
+
+    /// The offsets of the tiles on the edges of the chunk at the given coordinates through which corridors leave the
+    /// chunk, along with the edge each is on.
+    fn exits(&self, chunk_coords: ChunkCoords, hints: &NeighborHints) -> Vec<(Offset, Edge)> {
+        let (x, y) = (chunk_coords.x, chunk_coords.y);
+
+        let edges = [
+            (Edge::North, hints.north.as_ref(), self.edge_exit_offset(x, y, false), CHUNK_HEIGHT - 1),
+            (Edge::South, hints.south.as_ref(), self.edge_exit_offset(x, y - 1, false), 0),
+            (Edge::East, hints.east.as_ref(), self.edge_exit_offset(x, y, true), CHUNK_WIDTH - 1),
+            (Edge::West, hints.west.as_ref(), self.edge_exit_offset(x - 1, y, true), 0)
+        ];
+
+        let mut exits = Vec::new();
+
+        for (edge, hint, default_offset, edge_position) in edges {
+            // Exits should line up with the corridors that leave the neighbouring chunk if it has already been
+            // generated:
+            let offsets: Vec<i32> = match hint {
+                Some(neighbour_edge) => (EXIT_CORNER_MARGIN..neighbour_edge.len() as i32 - EXIT_CORNER_MARGIN)
+                    .filter(|offset| neighbour_edge[*offset as usize] == Tile::Ground)
+                    .collect(),
+                None => vec![default_offset]
+            };
+
+            for offset in offsets {
+                let exit = match edge {
+                    Edge::North | Edge::South => (offset, edge_position),
+                    Edge::East | Edge::West => (edge_position, offset)
+                };
+                exits.push((exit, edge));
+            }
+        }
+
+        exits
+    }
+
+    /// The offset along the edge between the chunk at the given coordinates and the chunk either to its right
+    /// (`vertical` edge) or above it at which a corridor crosses that edge.
+    fn edge_exit_offset(&self, x: i32, y: i32, vertical: bool) -> i32 {
+        let mut rng = StdRng::seed_from_u64(self.rng_seed(ChunkCoords { x, y }, if vertical { 1 } else { 2 }));
+        let edge_length = if vertical { CHUNK_HEIGHT } else { CHUNK_WIDTH };
+
+        rng.gen_range(EXIT_CORNER_MARGIN..edge_length - EXIT_CORNER_MARGIN)
+    }
+
+    /// Seed for a random number generator that is unique to the given chunk coordinates & purpose.
+    fn rng_seed(&self, coords: ChunkCoords, purpose: u64) -> u64 {
+        let packed_coords = ((coords.x as u32 as u64) << 32) | coords.y as u32 as u64;
+        packed_coords.wrapping_mul(3).wrapping_add(purpose) ^ ((self.seed as u64) << 21)
+    }
+}
+
+impl super::Generator for DungeonGenerator {
+    fn new(seed: u32) -> Self {
+        DungeonGenerator { seed }
+    }
+
+    fn generate(&self, chunk_coords: ChunkCoords) -> Chunk {
+        self.generate_with_hints(chunk_coords, &NeighborHints::default())
+    }
+
+    fn generate_with_hints(&self, chunk_coords: ChunkCoords, hints: &NeighborHints) -> Chunk {
+        self.generate_layout(chunk_coords, hints).0
+    }
+
+    fn name(&self) -> &'static str {
+        "dungeon"
+    }
+}
+
+/// Area of a chunk given in offset coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Rect {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32
+}
+
+impl Rect {
+    fn centre(&self) -> Offset {
+        (self.x + (self.width / 2), self.y + (self.height / 2))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Edge {
+    North,
+    South,
+    East,
+    West
+}
+
+/// Recursively split the given area, adding a room to `rooms` for each partition that is not split any further and
+/// the pair of points that should be joined by a corridor to `corridors` for each split. Returns the centre of one of
+/// the rooms placed within the area.
+fn partition(
+    area: Rect, depth: u32, rng: &mut StdRng, rooms: &mut Vec<Rect>, corridors: &mut Vec<(Offset, Offset)>
+) -> Offset {
+    let can_split_x = area.width >= MIN_PARTITION_SIZE * 2;
+    let can_split_y = area.height >= MIN_PARTITION_SIZE * 2;
+
+    if depth >= MAX_SPLIT_DEPTH || !(can_split_x || can_split_y) {
+        let room = room_within(area, rng);
+        rooms.push(room);
+        return room.centre();
+    }
+
+    // Prefer splitting the longer side so as to avoid long, thin partitions:
+    let split_x = match (can_split_x, can_split_y) {
+        (true, true) => area.width > area.height || (area.width == area.height && rng.gen()),
+        (can_split_x, _) => can_split_x
+    };
+
+    let (first, second) = {
+        if split_x {
+            let width = rng.gen_range(MIN_PARTITION_SIZE..=area.width - MIN_PARTITION_SIZE);
+            (Rect { width, ..area }, Rect { x: area.x + width, width: area.width - width, ..area })
+        }
+        else {
+            let height = rng.gen_range(MIN_PARTITION_SIZE..=area.height - MIN_PARTITION_SIZE);
+            (Rect { height, ..area }, Rect { y: area.y + height, height: area.height - height, ..area })
+        }
+    };
+
+    let first_room_centre = partition(first, depth + 1, rng, rooms, corridors);
+    let second_room_centre = partition(second, depth + 1, rng, rooms, corridors);
+    corridors.push((first_room_centre, second_room_centre));
+
+    first_room_centre
+}
+
+/// Randomly choose a room that fits within the given area with at least one tile to spare on every side.
+fn room_within(area: Rect, rng: &mut StdRng) -> Rect {
+    let width = rng.gen_range(MIN_ROOM_SIZE..=area.width - 2);
+    let height = rng.gen_range(MIN_ROOM_SIZE..=area.height - 2);
+
+    Rect {
+        x: rng.gen_range(area.x + 1..=area.x + area.width - 1 - width),
+        y: rng.gen_range(area.y + 1..=area.y + area.height - 1 - height),
+        width,
+        height
+    }
+}
+
+/// Carve an L-shaped corridor of ground tiles between the given offsets, moving either vertically or horizontally
+/// first.
+fn carve_corridor(chunk: &mut Chunk, from: Offset, to: Offset, vertical_first: bool) {
+    let corner = if vertical_first { (from.0, to.1) } else { (to.0, from.1) };
+
+    for (start, end) in [(from, corner), (corner, to)] {
+        for x in start.0.min(end.0)..=start.0.max(end.0) {
+            for y in start.1.min(end.1)..=start.1.max(end.1) {
+                set_ground(chunk, x, y);
+            }
+        }
+    }
+}
+
+/// Turn into ground those wall tiles away from the chunk's edges that are left empty by a cellular automaton and that
+/// are connected to existing ground, so that every cavern is reachable from the rooms.
+fn carve_caverns(chunk: &mut Chunk, rng: &mut StdRng) {
+    let mut plan = ChunkPlan::default();
+    plan.cellular_automata_fill(
+        CAVERN_FILL_PROBABILITY,
+        CAVERN_ITERATIONS,
+        CAVERN_BIRTH_THRESHOLD,
+        CAVERN_DEATH_THRESHOLD,
+        rng
+    );
+
+    let mut to_visit: Vec<Offset> = chunk
+        .tiles()
+        .filter(|(_, tile)| **tile == Tile::Ground)
+        .map(|(offset, _)| (offset.x as i32, offset.y as i32))
+        .collect();
+
+    while let Some((x, y)) = to_visit.pop() {
+        for (neighbour_x, neighbour_y) in [(x, y + 1), (x, y - 1), (x - 1, y), (x + 1, y)] {
+            let away_from_edges =
+                (1..CHUNK_WIDTH - 1).contains(&neighbour_x) && (1..CHUNK_HEIGHT - 1).contains(&neighbour_y);
+
+            if away_from_edges
+                && plan.get_category_at(neighbour_x, neighbour_y) == TileCategory::default()
+                && chunk.tile_at_offset(OffsetCoords { x: neighbour_x as u8, y: neighbour_y as u8 }) == Tile::Wall
+            {
+                set_ground(chunk, neighbour_x, neighbour_y);
+                to_visit.push((neighbour_x, neighbour_y));
+            }
+        }
+    }
+}
+
+fn set_ground(chunk: &mut Chunk, x: i32, y: i32) {
+    chunk.set_tile_at_offset(OffsetCoords { x: x as u8, y: y as u8 }, Tile::Ground);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use shared::maps::TileCoords;
+
+    use super::*;
+    use crate::maps::generators::Generator;
+
+    fn chunk_tiles(chunk: &Chunk, chunk_coords: ChunkCoords) -> impl Iterator<Item = (TileCoords, Tile)> + '_ {
+        chunk.tiles().map(move |(offset, tile)| {
+            let coords = TileCoords {
+                x: (chunk_coords.x * CHUNK_WIDTH) + offset.x as i32,
+                y: (chunk_coords.y * CHUNK_HEIGHT) + offset.y as i32
+            };
+            (coords, *tile)
+        })
+    }
+
+    #[test]
+    fn rooms_connected_across_chunks() {
+        // Fixed seeds so that any failure can be reproduced:
+        for seed in [0, 1, 42, 1234, u32::MAX] {
+            assert_rooms_connected_across_chunks(seed);
+        }
+    }
+
+    /// Asserts that every room generated around the origin using the given seed is connected to all others.
+    fn assert_rooms_connected_across_chunks(seed: u32) {
+        let generator = DungeonGenerator::new(seed);
+
+        let mut tiles = HashMap::new();
+        let mut rooms = Vec::new();
+
+        for chunk_x in -1..=1 {
+            for chunk_y in -1..=1 {
+                let chunk_coords = ChunkCoords { x: chunk_x, y: chunk_y };
+                let (chunk, chunk_rooms) = generator.generate_layout(chunk_coords, &NeighborHints::default());
+
+                tiles.extend(chunk_tiles(&chunk, chunk_coords));
+                rooms.extend(chunk_rooms.into_iter().map(|room| Rect {
+                    x: room.x + (chunk_x * CHUNK_WIDTH),
+                    y: room.y + (chunk_y * CHUNK_HEIGHT),
+                    ..room
+                }));
+            }
+        }
+
+        let is_ground = |coords: &TileCoords| tiles.get(coords) == Some(&Tile::Ground);
+        let neighbours = |coords: TileCoords| {
+            [(0, 1), (0, -1), (-1, 0), (1, 0)].map(|(x, y)| TileCoords { x: coords.x + x, y: coords.y + y })
+        };
+
+        // Every room is made of ground & has a corridor leading out of it:
+        for room in &rooms {
+            let room_tiles: Vec<_> = (room.x..room.x + room.width)
+                .flat_map(|x| (room.y..room.y + room.height).map(move |y| TileCoords { x, y }))
+                .collect();
+
+            assert!(room_tiles.iter().all(is_ground));
+            assert!(room_tiles
+                .iter()
+                .flat_map(|coords| neighbours(*coords))
+                .any(|coords| !room_tiles.contains(&coords) && is_ground(&coords)));
+        }
+
+        // All rooms (and the corridors between them) are connected, i.e. every ground tile can be reached from any
+        // room:
+        let start = TileCoords { x: rooms[0].x, y: rooms[0].y };
+        let mut reached = HashSet::new();
+        let mut to_visit = vec![start];
+
+        while let Some(coords) = to_visit.pop() {
+            if is_ground(&coords) && reached.insert(coords) {
+                to_visit.extend(neighbours(coords));
+            }
+        }
+
+        assert_eq!(reached.len(), tiles.keys().filter(|coords| is_ground(coords)).count());
+    }
+
+    #[test]
+    fn exits_aligned_with_hints() {
+        let generator = DungeonGenerator::new(0);
+        let chunk_coords = ChunkCoords { x: 5, y: 5 };
+
+        // A neighbour generated without hints has exits that line up with those of this chunk anyway:
+        let west_neighbour = generator.generate(ChunkCoords { x: 4, y: 5 });
+        let chunk = generator.generate(chunk_coords);
+
+        for y in 0..CHUNK_HEIGHT as u8 {
+            assert_eq!(
+                west_neighbour.tile_at_offset(OffsetCoords { x: CHUNK_WIDTH as u8 - 1, y }),
+                chunk.tile_at_offset(OffsetCoords { x: 0, y })
+            );
+        }
+
+        // Exits are placed wherever the neighbouring chunk's corridors end:
+        let mut west = [Tile::Wall; CHUNK_HEIGHT as usize];
+        west[3] = Tile::Ground;
+        west[9] = Tile::Ground;
+        let chunk =
+            generator.generate_with_hints(chunk_coords, &NeighborHints { west: Some(west), ..Default::default() });
+
+        let west_edge: Vec<Tile> =
+            (0..CHUNK_HEIGHT as u8).map(|y| chunk.tile_at_offset(OffsetCoords { x: 0, y })).collect();
+        assert_eq!(west_edge, west.to_vec());
+    }
+
+    #[test]
+    fn caverns_leave_walls() {
+        // Fixed seeds so that any failure can be reproduced:
+        for seed in [0, 1, 42, 1234, u32::MAX] {
+            assert_caverns_leave_walls(seed);
+        }
+    }
+
+    /// Asserts that at least a quarter of the tiles of each chunk generated using the given seed are walls.
+    fn assert_caverns_leave_walls(seed: u32) {
+        let generator = DungeonGenerator::new(seed);
+
+        for x in 0..10 {
+            let chunk = generator.generate(ChunkCoords { x, y: 0 });
+            let wall_count = chunk.tiles().filter(|(_, tile)| **tile == Tile::Wall).count();
+            assert!(wall_count >= (CHUNK_WIDTH * CHUNK_HEIGHT) as usize / 4, "Only {} wall tiles", wall_count);
+        }
+    }
+
+    #[test]
+    fn spawn_position_not_walled_in() {
+        let chunk = DungeonGenerator::new(0).generate(ChunkCoords { x: 0, y: 0 });
+
+        assert_eq!(chunk.tile_at_offset(OffsetCoords { x: 0, y: 0 }), Tile::Ground);
+        assert_eq!(chunk.tile_at_offset(OffsetCoords { x: 1, y: 1 }), Tile::Ground);
+    }
+}
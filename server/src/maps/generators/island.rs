@@ -0,0 +1,132 @@
+use noise::Seedable;
+use rand::{distributions::Distribution, rngs::StdRng, SeedableRng};
+use shared::maps::{Chunk, ChunkCoords, OffsetCoords, Tile, CHUNK_HEIGHT, CHUNK_WIDTH};
+
+use super::{
+    chunknoise::ChunkNoise,
+    default::{should_be_dirt, DIRT_TILE_CHOICES, DIRT_TILE_WEIGHTS, GRASS_TILE_CHOICES, GRASS_TILE_WEIGHTS}
+};
+
+/// Radius of the island (in tiles) used when none is specified in the upper bits of the seed.
+const DEFAULT_ISLAND_RADIUS: u32 = 96;
+
+/// Width (in tiles) of the ring of beach around the edge of the island.
+const BEACH_WIDTH: f64 = 4.0;
+
+/// Generates a single circular island centred on tile coordinates (0, 0) and surrounded by water. The island has a
+/// ring of [`Tile::Sand`] around its edge while the terrain inland is a mix of grass & dirt determined by noise.
+///
+/// When created using [`super::Generator::new`], the upper 16 bits of the seed specify the radius of the island in
+/// tiles (a value of 0 means [`DEFAULT_ISLAND_RADIUS`]) while the lower 16 bits are used as the noise seed.
+pub struct IslandGenerator {
+    radius: u32,
+    terrain_noise_func: noise::OpenSimplex,
+    dirt_dist: rand::distributions::WeightedIndex<usize>,
+    grass_dist: rand::distributions::WeightedIndex<usize>,
+    /// The probability of a chest being placed in each generated chunk (see [`super::maybe_place_chest`]).
+    chest_chance: f64
+}
+
+impl IslandGenerator {
+    pub fn with_radius(seed: u32, radius: u32) -> Self {
+        IslandGenerator {
+            radius,
+            terrain_noise_func: noise::OpenSimplex::new().set_seed(seed),
+            dirt_dist: rand::distributions::WeightedIndex::new(DIRT_TILE_WEIGHTS).unwrap(),
+            grass_dist: rand::distributions::WeightedIndex::new(GRASS_TILE_WEIGHTS).unwrap(),
+            chest_chance: super::CHEST_CHANCE_PER_CHUNK
+        }
+    }
+}
+
+impl super::Generator for IslandGenerator {
+    fn new(seed: u32) -> Self {
+        let radius = match seed >> 16 {
+            0 => DEFAULT_ISLAND_RADIUS,
+            radius => radius
+        };
+
+        IslandGenerator::with_radius(seed & 0xFFFF, radius)
+    }
+
+    fn generate(&self, chunk_coords: ChunkCoords) -> Chunk {
+        let rng_seed = (chunk_coords.x as u64) ^ (chunk_coords.y as u64);
+        let mut rng = StdRng::seed_from_u64(rng_seed);
+
+        let terrain_noise = ChunkNoise::new(self.terrain_noise_func, chunk_coords, 0.05, 1.0);
+
+        let mut chunk = Chunk::default();
+
+        for offset_x in 0..CHUNK_WIDTH {
+            for offset_y in 0..CHUNK_HEIGHT {
+                let tile_x = (chunk_coords.x * CHUNK_WIDTH + offset_x) as f64;
+                let tile_y = (chunk_coords.y * CHUNK_HEIGHT + offset_y) as f64;
+                let distance_from_centre = (tile_x * tile_x + tile_y * tile_y).sqrt();
+
+                let tile = {
+                    if distance_from_centre > self.radius as f64 {
+                        Tile::Water
+                    }
+                    else if distance_from_centre > self.radius as f64 - BEACH_WIDTH {
+                        Tile::Sand
+                    }
+                    else if should_be_dirt(terrain_noise.sample(offset_x, offset_y)) {
+                        DIRT_TILE_CHOICES[self.dirt_dist.sample(&mut rng)]
+                    }
+                    else {
+                        GRASS_TILE_CHOICES[self.grass_dist.sample(&mut rng)]
+                    }
+                };
+
+                let coords = OffsetCoords { x: offset_x as u8, y: offset_y as u8 };
+                chunk.set_tile_at_offset(coords, tile);
+            }
+        }
+
+        super::maybe_place_chest(&mut chunk, &mut rng, self.chest_chance);
+        super::place_signs(chunk_coords, &mut chunk);
+
+        chunk
+    }
+
+    fn name(&self) -> &'static str {
+        "island"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::maps::TileCoords;
+
+    use super::*;
+    use crate::maps::generators::Generator;
+
+    fn tile_at(gen: &IslandGenerator, coords: TileCoords) -> Tile {
+        gen.generate(coords.as_chunk_coords()).tile_at_offset(coords.as_chunk_offset_coords())
+    }
+
+    #[test]
+    fn generate_island() {
+        let gen = IslandGenerator::with_radius(0, 40);
+
+        assert_ne!(tile_at(&gen, TileCoords { x: 0, y: 0 }), Tile::Water);
+        assert_ne!(tile_at(&gen, TileCoords { x: 0, y: 0 }), Tile::Sand);
+
+        assert_eq!(tile_at(&gen, TileCoords { x: 38, y: 0 }), Tile::Sand);
+        assert_eq!(tile_at(&gen, TileCoords { x: 0, y: -38 }), Tile::Sand);
+
+        assert_eq!(tile_at(&gen, TileCoords { x: 41, y: 0 }), Tile::Water);
+        assert_eq!(tile_at(&gen, TileCoords { x: -30, y: 30 }), Tile::Water);
+
+        // Chunks far from the island should consist entirely of water:
+        let far_chunk = gen.generate(ChunkCoords { x: 10, y: -10 });
+        assert!(far_chunk.tiles().all(|(_, tile)| *tile == Tile::Water));
+    }
+
+    #[test]
+    fn island_radius_from_seed() {
+        assert_eq!(IslandGenerator::new(0).radius, DEFAULT_ISLAND_RADIUS);
+        assert_eq!(IslandGenerator::new(42).radius, DEFAULT_ISLAND_RADIUS);
+        assert_eq!(IslandGenerator::new((20 << 16) | 42).radius, 20);
+    }
+}
@@ -1,10 +1,31 @@
 mod chunknoise;
 mod chunkplan;
 pub mod default;
+pub mod desert;
+pub mod dungeon;
+pub mod island;
+pub mod queue;
 
 use chunkplan::TransitionTiles;
 pub use default::DefaultGenerator;
-use shared::maps::{Chunk, ChunkCoords, Tile};
+pub use desert::DesertGenerator;
+pub use dungeon::DungeonGenerator;
+pub use island::IslandGenerator;
+pub use queue::ChunkGenerationQueue;
+use rand::{seq::SliceRandom, Rng};
+use shared::maps::{Chunk, ChunkCoords, Map, OffsetCoords, Tile, TileCoords, CHUNK_HEIGHT, CHUNK_WIDTH};
+
+/// Names of all available generators (see [`by_name`]).
+pub const GENERATOR_NAMES: &[&str] = &["default", "island", "desert", "dungeon"];
+
+/// The default probability of a chest being placed in each generated chunk (see [`maybe_place_chest`]).
+pub const CHEST_CHANCE_PER_CHUNK: f64 = 0.05;
+
+/// Signs placed at fixed positions near where new players are spawned (see [`place_signs`]).
+const SIGNS: &[(TileCoords, &str)] = &[
+    (TileCoords { x: 0, y: 2 }, "Welcome to GemGame! Mine rocks to find gems."),
+    (TileCoords { x: -3, y: 1 }, "Chests like the ones scattered about this land can be opened by clicking on them.")
+];
 
 pub trait Generator {
     fn new(seed: u32) -> Self
@@ -12,9 +33,94 @@ pub trait Generator {
 
     fn generate(&self, chunk_coords: ChunkCoords) -> Chunk;
 
+    /// Generate the chunk at the given coordinates so that it lines up with those neighbouring chunks that have
+    /// already been generated (see [`NeighborHints`]). Generators that produce seamless chunks without knowledge of
+    /// their neighbours need not override this method, which by default ignores the hints.
+    fn generate_with_hints(&self, chunk_coords: ChunkCoords, _hints: &NeighborHints) -> Chunk {
+        self.generate(chunk_coords)
+    }
+
     fn name(&self) -> &'static str;
 }
 
+/// The tiles along the edges of the four chunks neighbouring a chunk that is to be generated. Each edge is the row or
+/// column of the neighbouring chunk that borders the chunk being generated (e.g. `north` is the bottom row of the chunk
+/// above), ordered by ascending offset. Edges of neighbours that have not yet been generated are `None`.
+#[derive(Debug, Default, Clone)]
+pub struct NeighborHints {
+    pub north: Option<[Tile; CHUNK_WIDTH as usize]>,
+    pub south: Option<[Tile; CHUNK_WIDTH as usize]>,
+    pub east: Option<[Tile; CHUNK_HEIGHT as usize]>,
+    pub west: Option<[Tile; CHUNK_HEIGHT as usize]>
+}
+
+impl NeighborHints {
+    /// Take the edges of those chunks neighbouring the given coordinates that are loaded in the given map.
+    pub fn from_loaded_chunks(map: &impl Map, coords: ChunkCoords) -> Self {
+        let row = |chunk_coords: ChunkCoords, y: i32| {
+            map.loaded_chunk_at(chunk_coords).map(|chunk| {
+                let mut edge = [Tile::default(); CHUNK_WIDTH as usize];
+                for (x, tile) in edge.iter_mut().enumerate() {
+                    *tile = chunk.tile_at_offset(OffsetCoords { x: x as u8, y: y as u8 });
+                }
+                edge
+            })
+        };
+
+        let column = |chunk_coords: ChunkCoords, x: i32| {
+            map.loaded_chunk_at(chunk_coords).map(|chunk| {
+                let mut edge = [Tile::default(); CHUNK_HEIGHT as usize];
+                for (y, tile) in edge.iter_mut().enumerate() {
+                    *tile = chunk.tile_at_offset(OffsetCoords { x: x as u8, y: y as u8 });
+                }
+                edge
+            })
+        };
+
+        NeighborHints {
+            north: row(ChunkCoords { x: coords.x, y: coords.y + 1 }, 0),
+            south: row(ChunkCoords { x: coords.x, y: coords.y - 1 }, CHUNK_HEIGHT - 1),
+            east: column(ChunkCoords { x: coords.x + 1, y: coords.y }, 0),
+            west: column(ChunkCoords { x: coords.x - 1, y: coords.y }, CHUNK_WIDTH - 1)
+        }
+    }
+}
+
+/// Create the generator with the given name (as returned by [`Generator::name`]), or return `None` should no such
+/// generator exist.
+pub fn by_name(name: &str, seed: u32) -> Option<Box<dyn Generator + Send>> {
+    match name {
+        "default" => Some(Box::new(DefaultGenerator::new(seed))),
+        "island" => Some(Box::new(IslandGenerator::new(seed))),
+        "desert" => Some(Box::new(DesertGenerator::new(seed))),
+        "dungeon" => Some(Box::new(DungeonGenerator::new(seed))),
+        _ => None
+    }
+}
+
+/// With the given probability, replace a randomly chosen grass tile in the chunk with a [`Tile::Chest`]. No chest is
+/// placed should the chunk contain no grass.
+fn maybe_place_chest(chunk: &mut Chunk, rng: &mut impl Rng, chance: f64) {
+    if !rng.gen_bool(chance) {
+        return;
+    }
+
+    let grass_offsets: Vec<_> =
+        chunk.tiles().filter(|(_, tile)| **tile == Tile::Grass).map(|(offset, _)| offset).collect();
+
+    if let Some(offset) = grass_offsets.choose(rng) {
+        chunk.set_tile_at_offset(*offset, Tile::Chest);
+    }
+}
+
+/// Place those signs given by [`SIGNS`] that are positioned within the chunk at the given coordinates.
+fn place_signs(chunk_coords: ChunkCoords, chunk: &mut Chunk) {
+    for (coords, text) in SIGNS.iter().filter(|(coords, _)| coords.as_chunk_coords() == chunk_coords) {
+        let placed = chunk.place_sign(coords.as_chunk_offset_coords(), text.to_string());
+        debug_assert!(placed, "sign text should not exceed the maximum length");
+    }
+}
+
 const DIRT_GRASS_TRANSITION_TILES: TransitionTiles = TransitionTiles {
     top: Tile::DirtGrassTop,
     bottom: Tile::DirtGrassBottom,
@@ -44,3 +150,49 @@ const WATER_GRASS_TRANSITION_TILES: TransitionTiles = TransitionTiles {
     corner_bottom_left: Tile::WaterGrassCornerBottomLeft,
     corner_bottom_right: Tile::WaterGrassCornerBottomRight
 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generators_by_name() {
+        for name in GENERATOR_NAMES {
+            assert_eq!(by_name(name, 0).unwrap().name(), *name);
+        }
+
+        assert!(by_name("nonexistent", 0).is_none());
+    }
+
+    #[test]
+    fn chest_placed_only_on_grass() {
+        let mut rng = rand::thread_rng();
+
+        let mut chunk = Chunk::default();
+        maybe_place_chest(&mut chunk, &mut rng, 1.0);
+        assert_eq!(chunk.tiles().filter(|(_, tile)| **tile == Tile::Chest).count(), 1);
+
+        let mut chunk = Chunk::default();
+        maybe_place_chest(&mut chunk, &mut rng, 0.0);
+        assert!(chunk.tiles().all(|(_, tile)| *tile == Tile::Grass));
+
+        let mut chunk = Chunk::default();
+        chunk.tiles_mut().for_each(|(_, tile)| *tile = Tile::Water);
+        maybe_place_chest(&mut chunk, &mut rng, 1.0);
+        assert!(chunk.tiles().all(|(_, tile)| *tile == Tile::Water));
+    }
+
+    #[test]
+    fn signs_placed_by_every_surface_generator() {
+        // Dungeons are entirely underground so contain no signs:
+        for name in GENERATOR_NAMES.iter().filter(|name| **name != "dungeon") {
+            let generator = by_name(name, 0).unwrap();
+
+            for (coords, text) in SIGNS {
+                let chunk = generator.generate(coords.as_chunk_coords());
+                assert_eq!(chunk.tile_at_offset(coords.as_chunk_offset_coords()), Tile::Sign);
+                assert_eq!(chunk.sign_text(coords.as_chunk_offset_coords()), Some(*text));
+            }
+        }
+    }
+}
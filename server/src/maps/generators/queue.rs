@@ -0,0 +1,123 @@
+use shared::maps::{Chunk, ChunkCoords};
+use tokio::sync::{mpsc, oneshot};
+
+use super::{Generator, NeighborHints};
+
+/// A request for the chunk at the given coordinates to be generated (taking into account the edges of its neighbours),
+/// along with the channel on which the generated chunk should be sent.
+type Request = (ChunkCoords, NeighborHints, oneshot::Sender<Chunk>);
+
+/// Generates chunks one at a time on a dedicated thread. As chunk generation is relatively expensive, generating
+/// many chunks simultaneously (e.g. when several players explore new areas of the map at once) would otherwise cause
+/// latency spikes for all connected players.
+#[derive(Clone)]
+pub struct ChunkGenerationQueue {
+    sender: mpsc::Sender<Request>,
+    generator_name: &'static str
+}
+
+impl ChunkGenerationQueue {
+    /// Spawn a thread that will generate chunks using the given generator. At most `queue_size` requests may be waiting
+    /// to be processed at any one time - should the queue be full, [`Self::generate`] will wait for space to become
+    /// available.
+    pub fn new(generator: Box<dyn Generator + Send>, queue_size: usize) -> Self {
+        let (sender, mut receiver) = mpsc::channel::<Request>(queue_size);
+        let generator_name = generator.name();
+
+        // Generation is CPU-bound so is performed on a separate thread so as to not hold up asynchronous tasks (the
+        // thread exits once every sender has been dropped):
+        std::thread::spawn(move || {
+            while let Some((coords, hints, response_sender)) = receiver.blocking_recv() {
+                tracing::trace!(chunk_coords = %coords, "Generating chunk using generator '{}'", generator_name);
+
+                // Requester may have stopped waiting for the chunk (e.g. their connection was closed):
+                let _ = response_sender.send(generator.generate_with_hints(coords, &hints));
+            }
+        });
+
+        ChunkGenerationQueue { sender, generator_name }
+    }
+
+    /// Wait for the chunk at the specified coordinates to be generated using the given hints.
+    pub async fn generate(&self, coords: ChunkCoords, hints: NeighborHints) -> Chunk {
+        let (response_sender, response_receiver) = oneshot::channel();
+
+        self.sender.send((coords, hints, response_sender)).await.expect("Chunk generation thread has stopped");
+        response_receiver.await.expect("Chunk generation thread stopped before generating requested chunk")
+    }
+
+    pub fn generator_name(&self) -> &'static str {
+        self.generator_name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc
+        },
+        thread,
+        time::Duration
+    };
+
+    use super::*;
+
+    /// Generator that keeps track of the greatest number of chunks it was ever generating simultaneously.
+    #[derive(Default)]
+    struct CountingGenerator {
+        generating: Arc<AtomicUsize>,
+        max_generating: Arc<AtomicUsize>,
+        generated: Arc<AtomicUsize>
+    }
+
+    impl Generator for CountingGenerator {
+        fn new(_seed: u32) -> Self {
+            CountingGenerator::default()
+        }
+
+        fn generate(&self, _chunk_coords: ChunkCoords) -> Chunk {
+            let generating = self.generating.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_generating.fetch_max(generating, Ordering::SeqCst);
+
+            thread::sleep(Duration::from_millis(5));
+
+            self.generating.fetch_sub(1, Ordering::SeqCst);
+            self.generated.fetch_add(1, Ordering::SeqCst);
+
+            Chunk::default()
+        }
+
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn concurrent_requests_serialised() {
+        let generator = CountingGenerator::new(0);
+        let (max_generating, generated) = (Arc::clone(&generator.max_generating), Arc::clone(&generator.generated));
+
+        let queue = ChunkGenerationQueue::new(Box::new(generator), 2);
+        assert_eq!(queue.generator_name(), "counting");
+
+        let tasks: Vec<_> = (0..2)
+            .map(|task| {
+                let queue = queue.clone();
+                tokio::spawn(async move {
+                    for y in 0..5 {
+                        queue.generate(ChunkCoords { x: task, y }, NeighborHints::default()).await;
+                    }
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        assert_eq!(generated.load(Ordering::SeqCst), 10);
+        assert_eq!(max_generating.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,131 @@
+use std::{convert::TryFrom, sync::Arc, time::Duration};
+
+use shared::{
+    maps::{Map, TileCoords},
+    messages::DamageSource,
+    Id
+};
+
+use super::{systems::WorldSystem, Modification, ServerMap};
+use crate::safe_zones::SafeZoneRegistry;
+
+/// The default number of milliseconds between each application of damage to players standing on hazardous tiles.
+pub const HAZARD_DAMAGE_INTERVAL_MS: u64 = 1000;
+
+/// Periodically damages players standing on hazardous tiles such as [`shared::maps::Tile::Lava`] by the amount given
+/// by [`shared::maps::Tile::hazard_damage`] (see [`ServerMap::damage_player`]). Players within a safe zone are not
+/// damaged.
+pub struct HazardSystem {
+    /// The number of ticks between each application of hazard damage.
+    ticks_per_damage: u32,
+    /// The number of ticks remaining until hazard damage is next applied.
+    ticks_until_damage: u32,
+    safe_zones: Arc<SafeZoneRegistry>
+}
+
+impl HazardSystem {
+    /// Create a system that damages players on hazardous tiles every `damage_interval` (rounded to a whole number of
+    /// ticks of length `tick_interval`).
+    pub fn new(damage_interval: Duration, tick_interval: Duration, safe_zones: Arc<SafeZoneRegistry>) -> Self {
+        let ticks_per_damage =
+            u32::try_from(damage_interval.as_millis() / tick_interval.as_millis().max(1)).unwrap_or(u32::MAX).max(1);
+
+        HazardSystem { ticks_per_damage, ticks_until_damage: ticks_per_damage, safe_zones }
+    }
+}
+
+impl WorldSystem for HazardSystem {
+    fn tick(&mut self, map: &mut ServerMap) -> Vec<Modification> {
+        self.ticks_until_damage -= 1;
+        if self.ticks_until_damage > 0 {
+            return Vec::new();
+        }
+        self.ticks_until_damage = self.ticks_per_damage;
+
+        let damaged: Vec<(Id, TileCoords, u32)> = map
+            .player_entities
+            .iter()
+            .filter(|(_, player)| !self.safe_zones.contains(player.pos))
+            .filter_map(|(id, player)| {
+                let damage = map.loaded_tile_at(player.pos)?.hazard_damage()?;
+                Some((*id, player.pos, damage))
+            })
+            .collect();
+
+        let mut modifications = Vec::new();
+
+        for (entity_id, position, amount) in damaged {
+            tracing::debug!("Entity {} dealt {} hazard damage at {}", entity_id, amount, position);
+
+            modifications.extend(map.damage_player(entity_id, amount));
+            modifications.push(Modification::EntityDamaged {
+                entity_id,
+                amount,
+                source: DamageSource::EnvironmentalHazard
+            });
+        }
+
+        modifications
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::{
+        maps::{
+            entities::{EntityBuilder, DEFAULT_MAX_HEALTH},
+            Chunk, ChunkCoords, SafeZone, Tile
+        },
+        mechanics::LAVA_DAMAGE
+    };
+
+    use super::*;
+
+    const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+    fn add_test_player(map: &mut ServerMap, pos: TileCoords) -> Id {
+        let player_id = crate::id::generate_random();
+        map.add_entity(player_id, EntityBuilder::new().pos(pos).build());
+        player_id
+    }
+
+    #[tokio::test]
+    async fn lava_damages_players_periodically() {
+        let mut map = ServerMap::new_with_default_generator(0);
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, Chunk::default());
+        let safe_zones = SafeZoneRegistry::new(vec![SafeZone { centre: TileCoords { x: 10, y: 10 }, radius: 0 }]);
+        map.add_system(Box::new(HazardSystem::new(Duration::from_millis(300), TICK_INTERVAL, Arc::new(safe_zones))));
+
+        for pos in [TileCoords { x: 2, y: 2 }, TileCoords { x: 10, y: 10 }] {
+            map.set_loaded_tile_at(pos, Tile::Lava);
+        }
+        let on_lava_id = add_test_player(&mut map, TileCoords { x: 2, y: 2 });
+        let on_grass_id = add_test_player(&mut map, TileCoords { x: 3, y: 2 });
+        let in_safe_zone_id = add_test_player(&mut map, TileCoords { x: 10, y: 10 });
+
+        assert!(map.tick().is_empty());
+        assert!(map.tick().is_empty());
+
+        match map.tick().as_slice() {
+            [Modification::EntityHealthChanged { entity_id, health, .. }, Modification::EntityDamaged {
+                entity_id: damaged_id,
+                amount,
+                source: DamageSource::EnvironmentalHazard
+            }] => {
+                assert_eq!(*entity_id, on_lava_id);
+                assert_eq!(*damaged_id, on_lava_id);
+                assert_eq!(*health, DEFAULT_MAX_HEALTH - LAVA_DAMAGE);
+                assert_eq!(*amount, LAVA_DAMAGE);
+            }
+            other => panic!("Unexpected modifications: {:?}", other)
+        }
+
+        assert!(map.tick().is_empty());
+        assert!(map.tick().is_empty());
+        assert_eq!(map.tick().len(), 2);
+
+        assert_eq!(map.entity_by_id(on_lava_id).unwrap().health, DEFAULT_MAX_HEALTH - (2 * LAVA_DAMAGE));
+        assert_eq!(map.entity_by_id(on_grass_id).unwrap().health, DEFAULT_MAX_HEALTH);
+        assert_eq!(map.entity_by_id(in_safe_zone_id).unwrap().health, DEFAULT_MAX_HEALTH);
+    }
+}
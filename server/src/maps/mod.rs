@@ -1,31 +1,51 @@
 pub mod chunks;
 pub mod entities;
 pub mod generators;
+pub mod hazards;
+pub mod monsters;
+pub mod snapshot;
+pub mod spatial_grid;
+pub mod systems;
 
 use std::{
     collections::{HashMap, HashSet},
     fmt
 };
 
-use generators::Generator;
+use generators::ChunkGenerationQueue;
 use shared::{
+    gems,
     maps::{
-        entities::{Direction, Entity},
+        entities::{Direction, Entity, FacialExpression, Monster},
         Chunk, ChunkCoords, Chunks, Map, Tile, TileCoords
     },
-    Id
+    messages, Id
 };
+use spatial_grid::SpatialGrid;
 use sqlx::Row;
 
 use crate::db_query_from_file;
 
-/// The context in which gameplay takes place. This structure manages all loaded tile chunks and player entities.
+/// The position at which new player entities are placed and at which player entities respawn after dying should no
+/// other spawn point be configured (see [`ServerMap::set_spawn_point`]).
+pub const DEFAULT_SPAWN_POINT: TileCoords = TileCoords { x: 0, y: 0 };
+
+/// The maximum Chebyshev distance from the spawn point at which a player entity may respawn should the spawn point
+/// itself be occupied.
+const RESPAWN_SEARCH_RADIUS: i32 = 3;
+
+/// The context in which gameplay takes place. This structure manages all loaded tile chunks, player entities, and
+/// monsters.
 pub struct ServerMap {
+    /// The name of the world that this map belongs to (see [`crate::worlds`]), under which its chunks & player
+    /// entities are stored in the database.
+    world: String,
+
     /// Seed used by the generator.
     seed: i32,
 
-    /// The generator to be used when new chunks are generated.
-    generator: Box<dyn Generator + Send>,
+    /// Queue through which new chunks are generated.
+    generation_queue: ChunkGenerationQueue,
 
     /// Chunks that are currently loaded (mapped to by chunk coordinate pairs).
     loaded_chunks: Chunks,
@@ -36,55 +56,118 @@ pub struct ServerMap {
     /// Player-controlled entities mapped to entity IDs.
     player_entities: HashMap<Id, Entity>,
 
-    /// Chunk coordinates mapped to sets of entity IDs. This hash map exists to allow the efficient look up of which
-    /// entities exists in which chunks.
-    chunk_coords_to_player_ids: HashMap<ChunkCoords, HashSet<Id>>
+    /// Names (in lowercase) reserved by players joining the game whose entities may not yet have been added to the map
+    /// (see [`ServerMap::reserve_player_name`]).
+    reserved_player_names: HashSet<String>,
+
+    /// The IDs of player entities partitioned by the chunks that those entities are in. This exists to allow the
+    /// efficient look up of which entities exist in and around a given position.
+    player_id_grid: SpatialGrid<Id>,
+
+    /// Monsters mapped to by their IDs. Unlike player entities, monsters are not saved to the database (see
+    /// [`monsters::MonsterSystem`]).
+    monsters: HashMap<Id, Monster>,
+
+    /// Systems run each server tick (see [`ServerMap::tick`]).
+    systems: Vec<Box<dyn systems::WorldSystem>>,
+
+    /// The position at which new player entities are placed and around which dead player entities respawn.
+    spawn_point: TileCoords
 }
 
 impl ServerMap {
-    pub async fn load_or_new(db_pool: &sqlx::PgPool) -> sqlx::Result<Self> {
+    /// Load the existing map of the named world from the database or create a new map should one not exist. A new map
+    /// will use the generator with the specified name (must be one of [`generators::GENERATOR_NAMES`]) with at most
+    /// `generation_queue_size` chunk generation requests queued at once. The name of that generator is saved with the
+    /// map so that an existing map continues to use the generator it was created with (a warning is logged should it
+    /// differ from the generator specified).
+    pub async fn load_or_new(
+        db_pool: &sqlx::PgPool, world: &str, generator_name: &str, generation_queue_size: usize
+    ) -> sqlx::Result<Self> {
+        let new_map = |seed: i32, generator_name: &str| {
+            let generator = generators::by_name(generator_name, seed as u32).expect("Unknown map generator name");
+            ServerMap {
+                world: world.to_string(),
+                ..ServerMap::new(seed, ChunkGenerationQueue::new(generator, generation_queue_size))
+            }
+        };
+
         let existing_map_option = db_query_from_file!("map/select row")
-            .map(|row: sqlx::postgres::PgRow| ServerMap::new_with_default_generator(row.get("seed")))
+            .bind(world)
+            .map(|row: sqlx::postgres::PgRow| {
+                let saved_generator_name: String = row.get("generator");
+
+                if saved_generator_name == generator_name {
+                    new_map(row.get("seed"), generator_name)
+                }
+                else if generators::GENERATOR_NAMES.contains(&saved_generator_name.as_str()) {
+                    tracing::warn!(
+                        world,
+                        saved_generator = %saved_generator_name,
+                        specified_generator = generator_name,
+                        "Map generator differs from that the existing map was created with so the latter will be used"
+                    );
+                    new_map(row.get("seed"), &saved_generator_name)
+                }
+                else {
+                    tracing::warn!(
+                        world,
+                        saved_generator = %saved_generator_name,
+                        specified_generator = generator_name,
+                        "Existing map was created with an unknown map generator so the generator specified will be used"
+                    );
+                    new_map(row.get("seed"), generator_name)
+                }
+            })
             .fetch_optional(db_pool)
             .await?;
 
         if let Some(existing_map) = existing_map_option {
-            log::debug!("Existing map loaded from database");
+            tracing::debug!(world, "Existing map loaded from database");
 
             sqlx::Result::Ok(existing_map)
         }
         else {
-            let new_map = ServerMap::new_with_default_generator(0); // TODO: Random seed.
+            let new_map = new_map(0, generator_name); // TODO: Random seed.
 
-            db_query_from_file!("map/create row").bind(new_map.seed).execute(db_pool).await.map(|_| {
-                log::debug!("Inserted newly generated map into database");
+            db_query_from_file!("map/create row", db_pool, world, new_map.seed, generator_name).await.map(|_| {
+                tracing::debug!(world, "Inserted newly generated map into database");
 
                 new_map
             })
         }
     }
 
-    pub fn new(seed: i32, generator: Box<dyn Generator + Send>) -> Self {
+    /// Create a map belonging to the world named [`crate::worlds::DEFAULT_WORLD_NAME`].
+    pub fn new(seed: i32, generation_queue: ChunkGenerationQueue) -> Self {
         ServerMap {
+            world: crate::worlds::DEFAULT_WORLD_NAME.to_string(),
             seed,
-            generator,
+            generation_queue,
             loaded_chunks: HashMap::new(),
             chunk_usage: HashMap::new(),
             player_entities: HashMap::new(),
-            chunk_coords_to_player_ids: HashMap::new()
+            reserved_player_names: HashSet::new(),
+            player_id_grid: SpatialGrid::default(),
+            monsters: HashMap::new(),
+            systems: Vec::new(),
+            spawn_point: DEFAULT_SPAWN_POINT
         }
     }
 
+    #[cfg(test)]
     pub fn new_with_default_generator(seed: i32) -> Self {
-        ServerMap::new(seed, Box::new(generators::DefaultGenerator::new(seed as u32)))
+        use generators::Generator;
+
+        let generator = Box::new(generators::DefaultGenerator::new(seed as u32));
+        ServerMap::new(seed, ChunkGenerationQueue::new(generator, 1))
     }
 
     /// Move an entity in a specified direction. This method checks if the desintation position is already occupied or
     /// a blocking tile (note that tile positions in unloaded chunks are considered blocking) - if it is then `None` is
     /// returned (`None` is also returned should an entity with the specified ID not be found). If the movement is
     /// deemed okay to go ahead, the entity's old position and new position (i.e. position after the movement is
-    /// applied) are returned. The hash map that keeps track of which entities reside in which chunks is updated
-    /// also.
+    /// applied) are returned. The spatial grid that keeps track of where entities are positioned is updated also.
     ///
     /// If the movement is on to a smashable tile (e.g. diamond rock) then the tile is updated. The caller does not have
     /// to notify their client nor the tasks of other clients of the tile change as it is the responsiblity of each
@@ -108,16 +191,9 @@ impl ServerMap {
                 entity_mut.pos = new_position;
                 entity_mut.direction = direction;
 
-                let old_pos_chunk_coords = old_position.as_chunk_coords();
-                let new_pos_chunk_coords = new_position.as_chunk_coords();
-
-                // Check if the entity is moving across chunk boundaries:
-                if old_pos_chunk_coords != new_pos_chunk_coords {
-                    self.chunk_coords_to_player_ids.entry(old_pos_chunk_coords).and_modify(|x| {
-                        x.remove(&entity_id);
-                    });
-                    self.chunk_coords_to_player_ids.entry(new_pos_chunk_coords).or_default().insert(entity_id);
-                }
+                // Move the entity's entry in the spatial grid (the map is borrowed mutably so this happens
+                // atomically with the position change above as far as other tasks are concerned):
+                self.player_id_grid.insert(new_position, entity_id);
 
                 // Create an option that is `Some` if the destination tile is smashable:
                 let smashed_tile_option =
@@ -139,19 +215,191 @@ impl ServerMap {
         }
     }
 
+    /// Move an entity directly to the given position (e.g. upon entering a portal) provided that position is free (see
+    /// [`Map::is_position_free`]). The entity's previous position is returned, or `None` should the position not be
+    /// free or an entity with the specified ID not be found.
+    pub fn teleport_entity(&mut self, entity_id: Id, destination: TileCoords) -> Option<TileCoords> {
+        if !self.is_position_free(destination) {
+            return None;
+        }
+
+        let entity = self.player_entities.get_mut(&entity_id)?;
+        let old_position = entity.pos;
+        entity.pos = destination;
+
+        self.player_id_grid.insert(destination, entity_id);
+
+        Some(old_position)
+    }
+
+    /// Deal the given amount of damage to the player entity with the specified ID. Should the entity's health reach
+    /// zero, it dies (see [`Self::kill_player`]). Returns the resulting modifications to be sent to connection tasks.
+    pub fn damage_player(&mut self, entity_id: Id, amount: u32) -> Vec<Modification> {
+        let player = match self.player_entities.get_mut(&entity_id) {
+            Some(player) => player,
+            None => return Vec::new()
+        };
+
+        player.health = player.health.saturating_sub(amount);
+
+        let mut modifications = vec![Modification::EntityHealthChanged {
+            entity_id,
+            chunk_coords: player.pos.as_chunk_coords(),
+            health: player.health
+        }];
+
+        if player.health == 0 {
+            modifications.extend(self.kill_player(entity_id));
+        }
+
+        modifications
+    }
+
+    /// Half of the gems of the player entity with the specified ID are dropped where it died (see
+    /// [`Tile::GemDrop`]), after which the entity is moved to the nearest free position to the spawn point and its
+    /// health is restored.
+    fn kill_player(&mut self, entity_id: Id) -> Vec<Modification> {
+        let respawn_at = self.respawn_position();
+
+        let death_position = match self.player_entities.get(&entity_id) {
+            Some(player) => player.pos,
+            None => return Vec::new()
+        };
+        let drop_loaded = self.is_tile_loaded(death_position);
+
+        let player = self.player_entities.get_mut(&entity_id).unwrap();
+        let direction = player.direction;
+
+        let gems_dropped = if drop_loaded { player.gem_collection.take_half() } else { gems::Collection::default() };
+
+        player.pos = respawn_at;
+        player.health = player.max_health;
+        let health = player.health;
+
+        self.player_id_grid.insert(respawn_at, entity_id);
+
+        tracing::debug!(
+            entity_id = %entity_id,
+            "Player entity died at {} and respawned at {}", death_position, respawn_at
+        );
+
+        let mut modifications = Vec::new();
+
+        if !gems_dropped.is_empty() {
+            self.drop_gems_at(death_position, gems_dropped.clone());
+            modifications.push(Modification::TileChanged(death_position, Tile::GemDrop));
+        }
+
+        modifications.push(Modification::EntityMoved {
+            entity_id,
+            old_position: death_position,
+            new_position: respawn_at,
+            direction
+        });
+        modifications.push(Modification::EntityDied { entity_id, respawn_at, gems_dropped });
+        modifications.push(Modification::EntityHealthChanged {
+            entity_id,
+            chunk_coords: respawn_at.as_chunk_coords(),
+            health
+        });
+
+        modifications
+    }
+
+    /// The free position closest to the spawn point (within [`RESPAWN_SEARCH_RADIUS`]) at which a dead player entity
+    /// is to respawn. Should no such position be free (or the area not be loaded), the spawn point itself is used.
+    fn respawn_position(&self) -> TileCoords {
+        (0..=RESPAWN_SEARCH_RADIUS)
+            .flat_map(|radius| self.spawn_point.tiles_in_chebyshev_range(radius))
+            .find(|coords| self.is_position_free(*coords))
+            .unwrap_or(self.spawn_point)
+    }
+
+    pub fn spawn_point(&self) -> TileCoords {
+        self.spawn_point
+    }
+
+    /// Change the position at which new player entities are placed and around which dead player entities respawn.
+    pub fn set_spawn_point(&mut self, spawn_point: TileCoords) {
+        self.spawn_point = spawn_point;
+    }
+
     /// Get all entity IDs and entities in the chunk at the given chunk coordinates.
     pub fn entities_in_chunk(&self, coords: ChunkCoords) -> Vec<(Id, Entity)> {
-        let mut entities = Vec::new();
+        self.player_id_grid
+            .in_chunk(coords)
+            .filter_map(|(_, entity_id)| self.player_entities.get(entity_id).map(|entity| (*entity_id, entity.clone())))
+            .collect()
+    }
 
-        if let Some(set) = self.chunk_coords_to_player_ids.get(&coords) {
-            for entity_id in set.iter() {
-                if let Some(entity) = self.player_entities.get(entity_id) {
-                    entities.push((*entity_id, entity.clone()));
-                }
-            }
+    /// Take copies of the names & gem collections of all player entities currently on the map.
+    pub fn player_gem_collections(&self) -> Vec<(Id, String, gems::Collection)> {
+        self.player_entities
+            .iter()
+            .map(|(id, entity)| (*id, entity.name.clone(), entity.gem_collection.clone()))
+            .collect()
+    }
+
+    /// Returns whether the given name is in use by any player entity currently on the map or is reserved. Names are
+    /// compared case insensitively so that players cannot impersonate one another using names that differ only in case.
+    pub fn is_player_name_taken(&self, name: &str) -> bool {
+        self.reserved_player_names.contains(&name.to_ascii_lowercase())
+            || self.player_entities.values().any(|entity| entity.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Reserve the given name so that no other player may use it until the reservation is released (see
+    /// [`ServerMap::release_player_name`]). Returns `false` without reserving the name should it already be taken.
+    pub fn reserve_player_name(&mut self, name: &str) -> bool {
+        !self.is_player_name_taken(name) && self.reserved_player_names.insert(name.to_ascii_lowercase())
+    }
+
+    pub fn release_player_name(&mut self, name: &str) {
+        self.reserved_player_names.remove(&name.to_ascii_lowercase());
+    }
+
+    /// Take copies of the player entities changed by the given modifications (e.g. those made by a tick) so that they
+    /// may be written to the database without the map's mutex having to remain locked while doing so. Each entity
+    /// is only included once however many modifications concern it.
+    pub fn player_entities_changed_by(&self, modifications: &[Modification]) -> Vec<(Id, Entity)> {
+        let mut ids = HashSet::new();
+
+        modifications
+            .iter()
+            .filter_map(|modification| match modification {
+                Modification::EntityMoved { entity_id, .. }
+                | Modification::EntityHealthChanged { entity_id, .. }
+                | Modification::EntityDamaged { entity_id, .. }
+                | Modification::EntityDied { entity_id, .. } => Some(*entity_id),
+                _ => None
+            })
+            .filter(|id| ids.insert(*id))
+            .filter_map(|id| self.player_entities.get(&id).map(|entity| (id, entity.clone())))
+            .collect()
+    }
+
+    /// Take copies of all loaded chunks & player entities so that they may be written to the database without the
+    /// map's mutex having to remain locked while doing so.
+    pub fn save(&self) -> SaveData {
+        SaveData {
+            world: self.world.clone(),
+            chunks: self.loaded_chunks.iter().map(|(coords, chunk)| (*coords, chunk.clone())).collect(),
+            entities: self.player_entities.iter().map(|(id, entity)| (*id, entity.clone())).collect()
         }
+    }
 
-        entities
+    /// The name of the world that this map belongs to.
+    pub fn world(&self) -> &str {
+        &self.world
+    }
+
+    /// Change the world that this map belongs to, under which its chunks & player entities are stored from then on.
+    pub fn set_world(&mut self, world: String) {
+        self.world = world;
+    }
+
+    /// The number of chunks currently loaded in memory.
+    pub fn loaded_chunk_count(&self) -> usize {
+        self.loaded_chunks.len()
     }
 
     /// To be called by a client task whenever their remote client is provided with a certain chunk.
@@ -187,13 +435,11 @@ impl Map for ServerMap {
 
     fn add_chunk(&mut self, coords: ChunkCoords, chunk: Chunk) {
         self.loaded_chunks.insert(coords, chunk);
-        self.chunk_coords_to_player_ids.insert(coords, HashSet::new());
     }
 
     fn remove_chunk(&mut self, coords: ChunkCoords) -> Option<Chunk> {
-        log::debug!("Chunk at {} unloaded", coords);
+        tracing::debug!(chunk_coords = %coords, "Chunk unloaded");
 
-        self.chunk_coords_to_player_ids.remove(&coords);
         self.loaded_chunks.remove(&coords)
     }
 
@@ -208,45 +454,111 @@ impl Map for ServerMap {
     fn add_entity(&mut self, id: Id, entity: Entity) {
         let chunk_coords = entity.pos.as_chunk_coords();
 
-        self.chunk_coords_to_player_ids.entry(chunk_coords).or_default().insert(id);
+        self.player_id_grid.insert(entity.pos, id);
         self.player_entities.insert(id, entity);
 
         if self.is_chunk_loaded(chunk_coords) {
-            log::debug!("Player entity with ID {} added to game map", id);
+            tracing::debug!(entity_id = %id, "Player entity added to game map");
         }
         else {
-            log::warn!("Add entity {} to map yet that entity's position is in an unloaded chunk", id);
+            tracing::warn!(entity_id = %id, "Added entity to map yet that entity's position is in an unloaded chunk");
         }
     }
 
     fn remove_entity(&mut self, id: Id) -> Option<Entity> {
-        log::debug!("Removing player entity with ID {} from game map", id);
+        tracing::debug!(entity_id = %id, "Removing player entity from game map");
         let opt = self.player_entities.remove(&id);
 
-        // Remove the association between the entity and the chunk that entity was in:
-        if let Some(entity) = &opt {
-            self.chunk_coords_to_player_ids.entry(entity.pos.as_chunk_coords()).and_modify(|x| {
-                x.remove(&id);
-            });
-        }
+        // Remove the entity from the spatial grid:
+        self.player_id_grid.remove(id);
 
         opt
     }
 
     fn is_blocking_entity_at(&self, coords: TileCoords) -> bool {
-        // First identify all entities in the chunk that the specified coordinates are in:
-        if let Some(entity_ids_in_chunk) = self.chunk_coords_to_player_ids.get(&coords.as_chunk_coords()) {
-            // Iterate through the entities in that chunk, checking each entity's position:
-            for entity_id in entity_ids_in_chunk {
-                if let Some(entity) = self.entity_by_id(*entity_id) {
-                    if entity.pos == coords {
-                        return true;
-                    }
-                }
+        self.player_id_grid.query_radius(coords, 0).next().is_some()
+    }
+}
+
+/// The loaded chunks & player entities of a [`ServerMap`] at the point at which [`ServerMap::save`] was called.
+pub struct SaveData {
+    /// The name of the world that the saved map belongs to.
+    pub world: String,
+    pub chunks: Vec<(ChunkCoords, Chunk)>,
+    pub entities: Vec<(Id, Entity)>
+}
+
+impl SaveData {
+    /// Write all chunks & player entities to the database (overwriting their existing rows). Chunk data is compressed
+    /// using zstd at the given compression level unless `None` is specified.
+    pub async fn write_to_database(
+        &self, db_pool: &sqlx::PgPool, compression_level: Option<i32>
+    ) -> chunks::Result<()> {
+        for (coords, chunk) in &self.chunks {
+            chunks::save_chunk(db_pool.acquire().await?, &self.world, *coords, chunk, compression_level).await?;
+        }
+
+        let entities: Vec<(Id, &Entity)> = self.entities.iter().map(|(id, entity)| (*id, entity)).collect();
+        entities::bulk_update_entities(&entities, &mut *db_pool.acquire().await?).await?;
+
+        Ok(())
+    }
+
+    /// Write all chunks & player entities to the database as is done by [`Self::write_to_database`] except that a
+    /// failure to save one chunk does not prevent the remaining chunks & entities from being saved. Returns a report
+    /// of what was saved along with any errors that occurred.
+    pub async fn write_to_database_on_shutdown(
+        &self, db_pool: &sqlx::PgPool, compression_level: Option<i32>
+    ) -> ShutdownReport {
+        let mut report = ShutdownReport::default();
+
+        for (coords, chunk) in &self.chunks {
+            let result = match db_pool.acquire().await {
+                Ok(db) => chunks::save_chunk(db, &self.world, *coords, chunk, compression_level).await,
+                Err(e) => Err(e.into())
+            };
+
+            match result {
+                Ok(()) => report.chunks_saved += 1,
+                Err(e) => report.errors.push(format!("Failed to save chunk at {} - {}", coords, e))
+            }
+        }
+
+        if !self.entities.is_empty() {
+            let entities: Vec<(Id, &Entity)> = self.entities.iter().map(|(id, entity)| (*id, entity)).collect();
+
+            let result = match db_pool.acquire().await {
+                Ok(mut db) => entities::bulk_update_entities(&entities, &mut db).await,
+                Err(e) => Err(e)
+            };
+
+            match result {
+                Ok(()) => report.entities_saved = entities.len(),
+                Err(e) => report.errors.push(format!("Failed to save {} player entities - {}", entities.len(), e))
             }
         }
 
-        false
+        report
+    }
+}
+
+/// Summary of the chunks & player entities written to the database as the server shuts down.
+#[derive(Debug, Default)]
+pub struct ShutdownReport {
+    pub chunks_saved: usize,
+    pub entities_saved: usize,
+    pub errors: Vec<String>
+}
+
+impl fmt::Display for ShutdownReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "saved {} chunks and {} player entities with {} errors",
+            self.chunks_saved,
+            self.entities_saved,
+            self.errors.len()
+        )
     }
 }
 
@@ -258,9 +570,8 @@ pub struct EntityMovement {
 
 /// Represents a change made to the game map (tiles and entities). This enum is used by client tasks to inform other
 /// tasks of changes made to the game map.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
 pub enum Modification {
-    #[allow(dead_code)]
     TileChanged(TileCoords, Tile),
 
     EntityMoved {
@@ -286,7 +597,109 @@ pub enum Modification {
     BombPlaced(TileCoords, Id),
 
     /// The player with the specified ID detonated their placed bombs.
-    BombsDetonated(Id)
+    BombsDetonated(Id),
+
+    /// The player with the specified entity ID sent a chat message on the given channel. Unlike other modifications,
+    /// chat messages are relayed to clients regardless of their loaded chunks - global messages are relayed to all
+    /// clients and local messages to those whose player entity is within [`messages::LOCAL_CHAT_RADIUS`] chunks of
+    /// the sender's entity.
+    ChatMessage {
+        sender_entity_id: Id,
+        sender_name: String,
+        text: String,
+        channel: messages::ChatChannel
+    },
+
+    /// The server operator made an announcement (see the [`crate::console`] module) which is to be relayed to all
+    /// clients.
+    Announcement {
+        text: String
+    },
+
+    /// The entity with the specified ID changed its facial expression. The coordinates of the chunk that the entity is
+    /// positioned in are included so that each task can decide whether to inform their client of the change.
+    EntityChangedExpression {
+        entity_id: Id,
+        chunk_coords: ChunkCoords,
+        expression: FacialExpression
+    },
+
+    /// A monster with the specified ID was spawned (see [`monsters::MonsterSystem`]).
+    MonsterAdded(Id),
+
+    MonsterMoved {
+        monster_id: Id,
+        old_position: TileCoords,
+        new_position: TileCoords,
+        direction: Direction
+    },
+
+    /// The monster with the specified ID was removed from the chunk at the given coordinates.
+    MonsterRemoved(Id, ChunkCoords),
+
+    /// The health of the player entity with the specified ID changed (e.g. due to being attacked by a monster). The
+    /// coordinates of the chunk that the entity is positioned in are included so that each task can decide whether to
+    /// inform their client of the change.
+    EntityHealthChanged {
+        entity_id: Id,
+        chunk_coords: ChunkCoords,
+        health: u32
+    },
+
+    /// The player with the entity ID `inviter_entity_id` invited the player with the entity ID `invitee_entity_id` to
+    /// their party (see [`crate::parties`]). Only the invited player's task informs its client.
+    PartyInvite {
+        inviter_entity_id: Id,
+        invitee_entity_id: Id
+    },
+
+    /// The members of a party changed. The tasks of all the party's members inform their clients.
+    PartyUpdated {
+        leader_entity_id: Id,
+        member_entity_ids: Vec<Id>
+    },
+
+    /// The players with the given entity IDs are no longer in a party (as they left or as their party was disbanded).
+    PartyLeft {
+        member_entity_ids: Vec<Id>
+    },
+
+    /// A share of the gems collected by a member of a party was given to another member of that party (the recipient)
+    /// whose task informs its client.
+    PartyGemsShared {
+        recipient_entity_id: Id,
+        gem: gems::Gem,
+        quantity: u32,
+        /// The position of the rock or chest that the gems were collected from.
+        source_tile: TileCoords
+    },
+
+    /// The player with the specified entity ID was dealt damage (see [`hazards::HazardSystem`]). Only the damaged
+    /// player's task informs its client. The resulting change in health is described by a separate
+    /// [`Modification::EntityHealthChanged`].
+    EntityDamaged {
+        entity_id: Id,
+        amount: u32,
+        source: messages::DamageSource
+    },
+
+    /// The entity with the specified ID performed an emote. The coordinates of the chunk that the entity is positioned
+    /// in are included so that each task can decide whether to inform their client.
+    EntityEmote {
+        entity_id: Id,
+        chunk_coords: ChunkCoords,
+        emote: messages::EmoteKind
+    },
+
+    /// The health of the player entity with the specified ID reached zero (see [`ServerMap::damage_player`]) so it
+    /// dropped the given gems and respawned at the specified position. Only the dead player's task informs its
+    /// client, as the entity's movement to the respawn position is described by a separate
+    /// [`Modification::EntityMoved`].
+    EntityDied {
+        entity_id: Id,
+        respawn_at: TileCoords,
+        gems_dropped: gems::Collection
+    }
 }
 
 impl fmt::Display for Modification {
@@ -312,6 +725,219 @@ impl fmt::Display for Modification {
             Modification::BombsDetonated(placed_by) => {
                 write!(f, "bombs placed by {} detonated", placed_by)
             }
+            Modification::ChatMessage { sender_entity_id, sender_name, text, channel } => {
+                write!(f, "{} chat message \"{}\" sent by {} (entity {})", channel, text, sender_name, sender_entity_id)
+            }
+            Modification::Announcement { text } => write!(f, "announcement \"{}\"", text),
+            Modification::EntityChangedExpression { entity_id, expression, .. } => {
+                write!(f, "entity {} changed facial expression to {}", entity_id, expression)
+            }
+            Modification::MonsterAdded(id) => write!(f, "monster {} added to map", id),
+            Modification::MonsterMoved { monster_id, old_position, new_position, direction } => {
+                write!(
+                    f,
+                    "monster {} moved from {} to {} in direction {}",
+                    monster_id, old_position, new_position, direction
+                )
+            }
+            Modification::MonsterRemoved(id, coords) => {
+                write!(f, "monster {} in chunk at {} removed from map", id, coords)
+            }
+            Modification::EntityHealthChanged { entity_id, health, .. } => {
+                write!(f, "entity {} health changed to {}", entity_id, health)
+            }
+            Modification::PartyInvite { inviter_entity_id, invitee_entity_id } => {
+                write!(f, "entity {} invited entity {} to party", inviter_entity_id, invitee_entity_id)
+            }
+            Modification::PartyUpdated { leader_entity_id, member_entity_ids } => {
+                write!(f, "party led by entity {} now has {} members", leader_entity_id, member_entity_ids.len())
+            }
+            Modification::PartyLeft { member_entity_ids } => {
+                write!(f, "{} entities no longer in a party", member_entity_ids.len())
+            }
+            Modification::PartyGemsShared { recipient_entity_id, gem, quantity, .. } => {
+                write!(f, "{} {:?} gems shared with party member {}", quantity, gem, recipient_entity_id)
+            }
+            Modification::EntityDamaged { entity_id, amount, source } => {
+                write!(f, "entity {} dealt {} damage by {}", entity_id, amount, source)
+            }
+            Modification::EntityEmote { entity_id, emote, .. } => {
+                write!(f, "entity {} performed emote {}", entity_id, emote)
+            }
+            Modification::EntityDied { entity_id, respawn_at, gems_dropped } => {
+                write!(f, "entity {} died dropping gems {} and respawned at {}", entity_id, gems_dropped, respawn_at)
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use shared::maps::entities::EntityBuilder;
+
+    use super::*;
+
+    #[test]
+    fn save_copies_loaded_chunks_and_entities() {
+        let mut map = ServerMap::new_with_default_generator(0);
+
+        let mut chunk = Chunk::default();
+        chunk.set_tile_at_offset(shared::maps::OffsetCoords { x: 1, y: 2 }, Tile::Rock);
+        map.add_chunk(ChunkCoords { x: 0, y: -1 }, chunk);
+
+        let entity_id = crate::id::generate_with_timestamp();
+        let entity = EntityBuilder::new().pos(TileCoords { x: 3, y: -4 }).build();
+        map.add_entity(entity_id, entity.clone());
+
+        let save_data = map.save();
+        assert_eq!(save_data.chunks.len(), 1);
+        assert_eq!(save_data.chunks[0].0, ChunkCoords { x: 0, y: -1 });
+        assert_eq!(save_data.chunks[0].1.tile_at_offset(shared::maps::OffsetCoords { x: 1, y: 2 }), Tile::Rock);
+        assert_eq!(save_data.entities, vec![(entity_id, entity)]);
+    }
+
+    #[tokio::test]
+    async fn player_entities_changed_by_modifications() {
+        let mut map = ServerMap::new_with_default_generator(0);
+
+        let damaged_id = crate::id::generate_with_timestamp();
+        let damaged = EntityBuilder::new().pos(TileCoords { x: 1, y: 1 }).build();
+        map.add_entity(damaged_id, damaged.clone());
+        map.add_entity(crate::id::generate_with_timestamp(), EntityBuilder::new().build());
+
+        let chunk_coords = ChunkCoords { x: 0, y: 0 };
+        let modifications = vec![
+            Modification::MonsterAdded(crate::id::generate_with_timestamp()),
+            Modification::EntityHealthChanged { entity_id: damaged_id, chunk_coords, health: 5 },
+            Modification::EntityDamaged {
+                entity_id: damaged_id,
+                amount: 1,
+                source: messages::DamageSource::EnvironmentalHazard
+            },
+        ];
+
+        // Only the damaged entity is included (once):
+        assert_eq!(map.player_entities_changed_by(&modifications), vec![(damaged_id, damaged)]);
+        assert!(map.player_entities_changed_by(&[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn entity_grid_follows_movement_across_chunks() {
+        let mut map = ServerMap::new_with_default_generator(0);
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, Chunk::default());
+        map.add_chunk(ChunkCoords { x: 1, y: 0 }, Chunk::default());
+
+        let entity_id = crate::id::generate_with_timestamp();
+        let start = TileCoords { x: shared::maps::CHUNK_WIDTH - 1, y: 0 };
+        map.add_entity(entity_id, EntityBuilder::new().pos(start).build());
+        assert!(map.is_blocking_entity_at(start));
+
+        let movement = map.move_entity_towards(entity_id, Direction::Right).unwrap();
+        assert_eq!(movement.new_position.as_chunk_coords(), ChunkCoords { x: 1, y: 0 });
+
+        assert!(!map.is_blocking_entity_at(start));
+        assert!(map.is_blocking_entity_at(movement.new_position));
+        assert!(map.entities_in_chunk(ChunkCoords { x: 0, y: 0 }).is_empty());
+        assert_eq!(map.entities_in_chunk(ChunkCoords { x: 1, y: 0 }).len(), 1);
+
+        map.remove_entity(entity_id);
+        assert!(!map.is_blocking_entity_at(movement.new_position));
+        assert!(map.entities_in_chunk(ChunkCoords { x: 1, y: 0 }).is_empty());
+    }
+
+    #[tokio::test]
+    async fn player_dies_dropping_gems_and_respawns() {
+        let mut map = ServerMap::new_with_default_generator(0);
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, Chunk::default());
+        map.set_spawn_point(TileCoords { x: 2, y: 2 });
+
+        // Another player occupying the spawn point:
+        map.add_entity(crate::id::generate_random(), EntityBuilder::new().pos(TileCoords { x: 2, y: 2 }).build());
+
+        let mut gem_collection = gems::Collection::default();
+        gem_collection.increase_quantity(gems::Gem::Emerald, 9);
+
+        let death_position = TileCoords { x: 10, y: 8 };
+        let entity_id = crate::id::generate_random();
+        map.add_entity(
+            entity_id,
+            EntityBuilder::new().pos(death_position).health(30).gem_collection(gem_collection).build()
+        );
+
+        match map.damage_player(entity_id, 20).as_slice() {
+            [Modification::EntityHealthChanged { health: 10, .. }] => {}
+            other => panic!("Unexpected modifications: {:?}", other)
+        }
+
+        let respawn_position = TileCoords { x: 1, y: 1 };
+        let mut expected_drop = gems::Collection::default();
+        expected_drop.increase_quantity(gems::Gem::Emerald, 4);
+
+        let modifications = map.damage_player(entity_id, 20);
+        let (drop_placed, moved, died, health_restored) = match modifications.as_slice() {
+            [Modification::EntityHealthChanged { health: 0, .. }, drop_placed, moved, died, health_restored] => {
+                (drop_placed, moved, died, health_restored)
+            }
+            other => panic!("Unexpected modifications: {:?}", other)
+        };
+
+        assert!(
+            matches!(drop_placed, Modification::TileChanged(position, Tile::GemDrop) if *position == death_position)
+        );
+        match moved {
+            Modification::EntityMoved { old_position, new_position, .. } => {
+                assert_eq!((*old_position, *new_position), (death_position, respawn_position));
+            }
+            other => panic!("Unexpected modification: {:?}", other)
+        }
+        match died {
+            Modification::EntityDied { respawn_at, gems_dropped, .. } => {
+                assert_eq!(*respawn_at, respawn_position);
+                assert_eq!(gems_dropped, &expected_drop);
+            }
+            other => panic!("Unexpected modification: {:?}", other)
+        }
+        assert!(matches!(
+            health_restored,
+            Modification::EntityHealthChanged { health, .. } if *health == shared::maps::entities::DEFAULT_MAX_HEALTH
+        ));
+
+        let entity = map.entity_by_id(entity_id).unwrap();
+        assert_eq!(entity.pos, respawn_position);
+        assert_eq!(entity.gem_collection.get_quantity(gems::Gem::Emerald), 5);
+        assert!(map.is_blocking_entity_at(respawn_position));
+        assert!(!map.is_blocking_entity_at(death_position));
+
+        assert_eq!(map.gem_drop_at(death_position).unwrap().gems, expected_drop);
+    }
+
+    #[tokio::test]
+    async fn shutdown_save_reports_errors() {
+        // Nothing should be listening on port 1 so every attempt to connect to the database fails immediately:
+        let db_pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_timeout(std::time::Duration::from_millis(100))
+            .connect_lazy("postgres://localhost:1/gemgame")
+            .unwrap();
+
+        let save_data = SaveData {
+            world: crate::worlds::DEFAULT_WORLD_NAME.to_string(),
+            chunks: vec![
+                (ChunkCoords { x: 0, y: 0 }, Chunk::default()),
+                (ChunkCoords { x: 1, y: 0 }, Chunk::default()),
+            ],
+            entities: vec![(crate::id::generate_with_timestamp(), EntityBuilder::new().build())]
+        };
+
+        let report = save_data.write_to_database_on_shutdown(&db_pool, None).await;
+        assert_eq!(report.chunks_saved, 0);
+        assert_eq!(report.entities_saved, 0);
+        assert_eq!(report.errors.len(), 3);
+        assert_eq!(report.to_string(), "saved 0 chunks and 0 player entities with 3 errors");
+
+        let report =
+            SaveData { world: crate::worlds::DEFAULT_WORLD_NAME.to_string(), chunks: Vec::new(), entities: Vec::new() }
+                .write_to_database_on_shutdown(&db_pool, None)
+                .await;
+        assert!(report.errors.is_empty());
+    }
+}
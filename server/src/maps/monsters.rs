@@ -0,0 +1,423 @@
+use std::{convert::TryFrom, sync::Arc, time::Duration};
+
+use rand::{rngs::StdRng, seq::IteratorRandom, Rng, SeedableRng};
+use shared::{
+    maps::{
+        entities::{Monster, MONSTER_AGGRO_RADIUS},
+        ChunkCoords, Map, TileCoords, CHUNK_HEIGHT, CHUNK_WIDTH
+    },
+    mechanics::BOMB_BLAST_RADIUS,
+    Id
+};
+
+use super::{systems::WorldSystem, Modification, ServerMap};
+use crate::safe_zones::SafeZoneRegistry;
+
+/// The default number of seconds between each monster spawn.
+pub const MONSTER_SPAWN_INTERVAL_SECONDS: u64 = 10;
+
+/// The default maximum number of monsters that may be on the map at once.
+pub const MAX_MONSTERS: usize = 20;
+
+/// The number of random positions tried when a monster spawn is due before giving up until the next spawn.
+const SPAWN_ATTEMPTS: usize = 10;
+
+/// Spawns monsters in loaded chunks at a regular interval (up to a maximum number of monsters), moves each monster
+/// one tile towards the nearest player within its aggro radius every tick, and has monsters damage any players that
+/// they share a tile with (unless that tile is within a safe zone). Monsters in chunks that are no longer loaded are
+/// removed from the map.
+pub struct MonsterSystem {
+    /// The number of ticks between each monster spawn.
+    ticks_per_spawn: u32,
+    /// The number of ticks remaining until the next monster spawn is due.
+    ticks_until_spawn: u32,
+    /// No monsters are spawned while there are this many monsters on the map.
+    max_monsters: usize,
+    /// Players within these safe zones are not damaged by monsters.
+    safe_zones: Arc<SafeZoneRegistry>,
+    rng: StdRng
+}
+
+impl MonsterSystem {
+    /// Create a system that spawns a monster every `spawn_interval` (rounded to a whole number of ticks of length
+    /// `tick_interval`) for as long as there are fewer than `max_monsters` monsters on the map.
+    pub fn new(
+        spawn_interval: Duration, tick_interval: Duration, max_monsters: usize, safe_zones: Arc<SafeZoneRegistry>
+    ) -> Self {
+        let ticks_per_spawn =
+            u32::try_from(spawn_interval.as_millis() / tick_interval.as_millis().max(1)).unwrap_or(u32::MAX).max(1);
+
+        MonsterSystem {
+            ticks_per_spawn,
+            ticks_until_spawn: ticks_per_spawn,
+            max_monsters,
+            safe_zones,
+            rng: StdRng::from_entropy()
+        }
+    }
+
+    /// Attempt to add a monster to a random free position in a random loaded chunk. Monsters are not spawned within
+    /// [`MONSTER_AGGRO_RADIUS`] of any player so that they do not appear right on top of them.
+    fn spawn_monster(&mut self, map: &mut ServerMap) -> Option<Modification> {
+        if map.monsters.len() >= self.max_monsters {
+            return None;
+        }
+
+        let chunk_coords = *map.loaded_chunks.keys().choose(&mut self.rng)?;
+
+        let position = (0..SPAWN_ATTEMPTS)
+            .map(|_| TileCoords {
+                x: (chunk_coords.x * CHUNK_WIDTH) + self.rng.gen_range(0..CHUNK_WIDTH),
+                y: (chunk_coords.y * CHUNK_HEIGHT) + self.rng.gen_range(0..CHUNK_HEIGHT)
+            })
+            .find(|pos| {
+                map.is_position_free_for_monster(*pos)
+                    && map.player_id_grid.query_radius(*pos, MONSTER_AGGRO_RADIUS as i32).next().is_none()
+            })?;
+
+        let monster_id = crate::id::generate_random();
+        map.add_monster(monster_id, Monster::new(position));
+
+        Some(Modification::MonsterAdded(monster_id))
+    }
+}
+
+impl WorldSystem for MonsterSystem {
+    fn tick(&mut self, map: &mut ServerMap) -> Vec<Modification> {
+        let mut modifications = despawn_monsters_in_unloaded_chunks(map);
+
+        self.ticks_until_spawn -= 1;
+        if self.ticks_until_spawn == 0 {
+            self.ticks_until_spawn = self.ticks_per_spawn;
+            modifications.extend(self.spawn_monster(map));
+        }
+
+        modifications.extend(move_monsters_towards_players(map));
+        modifications.extend(monsters_attack_players(map, &self.safe_zones));
+
+        modifications
+    }
+}
+
+impl ServerMap {
+    pub fn monster_by_id(&self, id: Id) -> Option<&Monster> {
+        self.monsters.get(&id)
+    }
+
+    pub fn add_monster(&mut self, id: Id, monster: Monster) {
+        tracing::debug!("Monster with ID {} added to game map at {}", id, monster.pos);
+
+        self.monsters.insert(id, monster);
+    }
+
+    /// Get all monster IDs and monsters in the chunk at the given chunk coordinates.
+    pub fn monsters_in_chunk(&self, coords: ChunkCoords) -> Vec<(Id, Monster)> {
+        self.monsters
+            .iter()
+            .filter(|(_, monster)| monster.pos.as_chunk_coords() == coords)
+            .map(|(id, monster)| (*id, monster.clone()))
+            .collect()
+    }
+
+    /// Remove all monsters within the blast radius of bombs detonated at the given positions (see
+    /// [`shared::mechanics::BOMB_BLAST_RADIUS`]). Returns the IDs of the killed monsters along with the coordinates of
+    /// the chunks they were in.
+    pub fn kill_monsters_around_bombs(&mut self, bomb_positions: &[TileCoords]) -> Vec<(Id, ChunkCoords)> {
+        let killed: Vec<(Id, ChunkCoords)> = self
+            .monsters
+            .iter()
+            .filter(|(_, monster)| {
+                bomb_positions
+                    .iter()
+                    .any(|bomb_pos| bomb_pos.chebyshev_distance(monster.pos) <= BOMB_BLAST_RADIUS as u32)
+            })
+            .map(|(id, monster)| (*id, monster.pos.as_chunk_coords()))
+            .collect();
+
+        for (id, _) in &killed {
+            tracing::debug!("Monster with ID {} killed by bomb blast", id);
+            self.monsters.remove(id);
+        }
+
+        killed
+    }
+
+    /// Monsters may move on to any loaded tile that is neither blocking nor smashable (so that monsters never change
+    /// tiles) provided there is not already a monster there. Players do not block monsters.
+    fn is_position_free_for_monster(&self, coords: TileCoords) -> bool {
+        let walkable = self.loaded_tile_at(coords).map(|tile| !tile.is_blocking() && !tile.is_smashable());
+
+        walkable.unwrap_or(false) && !self.monsters.values().any(|monster| monster.pos == coords)
+    }
+}
+
+fn despawn_monsters_in_unloaded_chunks(map: &mut ServerMap) -> Vec<Modification> {
+    let unloaded: Vec<(Id, ChunkCoords)> = map
+        .monsters
+        .iter()
+        .map(|(id, monster)| (*id, monster.pos.as_chunk_coords()))
+        .filter(|(_, chunk_coords)| !map.is_chunk_loaded(*chunk_coords))
+        .collect();
+
+    unloaded
+        .into_iter()
+        .map(|(id, chunk_coords)| {
+            tracing::debug!("Removing monster with ID {} in unloaded chunk at {}", id, chunk_coords);
+            map.monsters.remove(&id);
+
+            Modification::MonsterRemoved(id, chunk_coords)
+        })
+        .collect()
+}
+
+/// Move each monster one tile towards the nearest player within its aggro radius (see [`Monster::step_towards`]).
+fn move_monsters_towards_players(map: &mut ServerMap) -> Vec<Modification> {
+    let monster_ids: Vec<Id> = map.monsters.keys().copied().collect();
+    let mut modifications = Vec::new();
+
+    for monster_id in monster_ids {
+        let direction_option = {
+            let monster = &map.monsters[&monster_id];
+
+            map.player_id_grid
+                .query_radius(monster.pos, monster.aggro_radius as i32)
+                .map(|(player_pos, _)| player_pos)
+                .min_by_key(|player_pos| player_pos.chebyshev_distance(monster.pos))
+                .and_then(|target| monster.step_towards(target, |coords| map.is_position_free_for_monster(coords)))
+        };
+
+        if let Some(direction) = direction_option {
+            let monster = map.monsters.get_mut(&monster_id).unwrap();
+
+            let old_position = monster.pos;
+            monster.pos = direction.apply(old_position);
+            monster.direction = direction;
+
+            modifications.push(Modification::MonsterMoved {
+                monster_id,
+                old_position,
+                new_position: monster.pos,
+                direction
+            });
+        }
+    }
+
+    modifications
+}
+
+/// Each monster deals its damage to every player positioned on its tile (see [`ServerMap::damage_player`]). Monsters
+/// within a safe zone deal no damage. A player sharing a tile with multiple monsters is dealt their combined damage at
+/// once so that a player killed by one monster is not then damaged by another after respawning.
+fn monsters_attack_players(map: &mut ServerMap, safe_zones: &SafeZoneRegistry) -> Vec<Modification> {
+    let mut damage_dealt: Vec<(Id, u32)> = Vec::new();
+
+    for monster in map.monsters.values().filter(|monster| !safe_zones.contains(monster.pos)) {
+        for (_, player_id) in map.player_id_grid.query_radius(monster.pos, 0) {
+            match damage_dealt.iter_mut().find(|(id, _)| id == player_id) {
+                Some((_, total)) => *total = total.saturating_add(monster.damage),
+                None => damage_dealt.push((*player_id, monster.damage))
+            }
+        }
+    }
+
+    damage_dealt.into_iter().flat_map(|(player_id, damage)| map.damage_player(player_id, damage)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::maps::{
+        entities::{EntityBuilder, DEFAULT_MAX_HEALTH},
+        Chunk, SafeZone, Tile
+    };
+
+    use super::*;
+
+    const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+    fn make_test_map() -> ServerMap {
+        let mut map = ServerMap::new_with_default_generator(0);
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, Chunk::default());
+        map
+    }
+
+    fn no_safe_zones() -> Arc<SafeZoneRegistry> {
+        Arc::new(SafeZoneRegistry::new(Vec::new()))
+    }
+
+    fn add_test_player(map: &mut ServerMap, pos: TileCoords) -> Id {
+        let player_id = crate::id::generate_random();
+        map.add_entity(player_id, EntityBuilder::new().pos(pos).build());
+        player_id
+    }
+
+    #[tokio::test]
+    async fn monsters_spawn_at_interval_up_to_cap() {
+        let mut map = make_test_map();
+        map.add_system(Box::new(MonsterSystem::new(Duration::from_millis(300), TICK_INTERVAL, 2, no_safe_zones())));
+
+        let spawned = |modifications: Vec<Modification>| {
+            modifications.iter().filter(|modification| matches!(modification, Modification::MonsterAdded(_))).count()
+        };
+
+        assert_eq!(spawned(map.tick()), 0);
+        assert_eq!(spawned(map.tick()), 0);
+        assert_eq!(spawned(map.tick()), 1);
+        assert_eq!(map.monsters.len(), 1);
+
+        let total: usize = (0..9).map(|_| spawned(map.tick())).sum();
+        assert_eq!(total, 1);
+        assert_eq!(map.monsters.len(), 2);
+
+        for (_, monster) in map.monsters_in_chunk(ChunkCoords { x: 0, y: 0 }) {
+            assert_eq!(map.loaded_tile_at(monster.pos), Some(Tile::default()));
+        }
+    }
+
+    #[test]
+    fn spawn_interval_in_ticks_saturates() {
+        let system = MonsterSystem::new(Duration::from_millis(u64::MAX), Duration::from_millis(1), 2, no_safe_zones());
+        assert_eq!(system.ticks_per_spawn, u32::MAX);
+
+        let system = MonsterSystem::new(Duration::ZERO, TICK_INTERVAL, 2, no_safe_zones());
+        assert_eq!(system.ticks_per_spawn, 1);
+    }
+
+    #[tokio::test]
+    async fn monster_chases_player_within_aggro_radius() {
+        let mut map = make_test_map();
+        map.add_system(Box::new(MonsterSystem::new(Duration::from_secs(1000), TICK_INTERVAL, 0, no_safe_zones())));
+
+        let monster_id = crate::id::generate_random();
+        map.add_monster(monster_id, Monster::new(TileCoords { x: 2, y: 2 }));
+
+        // Player is out of range so the monster stays put:
+        let player_pos = TileCoords { x: 2 + MONSTER_AGGRO_RADIUS as i32 + 1, y: 4 };
+        let player_id = add_test_player(&mut map, player_pos);
+        assert!(map.tick().is_empty());
+
+        map.move_entity_towards(player_id, shared::maps::entities::Direction::Left).unwrap();
+
+        // Monsters only move orthogonally so reaching the player takes as many ticks as the Manhattan distance:
+        let player_pos = map.entity_by_id(player_id).unwrap().pos;
+        let steps = TileCoords { x: 2, y: 2 }.manhattan_distance(player_pos);
+
+        for step in 1..=steps {
+            let modifications = map.tick();
+
+            match modifications.first() {
+                Some(Modification::MonsterMoved { monster_id: id, old_position, new_position, .. }) => {
+                    assert_eq!(*id, monster_id);
+                    assert_eq!(old_position.manhattan_distance(*new_position), 1);
+                    assert_eq!(new_position.manhattan_distance(player_pos), steps - step);
+                }
+                other => panic!("Unexpected modification: {:?}", other)
+            }
+
+            // The monster attacks upon reaching the player's tile:
+            let attacked = modifications.iter().any(|modification| {
+                matches!(modification, Modification::EntityHealthChanged { entity_id, .. } if *entity_id == player_id)
+            });
+            assert_eq!(attacked, step == steps);
+        }
+
+        assert_eq!(map.monster_by_id(monster_id).unwrap().pos, player_pos);
+    }
+
+    #[tokio::test]
+    async fn bombs_kill_nearby_monsters() {
+        let mut map = make_test_map();
+
+        let near_id = crate::id::generate_random();
+        let far_id = crate::id::generate_random();
+        map.add_monster(near_id, Monster::new(TileCoords { x: 4, y: 6 }));
+        map.add_monster(far_id, Monster::new(TileCoords { x: 7, y: 5 }));
+
+        let killed = map.kill_monsters_around_bombs(&[TileCoords { x: 5, y: 5 }]);
+        assert_eq!(killed, vec![(near_id, ChunkCoords { x: 0, y: 0 })]);
+        assert!(map.monster_by_id(near_id).is_none());
+        assert!(map.monster_by_id(far_id).is_some());
+    }
+
+    #[tokio::test]
+    async fn monster_damages_player_on_same_tile() {
+        let mut map = make_test_map();
+        map.add_system(Box::new(MonsterSystem::new(Duration::from_secs(1000), TICK_INTERVAL, 0, no_safe_zones())));
+
+        let pos = TileCoords { x: 5, y: 5 };
+        let player_id = add_test_player(&mut map, pos);
+        map.add_monster(crate::id::generate_random(), Monster { damage: 40, ..Monster::new(pos) });
+
+        let expected_health = [60, 20];
+        for health in expected_health {
+            match map.tick().as_slice() {
+                [Modification::EntityHealthChanged { entity_id, health: new_health, .. }] => {
+                    assert_eq!(*entity_id, player_id);
+                    assert_eq!(*new_health, health);
+                }
+                other => panic!("Unexpected modifications: {:?}", other)
+            }
+        }
+
+        // The player dies and respawns away from the monster once on zero health:
+        let modifications = map.tick();
+        let (died, health_restored) = match modifications.as_slice() {
+            [Modification::EntityHealthChanged { health: 0, .. }, Modification::EntityMoved { .. }, died, health] => {
+                (died, health)
+            }
+            other => panic!("Unexpected modifications: {:?}", other)
+        };
+
+        match died {
+            Modification::EntityDied { entity_id, respawn_at, .. } => {
+                assert_eq!(*entity_id, player_id);
+                assert_eq!(*respawn_at, crate::maps::DEFAULT_SPAWN_POINT);
+            }
+            other => panic!("Unexpected modification: {:?}", other)
+        }
+        assert!(matches!(
+            health_restored,
+            Modification::EntityHealthChanged { health, .. } if *health == DEFAULT_MAX_HEALTH
+        ));
+        assert_eq!(map.entity_by_id(player_id).unwrap().pos, crate::maps::DEFAULT_SPAWN_POINT);
+    }
+
+    #[tokio::test]
+    async fn monster_deals_no_damage_in_safe_zone() {
+        let mut map = make_test_map();
+        let safe_zones = SafeZoneRegistry::new(vec![SafeZone { centre: TileCoords { x: 5, y: 5 }, radius: 1 }]);
+        map.add_system(Box::new(MonsterSystem::new(Duration::from_secs(1000), TICK_INTERVAL, 0, Arc::new(safe_zones))));
+
+        let safe_player_id = add_test_player(&mut map, TileCoords { x: 6, y: 6 });
+        map.add_monster(crate::id::generate_random(), Monster::new(TileCoords { x: 6, y: 6 }));
+
+        let unsafe_player_id = add_test_player(&mut map, TileCoords { x: 7, y: 6 });
+        map.add_monster(crate::id::generate_random(), Monster::new(TileCoords { x: 7, y: 6 }));
+
+        match map.tick().as_slice() {
+            [Modification::EntityHealthChanged { entity_id, .. }] => assert_eq!(*entity_id, unsafe_player_id),
+            other => panic!("Unexpected modifications: {:?}", other)
+        }
+        assert_eq!(map.entity_by_id(safe_player_id).unwrap().health, DEFAULT_MAX_HEALTH);
+    }
+
+    #[tokio::test]
+    async fn monsters_avoid_blocking_tiles_and_despawn_when_unloaded() {
+        let mut map = make_test_map();
+        map.add_system(Box::new(MonsterSystem::new(Duration::from_secs(1000), TICK_INTERVAL, 0, no_safe_zones())));
+
+        let monster_id = crate::id::generate_random();
+        map.add_monster(monster_id, Monster::new(TileCoords { x: 1, y: 1 }));
+        add_test_player(&mut map, TileCoords { x: 4, y: 1 });
+
+        map.set_loaded_tile_at(TileCoords { x: 2, y: 1 }, Tile::Water);
+        map.set_loaded_tile_at(TileCoords { x: 1, y: 2 }, Tile::RockDiamond);
+        assert!(map.tick().is_empty());
+
+        map.remove_chunk(ChunkCoords { x: 0, y: 0 });
+        match map.tick().as_slice() {
+            [Modification::MonsterRemoved(id, ChunkCoords { x: 0, y: 0 })] => assert_eq!(*id, monster_id),
+            other => panic!("Unexpected modifications: {:?}", other)
+        }
+        assert!(map.monster_by_id(monster_id).is_none());
+    }
+}
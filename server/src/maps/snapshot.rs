@@ -0,0 +1,226 @@
+//! Conversion of the game map to/from a human-readable JSON format for the purposes of map editing & backup. Snapshots
+//! take the following form (tile types are represented by their index within the `legend` array):
+//!
+//! ```json
+//! {
+//!     "legend": ["Grass", "FlowerPatch", ...],
+//!     "chunks": { "(x,y)": [[0, 0, ...], ...] },
+//!     "entities": [{ "id": "...", "entity": { ... } }]
+//! }
+//! ```
+//!
+//! Each chunk is an array of rows of tile type indices (row index corresponds to the y offset within the chunk). Note
+//! that bombs placed within chunks, the text of signs, and the destinations of portals are not included in snapshots.
+//!
+//! A plain text view of the map may also be exported for quick inspection (see [`ServerMap::export_to_text`]).
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+use shared::maps::{Chunk, ChunkCoords, OffsetCoords, Tile, CHUNK_HEIGHT, CHUNK_WIDTH};
+use strum::IntoEnumIterator;
+
+use super::ServerMap;
+
+impl ServerMap {
+    /// Produce a JSON snapshot of all loaded chunks and player entities.
+    pub fn export_to_json(&self) -> Value {
+        let legend: Vec<Tile> = Tile::iter().collect();
+        let legend_indices: HashMap<Tile, usize> = legend.iter().enumerate().map(|(i, tile)| (*tile, i)).collect();
+
+        let chunks: serde_json::Map<String, Value> = self
+            .loaded_chunks
+            .iter()
+            .map(|(coords, chunk)| {
+                let rows: Vec<Vec<usize>> = (0..CHUNK_HEIGHT)
+                    .map(|y| {
+                        (0..CHUNK_WIDTH)
+                            .map(|x| legend_indices[&chunk.tile_at_offset(OffsetCoords { x: x as u8, y: y as u8 })])
+                            .collect()
+                    })
+                    .collect();
+
+                (encode_chunk_coords(*coords), json!(rows))
+            })
+            .collect();
+
+        let entities: Vec<Value> =
+            self.player_entities.iter().map(|(id, entity)| json!({ "id": id.encode(), "entity": entity })).collect();
+
+        json!({
+            "legend": legend.iter().map(|tile| format!("{:?}", tile)).collect::<Vec<_>>(),
+            "chunks": chunks,
+            "entities": entities
+        })
+    }
+
+    /// Produce a plain text view of all loaded chunks, with each tile represented by a single character (see
+    /// [`Tile::display_char`]). Unlike JSON snapshots, these cannot be imported.
+    pub fn export_to_text(&self) -> String {
+        let mut coords: Vec<&ChunkCoords> = self.loaded_chunks.keys().collect();
+        coords.sort();
+
+        coords
+            .iter()
+            .map(|coords| format!("{}:\n{}\n", coords, self.loaded_chunks[coords]))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Read the chunks from a JSON snapshot produced by [`Self::export_to_json`]. Entities included in the snapshot are
+    /// ignored as player entities are stored separately in the database.
+    pub fn import_from_json(value: &Value) -> Result<Vec<(ChunkCoords, Chunk)>> {
+        let legend = value
+            .get("legend")
+            .and_then(Value::as_array)
+            .ok_or(ImportError::MissingField("legend"))?
+            .iter()
+            .map(|name| {
+                let name = name.as_str().ok_or(ImportError::MissingField("legend"))?;
+
+                Tile::iter()
+                    .find(|tile| format!("{:?}", tile) == name)
+                    .ok_or_else(|| ImportError::UnknownTileType(name.to_string()))
+            })
+            .collect::<Result<Vec<Tile>>>()?;
+
+        let chunks = value.get("chunks").and_then(Value::as_object).ok_or(ImportError::MissingField("chunks"))?;
+
+        chunks
+            .iter()
+            .map(|(key, rows)| {
+                let coords = decode_chunk_coords(key).ok_or_else(|| ImportError::InvalidChunkCoords(key.clone()))?;
+                let chunk = decode_chunk(rows, &legend).ok_or_else(|| ImportError::InvalidChunkData(key.clone()))?;
+
+                Ok((coords, chunk))
+            })
+            .collect()
+    }
+}
+
+fn encode_chunk_coords(coords: ChunkCoords) -> String {
+    format!("({},{})", coords.x, coords.y)
+}
+
+fn decode_chunk_coords(s: &str) -> Option<ChunkCoords> {
+    let (x, y) = s.strip_prefix('(')?.strip_suffix(')')?.split_once(',')?;
+
+    Some(ChunkCoords { x: x.trim().parse().ok()?, y: y.trim().parse().ok()? })
+}
+
+/// Decode an array of rows of tile type indices into a chunk. Returns `None` should the dimensions of the data not
+/// match those of a chunk or an index not correspond to a tile type in the legend.
+fn decode_chunk(rows: &Value, legend: &[Tile]) -> Option<Chunk> {
+    let rows = rows.as_array().filter(|rows| rows.len() == CHUNK_HEIGHT as usize)?;
+
+    let mut chunk = Chunk::default();
+
+    for (y, row) in rows.iter().enumerate() {
+        let row = row.as_array().filter(|row| row.len() == CHUNK_WIDTH as usize)?;
+
+        for (x, index) in row.iter().enumerate() {
+            let tile = *legend.get(index.as_u64()? as usize)?;
+            chunk.set_tile_at_offset(OffsetCoords { x: x as u8, y: y as u8 }, tile);
+        }
+    }
+
+    Some(chunk)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("Missing or invalid field '{0}'")]
+    MissingField(&'static str),
+    #[error("Unknown tile type '{0}' in legend")]
+    UnknownTileType(String),
+    #[error("Invalid chunk coordinates '{0}'")]
+    InvalidChunkCoords(String),
+    #[error("Invalid tile data for chunk at '{0}'")]
+    InvalidChunkData(String)
+}
+
+pub type Result<T> = std::result::Result<T, ImportError>;
+
+#[cfg(test)]
+mod tests {
+    use shared::maps::Map;
+
+    use super::*;
+    use crate::maps::generators::{DefaultGenerator, Generator};
+
+    fn test_map() -> ServerMap {
+        let mut map = ServerMap::new_with_default_generator(0);
+
+        let mut chunk = Chunk::default();
+        chunk.set_tile_at_offset(OffsetCoords { x: 0, y: 0 }, Tile::Water);
+        chunk.set_tile_at_offset(OffsetCoords { x: 15, y: 3 }, Tile::RockDiamond);
+        map.add_chunk(ChunkCoords { x: -2, y: 7 }, chunk);
+
+        map.add_chunk(ChunkCoords { x: 0, y: -1 }, DefaultGenerator::new(0).generate(ChunkCoords { x: 0, y: -1 }));
+
+        map
+    }
+
+    fn tiles_of(chunk: &Chunk) -> Vec<Tile> {
+        chunk.tiles().map(|(_, tile)| *tile).collect()
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let map = test_map();
+
+        let exported = map.export_to_json();
+        assert_eq!(exported["chunks"]["(-2,7)"][3][15], json!(Tile::iter().position(|t| t == Tile::RockDiamond)));
+
+        // Round trip through a string to ensure the snapshot survives being written to a file:
+        let reparsed: Value = serde_json::from_str(&exported.to_string()).unwrap();
+        let imported = ServerMap::import_from_json(&reparsed).unwrap();
+
+        assert_eq!(imported.len(), 2);
+
+        for (coords, chunk) in imported {
+            assert_eq!(tiles_of(&chunk), tiles_of(map.loaded_chunk_at(coords).unwrap()));
+        }
+    }
+
+    #[test]
+    fn import_invalid_snapshots() {
+        let mut snapshot = test_map().export_to_json();
+        snapshot["legend"][0] = json!("NotATile");
+        assert!(
+            matches!(ServerMap::import_from_json(&snapshot), Err(ImportError::UnknownTileType(name)) if name == "NotATile")
+        );
+
+        let mut snapshot = test_map().export_to_json();
+        snapshot["chunks"]["(-2,7)"][0] = json!([0, 1, 2]);
+        assert!(matches!(ServerMap::import_from_json(&snapshot), Err(ImportError::InvalidChunkData(_))));
+
+        let mut snapshot = test_map().export_to_json();
+        snapshot["chunks"]["(1;1)"] = snapshot["chunks"]["(-2,7)"].clone();
+        assert!(matches!(ServerMap::import_from_json(&snapshot), Err(ImportError::InvalidChunkCoords(_))));
+
+        assert!(matches!(ServerMap::import_from_json(&json!({})), Err(ImportError::MissingField("legend"))));
+    }
+
+    #[test]
+    fn chunk_coords_keys() {
+        let coords = ChunkCoords { x: -12, y: 5 };
+        assert_eq!(decode_chunk_coords(&encode_chunk_coords(coords)), Some(coords));
+        assert_eq!(decode_chunk_coords("(3, -4)"), Some(ChunkCoords { x: 3, y: -4 }));
+        assert_eq!(decode_chunk_coords("3,4"), None);
+    }
+
+    #[test]
+    fn export_text() {
+        let text = test_map().export_to_text();
+
+        // Chunks are ordered by coordinates:
+        let first_chunk_pos = text.find("chunk coordinates (-2, 7):").unwrap();
+        let second_chunk_pos = text.find("chunk coordinates (0, -1):").unwrap();
+        assert!(first_chunk_pos < second_chunk_pos);
+
+        let first_chunk_rows: Vec<&str> = text[first_chunk_pos..].lines().skip(1).take(CHUNK_HEIGHT as usize).collect();
+        assert!(first_chunk_rows[0].starts_with(Tile::Water.display_char()));
+        assert_eq!(first_chunk_rows[3].chars().nth(15), Some(Tile::RockDiamond.display_char()));
+    }
+}
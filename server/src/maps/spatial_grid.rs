@@ -0,0 +1,110 @@
+use std::{collections::HashMap, hash::Hash};
+
+use shared::maps::{ChunkCoords, TileCoords};
+
+/// Keeps track of the positions of items (e.g. entity IDs) partitioned by the chunk each item is positioned in,
+/// allowing the items near a given position to be found without having to check the position of every item.
+pub struct SpatialGrid<T> {
+    /// Items & their positions mapped to by the coordinates of the chunk that contains them.
+    buckets: HashMap<ChunkCoords, Vec<(TileCoords, T)>>,
+    /// The current position of each item so that an item's bucket can be found when it is moved or removed.
+    positions: HashMap<T, TileCoords>
+}
+
+impl<T> Default for SpatialGrid<T> {
+    fn default() -> Self {
+        SpatialGrid { buckets: HashMap::new(), positions: HashMap::new() }
+    }
+}
+
+impl<T: Copy + Eq + Hash> SpatialGrid<T> {
+    /// Insert an item at the given position. Should the item already be in the grid then it is moved to the new
+    /// position.
+    pub fn insert(&mut self, coords: TileCoords, item: T) {
+        self.remove(item);
+
+        self.buckets.entry(coords.as_chunk_coords()).or_default().push((coords, item));
+        self.positions.insert(item, coords);
+    }
+
+    /// Remove an item from the grid, returning its position should it have been in the grid.
+    pub fn remove(&mut self, item: T) -> Option<TileCoords> {
+        let coords = self.positions.remove(&item)?;
+        let chunk_coords = coords.as_chunk_coords();
+
+        if let Some(bucket) = self.buckets.get_mut(&chunk_coords) {
+            bucket.retain(|(_, other)| *other != item);
+
+            if bucket.is_empty() {
+                self.buckets.remove(&chunk_coords);
+            }
+        }
+
+        Some(coords)
+    }
+
+    /// Iterate over the items (and their positions) in the chunk at the given chunk coordinates.
+    pub fn in_chunk(&self, coords: ChunkCoords) -> impl Iterator<Item = (TileCoords, &T)> {
+        self.buckets.get(&coords).into_iter().flatten().map(|(coords, item)| (*coords, item))
+    }
+
+    /// Iterate over the items (and their positions) with a Chebyshev distance from the given position of at most
+    /// `radius`. Only the chunks overlapping that area are searched.
+    pub fn query_radius(&self, center: TileCoords, radius: i32) -> impl Iterator<Item = (TileCoords, &T)> {
+        let first_chunk =
+            TileCoords { x: center.x.saturating_sub(radius), y: center.y.saturating_sub(radius) }.as_chunk_coords();
+        let last_chunk =
+            TileCoords { x: center.x.saturating_add(radius), y: center.y.saturating_add(radius) }.as_chunk_coords();
+
+        (first_chunk.y..=last_chunk.y)
+            .flat_map(move |y| (first_chunk.x..=last_chunk.x).map(move |x| ChunkCoords { x, y }))
+            .flat_map(move |chunk_coords| self.in_chunk(chunk_coords))
+            .filter(move |(coords, _)| radius >= 0 && coords.chebyshev_distance(center) <= radius as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::maps::CHUNK_WIDTH;
+
+    use super::*;
+
+    fn sorted_query(grid: &SpatialGrid<u32>, center: TileCoords, radius: i32) -> Vec<u32> {
+        let mut items: Vec<u32> = grid.query_radius(center, radius).map(|(_, item)| *item).collect();
+        items.sort_unstable();
+        items
+    }
+
+    #[test]
+    fn query_across_chunk_boundaries() {
+        let mut grid = SpatialGrid::default();
+        grid.insert(TileCoords { x: -1, y: -1 }, 1);
+        grid.insert(TileCoords { x: 0, y: 0 }, 2);
+        grid.insert(TileCoords { x: 2, y: -2 }, 3);
+        grid.insert(TileCoords { x: CHUNK_WIDTH * 3, y: 0 }, 4);
+
+        assert_eq!(sorted_query(&grid, TileCoords { x: 0, y: 0 }, 0), vec![2]);
+        assert_eq!(sorted_query(&grid, TileCoords { x: 0, y: 0 }, 1), vec![1, 2]);
+        assert_eq!(sorted_query(&grid, TileCoords { x: 0, y: 0 }, 2), vec![1, 2, 3]);
+        assert_eq!(sorted_query(&grid, TileCoords { x: 0, y: 0 }, CHUNK_WIDTH * 3), vec![1, 2, 3, 4]);
+        assert!(sorted_query(&grid, TileCoords { x: 0, y: 0 }, -1).is_empty());
+    }
+
+    #[test]
+    fn moved_and_removed_items_stay_in_sync() {
+        let mut grid = SpatialGrid::default();
+        let (old_position, new_position) = (TileCoords { x: 0, y: 0 }, TileCoords { x: CHUNK_WIDTH, y: 0 });
+
+        grid.insert(old_position, 7);
+        grid.insert(new_position, 7);
+
+        assert!(grid.query_radius(old_position, 0).next().is_none());
+        assert_eq!(grid.in_chunk(old_position.as_chunk_coords()).count(), 0);
+        assert_eq!(grid.in_chunk(new_position.as_chunk_coords()).collect::<Vec<_>>(), vec![(new_position, &7)]);
+
+        assert_eq!(grid.remove(7), Some(new_position));
+        assert_eq!(grid.remove(7), None);
+        assert!(grid.query_radius(new_position, 0).next().is_none());
+        assert!(grid.buckets.is_empty());
+    }
+}
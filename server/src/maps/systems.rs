@@ -0,0 +1,88 @@
+use super::{Modification, ServerMap};
+
+/// The default number of milliseconds between server ticks.
+pub const TICK_INTERVAL_MS: u64 = 100;
+
+/// A stage of the world simulation that is run once every server tick (see [`ServerMap::tick`]). Systems are run in the
+/// order in which they were added to the map.
+pub trait WorldSystem: Send {
+    /// Advance this system by a single tick, making any necessary changes to the map. Returns modifications that other
+    /// tasks should be informed of.
+    fn tick(&mut self, map: &mut ServerMap) -> Vec<Modification>;
+}
+
+impl ServerMap {
+    /// Add a system to the end of the pipeline of systems run each tick.
+    pub fn add_system(&mut self, system: Box<dyn WorldSystem>) {
+        self.systems.push(system);
+    }
+
+    /// Run each system in the pipeline once, returning all the modifications made by those systems in the order in
+    /// which they were made.
+    pub fn tick(&mut self) -> Vec<Modification> {
+        // Systems are temporarily taken out of the map so that each can be given mutable access to the map itself:
+        let mut systems = std::mem::take(&mut self.systems);
+
+        let modifications = systems.iter_mut().flat_map(|system| system.tick(self)).collect();
+
+        // Keep any systems that were added while ticking after the existing ones:
+        systems.append(&mut self.systems);
+        self.systems = systems;
+
+        modifications
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::maps::{Map, Tile, TileCoords};
+
+    use super::*;
+
+    /// Sets a tile to rock on its first tick and to smashed rock on all subsequent ticks.
+    struct TestSystem {
+        position: TileCoords,
+        ticked: bool
+    }
+
+    impl WorldSystem for TestSystem {
+        fn tick(&mut self, map: &mut ServerMap) -> Vec<Modification> {
+            let tile = if self.ticked { Tile::RockSmashed } else { Tile::Rock };
+            self.ticked = true;
+
+            map.set_loaded_tile_at(self.position, tile);
+            vec![Modification::TileChanged(self.position, tile)]
+        }
+    }
+
+    #[tokio::test]
+    async fn empty_pipeline_makes_no_modifications() {
+        let mut map = ServerMap::new_with_default_generator(0);
+        assert!(map.tick().is_empty());
+    }
+
+    #[tokio::test]
+    async fn systems_run_in_order_every_tick() {
+        let mut map = ServerMap::new_with_default_generator(0);
+        map.add_chunk(shared::maps::ChunkCoords { x: 0, y: 0 }, Default::default());
+
+        let (first, second) = (TileCoords { x: 1, y: 1 }, TileCoords { x: 2, y: 2 });
+        map.add_system(Box::new(TestSystem { position: first, ticked: false }));
+        map.add_system(Box::new(TestSystem { position: second, ticked: false }));
+
+        let positions: Vec<_> = map
+            .tick()
+            .into_iter()
+            .map(|modification| match modification {
+                Modification::TileChanged(position, Tile::Rock) => position,
+                other => panic!("Unexpected modification: {}", other)
+            })
+            .collect();
+        assert_eq!(positions, vec![first, second]);
+        assert_eq!(map.loaded_tile_at(second), Some(Tile::Rock));
+
+        // Systems remain in the pipeline after ticking:
+        assert_eq!(map.tick().len(), 2);
+        assert_eq!(map.loaded_tile_at(first), Some(Tile::RockSmashed));
+    }
+}
@@ -0,0 +1,234 @@
+//! Optional HTTP server (enabled using the `--metrics-port` command-line option) allowing server operators to monitor
+//! the server without connecting a game client. Two endpoints are provided:
+//!
+//! * `GET /metrics` - JSON object containing the values described by [`MetricsReport`].
+//! * `GET /health` - `200 OK` should the database be able to execute a trivial query within [`HEALTH_CHECK_TIMEOUT`],
+//!   `503 Service Unavailable` otherwise.
+
+use std::{
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc
+    },
+    time::{Duration, Instant}
+};
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use serde::Serialize;
+
+use crate::{db_query_from_file, worlds::WorldRegistry};
+
+/// How long the database has to respond to the trivial query made by the health check endpoint.
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How much weight the most recent tick is given when smoothing the measured tick rate (between 0 and 1).
+const TICK_RATE_SMOOTHING: f64 = 0.1;
+
+/// Values recorded while the server is running, shared between connection tasks, the world tick task, and the metrics
+/// HTTP server.
+pub struct Metrics {
+    started_at: Instant,
+    connected_clients: AtomicU32,
+    /// The smoothed number of ticks per second, stored as the bits of an `f64` so that it can be updated atomically.
+    ticks_per_second_bits: AtomicU64
+}
+
+impl Metrics {
+    pub fn new(started_at: Instant) -> Self {
+        Metrics { started_at, connected_clients: AtomicU32::new(0), ticks_per_second_bits: AtomicU64::new(0) }
+    }
+
+    /// Record that a client has connected. The client is considered to have disconnected once the returned guard is
+    /// dropped.
+    pub fn client_connected(self: &Arc<Self>) -> ConnectedClient {
+        self.connected_clients.fetch_add(1, Ordering::Relaxed);
+        ConnectedClient(Arc::clone(self))
+    }
+
+    pub fn connected_clients(&self) -> u32 {
+        self.connected_clients.load(Ordering::Relaxed)
+    }
+
+    /// Record that a server tick has taken place the given duration after the previous tick.
+    pub fn tick_completed(&self, since_previous_tick: Duration) {
+        let secs = since_previous_tick.as_secs_f64();
+        if secs <= 0.0 {
+            return;
+        }
+
+        let previous = self.ticks_per_second();
+        let current = 1.0 / secs;
+        let smoothed = if previous == 0.0 { current } else { previous + (TICK_RATE_SMOOTHING * (current - previous)) };
+
+        self.ticks_per_second_bits.store(smoothed.to_bits(), Ordering::Relaxed);
+    }
+
+    /// The smoothed rate at which server ticks are taking place, or 0 should no ticks yet have been recorded.
+    pub fn ticks_per_second(&self) -> f64 {
+        f64::from_bits(self.ticks_per_second_bits.load(Ordering::Relaxed))
+    }
+
+    pub fn uptime(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.started_at)
+    }
+}
+
+/// Decrements the count of connected clients when dropped (see [`Metrics::client_connected`]).
+pub struct ConnectedClient(Arc<Metrics>);
+
+impl Drop for ConnectedClient {
+    fn drop(&mut self) {
+        self.0.connected_clients.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The body of the response to a `GET /metrics` request.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct MetricsReport {
+    pub connected_clients: u32,
+    pub loaded_chunks: usize,
+    pub ticks_per_second: f64,
+    pub uptime_seconds: u64
+}
+
+#[derive(Clone)]
+struct ServerState {
+    metrics: Arc<Metrics>,
+    worlds: Arc<WorldRegistry>,
+    db_pool: sqlx::PgPool
+}
+
+/// Serve the metrics & health check endpoints using the given listener until an error occurs.
+pub async fn serve(listener: TcpListener, metrics: Arc<Metrics>, worlds: Arc<WorldRegistry>, db_pool: sqlx::PgPool) {
+    let router = Router::new()
+        .route("/metrics", get(get_metrics))
+        .route("/health", get(get_health))
+        .with_state(ServerState { metrics, worlds, db_pool });
+
+    let result = match axum::Server::from_tcp(listener) {
+        Ok(builder) => builder.serve(router.into_make_service()).await,
+        Err(e) => Err(e)
+    };
+
+    if let Err(e) = result {
+        tracing::error!("Metrics HTTP server stopped due to error - {}", e);
+    }
+}
+
+async fn get_metrics(State(state): State<ServerState>) -> Json<MetricsReport> {
+    Json(MetricsReport {
+        connected_clients: state.metrics.connected_clients(),
+        loaded_chunks: state.worlds.iter().map(|(_, world)| world.map.lock().loaded_chunk_count()).sum(),
+        ticks_per_second: state.metrics.ticks_per_second(),
+        uptime_seconds: state.metrics.uptime(Instant::now()).as_secs()
+    })
+}
+
+async fn get_health(State(state): State<ServerState>) -> StatusCode {
+    match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, db_query_from_file!("health/select one", &state.db_pool)).await {
+        Ok(Ok(_)) => StatusCode::OK,
+        _ => StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    /// Start a metrics server on an unused local port, returning its address.
+    fn start_test_server(metrics: Arc<Metrics>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let mut worlds = WorldRegistry::default();
+        worlds.insert(
+            crate::worlds::DEFAULT_WORLD_NAME.to_string(),
+            crate::maps::ServerMap::new_with_default_generator(0)
+        );
+        // No database server is listening on port 1 so health checks will fail:
+        let db_pool = sqlx::postgres::PgPoolOptions::new().connect_lazy("postgres://localhost:1").unwrap();
+
+        tokio::spawn(serve(listener, metrics, Arc::new(worlds), db_pool));
+        address
+    }
+
+    /// Make a `GET` request for the given path, returning the response's status code & body.
+    async fn get(address: SocketAddr, path: &str) -> (u16, String) {
+        let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        stream.write_all(format!("GET {} HTTP/1.0\r\n\r\n", path).as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, body.to_string())
+    }
+
+    #[test]
+    fn tick_rate_smoothed() {
+        let metrics = Metrics::new(Instant::now());
+        assert_eq!(metrics.ticks_per_second(), 0.0);
+
+        metrics.tick_completed(Duration::from_millis(50));
+        assert!((metrics.ticks_per_second() - 20.0).abs() < 1e-9);
+
+        metrics.tick_completed(Duration::from_millis(100));
+        assert!((metrics.ticks_per_second() - 19.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn metrics_report_json() {
+        // Instants cannot be set in the past on platforms where the monotonic clock began too recently:
+        let started_at = Instant::now().checked_sub(Duration::from_secs(90));
+        let metrics = Arc::new(Metrics::new(started_at.unwrap_or_else(Instant::now)));
+        metrics.tick_completed(Duration::from_millis(50));
+        let address = start_test_server(Arc::clone(&metrics));
+
+        let (status, body) = get(address, "/metrics").await;
+        assert_eq!(status, 200);
+
+        let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+        let object = value.as_object().unwrap();
+        assert_eq!(object.len(), 4);
+        assert_eq!(object["connected_clients"], 0);
+        assert_eq!(object["loaded_chunks"], 0);
+        assert_eq!(object["ticks_per_second"], 20.0);
+        if started_at.is_some() {
+            assert!(object["uptime_seconds"].as_u64().unwrap() >= 90);
+        }
+    }
+
+    #[tokio::test]
+    async fn connected_clients_counted() {
+        let metrics = Arc::new(Metrics::new(Instant::now()));
+        let address = start_test_server(Arc::clone(&metrics));
+
+        let connected_clients = |body: String| {
+            serde_json::from_str::<serde_json::Value>(&body).unwrap()["connected_clients"].as_u64().unwrap()
+        };
+
+        let first = metrics.client_connected();
+        let second = metrics.client_connected();
+        assert_eq!(connected_clients(get(address, "/metrics").await.1), 2);
+
+        drop(first);
+        assert_eq!(connected_clients(get(address, "/metrics").await.1), 1);
+
+        drop(second);
+        assert_eq!(connected_clients(get(address, "/metrics").await.1), 0);
+    }
+
+    #[tokio::test]
+    async fn health_check_fails_without_database() {
+        let address = start_test_server(Arc::new(Metrics::new(Instant::now())));
+
+        assert_eq!(get(address, "/health").await.0, 503);
+        assert_eq!(get(address, "/nonexistent").await.0, 404);
+    }
+}
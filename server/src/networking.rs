@@ -4,7 +4,13 @@ use futures_util::{SinkExt, StreamExt};
 use shared::messages;
 use thiserror::Error;
 use tokio::net::TcpStream;
-use tokio_tungstenite::{tungstenite, WebSocketStream};
+use tokio_tungstenite::{
+    tungstenite::{
+        self,
+        protocol::{frame::coding::CloseCode, CloseFrame}
+    },
+    WebSocketStream
+};
 
 /// Manages a WebSocket connection and simplifies the process of sending and receiving bincode messages.
 pub struct Connection {
@@ -39,6 +45,12 @@ impl Connection {
     pub async fn close(&mut self) -> Result<()> {
         self.ws.close(None).await.map_err(convert::Into::into)
     }
+
+    /// Close the connection with a close frame containing the specified status code.
+    pub async fn close_with_code(&mut self, code: CloseCode) -> Result<()> {
+        let frame = CloseFrame { code, reason: "".into() };
+        self.ws.close(Some(frame)).await.map_err(convert::Into::into)
+    }
 }
 
 #[derive(Error, Debug)]
@@ -0,0 +1,250 @@
+//! Parties allow groups of players to share the gems collected by any one of them (see
+//! [`shared::messages::ToServer::InviteToParty`]). Parties only exist in memory so do not persist across server
+//! restarts. As players leave their party upon disconnecting, every member of a party is always online.
+
+use std::collections::{HashMap, HashSet};
+
+use shared::{messages::RejectionReason, Id};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Party {
+    pub id: Id,
+    /// The entity ID of the player who formed the party (by inviting another player). The party is disbanded should
+    /// its leader leave.
+    pub leader: Id,
+    /// The entity IDs of the party's members, including the leader (who is always first).
+    pub members: Vec<Id>
+}
+
+/// Describes the result of a player leaving their party.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartyChange {
+    /// The party was disbanded (as its leader left or as only one member would have remained) so none of the given
+    /// former members are in a party any longer.
+    Disbanded { former_members: Vec<Id> },
+    /// The given member left the party which continues on with its remaining members.
+    MemberLeft { member: Id, party: Party }
+}
+
+/// Keeps track of all parties & pending party invites.
+#[derive(Debug, Default)]
+pub struct Parties {
+    /// Parties mapped to by their IDs.
+    parties: HashMap<Id, Party>,
+    /// Maps the entity ID of each player in a party to the ID of that party.
+    memberships: HashMap<Id, Id>,
+    /// Maps the entity ID of each invited player to the entity IDs of the players who invited them.
+    invites: HashMap<Id, HashSet<Id>>
+}
+
+impl Parties {
+    /// Get the party that the player with the given entity ID is a member of (if any).
+    pub fn party_of(&self, entity_id: Id) -> Option<&Party> {
+        self.memberships.get(&entity_id).and_then(|party_id| self.parties.get(party_id))
+    }
+
+    /// Record an invite from one player to another. Only players who are not in a party or who lead their party may
+    /// invite others, and players who are already in a party cannot be invited.
+    pub fn invite(&mut self, inviter: Id, invitee: Id) -> Result<(), RejectionReason> {
+        debug_assert_ne!(inviter, invitee);
+
+        if self.party_of(inviter).is_some_and(|party| party.leader != inviter) {
+            return Err(RejectionReason::NotPartyLeader);
+        }
+
+        if self.memberships.contains_key(&invitee) {
+            return Err(RejectionReason::AlreadyInParty);
+        }
+
+        self.invites.entry(invitee).or_default().insert(inviter);
+        Ok(())
+    }
+
+    /// Have a player accept an invite previously sent to them, either joining the inviter's party or forming a new
+    /// party led by the inviter should the inviter not be in a party. The party joined is returned.
+    pub fn accept_invite(&mut self, invitee: Id, inviter: Id) -> Result<&Party, RejectionReason> {
+        if !self.invites.get(&invitee).is_some_and(|inviters| inviters.contains(&inviter)) {
+            return Err(RejectionReason::NoPartyInvite);
+        }
+
+        if self.memberships.contains_key(&invitee) {
+            return Err(RejectionReason::AlreadyInParty);
+        }
+
+        // Invites from players who have since joined a party they do not lead are no longer valid:
+        if self.party_of(inviter).is_some_and(|party| party.leader != inviter) {
+            self.remove_invite(invitee, inviter);
+            return Err(RejectionReason::NoPartyInvite);
+        }
+
+        // Now that the invitee is joining a party, no other invites sent to them can be accepted:
+        self.invites.remove(&invitee);
+
+        let party_id = match self.memberships.get(&inviter) {
+            Some(party_id) => *party_id,
+            None => {
+                let party_id = crate::id::generate_with_timestamp();
+                self.parties.insert(party_id, Party { id: party_id, leader: inviter, members: vec![inviter] });
+                self.memberships.insert(inviter, party_id);
+                party_id
+            }
+        };
+
+        let party = self.parties.get_mut(&party_id).unwrap();
+        party.members.push(invitee);
+        self.memberships.insert(invitee, party_id);
+
+        Ok(party)
+    }
+
+    /// Remove the player with the given entity ID from their party (if they are in one). The party is disbanded should
+    /// that player be its leader or should only one member remain.
+    pub fn leave(&mut self, member: Id) -> Option<PartyChange> {
+        let party_id = self.memberships.remove(&member)?;
+        let party = self.parties.get_mut(&party_id)?;
+
+        party.members.retain(|id| *id != member);
+
+        if party.leader == member || party.members.len() < 2 {
+            let party = self.parties.remove(&party_id)?;
+
+            for id in &party.members {
+                self.memberships.remove(id);
+            }
+
+            let former_members = std::iter::once(member).chain(party.members).collect();
+            Some(PartyChange::Disbanded { former_members })
+        }
+        else {
+            Some(PartyChange::MemberLeft { member, party: party.clone() })
+        }
+    }
+
+    /// Remove all invites sent to or by the player with the given entity ID (e.g. as they disconnected).
+    pub fn remove_invites_involving(&mut self, entity_id: Id) {
+        self.invites.remove(&entity_id);
+        self.invites.values_mut().for_each(|inviters| {
+            inviters.remove(&entity_id);
+        });
+        self.invites.retain(|_, inviters| !inviters.is_empty());
+    }
+
+    fn remove_invite(&mut self, invitee: Id, inviter: Id) {
+        if let Some(inviters) = self.invites.get_mut(&invitee) {
+            inviters.remove(&inviter);
+
+            if inviters.is_empty() {
+                self.invites.remove(&invitee);
+            }
+        }
+    }
+}
+
+/// Split the given quantity of gems evenly among the members of the given party, returning the entity ID of each member
+/// paired with their share. Any remainder that cannot be split evenly goes to the party's leader so that the shares
+/// always add up to the given quantity.
+pub fn split_gems(party: &Party, quantity: u32) -> Vec<(Id, u32)> {
+    let member_count = party.members.len().max(1) as u32;
+    let (share, remainder) = (quantity / member_count, quantity % member_count);
+
+    party
+        .members
+        .iter()
+        .map(|member| (*member, if *member == party.leader { share + remainder } else { share }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids() -> (Id, Id, Id) {
+        (Id::new(1), Id::new(2), Id::new(3))
+    }
+
+    #[test]
+    fn accepting_invites_forms_party() {
+        let (leader, first, second) = ids();
+        let mut parties = Parties::default();
+
+        assert_eq!(parties.accept_invite(first, leader), Err(RejectionReason::NoPartyInvite));
+
+        parties.invite(leader, first).unwrap();
+        parties.invite(leader, second).unwrap();
+        assert!(parties.party_of(leader).is_none());
+
+        let party = parties.accept_invite(first, leader).unwrap().clone();
+        assert_eq!((party.leader, party.members.clone()), (leader, vec![leader, first]));
+
+        // Only the leader may invite players, and players cannot be invited while in a party:
+        assert_eq!(parties.invite(first, second), Err(RejectionReason::NotPartyLeader));
+        assert_eq!(parties.invite(second, first), Err(RejectionReason::AlreadyInParty));
+
+        parties.accept_invite(second, leader).unwrap();
+        assert_eq!(parties.party_of(second).unwrap().members, vec![leader, first, second]);
+        assert_eq!(parties.party_of(first).unwrap().id, party.id);
+    }
+
+    #[test]
+    fn leader_leaving_disbands_party() {
+        let (leader, first, second) = ids();
+        let mut parties = Parties::default();
+
+        for member in [first, second] {
+            parties.invite(leader, member).unwrap();
+            parties.accept_invite(member, leader).unwrap();
+        }
+
+        match parties.leave(first) {
+            Some(PartyChange::MemberLeft { member, party }) => {
+                assert_eq!(member, first);
+                assert_eq!(party.members, vec![leader, second]);
+            }
+            other => panic!("unexpected party change {:?}", other)
+        }
+
+        assert_eq!(parties.leave(leader), Some(PartyChange::Disbanded { former_members: vec![leader, second] }));
+        assert!(parties.party_of(second).is_none());
+        assert_eq!(parties.leave(second), None);
+    }
+
+    #[test]
+    fn party_of_one_disbanded() {
+        let (leader, first, _) = ids();
+        let mut parties = Parties::default();
+
+        parties.invite(leader, first).unwrap();
+        parties.accept_invite(first, leader).unwrap();
+
+        assert_eq!(parties.leave(first), Some(PartyChange::Disbanded { former_members: vec![first, leader] }));
+        assert!(parties.party_of(leader).is_none());
+    }
+
+    #[test]
+    fn invites_removed_on_disconnect() {
+        let (leader, first, second) = ids();
+        let mut parties = Parties::default();
+
+        parties.invite(leader, first).unwrap();
+        parties.invite(second, first).unwrap();
+        parties.remove_invites_involving(leader);
+
+        assert_eq!(parties.accept_invite(first, leader), Err(RejectionReason::NoPartyInvite));
+        assert!(parties.accept_invite(first, second).is_ok());
+    }
+
+    #[test]
+    fn gem_splitting_lossless() {
+        let (leader, first, second) = ids();
+        let party = Party { id: Id::new(0), leader, members: vec![leader, first, second] };
+
+        assert_eq!(split_gems(&party, 9), vec![(leader, 3), (first, 3), (second, 3)]);
+        assert_eq!(split_gems(&party, 11), vec![(leader, 5), (first, 3), (second, 3)]);
+        assert_eq!(split_gems(&party, 2), vec![(leader, 2), (first, 0), (second, 0)]);
+
+        for quantity in 0..50 {
+            let shares = split_gems(&party, quantity);
+            assert_eq!(shares.iter().map(|(_, share)| share).sum::<u32>(), quantity);
+        }
+    }
+}
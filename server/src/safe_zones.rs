@@ -0,0 +1,110 @@
+//! Areas of the map in which players cannot place bombs and are not damaged by monsters (e.g. to protect the area in
+//! which players spawn).
+//!
+//! Safe zones are read from the JSON file specified using the `--safe-zones-file` command-line option, which contains
+//! an array of zones each with a centre tile & radius (e.g. `[{"centre": {"x": 0, "y": 0}, "radius": 5}]`). Should
+//! that option not be given, only the area around the spawn point is safe (see
+//! [`SafeZoneRegistry::around_spawn_point`]). The same safe zones apply to every world.
+
+use std::{
+    io,
+    path::{Path, PathBuf}
+};
+
+use shared::maps::{self, SafeZone, TileCoords};
+
+/// The safe zone used should no safe zones file be specified, which protects the area around the spawn point.
+pub const DEFAULT_SAFE_ZONE: SafeZone = SafeZone { centre: TileCoords { x: 0, y: 0 }, radius: 5 };
+
+#[derive(Debug, PartialEq)]
+pub struct SafeZoneRegistry {
+    zones: Vec<SafeZone>
+}
+
+impl SafeZoneRegistry {
+    pub fn new(zones: Vec<SafeZone>) -> Self {
+        SafeZoneRegistry { zones }
+    }
+
+    /// Read and parse the safe zones file at the given path.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| Error::IoError(path.to_owned(), e))?;
+        let zones = serde_json::from_str(&text).map_err(|e| Error::ParseError(path.to_owned(), e))?;
+
+        Ok(SafeZoneRegistry { zones })
+    }
+
+    /// Returns whether the tile at the given coordinates is within any safe zone.
+    pub fn contains(&self, coords: TileCoords) -> bool {
+        maps::is_in_safe_zone(&self.zones, coords)
+    }
+
+    /// Only the area around the given spawn point is safe (with the same radius as [`DEFAULT_SAFE_ZONE`]).
+    pub fn around_spawn_point(spawn_point: TileCoords) -> Self {
+        SafeZoneRegistry::new(vec![SafeZone { centre: spawn_point, ..DEFAULT_SAFE_ZONE }])
+    }
+
+    pub fn zones(&self) -> &[SafeZone] {
+        &self.zones
+    }
+}
+
+impl Default for SafeZoneRegistry {
+    fn default() -> Self {
+        SafeZoneRegistry::new(vec![DEFAULT_SAFE_ZONE])
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Failed to read safe zones file {0} - {1}")]
+    IoError(PathBuf, #[source] io::Error),
+    #[error("Failed to parse safe zones file {0} - {1}")]
+    ParseError(PathBuf, #[source] serde_json::Error)
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_protects_spawn() {
+        let safe_zones = SafeZoneRegistry::default();
+
+        assert!(safe_zones.contains(TileCoords { x: 0, y: 0 }));
+        assert!(safe_zones.contains(TileCoords { x: 5, y: -5 }));
+        assert!(!safe_zones.contains(TileCoords { x: 6, y: 0 }));
+
+        let safe_zones = SafeZoneRegistry::around_spawn_point(TileCoords { x: 20, y: -3 });
+
+        assert!(safe_zones.contains(TileCoords { x: 25, y: 2 }));
+        assert!(!safe_zones.contains(TileCoords { x: 0, y: 0 }));
+    }
+
+    #[test]
+    fn load_from_file() {
+        let path = std::env::temp_dir().join(format!("gemgame-safe-zones-test-{}.json", std::process::id()));
+
+        std::fs::write(
+            &path,
+            r#"[{"centre": {"x": 10, "y": -4}, "radius": 2}, {"centre": {"x": 0, "y": 0}, "radius": 0}]"#
+        )
+        .unwrap();
+        let loaded = SafeZoneRegistry::load(&path);
+
+        std::fs::write(&path, r#"[{"centre": {"x": 10}, "radius": 2}]"#).unwrap();
+        let invalid = SafeZoneRegistry::load(&path);
+
+        std::fs::remove_file(&path).unwrap();
+
+        let safe_zones = loaded.unwrap();
+        assert_eq!(safe_zones.zones().len(), 2);
+        assert!(safe_zones.contains(TileCoords { x: 12, y: -6 }));
+        assert!(!safe_zones.contains(TileCoords { x: 1, y: 0 }));
+
+        assert!(matches!(invalid, Err(Error::ParseError(..))));
+        assert!(matches!(SafeZoneRegistry::load(&path), Err(Error::IoError(..))));
+    }
+}
@@ -0,0 +1,153 @@
+//! Hosting of multiple game worlds by a single server. Each world has its own [`ServerMap`] (with chunks & player
+//! entities stored in the database under the world's name) and its own channel through which connection tasks are
+//! informed of changes to that map. Players in different worlds therefore never see one another.
+//!
+//! The worlds hosted are named after the subdirectories of the directory given using the `--worlds-dir` command-line
+//! option. Should that option not be given, only a single world named [`DEFAULT_WORLD_NAME`] is hosted.
+
+use std::{collections::HashMap, io, path::Path, sync::Arc};
+
+use parking_lot::Mutex;
+use tokio::sync::broadcast;
+
+use crate::{
+    maps::{self, ServerMap},
+    Shared
+};
+
+/// The name of the world hosted when no worlds directory is specified. Chunks & player entities stored before
+/// multiple worlds were supported belong to this world.
+pub const DEFAULT_WORLD_NAME: &str = "default";
+
+/// The number of map changes that may be waiting to be received by each connection task before older changes are
+/// dropped.
+const MAP_CHANGES_CHANNEL_CAPACITY: usize = 5;
+
+/// A game map along with the channel used to inform connection tasks of changes made to it.
+pub struct World {
+    pub map: Shared<ServerMap>,
+    pub map_changes_sender: broadcast::Sender<maps::Modification>
+}
+
+/// The worlds hosted by the server, mapped to by their names.
+#[derive(Default)]
+pub struct WorldRegistry {
+    worlds: HashMap<String, World>
+}
+
+impl WorldRegistry {
+    /// Add a world with the given name & map, creating a new map changes channel for it. The map is made to belong to
+    /// the named world should it not already so that the registry never disagrees with the database over which world a
+    /// map is stored under.
+    pub fn insert(&mut self, name: String, mut map: ServerMap) -> &World {
+        map.set_world(name.clone());
+
+        let (map_changes_sender, _) = broadcast::channel(MAP_CHANGES_CHANNEL_CAPACITY);
+        let world = World { map: Arc::new(Mutex::new(map)), map_changes_sender };
+
+        self.worlds.insert(name.clone(), world);
+        &self.worlds[&name]
+    }
+
+    pub fn get(&self, name: &str) -> Option<&World> {
+        self.worlds.get(name)
+    }
+
+    /// The names of all worlds in alphabetical order.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.worlds.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// The world that is joined by connection tasks until their client chooses a world, into which map snapshots are
+    /// imported, and from which map snapshots are exported. This is the world named [`DEFAULT_WORLD_NAME`] should
+    /// there be one, otherwise the first world in alphabetical order. Panics should there be no worlds.
+    pub fn default_world(&self) -> (&str, &World) {
+        let name = match self.worlds.get_key_value(DEFAULT_WORLD_NAME) {
+            Some((name, _)) => name,
+            None => self.worlds.keys().min().expect("No worlds hosted")
+        };
+
+        (name, &self.worlds[name])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &World)> {
+        self.worlds.iter().map(|(name, world)| (name.as_str(), world))
+    }
+}
+
+/// The names of the worlds in the given worlds directory (i.e. the names of its subdirectories) in alphabetical order.
+/// Subdirectories with names that are not valid UTF-8 are ignored.
+pub fn world_names_in_directory(directory: &Path) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+
+        if entry.file_type()?.is_dir() {
+            if let Ok(name) = entry.file_name().into_string() {
+                names.push(name);
+            }
+        }
+    }
+
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn default_world_chosen() {
+        let mut worlds = WorldRegistry::default();
+        worlds.insert("tundra".to_string(), ServerMap::new_with_default_generator(0));
+        worlds.insert("beach".to_string(), ServerMap::new_with_default_generator(0));
+
+        assert_eq!(worlds.names(), vec!["beach", "tundra"]);
+        assert_eq!(worlds.default_world().0, "beach");
+
+        worlds.insert(DEFAULT_WORLD_NAME.to_string(), ServerMap::new_with_default_generator(0));
+        assert_eq!(worlds.default_world().0, DEFAULT_WORLD_NAME);
+    }
+
+    #[tokio::test]
+    async fn maps_belong_to_world_inserted_as() {
+        let mut worlds = WorldRegistry::default();
+        worlds.insert("tundra".to_string(), ServerMap::new_with_default_generator(0));
+
+        assert_eq!(worlds.get("tundra").unwrap().map.lock().world(), "tundra");
+    }
+
+    #[tokio::test]
+    async fn worlds_have_separate_channels() {
+        let mut worlds = WorldRegistry::default();
+        worlds.insert("first".to_string(), ServerMap::new_with_default_generator(0));
+        worlds.insert("second".to_string(), ServerMap::new_with_default_generator(0));
+
+        let mut first_receiver = worlds.get("first").unwrap().map_changes_sender.subscribe();
+        let mut second_receiver = worlds.get("second").unwrap().map_changes_sender.subscribe();
+
+        let modification = maps::Modification::Announcement { text: "Hello".to_string() };
+        worlds.get("second").unwrap().map_changes_sender.send(modification).unwrap();
+
+        assert!(first_receiver.try_recv().is_err());
+        assert!(matches!(second_receiver.try_recv(), Ok(maps::Modification::Announcement { .. })));
+        assert!(worlds.get("third").is_none());
+    }
+
+    #[test]
+    fn world_names_read_from_subdirectories() {
+        let directory = std::env::temp_dir().join(format!("gemgame-worlds-test-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("overworld")).unwrap();
+        std::fs::create_dir_all(directory.join("arena")).unwrap();
+        std::fs::write(directory.join("notes.txt"), "Not a world").unwrap();
+
+        let names = world_names_in_directory(&directory);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(names.unwrap(), vec!["arena", "overworld"]);
+    }
+}
@@ -0,0 +1,75 @@
+//! Achievements unlocked by players for reaching milestones while playing (see
+//! [`crate::messages::FromServer::AchievementUnlocked`]).
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use strum::{EnumIter, IntoEnumIterator};
+
+/// The numbers of chunks that a player must explore (see [`crate::stats::PlayerStats::chunks_explored`]) in order to
+/// unlock each [`Achievement::ExploredChunks`] achievement.
+pub const EXPLORED_CHUNKS_MILESTONES: [u32; 3] = [10, 50, 100];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "std", derive(EnumIter))]
+pub enum Achievement {
+    FirstGem,
+    MinedRock,
+    KilledMonster,
+    CompletedQuest,
+    /// Explored the given number of chunks (one of [`EXPLORED_CHUNKS_MILESTONES`]).
+    ExploredChunks(u32)
+}
+
+#[cfg(feature = "std")]
+impl Achievement {
+    /// Iterate over every achievement that may be unlocked, including an [`Achievement::ExploredChunks`] achievement
+    /// for each of [`EXPLORED_CHUNKS_MILESTONES`]. The position of each achievement in this sequence is used to
+    /// identify it when stored (see [`Achievement::index`]) so new achievements must only ever be added to the end.
+    pub fn all() -> impl Iterator<Item = Achievement> {
+        Achievement::iter().flat_map(|achievement| match achievement {
+            Achievement::ExploredChunks(_) => {
+                EXPLORED_CHUNKS_MILESTONES.iter().map(|milestone| Achievement::ExploredChunks(*milestone)).collect()
+            }
+            other => vec![other]
+        })
+    }
+
+    /// The position of this achievement in the sequence produced by [`Achievement::all`], or `None` should this not be
+    /// an achievement that can be unlocked (i.e. an exploration achievement for a number of chunks that is not a
+    /// milestone).
+    pub fn index(self) -> Option<usize> {
+        Achievement::all().position(|achievement| achievement == self)
+    }
+}
+
+impl fmt::Display for Achievement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Achievement::FirstGem => write!(f, "Shiny! - collect your first gem"),
+            Achievement::MinedRock => write!(f, "Rock Bottom - smash a rock"),
+            Achievement::KilledMonster => write!(f, "Monster Masher - kill a monster"),
+            Achievement::CompletedQuest => write!(f, "Adventurer - complete a quest"),
+            Achievement::ExploredChunks(count) => write!(f, "Explorer - visit {} different chunks", count)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn achievements_indexed_uniquely() {
+        let all: Vec<Achievement> = Achievement::all().collect();
+        assert_eq!(all.len(), 4 + EXPLORED_CHUNKS_MILESTONES.len());
+
+        for (i, achievement) in all.iter().enumerate() {
+            assert_eq!(achievement.index(), Some(i));
+        }
+
+        assert_eq!(Achievement::ExploredChunks(0).index(), None);
+        assert_eq!(Achievement::ExploredChunks(50).to_string(), "Explorer - visit 50 different chunks");
+    }
+}
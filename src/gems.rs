@@ -1,13 +1,27 @@
-use std::{collections::HashMap, fmt};
+use core::{fmt, ops};
 
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::HashMap;
 
 /// Represents the types of gems which may be collected by players.
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "std", derive(EnumIter))]
 pub enum Gem {
     Emerald,
     Ruby,
-    Diamond
+    Diamond,
+    Sapphire
+}
+
+#[cfg(feature = "std")]
+impl Gem {
+    /// Iterate over every type of gem.
+    pub fn all() -> impl Iterator<Item = Gem> {
+        Gem::iter()
+    }
 }
 
 /// Represents the potential yield of gems produced from the smashing of a rock tile.
@@ -17,7 +31,16 @@ pub struct Yield {
     pub maximum_quantity: u32
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+/// The possible yields of gems produced from the opening of a chest (see [`crate::maps::Tile::Chest`]), each paired
+/// with the relative likelihood of that yield being chosen.
+pub const CHEST_LOOT_TABLE: [(Yield, u32); 4] = [
+    (Yield { gem: Gem::Emerald, minimum_quantity: 5, maximum_quantity: 10 }, 50),
+    (Yield { gem: Gem::Ruby, minimum_quantity: 3, maximum_quantity: 6 }, 30),
+    (Yield { gem: Gem::Diamond, minimum_quantity: 1, maximum_quantity: 3 }, 15),
+    (Yield { gem: Gem::Sapphire, minimum_quantity: 1, maximum_quantity: 2 }, 5)
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
 pub struct Collection {
     collection: HashMap<Gem, u32>
 }
@@ -31,22 +54,245 @@ impl Collection {
         *self.collection.entry(gem).or_default() += increase;
     }
 
+    /// Add the quantity of each type of gem in the given collection to this collection.
+    pub fn merge(&mut self, other: &Collection) {
+        for (gem, quantity) in &other.collection {
+            let entry = self.collection.entry(*gem).or_default();
+            *entry = entry.saturating_add(*quantity);
+        }
+    }
+
+    /// The total number of gems of all types in this collection.
+    pub fn total_count(&self) -> u32 {
+        self.collection.values().fold(0, |total, quantity| total.saturating_add(*quantity))
+    }
+
+    /// Returns `true` should this collection contain no gems at all.
+    pub fn is_empty(&self) -> bool {
+        self.collection.values().all(|quantity| *quantity == 0)
+    }
+
+    /// Decrease the quantity of the given gem type. Panics should the collection contain fewer gems of that type than
+    /// the specified decrease - use [`Self::try_subtract`] should this be possible.
     pub fn decrease_quantity(&mut self, gem: Gem, decrease: u32) {
-        *self.collection.entry(gem).or_default() -= decrease;
+        self.try_subtract(gem, decrease).expect("Attempted to decrease gem quantity below zero");
+    }
+
+    /// Decrease the quantity of the given gem type by the specified amount. If there are insufficient gems of that type
+    /// then an error is returned and the collection is left unchanged.
+    pub fn try_subtract(&mut self, gem: Gem, amount: u32) -> Result<(), InsufficientGemsError> {
+        let available = self.get_quantity(gem);
+
+        if available >= amount {
+            self.collection.insert(gem, available - amount);
+            Ok(())
+        }
+        else {
+            Err(InsufficientGemsError { gem, requested: amount, available })
+        }
+    }
+
+    /// Remove half (rounded down) of the quantity of each type of gem from this collection, returning the removed gems
+    /// as a separate collection.
+    pub fn take_half(&mut self) -> Collection {
+        let mut taken = Collection::default();
+
+        for (gem, quantity) in self.collection.iter_mut() {
+            let half = *quantity / 2;
+
+            *quantity -= half;
+            taken.collection.insert(*gem, half);
+        }
+
+        taken
     }
 }
 
+impl ops::AddAssign<&Collection> for Collection {
+    fn add_assign(&mut self, other: &Collection) {
+        self.merge(other);
+    }
+}
+
+impl ops::AddAssign for Collection {
+    fn add_assign(&mut self, other: Collection) {
+        self.merge(&other);
+    }
+}
+
+impl ops::Add for Collection {
+    type Output = Collection;
+
+    fn add(mut self, other: Collection) -> Collection {
+        self.merge(&other);
+        self
+    }
+}
+
+/// Error indicating that a [`Collection`] contains fewer gems of a given type than were requested.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InsufficientGemsError {
+    pub gem: Gem,
+    pub requested: u32,
+    pub available: u32
+}
+
+impl fmt::Display for InsufficientGemsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "requested {} gems of type {:?} but only {} are available", self.requested, self.gem, self.available)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InsufficientGemsError {}
+
 impl fmt::Display for Collection {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "({:?} x {}, {:?} x {}, {:?} x {})",
+            "({:?} x {}, {:?} x {}, {:?} x {}, {:?} x {})",
             Gem::Emerald,
             self.get_quantity(Gem::Emerald),
             Gem::Ruby,
             self.get_quantity(Gem::Ruby),
             Gem::Diamond,
-            self.get_quantity(Gem::Diamond)
+            self.get_quantity(Gem::Diamond),
+            Gem::Sapphire,
+            self.get_quantity(Gem::Sapphire)
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// Create a collection containing the given quantities of each type of gem (in the order given by [`Gem::all`]).
+    fn collection_of(quantities: &[u32]) -> Collection {
+        let mut collection = Collection::default();
+        for (gem, quantity) in Gem::all().zip(quantities) {
+            collection.increase_quantity(gem, *quantity);
+        }
+        collection
+    }
+
+    #[test]
+    fn merge_collections() {
+        let mut a = collection_of(&[1, 0, 4, 2]);
+        a.merge(&collection_of(&[2, 3, 0, 1]));
+        assert_eq!(a, collection_of(&[3, 3, 4, 3]));
+
+        assert_eq!(collection_of(&[1, 2, 3, 4]) + collection_of(&[3, 2, 1, 0]), collection_of(&[4, 4, 4, 4]));
+
+        let mut b = Collection::default();
+        b += collection_of(&[0, 5, 0, 0]);
+        b += &collection_of(&[0, 1, 1, 7]);
+        assert_eq!(b, collection_of(&[0, 6, 1, 7]));
+    }
+
+    #[test]
+    fn total_count_and_is_empty() {
+        assert_eq!(Collection::default().total_count(), 0);
+        assert!(Collection::default().is_empty());
+
+        let mut collection = collection_of(&[2, 0, 5, 0]);
+        assert_eq!(collection.total_count(), 7);
+        assert!(!collection.is_empty());
+
+        // Collection should be considered empty after all gems are removed:
+        collection.try_subtract(Gem::Emerald, 2).unwrap();
+        collection.try_subtract(Gem::Diamond, 5).unwrap();
+        assert!(collection.is_empty());
+
+        assert_eq!(collection_of(&[u32::MAX, 1, 0, 0]).total_count(), u32::MAX);
+    }
+
+    #[test]
+    fn try_subtract_sufficient() {
+        let mut collection = Collection::default();
+        collection.increase_quantity(Gem::Ruby, 5);
+
+        assert_eq!(collection.try_subtract(Gem::Ruby, 3), Ok(()));
+        assert_eq!(collection.get_quantity(Gem::Ruby), 2);
+
+        assert_eq!(collection.try_subtract(Gem::Ruby, 2), Ok(()));
+        assert_eq!(collection.get_quantity(Gem::Ruby), 0);
+    }
+
+    #[test]
+    fn try_subtract_insufficient() {
+        let mut collection = Collection::default();
+        collection.increase_quantity(Gem::Diamond, 1);
+
+        let expected_err = InsufficientGemsError { gem: Gem::Diamond, requested: 2, available: 1 };
+        assert_eq!(collection.try_subtract(Gem::Diamond, 2), Err(expected_err));
+        assert_eq!(collection.get_quantity(Gem::Diamond), 1);
+
+        let expected_err = InsufficientGemsError { gem: Gem::Emerald, requested: 1, available: 0 };
+        assert_eq!(collection.try_subtract(Gem::Emerald, 1), Err(expected_err));
+        assert_eq!(collection, {
+            let mut unchanged = Collection::default();
+            unchanged.increase_quantity(Gem::Diamond, 1);
+            unchanged
+        });
+    }
+
+    #[test]
+    fn take_half_rounds_down() {
+        let mut collection = collection_of(&[7, 1, 0, 10]);
+
+        assert_eq!(collection.take_half(), collection_of(&[3, 0, 0, 5]));
+        assert_eq!(collection, collection_of(&[4, 1, 0, 5]));
+
+        assert!(Collection::default().take_half().is_empty());
+    }
+
+    #[test]
+    fn display_collection() {
+        let collection = collection_of(&[1, 0, 2, 3]);
+        assert_eq!(collection.to_string(), "(Emerald x 1, Ruby x 0, Diamond x 2, Sapphire x 3)");
+    }
+
+    #[test]
+    fn all_gems() {
+        assert_eq!(Gem::all().collect::<Vec<_>>(), vec![Gem::Emerald, Gem::Ruby, Gem::Diamond, Gem::Sapphire]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn decrease_quantity_underflow_panics() {
+        Collection::default().decrease_quantity(Gem::Emerald, 1);
+    }
+
+    proptest! {
+        #[test]
+        fn merge_then_subtract_roundtrip(
+            a in prop::array::uniform4(0..u32::MAX / 2),
+            b in prop::array::uniform4(0..u32::MAX / 2)
+        ) {
+            let original = collection_of(&a);
+            let other = collection_of(&b);
+
+            let mut merged = original.clone();
+            merged.merge(&other);
+
+            for gem in Gem::all() {
+                prop_assert_eq!(merged.get_quantity(gem), original.get_quantity(gem) + other.get_quantity(gem));
+                prop_assert!(merged.try_subtract(gem, other.get_quantity(gem)).is_ok());
+            }
+
+            prop_assert_eq!(merged, original);
+        }
+
+        #[test]
+        fn merge_total_count_is_sum(
+            a in prop::array::uniform4(0..u32::MAX / 8),
+            b in prop::array::uniform4(0..u32::MAX / 8)
+        ) {
+            let (x, y) = (collection_of(&a), collection_of(&b));
+            prop_assert_eq!((x.clone() + y.clone()).total_count(), x.total_count() + y.total_count());
+        }
+    }
+}
@@ -1,10 +1,11 @@
 //! Defines an 'ID' type used to refer to remote clients (i.e. act as a session ID) as well as entities.
 
-use std::{convert::TryInto, fmt, str};
+use alloc::string::String;
+use core::{convert::TryInto, fmt};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
 pub struct Id {
     value: u128
 }
@@ -1,14 +1,25 @@
-use std::collections::HashMap;
-
 use serde::{Deserialize, Serialize};
 
-use crate::gems::Gem;
+use crate::{gems::Gem, HashMap};
+
+/// The amount of health restored by using a [`QuantitativeItem::Potion`].
+pub const POTION_HEALTH_RECOVERY: u32 = 25;
+
+/// The number of slots in an inventory unless specified otherwise (see [`Inventory::max_slots`]).
+pub const DEFAULT_MAX_INVENTORY_SLOTS: u32 = 20;
+
+/// The maximum combined weight of the quantitative items that may be carried in an inventory (see
+/// [`Inventory::total_weight`]).
+pub const MAX_INVENTORY_WEIGHT: u32 = 30;
 
 pub trait Item {
     fn get_price(&self) -> (Gem, u32);
+
+    /// Whether or not there is room in the given inventory for a single one of this item.
+    fn fits_in(&self, inventory: &Inventory) -> bool;
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum BoolItem {
     RunningShoes
 }
@@ -19,28 +30,80 @@ impl Item for BoolItem {
             BoolItem::RunningShoes => (Gem::Emerald, 20)
         }
     }
+
+    /// Items of this kind do not occupy inventory slots.
+    fn fits_in(&self, _inventory: &Inventory) -> bool {
+        true
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum QuantitativeItem {
-    Bomb
+    Bomb,
+    /// Restores [`POTION_HEALTH_RECOVERY`] health when used.
+    Potion
 }
 
 impl Item for QuantitativeItem {
     fn get_price(&self) -> (Gem, u32) {
         match self {
-            QuantitativeItem::Bomb => (Gem::Ruby, 5)
+            QuantitativeItem::Bomb => (Gem::Ruby, 5),
+            QuantitativeItem::Potion => (Gem::Ruby, 5)
         }
     }
+
+    fn fits_in(&self, inventory: &Inventory) -> bool {
+        inventory.can_add(*self, 1) && inventory.can_carry(*self, 1)
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+impl QuantitativeItem {
+    /// The weight of a single one of this item (see [`crate::mechanics::movement_speed_multiplier`]).
+    pub fn weight(&self) -> u32 {
+        match self {
+            QuantitativeItem::Bomb => 2,
+            QuantitativeItem::Potion => 1
+        }
+    }
+
+    /// The number of inventory slots occupied by a single one of this item (see [`Inventory::max_slots`]).
+    pub fn slots(&self) -> u32 {
+        match self {
+            QuantitativeItem::Bomb | QuantitativeItem::Potion => 1
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Inventory {
     bool_items: HashMap<BoolItem, bool>,
-    quantitive_items: HashMap<QuantitativeItem, u32>
+    quantitive_items: HashMap<QuantitativeItem, u32>,
+    /// The maximum total number of slots that may be occupied by quantitative items. Not (de)serialised so that
+    /// inventories stored before this field was introduced can still be loaded.
+    #[serde(skip, default = "default_max_slots")]
+    pub max_slots: u32
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Inventory {
+            bool_items: HashMap::default(),
+            quantitive_items: HashMap::default(),
+            max_slots: default_max_slots()
+        }
+    }
+}
+
+fn default_max_slots() -> u32 {
+    DEFAULT_MAX_INVENTORY_SLOTS
 }
 
 impl Inventory {
+    /// Create an empty inventory with the given number of slots.
+    pub fn with_max_slots(max_slots: u32) -> Self {
+        Inventory { max_slots, ..Inventory::default() }
+    }
+
     pub fn has(&self, itm: BoolItem) -> bool {
         *self.bool_items.get(&itm).unwrap_or(&false)
     }
@@ -57,9 +120,78 @@ impl Inventory {
         *self.quantitive_items.entry(itm).or_insert(0) += quantity;
     }
 
+    /// The combined weight of all the quantitative items in this inventory.
+    pub fn total_weight(&self) -> u32 {
+        self.quantitive_items
+            .iter()
+            .fold(0, |total: u32, (itm, quantity)| total.saturating_add(itm.weight().saturating_mul(*quantity)))
+    }
+
+    /// Whether or not the given quantity of an item can be added without exceeding [`MAX_INVENTORY_WEIGHT`].
+    pub fn can_carry(&self, itm: QuantitativeItem, quantity: u32) -> bool {
+        self.total_weight().saturating_add(itm.weight().saturating_mul(quantity)) <= MAX_INVENTORY_WEIGHT
+    }
+
+    /// The total number of slots occupied by the quantitative items in this inventory.
+    pub fn slots_used(&self) -> u32 {
+        self.quantitive_items.iter().map(|(itm, quantity)| itm.slots().saturating_mul(*quantity)).sum()
+    }
+
+    /// Whether or not the given quantity of an item can be added without exceeding [`Self::max_slots`].
+    pub fn can_add(&self, itm: QuantitativeItem, quantity: u32) -> bool {
+        self.slots_used().saturating_add(itm.slots().saturating_mul(quantity)) <= self.max_slots
+    }
+
     //pub fn take(&mut self, itm: BoolItem) { ... }
 
     pub fn take_quantity(&mut self, itm: QuantitativeItem, quantity: u32) {
         *self.quantitive_items.entry(itm).or_insert(0) -= quantity;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inventory_capacity() {
+        let mut inventory = Inventory::default();
+        assert_eq!(inventory.max_slots, DEFAULT_MAX_INVENTORY_SLOTS);
+        assert!(inventory.can_add(QuantitativeItem::Bomb, DEFAULT_MAX_INVENTORY_SLOTS));
+        assert!(!inventory.can_add(QuantitativeItem::Bomb, DEFAULT_MAX_INVENTORY_SLOTS + 1));
+        assert!(!inventory.can_add(QuantitativeItem::Bomb, u32::MAX));
+
+        // Potions are light enough that the inventory fills up before the weight limit is reached:
+        inventory.give_quantity(QuantitativeItem::Potion, DEFAULT_MAX_INVENTORY_SLOTS - 1);
+        assert!(QuantitativeItem::Potion.fits_in(&inventory));
+
+        inventory.give_quantity(QuantitativeItem::Potion, 1);
+        assert_eq!(inventory.slots_used(), DEFAULT_MAX_INVENTORY_SLOTS);
+        assert!(!QuantitativeItem::Potion.fits_in(&inventory));
+        assert!(BoolItem::RunningShoes.fits_in(&inventory));
+
+        // The maximum number of slots is not stored along with the inventory's items:
+        let mut small_inventory = Inventory::with_max_slots(5);
+        small_inventory.give(BoolItem::RunningShoes);
+        let loaded: Inventory = serde_json::from_str(&serde_json::to_string(&small_inventory).unwrap()).unwrap();
+        assert!(loaded.has(BoolItem::RunningShoes));
+        assert_eq!(loaded.max_slots, DEFAULT_MAX_INVENTORY_SLOTS);
+    }
+
+    #[test]
+    fn inventory_weight_limit() {
+        let mut inventory = Inventory::with_max_slots(u32::MAX);
+        let bombs_at_limit = MAX_INVENTORY_WEIGHT / QuantitativeItem::Bomb.weight();
+
+        assert!(inventory.can_carry(QuantitativeItem::Bomb, bombs_at_limit));
+        assert!(!inventory.can_carry(QuantitativeItem::Bomb, bombs_at_limit + 1));
+        assert!(!inventory.can_carry(QuantitativeItem::Bomb, u32::MAX));
+
+        inventory.give_quantity(QuantitativeItem::Bomb, bombs_at_limit);
+        assert!(!QuantitativeItem::Bomb.fits_in(&inventory));
+
+        // Weights too large to be represented saturate rather than overflowing:
+        inventory.give_quantity(QuantitativeItem::Potion, u32::MAX);
+        assert_eq!(inventory.total_weight(), u32::MAX);
+    }
+}
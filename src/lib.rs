@@ -1,10 +1,30 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod achievements;
 pub mod gems;
 pub mod id;
 pub mod items;
 pub mod maps;
+pub mod mechanics;
 pub mod messages;
+pub mod quests;
+pub mod stats;
 
 pub use id::Id;
 
+/// Map type used by the collections of the shared crate. This is a standard library hash map when the `std` feature is
+/// enabled and a B-tree map otherwise (as hash maps are unavailable without the standard library).
+#[cfg(feature = "std")]
+pub type HashMap<K, V> = std::collections::HashMap<K, V>;
+#[cfg(not(feature = "std"))]
+pub type HashMap<K, V> = alloc::collections::BTreeMap<K, V>;
+
 /// Version of this client/server build.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Version of the format of the messages exchanged by client and server (see the [`messages`] module). This must be
+/// incremented whenever a change is made that alters how messages are (de)serialised (e.g. adding, removing, or
+/// reordering message variants or fields).
+pub const PROTOCOL_VERSION: u32 = 30;
@@ -1,8 +1,8 @@
-use std::{cmp, fmt, hash::Hash};
+use core::{cmp, fmt, hash::Hash};
 
 use serde::{Deserialize, Serialize};
 
-use super::{CHUNK_HEIGHT, CHUNK_TILE_COUNT, CHUNK_WIDTH};
+use super::{Map, CHUNK_HEIGHT, CHUNK_TILE_COUNT, CHUNK_WIDTH};
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TileCoords {
@@ -32,6 +32,100 @@ impl TileCoords {
             y: (if self.y >= 0 || offset_y == 0 { offset_y } else { CHUNK_HEIGHT + offset_y }) as u8
         }
     }
+
+    /// The sum of the horizontal and vertical distances between these coordinates and the other coordinates. Saturates
+    /// at `u32::MAX`.
+    pub fn manhattan_distance(self, other: TileCoords) -> u32 {
+        self.x.abs_diff(other.x).saturating_add(self.y.abs_diff(other.y))
+    }
+
+    /// The greater of the horizontal and vertical distances between these coordinates and the other coordinates (i.e.
+    /// the number of moves required to travel between them should diagonal moves be allowed).
+    pub fn chebyshev_distance(self, other: TileCoords) -> u32 {
+        cmp::max(self.x.abs_diff(other.x), self.y.abs_diff(other.y))
+    }
+
+    /// Iterate over all tile coordinates with a Chebyshev distance from these coordinates of at most `radius` (i.e. a
+    /// square area, including these coordinates). Tiles are yielded row by row in order of ascending y then x
+    /// coordinate. Nothing is yielded should the radius be negative.
+    pub fn tiles_in_chebyshev_range(self, radius: i32) -> impl Iterator<Item = TileCoords> {
+        (self.y.saturating_sub(radius)..=self.y.saturating_add(radius)).flat_map(move |y| {
+            (self.x.saturating_sub(radius)..=self.x.saturating_add(radius)).map(move |x| TileCoords { x, y })
+        })
+    }
+
+    /// Iterate over all tile coordinates with a Manhattan distance from these coordinates of at most `radius` (i.e. a
+    /// diamond-shaped area, including these coordinates). Tiles are yielded in the same order as
+    /// [`Self::tiles_in_chebyshev_range`].
+    pub fn tiles_in_manhattan_range(self, radius: i32) -> impl Iterator<Item = TileCoords> {
+        (self.y.saturating_sub(radius)..=self.y.saturating_add(radius)).flat_map(move |y| {
+            let remaining = radius - self.y.abs_diff(y) as i32;
+            (self.x.saturating_sub(remaining)..=self.x.saturating_add(remaining)).map(move |x| TileCoords { x, y })
+        })
+    }
+}
+
+impl TileCoords {
+    /// Iterate over the tiles along a straight line (calculated using Bresenham's line algorithm) from these
+    /// coordinates (exclusive) to the target coordinates (inclusive).
+    pub fn line_to(self, target: TileCoords) -> Line {
+        // Differences between coordinates may not fit in an `i32` (e.g. from `i32::MIN` to `i32::MAX`):
+        let delta_x = (i64::from(target.x) - i64::from(self.x)).abs();
+        let delta_y = -(i64::from(target.y) - i64::from(self.y)).abs();
+
+        Line {
+            current: self,
+            target,
+            delta_x,
+            delta_y,
+            step_x: if self.x < target.x { 1 } else { -1 },
+            step_y: if self.y < target.y { 1 } else { -1 },
+            error: delta_x + delta_y
+        }
+    }
+
+    /// Returns `false` should any of the tiles between these coordinates and the target coordinates be blocking (see
+    /// [`super::Tile::is_blocking`]) or in chunks that are not loaded. Whether or not the target tile itself is
+    /// blocking is not considered.
+    pub fn has_line_of_sight(&self, target: TileCoords, map: &impl Map) -> bool {
+        self.line_to(target)
+            .take_while(|coords| *coords != target)
+            .all(|coords| matches!(map.loaded_tile_at(coords), Some(tile) if !tile.is_blocking()))
+    }
+}
+
+/// Iterator over the tile coordinates along a straight line. Created by [`TileCoords::line_to`].
+pub struct Line {
+    current: TileCoords,
+    target: TileCoords,
+    delta_x: i64,
+    delta_y: i64,
+    step_x: i32,
+    step_y: i32,
+    error: i64
+}
+
+impl Iterator for Line {
+    type Item = TileCoords;
+
+    fn next(&mut self) -> Option<TileCoords> {
+        if self.current == self.target {
+            return None;
+        }
+
+        let doubled_error = 2 * self.error;
+
+        if doubled_error >= self.delta_y {
+            self.error += self.delta_y;
+            self.current.x += self.step_x;
+        }
+        if doubled_error <= self.delta_x {
+            self.error += self.delta_x;
+            self.current.y += self.step_y;
+        }
+
+        Some(self.current)
+    }
 }
 
 impl fmt::Display for TileCoords {
@@ -40,19 +134,58 @@ impl fmt::Display for TileCoords {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ChunkCoords {
     pub x: i32,
     pub y: i32
 }
 
+impl ChunkCoords {
+    /// The coordinates of the 4 chunks directly above, below, left, and right of this chunk (in that order). Note
+    /// that y coordinates increase upwards. Coordinates wrap around at the limits of `i32`.
+    pub const fn orthogonal_neighbors(self) -> [ChunkCoords; 4] {
+        let ChunkCoords { x, y } = self;
+
+        [
+            ChunkCoords { x, y: y.wrapping_add(1) },
+            ChunkCoords { x, y: y.wrapping_sub(1) },
+            ChunkCoords { x: x.wrapping_sub(1), y },
+            ChunkCoords { x: x.wrapping_add(1), y }
+        ]
+    }
+
+    /// The coordinates of all 8 chunks surrounding this chunk (including diagonally adjacent chunks) ordered row by
+    /// row from the top-left to the bottom-right. Coordinates wrap around at the limits of `i32`.
+    pub const fn all_neighbors(self) -> [ChunkCoords; 8] {
+        let ChunkCoords { x, y } = self;
+        let (left, right, above, below) = (x.wrapping_sub(1), x.wrapping_add(1), y.wrapping_add(1), y.wrapping_sub(1));
+
+        [
+            ChunkCoords { x: left, y: above },
+            ChunkCoords { x, y: above },
+            ChunkCoords { x: right, y: above },
+            ChunkCoords { x: left, y },
+            ChunkCoords { x: right, y },
+            ChunkCoords { x: left, y: below },
+            ChunkCoords { x, y: below },
+            ChunkCoords { x: right, y: below }
+        ]
+    }
+
+    /// The greater of the horizontal and vertical distances (in chunks) between these chunk coordinates and the other
+    /// chunk coordinates.
+    pub fn chebyshev_distance(self, other: ChunkCoords) -> u32 {
+        cmp::max(self.x.abs_diff(other.x), self.y.abs_diff(other.y))
+    }
+}
+
 impl fmt::Display for ChunkCoords {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "chunk coordinates ({}, {})", self.x, self.y)
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct OffsetCoords {
     pub x: u8,
     pub y: u8
@@ -64,6 +197,13 @@ impl OffsetCoords {
     pub fn calculate_index(&self) -> usize {
         cmp::min((self.y as i32 * CHUNK_WIDTH + self.x as i32) as usize, CHUNK_TILE_COUNT - 1)
     }
+
+    /// Calculate the index within the array used to store tiles in chunks. Unlike [`Self::calculate_index`], `None` is
+    /// returned should either offset coordinate value exceed the bounds of a chunk.
+    pub fn try_calculate_index(&self) -> Option<usize> {
+        let within_bounds = (self.x as i32) < CHUNK_WIDTH && (self.y as i32) < CHUNK_HEIGHT;
+        within_bounds.then(|| (self.y as i32 * CHUNK_WIDTH + self.x as i32) as usize)
+    }
 }
 
 impl fmt::Display for OffsetCoords {
@@ -74,6 +214,8 @@ impl fmt::Display for OffsetCoords {
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::{ChunkCoords, OffsetCoords, TileCoords};
 
     const TEST_DATA: &[(TileCoords, ChunkCoords, OffsetCoords)] = &[
@@ -99,4 +241,195 @@ mod tests {
             assert_eq!(tile.as_chunk_offset_coords(), *offset);
         }
     }
+
+    #[test]
+    fn tile_coords_distances() {
+        let (a, b) = (TileCoords { x: 2, y: -3 }, TileCoords { x: -1, y: 4 });
+        assert_eq!(a.manhattan_distance(b), 10);
+        assert_eq!(a.chebyshev_distance(b), 7);
+        assert_eq!(a.manhattan_distance(a), 0);
+
+        let (min, max) = (TileCoords { x: i32::MIN, y: i32::MIN }, TileCoords { x: i32::MAX, y: i32::MAX });
+        assert_eq!(min.manhattan_distance(max), u32::MAX);
+        assert_eq!(min.chebyshev_distance(max), u32::MAX);
+    }
+
+    #[test]
+    fn tiles_in_range_counts() {
+        let centre = TileCoords { x: -7, y: 3 };
+
+        for radius in 0..6 {
+            let square: Vec<TileCoords> = centre.tiles_in_chebyshev_range(radius).collect();
+            assert_eq!(square.len() as i32, (2 * radius + 1).pow(2));
+            assert!(square.iter().all(|coords| centre.chebyshev_distance(*coords) <= radius as u32));
+
+            let diamond: Vec<TileCoords> = centre.tiles_in_manhattan_range(radius).collect();
+            assert_eq!(diamond.len() as i32, 2 * radius * radius + 2 * radius + 1);
+            assert!(diamond.iter().all(|coords| centre.manhattan_distance(*coords) <= radius as u32));
+
+            assert!(square.contains(&centre) && diamond.contains(&centre));
+        }
+
+        assert_eq!(centre.tiles_in_chebyshev_range(-1).count(), 0);
+        assert_eq!(centre.tiles_in_manhattan_range(-1).count(), 0);
+    }
+
+    #[test]
+    fn tiles_in_range_order() {
+        let c = |x, y| TileCoords { x, y };
+
+        let square: Vec<TileCoords> = c(0, 0).tiles_in_chebyshev_range(1).collect();
+        assert_eq!(square, vec![c(-1, -1), c(0, -1), c(1, -1), c(-1, 0), c(0, 0), c(1, 0), c(-1, 1), c(0, 1), c(1, 1)]);
+
+        let diamond: Vec<TileCoords> = c(0, 0).tiles_in_manhattan_range(1).collect();
+        assert_eq!(diamond, vec![c(0, -1), c(-1, 0), c(0, 0), c(1, 0), c(0, 1)]);
+
+        // Tiles beyond the limits of `i32` are not yielded:
+        assert_eq!(c(i32::MAX, i32::MIN).tiles_in_chebyshev_range(1).count(), 4);
+    }
+
+    proptest! {
+        #[test]
+        fn tile_coords_distances_symmetric(ax: i32, ay: i32, bx: i32, by: i32) {
+            let (a, b) = (TileCoords { x: ax, y: ay }, TileCoords { x: bx, y: by });
+            prop_assert_eq!(a.manhattan_distance(b), b.manhattan_distance(a));
+            prop_assert_eq!(a.chebyshev_distance(b), b.chebyshev_distance(a));
+        }
+
+        #[test]
+        fn manhattan_distance_triangle_inequality(ax: i32, ay: i32, bx: i32, by: i32, cx: i32, cy: i32) {
+            let (a, b, c) = (TileCoords { x: ax, y: ay }, TileCoords { x: bx, y: by }, TileCoords { x: cx, y: cy });
+            prop_assert!(a.manhattan_distance(c) <= a.manhattan_distance(b).saturating_add(b.manhattan_distance(c)));
+        }
+
+        #[test]
+        fn chebyshev_distance_at_most_manhattan(ax: i32, ay: i32, bx: i32, by: i32) {
+            let (a, b) = (TileCoords { x: ax, y: ay }, TileCoords { x: bx, y: by });
+            prop_assert!(a.chebyshev_distance(b) <= a.manhattan_distance(b));
+        }
+    }
+
+    #[test]
+    fn chunk_coords_orthogonal_neighbors() {
+        let c = |x, y| ChunkCoords { x, y };
+
+        assert_eq!(c(0, 0).orthogonal_neighbors(), [c(0, 1), c(0, -1), c(-1, 0), c(1, 0)]);
+        assert_eq!(c(5, 7).orthogonal_neighbors(), [c(5, 8), c(5, 6), c(4, 7), c(6, 7)]);
+        assert_eq!(c(-3, -9).orthogonal_neighbors(), [c(-3, -8), c(-3, -10), c(-4, -9), c(-2, -9)]);
+
+        assert_eq!(
+            c(i32::MAX, i32::MIN).orthogonal_neighbors(),
+            [c(i32::MAX, i32::MIN + 1), c(i32::MAX, i32::MAX), c(i32::MAX - 1, i32::MIN), c(i32::MIN, i32::MIN)]
+        );
+
+        const NEIGHBORS: [ChunkCoords; 4] = ChunkCoords { x: 1, y: 1 }.orthogonal_neighbors();
+        assert_eq!(NEIGHBORS[0], c(1, 2));
+    }
+
+    #[test]
+    fn chunk_coords_all_neighbors() {
+        let c = |x, y| ChunkCoords { x, y };
+
+        assert_eq!(
+            c(0, 0).all_neighbors(),
+            [c(-1, 1), c(0, 1), c(1, 1), c(-1, 0), c(1, 0), c(-1, -1), c(0, -1), c(1, -1)]
+        );
+        assert_eq!(
+            c(-4, 2).all_neighbors(),
+            [c(-5, 3), c(-4, 3), c(-3, 3), c(-5, 2), c(-3, 2), c(-5, 1), c(-4, 1), c(-3, 1)]
+        );
+
+        let extreme = c(i32::MIN, i32::MAX).all_neighbors();
+        assert_eq!(extreme[0], c(i32::MAX, i32::MIN));
+        assert_eq!(extreme[7], c(i32::MIN + 1, i32::MAX - 1));
+
+        // Orthogonal neighbours are a subset of all neighbours & the centre is never included:
+        for centre in &[c(0, 0), c(12, -30), c(i32::MAX, i32::MAX)] {
+            let all = centre.all_neighbors();
+            assert!(centre.orthogonal_neighbors().iter().all(|coords| all.contains(coords)));
+            assert!(!all.contains(centre));
+        }
+    }
+
+    #[test]
+    fn chunk_coords_chebyshev_distance() {
+        let c = |x, y| ChunkCoords { x, y };
+
+        assert_eq!(c(0, 0).chebyshev_distance(c(0, 0)), 0);
+        assert_eq!(c(2, -3).chebyshev_distance(c(-1, 4)), 7);
+        assert!(c(5, 5).all_neighbors().iter().all(|neighbour| neighbour.chebyshev_distance(c(5, 5)) == 1));
+        assert_eq!(c(i32::MIN, 0).chebyshev_distance(c(i32::MAX, 0)), u32::MAX);
+    }
+
+    #[test]
+    fn coords_json_named_fields() {
+        assert_eq!(serde_json::to_string(&TileCoords { x: 1, y: 2 }).unwrap(), r#"{"x":1,"y":2}"#);
+        assert_eq!(serde_json::to_string(&ChunkCoords { x: -3, y: 4 }).unwrap(), r#"{"x":-3,"y":4}"#);
+
+        assert_eq!(serde_json::from_str::<TileCoords>(r#"{"x":1,"y":2}"#).unwrap(), TileCoords { x: 1, y: 2 });
+    }
+
+    #[test]
+    fn offset_coords_json_named_fields() {
+        assert_eq!(serde_json::to_string(&OffsetCoords { x: 5, y: 6 }).unwrap(), r#"{"x":5,"y":6}"#);
+        assert_eq!(serde_json::from_str::<OffsetCoords>(r#"{"x":5,"y":6}"#).unwrap(), OffsetCoords { x: 5, y: 6 });
+    }
+
+    #[test]
+    fn line_to_known_tiles() {
+        let origin = TileCoords { x: 0, y: 0 };
+
+        assert_eq!(origin.line_to(origin).count(), 0);
+
+        let line: Vec<TileCoords> = origin.line_to(TileCoords { x: 3, y: 0 }).collect();
+        assert_eq!(line, vec![TileCoords { x: 1, y: 0 }, TileCoords { x: 2, y: 0 }, TileCoords { x: 3, y: 0 }]);
+
+        let line: Vec<TileCoords> = origin.line_to(TileCoords { x: -2, y: -2 }).collect();
+        assert_eq!(line, vec![TileCoords { x: -1, y: -1 }, TileCoords { x: -2, y: -2 }]);
+
+        let line: Vec<TileCoords> = TileCoords { x: 1, y: 1 }.line_to(TileCoords { x: 5, y: 3 }).collect();
+        assert_eq!(
+            line,
+            vec![
+                TileCoords { x: 2, y: 2 },
+                TileCoords { x: 3, y: 2 },
+                TileCoords { x: 4, y: 3 },
+                TileCoords { x: 5, y: 3 }
+            ]
+        );
+
+        // Lines spanning the full range of coordinates do not overflow:
+        let line: Vec<TileCoords> =
+            TileCoords { x: i32::MIN, y: i32::MAX }.line_to(TileCoords { x: i32::MAX, y: i32::MIN }).take(2).collect();
+        assert_eq!(
+            line,
+            vec![TileCoords { x: i32::MIN + 1, y: i32::MAX - 1 }, TileCoords { x: i32::MIN + 2, y: i32::MAX - 2 }]
+        );
+    }
+
+    #[test]
+    fn line_to_properties() {
+        let start = TileCoords { x: 2, y: -3 };
+
+        for target_x in -8..8 {
+            for target_y in -8..8 {
+                let target = TileCoords { x: target_x, y: target_y };
+                let line: Vec<TileCoords> = start.line_to(target).collect();
+
+                // One tile per step along the longest axis:
+                let expected_len = (target.x - start.x).abs().max((target.y - start.y).abs());
+                assert_eq!(line.len(), expected_len as usize);
+
+                // Ends at the target & each tile is adjacent (including diagonally) to the previous tile:
+                if expected_len > 0 {
+                    assert_eq!(*line.last().unwrap(), target);
+                }
+                let mut previous = start;
+                for coords in line {
+                    assert!((coords.x - previous.x).abs() <= 1 && (coords.y - previous.y).abs() <= 1);
+                    previous = coords;
+                }
+            }
+        }
+    }
 }
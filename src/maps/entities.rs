@@ -1,18 +1,28 @@
-use std::{collections::HashMap, fmt};
+use alloc::string::String;
+use core::fmt;
 
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use strum::EnumIter;
 
 use super::{Tile, TileCoords};
 use crate::{
     gems,
     items::{self, BoolItem},
-    Id
+    mechanics, stats, HashMap, Id
 };
 
 /// Type alias for a hash map of entity IDs to entities.
 pub type Entities = HashMap<Id, Entity>;
 
+/// The maximum health of entities unless specified otherwise.
+pub const DEFAULT_MAX_HEALTH: u32 = 100;
+
+/// The minimum length (in characters) of a player's name.
+pub const MIN_PLAYER_NAME_LENGTH: usize = 2;
+/// The maximum length (in characters) of a player's name.
+pub const MAX_PLAYER_NAME_LENGTH: usize = 24;
+
 const STANDARD_MOVEMENT_TIME: f32 = 0.13;
 const RUNNING_MOVEMENT_TIME: f32 = STANDARD_MOVEMENT_TIME * 0.75;
 
@@ -22,8 +32,11 @@ const GRASSY_TILE_MOVEMENT_TIME_MODIFIER: f32 = 0.8;
 // TODO: 'Player' would probably be better name than `Entity`.
 /// An 'entity' in the context of the GemGame codebase refers specifically to the player characters that exist within
 /// the game world.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct Entity {
+    /// The name chosen by the player controlling this entity (see [`validate_player_name`]). Empty should the player
+    /// not have chosen a name since names were introduced.
+    pub name: String,
     /// The position of the entity within its current map.
     pub pos: TileCoords,
     /// Direction that this entity is facing/travelling towards.
@@ -43,11 +56,38 @@ pub struct Entity {
     /// Stores items that this entity has.
     pub item_inventory: items::Inventory,
     /// Number of bombs the entity has placed (excluding detonated bombs).
-    pub bombs_placed_count: i32
+    pub bombs_placed_count: i32,
+    /// Current health of the entity, which never exceeds `max_health`.
+    pub health: u32,
+    pub max_health: u32,
+    /// Running totals of things done by the player controlling this entity (maintained by the server).
+    pub stats: stats::PlayerStats
+}
+
+impl Default for Entity {
+    fn default() -> Self {
+        Entity {
+            name: String::new(),
+            pos: TileCoords::default(),
+            direction: Direction::default(),
+            facial_expression: FacialExpression::default(),
+            hair_style: HairStyle::default(),
+            clothing_colour: ClothingColour::default(),
+            skin_colour: SkinColour::default(),
+            hair_colour: HairColour::default(),
+            gem_collection: gems::Collection::default(),
+            item_inventory: items::Inventory::default(),
+            bombs_placed_count: 0,
+            health: DEFAULT_MAX_HEALTH,
+            max_health: DEFAULT_MAX_HEALTH,
+            stats: stats::PlayerStats::default()
+        }
+    }
 }
 
 impl Entity {
-    /// The amount of time in seconds taken for the entity to move to an adjacent tile.
+    /// The amount of time in seconds taken for the entity to move to an adjacent tile. Takes into account the
+    /// destination tile type, whether the entity has running shoes, and the weight of the entity's inventory.
     pub fn movement_time(&self, tile_at_destination: Tile) -> f32 {
         let base_time = if self.item_inventory.has(BoolItem::RunningShoes) {
             RUNNING_MOVEMENT_TIME
@@ -56,15 +96,44 @@ impl Entity {
             STANDARD_MOVEMENT_TIME
         };
 
-        if tile_at_destination.is_smashable() {
-            base_time * SMASHABLE_TILE_MOVEMENT_TIME_MODIFIER
-        }
-        else if tile_at_destination.is_grassy() {
-            base_time * GRASSY_TILE_MOVEMENT_TIME_MODIFIER
+        let tile_time = {
+            if tile_at_destination.is_smashable() {
+                base_time * SMASHABLE_TILE_MOVEMENT_TIME_MODIFIER
+            }
+            else if tile_at_destination.is_grassy() {
+                base_time * GRASSY_TILE_MOVEMENT_TIME_MODIFIER
+            }
+            else {
+                base_time
+            }
+        };
+
+        // Entities carrying heavy inventories move more slowly:
+        tile_time / mechanics::movement_speed_multiplier(&self.item_inventory)
+    }
+
+    /// The total weight of the items in this entity's inventory.
+    pub fn inventory_weight(&self) -> u32 {
+        self.item_inventory.total_weight()
+    }
+
+    /// Use one of the given item from this entity's inventory, applying its effect. Returns whether the item was used,
+    /// which is not the case should the entity not have the item, should the item have no effect (e.g. a potion used
+    /// at full health), or should the item not be usable in this way (bombs are instead placed).
+    pub fn use_item(&mut self, item: items::QuantitativeItem) -> bool {
+        if self.item_inventory.has_how_many(item) == 0 {
+            return false;
         }
-        else {
-            base_time
+
+        match item {
+            items::QuantitativeItem::Potion if self.health < self.max_health => {
+                self.health = self.health.saturating_add(items::POTION_HEALTH_RECOVERY).min(self.max_health);
+            }
+            _ => return false
         }
+
+        self.item_inventory.take_quantity(item, 1);
+        true
     }
 
     /// Modify entity position without performing any sort of checks.
@@ -74,6 +143,131 @@ impl Entity {
     }
 }
 
+/// Check that the given name may be used by a player: it must be between [`MIN_PLAYER_NAME_LENGTH`] and
+/// [`MAX_PLAYER_NAME_LENGTH`] characters long and consist only of ASCII letters, digits, and underscores. Whether the
+/// name is already in use by another player is checked by the server.
+pub fn validate_player_name(name: &str) -> Result<(), InvalidNameError> {
+    if let Some(c) = name.chars().find(|c| !c.is_ascii_alphanumeric() && *c != '_') {
+        return Err(InvalidNameError::InvalidCharacter(c));
+    }
+
+    match name.len() {
+        len if len < MIN_PLAYER_NAME_LENGTH => Err(InvalidNameError::TooShort),
+        len if len > MAX_PLAYER_NAME_LENGTH => Err(InvalidNameError::TooLong),
+        _ => Ok(())
+    }
+}
+
+/// The reason that a name does not satisfy [`validate_player_name`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidNameError {
+    TooShort,
+    TooLong,
+    InvalidCharacter(char)
+}
+
+impl fmt::Display for InvalidNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidNameError::TooShort => write!(f, "name must be at least {} characters long", MIN_PLAYER_NAME_LENGTH),
+            InvalidNameError::TooLong => write!(f, "name must be at most {} characters long", MAX_PLAYER_NAME_LENGTH),
+            InvalidNameError::InvalidCharacter(c) => {
+                write!(f, "name may only contain letters, digits & underscores (found '{}')", c)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidNameError {}
+
+/// Allows for the construction of an [`Entity`] with only some fields specified. Fields that are not set are given
+/// the same values as [`Entity::default`].
+#[derive(Default)]
+pub struct EntityBuilder {
+    entity: Entity
+}
+
+impl EntityBuilder {
+    pub fn new() -> Self {
+        EntityBuilder::default()
+    }
+
+    pub fn name(mut self, name: String) -> Self {
+        self.entity.name = name;
+        self
+    }
+
+    pub fn pos(mut self, pos: TileCoords) -> Self {
+        self.entity.pos = pos;
+        self
+    }
+
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.entity.direction = direction;
+        self
+    }
+
+    pub fn facial_expression(mut self, facial_expression: FacialExpression) -> Self {
+        self.entity.facial_expression = facial_expression;
+        self
+    }
+
+    pub fn hair_style(mut self, hair_style: HairStyle) -> Self {
+        self.entity.hair_style = hair_style;
+        self
+    }
+
+    pub fn clothing_colour(mut self, clothing_colour: ClothingColour) -> Self {
+        self.entity.clothing_colour = clothing_colour;
+        self
+    }
+
+    pub fn skin_colour(mut self, skin_colour: SkinColour) -> Self {
+        self.entity.skin_colour = skin_colour;
+        self
+    }
+
+    pub fn hair_colour(mut self, hair_colour: HairColour) -> Self {
+        self.entity.hair_colour = hair_colour;
+        self
+    }
+
+    pub fn gem_collection(mut self, gem_collection: gems::Collection) -> Self {
+        self.entity.gem_collection = gem_collection;
+        self
+    }
+
+    pub fn item_inventory(mut self, item_inventory: items::Inventory) -> Self {
+        self.entity.item_inventory = item_inventory;
+        self
+    }
+
+    pub fn bombs_placed_count(mut self, bombs_placed_count: i32) -> Self {
+        self.entity.bombs_placed_count = bombs_placed_count;
+        self
+    }
+
+    pub fn health(mut self, health: u32) -> Self {
+        self.entity.health = health;
+        self
+    }
+
+    pub fn max_health(mut self, max_health: u32) -> Self {
+        self.entity.max_health = max_health;
+        self
+    }
+
+    pub fn stats(mut self, stats: stats::PlayerStats) -> Self {
+        self.entity.stats = stats;
+        self
+    }
+
+    pub fn build(self) -> Entity {
+        self.entity
+    }
+}
+
 impl fmt::Display for Entity {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -89,9 +283,63 @@ impl fmt::Display for Entity {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// The health, damage, and aggro radius given to newly spawned monsters.
+pub const MONSTER_HEALTH: u32 = 30;
+pub const MONSTER_DAMAGE: u32 = 2;
+pub const MONSTER_AGGRO_RADIUS: u32 = 6;
+
+/// A hostile non-player entity controlled by the server. Monsters pursue players within their aggro radius and damage
+/// any player whose tile they share.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Monster {
+    pub pos: TileCoords,
+    pub direction: Direction,
+    pub health: u32,
+    /// The amount of health taken from a player each server tick that this monster shares a tile with them.
+    pub damage: u32,
+    /// Players further than this from the monster (Chebyshev distance) are ignored by it.
+    pub aggro_radius: u32
+}
+
+impl Monster {
+    pub fn new(pos: TileCoords) -> Self {
+        Monster {
+            pos,
+            direction: Direction::default(),
+            health: MONSTER_HEALTH,
+            damage: MONSTER_DAMAGE,
+            aggro_radius: MONSTER_AGGRO_RADIUS
+        }
+    }
+
+    /// Greedily pick the direction in which this monster should move so as to approach the given target position,
+    /// being that of the adjacent tile with the smallest Chebyshev distance to the target (ties are broken using
+    /// Manhattan distance so that diagonal approaches still make progress). Only adjacent tiles for which `is_free`
+    /// returns true are considered. Returns `None` should no such tile be closer to the target than the monster's
+    /// current position.
+    pub fn step_towards(&self, target: TileCoords, is_free: impl Fn(TileCoords) -> bool) -> Option<Direction> {
+        let distance = |coords: TileCoords| (coords.chebyshev_distance(target), coords.manhattan_distance(target));
+
+        [Direction::Up, Direction::Down, Direction::Left, Direction::Right]
+            .iter()
+            .map(|direction| (*direction, direction.apply(self.pos)))
+            .filter(|(_, coords)| distance(*coords) < distance(self.pos) && is_free(*coords))
+            .min_by_key(|(_, coords)| distance(*coords))
+            .map(|(direction, _)| direction)
+    }
+}
+
+impl fmt::Display for Monster {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "monster at {} facing {} with {} health", self.pos, self.direction, self.health)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "std", derive(EnumIter))]
 pub enum Direction {
     Up,
+    #[default]
     Down,
     Left,
     Right
@@ -120,8 +368,10 @@ impl fmt::Display for Direction {
     }
 }
 
-#[derive(Serialize, Deserialize, EnumIter, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "std", derive(EnumIter))]
 pub enum HairStyle {
+    #[default]
     Quiff,
     Mohawk,
     Fringe
@@ -137,9 +387,11 @@ impl fmt::Display for HairStyle {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "std", derive(EnumIter))]
 pub enum FacialExpression {
     /// Neutral 😐 facial expression.
+    #[default]
     Neutral,
     /// Angry 😠 facial expression (both eyebrows slanted inward).
     Angry,
@@ -160,30 +412,173 @@ impl fmt::Display for FacialExpression {
     }
 }
 
-#[derive(Serialize, Deserialize, EnumIter, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "std", derive(EnumIter))]
 pub enum ClothingColour {
     White,
+    #[default]
     Grey,
     Red,
     Green,
     Blue
 }
 
-#[derive(Serialize, Deserialize, EnumIter, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// Discriminant values are stable (new variants must only ever be added to the end) as they are used when storing
+/// entities in the database.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "std", derive(EnumIter))]
+#[repr(i16)]
 pub enum SkinColour {
-    Black,
-    Brown,
-    Pale,
-    White
+    #[default]
+    Black = 0,
+    Brown = 1,
+    Pale = 2,
+    White = 3,
+    Tan = 4,
+    Olive = 5
 }
 
-#[derive(Serialize, Deserialize, EnumIter, Debug, PartialEq, Eq, Clone, Copy, Hash)]
+/// Discriminant values are stable (new variants must only ever be added to the end) as they are used when storing
+/// entities in the database.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "std", derive(EnumIter))]
+#[repr(i16)]
 pub enum HairColour {
-    Black,
-    Brown,
-    Blonde,
-    White,
-    Red,
-    Green,
-    Blue
+    #[default]
+    Black = 0,
+    Brown = 1,
+    Blonde = 2,
+    White = 3,
+    Red = 4,
+    Green = 5,
+    Blue = 6,
+    Grey = 7
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_name_validation() {
+        assert_eq!(validate_player_name("Gem_Hunter99"), Ok(()));
+        assert_eq!(validate_player_name("ab"), Ok(()));
+        assert_eq!(validate_player_name("a"), Err(InvalidNameError::TooShort));
+        assert_eq!(validate_player_name(&"a".repeat(MAX_PLAYER_NAME_LENGTH)), Ok(()));
+        assert_eq!(validate_player_name(&"a".repeat(MAX_PLAYER_NAME_LENGTH + 1)), Err(InvalidNameError::TooLong));
+        assert_eq!(validate_player_name("gem hunter"), Err(InvalidNameError::InvalidCharacter(' ')));
+        assert_eq!(validate_player_name("émile"), Err(InvalidNameError::InvalidCharacter('é')));
+        assert_eq!(
+            InvalidNameError::InvalidCharacter('!').to_string(),
+            "name may only contain letters, digits & underscores (found '!')"
+        );
+    }
+
+    #[test]
+    fn potion_restores_health() {
+        let mut entity = EntityBuilder::new().health(DEFAULT_MAX_HEALTH - 30).build();
+        assert!(!entity.use_item(items::QuantitativeItem::Potion));
+
+        entity.item_inventory.give_quantity(items::QuantitativeItem::Potion, 3);
+        entity.item_inventory.give_quantity(items::QuantitativeItem::Bomb, 1);
+
+        assert!(entity.use_item(items::QuantitativeItem::Potion));
+        assert_eq!(entity.health, DEFAULT_MAX_HEALTH - 30 + items::POTION_HEALTH_RECOVERY);
+
+        // Health is restored no further than the entity's maximum health:
+        assert!(entity.use_item(items::QuantitativeItem::Potion));
+        assert_eq!(entity.health, DEFAULT_MAX_HEALTH);
+        assert!(!entity.use_item(items::QuantitativeItem::Potion));
+        assert_eq!(entity.item_inventory.has_how_many(items::QuantitativeItem::Potion), 1);
+
+        // Bombs must be placed rather than used:
+        assert!(!entity.use_item(items::QuantitativeItem::Bomb));
+        assert_eq!(entity.item_inventory.has_how_many(items::QuantitativeItem::Bomb), 1);
+    }
+
+    #[test]
+    fn monster_steps_towards_target() {
+        let monster = Monster::new(TileCoords { x: 0, y: 0 });
+        let anywhere = |_| true;
+
+        assert_eq!(monster.step_towards(TileCoords { x: 4, y: 1 }, anywhere), Some(Direction::Right));
+        assert_eq!(monster.step_towards(TileCoords { x: -1, y: -5 }, anywhere), Some(Direction::Down));
+        assert_eq!(monster.step_towards(TileCoords { x: 0, y: 0 }, anywhere), None);
+
+        // A diagonal approach does not reduce Chebyshev distance with the first step but should still make progress:
+        let mut monster = Monster::new(TileCoords { x: 0, y: 0 });
+        let target = TileCoords { x: 3, y: 3 };
+        for _ in 0..6 {
+            let direction = monster.step_towards(target, anywhere).unwrap();
+            monster.pos = direction.apply(monster.pos);
+        }
+        assert_eq!(monster.pos, target);
+
+        // Blocked tiles are avoided and the monster stays put should no free tile be closer to the target:
+        let monster = Monster::new(TileCoords { x: 0, y: 0 });
+        let target = TileCoords { x: 5, y: 0 };
+        assert_eq!(monster.step_towards(target, |coords| coords.x <= 0), None);
+        assert_eq!(monster.step_towards(TileCoords { x: 5, y: 2 }, |coords| coords.x <= 0), Some(Direction::Up));
+    }
+
+    #[test]
+    fn cloned_entity_is_equal() {
+        let mut entity = EntityBuilder::new()
+            .pos(TileCoords { x: 4, y: -2 })
+            .direction(Direction::Left)
+            .bombs_placed_count(1)
+            .build();
+        entity.gem_collection.increase_quantity(gems::Gem::Ruby, 3);
+        entity.item_inventory.give(BoolItem::RunningShoes);
+
+        let snapshot = entity.clone();
+        assert_eq!(snapshot, entity);
+
+        entity.move_towards_unchecked(Direction::Up);
+        assert_ne!(snapshot, entity);
+    }
+
+    #[test]
+    fn entity_builder_defaults() {
+        assert_eq!(EntityBuilder::new().build(), Entity::default());
+
+        let entity = EntityBuilder::new()
+            .pos(TileCoords { x: -3, y: 8 })
+            .hair_style(HairStyle::Mohawk)
+            .clothing_colour(ClothingColour::Blue)
+            .build();
+
+        assert_eq!(entity.pos, TileCoords { x: -3, y: 8 });
+        assert_eq!(entity.hair_style, HairStyle::Mohawk);
+        assert_eq!(entity.clothing_colour, ClothingColour::Blue);
+
+        // Unspecified fields should have default values:
+        assert_eq!(entity.direction, Direction::Down);
+        assert_eq!(entity.facial_expression, FacialExpression::Neutral);
+        assert_eq!(entity.skin_colour, SkinColour::Black);
+        assert_eq!(entity.hair_colour, HairColour::Black);
+        assert_eq!(entity.gem_collection, gems::Collection::default());
+        assert_eq!(entity.item_inventory, items::Inventory::default());
+        assert_eq!(entity.bombs_placed_count, 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn colour_variants_stable_and_serialisable() {
+        use strum::IntoEnumIterator;
+
+        // Iteration order (used to encode variants in the database) must match the stable discriminant values:
+        for (i, colour) in SkinColour::iter().enumerate() {
+            assert_eq!(colour as i16, i as i16);
+
+            let json = serde_json::to_string(&colour).unwrap();
+            assert_eq!(serde_json::from_str::<SkinColour>(&json).unwrap(), colour);
+        }
+        for (i, colour) in HairColour::iter().enumerate() {
+            assert_eq!(colour as i16, i as i16);
+
+            let json = serde_json::to_string(&colour).unwrap();
+            assert_eq!(serde_json::from_str::<HairColour>(&json).unwrap(), colour);
+        }
+    }
 }
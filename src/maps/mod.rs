@@ -1,16 +1,19 @@
 pub mod coords;
 pub mod entities;
 
-use std::collections::HashMap;
+use alloc::{boxed::Box, collections::VecDeque, string::String, vec::Vec};
+use core::fmt;
 
 pub use coords::*;
-use entities::Entity;
+use entities::{Direction, Entity};
 use serde::{Deserialize, Serialize};
 use serde_big_array::big_array;
+#[cfg(feature = "std")]
+use strum::EnumIter;
 
 use crate::{
     gems::{self, Gem},
-    Id
+    mechanics, HashMap, Id
 };
 
 // TODO: Remove this workaround when const generics are properly stablised.
@@ -25,11 +28,41 @@ pub const CHUNK_HEIGHT: i32 = 16;
 /// Total number of tiles contained in a chunk.
 pub const CHUNK_TILE_COUNT: usize = CHUNK_WIDTH as usize * CHUNK_HEIGHT as usize;
 
+/// Chunks within this many chunks (Chebyshev distance) of the chunk containing a player's entity are provided to that
+/// player's client by the server. Chunks further away are unloaded.
+pub const VIEW_RADIUS: i32 = 2;
+
+/// Tiles within this many tiles (Chebyshev distance) of a player's entity are visible to that player and are marked as
+/// seen (see [`Chunk::is_tile_seen`]).
+pub const SIGHT_RADIUS: i32 = 8;
+
+/// The maximum number of characters that the text of a sign may contain (see [`Chunk::place_sign`]).
+pub const MAX_SIGN_TEXT_LENGTH: usize = 256;
+
+/// The number of bytes required to store a seen flag for each tile in a chunk.
+const SEEN_FLAGS_LEN: usize = CHUNK_TILE_COUNT / 8;
+
 pub trait Map {
-    /// Fetch the tile at the given tile coordinates assuming it is in a chunk that is already loaded.
+    /// Query the tile at the given tile coordinates. Distinguishes between the tile's chunk simply not being loaded yet
+    /// (an expected state while waiting on chunk data) and the chunk offset being out of bounds (which indicates a
+    /// bug).
+    fn query_tile_at(&self, coords: TileCoords) -> TileQuery {
+        match self.loaded_chunk_at(coords.as_chunk_coords()) {
+            Some(chunk) => chunk
+                .try_tile_at_offset(coords.as_chunk_offset_coords())
+                .map_or(TileQuery::OutOfBounds, TileQuery::Loaded),
+            None => TileQuery::ChunkNotLoaded
+        }
+    }
+
+    /// Fetch the tile at the given tile coordinates assuming it is in a chunk that is already loaded. Should the chunk
+    /// be loaded but the offset within that chunk be out of bounds, the default tile is returned.
     fn loaded_tile_at(&self, coords: TileCoords) -> Option<Tile> {
-        let chunk = self.loaded_chunk_at(coords.as_chunk_coords())?;
-        Some(chunk.tile_at_offset(coords.as_chunk_offset_coords()))
+        match self.query_tile_at(coords) {
+            TileQuery::Loaded(tile) => Some(tile),
+            TileQuery::OutOfBounds => Some(Tile::default()),
+            TileQuery::ChunkNotLoaded => None
+        }
     }
 
     /// Change the tile at the specified tile coordinates assuming it is in a chunk that is already loaded.
@@ -54,18 +87,130 @@ pub trait Map {
         }
     }
 
+    /// Remove a single bomb placed by the entity with the given ID at the specified tile coordinates. Returns `false`
+    /// should no such bomb exist within the loaded chunks.
+    fn remove_bomb_at(&mut self, pos: TileCoords, placed_by_id: Id) -> bool {
+        match self.loaded_chunk_at_mut(pos.as_chunk_coords()) {
+            Some(chunk) => chunk.remove_bomb_placed_by_at(placed_by_id, pos),
+            None => false
+        }
+    }
+
+    /// Smash all smashable tiles (see [`Tile::is_smashable`]) within the blast radius of bombs detonated at the given
+    /// positions (see [`mechanics::BOMB_BLAST_RADIUS`]). Tiles in unloaded chunks are unaffected. Returns the
+    /// positions of the smashed tiles along with the tiles as they were before being smashed.
+    fn smash_tiles_around_bombs(&mut self, bomb_positions: &[TileCoords]) -> Vec<(TileCoords, Tile)> {
+        let mut smashed = Vec::new();
+
+        for bomb_pos in bomb_positions {
+            for coords in bomb_pos.tiles_in_chebyshev_range(mechanics::BOMB_BLAST_RADIUS) {
+                if let TileQuery::Loaded(tile) = self.query_tile_at(coords) {
+                    if tile.is_smashable() {
+                        self.set_loaded_tile_at(coords, Tile::RockSmashed);
+                        smashed.push((coords, tile));
+                    }
+                }
+            }
+        }
+
+        smashed
+    }
+
+    /// Returns `true` should the tile at the given coordinates be in a loaded chunk at a valid offset within that
+    /// chunk.
     fn is_tile_loaded(&self, coords: TileCoords) -> bool {
-        self.loaded_chunk_at(coords.as_chunk_coords()).is_some()
+        matches!(self.query_tile_at(coords), TileQuery::Loaded(_))
     }
 
     fn is_chunk_loaded(&self, coords: ChunkCoords) -> bool {
         self.loaded_chunk_at(coords).is_some()
     }
 
+    /// Returns `true` should the tile at the given coordinates be in a loaded chunk and have been seen by a player.
+    fn is_tile_seen(&self, coords: TileCoords) -> bool {
+        self.loaded_chunk_at(coords.as_chunk_coords())
+            .is_some_and(|chunk| chunk.is_tile_seen(coords.as_chunk_offset_coords()))
+    }
+
+    /// Returns the text of the sign at the given tile coordinates should there be a sign there in a loaded chunk.
+    fn sign_text_at(&self, coords: TileCoords) -> Option<&str> {
+        self.loaded_chunk_at(coords.as_chunk_coords())?.sign_text(coords.as_chunk_offset_coords())
+    }
+
+    /// Returns the destination of the portal at the given tile coordinates should there be a portal there in a loaded
+    /// chunk.
+    fn portal_destination_at(&self, coords: TileCoords) -> Option<TileCoords> {
+        self.loaded_chunk_at(coords.as_chunk_coords())?.portal_destination(coords.as_chunk_offset_coords())
+    }
+
+    /// Returns the gems dropped at the given tile coordinates should there be a gem drop there in a loaded chunk.
+    fn gem_drop_at(&self, coords: TileCoords) -> Option<&GemDrop> {
+        self.loaded_chunk_at(coords.as_chunk_coords())?.gem_drop(coords.as_chunk_offset_coords())
+    }
+
+    /// Drop the given gems at the specified tile coordinates assuming it is in a chunk that is already loaded (see
+    /// [`Chunk::drop_gems`]).
+    fn drop_gems_at(&mut self, coords: TileCoords, gems: gems::Collection) -> bool {
+        if let Some(chunk) = self.loaded_chunk_at_mut(coords.as_chunk_coords()) {
+            chunk.drop_gems(coords.as_chunk_offset_coords(), gems);
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    /// Pick up the gems dropped at the given tile coordinates should there be a gem drop there in a loaded chunk (see
+    /// [`Chunk::take_gem_drop`]).
+    fn take_gem_drop_at(&mut self, coords: TileCoords) -> Option<GemDrop> {
+        self.loaded_chunk_at_mut(coords.as_chunk_coords())?.take_gem_drop(coords.as_chunk_offset_coords())
+    }
+
+    /// Mark all tiles within [`SIGHT_RADIUS`] of the given position as seen. Tiles in unloaded chunks are unaffected.
+    fn mark_tiles_seen_around(&mut self, centre: TileCoords) {
+        let top_left = TileCoords { x: centre.x - SIGHT_RADIUS, y: centre.y + SIGHT_RADIUS }.as_chunk_coords();
+        let bottom_right = TileCoords { x: centre.x + SIGHT_RADIUS, y: centre.y - SIGHT_RADIUS }.as_chunk_coords();
+
+        self.for_each_chunk_in_rect_mut(top_left, bottom_right, |coords, chunk| {
+            chunk.mark_tiles_seen_around(coords, centre)
+        });
+    }
+
+    /// Returns an iterator of the loaded chunks (along with their coordinates) within the rectangular area between the
+    /// given top-left and bottom-right chunk coordinates (inclusive). Unloaded chunks within the area are skipped.
+    fn chunks_in_rect(
+        &self, top_left: ChunkCoords, bottom_right: ChunkCoords
+    ) -> Box<dyn Iterator<Item = (ChunkCoords, &Chunk)> + '_> {
+        Box::new(
+            chunk_coords_in_rect(top_left, bottom_right)
+                .filter_map(move |coords| self.loaded_chunk_at(coords).map(|chunk| (coords, chunk)))
+        )
+    }
+
+    /// Calls the given closure with mutable access to each loaded chunk within the rectangular area between the given
+    /// top-left and bottom-right chunk coordinates (inclusive).
+    fn for_each_chunk_in_rect_mut(
+        &mut self, top_left: ChunkCoords, bottom_right: ChunkCoords, mut f: impl FnMut(ChunkCoords, &mut Chunk)
+    ) {
+        for coords in chunk_coords_in_rect(top_left, bottom_right) {
+            if let Some(chunk) = self.loaded_chunk_at_mut(coords) {
+                f(coords, chunk);
+            }
+        }
+    }
+
     fn is_position_free(&self, coords: TileCoords) -> bool {
         !self.is_blocking_tile_at(coords) && !self.is_blocking_entity_at(coords)
     }
 
+    /// Returns the position that an entity which moved in the given direction on to the tile at the specified
+    /// coordinates slides on to next, should that tile be [`Tile::Ice`] and the next tile in that direction be free.
+    /// Entities slide at most [`mechanics::MAX_ICE_SLIDE`] tiles after each movement.
+    fn ice_slide_destination(&self, coords: TileCoords, direction: Direction) -> Option<TileCoords> {
+        let next = direction.apply(coords);
+        (self.loaded_tile_at(coords) == Some(Tile::Ice) && self.is_position_free(next)).then_some(next)
+    }
+
     fn is_blocking_tile_at(&self, coords: TileCoords) -> bool {
         self.loaded_tile_at(coords).map(|tile| tile.is_blocking()).unwrap_or(true)
     }
@@ -103,20 +248,76 @@ pub trait Map {
     ) -> Vec<TileCoords> {
         let mut positions = Vec::new();
 
-        for x_offset in -1..2 {
-            for y_offset in -1..2 {
-                let coords = ChunkCoords { x: centre_chunk_coords.x + x_offset, y: centre_chunk_coords.y + y_offset };
+        let top_left = ChunkCoords { x: centre_chunk_coords.x - 1, y: centre_chunk_coords.y + 1 };
+        let bottom_right = ChunkCoords { x: centre_chunk_coords.x + 1, y: centre_chunk_coords.y - 1 };
+
+        self.for_each_chunk_in_rect_mut(top_left, bottom_right, |_, chunk| {
+            positions.extend(chunk.take_bombs_placed_by(placed_by).into_iter());
+        });
+
+        positions
+    }
+
+    /// Takes the positions of all bombs detonated when the entity with the given ID detonates their bombs while in the
+    /// specified chunk. This includes the bombs at and adjacent to that chunk (see
+    /// [`Self::take_bombs_placed_by_in_and_around_chunk`]) as well as any further bombs placed by the same entity
+    /// within the blast radius of a detonated bomb (see [`mechanics::BOMB_BLAST_RADIUS`]), which are detonated in a
+    /// chain reaction. Bombs placed by other entities are never detonated.
+    fn take_detonated_bombs(&mut self, placed_by: Id, centre_chunk_coords: ChunkCoords) -> Vec<TileCoords> {
+        let mut detonated = self.take_bombs_placed_by_in_and_around_chunk(placed_by, centre_chunk_coords);
+        let mut queue: VecDeque<TileCoords> = detonated.iter().copied().collect();
 
-                if let Some(chunk) = self.loaded_chunk_at_mut(coords) {
-                    positions.extend(chunk.take_bombs_placed_by(placed_by).into_iter());
+        // Breadth-first search through the bombs caught in each blast. Each bomb is removed from the map as soon as it
+        // is reached so can never be visited twice:
+        while let Some(bomb_pos) = queue.pop_front() {
+            for coords in bomb_pos.tiles_in_chebyshev_range(mechanics::BOMB_BLAST_RADIUS) {
+                while self.remove_bomb_at(coords, placed_by) {
+                    detonated.push(coords);
+                    queue.push_back(coords);
                 }
             }
         }
 
-        positions
+        detonated
     }
 }
 
+/// Iterate the chunk coordinates within the rectangular area between the given top-left and bottom-right chunk
+/// coordinates (inclusive). Note that y coordinates increase upwards.
+fn chunk_coords_in_rect(top_left: ChunkCoords, bottom_right: ChunkCoords) -> impl Iterator<Item = ChunkCoords> {
+    (top_left.x..=bottom_right.x).flat_map(move |x| (bottom_right.y..=top_left.y).map(move |y| ChunkCoords { x, y }))
+}
+
+/// A square area of the map in which players cannot place bombs and cannot be damaged (e.g. to protect the area in
+/// which players spawn). Includes every tile within the given radius (Chebyshev distance) of the centre tile.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SafeZone {
+    pub centre: TileCoords,
+    pub radius: u32
+}
+
+impl SafeZone {
+    pub fn contains(&self, coords: TileCoords) -> bool {
+        self.centre.chebyshev_distance(coords) <= self.radius
+    }
+}
+
+/// Returns whether the tile at the given coordinates is within any of the given safe zones.
+pub fn is_in_safe_zone(safe_zones: &[SafeZone], coords: TileCoords) -> bool {
+    safe_zones.iter().any(|zone| zone.contains(coords))
+}
+
+/// The outcome of querying a map for the tile at some tile coordinates (see [`Map::query_tile_at`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileQuery {
+    /// The chunk containing the tile is loaded and the tile was found.
+    Loaded(Tile),
+    /// The chunk that the tile would be found in is not loaded.
+    ChunkNotLoaded,
+    /// The chunk is loaded but the offset within that chunk is out of bounds.
+    OutOfBounds
+}
+
 /// Type alias for a hash map that maps chunk coordinates to chunks.
 pub type Chunks = HashMap<ChunkCoords, Chunk>;
 
@@ -128,7 +329,34 @@ pub struct Chunk {
     tiles: [Tile; CHUNK_TILE_COUNT],
     /// Bombs placed in this chunk - sets of bomb positions are mapped to by the ID of the entity that placed those
     /// bombs.
-    undetonated_bombs: HashMap<Id, Vec<TileCoords>>
+    undetonated_bombs: HashMap<Id, Vec<TileCoords>>,
+    /// A flag for each tile (in the same order as [`Self::tiles`]) indicating whether that tile has ever been seen by
+    /// a player. Chunks that predate this field have no tiles seen.
+    #[serde(default)]
+    seen_tiles: [u8; SEEN_FLAGS_LEN],
+    /// The text of each [`Tile::Sign`] in this chunk paired with the offset of that sign.
+    #[serde(default)]
+    sign_texts: Vec<(OffsetCoords, String)>,
+    /// The destination of each [`Tile::Portal`] in this chunk paired with the offset of that portal.
+    #[serde(default)]
+    portal_destinations: Vec<(OffsetCoords, TileCoords)>,
+    /// The gems left behind by each [`Tile::GemDrop`] in this chunk paired with the offset of that drop.
+    #[serde(default)]
+    gem_drops: Vec<(OffsetCoords, GemDrop)>
+}
+
+/// Gems dropped by a player entity upon dying (see [`Tile::GemDrop`]) which may be picked up by any player.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GemDrop {
+    pub gems: gems::Collection,
+    /// The tile that the gem drop was placed over, which is restored once the gems are picked up.
+    pub replaced_tile: Tile,
+    /// The text of the sign that the gem drop was placed over (should [`Self::replaced_tile`] be a [`Tile::Sign`]),
+    /// which is restored along with the sign.
+    pub replaced_sign_text: Option<String>,
+    /// The destination of the portal that the gem drop was placed over (should [`Self::replaced_tile`] be a
+    /// [`Tile::Portal`]), which is restored along with the portal.
+    pub replaced_portal_destination: Option<TileCoords>
 }
 
 impl Chunk {
@@ -136,8 +364,129 @@ impl Chunk {
         self.tiles[offset.calculate_index()]
     }
 
+    /// Returns the tile at the given offset or `None` should that offset be outside the bounds of the chunk.
+    pub fn try_tile_at_offset(&self, offset: OffsetCoords) -> Option<Tile> {
+        offset.try_calculate_index().map(|index| self.tiles[index])
+    }
+
+    /// Change the tile at the given offset. Should a sign, portal, or gem drop be replaced, its text, destination, or
+    /// gems are removed.
     pub fn set_tile_at_offset(&mut self, offset: OffsetCoords, tile: Tile) {
         self.tiles[offset.calculate_index()] = tile;
+
+        if tile != Tile::Sign {
+            self.sign_texts.retain(|(sign_offset, _)| *sign_offset != offset);
+        }
+        if tile != Tile::Portal {
+            self.portal_destinations.retain(|(portal_offset, _)| *portal_offset != offset);
+        }
+        if tile != Tile::GemDrop {
+            self.gem_drops.retain(|(drop_offset, _)| *drop_offset != offset);
+        }
+    }
+
+    /// Place a [`Tile::Sign`] with the given text at the specified offset (replacing any existing sign there). Returns
+    /// `false` without placing the sign should the text be longer than [`MAX_SIGN_TEXT_LENGTH`] characters.
+    pub fn place_sign(&mut self, offset: OffsetCoords, text: String) -> bool {
+        if text.chars().count() > MAX_SIGN_TEXT_LENGTH {
+            return false;
+        }
+
+        self.set_tile_at_offset(offset, Tile::Sign);
+        self.sign_texts.retain(|(sign_offset, _)| *sign_offset != offset);
+        self.sign_texts.push((offset, text));
+
+        true
+    }
+
+    /// Returns the text of the sign at the given offset, or `None` should there be no sign there.
+    pub fn sign_text(&self, offset: OffsetCoords) -> Option<&str> {
+        self.sign_texts.iter().find(|(sign_offset, _)| *sign_offset == offset).map(|(_, text)| text.as_str())
+    }
+
+    /// Place a [`Tile::Portal`] leading to the given destination at the specified offset (replacing any existing
+    /// portal there).
+    pub fn place_portal(&mut self, offset: OffsetCoords, destination: TileCoords) {
+        self.set_tile_at_offset(offset, Tile::Portal);
+        self.portal_destinations.retain(|(portal_offset, _)| *portal_offset != offset);
+        self.portal_destinations.push((offset, destination));
+    }
+
+    /// Returns the destination of the portal at the given offset, or `None` should there be no portal there.
+    pub fn portal_destination(&self, offset: OffsetCoords) -> Option<TileCoords> {
+        self.portal_destinations.iter().find(|(portal_offset, _)| *portal_offset == offset).map(|(_, dest)| *dest)
+    }
+
+    /// Place a [`Tile::GemDrop`] containing the given gems at the specified offset. Should there already be a gem
+    /// drop there, the gems are added to that drop instead. The text or destination of a sign or portal replaced by the
+    /// drop is kept with the drop so that the sign or portal can be restored intact (see [`Self::take_gem_drop`]).
+    pub fn drop_gems(&mut self, offset: OffsetCoords, gems: gems::Collection) {
+        if let Some((_, drop)) = self.gem_drops.iter_mut().find(|(drop_offset, _)| *drop_offset == offset) {
+            drop.gems.merge(&gems);
+            return;
+        }
+
+        let drop = GemDrop {
+            gems,
+            replaced_tile: self.tile_at_offset(offset),
+            replaced_sign_text: self.sign_text(offset).map(String::from),
+            replaced_portal_destination: self.portal_destination(offset)
+        };
+        self.set_tile_at_offset(offset, Tile::GemDrop);
+        self.gem_drops.push((offset, drop));
+    }
+
+    /// Returns the gem drop at the given offset, or `None` should there be no gem drop there.
+    pub fn gem_drop(&self, offset: OffsetCoords) -> Option<&GemDrop> {
+        self.gem_drops.iter().find(|(drop_offset, _)| *drop_offset == offset).map(|(_, drop)| drop)
+    }
+
+    /// Remove the gem drop at the given offset (restoring the tile it replaced along with the text or destination of a
+    /// replaced sign or portal), returning it should there be one.
+    pub fn take_gem_drop(&mut self, offset: OffsetCoords) -> Option<GemDrop> {
+        let index = self.gem_drops.iter().position(|(drop_offset, _)| *drop_offset == offset)?;
+        let (_, drop) = self.gem_drops.remove(index);
+
+        self.set_tile_at_offset(offset, drop.replaced_tile);
+        if let Some(text) = &drop.replaced_sign_text {
+            self.sign_texts.push((offset, text.clone()));
+        }
+        if let Some(destination) = drop.replaced_portal_destination {
+            self.portal_destinations.push((offset, destination));
+        }
+
+        Some(drop)
+    }
+
+    /// Iterate over all tiles in this chunk along with their offsets. Tiles are yielded in row-major order (i.e. in
+    /// order of ascending y offset then x offset), matching [`OffsetCoords::calculate_index`].
+    pub fn tiles(&self) -> impl Iterator<Item = (OffsetCoords, &Tile)> {
+        self.tiles.iter().enumerate().map(|(index, tile)| (offset_at_index(index), tile))
+    }
+
+    /// Mutably iterate over all tiles in this chunk along with their offsets (in the same order as [`Self::tiles`]).
+    pub fn tiles_mut(&mut self) -> impl Iterator<Item = (OffsetCoords, &mut Tile)> {
+        self.tiles.iter_mut().enumerate().map(|(index, tile)| (offset_at_index(index), tile))
+    }
+
+    pub fn is_tile_seen(&self, offset: OffsetCoords) -> bool {
+        let index = offset.calculate_index();
+        self.seen_tiles[index / 8] & (1 << (index % 8)) != 0
+    }
+
+    pub fn mark_tile_seen(&mut self, offset: OffsetCoords) {
+        let index = offset.calculate_index();
+        self.seen_tiles[index / 8] |= 1 << (index % 8);
+    }
+
+    /// Mark the tiles in this chunk (which is at the given chunk coordinates) that are within [`SIGHT_RADIUS`] of the
+    /// given position as seen.
+    pub fn mark_tiles_seen_around(&mut self, chunk_coords: ChunkCoords, centre: TileCoords) {
+        for coords in centre.tiles_in_chebyshev_range(SIGHT_RADIUS) {
+            if coords.as_chunk_coords() == chunk_coords {
+                self.mark_tile_seen(coords.as_chunk_offset_coords());
+            }
+        }
     }
 
     pub fn get_undetonated_bomb_positions(&self) -> impl Iterator<Item = &TileCoords> {
@@ -147,15 +496,66 @@ impl Chunk {
     pub fn take_bombs_placed_by(&mut self, placed_by: Id) -> Vec<TileCoords> {
         self.undetonated_bombs.remove(&placed_by).unwrap_or_default()
     }
+
+    /// Remove a single bomb placed by the specified entity at the given position, returning `true` if such a bomb was
+    /// found.
+    pub fn remove_bomb_placed_by_at(&mut self, placed_by: Id, pos: TileCoords) -> bool {
+        let positions = match self.undetonated_bombs.get_mut(&placed_by) {
+            Some(positions) => positions,
+            None => return false
+        };
+
+        match positions.iter().rposition(|bomb_pos| *bomb_pos == pos) {
+            Some(index) => {
+                positions.remove(index);
+
+                if positions.is_empty() {
+                    self.undetonated_bombs.remove(&placed_by);
+                }
+                true
+            }
+            None => false
+        }
+    }
+}
+
+/// The inverse of [`OffsetCoords::calculate_index`].
+fn offset_at_index(index: usize) -> OffsetCoords {
+    OffsetCoords { x: (index % CHUNK_WIDTH as usize) as u8, y: (index / CHUNK_WIDTH as usize) as u8 }
 }
 
 impl Default for Chunk {
     fn default() -> Self {
-        Chunk { tiles: [Tile::default(); CHUNK_TILE_COUNT], undetonated_bombs: HashMap::new() }
+        Chunk {
+            tiles: [Tile::default(); CHUNK_TILE_COUNT],
+            undetonated_bombs: HashMap::new(),
+            seen_tiles: [0; SEEN_FLAGS_LEN],
+            sign_texts: Vec::new(),
+            portal_destinations: Vec::new(),
+            gem_drops: Vec::new()
+        }
+    }
+}
+
+/// Displays the tiles of the chunk as rows of characters (see [`Tile::display_char`]) with the first row being that
+/// with a y offset of 0.
+impl fmt::Display for Chunk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, row) in self.tiles.chunks(CHUNK_WIDTH as usize).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for tile in row {
+                write!(f, "{}", tile.display_char())?;
+            }
+        }
+
+        Ok(())
     }
 }
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "std", derive(EnumIter))]
 pub enum Tile {
     Grass,
     FlowerPatch,
@@ -193,7 +593,33 @@ pub enum Tile {
     WaterGrassCornerTopLeft,
     WaterGrassCornerTopRight,
     WaterGrassCornerBottomLeft,
-    WaterGrassCornerBottomRight
+    WaterGrassCornerBottomRight,
+    /// Solid rock surrounding cave passages.
+    Wall,
+    /// Floor of a cave passage.
+    Ground,
+    /// Beach surrounding an island.
+    Sand,
+    RockSapphire,
+    /// A chest that yields gems when a player interacts with it (see [`gems::CHEST_LOOT_TABLE`]) after which it
+    /// becomes [`Tile::ChestOpened`].
+    Chest,
+    ChestOpened,
+    /// A sign displaying text to players that interact with it. The text is stored by the chunk containing the sign
+    /// (see [`Chunk::sign_text`]).
+    Sign,
+    /// Molten rock that damages players standing on it (see [`Tile::hazard_damage`]). Lava is never placed by map
+    /// generators.
+    Lava,
+    /// Slippery ice on which entities continue to slide in the direction they moved (see
+    /// [`Map::ice_slide_destination`]). Ice is never placed by map generators.
+    Ice,
+    /// Warps players that move on to it to a destination elsewhere on the map. The destination is stored by the chunk
+    /// containing the portal (see [`Chunk::portal_destination`]). Portals are never placed by map generators.
+    Portal,
+    /// Gems left behind where a player entity died, which are given to the next player to move on to it. The gems are
+    /// stored by the chunk containing the drop (see [`Chunk::gem_drop`]).
+    GemDrop
 }
 
 impl Tile {
@@ -207,12 +633,16 @@ impl Tile {
                 | Tile::WaterGrassTop
                 | Tile::WaterGrassCornerTopLeft
                 | Tile::WaterGrassCornerTopRight
+                | Tile::Wall
+                | Tile::Chest
+                | Tile::ChestOpened
+                | Tile::Sign
         )
     }
 
     /// Returns `true` for a tile that should become [`Tile::RockSmashed`] when an entity walks over it.
     pub fn is_smashable(&self) -> bool {
-        matches!(self, Tile::Rock | Tile::RockEmerald | Tile::RockRuby | Tile::RockDiamond)
+        matches!(self, Tile::Rock | Tile::RockEmerald | Tile::RockRuby | Tile::RockDiamond | Tile::RockSapphire)
     }
 
     pub fn is_grassy(&self) -> bool {
@@ -228,6 +658,69 @@ impl Tile {
         }
     }
 
+    /// A single character representation of this tile for use in text-based displays of the map. Each tile type has a
+    /// distinct character.
+    pub fn display_char(&self) -> char {
+        match self {
+            Tile::Grass => ',',
+            Tile::FlowerPatch => '"',
+            Tile::Stones => '%',
+            Tile::Dirt => ':',
+            Tile::DirtGrassTop => 'a',
+            Tile::DirtGrassBottom => 'b',
+            Tile::DirtGrassLeft => 'c',
+            Tile::DirtGrassRight => 'd',
+            Tile::DirtGrassTopLeft => 'e',
+            Tile::DirtGrassTopRight => 'f',
+            Tile::DirtGrassBottomLeft => 'g',
+            Tile::DirtGrassBottomRight => 'h',
+            Tile::DirtGrassCornerTopLeft => 'i',
+            Tile::DirtGrassCornerTopRight => 'j',
+            Tile::DirtGrassCornerBottomLeft => 'k',
+            Tile::DirtGrassCornerBottomRight => 'l',
+            Tile::Rock => 'R',
+            Tile::RockEmerald => 'E',
+            Tile::RockRuby => 'U',
+            Tile::RockDiamond => 'D',
+            Tile::RockSmashed => '_',
+            Tile::Shrub => '&',
+            Tile::FlowerBlue => '*',
+            Tile::FlowersYellowOrange => '+',
+            Tile::Water => '~',
+            Tile::WaterGrassTop => 'm',
+            Tile::WaterGrassBottom => 'n',
+            Tile::WaterGrassLeft => 'o',
+            Tile::WaterGrassRight => 'p',
+            Tile::WaterGrassTopLeft => 'q',
+            Tile::WaterGrassTopRight => 'r',
+            Tile::WaterGrassBottomLeft => 's',
+            Tile::WaterGrassBottomRight => 't',
+            Tile::WaterGrassCornerTopLeft => 'u',
+            Tile::WaterGrassCornerTopRight => 'v',
+            Tile::WaterGrassCornerBottomLeft => 'w',
+            Tile::WaterGrassCornerBottomRight => 'x',
+            Tile::Wall => '#',
+            Tile::Ground => '.',
+            Tile::Sand => '=',
+            Tile::RockSapphire => 'S',
+            Tile::Chest => 'C',
+            Tile::ChestOpened => 'O',
+            Tile::Sign => '?',
+            Tile::Lava => '^',
+            Tile::Ice => 'I',
+            Tile::Portal => 'P',
+            Tile::GemDrop => '$'
+        }
+    }
+
+    /// Returns the damage dealt periodically to players standing on this tile, or `None` should the tile be harmless.
+    pub fn hazard_damage(&self) -> Option<u32> {
+        match self {
+            Tile::Lava => Some(mechanics::LAVA_DAMAGE),
+            _ => None
+        }
+    }
+
     /// Returns the gem yield for a smashable tile (except [`Tile::Rock`] which is smashable but does not yield any
     /// gems).
     pub fn get_gem_yield(&self) -> Option<gems::Yield> {
@@ -235,6 +728,7 @@ impl Tile {
             Tile::RockEmerald => Some(gems::Yield { gem: Gem::Emerald, minimum_quantity: 3, maximum_quantity: 5 }),
             Tile::RockRuby => Some(gems::Yield { gem: Gem::Ruby, minimum_quantity: 1, maximum_quantity: 3 }),
             Tile::RockDiamond => Some(gems::Yield { gem: Gem::Diamond, minimum_quantity: 1, maximum_quantity: 1 }),
+            Tile::RockSapphire => Some(gems::Yield { gem: Gem::Sapphire, minimum_quantity: 1, maximum_quantity: 1 }),
             _ => None
         }
     }
@@ -245,3 +739,496 @@ impl Default for Tile {
         Tile::Grass
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal map implementation storing only chunks.
+    #[derive(Default)]
+    struct TestMap {
+        chunks: Chunks,
+        entities: entities::Entities
+    }
+
+    impl Map for TestMap {
+        fn is_blocking_entity_at(&self, coords: TileCoords) -> bool {
+            self.entities.values().any(|entity| entity.pos == coords)
+        }
+
+        fn loaded_chunk_at(&self, coords: ChunkCoords) -> Option<&Chunk> {
+            self.chunks.get(&coords)
+        }
+
+        fn loaded_chunk_at_mut(&mut self, coords: ChunkCoords) -> Option<&mut Chunk> {
+            self.chunks.get_mut(&coords)
+        }
+
+        fn add_chunk(&mut self, coords: ChunkCoords, chunk: Chunk) {
+            self.chunks.insert(coords, chunk);
+        }
+
+        fn remove_chunk(&mut self, coords: ChunkCoords) -> Option<Chunk> {
+            self.chunks.remove(&coords)
+        }
+
+        fn entity_by_id(&self, id: Id) -> Option<&Entity> {
+            self.entities.get(&id)
+        }
+
+        fn entity_by_id_mut(&mut self, id: Id) -> Option<&mut Entity> {
+            self.entities.get_mut(&id)
+        }
+
+        fn add_entity(&mut self, id: Id, entity: Entity) {
+            self.entities.insert(id, entity);
+        }
+
+        fn remove_entity(&mut self, id: Id) -> Option<Entity> {
+            self.entities.remove(&id)
+        }
+    }
+
+    #[test]
+    fn chunk_tiles_iteration() {
+        let mut chunk = Chunk::default();
+
+        // Yielded in row-major order with offsets matching their storage indices:
+        let offsets: Vec<OffsetCoords> = chunk.tiles().map(|(offset, _)| offset).collect();
+        assert_eq!(offsets.len(), CHUNK_TILE_COUNT);
+        assert_eq!(offsets[1], OffsetCoords { x: 1, y: 0 });
+        assert_eq!(offsets[CHUNK_WIDTH as usize], OffsetCoords { x: 0, y: 1 });
+        assert!(offsets.iter().enumerate().all(|(index, offset)| offset.calculate_index() == index));
+
+        for (offset, tile) in chunk.tiles_mut() {
+            if offset.x == offset.y {
+                *tile = Tile::Water;
+            }
+        }
+
+        assert_eq!(chunk.tile_at_offset(OffsetCoords { x: 7, y: 7 }), Tile::Water);
+        assert_eq!(chunk.tile_at_offset(OffsetCoords { x: 7, y: 8 }), Tile::default());
+        assert_eq!(chunk.tiles().filter(|(_, tile)| **tile == Tile::Water).count(), CHUNK_WIDTH as usize);
+    }
+
+    #[test]
+    fn query_tile_in_loaded_and_unloaded_chunks() {
+        let mut map = TestMap::default();
+
+        let mut chunk = Chunk::default();
+        chunk.set_tile_at_offset(OffsetCoords { x: 3, y: 4 }, Tile::Water);
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+
+        let queries = &[
+            (TileCoords { x: 3, y: 4 }, TileQuery::Loaded(Tile::Water)),
+            (TileCoords { x: 0, y: 0 }, TileQuery::Loaded(Tile::Grass)),
+            (TileCoords { x: -1, y: 0 }, TileQuery::ChunkNotLoaded),
+            (TileCoords { x: CHUNK_WIDTH, y: CHUNK_HEIGHT }, TileQuery::ChunkNotLoaded)
+        ];
+
+        for (coords, expected) in queries {
+            let query = map.query_tile_at(*coords);
+            assert_eq!(query, *expected);
+
+            match query {
+                TileQuery::Loaded(tile) => assert_eq!(map.loaded_tile_at(*coords), Some(tile)),
+                TileQuery::ChunkNotLoaded => assert_eq!(map.loaded_tile_at(*coords), None),
+                TileQuery::OutOfBounds => unreachable!()
+            }
+        }
+    }
+
+    #[test]
+    fn default_loaded_tile_methods() {
+        let mut map = TestMap::default();
+        map.add_chunk(ChunkCoords { x: -1, y: 0 }, Chunk::default());
+
+        let loaded = TileCoords { x: -1, y: CHUNK_HEIGHT - 1 };
+        let unloaded = TileCoords { x: 0, y: 0 };
+
+        assert!(map.is_tile_loaded(loaded));
+        assert!(!map.is_tile_loaded(unloaded));
+        assert!(map.is_chunk_loaded(loaded.as_chunk_coords()));
+        assert!(!map.is_chunk_loaded(unloaded.as_chunk_coords()));
+
+        assert!(map.set_loaded_tile_at(loaded, Tile::Water));
+        assert!(!map.set_loaded_tile_at(unloaded, Tile::Water));
+        assert_eq!(map.loaded_tile_at(loaded), Some(Tile::Water));
+        assert_eq!(map.loaded_tile_at(unloaded), None);
+
+        // Tiles in unloaded chunks are treated as blocking:
+        assert!(map.is_blocking_tile_at(loaded));
+        assert!(map.is_blocking_tile_at(unloaded));
+        assert!(!map.is_blocking_tile_at(TileCoords { x: -2, y: 0 }));
+    }
+
+    #[test]
+    fn chunks_in_rect_excludes_outside_and_unloaded_chunks() {
+        let mut map = TestMap::default();
+
+        for x in -3..4 {
+            for y in -3..4 {
+                if (x, y) != (0, 0) {
+                    map.add_chunk(ChunkCoords { x, y }, Chunk::default());
+                }
+            }
+        }
+
+        let top_left = ChunkCoords { x: -1, y: 2 };
+        let bottom_right = ChunkCoords { x: 1, y: -1 };
+
+        let mut coords: Vec<ChunkCoords> =
+            map.chunks_in_rect(top_left, bottom_right).map(|(coords, _)| coords).collect();
+        coords.sort_by_key(|coords| (coords.x, coords.y));
+
+        let mut expected = Vec::new();
+        for x in -1..2 {
+            for y in -1..3 {
+                if (x, y) != (0, 0) {
+                    expected.push(ChunkCoords { x, y });
+                }
+            }
+        }
+        assert_eq!(coords, expected);
+
+        let mut mut_coords = Vec::new();
+        map.for_each_chunk_in_rect_mut(top_left, bottom_right, |coords, _| mut_coords.push(coords));
+        mut_coords.sort_by_key(|coords| (coords.x, coords.y));
+        assert_eq!(mut_coords, expected);
+    }
+
+    #[test]
+    fn tiles_within_sight_marked_seen_across_chunks() {
+        let mut map = TestMap::default();
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, Chunk::default());
+        map.add_chunk(ChunkCoords { x: -1, y: 0 }, Chunk::default());
+
+        let centre = TileCoords { x: 1, y: 12 };
+        map.mark_tiles_seen_around(centre);
+
+        for (coords, seen) in [
+            (centre, true),
+            (TileCoords { x: 1 + SIGHT_RADIUS, y: 12 - SIGHT_RADIUS }, true),
+            (TileCoords { x: 1 - SIGHT_RADIUS, y: 12 - SIGHT_RADIUS }, true),
+            (TileCoords { x: 2 + SIGHT_RADIUS, y: 12 }, false),
+            (TileCoords { x: 1, y: 11 - SIGHT_RADIUS }, false),
+            // Within sight but in an unloaded chunk:
+            (TileCoords { x: 1, y: 12 + SIGHT_RADIUS }, false)
+        ] {
+            assert_eq!(map.is_tile_seen(coords), seen, "{}", coords);
+        }
+
+        let chunk = map.loaded_chunk_at(ChunkCoords { x: 0, y: 0 }).unwrap();
+        assert!(chunk.is_tile_seen(OffsetCoords { x: 0, y: 4 }));
+        assert!(!chunk.is_tile_seen(OffsetCoords { x: 0, y: 3 }));
+        assert!(!chunk.is_tile_seen(OffsetCoords { x: CHUNK_WIDTH as u8 - 1, y: 12 }));
+    }
+
+    #[test]
+    fn place_and_replace_signs() {
+        let mut map = TestMap::default();
+        let mut chunk = Chunk::default();
+        let offset = OffsetCoords { x: 2, y: 9 };
+
+        assert!(chunk.place_sign(offset, "Welcome!".to_string()));
+        assert!(!chunk.place_sign(OffsetCoords { x: 0, y: 0 }, "a".repeat(MAX_SIGN_TEXT_LENGTH + 1)));
+        assert_eq!(chunk.tile_at_offset(OffsetCoords { x: 0, y: 0 }), Tile::Grass);
+
+        map.add_chunk(ChunkCoords { x: -1, y: 0 }, chunk);
+        let coords = TileCoords { x: 2 - CHUNK_WIDTH, y: 9 };
+
+        assert_eq!(map.loaded_tile_at(coords), Some(Tile::Sign));
+        assert!(map.is_blocking_tile_at(coords));
+        assert_eq!(map.sign_text_at(coords), Some("Welcome!"));
+        assert_eq!(map.sign_text_at(TileCoords { x: 2, y: 9 }), None);
+
+        let chunk = map.loaded_chunk_at_mut(ChunkCoords { x: -1, y: 0 }).unwrap();
+        assert!(chunk.place_sign(offset, "a".repeat(MAX_SIGN_TEXT_LENGTH)));
+        assert_eq!(chunk.sign_text(offset).map(str::len), Some(MAX_SIGN_TEXT_LENGTH));
+
+        // Replacing the sign removes its text:
+        map.set_loaded_tile_at(coords, Tile::Grass);
+        assert_eq!(map.sign_text_at(coords), None);
+    }
+
+    #[test]
+    fn place_and_replace_portals() {
+        let mut map = TestMap::default();
+        let mut chunk = Chunk::default();
+        let destination = TileCoords { x: 100, y: -40 };
+
+        chunk.place_portal(OffsetCoords { x: 4, y: 4 }, TileCoords { x: 0, y: 0 });
+        chunk.place_portal(OffsetCoords { x: 4, y: 4 }, destination);
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+
+        let coords = TileCoords { x: 4, y: 4 };
+        assert_eq!(map.loaded_tile_at(coords), Some(Tile::Portal));
+        assert!(!map.is_blocking_tile_at(coords));
+        assert_eq!(map.portal_destination_at(coords), Some(destination));
+        assert_eq!(map.portal_destination_at(TileCoords { x: 5, y: 4 }), None);
+
+        // Replacing the portal removes its destination:
+        map.set_loaded_tile_at(coords, Tile::Grass);
+        assert_eq!(map.portal_destination_at(coords), None);
+    }
+
+    #[test]
+    fn drop_and_take_gems() {
+        let mut map = TestMap::default();
+        let mut chunk = Chunk::default();
+        chunk.set_tile_at_offset(OffsetCoords { x: 6, y: 1 }, Tile::Lava);
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+
+        let coords = TileCoords { x: 6, y: 1 };
+        let gems_of = |gem, quantity| {
+            let mut collection = gems::Collection::default();
+            collection.increase_quantity(gem, quantity);
+            collection
+        };
+
+        assert!(map.drop_gems_at(coords, gems_of(Gem::Ruby, 3)));
+        assert!(map.drop_gems_at(coords, gems_of(Gem::Emerald, 2)));
+        assert!(!map.drop_gems_at(TileCoords { x: 6, y: -1 }, gems_of(Gem::Ruby, 1)));
+
+        assert_eq!(map.loaded_tile_at(coords), Some(Tile::GemDrop));
+        assert!(!map.is_blocking_tile_at(coords));
+        assert_eq!(map.gem_drop_at(coords).unwrap().gems, gems_of(Gem::Ruby, 3) + gems_of(Gem::Emerald, 2));
+
+        // Taking the drop restores the tile it was placed over:
+        let drop = map.take_gem_drop_at(coords).unwrap();
+        assert_eq!(drop.replaced_tile, Tile::Lava);
+        assert_eq!(map.loaded_tile_at(coords), Some(Tile::Lava));
+        assert_eq!(map.take_gem_drop_at(coords), None);
+
+        // Signs & portals covered by a drop keep their text & destination:
+        let sign_coords = TileCoords { x: 7, y: 1 };
+        let portal_coords = TileCoords { x: 8, y: 1 };
+        let portal_destination = TileCoords { x: -40, y: 25 };
+        let chunk = map.loaded_chunk_at_mut(ChunkCoords { x: 0, y: 0 }).unwrap();
+        chunk.place_sign(sign_coords.as_chunk_offset_coords(), "Beware!".to_string());
+        chunk.place_portal(portal_coords.as_chunk_offset_coords(), portal_destination);
+
+        map.drop_gems_at(sign_coords, gems_of(Gem::Ruby, 1));
+        map.drop_gems_at(portal_coords, gems_of(Gem::Ruby, 1));
+        assert_eq!(map.sign_text_at(sign_coords), None);
+        assert_eq!(map.portal_destination_at(portal_coords), None);
+
+        map.take_gem_drop_at(sign_coords).unwrap();
+        map.take_gem_drop_at(portal_coords).unwrap();
+        assert_eq!(map.loaded_tile_at(sign_coords), Some(Tile::Sign));
+        assert_eq!(map.sign_text_at(sign_coords), Some("Beware!"));
+        assert_eq!(map.loaded_tile_at(portal_coords), Some(Tile::Portal));
+        assert_eq!(map.portal_destination_at(portal_coords), Some(portal_destination));
+
+        // Replacing the drop removes its gems:
+        map.drop_gems_at(coords, gems_of(Gem::Diamond, 1));
+        map.set_loaded_tile_at(coords, Tile::Grass);
+        assert_eq!(map.gem_drop_at(coords), None);
+    }
+
+    #[test]
+    fn try_tile_at_out_of_bounds_offset() {
+        let chunk = Chunk::default();
+
+        assert_eq!(chunk.try_tile_at_offset(OffsetCoords { x: 0, y: 0 }), Some(Tile::default()));
+        assert_eq!(chunk.try_tile_at_offset(OffsetCoords { x: CHUNK_WIDTH as u8 - 1, y: 0 }), Some(Tile::default()));
+        assert_eq!(chunk.try_tile_at_offset(OffsetCoords { x: CHUNK_WIDTH as u8, y: 0 }), None);
+        assert_eq!(chunk.try_tile_at_offset(OffsetCoords { x: 0, y: CHUNK_HEIGHT as u8 }), None);
+    }
+
+    #[test]
+    fn bombs_smash_surrounding_rocks() {
+        let mut map = TestMap::default();
+
+        let mut chunk = Chunk::default();
+        chunk.set_tile_at_offset(OffsetCoords { x: 4, y: 4 }, Tile::RockRuby);
+        chunk.set_tile_at_offset(OffsetCoords { x: 5, y: 5 }, Tile::Rock);
+        chunk.set_tile_at_offset(OffsetCoords { x: 6, y: 5 }, Tile::Rock); // Outside blast radius.
+        chunk.set_tile_at_offset(OffsetCoords { x: 3, y: 3 }, Tile::Water);
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, chunk);
+
+        // Both bombs are in range of the ruby rock but it should only be smashed once:
+        let bombs = [TileCoords { x: 4, y: 5 }, TileCoords { x: 3, y: 4 }];
+        let mut smashed = map.smash_tiles_around_bombs(&bombs);
+        smashed.sort_by_key(|(coords, _)| coords.x);
+
+        assert_eq!(smashed, vec![(TileCoords { x: 4, y: 4 }, Tile::RockRuby), (TileCoords { x: 5, y: 5 }, Tile::Rock)]);
+        assert_eq!(map.loaded_tile_at(TileCoords { x: 4, y: 4 }), Some(Tile::RockSmashed));
+        assert_eq!(map.loaded_tile_at(TileCoords { x: 5, y: 5 }), Some(Tile::RockSmashed));
+        assert_eq!(map.loaded_tile_at(TileCoords { x: 6, y: 5 }), Some(Tile::Rock));
+        assert_eq!(map.loaded_tile_at(TileCoords { x: 3, y: 3 }), Some(Tile::Water));
+
+        // Bombs at the edge of the loaded area should not affect unloaded chunks:
+        assert!(map.smash_tiles_around_bombs(&[TileCoords { x: 0, y: 0 }]).is_empty());
+    }
+
+    #[test]
+    fn bombs_chain_detonation() {
+        let mut map = TestMap::default();
+        for x in -1..=3 {
+            map.add_chunk(ChunkCoords { x, y: 0 }, Chunk::default());
+        }
+
+        let (placer, other) = (Id::new(1), Id::new(2));
+
+        // Chain of bombs leading away from the chunks surrounding chunk (0, 0):
+        let chained = [(CHUNK_WIDTH * 2) - 1, CHUNK_WIDTH * 2, (CHUNK_WIDTH * 2) + 1, (CHUNK_WIDTH * 2) + 2]
+            .map(|x| TileCoords { x, y: 3 });
+        for pos in &chained {
+            map.set_bomb_at(*pos, placer);
+        }
+
+        // Bomb placed by a different entity does not continue the chain:
+        map.set_bomb_at(TileCoords { x: (CHUNK_WIDTH * 2) + 3, y: 3 }, other);
+        let beyond_other = TileCoords { x: (CHUNK_WIDTH * 2) + 4, y: 3 };
+        map.set_bomb_at(beyond_other, placer);
+
+        // Bomb too far away from the chain to be detonated:
+        let isolated = TileCoords { x: CHUNK_WIDTH * 2, y: 6 };
+        map.set_bomb_at(isolated, placer);
+
+        let mut detonated = map.take_detonated_bombs(placer, ChunkCoords { x: 0, y: 0 });
+        detonated.sort_by_key(|pos| pos.x);
+        assert_eq!(detonated, chained.to_vec());
+
+        assert!(map.remove_bomb_at(beyond_other, placer));
+        assert!(map.remove_bomb_at(isolated, placer));
+        assert!(map.remove_bomb_at(TileCoords { x: (CHUNK_WIDTH * 2) + 3, y: 3 }, other));
+    }
+
+    #[test]
+    fn gem_yield_only_for_gem_rocks() {
+        assert!(Tile::Rock.get_gem_yield().is_none());
+        assert!(Tile::RockSmashed.get_gem_yield().is_none());
+        assert!(Tile::Grass.get_gem_yield().is_none());
+        assert!(Tile::Wall.get_gem_yield().is_none());
+        assert!(Tile::RockEmerald.get_gem_yield().is_some());
+    }
+
+    #[test]
+    fn remove_single_bomb() {
+        let mut map = TestMap::default();
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, Chunk::default());
+
+        let (placer, other) = (Id::new(1), Id::new(2));
+        let pos = TileCoords { x: 3, y: 4 };
+
+        assert!(map.set_bomb_at(pos, placer));
+        assert!(map.set_bomb_at(pos, placer));
+        assert!(map.set_bomb_at(TileCoords { x: 5, y: 4 }, placer));
+
+        assert!(!map.remove_bomb_at(pos, other));
+        assert!(!map.remove_bomb_at(TileCoords { x: 0, y: 0 }, placer));
+        assert!(!map.remove_bomb_at(TileCoords { x: -1, y: 0 }, placer));
+
+        assert!(map.remove_bomb_at(pos, placer));
+
+        let chunk = map.loaded_chunk_at(ChunkCoords { x: 0, y: 0 }).unwrap();
+        let mut remaining: Vec<_> = chunk.get_undetonated_bomb_positions().copied().collect();
+        remaining.sort_by_key(|bomb_pos| bomb_pos.x);
+        assert_eq!(remaining, vec![pos, TileCoords { x: 5, y: 4 }]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn tile_display_chars_distinct() {
+        use std::collections::HashSet;
+
+        use strum::IntoEnumIterator;
+
+        let chars: HashSet<char> = Tile::iter().map(|tile| tile.display_char()).collect();
+        assert_eq!(chars.len(), Tile::iter().count());
+    }
+
+    #[test]
+    fn slide_across_ice() {
+        let mut map = TestMap::default();
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, Chunk::default());
+
+        for x in 2..5 {
+            map.set_loaded_tile_at(TileCoords { x, y: 1 }, Tile::Ice);
+        }
+        map.set_loaded_tile_at(TileCoords { x: 2, y: 2 }, Tile::Ice);
+        map.set_loaded_tile_at(TileCoords { x: 2, y: 3 }, Tile::Water);
+
+        let slide = |x, y, direction| map.ice_slide_destination(TileCoords { x, y }, direction);
+
+        assert_eq!(slide(2, 1, Direction::Right), Some(TileCoords { x: 3, y: 1 }));
+        assert_eq!(slide(4, 1, Direction::Right), Some(TileCoords { x: 5, y: 1 }));
+        assert_eq!(slide(5, 1, Direction::Right), None);
+        assert_eq!(slide(2, 2, Direction::Up), None);
+        assert!(!Tile::Ice.is_blocking());
+    }
+
+    #[test]
+    fn lava_walkable_but_hazardous() {
+        assert!(!Tile::Lava.is_blocking());
+        assert!(!Tile::Lava.is_smashable());
+        assert_eq!(Tile::Lava.hazard_damage(), Some(5));
+        assert_eq!(Tile::Grass.hazard_damage(), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn every_gem_yielded_by_smashable_tile() {
+        use strum::IntoEnumIterator;
+
+        for gem in Gem::all() {
+            let yielding_tiles: Vec<Tile> =
+                Tile::iter().filter(|tile| tile.get_gem_yield().map(|y| y.gem) == Some(gem)).collect();
+
+            assert_eq!(yielding_tiles.len(), 1, "{:?} should be yielded by exactly one tile type", gem);
+            assert!(yielding_tiles[0].is_smashable());
+        }
+    }
+
+    #[test]
+    fn display_chunk() {
+        let mut chunk = Chunk::default();
+        chunk.set_tile_at_offset(OffsetCoords { x: 0, y: 0 }, Tile::Water);
+        chunk.set_tile_at_offset(OffsetCoords { x: 2, y: 1 }, Tile::Rock);
+
+        let text = chunk.to_string();
+        let rows: Vec<&str> = text.lines().collect();
+
+        assert_eq!(rows.len(), CHUNK_HEIGHT as usize);
+        assert!(rows.iter().all(|row| row.chars().count() == CHUNK_WIDTH as usize));
+        assert!(rows[0].starts_with("~,,"));
+        assert!(rows[1].starts_with(",,R"));
+    }
+
+    #[test]
+    fn line_of_sight() {
+        let mut map = TestMap::default();
+        map.add_chunk(ChunkCoords { x: 0, y: 0 }, Chunk::default());
+
+        let from = TileCoords { x: 1, y: 1 };
+        let to = TileCoords { x: 6, y: 1 };
+        assert!(from.has_line_of_sight(to, &map));
+
+        // Blocking target tile does not obstruct line of sight:
+        map.set_loaded_tile_at(to, Tile::Stones);
+        assert!(from.has_line_of_sight(to, &map));
+
+        map.set_loaded_tile_at(TileCoords { x: 3, y: 1 }, Tile::Water);
+        assert!(!from.has_line_of_sight(to, &map));
+
+        // Tiles in unloaded chunks obstruct line of sight:
+        assert!(!from.has_line_of_sight(TileCoords { x: -5, y: 1 }, &map));
+    }
+
+    #[test]
+    fn safe_zone_containment() {
+        let zones = [
+            SafeZone { centre: TileCoords { x: 0, y: 0 }, radius: 5 },
+            SafeZone { centre: TileCoords { x: 20, y: -20 }, radius: 0 }
+        ];
+
+        assert!(is_in_safe_zone(&zones, TileCoords { x: 0, y: 0 }));
+        assert!(is_in_safe_zone(&zones, TileCoords { x: -5, y: 5 }));
+        assert!(!is_in_safe_zone(&zones, TileCoords { x: 6, y: 0 }));
+        assert!(is_in_safe_zone(&zones, TileCoords { x: 20, y: -20 }));
+        assert!(!is_in_safe_zone(&zones, TileCoords { x: 20, y: -21 }));
+        assert!(!is_in_safe_zone(&[], TileCoords { x: 0, y: 0 }));
+    }
+}
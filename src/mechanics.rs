@@ -0,0 +1,114 @@
+//! Gameplay rules that are applied identically on both the client and server.
+
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::items::{Inventory, QuantitativeItem};
+
+/// Smashable tiles within this distance (Chebyshev distance, so including diagonally adjacent tiles) of a detonated
+/// bomb are smashed.
+pub const BOMB_BLAST_RADIUS: i32 = 1;
+
+/// The damage dealt to a player each time the server applies hazard damage while they stand on [`Tile::Lava`].
+///
+/// [`Tile::Lava`]: crate::maps::Tile::Lava
+pub const LAVA_DAMAGE: u32 = 5;
+
+/// The maximum number of tiles that an entity slides across ice (see [`Map::ice_slide_destination`]) after a single
+/// movement.
+///
+/// [`Map::ice_slide_destination`]: crate::maps::Map::ice_slide_destination
+pub const MAX_ICE_SLIDE: u32 = 16;
+
+/// Actions that a player may only perform once their cooldown (see [`ActionKind::cooldown_ms`]) since they last
+/// performed the same action has passed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ActionKind {
+    PlaceBomb,
+    UsePotion,
+    Emote
+}
+
+impl ActionKind {
+    /// The number of milliseconds after performing this action before the player may perform it again.
+    pub fn cooldown_ms(self) -> u32 {
+        match self {
+            ActionKind::PlaceBomb => 500,
+            ActionKind::UsePotion => 3000,
+            ActionKind::Emote => 2000
+        }
+    }
+
+    /// The action (should there be one with a cooldown) of using the given item.
+    pub fn of_using(item: QuantitativeItem) -> Option<Self> {
+        match item {
+            QuantitativeItem::Potion => Some(ActionKind::UsePotion),
+            QuantitativeItem::Bomb => None
+        }
+    }
+}
+
+impl fmt::Display for ActionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionKind::PlaceBomb => write!(f, "place a bomb"),
+            ActionKind::UsePotion => write!(f, "use a potion"),
+            ActionKind::Emote => write!(f, "emote")
+        }
+    }
+}
+
+/// Players may carry items up to this total weight without being slowed down.
+const UNENCUMBERED_WEIGHT: u32 = 20;
+
+/// Movement speed multiplier approached (but never reached) as the weight of a player's inventory increases.
+const MIN_SPEED_MULTIPLIER: f32 = 0.1;
+
+/// Weight beyond [`UNENCUMBERED_WEIGHT`] at which a player's speed is reduced halfway towards
+/// [`MIN_SPEED_MULTIPLIER`].
+const ENCUMBRANCE_WEIGHT_SCALE: f32 = 40.0;
+
+/// Calculate the multiplier for the movement speed of a player carrying the given inventory. The returned value is
+/// within the range (0.1, 1.0] and decreases as the total weight of the inventory increases.
+pub fn movement_speed_multiplier(inventory: &Inventory) -> f32 {
+    let excess_weight = inventory.total_weight().saturating_sub(UNENCUMBERED_WEIGHT) as f32;
+
+    MIN_SPEED_MULTIPLIER
+        + (1.0 - MIN_SPEED_MULTIPLIER) * (ENCUMBRANCE_WEIGHT_SCALE / (ENCUMBRANCE_WEIGHT_SCALE + excess_weight))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inventory_of_weight(weight: u32) -> Inventory {
+        let mut inventory = Inventory::default();
+        inventory.give_quantity(QuantitativeItem::Bomb, weight / QuantitativeItem::Bomb.weight());
+        assert_eq!(inventory.total_weight(), weight);
+
+        inventory
+    }
+
+    #[test]
+    fn speed_multiplier_at_weights() {
+        assert_eq!(movement_speed_multiplier(&Inventory::default()), 1.0);
+        assert_eq!(movement_speed_multiplier(&inventory_of_weight(UNENCUMBERED_WEIGHT)), 1.0);
+
+        let halfway = movement_speed_multiplier(&inventory_of_weight(UNENCUMBERED_WEIGHT + 40));
+        assert!((halfway - 0.55).abs() < 0.0001);
+
+        let heavy = movement_speed_multiplier(&inventory_of_weight(10_000));
+        assert!(heavy > MIN_SPEED_MULTIPLIER && heavy < 0.2);
+
+        // Speed should only ever decrease as weight increases:
+        let multipliers: Vec<f32> = (0..50).map(|i| movement_speed_multiplier(&inventory_of_weight(i * 10))).collect();
+        assert!(multipliers.windows(2).all(|pair| pair[1] <= pair[0]));
+    }
+
+    #[test]
+    fn actions_of_using_items() {
+        assert_eq!(ActionKind::of_using(QuantitativeItem::Potion), Some(ActionKind::UsePotion));
+        assert_eq!(ActionKind::of_using(QuantitativeItem::Bomb), None);
+    }
+}
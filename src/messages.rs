@@ -1,23 +1,116 @@
 //! Contains (de)serialisable enumerations that the server and client applications may communicate with.
 
-use std::fmt;
+use alloc::{string::String, vec::Vec};
+use core::fmt;
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    gems, items,
+    achievements, gems, items,
     maps::{
         self,
-        entities::{self, Entity}
+        entities::{self, Entity, Monster}
     },
-    Id
+    mechanics::ActionKind,
+    quests, stats, Id
 };
 
+/// The maximum length (in characters) of the text of a chat message.
+pub const MAX_CHAT_MESSAGE_LENGTH: usize = 256;
+
+/// Returns whether or not the given text may be sent as a chat message (i.e. is not blank and does not exceed
+/// [`MAX_CHAT_MESSAGE_LENGTH`] characters).
+pub fn is_valid_chat_message(text: &str) -> bool {
+    !text.trim().is_empty() && text.chars().count() <= MAX_CHAT_MESSAGE_LENGTH
+}
+
+/// Chat messages sent on the [`ChatChannel::Local`] channel are only delivered to players whose entity is within this
+/// many chunks (horizontally or vertically) of the sender's entity.
+pub const LOCAL_CHAT_RADIUS: u32 = 3;
+
+/// The channels on which chat messages may be sent (see [`ToServer::ChatMessage`]).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChatChannel {
+    /// Delivered only to nearby players (see [`LOCAL_CHAT_RADIUS`]).
+    #[default]
+    Local,
+    /// Delivered to every connected player, though costs the sender gems for each message sent.
+    Global
+}
+
+impl ChatChannel {
+    /// The channel that follows this one when cycling through channels in the client's user interface.
+    pub fn next(self) -> Self {
+        match self {
+            ChatChannel::Local => ChatChannel::Global,
+            ChatChannel::Global => ChatChannel::Local
+        }
+    }
+}
+
+impl fmt::Display for ChatChannel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChatChannel::Local => write!(f, "Local"),
+            ChatChannel::Global => write!(f, "Global")
+        }
+    }
+}
+
+/// The maximum number of players ranked in a [`FromServer::Leaderboard`] message.
+pub const LEADERBOARD_SIZE: usize = 10;
+
+/// The maximum length (in characters) of the text of a server announcement.
+pub const MAX_ANNOUNCEMENT_LENGTH: usize = 512;
+
+/// Returns whether or not the given text may be broadcast as a server announcement (i.e. is not blank and does not
+/// exceed [`MAX_ANNOUNCEMENT_LENGTH`] characters).
+pub fn is_valid_announcement(text: &str) -> bool {
+    !text.trim().is_empty() && text.chars().count() <= MAX_ANNOUNCEMENT_LENGTH
+}
+
+/// The number of seconds between each [`ToServer::Ping`] message sent by the client. This should be well below the idle
+/// timeout after which the server closes connections with clients that have not sent any messages.
+pub const PING_INTERVAL_SECS: u64 = 5;
+
+/// The short animations that players may perform for nearby players to see (see [`ToServer::Emote`]).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EmoteKind {
+    Wave,
+    Dance,
+    Cheer,
+    Sad
+}
+
+impl fmt::Display for EmoteKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmoteKind::Wave => write!(f, "wave"),
+            EmoteKind::Dance => write!(f, "dance"),
+            EmoteKind::Cheer => write!(f, "cheer"),
+            EmoteKind::Sad => write!(f, "sad")
+        }
+    }
+}
+
+/// The request number of a [`FromServer::YourEntityMoved`] message informing the client of a change in its player
+/// entity's position that the server made of its own accord (e.g. warping the player through a portal) rather than in
+/// response to a [`ToServer::MoveMyEntity`] message. Clients number their movement requests starting after this value.
+pub const SERVER_MOVE_REQUEST_NUMBER: u32 = 0;
+
 /// Message sent from the client to the server over the WebSocket protocol.
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub enum ToServer {
-    /// This must be the first message sent by a client to the server after establishing a WebSocket connection.
+    /// This must be the first message sent by a client to the server after establishing a WebSocket connection. The
+    /// server will respond with a [`FromServer::HelloAck`] message, after which the client must send a
+    /// [`ToServer::JoinWorld`] message followed by a [`ToServer::SetName`] message.
+    ///
+    /// Note that this variant and its `protocol_version` field must remain first so that the protocol version can
+    /// always be read by the server regardless of the client's version.
     Hello {
+        /// The value of [`crate::PROTOCOL_VERSION`] that the client was built with. Should this not match that of the
+        /// server then the server will refuse the connection.
+        protocol_version: u32,
         /// If this player has played before then a client ID value will be sent so that they may continue playing as
         /// their pre-existing character. If this player has never played before (or have cleared their browser
         /// cookies) then this field should be `None` (but note that a 'hello' message must still be the first
@@ -29,13 +122,16 @@ pub enum ToServer {
     /// [`FromServer::YourEntityMoved`] message to inform the client of their player entity's new position.
     MoveMyEntity {
         /// In order to facilitate client side prediction of movement and reconciliation with the server afterwards
-        /// regardless of connection speed, requests to move a player entity are incrementally numbered.
+        /// regardless of connection speed, requests to move a player entity are incrementally numbered (beginning
+        /// after [`SERVER_MOVE_REQUEST_NUMBER`]).
         request_number: u32,
         direction: entities::Direction
     },
 
     /// Attempt to place a bomb at the player entity's position. The client is expected to ensure that their player
-    /// actually has a bomb to place before sending this message.
+    /// actually has a bomb to place before sending this message. Should the player already have the maximum number of
+    /// bombs placed or be within a safe zone (see [`maps::SafeZone`]), the server responds with a
+    /// [`FromServer::ActionRejected`] message.
     PlaceBomb,
 
     /// Have the server detonate all of the player's placed bombs that are within the 9 chunks they are in and
@@ -49,16 +145,106 @@ pub enum ToServer {
 
     /// Inform the server that the player wishes the purchase the specified quantity of the given item (of type
     /// [`items::QuantitativeItem`]). The server will ignore the message if the player does have enough gems to
-    /// complete the purchase.
-    PurchaseItemQuantity { item: items::QuantitativeItem, quantity: u32 }
+    /// complete the purchase, and responds with a [`FromServer::ActionRejected`] message should there not be enough
+    /// free slots in the player's inventory for the items.
+    PurchaseItemQuantity { item: items::QuantitativeItem, quantity: u32 },
+
+    /// Request up-to-date information about the entity with the specified ID so that it can be inspected by the
+    /// player. The server will respond with a [`FromServer::EntityInfo`] message provided that the entity is within
+    /// the client's loaded chunks.
+    RequestEntityInfo(Id),
+
+    /// Send a chat message to other players on the given channel. The server will ignore the message if its text is
+    /// not valid (see [`is_valid_chat_message`]). Messages sent on the [`ChatChannel::Global`] channel cost gems, with
+    /// the server responding with a [`FromServer::YouSpentGems`] message once the cost is paid or a
+    /// [`FromServer::ActionRejected`] message should the player not have enough gems (in which case the message is not
+    /// sent).
+    ChatMessage { text: String, channel: ChatChannel },
+
+    /// Inform the server that the client is about to close the connection. The server will save and remove the
+    /// player's entity before closing the WebSocket connection normally.
+    Disconnect,
+
+    /// Sent periodically (see [`PING_INTERVAL_SECS`]) so that the client can measure the latency of its connection.
+    /// The server immediately responds with a [`FromServer::Pong`] message echoing these fields. This also ensures
+    /// that the server does not consider the connection to have stalled when the player is not otherwise doing
+    /// anything.
+    Ping {
+        /// Incremented with each ping sent by the client.
+        sequence: u32,
+        /// The client's time (in milliseconds) at which the ping was sent.
+        sent_at_ms: u64
+    },
+
+    /// Change the facial expression of the player entity. Clients with the player entity's chunk loaded are informed
+    /// of the change.
+    SetFacialExpression(entities::FacialExpression),
+
+    /// Interact with the tile at the specified tile coordinates, which must be adjacent to the player's entity. Should
+    /// the tile be an unopened chest, it is opened and the server responds with a [`FromServer::YouCollectedGems`]
+    /// message. Should it be a sign, the server responds with a [`FromServer::ShowSignText`] message.
+    Interact { tile: maps::TileCoords },
+
+    /// Use one of the given item from the player's inventory (e.g. drink a potion to restore health). The server will
+    /// ignore the message should the player not have the item or should using it have no effect (see
+    /// [`entities::Entity::use_item`]).
+    UseItem(items::QuantitativeItem),
+
+    /// Begin the quest with the given ID (see [`quests::all_quests`]). The server responds with a
+    /// [`FromServer::QuestUpdate`] message containing the accepted quest, or ignores the message should there be no
+    /// such quest or should the player already have the quest active.
+    AcceptQuest(u32),
+
+    /// Request the up-to-date statistics of the player's entity. The server responds with a [`FromServer::YourStats`]
+    /// message.
+    RequestStats,
+
+    /// Request the players with the most gems. The server responds with a [`FromServer::Leaderboard`] message.
+    RequestLeaderboard,
+
+    /// Invite the player with the given entity ID to join this client's player's party (a new party led by this
+    /// client's player is formed upon the invite being accepted should they not already be in a party). The invited
+    /// player is sent a [`FromServer::InvitedToParty`] message.
+    InviteToParty(Id),
+
+    /// Accept the invite to join a party sent by the player with the given entity ID. Every member of the party
+    /// (including this client's player) is then sent a [`FromServer::PartyUpdated`] message.
+    AcceptPartyInvite(Id),
+
+    /// Leave the party that this client's player is a member of. Should the player be the party's leader then the
+    /// party is disbanded.
+    LeaveParty,
+
+    /// Choose the name of this client's player. This must be sent immediately after the client's
+    /// [`ToServer::JoinWorld`] message. Should the name be valid (see [`entities::validate_player_name`]) and
+    /// not in use by any player currently online in the chosen world, the server responds with a
+    /// [`FromServer::Welcome`] message. Otherwise, the server responds with a [`FromServer::NameRejected`] message and
+    /// closes the connection.
+    SetName(String),
+
+    /// Request the names of the worlds hosted by the server, to which the server responds with a
+    /// [`FromServer::AvailableWorlds`] message. May be sent any number of times after the client's [`ToServer::Hello`]
+    /// message is accepted.
+    ListWorlds,
+
+    /// Choose which of the worlds hosted by the server (see [`ToServer::ListWorlds`]) to join. This must be sent after
+    /// the client's [`ToServer::Hello`] message is accepted and before its [`ToServer::SetName`] message. Should no
+    /// world with the given name exist, the server closes the connection. Players only see the players & chunks of the
+    /// world that they joined and cannot move to another world without reconnecting.
+    JoinWorld(String),
+
+    /// Perform the given emote, which the server relays to this client and all nearby clients as a
+    /// [`FromServer::EntityEmote`] message. Emotes sent before the cooldown of the player's previous emote has passed
+    /// are rejected (see [`RejectionReason::Cooldown`]).
+    Emote(EmoteKind)
 }
 
 impl fmt::Display for ToServer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ToServer::Hello { client_id_option } => match client_id_option {
-                Some(id) => write!(f, "hello as existing client {}", id),
-                None => write!(f, "hello as new client")
+            ToServer::Hello { protocol_version, client_id_option } => match client_id_option {
+                Some(id) => write!(f, "hello as existing client {} (protocol version {})", id, protocol_version),
+                None => write!(f, "hello as new client (protocol version {})", protocol_version)
             },
             ToServer::MoveMyEntity { request_number, direction } => {
                 write!(f, "move my player entity {} (request #{})", direction, request_number)
@@ -66,7 +252,24 @@ impl fmt::Display for ToServer {
             ToServer::PlaceBomb => write!(f, "place bomb"),
             ToServer::DetonateBombs => write!(f, "detonate bombs"),
             ToServer::PurchaseSingleItem(item) => write!(f, "purchase {:?}", item),
-            ToServer::PurchaseItemQuantity { item, quantity } => write!(f, "purchase {} of {:?}", quantity, item)
+            ToServer::PurchaseItemQuantity { item, quantity } => write!(f, "purchase {} of {:?}", quantity, item),
+            ToServer::RequestEntityInfo(id) => write!(f, "request information about entity {}", id),
+            ToServer::ChatMessage { text, channel } => write!(f, "{} chat message \"{}\"", channel, text),
+            ToServer::Disconnect => write!(f, "disconnect"),
+            ToServer::Ping { sequence, sent_at_ms } => write!(f, "ping #{} sent at {}ms", sequence, sent_at_ms),
+            ToServer::SetFacialExpression(expression) => write!(f, "set facial expression to {}", expression),
+            ToServer::Interact { tile } => write!(f, "interact with tile at {}", tile),
+            ToServer::UseItem(item) => write!(f, "use {:?}", item),
+            ToServer::AcceptQuest(id) => write!(f, "accept quest #{}", id),
+            ToServer::RequestStats => write!(f, "request stats"),
+            ToServer::RequestLeaderboard => write!(f, "request leaderboard"),
+            ToServer::InviteToParty(entity_id) => write!(f, "invite player {} to party", entity_id),
+            ToServer::AcceptPartyInvite(entity_id) => write!(f, "accept party invite from player {}", entity_id),
+            ToServer::LeaveParty => write!(f, "leave party"),
+            ToServer::SetName(name) => write!(f, "set name to \"{}\"", name),
+            ToServer::ListWorlds => write!(f, "list worlds"),
+            ToServer::JoinWorld(world) => write!(f, "join world \"{}\"", world),
+            ToServer::Emote(emote) => write!(f, "emote {}", emote)
         }
     }
 }
@@ -74,8 +277,18 @@ impl fmt::Display for ToServer {
 /// Message sent from the server to the client over the WebSocket protocol.
 #[derive(Serialize, Deserialize)]
 pub enum FromServer {
-    /// Response to a [`ToServer::Hello`] message. This should be the first message sent from the server to each
-    /// client.
+    /// Response to a [`ToServer::Hello`] message indicating whether or not the server accepted the client's protocol
+    /// version. If not accepted, the server closes the connection after sending this message. Like
+    /// [`ToServer::Hello`], this variant and its fields must remain unchanged so that clients of any version can read
+    /// it.
+    HelloAck {
+        accepted: bool,
+        /// The value of [`crate::PROTOCOL_VERSION`] that the server was built with.
+        server_version: u32
+    },
+
+    /// Sent after a [`FromServer::HelloAck`] message accepting the client's protocol version once the client has
+    /// chosen a world to join (see [`ToServer::JoinWorld`]) and a valid name (see [`ToServer::SetName`]).
     Welcome {
         /// The version of the game that the server is running. If this does not match the client's version then the
         /// client should close the connection.
@@ -83,7 +296,10 @@ pub enum FromServer {
         /// The ID assigned to the client.
         your_client_id: Id,
         /// The entity ID and player entity that the client controls.
-        your_entity_with_id: (Id, Entity)
+        your_entity_with_id: (Id, Entity),
+        /// Whether the client should obscure tiles that are not within [`maps::SIGHT_RADIUS`] of its player entity
+        /// (see [`maps::Chunk::is_tile_seen`]).
+        fog_of_war: bool
     },
 
     /// Provide chunk data to a client so it may store it locally. Chunks are provided automatically based on the
@@ -99,7 +315,8 @@ pub enum FromServer {
     ChangeTile(maps::TileCoords, maps::Tile),
 
     /// Inform a client that their player entity's position has changed. This is most frequently sent as a response to
-    /// a client sending a [`ToServer::MoveMyEntity`] message.
+    /// a client sending a [`ToServer::MoveMyEntity`] message. Changes made by the server of its own accord have the
+    /// request number [`SERVER_MOVE_REQUEST_NUMBER`].
     YourEntityMoved { request_number: u32, new_position: maps::TileCoords },
 
     /// Inform a client that an entity that is not the player entity that they control has moved within the bounds of
@@ -126,14 +343,196 @@ pub enum FromServer {
     /// chunk have now detonated.
     BombsDetonated { placed_by_entity_id: Id, in_and_around_chunk_coords: maps::ChunkCoords },
 
-    /// Informs the client of the type and quantity of gems they received after their entity smashed a rock.
-    YouCollectedGems { gem_type: gems::Gem, quantity_increase: u32 }
+    /// Informs the client of the type and quantity of gems they received after their entity smashed a rock, as well as
+    /// the coordinates of the smashed rock tile.
+    YouCollectedGems { gem_type: gems::Gem, quantity_increase: u32, source_tile: maps::TileCoords },
+
+    /// Response to a [`ToServer::RequestEntityInfo`] message containing the current state of the requested entity.
+    EntityInfo(Id, Entity),
+
+    /// Inform the client that their player entity's health reached zero. The entity has already been moved to the
+    /// specified respawn position with its health restored, and the given gems were removed from its collection and
+    /// left behind where it died (see [`maps::Tile::GemDrop`]).
+    YouDied { respawn_at: maps::TileCoords, gems_dropped: gems::Collection },
+
+    /// A chat message sent by another player on the given channel.
+    ChatMessage { sender_entity_id: Id, sender_name: String, text: String, channel: ChatChannel },
+
+    /// Response to a [`ToServer::PurchaseSingleItem`] or [`ToServer::PurchaseItemQuantity`] message should the
+    /// client's player entity not have enough gems to make the purchase.
+    PurchaseFailed(gems::InsufficientGemsError),
+
+    /// Inform the client that an action they attempted was not permitted by the server.
+    ActionRejected { reason: RejectionReason },
+
+    /// Response to a [`ToServer::Ping`] message with the same sequence number and time sent.
+    Pong { sequence: u32, sent_at_ms: u64 },
+
+    /// A notice from the server operator broadcast to all connected clients (e.g. warning of an upcoming restart).
+    ServerAnnouncement { text: String },
+
+    /// Inform the client that a remote entity has changed its facial expression.
+    EntityChangedExpression { entity_id: Id, expression: entities::FacialExpression },
+
+    /// Display the text of the sign that the client's player interacted with (see [`ToServer::Interact`]).
+    ShowSignText { text: String },
+
+    /// Provide the client with a monster that is either in a newly provided chunk, has just spawned in one of the
+    /// client's loaded chunks, or has just moved into one of those chunks.
+    ProvideMonster(Id, Monster),
+
+    /// Inform the client that the monster with the specified ID has moved within the client's loaded chunks.
+    MoveMonster(Id, maps::TileCoords, entities::Direction),
+
+    /// Instruct the client to unload the monster with the specified ID (as it has left the client's loaded chunks or
+    /// been removed from the map).
+    ShouldUnloadMonster(Id),
+
+    /// Inform the client that the health of an entity within their loaded chunks (possibly their own player entity)
+    /// has changed.
+    EntityHealthChanged { entity_id: Id, health: u32 },
+
+    /// Inform the client of progress made towards one of their active quests, or that a quest has just been accepted.
+    /// Once a quest's objective is complete, its reward is given to the player's entity and this message is sent one
+    /// final time with the completed quest.
+    QuestUpdate(quests::Quest),
+
+    /// Inform the client that their player has just unlocked the given achievement. Each achievement is only ever
+    /// unlocked once per player.
+    AchievementUnlocked(achievements::Achievement),
+
+    /// Response to a [`ToServer::RequestStats`] message containing the statistics of the client's player entity.
+    YourStats(stats::PlayerStats),
+
+    /// Response to a [`ToServer::RequestLeaderboard`] message containing (at most [`LEADERBOARD_SIZE`]) players ranked
+    /// by the total number of gems they have, highest first.
+    Leaderboard(Vec<LeaderboardEntry>),
+
+    /// Inform the client that the player with the given entity ID has invited the client's player to join their party
+    /// (see [`ToServer::AcceptPartyInvite`]).
+    InvitedToParty { inviter_entity_id: Id },
+
+    /// Inform the client of the members of the party that its player is in, sent whenever a player joins or leaves
+    /// that party. Gems collected by any member are split evenly among all members (with any remainder going to the
+    /// leader) with each member being sent a [`FromServer::YouCollectedGems`] message for their share.
+    PartyUpdated { leader_entity_id: Id, member_entity_ids: Vec<Id> },
+
+    /// Inform the client that its player is no longer in a party, either as they left or as the party was disbanded.
+    PartyLeft,
+
+    /// Inform the client that the given quantity of gems of the specified type has been removed from its player's gem
+    /// collection to pay for something other than an item (e.g. a global chat message).
+    YouSpentGems { gem_type: gems::Gem, quantity_decrease: u32 },
+
+    /// Response to a [`ToServer::SetName`] message should the chosen name be invalid or already in use. The server
+    /// closes the connection after sending this message.
+    NameRejected { reason: String },
+
+    /// Response to a [`ToServer::ListWorlds`] message giving the names of the worlds hosted by the server in
+    /// alphabetical order.
+    AvailableWorlds(Vec<String>),
+
+    /// Inform the client of the areas of the map in which bombs cannot be placed and players cannot be damaged. Sent
+    /// immediately after the [`FromServer::Welcome`] message.
+    SafeZones(Vec<maps::SafeZone>),
+
+    /// Inform the client that its player was dealt the given amount of damage from the specified source. The player's
+    /// new health is given by an accompanying [`FromServer::EntityHealthChanged`] message.
+    ReceivedDamage { amount: u32, source: DamageSource },
+
+    /// Inform the client that the specified player entity (which may be the client's own player entity) is performing
+    /// the given emote.
+    EntityEmote { entity_id: Id, emote: EmoteKind }
+}
+
+/// A player's position on the leaderboard (see [`FromServer::Leaderboard`]).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LeaderboardEntry {
+    pub entity_id: Id,
+    pub name: String,
+    /// The total quantity of gems (of all types) that the player has.
+    pub gem_total: u32
+}
+
+/// What dealt damage to a player (see [`FromServer::ReceivedDamage`]).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DamageSource {
+    /// A tile that damages players standing on it, such as [`maps::Tile::Lava`].
+    EnvironmentalHazard
+}
+
+impl fmt::Display for DamageSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DamageSource::EnvironmentalHazard => write!(f, "environmental hazard")
+        }
+    }
+}
+
+/// The reason given by the server for not permitting an action requested by a client (see
+/// [`FromServer::ActionRejected`]).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RejectionReason {
+    /// The player attempted to place a bomb at the given position while already having the maximum number of bombs
+    /// placed.
+    BombLimitReached { limit: u32, position: maps::TileCoords },
+    /// The player attempted to purchase more items than there are free slots for in their inventory (see
+    /// [`items::Inventory::max_slots`]).
+    InventoryFull { max_slots: u32 },
+    /// The player attempted to purchase items that would take the combined weight of their inventory above
+    /// [`items::MAX_INVENTORY_WEIGHT`].
+    InventoryTooHeavy { max_weight: u32 },
+    /// The player attempted to invite a player who is already in a party, or to accept an invite while in a party.
+    AlreadyInParty,
+    /// The player attempted to invite another player to the party they are in but do not lead.
+    NotPartyLeader,
+    /// The player attempted to accept a party invite that they were not sent (or that is no longer valid).
+    NoPartyInvite,
+    /// The player attempted to send a chat message on the [`ChatChannel::Global`] channel without enough gems to pay
+    /// for it.
+    CannotAffordGlobalChat(gems::InsufficientGemsError),
+    /// The player attempted to place a bomb at the given position, which is within a safe zone (see
+    /// [`maps::SafeZone`]).
+    InSafeZone { position: maps::TileCoords },
+    /// The player attempted to perform an action before its cooldown (see [`ActionKind::cooldown_ms`]) since they last
+    /// performed it had passed. The action may be performed again after the given number of milliseconds.
+    Cooldown { action: ActionKind, remaining_ms: u32 }
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RejectionReason::BombLimitReached { limit, position } => {
+                write!(f, "cannot place bomb at {} as limit of {} placed bombs reached", position, limit)
+            }
+            RejectionReason::InventoryFull { max_slots } => {
+                write!(f, "not enough room in inventory with {} slots", max_slots)
+            }
+            RejectionReason::InventoryTooHeavy { max_weight } => {
+                write!(f, "inventory would exceed maximum weight of {}", max_weight)
+            }
+            RejectionReason::AlreadyInParty => write!(f, "player is already in a party"),
+            RejectionReason::NotPartyLeader => write!(f, "only the party leader may invite players"),
+            RejectionReason::NoPartyInvite => write!(f, "no such party invite"),
+            RejectionReason::CannotAffordGlobalChat(err) => write!(f, "cannot afford global chat message as {}", err),
+            RejectionReason::InSafeZone { position } => {
+                write!(f, "cannot place bomb at {} as it is within a safe zone", position)
+            }
+            RejectionReason::Cooldown { action, remaining_ms } => {
+                write!(f, "cannot {} again for another {}ms", action, remaining_ms)
+            }
+        }
+    }
 }
 
 impl fmt::Display for FromServer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            FromServer::Welcome { version, your_client_id, your_entity_with_id: (entity_id, entity) } => {
+            FromServer::HelloAck { accepted, server_version } => {
+                let outcome = if *accepted { "accepted" } else { "rejected" };
+                write!(f, "hello {} by server using protocol version {}", outcome, server_version)
+            }
+            FromServer::Welcome { version, your_client_id, your_entity_with_id: (entity_id, entity), .. } => {
                 write!(
                     f,
                     "welcome client {} to server running version '{}' and provide entity {} - {}",
@@ -161,9 +560,218 @@ impl fmt::Display for FromServer {
                     placed_by_entity_id, in_and_around_chunk_coords
                 )
             }
-            FromServer::YouCollectedGems { gem_type, quantity_increase } => {
-                write!(f, "you collected {} gems of type {:?}", quantity_increase, gem_type)
+            FromServer::YouCollectedGems { gem_type, quantity_increase, source_tile } => {
+                write!(
+                    f,
+                    "you collected {} gems of type {:?} from tile at {}",
+                    quantity_increase, gem_type, source_tile
+                )
+            }
+            FromServer::EntityInfo(id, entity) => write!(f, "information about entity {} - {}", id, entity),
+            FromServer::YouDied { respawn_at, gems_dropped } => {
+                write!(f, "you died dropping {} gems and will respawn at {}", gems_dropped.total_count(), respawn_at)
+            }
+            FromServer::ChatMessage { sender_entity_id, sender_name, text, channel } => {
+                write!(f, "{} chat message \"{}\" from {} (entity {})", channel, text, sender_name, sender_entity_id)
+            }
+            FromServer::PurchaseFailed(err) => write!(f, "purchase failed as {}", err),
+            FromServer::ActionRejected { reason } => write!(f, "action rejected - {}", reason),
+            FromServer::Pong { sequence, sent_at_ms } => {
+                write!(f, "pong #{} for ping sent at {}ms", sequence, sent_at_ms)
+            }
+            FromServer::ServerAnnouncement { text } => write!(f, "server announcement \"{}\"", text),
+            FromServer::EntityChangedExpression { entity_id, expression } => {
+                write!(f, "entity {} changed facial expression to {}", entity_id, expression)
             }
+            FromServer::ShowSignText { text } => write!(f, "show sign text \"{}\"", text),
+            FromServer::ProvideMonster(id, monster) => write!(f, "provide {} - {}", monster, id),
+            FromServer::MoveMonster(id, pos, direction) => {
+                write!(f, "move monster {} to {} in direction {}", id, pos, direction)
+            }
+            FromServer::ShouldUnloadMonster(id) => write!(f, "should unload monster {}", id),
+            FromServer::EntityHealthChanged { entity_id, health } => {
+                write!(f, "entity {} health changed to {}", entity_id, health)
+            }
+            FromServer::QuestUpdate(quest) => write!(f, "update to {}", quest),
+            FromServer::AchievementUnlocked(achievement) => write!(f, "achievement unlocked \"{}\"", achievement),
+            FromServer::YourStats(stats) => write!(f, "your stats - {}", stats),
+            FromServer::Leaderboard(entries) => write!(f, "leaderboard with {} entries", entries.len()),
+            FromServer::InvitedToParty { inviter_entity_id } => {
+                write!(f, "invited to party by player {}", inviter_entity_id)
+            }
+            FromServer::PartyUpdated { leader_entity_id, member_entity_ids } => {
+                write!(f, "party led by {} now has {} members", leader_entity_id, member_entity_ids.len())
+            }
+            FromServer::PartyLeft => write!(f, "no longer in a party"),
+            FromServer::YouSpentGems { gem_type, quantity_decrease } => {
+                write!(f, "you spent {} gems of type {:?}", quantity_decrease, gem_type)
+            }
+            FromServer::NameRejected { reason } => write!(f, "name rejected as {}", reason),
+            FromServer::AvailableWorlds(names) => write!(f, "available worlds: {}", names.join(", ")),
+            FromServer::SafeZones(zones) => write!(f, "{} safe zones", zones.len()),
+            FromServer::ReceivedDamage { amount, source } => write!(f, "received {} damage from {}", amount, source),
+            FromServer::EntityEmote { entity_id, emote } => write!(f, "entity {} performed emote {}", entity_id, emote)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maps::{ChunkCoords, Tile, TileCoords};
+
+    #[test]
+    fn display_to_server_messages() {
+        let msg = ToServer::MoveMyEntity { request_number: 4, direction: entities::Direction::Left };
+        assert_eq!(msg.to_string(), "move my player entity 🡐 left (request #4)");
+
+        let msg = ToServer::Hello { protocol_version: 3, client_id_option: None };
+        assert_eq!(msg.to_string(), "hello as new client (protocol version 3)");
+        assert_eq!(ToServer::Disconnect.to_string(), "disconnect");
+        assert_eq!(ToServer::Ping { sequence: 2, sent_at_ms: 5000 }.to_string(), "ping #2 sent at 5000ms");
+
+        let msg = ToServer::ChatMessage { text: "hello".into(), channel: ChatChannel::Global };
+        assert_eq!(msg.to_string(), "Global chat message \"hello\"");
+
+        let msg = ToServer::SetFacialExpression(entities::FacialExpression::Angry);
+        assert_eq!(msg.to_string(), "set facial expression to 😠 angry");
+
+        assert_eq!(ToServer::UseItem(items::QuantitativeItem::Potion).to_string(), "use Potion");
+        assert_eq!(ToServer::AcceptQuest(2).to_string(), "accept quest #2");
+        assert_eq!(ToServer::RequestStats.to_string(), "request stats");
+        assert_eq!(ToServer::RequestLeaderboard.to_string(), "request leaderboard");
+        assert_eq!(ToServer::LeaveParty.to_string(), "leave party");
+        assert!(ToServer::InviteToParty(Id::new(1)).to_string().starts_with("invite player "));
+        assert_eq!(ToServer::SetName("Gem_Hunter".into()).to_string(), "set name to \"Gem_Hunter\"");
+        assert_eq!(ToServer::ListWorlds.to_string(), "list worlds");
+        assert_eq!(ToServer::JoinWorld("arena".into()).to_string(), "join world \"arena\"");
+        assert_eq!(ToServer::Emote(EmoteKind::Wave).to_string(), "emote wave");
+    }
+
+    #[test]
+    fn chat_message_validity() {
+        assert!(is_valid_chat_message("hi there"));
+        assert!(!is_valid_chat_message(""));
+        assert!(!is_valid_chat_message(" \t\n"));
+
+        // Length is measured in characters rather than bytes:
+        let longest = "é".repeat(MAX_CHAT_MESSAGE_LENGTH);
+        assert!(is_valid_chat_message(&longest));
+        assert!(!is_valid_chat_message(&(longest + "e")));
+    }
+
+    #[test]
+    fn announcement_validity() {
+        assert!(is_valid_announcement("Server restarting in 5 minutes"));
+        assert!(!is_valid_announcement("  "));
+
+        let longest = "a".repeat(MAX_ANNOUNCEMENT_LENGTH);
+        assert!(is_valid_announcement(&longest));
+        assert!(!is_valid_announcement(&(longest + "a")));
+    }
+
+    #[test]
+    fn display_from_server_messages() {
+        let msg = FromServer::ChangeTile(TileCoords { x: 1, y: -2 }, Tile::RockSmashed);
+        assert_eq!(msg.to_string(), "change tile at tile coordinates (1, -2) to RockSmashed");
+
+        let msg = FromServer::ShouldUnloadChunk(ChunkCoords { x: 1, y: -2 });
+        assert_eq!(msg.to_string(), "should unload chunk at chunk coordinates (1, -2)");
+
+        let msg = FromServer::HelloAck { accepted: false, server_version: 2 };
+        assert_eq!(msg.to_string(), "hello rejected by server using protocol version 2");
+
+        let mut gems_dropped = gems::Collection::default();
+        gems_dropped.increase_quantity(gems::Gem::Emerald, 4);
+        gems_dropped.increase_quantity(gems::Gem::Ruby, 1);
+        let msg = FromServer::YouDied { respawn_at: TileCoords { x: 0, y: 3 }, gems_dropped };
+        assert_eq!(msg.to_string(), "you died dropping 5 gems and will respawn at tile coordinates (0, 3)");
+
+        let err = gems::InsufficientGemsError { gem: gems::Gem::Ruby, requested: 10, available: 4 };
+        let msg = FromServer::PurchaseFailed(err);
+        assert_eq!(msg.to_string(), "purchase failed as requested 10 gems of type Ruby but only 4 are available");
+
+        let reason = RejectionReason::BombLimitReached { limit: 5, position: TileCoords { x: 1, y: 2 } };
+        let msg = FromServer::ActionRejected { reason };
+        assert_eq!(
+            msg.to_string(),
+            "action rejected - cannot place bomb at tile coordinates (1, 2) as limit of 5 placed bombs reached"
+        );
+
+        let msg = FromServer::ActionRejected { reason: RejectionReason::InventoryFull { max_slots: 20 } };
+        assert_eq!(msg.to_string(), "action rejected - not enough room in inventory with 20 slots");
+
+        let msg = FromServer::ActionRejected { reason: RejectionReason::InventoryTooHeavy { max_weight: 30 } };
+        assert_eq!(msg.to_string(), "action rejected - inventory would exceed maximum weight of 30");
+
+        let msg = FromServer::ProvideMonster(Id::new(1), Monster::new(TileCoords { x: 2, y: 3 }));
+        assert!(msg.to_string().starts_with("provide monster at tile coordinates (2, 3) facing 🡓 down with 30 health"));
+
+        let msg = FromServer::EntityInfo(Id::new(1), entities::EntityBuilder::new().build());
+        assert!(msg.to_string().starts_with(&format!("information about entity {} - entity at ", Id::new(1))));
+
+        let msg = FromServer::QuestUpdate(quests::quest_by_id(1).unwrap());
+        assert_eq!(msg.to_string(), "update to quest #1 'Rock Breaker' (mine rocks 0/20)");
+
+        let msg = FromServer::AchievementUnlocked(achievements::Achievement::FirstGem);
+        assert_eq!(msg.to_string(), "achievement unlocked \"Shiny! - collect your first gem\"");
+
+        let msg = FromServer::YourStats(stats::PlayerStats { tiles_walked: 12, bombs_placed: 3, ..Default::default() });
+        assert_eq!(
+            msg.to_string(),
+            "your stats - 12 tiles walked, 0 gems collected, 3 bombs placed, 0 bombs detonated, 0 chunks explored"
+        );
+
+        let entry = LeaderboardEntry { entity_id: Id::new(1), name: "Player".into(), gem_total: 40 };
+        let msg = FromServer::Leaderboard(alloc::vec![entry.clone(), entry]);
+        assert_eq!(msg.to_string(), "leaderboard with 2 entries");
+
+        let msg =
+            FromServer::PartyUpdated { leader_entity_id: Id::new(1), member_entity_ids: alloc::vec![Id::new(1); 3] };
+        assert!(msg.to_string().ends_with("now has 3 members"));
+        assert_eq!(FromServer::PartyLeft.to_string(), "no longer in a party");
+
+        let msg = FromServer::ActionRejected { reason: RejectionReason::NotPartyLeader };
+        assert_eq!(msg.to_string(), "action rejected - only the party leader may invite players");
+
+        let msg = FromServer::YouSpentGems { gem_type: gems::Gem::Emerald, quantity_decrease: 5 };
+        assert_eq!(msg.to_string(), "you spent 5 gems of type Emerald");
+
+        let msg = FromServer::NameRejected { reason: "name is already taken".into() };
+        assert_eq!(msg.to_string(), "name rejected as name is already taken");
+
+        let msg = FromServer::AvailableWorlds(vec!["arena".into(), "default".into()]);
+        assert_eq!(msg.to_string(), "available worlds: arena, default");
+
+        let msg = FromServer::SafeZones(vec![maps::SafeZone { centre: TileCoords { x: 0, y: 0 }, radius: 5 }]);
+        assert_eq!(msg.to_string(), "1 safe zones");
+
+        let msg = FromServer::ReceivedDamage { amount: 5, source: DamageSource::EnvironmentalHazard };
+        assert_eq!(msg.to_string(), "received 5 damage from environmental hazard");
+
+        let msg = FromServer::EntityEmote { entity_id: Id::new(1), emote: EmoteKind::Sad };
+        assert!(msg.to_string().ends_with("performed emote sad"));
+
+        let msg =
+            FromServer::ActionRejected { reason: RejectionReason::InSafeZone { position: TileCoords { x: 1, y: 2 } } };
+        assert_eq!(
+            msg.to_string(),
+            "action rejected - cannot place bomb at tile coordinates (1, 2) as it is within a safe zone"
+        );
+
+        let reason = RejectionReason::Cooldown { action: ActionKind::UsePotion, remaining_ms: 1200 };
+        let msg = FromServer::ActionRejected { reason };
+        assert_eq!(msg.to_string(), "action rejected - cannot use a potion again for another 1200ms");
+
+        let err = gems::InsufficientGemsError { gem: gems::Gem::Emerald, requested: 5, available: 1 };
+        let msg = FromServer::ActionRejected { reason: RejectionReason::CannotAffordGlobalChat(err) };
+        assert!(msg.to_string().starts_with("action rejected - cannot afford global chat message as requested 5"));
+    }
+
+    #[test]
+    fn chat_channels_cycle() {
+        assert_eq!(ChatChannel::default(), ChatChannel::Local);
+        assert_eq!(ChatChannel::Local.next(), ChatChannel::Global);
+        assert_eq!(ChatChannel::Global.next(), ChatChannel::Local);
+    }
+}
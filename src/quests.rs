@@ -0,0 +1,176 @@
+//! Quests that players may accept in order to earn gems by completing objectives (see
+//! [`crate::messages::ToServer::AcceptQuest`]). Progress towards a quest's objective is tracked by the server which
+//! delivers the quest's reward once the objective is satisfied.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::gems;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Quest {
+    pub id: u32,
+    pub title: String,
+    pub objective: QuestObjective,
+    /// The gems given to the player upon completion of the quest's objective.
+    pub reward: gems::Collection
+}
+
+impl Quest {
+    pub fn is_complete(&self) -> bool {
+        self.objective.is_complete()
+    }
+
+    /// Update the progress made towards this quest's objective based on the given event. Returns `true` should the
+    /// event have counted towards the objective. Progress is capped at the amount required by the objective.
+    pub fn record(&mut self, event: QuestEvent) -> bool {
+        let (progress, required, increase) = match (&mut self.objective, event) {
+            (
+                QuestObjective::CollectGems { gem, required, collected },
+                QuestEvent::GemsCollected { gem: event_gem, quantity }
+            ) if *gem == event_gem => (collected, *required, quantity),
+            (QuestObjective::MineRocks { required, mined }, QuestEvent::RockMined) => (mined, *required, 1),
+            (QuestObjective::KillMonsters { required, killed }, QuestEvent::MonsterKilled) => (killed, *required, 1),
+            _ => return false
+        };
+
+        if *progress >= required {
+            return false;
+        }
+
+        *progress = progress.saturating_add(increase).min(required);
+        true
+    }
+}
+
+impl fmt::Display for Quest {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "quest #{} '{}' ({})", self.id, self.title, self.objective)
+    }
+}
+
+/// What a player must do in order to complete a quest, along with their progress towards doing so.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestObjective {
+    CollectGems { gem: gems::Gem, required: u32, collected: u32 },
+    MineRocks { required: u32, mined: u32 },
+    KillMonsters { required: u32, killed: u32 }
+}
+
+impl QuestObjective {
+    /// Returns the progress made towards this objective paired with the amount required to satisfy it.
+    pub fn progress(&self) -> (u32, u32) {
+        match *self {
+            QuestObjective::CollectGems { required, collected, .. } => (collected, required),
+            QuestObjective::MineRocks { required, mined } => (mined, required),
+            QuestObjective::KillMonsters { required, killed } => (killed, required)
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        let (progress, required) = self.progress();
+        progress >= required
+    }
+}
+
+impl fmt::Display for QuestObjective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (progress, required) = self.progress();
+
+        match self {
+            QuestObjective::CollectGems { gem, .. } => write!(f, "collect {:?}s {}/{}", gem, progress, required),
+            QuestObjective::MineRocks { .. } => write!(f, "mine rocks {}/{}", progress, required),
+            QuestObjective::KillMonsters { .. } => write!(f, "kill monsters {}/{}", progress, required)
+        }
+    }
+}
+
+/// Something done by a player that may count towards the objective of one of their active quests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestEvent {
+    GemsCollected { gem: gems::Gem, quantity: u32 },
+    RockMined,
+    MonsterKilled
+}
+
+/// Every quest that players may accept, with no progress made towards any of their objectives.
+pub fn all_quests() -> Vec<Quest> {
+    let reward = |gem, quantity| {
+        let mut collection = gems::Collection::default();
+        collection.increase_quantity(gem, quantity);
+        collection
+    };
+
+    alloc::vec![
+        Quest {
+            id: 1,
+            title: "Rock Breaker".into(),
+            objective: QuestObjective::MineRocks { required: 20, mined: 0 },
+            reward: reward(gems::Gem::Emerald, 25)
+        },
+        Quest {
+            id: 2,
+            title: "Ruby Hunter".into(),
+            objective: QuestObjective::CollectGems { gem: gems::Gem::Ruby, required: 15, collected: 0 },
+            reward: reward(gems::Gem::Diamond, 5)
+        },
+        Quest {
+            id: 3,
+            title: "Monster Slayer".into(),
+            objective: QuestObjective::KillMonsters { required: 3, killed: 0 },
+            reward: reward(gems::Gem::Sapphire, 3)
+        }
+    ]
+}
+
+/// Returns the quest with the given ID (with no progress made towards its objective) or `None` should there be no
+/// such quest.
+pub fn quest_by_id(id: u32) -> Option<Quest> {
+    all_quests().into_iter().find(|quest| quest.id == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quest_ids_unique() {
+        let quests = all_quests();
+
+        for (i, quest) in quests.iter().enumerate() {
+            assert!(!quest.is_complete());
+            assert!(quests[i + 1..].iter().all(|other| other.id != quest.id));
+            assert_eq!(quest_by_id(quest.id).as_ref(), Some(quest));
+        }
+
+        assert_eq!(quest_by_id(0), None);
+    }
+
+    #[test]
+    fn record_progress() {
+        let mut quest = Quest {
+            id: 0,
+            title: "Test".into(),
+            objective: QuestObjective::CollectGems { gem: gems::Gem::Ruby, required: 10, collected: 0 },
+            reward: gems::Collection::default()
+        };
+
+        assert!(!quest.record(QuestEvent::RockMined));
+        assert!(!quest.record(QuestEvent::GemsCollected { gem: gems::Gem::Emerald, quantity: 4 }));
+        assert!(quest.record(QuestEvent::GemsCollected { gem: gems::Gem::Ruby, quantity: 4 }));
+        assert_eq!(quest.objective.progress(), (4, 10));
+        assert!(!quest.is_complete());
+
+        // Progress is capped at the required amount & no further progress is recorded once complete:
+        assert!(quest.record(QuestEvent::GemsCollected { gem: gems::Gem::Ruby, quantity: 8 }));
+        assert_eq!(quest.objective.progress(), (10, 10));
+        assert!(quest.is_complete());
+        assert!(!quest.record(QuestEvent::GemsCollected { gem: gems::Gem::Ruby, quantity: 1 }));
+
+        let mut quest = quest_by_id(3).unwrap();
+        assert!(quest.record(QuestEvent::MonsterKilled));
+        assert_eq!(quest.to_string(), "quest #3 'Monster Slayer' (kill monsters 1/3)");
+    }
+}
@@ -0,0 +1,27 @@
+use core::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Running totals of things that a player has done while playing, kept by the server and persisted with their player
+/// entity (see [`crate::maps::entities::Entity::stats`]).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlayerStats {
+    pub tiles_walked: u64,
+    /// Gems collected from smashed rocks & opened chests (excluding gems received as quest rewards).
+    pub gems_collected_total: u64,
+    pub bombs_placed: u64,
+    pub bombs_detonated: u64,
+    /// The number of chunks visited for the first time in a session (so chunks visited in multiple sessions are
+    /// counted once per session).
+    pub chunks_explored: u64
+}
+
+impl fmt::Display for PlayerStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} tiles walked, {} gems collected, {} bombs placed, {} bombs detonated, {} chunks explored",
+            self.tiles_walked, self.gems_collected_total, self.bombs_placed, self.bombs_detonated, self.chunks_explored
+        )
+    }
+}